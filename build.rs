@@ -1,303 +1,462 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::process::Command;
-use std::{env, fs, path::Path};
+use std::{env, fs, path::{Path, PathBuf}};
+
+/// Where `cargo contract build` writes the contract's metadata, checked in order when
+/// `CONTRACT_METADATA_PATH` isn't set. The bundle holds the same metadata alongside the Wasm.
+const METADATA_PATHS: &[&str] = &[
+    "contracts/target/ink/lsrwa_express_contract.json",
+    "contracts/target/ink/lsrwa_express_contract.contract",
+];
+
+/// Names `LsrwaExpressContract` already uses, which no message may be bound to
+const RESERVED_METHODS: &[&str] = &["new", "submit", "submit_finalized", "submit_batch_finalized", "dry_run"];
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=contracts/lib.rs");
     println!("cargo:rerun-if-changed=contracts/Cargo.toml");
     println!("cargo:rerun-if-changed=contracts/Cargo.lock");
-    
-    // Check if we're building for the host platform (not wasm32)
+    println!("cargo:rerun-if-env-changed=CONTRACT_METADATA_PATH");
+    // Migrations are embedded by `sqlx::migrate!`, so rebuild when they change
+    println!("cargo:rerun-if-changed=migrations");
+
+    // Building the contract needs cargo-contract, so it's only done for wasm32 targets
     let target = env::var("TARGET").unwrap_or_default();
-    
-    if !target.contains("wasm32") {
-        println!("cargo:warning=Skipping contract compilation for non-wasm32 target");
-        // Still generate the bindings, but with placeholder code
-        generate_placeholder_bindings()?;
-    } else {
-        // Generate contract bindings for wasm32 target
-        generate_contract_bindings()?;
+    let mut metadata_path = find_metadata();
+    if metadata_path.is_none() && target.contains("wasm32") {
+        build_contract()?;
+        metadata_path = find_metadata();
     }
-    
-    Ok(())
-}
 
-fn generate_placeholder_bindings() -> Result<(), Box<dyn std::error::Error>> {
-    // Create the directory for generated code
+    let bindings = match metadata_path {
+        Some(path) => {
+            println!("cargo:rerun-if-changed={}", path.display());
+            generate_bindings(&path)?
+        }
+        None => {
+            println!(
+                "cargo:warning=No contract metadata found, so no contract messages are bound; \
+                 run `cargo contract build --release --manifest-path contracts/Cargo.toml` or set CONTRACT_METADATA_PATH"
+            );
+            "// No contract metadata was found when building, so no messages are bound\n".to_string()
+        }
+    };
+
     let out_dir = env::var("OUT_DIR").unwrap();
     let generated_dir = Path::new(&out_dir).join("generated");
     fs::create_dir_all(&generated_dir)?;
-    
-    // Generate placeholder bindings that will compile on the host platform
-    let binding_code = r#"
-// Placeholder contract bindings for non-wasm32 targets
-use std::fmt;
-
-// Dummy types to make the code compile
-pub type AccountId = [u8; 32];
-pub type H256 = [u8; 32];
-
-// Contract interface
-pub struct LsrwaExpressContract {
-    pub client: (),
-    pub address: AccountId,
-}
-
-// Selector for create_deposit_request
-pub const CREATE_DEPOSIT_REQUEST_SELECTOR: [u8; 4] = [0x44, 0x79, 0x78, 0x8a];
-
-// Selector for create_withdrawal_request
-pub const CREATE_WITHDRAWAL_REQUEST_SELECTOR: [u8; 4] = [0x53, 0x8a, 0x4f, 0x2b];
-
-// Result types
-#[derive(Debug)]
-pub enum DepositRequestResult {
-    Ok(u128),
-    Err(()),
-}
+    fs::write(generated_dir.join("contract_bindings.rs"), bindings)?;
 
-impl fmt::Display for DepositRequestResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DepositRequestResult::Ok(id) => write!(f, "Request ID: {}", id),
-            DepositRequestResult::Err(_) => write!(f, "Error"),
-        }
-    }
+    Ok(())
 }
 
-impl LsrwaExpressContract {
-    pub fn new(_client: (), address: AccountId) -> Self {
-        Self { client: (), address }
+/// Path of the contract metadata to bind, if the contract has been built
+fn find_metadata() -> Option<PathBuf> {
+    if let Ok(path) = env::var("CONTRACT_METADATA_PATH") {
+        return Some(PathBuf::from(path));
     }
-    
-    // Create deposit request method (placeholder)
-    pub async fn create_deposit_request(
-        &self, 
-        _signer: &(),
-        _amount: u128,
-        _gas_limit: u64,
-    ) -> Result<H256, Box<dyn std::error::Error>> {
-        // This is just a placeholder that will compile but not be used
-        Err("Contract calls not available in non-wasm32 builds".into())
-    }
-}
-"#;
 
-    let binding_path = generated_dir.join("contract_bindings.rs");
-    fs::write(binding_path, binding_code)?;
-    
-    Ok(())
+    METADATA_PATHS.iter().map(PathBuf::from).find(|path| path.exists())
 }
 
-fn generate_contract_bindings() -> Result<(), Box<dyn std::error::Error>> {
-    // Create the directory for generated code
-    let out_dir = env::var("OUT_DIR").unwrap();
-    let generated_dir = Path::new(&out_dir).join("generated");
-    fs::create_dir_all(&generated_dir)?;
-    
-    // Ensure the contract is built
+fn build_contract() -> Result<(), Box<dyn std::error::Error>> {
     let status = Command::new("cargo")
-        .args(&["contract", "build", "--release", "--manifest-path", "contracts/Cargo.toml"])
+        .args(["contract", "build", "--release", "--manifest-path", "contracts/Cargo.toml"])
         .status()?;
-        
+
     if !status.success() {
         return Err("Failed to build contract".into());
     }
-    
-    // Copy metadata.json to the generated directory
-    let metadata_source = Path::new("target/ink/metadata.json");
-    let metadata_dest = generated_dir.join("metadata.json");
-    fs::copy(metadata_source, metadata_dest)?;
-    
-    // Generate bindings using subxt-codegen or a similar tool
-    // Here we'll create a simple binding generator module for demonstration
-    let binding_code = r#"
-// Auto-generated contract bindings for LSRWA Express
-use ink::primitives::AccountId;
-use ink::env::DefaultEnvironment;
-use ink::LangError;
-use scale::{Encode, Decode};
-use subxt::{
-    tx::PairSigner,
-    utils::MultiAddress,
-    OnlineClient,
-    PolkadotConfig,
-    ext::sp_core::{sr25519, ByteArray, Pair as PairTrait, H256}
-};
-use std::fmt;
-
-// Contract interface
-pub struct LsrwaExpressContract {
-    pub client: OnlineClient<PolkadotConfig>,
-    pub address: AccountId,
+
+    Ok(())
 }
 
-// Selector for create_deposit_request
-pub const CREATE_DEPOSIT_REQUEST_SELECTOR: [u8; 4] = [0x44, 0x79, 0x78, 0x8a];
+fn generate_bindings(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read contract metadata {}: {}", path.display(), e))?;
+    let metadata: Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("{} is not contract metadata: {}", path.display(), e))?;
 
-// Selector for create_withdrawal_request
-pub const CREATE_WITHDRAWAL_REQUEST_SELECTOR: [u8; 4] = [0x53, 0x8a, 0x4f, 0x2b];
+    let bindings = Bindings::new(&metadata)
+        .and_then(|mut bindings| bindings.render())
+        .map_err(|e| format!("Failed to generate contract bindings from {}: {}", path.display(), e))?;
 
-// Result types
-#[derive(Debug, Encode, Decode)]
-pub enum DepositRequestResult {
-    Ok(u128),
-    Err(LangError),
+    Ok(bindings)
 }
 
-impl fmt::Display for DepositRequestResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DepositRequestResult::Ok(id) => write!(f, "Request ID: {}", id),
-            DepositRequestResult::Err(err) => write!(f, "Error: {:?}", err),
+/// A message of the contract, with its types as Rust
+struct Message {
+    label: String,
+    method: String,
+    selector: [u8; 4],
+    docs: Vec<String>,
+    args: Vec<(String, String)>,
+    /// What the message returns once ink!'s `LangError` is unwrapped
+    returns: String,
+    /// Whether the message returns a `Result`, whose `Err` means the call would be reverted
+    fallible: bool,
+    mutates: bool,
+}
+
+/// Rust bindings of the messages in a contract's ink! metadata
+struct Bindings<'a> {
+    /// The metadata's type registry, by type ID
+    registry: BTreeMap<u64, &'a Value>,
+    messages: &'a [Value],
+    /// Names of the contract types bound so far, by type ID
+    names: BTreeMap<u64, String>,
+    /// Definitions of the contract types bound so far, by name
+    definitions: BTreeMap<String, String>,
+}
+
+impl<'a> Bindings<'a> {
+    fn new(metadata: &'a Value) -> Result<Self, String> {
+        let version = metadata["version"]
+            .as_u64()
+            .or_else(|| metadata["version"].as_str().and_then(|version| version.parse().ok()))
+            .ok_or("the metadata has no version")?;
+        if version < 4 {
+            return Err(format!("metadata version {} isn't supported; rebuild the contract with ink! 4 or later", version));
+        }
+
+        let registry = metadata["types"]
+            .as_array()
+            .ok_or("the metadata has no type registry")?
+            .iter()
+            .map(|entry| Ok((type_id(&entry["id"])?, &entry["type"])))
+            .collect::<Result<_, String>>()?;
+        let messages = metadata["spec"]["messages"]
+            .as_array()
+            .ok_or("the metadata has no messages")?;
+
+        Ok(Self {
+            registry,
+            messages,
+            names: BTreeMap::new(),
+            definitions: BTreeMap::new(),
+        })
+    }
+
+    fn render(&mut self) -> Result<String, String> {
+        let messages = self.messages;
+        let messages = messages
+            .iter()
+            .map(|message| self.message(message))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut code = String::from("// Generated by build.rs from the contract's ink! metadata. Don't edit.\n");
+
+        for message in &messages {
+            code.push_str(&format!(
+                "\n/// Selector of the `{}` message\npub const {}_SELECTOR: [u8; 4] = [{}];\n",
+                message.label,
+                message.method.to_uppercase(),
+                message.selector.iter().map(|byte| format!("0x{:02x}", byte)).collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        if !self.definitions.is_empty() {
+            code.push_str("\n/// Types the contract's messages take and return\npub mod types {\n");
+            let definitions = self.definitions.values().map(|definition| indent(definition)).collect::<Vec<_>>();
+            code.push_str(&definitions.join("\n"));
+            code.push_str("}\n");
         }
+
+        code.push_str("\n#[allow(clippy::too_many_arguments)]\nimpl LsrwaExpressContract {\n");
+        let methods = messages.iter().map(|message| indent(&render_method(message))).collect::<Vec<_>>();
+        code.push_str(&methods.join("\n"));
+        code.push_str("}\n");
+
+        Ok(code)
+    }
+
+    fn message(&mut self, message: &Value) -> Result<Message, String> {
+        let label = message["label"].as_str().ok_or("a message has no label")?.to_string();
+        let method = label.replace("::", "_").to_lowercase();
+        if RESERVED_METHODS.contains(&method.as_str()) {
+            return Err(format!("message `{}` would shadow LsrwaExpressContract::{}", label, method));
+        }
+
+        let selector = message["selector"].as_str().ok_or_else(|| format!("message `{}` has no selector", label))?;
+        let selector = parse_selector(selector).ok_or_else(|| format!("message `{}` has an invalid selector {}", label, selector))?;
+
+        let args = message["args"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|arg| {
+                let name = arg["label"].as_str().ok_or_else(|| format!("an argument of `{}` has no label", label))?;
+                Ok((ident(name), self.rust_type(type_id(&arg["type"]["type"])?)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // Messages return `Result<T, LangError>`, whose `Err` means the contract couldn't read
+        // the call. The contract call itself handles that, so bindings return `T`.
+        let message_result = type_id(&message["returnType"]["type"])?;
+        let message_result_type = self.lookup(message_result)?;
+        if path(message_result_type) != ["Result"] {
+            return Err(format!("message `{}` doesn't return ink!'s MessageResult", label));
+        }
+        let returned = param(message_result_type, 0)?;
+        let returns = self.rust_type(returned)?;
+        let fallible = path(self.lookup(returned)?) == ["Result"];
+
+        Ok(Message {
+            label,
+            method,
+            selector,
+            docs: docs(message),
+            args,
+            returns,
+            fallible,
+            mutates: message["mutates"].as_bool().unwrap_or(false),
+        })
+    }
+
+    fn lookup(&self, id: u64) -> Result<&'a Value, String> {
+        self.registry.get(&id).copied().ok_or_else(|| format!("type {} isn't in the registry", id))
+    }
+
+    /// Rust type of a type in the registry, binding contract types as it meets them
+    fn rust_type(&mut self, id: u64) -> Result<String, String> {
+        let ty = self.lookup(id)?;
+        let def = &ty["def"];
+
+        match path(ty).join("::").as_str() {
+            "ink_primitives::types::AccountId" => return Ok("::subxt::utils::AccountId32".to_string()),
+            "ink_primitives::types::Hash" => return Ok("::subxt::ext::sp_core::H256".to_string()),
+            "Option" => return Ok(format!("::core::option::Option<{}>", self.rust_type(param(ty, 0)?)?)),
+            "Result" => {
+                return Ok(format!(
+                    "::core::result::Result<{}, {}>",
+                    self.rust_type(param(ty, 0)?)?,
+                    self.rust_type(param(ty, 1)?)?
+                ))
+            }
+            _ => {}
+        }
+
+        if let Some(primitive) = def["primitive"].as_str() {
+            return match primitive {
+                "bool" | "char" | "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" => {
+                    Ok(primitive.to_string())
+                }
+                "str" => Ok("::std::string::String".to_string()),
+                other => Err(format!("primitive type {} isn't supported", other)),
+            };
+        }
+        if def.get("sequence").is_some() {
+            return Ok(format!("::std::vec::Vec<{}>", self.rust_type(type_id(&def["sequence"]["type"])?)?));
+        }
+        if def.get("array").is_some() {
+            let len = def["array"]["len"].as_u64().ok_or_else(|| format!("array type {} has no length", id))?;
+            return Ok(format!("[{}; {}]", self.rust_type(type_id(&def["array"]["type"])?)?, len));
+        }
+        if let Some(elements) = def["tuple"].as_array() {
+            let elements = elements
+                .iter()
+                .map(|element| self.rust_type(type_id(element)?))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(match elements.len() {
+                1 => format!("({},)", elements[0]),
+                _ => format!("({})", elements.join(", ")),
+            });
+        }
+        if def.get("compact").is_some() {
+            return Ok(format!("::scale::Compact<{}>", self.rust_type(type_id(&def["compact"]["type"])?)?));
+        }
+        if def.get("composite").is_some() || def.get("variant").is_some() {
+            return self.define(id, ty);
+        }
+
+        Err(format!("type {} isn't supported", id))
+    }
+
+    /// Binds a struct or enum the contract defines, under its own name in `types`
+    fn define(&mut self, id: u64, ty: &'a Value) -> Result<String, String> {
+        if let Some(name) = self.names.get(&id) {
+            return Ok(format!("crate::contract::types::{}", name));
+        }
+
+        let name = path(ty).last().copied().ok_or_else(|| format!("type {} has no name", id))?.to_string();
+        if ty["params"].as_array().is_some_and(|params| !params.is_empty()) {
+            return Err(format!("generic type {} isn't supported", name));
+        }
+        if self.definitions.contains_key(&name) {
+            return Err(format!("more than one type is named {}", name));
+        }
+        // Named before its fields are bound, so a type can refer to itself
+        self.names.insert(id, name.clone());
+        self.definitions.insert(name.clone(), String::new());
+
+        let mut definition = doc_comment(&docs(ty));
+        definition.push_str("#[derive(Debug, Clone, PartialEq, Eq, ::scale::Encode, ::scale::Decode)]\n");
+
+        if let Some(variants) = ty["def"]["variant"]["variants"].as_array() {
+            definition.push_str(&format!("pub enum {} {{\n", name));
+            for variant in variants {
+                let variant_name = variant["name"].as_str().ok_or_else(|| format!("a variant of {} has no name", name))?;
+                let index = variant["index"].as_u64().ok_or_else(|| format!("{}::{} has no index", name, variant_name))?;
+                let fields = self.fields(variant, false)?;
+                let mut variant_definition = doc_comment(&docs(variant));
+                variant_definition.push_str(&format!("#[codec(index = {})]\n{}{},\n", index, variant_name, fields));
+                definition.push_str(&indent(&variant_definition));
+            }
+            definition.push_str("}\n");
+        } else {
+            let fields = self.fields(&ty["def"]["composite"], true)?;
+            let terminator = if fields.starts_with(" {") { "" } else { ";" };
+            definition.push_str(&format!("pub struct {}{}{}\n", name, fields, terminator));
+        }
+
+        self.definitions.insert(name.clone(), definition);
+        Ok(format!("crate::contract::types::{}", name))
+    }
+
+    /// Fields of a struct or variant, as they follow its name
+    fn fields(&mut self, parent: &'a Value, public: bool) -> Result<String, String> {
+        let fields = parent["fields"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let visibility = if public { "pub " } else { "" };
+
+        let mut rendered = Vec::new();
+        for field in fields {
+            let rust_type = self.rust_type(type_id(&field["type"])?)?;
+            rendered.push(match field["name"].as_str() {
+                Some(name) => format!("{}{}: {}", visibility, ident(name), rust_type),
+                None => format!("{}{}", visibility, rust_type),
+            });
+        }
+
+        Ok(match fields.first() {
+            None => String::new(),
+            Some(field) if field["name"].is_string() => {
+                format!(" {{\n{}}}", rendered.iter().map(|field| format!("    {},\n", field)).collect::<String>())
+            }
+            Some(_) => format!("({})", rendered.join(", ")),
+        })
     }
 }
 
-impl LsrwaExpressContract {
-    pub fn new(client: OnlineClient<PolkadotConfig>, address: AccountId) -> Self {
-        Self { client, address }
+/// A method calling `message`. Messages that mutate are dry-run from the signer's account
+/// first, so a call the contract would reject fails with the contract's error before it's
+/// submitted. Read-only messages are only dry-run, and return what the message returns.
+fn render_method(message: &Message) -> String {
+    let mut method = doc_comment(&message.docs);
+    if message.docs.is_empty() {
+        method.push_str(&format!("/// Calls the `{}` message\n", message.label));
     }
-    
-    // Create deposit request method
-    pub async fn create_deposit_request(
-        &self, 
-        signer: &PairSigner<PolkadotConfig, sr25519::Pair>,
-        amount: u128,
-        gas_limit: u64,
-    ) -> Result<H256, Box<dyn std::error::Error>> {
-        use subxt::tx::SubmittableExtrinsic;
-        
-        // Prepare the call data - selector + encoded parameters
-        let mut call_data = CREATE_DEPOSIT_REQUEST_SELECTOR.to_vec();
-        
-        // Encode the amount parameter (SCALE encoding)
-        let mut amount_bytes = Vec::new();
-        amount.encode_to(&mut amount_bytes);
-        call_data.extend(amount_bytes);
-        
-        // Contract call
-        use crate::substrate::tx::contracts::call;
-        
-        // Value to send with the call (0 for now)
-        let value = 0u128;
-        
-        // Call parameters
-        let params = call {
-            dest: MultiAddress::Id(self.address.into()),
-            value,
-            gas_limit,
-            storage_deposit_limit: None,
-            data: call_data,
-        };
-        
-        // Create the signed transaction
-        let tx = self.client
-            .tx()
-            .create_signed(&params, signer, Default::default())
-            .await?;
-            
-        // Submit and watch for finalization
-        let events = tx.submit_and_watch()
-            .await?
-            .wait_for_finalized_success()
-            .await?;
-            
-        // Get the transaction hash
-        let tx_hash = events.extrinsic_hash();
-        
-        Ok(tx_hash)
+
+    let mut params = vec!["&self".to_string()];
+    if message.mutates {
+        params.push("signer: &ContractSigner".to_string());
     }
-    
-    // Create withdrawal request method
-    pub async fn create_withdrawal_request(
-        &self, 
-        signer: &PairSigner<PolkadotConfig, sr25519::Pair>,
-        amount: u128,
-        gas_limit: u64,
-    ) -> Result<H256, Box<dyn std::error::Error>> {
-        use subxt::tx::SubmittableExtrinsic;
-        
-        // Prepare the call data - selector + encoded parameters
-        let mut call_data = CREATE_WITHDRAWAL_REQUEST_SELECTOR.to_vec();
-        
-        // Encode the amount parameter (SCALE encoding)
-        let mut amount_bytes = Vec::new();
-        amount.encode_to(&mut amount_bytes);
-        call_data.extend(amount_bytes);
-        
-        // Contract call
-        use crate::substrate::tx::contracts::call;
-        
-        // Value to send with the call (0 for now)
-        let value = 0u128;
-        
-        // Call parameters
-        let params = call {
-            dest: MultiAddress::Id(self.address.into()),
-            value,
-            gas_limit,
-            storage_deposit_limit: None,
-            data: call_data,
-        };
-        
-        // Create the signed transaction
-        let tx = self.client
-            .tx()
-            .create_signed(&params, signer, Default::default())
-            .await?;
-            
-        // Submit and watch for finalization
-        let events = tx.submit_and_watch()
-            .await?
-            .wait_for_finalized_success()
-            .await?;
-            
-        // Get the transaction hash
-        let tx_hash = events.extrinsic_hash();
-        
-        Ok(tx_hash)
+    params.extend(message.args.iter().map(|(name, rust_type)| format!("{}: {}", name, rust_type)));
+    if message.mutates {
+        params.push("gas_limit: u64".to_string());
     }
+
+    let returns = if message.mutates { "::subxt::ext::sp_core::H256" } else { message.returns.as_str() };
+    method.push_str(&format!(
+        "pub async fn {}(\n{}) -> ::anyhow::Result<{}> {{\n",
+        message.method,
+        params.iter().map(|param| format!("    {},\n", param)).collect::<String>(),
+        returns
+    ));
+
+    let args = message.args.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    let args = match args.len() {
+        1 => format!("({},)", args[0]),
+        _ => format!("({})", args.join(", ")),
+    };
+    method.push_str(&format!("    let input = message_input({}_SELECTOR, {});\n", message.method.to_uppercase(), args));
+
+    if !message.mutates {
+        method.push_str("    self.dry_run(&self.address, input).await\n}\n");
+        return method;
+    }
+
+    let dry_run = format!("self.dry_run::<{}>(&signer_account(signer), input.clone()).await?", message.returns);
+    if message.fallible {
+        method.push_str(&format!(
+            "    if let ::core::result::Result::Err(err) = {} {{\n        ::anyhow::bail!(\"`{}` would fail with {{:?}}\", err);\n    }}\n",
+            dry_run, message.label
+        ));
+    } else {
+        method.push_str(&format!("    {};\n", dry_run));
+    }
+    method.push_str("    self.submit(signer, input, gas_limit).await\n}\n");
+
+    method
+}
+
+fn type_id(value: &Value) -> Result<u64, String> {
+    value.as_u64().ok_or_else(|| format!("invalid type ID {}", value))
+}
+
+fn path(ty: &Value) -> Vec<&str> {
+    ty["path"]
+        .as_array()
+        .map(|segments| segments.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
 }
 
-/// Estimates gas for a deposit request
-pub fn estimate_gas_for_deposit_request(amount: u128) -> u64 {
-    // For now, use a simple gas estimation based on the amount
-    // In production, this would be more sophisticated
-    let base_gas = 500_000;
-    let variable_gas = (amount / 1_000_000) as u64;
-    
-    // Get the gas limit from environment if available
-    let env_gas_limit = std::env::var("DEPOSIT_GAS_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok());
-    
-    match env_gas_limit {
-        Some(limit) => limit,
-        None => base_gas + variable_gas.min(500_000), // Cap at additional 500k gas
+/// Type ID of the `index`th type parameter of a generic type
+fn param(ty: &Value, index: usize) -> Result<u64, String> {
+    type_id(&ty["params"][index]["type"]).map_err(|_| format!("{} has no type parameter {}", path(ty).join("::"), index))
+}
+
+fn docs(item: &Value) -> Vec<String> {
+    item["docs"]
+        .as_array()
+        .map(|lines| lines.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn doc_comment(docs: &[String]) -> String {
+    docs.iter()
+        .map(|line| {
+            if line.is_empty() || line.starts_with(' ') {
+                format!("///{}\n", line)
+            } else {
+                format!("/// {}\n", line)
+            }
+        })
+        .collect()
+}
+
+fn parse_selector(selector: &str) -> Option<[u8; 4]> {
+    let digits = selector.strip_prefix("0x")?;
+    if digits.len() != 8 {
+        return None;
     }
+
+    let mut bytes = [0u8; 4];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(digits.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
 }
 
-/// Estimates gas for a withdrawal request
-pub fn estimate_gas_for_withdrawal_request(amount: u128) -> u64 {
-    // For now, use a simple gas estimation based on the amount
-    // In production, this would be more sophisticated
-    let base_gas = 600_000; // Slightly higher than deposit due to balance checks
-    let variable_gas = (amount / 1_000_000) as u64;
-    
-    // Get the gas limit from environment if available
-    let env_gas_limit = std::env::var("WITHDRAWAL_GAS_LIMIT")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok());
-    
-    match env_gas_limit {
-        Some(limit) => limit,
-        None => base_gas + variable_gas.min(500_000), // Cap at additional 500k gas
+/// `name` as an identifier, escaped if it's a keyword
+fn ident(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern", "fn", "for",
+        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+        "struct", "trait", "type", "unsafe", "use", "where", "while", "yield",
+    ];
+
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
     }
 }
-"#;
 
-    let binding_path = generated_dir.join("contract_bindings.rs");
-    fs::write(binding_path, binding_code)?;
-    
-    Ok(())
-} 
\ No newline at end of file
+/// Indents every non-empty line of `code` by one level
+fn indent(code: &str) -> String {
+    code.lines()
+        .map(|line| if line.is_empty() { "\n".to_string() } else { format!("    {}\n", line) })
+        .collect()
+}
@@ -0,0 +1,10 @@
+//! Feeds arbitrary call sequences to the contract's message dispatch; see
+//! `lsrwa_express_contract::fuzzing` for the input format
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    lsrwa_express_contract::fuzzing::dispatch(data);
+});
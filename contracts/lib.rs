@@ -31,6 +31,11 @@ mod lsrwa_express {
         WithdrawalNotProcessed,
         NotRequestOwner,
         TransferFailed,
+        BorrowNotProcessed,
+        AlreadyLiquidated,
+        FeeTooHigh,
+        InvalidRiskParameters,
+        Overflow,
     }
 
     /// Result type for the contract
@@ -53,6 +58,8 @@ mod lsrwa_express {
         request_type: RequestType,
         wallet_address: AccountId,
         amount: Balance,
+        /// Collateral pledged for a borrow, zero for deposits and withdrawals
+        collateral: Balance,
         timestamp: Timestamp,
         is_processed: bool,
     }
@@ -125,6 +132,53 @@ mod lsrwa_express {
         failed_count: u32,
     }
 
+    /// Event emitted when a wallet's KYC approval changes
+    #[ink(event)]
+    pub struct KycStatusUpdated {
+        #[ink(topic)]
+        wallet_address: AccountId,
+        approved: bool,
+    }
+
+    /// Event emitted when an under-collateralized borrow is liquidated
+    #[ink(event)]
+    pub struct BorrowLiquidated {
+        #[ink(topic)]
+        request_id: u128,
+        #[ink(topic)]
+        wallet_address: AccountId,
+        amount: Balance,
+    }
+
+    /// Protocol fee types
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub enum FeeType {
+        /// Charged on executed withdrawals
+        Withdrawal,
+        /// Penalty charged on liquidated borrows
+        Liquidation,
+    }
+
+    /// Event emitted when a protocol fee is paid to the treasury
+    #[ink(event)]
+    pub struct FeeCollected {
+        #[ink(topic)]
+        request_id: u128,
+        #[ink(topic)]
+        wallet_address: AccountId,
+        fee_type: FeeType,
+        amount: Balance,
+    }
+
+    /// Event emitted when the owner updates the risk parameters
+    #[ink(event)]
+    pub struct RiskParametersUpdated {
+        min_deposit_amount: Balance,
+        min_withdrawal_amount: Balance,
+        min_collateral_ratio: u128,
+    }
+
     /// Epoch status enum
     #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
@@ -218,6 +272,21 @@ mod lsrwa_express {
         
         /// Minimum collateral ratio (in percentage, e.g. 150 means 150%)
         min_collateral_ratio: u128,
+        
+        /// Wallets whose KYC has been approved off-chain
+        kyc_approved: Mapping<AccountId, bool>,
+        
+        /// Borrow request IDs that have been liquidated
+        liquidated_borrows: Mapping<u128, bool>,
+        
+        /// Account protocol fees are paid to
+        treasury: AccountId,
+        
+        /// Fee on executed withdrawals, in basis points
+        withdrawal_fee_bps: u32,
+        
+        /// Penalty on liquidated borrows, in basis points
+        liquidation_fee_bps: u32,
     }
 
     impl LsrwaExpress {
@@ -253,6 +322,11 @@ mod lsrwa_express {
                 min_deposit_amount: 10,         // Minimum 10 tokens for deposit
                 min_withdrawal_amount: 10,      // Minimum 10 tokens for withdrawal
                 min_collateral_ratio: 150,      // Minimum 150% collateral ratio
+                kyc_approved: Mapping::default(),
+                liquidated_borrows: Mapping::default(),
+                treasury: caller,
+                withdrawal_fee_bps: 0,
+                liquidation_fee_bps: 0,
             }
         }
         
@@ -290,6 +364,12 @@ mod lsrwa_express {
                 return Err(Error::AmountTooLow);
             }
             
+            // Ensure the user's pending deposits can hold the amount
+            let pending_deposits = self.users.get(caller)
+                .map_or(0, |user| user.pending_deposits)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            
             // Check if the user exists, if not, register them
             let user = self.users.get(caller);
             if user.is_none() {
@@ -323,6 +403,7 @@ mod lsrwa_express {
                 request_type: RequestType::Deposit,
                 wallet_address: caller,
                 amount,
+                collateral: 0,
                 timestamp: current_time,
                 is_processed: false,
             };
@@ -337,7 +418,7 @@ mod lsrwa_express {
             
             // Update user's pending deposits
             if let Some(mut user) = self.users.get(caller) {
-                user.pending_deposits += amount;
+                user.pending_deposits = pending_deposits;
                 self.users.insert(caller, &user);
             }
             
@@ -382,6 +463,9 @@ mod lsrwa_express {
                 return Err(Error::InsufficientBalance);
             }
             
+            // Ensure the user's pending withdrawals can hold the amount
+            let pending_withdrawals = user.pending_withdrawals.checked_add(amount).ok_or(Error::Overflow)?;
+            
             // Get current request ID and increment for next use
             let request_id = self.next_request_id;
             self.next_request_id += 1;
@@ -395,6 +479,7 @@ mod lsrwa_express {
                 request_type: RequestType::Withdrawal,
                 wallet_address: caller,
                 amount,
+                collateral: 0,
                 timestamp: current_time,
                 is_processed: false,
             };
@@ -410,7 +495,7 @@ mod lsrwa_express {
             // Update user's balances
             if let Some(mut user) = self.users.get(caller) {
                 user.active_balance -= amount;
-                user.pending_withdrawals += amount;
+                user.pending_withdrawals = pending_withdrawals;
                 self.users.insert(caller, &user);
             }
             
@@ -456,7 +541,7 @@ mod lsrwa_express {
             };
             
             // Update the user's balances
-            user.active_balance += request.amount;
+            user.active_balance = user.active_balance.checked_add(request.amount).ok_or(Error::Overflow)?;
             user.pending_deposits -= request.amount;
             
             // Mark the request as processed
@@ -552,7 +637,7 @@ mod lsrwa_express {
             }
             
             // Ensure collateral is sufficient (collateral >= amount * min_collateral_ratio / 100)
-            let min_required_collateral = amount * self.min_collateral_ratio / 100;
+            let min_required_collateral = amount.checked_mul(self.min_collateral_ratio).ok_or(Error::Overflow)? / 100;
             if collateral < min_required_collateral {
                 return Err(Error::InsufficientBalance);
             }
@@ -580,6 +665,7 @@ mod lsrwa_express {
                 request_type: RequestType::Borrow,
                 wallet_address: caller,
                 amount,
+                collateral,
                 timestamp: current_time,
                 is_processed: false,
             };
@@ -635,7 +721,7 @@ mod lsrwa_express {
             };
             
             // Update the user's balances
-            user.active_balance += request.amount;
+            user.active_balance = user.active_balance.checked_add(request.amount).ok_or(Error::Overflow)?;
             
             // Mark the request as processed
             request.is_processed = true;
@@ -660,6 +746,180 @@ mod lsrwa_express {
             Ok(())
         }
         
+        /// Liquidate a processed borrow whose collateral no longer covers it (owner only)
+        ///
+        /// The borrowed amount is taken back out of the borrower's active balance.
+        #[ink(message)]
+        pub fn liquidate_borrow(&mut self, request_id: u128) -> Result<()> {
+            // Only owner can liquidate borrows
+            let caller = Self::env().caller();
+            if caller != self.owner {
+                return Err(Error::NotOwner);
+            }
+            
+            // Get the request
+            let request = match self.requests.get(request_id) {
+                Some(request) => request,
+                None => return Err(Error::RequestNotFound),
+            };
+            
+            // Ensure the request is a borrow
+            if request.request_type != RequestType::Borrow {
+                return Err(Error::NotBorrowRequest);
+            }
+            
+            // Only funded borrows can be liquidated
+            if !request.is_processed {
+                return Err(Error::BorrowNotProcessed);
+            }
+            
+            // Ensure the borrow is not already liquidated
+            if self.liquidated_borrows.get(request_id).unwrap_or(false) {
+                return Err(Error::AlreadyLiquidated);
+            }
+            
+            // Get the user
+            let mut user = match self.users.get(request.wallet_address) {
+                Some(user) => user,
+                None => return Err(Error::UserNotFound),
+            };
+            
+            // Take the borrowed amount back, then the liquidation penalty out of what is left
+            user.active_balance = user.active_balance.saturating_sub(request.amount);
+            let fee = Self::fee(request.amount, self.liquidation_fee_bps).min(user.active_balance);
+            user.active_balance -= fee;
+            
+            // Store the updated user and mark the borrow as liquidated
+            self.users.insert(request.wallet_address, &user);
+            self.liquidated_borrows.insert(request_id, &true);
+            self.collect_fee(request_id, request.wallet_address, FeeType::Liquidation, fee)?;
+            
+            // Emit borrow liquidated event
+            Self::env().emit_event(BorrowLiquidated {
+                request_id,
+                wallet_address: request.wallet_address,
+                amount: request.amount,
+            });
+            
+            Ok(())
+        }
+        
+        /// Set the account protocol fees are paid to (owner only)
+        #[ink(message)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<()> {
+            if Self::env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            
+            self.treasury = treasury;
+            
+            Ok(())
+        }
+        
+        /// Get the account protocol fees are paid to
+        #[ink(message)]
+        pub fn get_treasury(&self) -> AccountId {
+            self.treasury
+        }
+        
+        /// Set a protocol fee in basis points (owner only)
+        #[ink(message)]
+        pub fn set_fee_bps(&mut self, fee_type: FeeType, fee_bps: u32) -> Result<()> {
+            if Self::env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            
+            // Fees are capped at 10%
+            if fee_bps > 1_000 {
+                return Err(Error::FeeTooHigh);
+            }
+            
+            match fee_type {
+                FeeType::Withdrawal => self.withdrawal_fee_bps = fee_bps,
+                FeeType::Liquidation => self.liquidation_fee_bps = fee_bps,
+            }
+            
+            Ok(())
+        }
+        
+        /// Get a protocol fee in basis points
+        #[ink(message)]
+        pub fn get_fee_bps(&self, fee_type: FeeType) -> u32 {
+            match fee_type {
+                FeeType::Withdrawal => self.withdrawal_fee_bps,
+                FeeType::Liquidation => self.liquidation_fee_bps,
+            }
+        }
+        
+        /// Set the minimum request amounts and collateral ratio (owner only)
+        #[ink(message)]
+        pub fn set_risk_parameters(
+            &mut self,
+            min_deposit_amount: Balance,
+            min_withdrawal_amount: Balance,
+            min_collateral_ratio: u128,
+        ) -> Result<()> {
+            if Self::env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            
+            // Borrows must be at least fully collateralized
+            if min_collateral_ratio < 100 {
+                return Err(Error::InvalidRiskParameters);
+            }
+            
+            self.min_deposit_amount = min_deposit_amount;
+            self.min_withdrawal_amount = min_withdrawal_amount;
+            self.min_collateral_ratio = min_collateral_ratio;
+            
+            Self::env().emit_event(RiskParametersUpdated {
+                min_deposit_amount,
+                min_withdrawal_amount,
+                min_collateral_ratio,
+            });
+            
+            Ok(())
+        }
+        
+        /// Get the minimum deposit amount, minimum withdrawal amount and minimum collateral ratio
+        #[ink(message)]
+        pub fn get_risk_parameters(&self) -> (Balance, Balance, u128) {
+            (self.min_deposit_amount, self.min_withdrawal_amount, self.min_collateral_ratio)
+        }
+        
+        /// Fee of `fee_bps` basis points on an amount
+        fn fee(amount: Balance, fee_bps: u32) -> Balance {
+            amount.saturating_mul(fee_bps as Balance) / 10_000
+        }
+        
+        /// Pays a fee to the treasury
+        fn collect_fee(&mut self, request_id: u128, wallet_address: AccountId, fee_type: FeeType, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Ok(());
+            }
+            
+            // In the test environment the contract holds no funds, so the transfer is skipped
+            #[cfg(not(test))]
+            if self.env().transfer(self.treasury, amount).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            
+            Self::env().emit_event(FeeCollected {
+                request_id,
+                wallet_address,
+                fee_type,
+                amount,
+            });
+            
+            Ok(())
+        }
+        
+        /// Check whether a borrow has been liquidated
+        #[ink(message)]
+        pub fn is_borrow_liquidated(&self, request_id: u128) -> bool {
+            self.liquidated_borrows.get(request_id).unwrap_or(false)
+        }
+        
         /// Gets all deposit request IDs for a user
         #[ink(message)]
         pub fn get_user_deposit_requests(&self, wallet_address: AccountId) -> Vec<u128> {
@@ -880,10 +1140,12 @@ mod lsrwa_express {
                 return Err(Error::WithdrawalNotProcessed);
             }
             
-            // Transfer the funds to the user
-            if self.env().transfer(caller, request.amount).is_err() {
+            // The withdrawal fee goes to the treasury and the rest to the user
+            let fee = Self::fee(request.amount, self.withdrawal_fee_bps);
+            if self.env().transfer(caller, request.amount - fee).is_err() {
                 return Err(Error::TransferFailed);
             }
+            self.collect_fee(request_id, caller, FeeType::Withdrawal, fee)?;
             
             // Emit withdrawal executed event
             Self::env().emit_event(WithdrawalExecuted {
@@ -933,6 +1195,42 @@ mod lsrwa_express {
             Ok(())
         }
         
+        /// Set the KYC approval of a batch of wallets (owner only)
+        #[ink(message)]
+        pub fn set_kyc_approvals(&mut self, wallet_addresses: Vec<AccountId>, approved: bool) -> Result<()> {
+            // Only owner can update the KYC allowlist
+            let caller = Self::env().caller();
+            if caller != self.owner {
+                return Err(Error::NotOwner);
+            }
+            
+            // Ensure the batch is not empty
+            if wallet_addresses.is_empty() {
+                return Err(Error::EmptyBatch);
+            }
+            
+            for wallet_address in wallet_addresses {
+                if approved {
+                    self.kyc_approved.insert(wallet_address, &true);
+                } else {
+                    self.kyc_approved.remove(wallet_address);
+                }
+                
+                Self::env().emit_event(KycStatusUpdated {
+                    wallet_address,
+                    approved,
+                });
+            }
+            
+            Ok(())
+        }
+        
+        /// Check whether a wallet's KYC is approved
+        #[ink(message)]
+        pub fn is_kyc_approved(&self, wallet_address: AccountId) -> bool {
+            self.kyc_approved.get(wallet_address).unwrap_or(false)
+        }
+        
         /// Get the contract balance
         #[ink(message)]
         pub fn get_contract_balance(&self) -> Balance {
@@ -1037,6 +1335,7 @@ mod lsrwa_express {
             assert_eq!(request.request_type, RequestType::Deposit);
             assert_eq!(request.wallet_address, accounts.bob);
             assert_eq!(request.amount, deposit_amount);
+            assert_eq!(request.collateral, 0);
             assert!(!request.is_processed);
             
             // Verify the user was created and automatically registered
@@ -1168,6 +1467,7 @@ mod lsrwa_express {
             assert_eq!(request.request_type, RequestType::Borrow);
             assert_eq!(request.wallet_address, accounts.bob);
             assert_eq!(request.amount, borrow_amount);
+            assert_eq!(request.collateral, collateral);
             assert!(!request.is_processed);
             
             // Process the borrow request as owner
@@ -1279,5 +1579,736 @@ mod lsrwa_express {
             // We don't test the actual transfer as it requires setting up contract balance
             // which is more complex in the test environment
         }
+        
+        /// Test KYC allowlist updates
+        #[ink::test]
+        fn test_set_kyc_approvals() {
+            let accounts = get_default_accounts();
+            let mut contract = init_contract();
+            
+            // Try as non-owner (should fail)
+            test::set_caller::<Env>(accounts.bob);
+            let result = contract.set_kyc_approvals(vec![accounts.bob], true);
+            assert_eq!(result.unwrap_err(), Error::NotOwner);
+            
+            // Try an empty batch (should fail)
+            test::set_caller::<Env>(accounts.alice);
+            let result = contract.set_kyc_approvals(Vec::new(), true);
+            assert_eq!(result.unwrap_err(), Error::EmptyBatch);
+            
+            // Approve Bob and Charlie
+            contract.set_kyc_approvals(vec![accounts.bob, accounts.charlie], true).expect("Should approve wallets");
+            assert!(contract.is_kyc_approved(accounts.bob));
+            assert!(contract.is_kyc_approved(accounts.charlie));
+            assert!(!contract.is_kyc_approved(accounts.django));
+            
+            // Revoke Charlie
+            contract.set_kyc_approvals(vec![accounts.charlie], false).expect("Should revoke wallet");
+            assert!(contract.is_kyc_approved(accounts.bob));
+            assert!(!contract.is_kyc_approved(accounts.charlie));
+        }
+        
+        /// Test liquidating a borrow
+        #[ink::test]
+        fn test_liquidate_borrow() {
+            let accounts = get_default_accounts();
+            let mut contract = init_contract();
+            
+            // Register Bob with a processed deposit
+            test::set_caller::<Env>(accounts.bob);
+            let deposit_id = contract.create_deposit_request(100).expect("Should create deposit request");
+            test::set_caller::<Env>(accounts.alice);
+            contract.process_deposit_request(deposit_id).expect("Should process deposit");
+            
+            // Bob borrows 50 against 100 collateral
+            test::set_caller::<Env>(accounts.bob);
+            let borrow_id = contract.create_borrow_request(50, 100).expect("Should create borrow request");
+            
+            // An unprocessed borrow can't be liquidated
+            test::set_caller::<Env>(accounts.alice);
+            let result = contract.liquidate_borrow(borrow_id);
+            assert_eq!(result.unwrap_err(), Error::BorrowNotProcessed);
+            
+            contract.process_borrow_request(borrow_id).expect("Should process borrow");
+            
+            // Try as non-owner (should fail)
+            test::set_caller::<Env>(accounts.bob);
+            let result = contract.liquidate_borrow(borrow_id);
+            assert_eq!(result.unwrap_err(), Error::NotOwner);
+            
+            // Deposits can't be liquidated
+            test::set_caller::<Env>(accounts.alice);
+            let result = contract.liquidate_borrow(deposit_id);
+            assert_eq!(result.unwrap_err(), Error::NotBorrowRequest);
+            
+            // Liquidate as owner
+            contract.liquidate_borrow(borrow_id).expect("Should liquidate borrow");
+            assert!(contract.is_borrow_liquidated(borrow_id));
+            
+            // The borrowed amount is taken back
+            let user = contract.get_user(accounts.bob).expect("User should exist");
+            assert_eq!(user.active_balance, 100);
+            
+            // A second liquidation fails
+            let result = contract.liquidate_borrow(borrow_id);
+            assert_eq!(result.unwrap_err(), Error::AlreadyLiquidated);
+        }
+        
+        /// Test protocol fee settings and the liquidation penalty
+        #[ink::test]
+        fn test_protocol_fees() {
+            let accounts = get_default_accounts();
+            let mut contract = init_contract();
+            
+            // Fees start at zero and go to the owner
+            assert_eq!(contract.get_fee_bps(FeeType::Withdrawal), 0);
+            assert_eq!(contract.get_treasury(), accounts.alice);
+            
+            // Try as non-owner (should fail)
+            test::set_caller::<Env>(accounts.bob);
+            assert_eq!(contract.set_fee_bps(FeeType::Withdrawal, 50).unwrap_err(), Error::NotOwner);
+            assert_eq!(contract.set_treasury(accounts.bob).unwrap_err(), Error::NotOwner);
+            
+            // Fees above 10% are refused
+            test::set_caller::<Env>(accounts.alice);
+            assert_eq!(contract.set_fee_bps(FeeType::Liquidation, 1_001).unwrap_err(), Error::FeeTooHigh);
+            
+            contract.set_fee_bps(FeeType::Withdrawal, 50).expect("Should set withdrawal fee");
+            contract.set_fee_bps(FeeType::Liquidation, 1_000).expect("Should set liquidation fee");
+            contract.set_treasury(accounts.charlie).expect("Should set treasury");
+            assert_eq!(contract.get_fee_bps(FeeType::Withdrawal), 50);
+            assert_eq!(contract.get_fee_bps(FeeType::Liquidation), 1_000);
+            assert_eq!(contract.get_treasury(), accounts.charlie);
+            
+            // Bob deposits 100 and borrows 50
+            test::set_caller::<Env>(accounts.bob);
+            let deposit_id = contract.create_deposit_request(100).expect("Should create deposit request");
+            test::set_caller::<Env>(accounts.alice);
+            contract.process_deposit_request(deposit_id).expect("Should process deposit");
+            test::set_caller::<Env>(accounts.bob);
+            let borrow_id = contract.create_borrow_request(50, 100).expect("Should create borrow request");
+            test::set_caller::<Env>(accounts.alice);
+            contract.process_borrow_request(borrow_id).expect("Should process borrow");
+            
+            // Liquidation takes back the borrow plus a 10% penalty
+            contract.liquidate_borrow(borrow_id).expect("Should liquidate borrow");
+            let user = contract.get_user(accounts.bob).expect("User should exist");
+            assert_eq!(user.active_balance, 95);
+        }
+        
+        #[ink::test]
+        fn test_set_risk_parameters() {
+            let accounts = get_default_accounts();
+            let mut contract = init_contract();
+            
+            assert_eq!(contract.get_risk_parameters(), (10, 10, 150));
+            
+            // Try as non-owner (should fail)
+            test::set_caller::<Env>(accounts.bob);
+            assert_eq!(contract.set_risk_parameters(20, 20, 200).unwrap_err(), Error::NotOwner);
+            
+            // Under-collateralized borrows are refused
+            test::set_caller::<Env>(accounts.alice);
+            assert_eq!(contract.set_risk_parameters(20, 20, 99).unwrap_err(), Error::InvalidRiskParameters);
+            
+            contract.set_risk_parameters(20, 30, 200).expect("Should set risk parameters");
+            assert_eq!(contract.get_risk_parameters(), (20, 30, 200));
+            
+            // The new minimum applies to deposits
+            test::set_caller::<Env>(accounts.bob);
+            assert_eq!(contract.create_deposit_request(15).unwrap_err(), Error::AmountTooLow);
+        }
+    }
+
+    /// Property tests: random sequences of calls must keep every user's books consistent
+    ///
+    /// Sequences mix requests, single and batch processing, liquidations and epoch closes, with
+    /// amounts skewed towards `Balance::MAX` to reach the arithmetic limits. The contract has no
+    /// cancellation messages, so none are generated. Balances are unsigned and tests build with
+    /// overflow checks, so an underflow or overflow fails the test as a panic.
+    #[cfg(test)]
+    mod invariant_tests {
+        use super::*;
+        use ink::env::test::{self, DefaultAccounts};
+        use ink::env::DefaultEnvironment;
+        use proptest::prelude::*;
+        use scale::Encode;
+
+        type Env = DefaultEnvironment;
+
+        /// Highest request ID calls refer to; some of them never exist
+        const MAX_REQUEST_ID: u128 = 32;
+
+        /// A contract call, made by the owner unless it names a user
+        #[derive(Debug, Clone)]
+        enum Op {
+            Deposit { user: usize, amount: Balance },
+            Withdraw { user: usize, amount: Balance },
+            Borrow { user: usize, amount: Balance, collateral: Balance },
+            Process { request_type: RequestType, request_id: u128 },
+            BatchProcess { request_type: RequestType, request_ids: Vec<u128> },
+            Liquidate { request_id: u128 },
+            CloseEpoch,
+        }
+
+        fn amount() -> impl Strategy<Value = Balance> {
+            prop_oneof![
+                4 => 0..1_000 as Balance,
+                1 => Just(Balance::MAX),
+                1 => Balance::MAX - 1_000..=Balance::MAX,
+                1 => any::<Balance>(),
+            ]
+        }
+
+        fn request_type() -> impl Strategy<Value = RequestType> {
+            prop_oneof![Just(RequestType::Deposit), Just(RequestType::Withdrawal), Just(RequestType::Borrow)]
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            prop_oneof![
+                3 => (0..3usize, amount()).prop_map(|(user, amount)| Op::Deposit { user, amount }),
+                2 => (0..3usize, amount()).prop_map(|(user, amount)| Op::Withdraw { user, amount }),
+                2 => (0..3usize, amount(), any::<Balance>())
+                    .prop_map(|(user, amount, collateral)| Op::Borrow { user, amount, collateral }),
+                4 => (request_type(), 0..=MAX_REQUEST_ID)
+                    .prop_map(|(request_type, request_id)| Op::Process { request_type, request_id }),
+                1 => (request_type(), prop::collection::vec(0..=MAX_REQUEST_ID, 0..6))
+                    .prop_map(|(request_type, request_ids)| Op::BatchProcess { request_type, request_ids }),
+                1 => (0..=MAX_REQUEST_ID).prop_map(|request_id| Op::Liquidate { request_id }),
+                1 => Just(Op::CloseEpoch),
+            ]
+        }
+
+        fn users(accounts: &DefaultAccounts<Env>) -> [AccountId; 3] {
+            [accounts.bob, accounts.charlie, accounts.django]
+        }
+
+        fn apply(contract: &mut LsrwaExpress, accounts: &DefaultAccounts<Env>, op: &Op) -> Result<()> {
+            let users = users(accounts);
+            test::set_caller::<Env>(accounts.alice);
+
+            match op.clone() {
+                Op::Deposit { user, amount } => {
+                    test::set_caller::<Env>(users[user]);
+                    contract.create_deposit_request(amount).map(|_| ())
+                },
+                Op::Withdraw { user, amount } => {
+                    test::set_caller::<Env>(users[user]);
+                    contract.create_withdrawal_request(amount).map(|_| ())
+                },
+                Op::Borrow { user, amount, collateral } => {
+                    test::set_caller::<Env>(users[user]);
+                    contract.create_borrow_request(amount, collateral).map(|_| ())
+                },
+                Op::Process { request_type, request_id } => match request_type {
+                    RequestType::Deposit => contract.process_deposit_request(request_id),
+                    RequestType::Withdrawal => contract.process_withdrawal_request(request_id),
+                    RequestType::Borrow => contract.process_borrow_request(request_id),
+                },
+                Op::BatchProcess { request_type, request_ids } => match request_type {
+                    RequestType::Deposit => contract.batch_process_deposit_requests(request_ids),
+                    RequestType::Withdrawal => contract.batch_process_withdrawal_requests(request_ids),
+                    RequestType::Borrow => contract.batch_process_borrow_requests(request_ids),
+                },
+                Op::Liquidate { request_id } => contract.liquidate_borrow(request_id),
+                Op::CloseEpoch => contract.close_current_epoch().map(|_| ()),
+            }
+        }
+
+        /// Everything a call can change, encoded
+        fn snapshot(contract: &LsrwaExpress, users: &[AccountId]) -> Vec<u8> {
+            let requests = (1..contract.next_request_id)
+                .map(|request_id| (contract.get_request(request_id), contract.is_borrow_liquidated(request_id)))
+                .collect::<Vec<_>>();
+            let users = users.iter().map(|user| contract.get_user(*user)).collect::<Vec<_>>();
+            (users, requests, contract.current_epoch.clone(), contract.next_epoch_id).encode()
+        }
+
+        /// Sums amounts as (carries, remainder), so totals past `Balance::MAX` still compare
+        fn wide_sum(amounts: impl IntoIterator<Item = Balance>) -> (u128, u128) {
+            amounts.into_iter().fold((0, 0), |(carries, remainder), amount| {
+                let (remainder, carried) = remainder.overflowing_add(amount);
+                (carries + carried as u128, remainder)
+            })
+        }
+
+        fn requests(contract: &LsrwaExpress, request_ids: Vec<u128>) -> Vec<Request> {
+            request_ids
+                .into_iter()
+                .map(|request_id| contract.get_request(request_id).expect("Listed requests should exist"))
+                .collect()
+        }
+
+        fn amounts(requests: &[Request], processed: bool) -> Vec<Balance> {
+            requests.iter().filter(|request| request.is_processed == processed).map(|request| request.amount).collect()
+        }
+
+        fn check_invariants(contract: &LsrwaExpress, users: &[AccountId]) {
+            for wallet_address in users {
+                let Some(user) = contract.get_user(*wallet_address) else {
+                    continue;
+                };
+                let deposits = requests(contract, contract.get_user_deposit_requests(*wallet_address));
+                let withdrawals = requests(contract, contract.get_user_withdrawal_requests(*wallet_address));
+                let borrows = requests(contract, contract.get_user_borrow_requests(*wallet_address));
+
+                // Pending totals are exactly the unprocessed requests
+                assert_eq!(user.pending_deposits, amounts(&deposits, false).into_iter().sum::<Balance>());
+                assert_eq!(user.pending_withdrawals, amounts(&withdrawals, false).into_iter().sum::<Balance>());
+
+                // Everything a user holds or has had paid out was credited to them; liquidations
+                // take borrows and penalties back, otherwise nothing is lost
+                let held = wide_sum([user.active_balance, user.pending_withdrawals].into_iter().chain(amounts(&withdrawals, true)));
+                let credited = wide_sum(amounts(&deposits, true).into_iter().chain(amounts(&borrows, true)));
+                let liquidated = borrows.iter().any(|borrow| contract.is_borrow_liquidated(borrow.id));
+                if liquidated {
+                    assert!(held <= credited, "{:?} holds more than it was credited", user);
+                } else {
+                    assert_eq!(held, credited, "{:?} does not hold what it was credited", user);
+                }
+
+                // Only processed borrows are liquidated
+                for borrow in &borrows {
+                    assert!(borrow.is_processed || !contract.is_borrow_liquidated(borrow.id));
+                }
+            }
+
+            // Each processing is counted once, in the epoch it happened in
+            let epochs = (1..contract.next_epoch_id)
+                .filter_map(|epoch_id| contract.get_epoch(epoch_id))
+                .chain(contract.get_current_epoch())
+                .collect::<Vec<_>>();
+            let processed = (1..contract.next_request_id)
+                .filter_map(|request_id| contract.get_request(request_id))
+                .filter(|request| request.is_processed)
+                .collect::<Vec<_>>();
+            for (request_type, counted) in [
+                (RequestType::Deposit, epochs.iter().map(|epoch| epoch.processed_deposit_count).sum::<u32>()),
+                (RequestType::Withdrawal, epochs.iter().map(|epoch| epoch.processed_withdrawal_count).sum::<u32>()),
+                (RequestType::Borrow, epochs.iter().map(|epoch| epoch.processed_borrow_count).sum::<u32>()),
+            ] {
+                let processed = processed.iter().filter(|request| request.request_type == request_type).count();
+                assert_eq!(counted as usize, processed, "{:?} requests counted in epochs", request_type);
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn random_calls_keep_the_books_consistent(
+                ops in prop::collection::vec(op(), 1..40),
+                liquidation_fee_bps in 0..=1_000u32,
+            ) {
+                test::run_test::<Env, _>(|accounts| {
+                    test::set_caller::<Env>(accounts.alice);
+                    let mut contract = LsrwaExpress::new();
+                    contract.set_fee_bps(FeeType::Liquidation, liquidation_fee_bps).expect("Fee should be within the cap");
+                    let users = users(&accounts);
+
+                    for op in &ops {
+                        let before = snapshot(&contract, &users);
+                        if let Err(err) = apply(&mut contract, &accounts, op) {
+                            assert_eq!(snapshot(&contract, &users), before, "{:?} failed with {:?} but changed the contract", op, err);
+                        }
+                        check_invariants(&contract, &users);
+                    }
+
+                    Ok(())
+                })
+                .expect("Off-chain environment should run the sequence");
+            }
+        }
+    }
+
+    /// End-to-end tests against a `substrate-contracts-node`, where transfers are real
+    ///
+    /// Run with `cargo test --features e2e-tests`; the node is started from `CONTRACTS_NODE`,
+    /// or `substrate-contracts-node` on the path.
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::subxt::dynamic::Value;
+        use ink_e2e::{ChainBackend, ContractsBackend, E2EBackend};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// Funds the contract holds for withdrawals to be paid from
+        const CONTRACT_FUNDS: Balance = 1_000_000_000_000;
+
+        /// Deposited by each user
+        const DEPOSIT: Balance = 10_000_000_000;
+
+        /// Transfers from Alice to an account with `Balances.transfer_allow_death`
+        async fn fund<Client: E2EBackend>(client: &mut Client, account: AccountId, amount: Balance) {
+            let call_data = vec![
+                Value::unnamed_variant("Id", [Value::from_bytes(account)]),
+                Value::u128(amount),
+            ];
+            client
+                .runtime_call(&ink_e2e::alice(), "Balances", "transfer_allow_death", call_data)
+                .await
+                .expect("transfer failed");
+        }
+
+        #[ink_e2e::test]
+        async fn deposits_are_withdrawn_with_real_transfers<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let mut constructor = LsrwaExpressRef::new();
+            let contract = client
+                .instantiate("lsrwa_express_contract", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut call_builder = contract.call_builder::<LsrwaExpress>();
+            fund(&mut client, contract.account_id, CONTRACT_FUNDS).await;
+
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let charlie = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+
+            // A 0.5% withdrawal fee, paid to Charlie as the treasury
+            client
+                .call(&ink_e2e::alice(), &call_builder.set_fee_bps(FeeType::Withdrawal, 50))
+                .submit()
+                .await
+                .expect("set_fee_bps failed");
+            client
+                .call(&ink_e2e::alice(), &call_builder.set_treasury(charlie))
+                .submit()
+                .await
+                .expect("set_treasury failed");
+
+            // Bob deposits and the owner processes it
+            let deposit_id = client
+                .call(&ink_e2e::bob(), &call_builder.create_deposit_request(DEPOSIT))
+                .submit()
+                .await
+                .expect("create_deposit_request failed")
+                .return_value()
+                .expect("the call should have succeeded");
+            client
+                .call(&ink_e2e::alice(), &call_builder.process_deposit_request(deposit_id))
+                .submit()
+                .await
+                .expect("process_deposit_request failed");
+
+            // Bob withdraws part of it
+            let withdrawal = DEPOSIT / 4;
+            let withdrawal_id = client
+                .call(&ink_e2e::bob(), &call_builder.create_withdrawal_request(withdrawal))
+                .submit()
+                .await
+                .expect("create_withdrawal_request failed")
+                .return_value()
+                .expect("the call should have succeeded");
+
+            // It can't be executed until it's processed, and only by Bob
+            let early = client
+                .call(&ink_e2e::bob(), &call_builder.execute_withdrawal(withdrawal_id))
+                .dry_run()
+                .await?;
+            assert_eq!(early.return_value(), Err(Error::WithdrawalNotProcessed));
+            client
+                .call(&ink_e2e::alice(), &call_builder.process_withdrawal_request(withdrawal_id))
+                .submit()
+                .await
+                .expect("process_withdrawal_request failed");
+            let stranger = client
+                .call(&ink_e2e::charlie(), &call_builder.execute_withdrawal(withdrawal_id))
+                .dry_run()
+                .await?;
+            assert_eq!(stranger.return_value(), Err(Error::NotRequestOwner));
+
+            let contract_before = client.free_balance(contract.account_id).await.expect("balance query failed");
+            let bob_before = client.free_balance(bob).await.expect("balance query failed");
+            let charlie_before = client.free_balance(charlie).await.expect("balance query failed");
+
+            client
+                .call(&ink_e2e::bob(), &call_builder.execute_withdrawal(withdrawal_id))
+                .submit()
+                .await
+                .expect("execute_withdrawal failed");
+
+            // The contract pays out the whole amount: the fee to the treasury and the rest to
+            // Bob, who also paid for the call
+            let fee = withdrawal * 50 / 10_000;
+            assert_eq!(client.free_balance(contract.account_id).await.expect("balance query failed"), contract_before - withdrawal);
+            assert_eq!(client.free_balance(charlie).await.expect("balance query failed"), charlie_before + fee);
+            let bob_after = client.free_balance(bob).await.expect("balance query failed");
+            assert!(bob_after > bob_before && bob_after <= bob_before + withdrawal - fee);
+
+            let user = client
+                .call(&ink_e2e::bob(), &call_builder.get_user(bob))
+                .dry_run()
+                .await?
+                .return_value()
+                .expect("Bob should be registered");
+            assert_eq!(user.active_balance, DEPOSIT - withdrawal);
+            assert_eq!(user.pending_withdrawals, 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn batches_are_processed_and_counted_in_the_closed_epoch<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let mut constructor = LsrwaExpressRef::new();
+            let contract = client
+                .instantiate("lsrwa_express_contract", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut call_builder = contract.call_builder::<LsrwaExpress>();
+
+            let mut request_ids = Vec::new();
+            for signer in [ink_e2e::bob(), ink_e2e::charlie(), ink_e2e::dave()] {
+                let request_id = client
+                    .call(&signer, &call_builder.create_deposit_request(DEPOSIT))
+                    .submit()
+                    .await
+                    .expect("create_deposit_request failed")
+                    .return_value()
+                .expect("the call should have succeeded");
+                request_ids.push(request_id);
+            }
+            assert_eq!(
+                client.call(&ink_e2e::alice(), &call_builder.get_total_pending_deposits()).dry_run().await?.return_value(),
+                DEPOSIT * 3
+            );
+
+            // Unknown IDs in a batch are skipped rather than failing it
+            let mut batch = request_ids.clone();
+            batch.push(999);
+            client
+                .call(&ink_e2e::alice(), &call_builder.batch_process_deposit_requests(batch))
+                .submit()
+                .await
+                .expect("batch_process_deposit_requests failed");
+
+            for request_id in &request_ids {
+                let request = client
+                    .call(&ink_e2e::alice(), &call_builder.get_request(*request_id))
+                    .dry_run()
+                    .await?
+                    .return_value()
+                    .expect("request should exist");
+                assert!(request.is_processed);
+            }
+            assert_eq!(
+                client.call(&ink_e2e::alice(), &call_builder.get_total_pending_deposits()).dry_run().await?.return_value(),
+                0
+            );
+
+            // Only the owner closes epochs
+            let refused = client.call(&ink_e2e::bob(), &call_builder.close_current_epoch()).dry_run().await?;
+            assert_eq!(refused.return_value(), Err(Error::NotOwner));
+
+            let next_epoch = client
+                .call(&ink_e2e::alice(), &call_builder.close_current_epoch())
+                .submit()
+                .await
+                .expect("close_current_epoch failed")
+                .return_value()
+                .expect("the call should have succeeded");
+            assert_eq!(next_epoch, 2);
+
+            let closed = client
+                .call(&ink_e2e::alice(), &call_builder.get_epoch(1))
+                .dry_run()
+                .await?
+                .return_value()
+                .expect("epoch 1 should be stored");
+            assert_eq!(closed.status, EpochStatus::Completed);
+            assert_eq!(closed.processed_deposit_count, 3);
+            assert!(closed.end_timestamp.is_some());
+
+            let current = client
+                .call(&ink_e2e::alice(), &call_builder.get_current_epoch())
+                .dry_run()
+                .await?
+                .return_value()
+                .expect("a new epoch should be active");
+            assert_eq!((current.id, current.status, current.processed_deposit_count), (2, EpochStatus::Active, 0));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn failed_calls_change_nothing<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let mut constructor = LsrwaExpressRef::new();
+            let contract = client
+                .instantiate("lsrwa_express_contract", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut call_builder = contract.call_builder::<LsrwaExpress>();
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+
+            let too_low = client.call(&ink_e2e::bob(), &call_builder.create_deposit_request(5)).dry_run().await?;
+            assert_eq!(too_low.return_value(), Err(Error::AmountTooLow));
+            let unregistered = client.call(&ink_e2e::bob(), &call_builder.create_withdrawal_request(DEPOSIT)).dry_run().await?;
+            assert_eq!(unregistered.return_value(), Err(Error::UserNotRegistered));
+
+            let deposit_id = client
+                .call(&ink_e2e::bob(), &call_builder.create_deposit_request(DEPOSIT))
+                .submit()
+                .await
+                .expect("create_deposit_request failed")
+                .return_value()
+                .expect("the call should have succeeded");
+
+            // Reverted calls are rejected by `submit`
+            let not_owner = client.call(&ink_e2e::bob(), &call_builder.process_deposit_request(deposit_id)).submit().await;
+            assert!(not_owner.is_err());
+            let user = client.call(&ink_e2e::bob(), &call_builder.get_user(bob)).dry_run().await?.return_value().expect("Bob should be registered");
+            assert_eq!((user.active_balance, user.pending_deposits), (0, DEPOSIT));
+
+            client
+                .call(&ink_e2e::alice(), &call_builder.process_deposit_request(deposit_id))
+                .submit()
+                .await
+                .expect("process_deposit_request failed");
+            let twice = client.call(&ink_e2e::alice(), &call_builder.process_deposit_request(deposit_id)).dry_run().await?;
+            assert_eq!(twice.return_value(), Err(Error::AlreadyProcessed));
+            let overdrawn = client.call(&ink_e2e::bob(), &call_builder.create_withdrawal_request(DEPOSIT + 1)).dry_run().await?;
+            assert_eq!(overdrawn.return_value(), Err(Error::InsufficientBalance));
+
+            // Deposits don't move funds, so an unfunded contract can't pay a withdrawal out
+            let withdrawal_id = client
+                .call(&ink_e2e::bob(), &call_builder.create_withdrawal_request(DEPOSIT))
+                .submit()
+                .await
+                .expect("create_withdrawal_request failed")
+                .return_value()
+                .expect("the call should have succeeded");
+            client
+                .call(&ink_e2e::alice(), &call_builder.process_withdrawal_request(withdrawal_id))
+                .submit()
+                .await
+                .expect("process_withdrawal_request failed");
+            let unfunded = client.call(&ink_e2e::bob(), &call_builder.execute_withdrawal(withdrawal_id)).dry_run().await?;
+            assert_eq!(unfunded.return_value(), Err(Error::TransferFailed));
+
+            // Nor can the owner take out more than it holds
+            let emergency = client.call(&ink_e2e::alice(), &call_builder.emergency_withdraw(CONTRACT_FUNDS)).dry_run().await?;
+            assert_eq!(emergency.return_value(), Err(Error::InsufficientBalance));
+
+            Ok(())
+        }
     }
-} 
\ No newline at end of file
+
+    /// Entry point of the `dispatch` fuzz target in `fuzz/`: runs call data through the contract
+    /// in the off-chain environment
+    ///
+    /// An input is a sequence of calls, each a caller byte, a length byte and that many bytes of
+    /// call data: a message selector followed by its SCALE-encoded arguments, as a node passes
+    /// them to the contract. Call data is first decoded by the dispatcher ink! generates; what it
+    /// accepts is decoded again against the messages listed here and called on one contract
+    /// instance, so state carries from call to call. Any panic, from decoding or from arithmetic
+    /// that overflows, is a finding.
+    #[cfg(feature = "fuzzing")]
+    pub mod fuzzing {
+        use super::*;
+        use ink::env::hash::{Blake2x256, HashOutput};
+        use ink::env::test::{self, DefaultAccounts};
+        use ink::env::DefaultEnvironment;
+        use ink::reflect::{ContractMessageDecoder, DecodeDispatch};
+        use scale::Decode;
+
+        type Env = DefaultEnvironment;
+
+        /// Runs every call in `data` against a freshly constructed contract
+        pub fn dispatch(data: &[u8]) {
+            test::run_test::<Env, _>(|accounts| {
+                test::set_caller::<Env>(accounts.alice);
+                let mut contract = LsrwaExpress::new();
+
+                let mut input = data;
+                while let [caller, len, rest @ ..] = input {
+                    let (call_data, rest) = rest.split_at((*len as usize).min(rest.len()));
+                    input = rest;
+
+                    if <<LsrwaExpress as ContractMessageDecoder>::Type as DecodeDispatch>::decode_dispatch(&mut &call_data[..]).is_err() {
+                        continue;
+                    }
+                    test::set_caller::<Env>(caller_account(&accounts, *caller));
+                    let (selector, mut args) = call_data.split_at(4);
+                    assert!(
+                        call(&mut contract, selector, &mut args).is_some(),
+                        "ink! accepted call data {:?} that the fuzz dispatcher doesn't, so a message is missing from it",
+                        call_data
+                    );
+                }
+
+                Ok(())
+            })
+            .expect("Off-chain environment should run the calls");
+        }
+
+        /// The owner for 0, then the other default accounts
+        fn caller_account(accounts: &DefaultAccounts<Env>, caller: u8) -> AccountId {
+            [accounts.alice, accounts.bob, accounts.charlie, accounts.django, accounts.eve, accounts.frank][caller as usize % 6]
+        }
+
+        /// First four bytes of the BLAKE2 hash of a message's name, as ink! derives selectors
+        fn selector(name: &str) -> [u8; 4] {
+            let mut hash = <Blake2x256 as HashOutput>::Type::default();
+            ink::env::hash_bytes::<Blake2x256>(name.as_bytes(), &mut hash);
+            [hash[0], hash[1], hash[2], hash[3]]
+        }
+
+        /// Decodes the arguments of the message with `selector` and calls it, or returns `None`
+        /// when no message has the selector or the arguments don't decode
+        fn call(contract: &mut LsrwaExpress, selector_bytes: &[u8], args: &mut &[u8]) -> Option<()> {
+            macro_rules! messages {
+                ($($name:ident($($arg:ident: $ty:ty),*);)*) => {
+                    $(
+                        if selector_bytes == selector(stringify!($name)) {
+                            $(let $arg = <$ty>::decode(args).ok()?;)*
+                            let _ = contract.$name($($arg),*);
+                            return Some(());
+                        }
+                    )*
+                };
+            }
+
+            messages! {
+                get_owner();
+                get_request(request_id: u128);
+                get_user(wallet_address: AccountId);
+                create_deposit_request(amount: Balance);
+                create_withdrawal_request(amount: Balance);
+                process_deposit_request(request_id: u128);
+                process_withdrawal_request(request_id: u128);
+                create_borrow_request(amount: Balance, collateral: Balance);
+                process_borrow_request(request_id: u128);
+                liquidate_borrow(request_id: u128);
+                set_treasury(treasury: AccountId);
+                get_treasury();
+                set_fee_bps(fee_type: FeeType, fee_bps: u32);
+                get_fee_bps(fee_type: FeeType);
+                set_risk_parameters(min_deposit_amount: Balance, min_withdrawal_amount: Balance, min_collateral_ratio: u128);
+                get_risk_parameters();
+                is_borrow_liquidated(request_id: u128);
+                get_user_deposit_requests(wallet_address: AccountId);
+                get_user_withdrawal_requests(wallet_address: AccountId);
+                get_user_borrow_requests(wallet_address: AccountId);
+                batch_process_deposit_requests(request_ids: Vec<u128>);
+                batch_process_withdrawal_requests(request_ids: Vec<u128>);
+                batch_process_borrow_requests(request_ids: Vec<u128>);
+                get_current_epoch();
+                get_epoch(epoch_id: u32);
+                close_current_epoch();
+                execute_withdrawal(request_id: u128);
+                emergency_withdraw(amount: Balance);
+                set_kyc_approvals(wallet_addresses: Vec<AccountId>, approved: bool);
+                is_kyc_approved(wallet_address: AccountId);
+                get_contract_balance();
+                get_total_pending_deposits();
+                get_total_pending_withdrawals();
+            }
+
+            None
+        }
+    }
+}
+
+/// Fuzzing entry point, see `fuzz/`
+#[cfg(feature = "fuzzing")]
+pub use lsrwa_express::fuzzing;
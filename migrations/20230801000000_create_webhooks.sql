@@ -0,0 +1,46 @@
+-- Webhook endpoints registered by admins
+CREATE TABLE IF NOT EXISTS lsrwa_express.webhook_endpoints (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    -- Empty array means the endpoint receives every event type
+    event_types TEXT[] NOT NULL DEFAULT '{}',
+    description TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS webhook_endpoints_active_idx ON lsrwa_express.webhook_endpoints (is_active);
+
+-- Delivery log - one row per (event, endpoint) pair
+CREATE TABLE IF NOT EXISTS lsrwa_express.webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    endpoint_id UUID NOT NULL REFERENCES lsrwa_express.webhook_endpoints(id) ON DELETE CASCADE,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_status_code INTEGER,
+    last_error TEXT,
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_webhook_delivery_status CHECK (status IN ('pending', 'delivered', 'failed'))
+);
+
+-- Index used by the delivery worker to find due deliveries
+CREATE INDEX IF NOT EXISTS webhook_deliveries_due_idx ON lsrwa_express.webhook_deliveries (status, next_attempt_at);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_endpoint_idx ON lsrwa_express.webhook_deliveries (endpoint_id, created_at DESC);
+
+CREATE TRIGGER update_webhook_endpoints_updated_at
+BEFORE UPDATE ON lsrwa_express.webhook_endpoints
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
+
+CREATE TRIGGER update_webhook_deliveries_updated_at
+BEFORE UPDATE ON lsrwa_express.webhook_deliveries
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
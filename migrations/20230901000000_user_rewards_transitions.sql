@@ -0,0 +1,22 @@
+-- One reward row per user and epoch, so epoch reward inserts are idempotent
+CREATE UNIQUE INDEX IF NOT EXISTS user_rewards_user_epoch_idx ON lsrwa_express.user_rewards (user_id, epoch_id);
+
+-- Claimed rewards must record the claim
+ALTER TABLE lsrwa_express.user_rewards
+ADD CONSTRAINT check_reward_claim_recorded
+CHECK (status <> 'claimed' OR (claim_transaction_hash IS NOT NULL AND claim_timestamp IS NOT NULL));
+
+-- Only pending rewards may change status (pending -> claimed | expired)
+CREATE OR REPLACE FUNCTION lsrwa_express.enforce_reward_status_transition()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.status IS DISTINCT FROM OLD.status AND OLD.status <> 'pending' THEN
+        RAISE EXCEPTION 'Invalid reward status transition from % to %', OLD.status, NEW.status;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER enforce_user_rewards_status_transition
+BEFORE UPDATE OF status ON lsrwa_express.user_rewards
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.enforce_reward_status_transition();
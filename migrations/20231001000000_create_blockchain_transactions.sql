@@ -0,0 +1,29 @@
+-- Extrinsics submitted by the backend and their inclusion status
+CREATE TABLE IF NOT EXISTS lsrwa_express.blockchain_transactions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    transaction_hash TEXT NOT NULL UNIQUE,
+    -- Contract message or pallet call, e.g. 'create_deposit_request' or 'utility.batch'
+    call_name TEXT NOT NULL,
+    wallet_address TEXT NOT NULL,
+    user_id UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    blockchain_request_id INTEGER REFERENCES lsrwa_express.blockchain_requests(id) ON DELETE SET NULL,
+    status TEXT NOT NULL DEFAULT 'submitted',
+    block_number BIGINT,
+    nonce BIGINT,
+    error_message TEXT,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finalized_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_blockchain_transaction_status CHECK (status IN ('submitted', 'in_block', 'finalized', 'failed', 'dropped'))
+);
+
+CREATE INDEX IF NOT EXISTS blockchain_transactions_wallet_idx ON lsrwa_express.blockchain_transactions (wallet_address, submitted_at DESC);
+CREATE INDEX IF NOT EXISTS blockchain_transactions_status_idx ON lsrwa_express.blockchain_transactions (status, submitted_at);
+CREATE INDEX IF NOT EXISTS blockchain_transactions_block_number_idx ON lsrwa_express.blockchain_transactions (block_number);
+CREATE INDEX IF NOT EXISTS blockchain_transactions_request_idx ON lsrwa_express.blockchain_transactions (blockchain_request_id);
+
+CREATE TRIGGER update_blockchain_transactions_updated_at
+BEFORE UPDATE ON lsrwa_express.blockchain_transactions
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
@@ -0,0 +1,26 @@
+-- API keys for service-to-service and admin access. Only a hash of the key is stored.
+CREATE TABLE IF NOT EXISTS lsrwa_express.api_keys (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name TEXT NOT NULL,
+    -- First characters of the key, shown to operators to identify it
+    key_prefix TEXT NOT NULL,
+    -- Hex-encoded SHA-256 of the full key
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    created_by UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    last_used_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_prefix_idx ON lsrwa_express.api_keys (key_prefix);
+
+-- Lookups only ever consider keys that haven't been revoked
+CREATE INDEX IF NOT EXISTS api_keys_active_idx ON lsrwa_express.api_keys (key_hash) WHERE revoked_at IS NULL;
+
+CREATE TRIGGER update_api_keys_updated_at
+BEFORE UPDATE ON lsrwa_express.api_keys
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
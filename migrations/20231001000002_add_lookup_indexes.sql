@@ -0,0 +1,27 @@
+-- Lookup indexes for the indexer and read endpoints
+
+CREATE INDEX IF NOT EXISTS blockchain_requests_on_chain_id_idx ON lsrwa_express.blockchain_requests (on_chain_id);
+CREATE INDEX IF NOT EXISTS blockchain_requests_block_number_idx ON lsrwa_express.blockchain_requests (block_number);
+CREATE INDEX IF NOT EXISTS blockchain_requests_user_idx ON lsrwa_express.blockchain_requests (user_id);
+
+CREATE INDEX IF NOT EXISTS request_processing_events_epoch_idx ON lsrwa_express.request_processing_events (epoch_id);
+CREATE INDEX IF NOT EXISTS request_processing_events_block_number_idx ON lsrwa_express.request_processing_events (block_number);
+
+CREATE INDEX IF NOT EXISTS request_execution_events_request_idx ON lsrwa_express.request_execution_events (request_id);
+CREATE INDEX IF NOT EXISTS request_execution_events_wallet_idx ON lsrwa_express.request_execution_events (wallet_address);
+CREATE INDEX IF NOT EXISTS request_execution_events_block_number_idx ON lsrwa_express.request_execution_events (block_number);
+
+CREATE INDEX IF NOT EXISTS batch_processing_items_event_idx ON lsrwa_express.batch_processing_items (processing_event_id);
+CREATE INDEX IF NOT EXISTS batch_processing_items_request_idx ON lsrwa_express.batch_processing_items (request_type, request_id);
+
+CREATE INDEX IF NOT EXISTS user_rewards_epoch_status_idx ON lsrwa_express.user_rewards (epoch_id, status);
+CREATE INDEX IF NOT EXISTS epochs_status_idx ON lsrwa_express.epochs (status);
+CREATE INDEX IF NOT EXISTS activity_logs_user_idx ON lsrwa_express.activity_logs (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS event_queue_status_idx ON lsrwa_express.event_queue (status, created_at);
+CREATE INDEX IF NOT EXISTS event_queue_request_type_idx ON lsrwa_express.event_queue (request_type, request_id);
+
+-- Resume point of the event processor
+INSERT INTO lsrwa_express.system_settings (key, value)
+VALUES ('last_processed_block', '0')
+ON CONFLICT (key) DO NOTHING;
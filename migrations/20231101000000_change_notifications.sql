@@ -0,0 +1,70 @@
+-- Notify API processes of changes so every replica can invalidate caches and push live updates.
+-- Payloads carry only identifying fields to stay well below the 8000 byte NOTIFY limit.
+
+CREATE OR REPLACE FUNCTION lsrwa_express.notify_blockchain_request_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('lsrwa_changes', json_build_object(
+        'entity', 'blockchain_request',
+        'operation', lower(TG_OP),
+        'request_type', NEW.request_type,
+        'on_chain_id', NEW.on_chain_id,
+        'wallet_address', NEW.wallet_address,
+        'user_id', NEW.user_id,
+        'is_processed', NEW.is_processed
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION lsrwa_express.notify_user_balance_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    changed RECORD;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM pg_notify('lsrwa_changes', json_build_object(
+        'entity', 'user_balance',
+        'operation', lower(TG_OP),
+        'user_id', changed.user_id
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION lsrwa_express.notify_system_parameter_change()
+RETURNS TRIGGER AS $$
+DECLARE
+    changed RECORD;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+
+    PERFORM pg_notify('lsrwa_changes', json_build_object(
+        'entity', 'system_parameter',
+        'operation', lower(TG_OP),
+        'parameter_name', changed.parameter_name
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notify_blockchain_requests_change
+AFTER INSERT OR UPDATE ON lsrwa_express.blockchain_requests
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.notify_blockchain_request_change();
+
+CREATE TRIGGER notify_user_balances_change
+AFTER INSERT OR UPDATE OR DELETE ON lsrwa_express.user_balances
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.notify_user_balance_change();
+
+CREATE TRIGGER notify_system_parameters_change
+AFTER INSERT OR UPDATE OR DELETE ON lsrwa_express.system_parameters
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.notify_system_parameter_change();
@@ -0,0 +1,192 @@
+-- Monthly range partitioning for the append-heavy event tables, plus an archive schema that
+-- retired partitions and rows are moved into.
+--
+-- blockchain_requests is not partitioned: its (request_type, on_chain_id) key makes inserts
+-- idempotent and blockchain_transactions references its id, and neither can include a time
+-- column. Processed requests are moved to the archive table in batches instead.
+
+CREATE SCHEMA IF NOT EXISTS lsrwa_express_archive;
+
+-- Creates the monthly partition of `parent` containing `month`, named <parent>_pYYYYMM
+CREATE OR REPLACE FUNCTION lsrwa_express.create_monthly_partition(parent TEXT, month TIMESTAMPTZ)
+RETURNS VOID AS $$
+DECLARE
+    range_start TIMESTAMP := date_trunc('month', month AT TIME ZONE 'UTC');
+    range_end TIMESTAMP := range_start + INTERVAL '1 month';
+    partition_name TEXT := parent || '_p' || to_char(range_start, 'YYYYMM');
+BEGIN
+    -- Bounds carry an explicit UTC offset so they mean the same for TIMESTAMP and TIMESTAMPTZ keys
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS lsrwa_express.%I PARTITION OF lsrwa_express.%I FOR VALUES FROM (%L) TO (%L)',
+        partition_name, parent, range_start::TEXT || '+00', range_end::TEXT || '+00'
+    );
+END;
+$$ LANGUAGE plpgsql;
+
+-- Creates partitions for the current month and the next `months_ahead` months
+CREATE OR REPLACE FUNCTION lsrwa_express.ensure_monthly_partitions(parent TEXT, months_ahead INTEGER)
+RETURNS VOID AS $$
+BEGIN
+    FOR i IN 0..months_ahead LOOP
+        PERFORM lsrwa_express.create_monthly_partition(parent, NOW() + make_interval(months => i));
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Detaches monthly partitions of `parent` that end before `older_than` and moves them to the
+-- archive schema. Returns the number of partitions archived.
+CREATE OR REPLACE FUNCTION lsrwa_express.archive_monthly_partitions(parent TEXT, older_than TIMESTAMPTZ)
+RETURNS INTEGER AS $$
+DECLARE
+    retired RECORD;
+    archived INTEGER := 0;
+BEGIN
+    FOR retired IN
+        SELECT child.relname AS name
+        FROM pg_inherits
+        JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+        JOIN pg_class parent_table ON parent_table.oid = pg_inherits.inhparent
+        JOIN pg_namespace ns ON ns.oid = parent_table.relnamespace
+        WHERE ns.nspname = 'lsrwa_express'
+          AND parent_table.relname = parent
+          AND child.relname ~ ('^' || parent || '_p[0-9]{6}$')
+        ORDER BY child.relname
+    LOOP
+        -- The partition covers its month, so it is retired once the following month has passed
+        IF to_date(right(retired.name, 6), 'YYYYMM') + INTERVAL '1 month' <= older_than AT TIME ZONE 'UTC' THEN
+            EXECUTE format('ALTER TABLE lsrwa_express.%I DETACH PARTITION lsrwa_express.%I', parent, retired.name);
+            EXECUTE format('ALTER TABLE lsrwa_express.%I SET SCHEMA lsrwa_express_archive', retired.name);
+            archived := archived + 1;
+        END IF;
+    END LOOP;
+
+    RETURN archived;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Creates monthly partitions from the oldest row in `source` up to `months_ahead` months from now
+CREATE OR REPLACE FUNCTION lsrwa_express.backfill_monthly_partitions(parent TEXT, source TEXT, months_ahead INTEGER)
+RETURNS VOID AS $$
+DECLARE
+    oldest TIMESTAMP;
+    month TIMESTAMPTZ;
+BEGIN
+    EXECUTE format('SELECT MIN(created_at) AT TIME ZONE ''UTC'' FROM lsrwa_express.%I', source) INTO oldest;
+    month := COALESCE(date_trunc('month', oldest) AT TIME ZONE 'UTC', NOW());
+
+    WHILE month < NOW() LOOP
+        PERFORM lsrwa_express.create_monthly_partition(parent, month);
+        month := month + INTERVAL '1 month';
+    END LOOP;
+
+    PERFORM lsrwa_express.ensure_monthly_partitions(parent, months_ahead);
+END;
+$$ LANGUAGE plpgsql;
+
+-- event_queue, partitioned by created_at. The key must include created_at; ids are UUIDs, so
+-- they stay unique across partitions.
+ALTER TABLE lsrwa_express.event_queue RENAME TO event_queue_unpartitioned;
+ALTER TABLE lsrwa_express.event_queue_unpartitioned RENAME CONSTRAINT event_queue_pkey TO event_queue_unpartitioned_pkey;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_status_attempts_idx;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_block_number_idx;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_transaction_hash_idx;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_request_id_idx;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_wallet_address_idx;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_status_idx;
+DROP INDEX IF EXISTS lsrwa_express.event_queue_request_type_idx;
+
+CREATE TABLE lsrwa_express.event_queue (
+    id VARCHAR(50) NOT NULL,
+    event_type INTEGER NOT NULL,
+    block_number BIGINT NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    request_id BIGINT,
+    wallet_address VARCHAR(100),
+    amount VARCHAR(100),
+    request_type VARCHAR(20),
+    timestamp TIMESTAMPTZ NOT NULL,
+    raw_data TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_attempt TIMESTAMPTZ,
+    error_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+CREATE TABLE lsrwa_express.event_queue_default PARTITION OF lsrwa_express.event_queue DEFAULT;
+SELECT lsrwa_express.backfill_monthly_partitions('event_queue', 'event_queue_unpartitioned', 3);
+
+INSERT INTO lsrwa_express.event_queue SELECT * FROM lsrwa_express.event_queue_unpartitioned;
+DROP TABLE lsrwa_express.event_queue_unpartitioned;
+
+CREATE INDEX event_queue_status_attempts_idx ON lsrwa_express.event_queue (status, attempts);
+CREATE INDEX event_queue_block_number_idx ON lsrwa_express.event_queue (block_number);
+CREATE INDEX event_queue_transaction_hash_idx ON lsrwa_express.event_queue (transaction_hash);
+CREATE INDEX event_queue_request_id_idx ON lsrwa_express.event_queue (request_id);
+CREATE INDEX event_queue_wallet_address_idx ON lsrwa_express.event_queue (wallet_address);
+CREATE INDEX event_queue_status_idx ON lsrwa_express.event_queue (status, created_at);
+CREATE INDEX event_queue_request_type_idx ON lsrwa_express.event_queue (request_type, request_id);
+
+CREATE TRIGGER update_event_queue_updated_at
+BEFORE UPDATE ON lsrwa_express.event_queue
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
+
+-- activity_logs, partitioned by created_at
+ALTER TABLE lsrwa_express.activity_logs RENAME TO activity_logs_unpartitioned;
+ALTER TABLE lsrwa_express.activity_logs_unpartitioned RENAME CONSTRAINT activity_logs_pkey TO activity_logs_unpartitioned_pkey;
+DROP INDEX IF EXISTS lsrwa_express.activity_logs_user_idx;
+
+CREATE TABLE lsrwa_express.activity_logs (
+    id UUID NOT NULL DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES lsrwa_express.users(id),
+    activity_type VARCHAR(50) NOT NULL,
+    description TEXT,
+    data JSONB,
+    ip_address VARCHAR(45),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+CREATE TABLE lsrwa_express.activity_logs_default PARTITION OF lsrwa_express.activity_logs DEFAULT;
+SELECT lsrwa_express.backfill_monthly_partitions('activity_logs', 'activity_logs_unpartitioned', 3);
+
+INSERT INTO lsrwa_express.activity_logs SELECT * FROM lsrwa_express.activity_logs_unpartitioned;
+DROP TABLE lsrwa_express.activity_logs_unpartitioned;
+
+CREATE INDEX activity_logs_user_idx ON lsrwa_express.activity_logs (user_id, created_at DESC);
+CREATE INDEX activity_logs_type_idx ON lsrwa_express.activity_logs (activity_type, created_at DESC);
+
+-- Archived blockchain requests keep their original ids so old transactions can still be joined
+CREATE TABLE IF NOT EXISTS lsrwa_express_archive.blockchain_requests (
+    LIKE lsrwa_express.blockchain_requests INCLUDING DEFAULTS INCLUDING CONSTRAINTS,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (id)
+);
+
+-- Moves up to `batch_size` processed requests last updated before `older_than` to the archive.
+-- Returns the number of requests moved.
+CREATE OR REPLACE FUNCTION lsrwa_express.archive_blockchain_requests(older_than TIMESTAMPTZ, batch_size INTEGER)
+RETURNS INTEGER AS $$
+DECLARE
+    moved INTEGER;
+BEGIN
+    WITH archived AS (
+        DELETE FROM lsrwa_express.blockchain_requests
+        WHERE id IN (
+            SELECT id FROM lsrwa_express.blockchain_requests
+            WHERE is_processed = TRUE AND updated_at < older_than AT TIME ZONE 'UTC'
+            ORDER BY id
+            LIMIT batch_size
+        )
+        RETURNING *
+    )
+    INSERT INTO lsrwa_express_archive.blockchain_requests
+    SELECT archived.* FROM archived;
+
+    GET DIAGNOSTICS moved = ROW_COUNT;
+    RETURN moved;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,47 @@
+-- Substrate (ss58) wallets authenticate by signature and are up to 48 characters long
+ALTER TABLE lsrwa_express.users ALTER COLUMN wallet_address TYPE VARCHAR(64);
+
+-- One row per verification attempt with a KYC provider
+CREATE TABLE IF NOT EXISTS lsrwa_express.kyc_verifications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    level TEXT NOT NULL,
+    -- Provider's applicant identifier, set once the applicant has been created
+    applicant_id TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    review_answer TEXT,
+    rejection_reasons TEXT[] NOT NULL DEFAULT '{}',
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_kyc_verification_status CHECK (status IN ('pending', 'approved', 'rejected')),
+    CONSTRAINT check_kyc_verification_level CHECK (level IN ('basic', 'advanced', 'full'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS kyc_verifications_applicant_idx
+ON lsrwa_express.kyc_verifications (provider, applicant_id)
+WHERE applicant_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS kyc_verifications_user_idx ON lsrwa_express.kyc_verifications (user_id, created_at DESC);
+
+-- Every webhook received from a provider, kept for audit and replay
+CREATE TABLE IF NOT EXISTS lsrwa_express.kyc_webhook_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    provider TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    applicant_id TEXT NOT NULL,
+    verification_id UUID REFERENCES lsrwa_express.kyc_verifications(id) ON DELETE SET NULL,
+    payload JSONB NOT NULL,
+    processed_at TIMESTAMPTZ,
+    error_message TEXT,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS kyc_webhook_events_applicant_idx
+ON lsrwa_express.kyc_webhook_events (provider, applicant_id, received_at DESC);
+
+CREATE TRIGGER update_kyc_verifications_updated_at
+BEFORE UPDATE ON lsrwa_express.kyc_verifications
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
@@ -0,0 +1,27 @@
+-- Sanctions/AML screening results, one row per check of a wallet
+CREATE TABLE IF NOT EXISTS lsrwa_express.screenings (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    wallet_address TEXT NOT NULL,
+    user_id UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    trigger TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    risk_level TEXT NOT NULL,
+    flagged BOOLEAN NOT NULL,
+    matches JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_screening_trigger CHECK (trigger IN ('registration', 'withdrawal', 'rescreen', 'manual')),
+    CONSTRAINT check_screening_risk_level CHECK (risk_level IN ('none', 'low', 'medium', 'high', 'severe'))
+);
+
+CREATE INDEX IF NOT EXISTS screenings_wallet_idx ON lsrwa_express.screenings (wallet_address, created_at DESC);
+CREATE INDEX IF NOT EXISTS screenings_flagged_idx ON lsrwa_express.screenings (created_at DESC) WHERE flagged;
+
+-- Wallets barred from the protocol after a screening hit, until an admin clears them
+CREATE TABLE IF NOT EXISTS lsrwa_express.blocked_wallets (
+    wallet_address TEXT PRIMARY KEY,
+    reason TEXT NOT NULL,
+    screening_id UUID REFERENCES lsrwa_express.screenings(id) ON DELETE SET NULL,
+    blocked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    cleared_at TIMESTAMPTZ,
+    cleared_reason TEXT
+);
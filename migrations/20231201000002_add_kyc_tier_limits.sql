@@ -0,0 +1,7 @@
+-- Per-epoch submission caps by KYC level, in whole USDC per request type ('unlimited' for no cap)
+INSERT INTO lsrwa_express.system_parameters (parameter_name, parameter_value, description)
+VALUES
+('kyc_basic_epoch_limit', '10000', 'Per-epoch deposit/withdrawal/borrow cap for Basic KYC users in USDC'),
+('kyc_advanced_epoch_limit', '100000', 'Per-epoch deposit/withdrawal/borrow cap for Advanced KYC users in USDC'),
+('kyc_full_epoch_limit', 'unlimited', 'Per-epoch deposit/withdrawal/borrow cap for Full KYC users in USDC')
+ON CONFLICT (parameter_name) DO NOTHING;
@@ -0,0 +1,19 @@
+-- Identity documents uploaded for manual review, stored encrypted in object storage
+CREATE TABLE IF NOT EXISTS lsrwa_express.kyc_documents (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    verification_id UUID NOT NULL REFERENCES lsrwa_express.kyc_verifications(id) ON DELETE CASCADE,
+    document_type TEXT NOT NULL,
+    side TEXT,
+    file_name TEXT,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    sha256 TEXT NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_kyc_document_type CHECK (
+        document_type IN ('passport', 'id_card', 'driving_license', 'residence_permit', 'proof_of_address', 'selfie')
+    ),
+    CONSTRAINT check_kyc_document_side CHECK (side IS NULL OR side IN ('front', 'back'))
+);
+
+CREATE INDEX IF NOT EXISTS kyc_documents_verification_idx ON lsrwa_express.kyc_documents (verification_id, created_at);
@@ -0,0 +1,21 @@
+-- Tracks submission of approved wallets to the contract's KYC allowlist
+ALTER TABLE lsrwa_express.kyc_verifications
+    ADD COLUMN IF NOT EXISTS onchain_sync_status TEXT,
+    ADD COLUMN IF NOT EXISTS onchain_tx_hash TEXT,
+    ADD COLUMN IF NOT EXISTS onchain_synced_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS onchain_sync_attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS onchain_sync_error TEXT,
+    ADD COLUMN IF NOT EXISTS onchain_next_attempt_at TIMESTAMPTZ;
+
+ALTER TABLE lsrwa_express.kyc_verifications
+    ADD CONSTRAINT check_kyc_onchain_sync_status
+    CHECK (onchain_sync_status IS NULL OR onchain_sync_status IN ('pending', 'synced', 'failed'));
+
+-- Verifications approved before the sync existed still need to reach the chain
+UPDATE lsrwa_express.kyc_verifications
+SET onchain_sync_status = 'pending', onchain_next_attempt_at = NOW()
+WHERE status = 'approved';
+
+CREATE INDEX IF NOT EXISTS kyc_verifications_onchain_sync_idx
+ON lsrwa_express.kyc_verifications (onchain_next_attempt_at)
+WHERE onchain_sync_status = 'pending';
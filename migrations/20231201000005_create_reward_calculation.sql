@@ -0,0 +1,43 @@
+-- Every change to a user's active balance, so rewards can weight balances by how long they were held
+CREATE TABLE IF NOT EXISTS lsrwa_express.active_balance_history (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    active_balance NUMERIC(36, 18) NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS active_balance_history_user_idx
+ON lsrwa_express.active_balance_history (user_id, changed_at);
+
+CREATE OR REPLACE FUNCTION lsrwa_express.record_active_balance_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.active_balance IS DISTINCT FROM OLD.active_balance THEN
+        INSERT INTO lsrwa_express.active_balance_history (user_id, active_balance)
+        VALUES (NEW.user_id, NEW.active_balance);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_user_balances_active_balance
+AFTER INSERT OR UPDATE OF active_balance ON lsrwa_express.user_balances
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.record_active_balance_change();
+
+-- Balances from before the history existed count from their last change
+INSERT INTO lsrwa_express.active_balance_history (user_id, active_balance, changed_at)
+SELECT user_id, active_balance, updated_at AT TIME ZONE 'UTC'
+FROM lsrwa_express.user_balances;
+
+-- Distribution report written alongside each epoch's rewards
+CREATE TABLE IF NOT EXISTS lsrwa_express.epoch_reward_reports (
+    epoch_id INTEGER PRIMARY KEY REFERENCES lsrwa_express.epochs(id),
+    apr_bps INTEGER NOT NULL,
+    epoch_start TIMESTAMPTZ NOT NULL,
+    epoch_end TIMESTAMPTZ NOT NULL,
+    recipient_count INTEGER NOT NULL,
+    total_time_weighted_balance NUMERIC(36, 18) NOT NULL,
+    total_rewards NUMERIC(36, 18) NOT NULL,
+    distribution JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,31 @@
+-- Progress of each epoch's close sequence, so an interrupted run can resume where it stopped
+CREATE TABLE IF NOT EXISTS lsrwa_express.epoch_processing_runs (
+    epoch_id INTEGER PRIMARY KEY REFERENCES lsrwa_express.epochs(id),
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    step VARCHAR(30) NOT NULL DEFAULT 'closing_submissions',
+    deposits_processed INTEGER NOT NULL DEFAULT 0,
+    withdrawals_processed INTEGER NOT NULL DEFAULT 0,
+    borrows_processed INTEGER NOT NULL DEFAULT 0,
+    close_tx_hash VARCHAR(66),
+    next_epoch_id INTEGER REFERENCES lsrwa_express.epochs(id),
+    stats JSONB,
+    result JSONB,
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    CONSTRAINT check_epoch_processing_status CHECK (status IN ('running', 'completed', 'failed')),
+    CONSTRAINT check_epoch_processing_step CHECK (step IN (
+        'closing_submissions', 'processing_deposits', 'processing_withdrawals',
+        'closing_epoch', 'calculating_rewards', 'snapshotting_stats', 'completed'
+    ))
+);
+
+CREATE TRIGGER update_epoch_processing_runs_updated_at
+BEFORE UPDATE ON lsrwa_express.epoch_processing_runs
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
+
+-- Requests already included in a batch are skipped when a run resumes
+CREATE INDEX IF NOT EXISTS idx_batch_processing_items_request
+    ON lsrwa_express.batch_processing_items(request_type, request_id);
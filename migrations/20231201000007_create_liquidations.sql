@@ -0,0 +1,53 @@
+-- Borrow positions that fell under the liquidation threshold, one row per borrow request
+CREATE TABLE IF NOT EXISTS lsrwa_express.borrow_liquidations (
+    id BIGSERIAL PRIMARY KEY,
+    request_id BIGINT NOT NULL UNIQUE,
+    wallet_address TEXT NOT NULL,
+    user_id UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'at_risk',
+    borrow_amount NUMERIC(36, 18) NOT NULL,
+    collateral_amount NUMERIC(36, 18) NOT NULL,
+    collateral_price NUMERIC(36, 18) NOT NULL,
+    collateral_ratio_bps INTEGER NOT NULL,
+    flagged_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    grace_ends_at TIMESTAMPTZ NOT NULL,
+    notified_at TIMESTAMPTZ,
+    liquidated_at TIMESTAMPTZ,
+    liquidation_tx_hash VARCHAR(66),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_borrow_liquidation_status CHECK (status IN ('at_risk', 'recovered', 'liquidated'))
+);
+
+CREATE INDEX IF NOT EXISTS borrow_liquidations_status_idx
+ON lsrwa_express.borrow_liquidations (status, flagged_at DESC);
+
+CREATE TRIGGER update_borrow_liquidations_updated_at
+BEFORE UPDATE ON lsrwa_express.borrow_liquidations
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
+
+-- Everything the liquidation monitor did to a position
+CREATE TABLE IF NOT EXISTS lsrwa_express.liquidation_actions (
+    id BIGSERIAL PRIMARY KEY,
+    liquidation_id BIGINT NOT NULL REFERENCES lsrwa_express.borrow_liquidations(id) ON DELETE CASCADE,
+    action VARCHAR(30) NOT NULL,
+    collateral_ratio_bps INTEGER,
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_liquidation_action CHECK (action IN (
+        'flagged', 'notified', 'recovered', 'liquidated', 'liquidation_failed'
+    ))
+);
+
+CREATE INDEX IF NOT EXISTS liquidation_actions_liquidation_idx
+ON lsrwa_express.liquidation_actions (liquidation_id, created_at);
+
+INSERT INTO lsrwa_express.system_parameters (parameter_name, parameter_value, description)
+VALUES
+('liquidation_threshold_bps', '12000', 'Collateral ratio in basis points under which a borrow is flagged for liquidation (120%)'),
+('liquidation_grace_period_seconds', '86400', 'Time a flagged borrower has to restore collateral before liquidation (1 day)'),
+('collateral_price', '1', 'Price of one unit of collateral in the borrowed asset')
+ON CONFLICT (parameter_name) DO NOTHING;
@@ -0,0 +1,2 @@
+-- The collateral price now comes from the price oracle
+DELETE FROM lsrwa_express.system_parameters WHERE parameter_name = 'collateral_price';
@@ -0,0 +1,42 @@
+-- Interest accrued by each borrow position over each epoch
+CREATE TABLE IF NOT EXISTS lsrwa_express.borrow_interest_accruals (
+    id BIGSERIAL PRIMARY KEY,
+    -- On-chain borrow request ID
+    request_id BIGINT NOT NULL,
+    epoch_id INTEGER NOT NULL REFERENCES lsrwa_express.epochs(id),
+    wallet_address VARCHAR(42) NOT NULL,
+    user_id UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    principal NUMERIC(36, 18) NOT NULL,
+    rate_bps INTEGER NOT NULL,
+    period_start TIMESTAMPTZ NOT NULL,
+    period_end TIMESTAMPTZ NOT NULL,
+    interest NUMERIC(36, 18) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_borrow_interest_accrual UNIQUE (request_id, epoch_id),
+    CONSTRAINT check_accrual_period CHECK (period_end > period_start)
+);
+
+CREATE INDEX IF NOT EXISTS borrow_interest_accruals_wallet_idx
+ON lsrwa_express.borrow_interest_accruals (wallet_address, period_end);
+
+-- Monthly debt statement of a borrower
+CREATE TABLE IF NOT EXISTS lsrwa_express.debt_statements (
+    id BIGSERIAL PRIMARY KEY,
+    wallet_address VARCHAR(42) NOT NULL,
+    user_id UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    opening_debt NUMERIC(36, 18) NOT NULL,
+    new_borrows NUMERIC(36, 18) NOT NULL,
+    interest_accrued NUMERIC(36, 18) NOT NULL,
+    liquidated_debt NUMERIC(36, 18) NOT NULL,
+    closing_debt NUMERIC(36, 18) NOT NULL,
+    positions JSONB NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_debt_statement UNIQUE (wallet_address, period_start)
+);
+
+INSERT INTO lsrwa_express.system_parameters (parameter_name, parameter_value, description)
+VALUES
+('borrow_interest_rate_bps', '800', 'Annual interest rate charged on borrows in basis points (8%)')
+ON CONFLICT (parameter_name) DO NOTHING;
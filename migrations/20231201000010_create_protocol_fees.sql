@@ -0,0 +1,25 @@
+-- Protocol fees paid to the treasury, recorded from FeeCollected events
+CREATE TABLE IF NOT EXISTS lsrwa_express.protocol_fees (
+    id BIGSERIAL PRIMARY KEY,
+    fee_type VARCHAR(20) NOT NULL,
+    -- On-chain request the fee was charged on
+    request_id BIGINT NOT NULL,
+    wallet_address VARCHAR(42) NOT NULL,
+    user_id UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    -- Epoch that was active when the fee was collected
+    epoch_id INTEGER REFERENCES lsrwa_express.epochs(id),
+    amount NUMERIC(36, 18) NOT NULL,
+    block_number BIGINT NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    collected_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_protocol_fee UNIQUE (fee_type, request_id),
+    CONSTRAINT check_protocol_fee_type CHECK (fee_type IN ('withdrawal', 'liquidation')),
+    CONSTRAINT check_protocol_fee_amount CHECK (amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS protocol_fees_collected_idx
+ON lsrwa_express.protocol_fees (collected_at);
+
+CREATE INDEX IF NOT EXISTS protocol_fees_epoch_idx
+ON lsrwa_express.protocol_fees (epoch_id);
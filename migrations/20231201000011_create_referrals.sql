@@ -0,0 +1,44 @@
+-- Who referred whom; a user can be referred at most once
+CREATE TABLE IF NOT EXISTS lsrwa_express.referrals (
+    referee_user_id UUID PRIMARY KEY REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    referrer_user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_referral_not_self CHECK (referee_user_id <> referrer_user_id)
+);
+
+CREATE INDEX IF NOT EXISTS referrals_referrer_idx
+ON lsrwa_express.referrals (referrer_user_id);
+
+-- Referral bonuses are separate reward rows, so a user can hold both kinds in one epoch
+ALTER TABLE lsrwa_express.user_rewards
+ADD COLUMN IF NOT EXISTS reward_type VARCHAR(20) NOT NULL DEFAULT 'epoch';
+
+ALTER TABLE lsrwa_express.user_rewards
+ADD CONSTRAINT check_reward_type CHECK (reward_type IN ('epoch', 'referral'));
+
+DROP INDEX IF EXISTS lsrwa_express.user_rewards_user_epoch_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS user_rewards_user_epoch_type_idx
+ON lsrwa_express.user_rewards (user_id, epoch_id, reward_type);
+
+-- Per-referee breakdown of each referral reward row
+CREATE TABLE IF NOT EXISTS lsrwa_express.referral_bonuses (
+    id BIGSERIAL PRIMARY KEY,
+    epoch_id INTEGER NOT NULL REFERENCES lsrwa_express.epochs(id),
+    referrer_user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    referee_user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    -- Referee's epoch reward the bonus was computed from
+    referee_reward NUMERIC(36, 18) NOT NULL,
+    bonus_bps INTEGER NOT NULL,
+    amount NUMERIC(36, 18) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_referral_bonus UNIQUE (epoch_id, referee_user_id),
+    CONSTRAINT check_referral_bonus_amount CHECK (amount > 0)
+);
+
+CREATE INDEX IF NOT EXISTS referral_bonuses_referrer_idx
+ON lsrwa_express.referral_bonuses (referrer_user_id, epoch_id);
+
+INSERT INTO lsrwa_express.system_parameters (parameter_name, parameter_value, description)
+VALUES
+('referral_bonus_bps', '1000', 'Share of a referee''s epoch rewards paid to their referrer in basis points (10%)')
+ON CONFLICT (parameter_name) DO NOTHING;
@@ -0,0 +1,14 @@
+-- Every change of the risk parameters, with the full set as of the change
+CREATE TABLE IF NOT EXISTS lsrwa_express.risk_parameter_versions (
+    version BIGSERIAL PRIMARY KEY,
+    parameters JSONB NOT NULL,
+    changed TEXT[] NOT NULL,
+    reason TEXT,
+    updated_by UUID REFERENCES lsrwa_express.users(id) ON DELETE SET NULL,
+    onchain_status VARCHAR(20) NOT NULL,
+    onchain_transaction_hash VARCHAR(66),
+    onchain_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    synced_at TIMESTAMPTZ,
+    CONSTRAINT check_risk_onchain_status CHECK (onchain_status IN ('not_required', 'pending', 'synced', 'failed'))
+);
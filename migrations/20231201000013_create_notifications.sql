@@ -0,0 +1,40 @@
+-- Per-user opt-outs; a missing row means the notification is enabled
+CREATE TABLE IF NOT EXISTS lsrwa_express.notification_preferences (
+    user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    notification_type VARCHAR(40) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, notification_type)
+);
+
+-- Delivery log - one row per email, rendered when queued
+CREATE TABLE IF NOT EXISTS lsrwa_express.email_notifications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    notification_type VARCHAR(40) NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    -- Identifies the event the email is about, so replayed events don't send it twice
+    dedupe_key TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_error TEXT,
+    provider_message_id TEXT,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_email_notification UNIQUE (notification_type, dedupe_key),
+    CONSTRAINT check_email_notification_status CHECK (status IN ('pending', 'sent', 'failed'))
+);
+
+-- Index used by the email worker to find due emails
+CREATE INDEX IF NOT EXISTS email_notifications_due_idx ON lsrwa_express.email_notifications (status, next_attempt_at);
+
+CREATE INDEX IF NOT EXISTS email_notifications_user_idx ON lsrwa_express.email_notifications (user_id, created_at DESC);
+
+CREATE TRIGGER update_email_notifications_updated_at
+BEFORE UPDATE ON lsrwa_express.email_notifications
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.update_updated_at_column();
@@ -0,0 +1,19 @@
+-- Manifest of indexed events exported to object storage, one file per UTC day of indexing.
+-- Days are exported in order, so every day before the latest one here is either listed or had
+-- no events.
+CREATE TABLE IF NOT EXISTS lsrwa_express.event_archives (
+    day DATE PRIMARY KEY,
+    bucket TEXT NOT NULL,
+    object_key TEXT NOT NULL,
+    format VARCHAR(20) NOT NULL,
+    event_count BIGINT NOT NULL,
+    first_block BIGINT NOT NULL,
+    last_block BIGINT NOT NULL,
+    byte_size BIGINT NOT NULL,
+    -- SHA-256 of the stored object, hex encoded
+    checksum VARCHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Exports read event_queue a day at a time in indexing order
+CREATE INDEX IF NOT EXISTS event_queue_created_at_idx ON lsrwa_express.event_queue (created_at, id);
@@ -0,0 +1,13 @@
+-- Secret replaced by the last rotation, still signed with until it expires so consumers can
+-- switch over without rejecting deliveries
+ALTER TABLE lsrwa_express.webhook_endpoints
+    ADD COLUMN IF NOT EXISTS previous_secret TEXT,
+    ADD COLUMN IF NOT EXISTS previous_secret_expires_at TIMESTAMPTZ;
+
+-- Monotonically increasing delivery number, used by consumers to order deliveries, drop
+-- duplicates and fetch the ones they missed
+ALTER TABLE lsrwa_express.webhook_deliveries
+    ADD COLUMN IF NOT EXISTS sequence BIGINT GENERATED ALWAYS AS IDENTITY;
+
+CREATE UNIQUE INDEX IF NOT EXISTS webhook_deliveries_endpoint_sequence_idx
+    ON lsrwa_express.webhook_deliveries (endpoint_id, sequence);
@@ -0,0 +1,14 @@
+-- Feature flags for gradual rollouts, per deployment environment. Flags without a row here
+-- keep the default built into the service.
+CREATE TABLE IF NOT EXISTS lsrwa_express.feature_flags (
+    name VARCHAR(100) NOT NULL,
+    environment VARCHAR(20) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    -- Share of wallets the flag is on for, for checks about a wallet
+    rollout_percentage SMALLINT NOT NULL DEFAULT 100 CHECK (rollout_percentage BETWEEN 0 AND 100),
+    description TEXT,
+    updated_by UUID REFERENCES lsrwa_express.users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (name, environment)
+);
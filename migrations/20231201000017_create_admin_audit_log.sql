@@ -0,0 +1,44 @@
+-- Append-only record of privileged operations: admin API calls, parameter changes, manual
+-- reconciliations and admin extrinsics
+CREATE TABLE IF NOT EXISTS lsrwa_express.admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    -- Operator named by X-Admin-Actor, or `system` for background jobs
+    actor VARCHAR(255) NOT NULL,
+    action VARCHAR(30) NOT NULL,
+    -- What was acted on, e.g. `PUT /api/v1/admin/parameters/:name` or `system_parameter:epoch_duration`
+    target VARCHAR(255) NOT NULL,
+    before_state JSONB,
+    after_state JSONB,
+    ip_address VARCHAR(45),
+    request_id VARCHAR(64),
+    status_code SMALLINT,
+    transaction_hash VARCHAR(66),
+    details JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_admin_audit_action
+        CHECK (action IN ('admin_request', 'parameter_change', 'reconciliation', 'extrinsic'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_created_at ON lsrwa_express.admin_audit_log (created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_actor ON lsrwa_express.admin_audit_log (actor, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_action ON lsrwa_express.admin_audit_log (action, created_at DESC);
+
+-- Entries can be added but never changed or removed, not even by the service's own role
+CREATE OR REPLACE FUNCTION lsrwa_express.reject_admin_audit_log_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'admin_audit_log is append-only; % is not allowed', TG_OP;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER admin_audit_log_immutable_rows
+BEFORE UPDATE OR DELETE ON lsrwa_express.admin_audit_log
+FOR EACH ROW
+EXECUTE FUNCTION lsrwa_express.reject_admin_audit_log_change();
+
+CREATE TRIGGER admin_audit_log_no_truncate
+BEFORE TRUNCATE ON lsrwa_express.admin_audit_log
+FOR EACH STATEMENT
+EXECUTE FUNCTION lsrwa_express.reject_admin_audit_log_change();
+
+REVOKE UPDATE, DELETE, TRUNCATE ON lsrwa_express.admin_audit_log FROM PUBLIC;
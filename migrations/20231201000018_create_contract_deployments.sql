@@ -0,0 +1,24 @@
+-- Contract deployments per network, recorded by the deploy tool. The latest verified row of a
+-- network is the contract the services there should be configured with.
+CREATE TABLE IF NOT EXISTS lsrwa_express.contract_deployments (
+    id BIGSERIAL PRIMARY KEY,
+    network VARCHAR(20) NOT NULL,
+    chain_genesis_hash VARCHAR(66) NOT NULL,
+    contract_address VARCHAR(64) NOT NULL,
+    code_hash VARCHAR(66) NOT NULL,
+    -- Salt the address was derived from, so the same build redeploys to the same address
+    salt VARCHAR(66) NOT NULL,
+    deployer VARCHAR(64) NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    block_number BIGINT NOT NULL,
+    -- Estimates from the dry run the deployment was submitted with
+    gas_ref_time BIGINT NOT NULL,
+    gas_proof_size BIGINT NOT NULL,
+    storage_deposit NUMERIC(78, 0) NOT NULL,
+    -- Whether the code hash stored on-chain matched the local build after instantiation
+    verified BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (network, contract_address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_contract_deployments_network ON lsrwa_express.contract_deployments(network, created_at DESC);
@@ -0,0 +1,24 @@
+-- Code upgrades of deployed contracts, recorded by the upgrade tool. The latest row of a
+-- contract is the build it runs; before its first upgrade, that's the deployed build.
+CREATE TABLE IF NOT EXISTS lsrwa_express.contract_versions (
+    id BIGSERIAL PRIMARY KEY,
+    network VARCHAR(20) NOT NULL,
+    contract_address VARCHAR(64) NOT NULL,
+    previous_code_hash VARCHAR(66) NOT NULL,
+    code_hash VARCHAR(66) NOT NULL,
+    -- NULL when the code had already been uploaded
+    upload_transaction_hash VARCHAR(66),
+    schedule_transaction_hash VARCHAR(66) NOT NULL,
+    -- When the timelock allowed the upgrade, in chain time, as estimated when it was scheduled
+    executable_at TIMESTAMPTZ NOT NULL,
+    upgrade_transaction_hash VARCHAR(66) NOT NULL,
+    block_number BIGINT NOT NULL,
+    upgraded_by VARCHAR(64) NOT NULL,
+    -- What changed and any data migration the new version needs
+    migration_notes TEXT NOT NULL,
+    -- Whether the code hash stored on-chain matched the new build after the upgrade
+    verified BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_contract_versions_contract ON lsrwa_express.contract_versions(network, contract_address, created_at DESC);
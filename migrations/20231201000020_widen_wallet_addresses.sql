@@ -0,0 +1,8 @@
+-- Substrate (ss58) wallets are up to 48 characters long, so every table keyed by wallet needs
+-- the width users already has. Requests from devnet and testnet accounts didn't fit before.
+ALTER TABLE lsrwa_express.blockchain_requests ALTER COLUMN wallet_address TYPE VARCHAR(64);
+ALTER TABLE lsrwa_express_archive.blockchain_requests ALTER COLUMN wallet_address TYPE VARCHAR(64);
+ALTER TABLE lsrwa_express.request_execution_events ALTER COLUMN wallet_address TYPE VARCHAR(64);
+ALTER TABLE lsrwa_express.borrow_interest_accruals ALTER COLUMN wallet_address TYPE VARCHAR(64);
+ALTER TABLE lsrwa_express.debt_statements ALTER COLUMN wallet_address TYPE VARCHAR(64);
+ALTER TABLE lsrwa_express.protocol_fees ALTER COLUMN wallet_address TYPE VARCHAR(64);
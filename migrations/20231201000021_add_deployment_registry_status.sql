@@ -0,0 +1,23 @@
+-- Deployment registry: which contract is live on each network. A deployment is recorded as
+-- pending until its on-chain code is verified, then goes live and supersedes the network's
+-- previous live deployment. Upgrades keep the address and update the code hash in place.
+ALTER TABLE lsrwa_express.contract_deployments
+    ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'live', 'superseded')),
+    ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+-- The newest verified deployment of each network is the one services were configured with
+UPDATE lsrwa_express.contract_deployments deployment
+SET status = CASE
+    WHEN deployment.id = (
+        SELECT latest.id FROM lsrwa_express.contract_deployments latest
+        WHERE latest.network = deployment.network AND latest.verified
+        ORDER BY latest.created_at DESC, latest.id DESC
+        LIMIT 1
+    ) THEN 'live'
+    WHEN deployment.verified THEN 'superseded'
+    ELSE 'pending'
+END;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_contract_deployments_live
+    ON lsrwa_express.contract_deployments(network) WHERE status = 'live';
@@ -0,0 +1,4 @@
+-- Rotations of operator signing keys are audited alongside other privileged operations
+ALTER TABLE lsrwa_express.admin_audit_log DROP CONSTRAINT IF EXISTS check_admin_audit_action;
+ALTER TABLE lsrwa_express.admin_audit_log ADD CONSTRAINT check_admin_audit_action
+    CHECK (action IN ('admin_request', 'parameter_change', 'reconciliation', 'extrinsic', 'key_change'));
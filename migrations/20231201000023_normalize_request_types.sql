@@ -0,0 +1,15 @@
+-- Request types are stored by their lowercase names, which the other tables already enforce.
+-- The event queue never checked them, so rows may still carry the contract's capitalized variant
+-- names; those are lowercased, and anything that isn't a request type at all is cleared (the
+-- event's raw_data keeps what was received).
+UPDATE lsrwa_express.event_queue
+SET request_type = LOWER(request_type)
+WHERE request_type <> LOWER(request_type);
+
+UPDATE lsrwa_express.event_queue
+SET request_type = NULL
+WHERE request_type NOT IN ('deposit', 'withdrawal', 'borrow');
+
+ALTER TABLE lsrwa_express.event_queue DROP CONSTRAINT IF EXISTS check_event_queue_request_type;
+ALTER TABLE lsrwa_express.event_queue ADD CONSTRAINT check_event_queue_request_type
+    CHECK (request_type IN ('deposit', 'withdrawal', 'borrow'));
@@ -0,0 +1,70 @@
+-- Requests carry their lifecycle status instead of only whether they were processed. is_processed
+-- stays, kept equal to status = 'executed' by the application, for the reports that read it.
+-- Existing rows were all recorded from the chain, so open ones are confirmed, or processed once a
+-- batch has included them. Archiving moves every request that reached a final status.
+ALTER TABLE lsrwa_express.blockchain_requests
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'submitted';
+
+UPDATE lsrwa_express.blockchain_requests r
+SET status = CASE
+    WHEN r.is_processed THEN 'executed'
+    WHEN EXISTS (
+        SELECT 1 FROM lsrwa_express.batch_processing_items i
+        WHERE i.request_type = r.request_type AND i.request_id = r.on_chain_id
+    ) THEN 'processed'
+    ELSE 'confirmed'
+END;
+
+ALTER TABLE lsrwa_express.blockchain_requests ADD CONSTRAINT check_blockchain_requests_status
+    CHECK (status IN ('submitted', 'confirmed', 'processed', 'executed', 'cancelled', 'expired'));
+
+CREATE INDEX idx_blockchain_requests_status ON lsrwa_express.blockchain_requests(status, request_type);
+
+-- The archive follows, and archiving now names its columns since status lands after archived_at
+ALTER TABLE lsrwa_express_archive.blockchain_requests
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'executed';
+
+CREATE OR REPLACE FUNCTION lsrwa_express.archive_blockchain_requests(older_than TIMESTAMPTZ, batch_size INTEGER)
+RETURNS INTEGER AS $$
+DECLARE
+    moved INTEGER;
+BEGIN
+    WITH archived AS (
+        DELETE FROM lsrwa_express.blockchain_requests
+        WHERE id IN (
+            SELECT id FROM lsrwa_express.blockchain_requests
+            WHERE status IN ('executed', 'cancelled', 'expired') AND updated_at < older_than AT TIME ZONE 'UTC'
+            ORDER BY id
+            LIMIT batch_size
+        )
+        RETURNING *
+    )
+    INSERT INTO lsrwa_express_archive.blockchain_requests (
+        id, request_type, on_chain_id, wallet_address, user_id, amount, collateral_amount,
+        submission_timestamp, is_processed, block_number, transaction_hash, created_at, updated_at, status
+    )
+    SELECT id, request_type, on_chain_id, wallet_address, user_id, amount, collateral_amount,
+           submission_timestamp, is_processed, block_number, transaction_hash, created_at, updated_at, status
+    FROM archived;
+
+    GET DIAGNOSTICS moved = ROW_COUNT;
+    RETURN moved;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION lsrwa_express.notify_blockchain_request_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('lsrwa_changes', json_build_object(
+        'entity', 'blockchain_request',
+        'operation', lower(TG_OP),
+        'request_type', NEW.request_type,
+        'on_chain_id', NEW.on_chain_id,
+        'wallet_address', NEW.wallet_address,
+        'user_id', NEW.user_id,
+        'is_processed', NEW.is_processed,
+        'status', NEW.status
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,7 @@
+-- Store indexed event payloads as JSON documents
+--
+-- The indexer writes each event's typed payload as JSON, so raw_data always holds a JSON object;
+-- the few rows written with an empty payload become an empty object.
+
+ALTER TABLE lsrwa_express.event_queue
+    ALTER COLUMN raw_data TYPE JSONB USING COALESCE(NULLIF(raw_data, ''), '{}')::JSONB;
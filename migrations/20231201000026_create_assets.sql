@@ -0,0 +1,47 @@
+-- Assets amounts are denominated in. The default asset is the one the contract holds: requests,
+-- balances and rewards are recorded in it unless they name another. The network's own token is
+-- seeded as the default; its decimals are kept in step with the network at startup.
+CREATE TABLE IF NOT EXISTS lsrwa_express.assets (
+    symbol VARCHAR(16) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    decimals SMALLINT NOT NULL CHECK (decimals BETWEEN 0 AND 30),
+    -- 'native' is the network's token; 'psp22' a token contract at `address`
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('native', 'psp22')),
+    address VARCHAR(64),
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT check_asset_address CHECK ((kind = 'native') = (address IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_assets_default ON lsrwa_express.assets(is_default) WHERE is_default;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_assets_native ON lsrwa_express.assets(kind) WHERE kind = 'native';
+
+CREATE TRIGGER update_assets_timestamp
+BEFORE UPDATE ON lsrwa_express.assets
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.update_timestamp();
+
+INSERT INTO lsrwa_express.assets (symbol, name, decimals, kind, is_default)
+VALUES ('NATIVE', 'Network token', 12, 'native', TRUE)
+ON CONFLICT (symbol) DO NOTHING;
+
+-- Rows written without an asset are in whichever asset is the default at the time
+CREATE OR REPLACE FUNCTION lsrwa_express.default_asset()
+RETURNS VARCHAR AS $$
+    SELECT symbol FROM lsrwa_express.assets WHERE is_default
+$$ LANGUAGE sql STABLE;
+
+ALTER TABLE lsrwa_express.blockchain_requests
+    ADD COLUMN IF NOT EXISTS asset VARCHAR(16) NOT NULL DEFAULT 'NATIVE'
+        REFERENCES lsrwa_express.assets(symbol) ON UPDATE CASCADE;
+ALTER TABLE lsrwa_express.blockchain_requests ALTER COLUMN asset SET DEFAULT lsrwa_express.default_asset();
+
+ALTER TABLE lsrwa_express.user_balances
+    ADD COLUMN IF NOT EXISTS asset VARCHAR(16) NOT NULL DEFAULT 'NATIVE'
+        REFERENCES lsrwa_express.assets(symbol) ON UPDATE CASCADE;
+ALTER TABLE lsrwa_express.user_balances ALTER COLUMN asset SET DEFAULT lsrwa_express.default_asset();
+
+ALTER TABLE lsrwa_express.user_rewards
+    ADD COLUMN IF NOT EXISTS asset VARCHAR(16) NOT NULL DEFAULT 'NATIVE'
+        REFERENCES lsrwa_express.assets(symbol) ON UPDATE CASCADE;
+ALTER TABLE lsrwa_express.user_rewards ALTER COLUMN asset SET DEFAULT lsrwa_express.default_asset();
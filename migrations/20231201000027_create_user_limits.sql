@@ -0,0 +1,25 @@
+-- Rolling submission volume limits per wallet, in tokens of the contract's asset
+-- ('unlimited' for no cap); user_limits rows override them for one user
+INSERT INTO lsrwa_express.system_parameters (parameter_name, parameter_value, description)
+VALUES
+('deposit_epoch_volume_limit', 'unlimited', 'Deposits a wallet may request per epoch, in tokens'),
+('withdrawal_epoch_volume_limit', 'unlimited', 'Withdrawals a wallet may request per epoch, in tokens'),
+('deposit_daily_volume_limit', 'unlimited', 'Deposits a wallet may request in any 24 hours, in tokens'),
+('withdrawal_daily_volume_limit', 'unlimited', 'Withdrawals a wallet may request in any 24 hours, in tokens')
+ON CONFLICT (parameter_name) DO NOTHING;
+
+-- A NULL limit falls back to the system parameter
+CREATE TABLE lsrwa_express.user_limits (
+    user_id UUID PRIMARY KEY REFERENCES lsrwa_express.users(id) ON DELETE CASCADE,
+    deposit_epoch_limit NUMERIC(36, 18) CHECK (deposit_epoch_limit >= 0),
+    withdrawal_epoch_limit NUMERIC(36, 18) CHECK (withdrawal_epoch_limit >= 0),
+    deposit_daily_limit NUMERIC(36, 18) CHECK (deposit_daily_limit >= 0),
+    withdrawal_daily_limit NUMERIC(36, 18) CHECK (withdrawal_daily_limit >= 0),
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_user_limits_timestamp
+BEFORE UPDATE ON lsrwa_express.user_limits
+FOR EACH ROW EXECUTE FUNCTION lsrwa_express.update_timestamp();
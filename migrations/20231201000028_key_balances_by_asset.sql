@@ -0,0 +1,19 @@
+-- A user holds one balance per asset
+ALTER TABLE lsrwa_express.user_balances DROP CONSTRAINT IF EXISTS user_balances_user_id_key;
+ALTER TABLE lsrwa_express.user_balances
+    ADD CONSTRAINT user_balances_user_id_asset_key UNIQUE (user_id, asset);
+
+-- Rewards are paid on balances in the default asset, so only those are kept in the history
+CREATE OR REPLACE FUNCTION lsrwa_express.record_active_balance_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.asset IS DISTINCT FROM lsrwa_express.default_asset() THEN
+        RETURN NEW;
+    END IF;
+    IF TG_OP = 'INSERT' OR NEW.active_balance IS DISTINCT FROM OLD.active_balance THEN
+        INSERT INTO lsrwa_express.active_balance_history (user_id, active_balance)
+        VALUES (NEW.user_id, NEW.active_balance);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
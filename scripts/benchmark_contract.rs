@@ -0,0 +1,128 @@
+//! Benchmarks the contract's messages against a development node and checks them against the
+//! recorded baseline.
+//!
+//! Usage: `cargo run --bin benchmark_contract -- [--bundle PATH] [--rpc-url URL] [--baseline PATH] [--threshold PERCENT] [--update]`
+//!
+//! The build is deployed from `//Alice`, once per code hash, and each message is dry-run from
+//! the owner with arguments of a few sizes. The ref time, proof size and storage deposit each
+//! call needs are compared with the baseline, and the command fails if any grew by more than
+//! the threshold. `--update` records the results as the new baseline instead; so does the
+//! first run, when there's no baseline yet.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use clap::Parser;
+use std::path::PathBuf;
+use subxt::ext::sp_core::{sr25519, Pair as PairTrait};
+
+use lsrwa_express_rust::contract::benchmark::{self, Baseline, BenchmarkResult};
+use lsrwa_express_rust::contract::deployment::{
+    contract_address, deterministic_salt, hex_string, ContractArtifact, Deployer,
+};
+
+/// Dev account the contract is deployed and called from, endowed on every `--dev` chain
+const OWNER_SEED: &str = "//Alice";
+
+/// Benchmarks contract messages
+#[derive(Parser)]
+#[command(name = "benchmark_contract")]
+struct Options {
+    /// Contract bundle written by `cargo contract build --release`
+    #[arg(long, default_value = "contracts/target/ink/lsrwa_express_contract.contract")]
+    bundle: PathBuf,
+
+    /// Development node to benchmark on
+    #[arg(long, default_value = "ws://127.0.0.1:9944")]
+    rpc_url: String,
+
+    /// Recorded benchmarks to compare with
+    #[arg(long, default_value = "contracts/benchmarks.json")]
+    baseline: PathBuf,
+
+    /// Growth over the baseline, in percent, that counts as a regression
+    #[arg(long, default_value_t = 10.0)]
+    threshold: f64,
+
+    /// Record the results as the new baseline
+    #[arg(long)]
+    update: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let options = Options::parse();
+
+    let artifact = ContractArtifact::load(&options.bundle)?;
+    let code_hash = hex_string(&artifact.code_hash);
+    println!("Build:    code hash {}", code_hash);
+
+    let owner = sr25519::Pair::from_string(OWNER_SEED, None).expect("dev seed phrases are valid");
+    let deployer = Deployer::connect(&options.rpc_url, owner).await?;
+    println!("Node:     {} (genesis {})", options.rpc_url, deployer.genesis_hash());
+
+    let salt = deterministic_salt("local", &format!("benchmark:{}", code_hash));
+    let address = contract_address(&deployer.account(), &artifact.code_hash, &artifact.constructor, &salt);
+    if deployer.onchain_code_hash(&address).await?.is_none() {
+        let estimate = deployer.dry_run(&artifact, &salt).await?;
+        deployer.instantiate(&artifact, &salt, &estimate).await?;
+    }
+    println!("Contract: {}\n", address);
+
+    let contract = deployer.contract(address);
+    let caller = deployer.account();
+    let mut results = Vec::new();
+    for (message, selector) in &artifact.messages {
+        let Some(cases) = benchmark::cases(message, &caller) else {
+            println!("{:<36} skipped: no benchmark arguments are defined for it", message);
+            continue;
+        };
+
+        for case in cases {
+            let mut input = selector.to_vec();
+            input.extend(&case.arguments);
+            let estimate = contract
+                .estimate(&caller, input)
+                .await
+                .with_context(|| format!("Failed to dry-run '{}' with size {}", message, case.size))?;
+
+            let result = BenchmarkResult::new(message, case.size, &estimate);
+            println!(
+                "{:<30} {:>4}  ref_time {:>14}  proof_size {:>9}  deposit {:>16}{}",
+                result.message,
+                result.size,
+                result.ref_time,
+                result.proof_size,
+                result.storage_deposit,
+                if result.reverted { "  (reverted)" } else { "" }
+            );
+            results.push(result);
+        }
+    }
+
+    let current = Baseline {
+        code_hash,
+        recorded_at: Utc::now(),
+        results,
+    };
+
+    let baseline = match Baseline::load(&options.baseline)? {
+        Some(baseline) if !options.update => baseline,
+        _ => {
+            current.save(&options.baseline)?;
+            println!("\n✅ Recorded {} benchmarks as the baseline in {}", current.results.len(), options.baseline.display());
+            return Ok(());
+        },
+    };
+
+    let regressions = benchmark::regressions(&baseline.results, &current.results, options.threshold);
+    if regressions.is_empty() {
+        println!("\n✅ No message regressed by more than {}% against {} ({})", options.threshold, options.baseline.display(), baseline.code_hash);
+        return Ok(());
+    }
+
+    println!("\nRegressions against {} ({}):", options.baseline.display(), baseline.code_hash);
+    for regression in &regressions {
+        println!("  {}", regression);
+    }
+    bail!("{} benchmarks regressed by more than {}%; run with --update if that's expected", regressions.len(), options.threshold)
+}
@@ -1,45 +1,174 @@
-use anyhow::Result;
-use std::fs;
-use std::path::Path;
+//! Deploys the LSRWA Express contract and records the deployment in Postgres.
+//!
+//! Usage: `cargo run --bin deploy_contract -- [--bundle PATH] [--salt LABEL] [--dry-run]`, or
+//! `--verify ADDRESS` to check a deployed contract against the local build.
+//!
+//! The contract is signed by `CONTRACT_OWNER_SEED_PHRASE` on the node at `SUBSTRATE_RPC_URL`,
+//! and recorded under `CHAIN_NETWORK` in the deployment registry, where it goes live once
+//! verified. Its address depends only on the network, the salt label, the build and the owner,
+//! so it's printed before anything is submitted.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use sqlx::types::BigDecimal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use subxt::utils::AccountId32;
+
+use lsrwa_express_rust::config::{ChainNetwork, DatabaseConfig, SecretsConfig, Settings};
+use lsrwa_express_rust::contract::deployment::{
+    contract_address, deterministic_salt, hex_string, ContractArtifact, Deployer,
+};
+use lsrwa_express_rust::db::{self, DeploymentRepository};
+use lsrwa_express_rust::models::deployment::NewContractDeployment;
+use lsrwa_express_rust::services::keystore;
+use lsrwa_express_rust::services::secrets::SecretStore;
+
+/// Deploys the LSRWA Express contract
+#[derive(Parser)]
+#[command(name = "deploy_contract")]
+struct Options {
+    /// Contract bundle written by `cargo contract build --release`
+    #[arg(long, default_value = "contracts/target/ink/lsrwa_express_contract.contract")]
+    bundle: PathBuf,
+
+    /// Label the address is derived from; change it to deploy another instance of the same build
+    #[arg(long, default_value = "1")]
+    salt: String,
+
+    /// Only estimate gas and storage deposit and print the address
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Check the contract at this address against the local build instead of deploying
+    #[arg(long, conflicts_with = "dry_run")]
+    verify: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    println!("LSRWA Express Contract Deployment Tool");
-    println!("======================================");
-    
-    // This script is for development purposes only and won't work in non-wasm32 environments
-    // Instead, we'll just print instructions on how to deploy the contract
-    println!("This script is designed to deploy the LSRWA Express contract to a Substrate chain.");
-    println!("However, it requires the ink! crate which is only available for wasm32 targets.");
-    println!("\nTo deploy the contract, follow these steps:");
-    println!("1. Build the contract with: cargo contract build --release");
-    println!("2. Deploy using the Contracts UI: https://contracts-ui.substrate.io/");
-    println!("3. Set the CONTRACT_ADDRESS environment variable with the deployed address");
-    
-    // For development, create a mock deployment record
-    let deployment_info = DeploymentInfo {
-        contract_address: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
-        code_hash: "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string(),
-        block_number: 1,
-        transaction_hash: "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
-    };
-    
-    // Write the deployment info to a file
-    let deployment_file = Path::new("deployment_info.json");
-    fs::write(deployment_file, serde_json::to_string_pretty(&deployment_info)?)?;
-    
-    println!("\nMock deployment info written to: {:?}", deployment_file);
-    println!("Set CONTRACT_ADDRESS=5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
-    
+    dotenv::from_filename(".env.local").ok();
+    dotenv::dotenv().ok();
+
+    let options = Options::parse();
+
+    println!("LSRWA Express Contract Deployment");
+    println!("=================================");
+
+    let mut settings = Settings::load().context("Failed to load configuration files")?;
+    let secrets_config = SecretsConfig::from_settings(&settings).context("Invalid secrets configuration")?;
+    let secrets = SecretStore::from_config(&secrets_config, &settings).context("Failed to initialize secrets backend")?;
+    secrets.resolve_settings(&mut settings).await.context("Failed to read secrets")?;
+
+    let network = settings.get_or("CHAIN_NETWORK", ChainNetwork::Local)?.to_string();
+    let rpc_url = settings.var("SUBSTRATE_RPC_URL")
+        .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string());
+
+    let artifact = ContractArtifact::load(&options.bundle)?;
+    println!("Local build:  code hash {}", hex_string(&artifact.code_hash));
+
+    let seed_phrase = secrets.require("CONTRACT_OWNER_SEED_PHRASE").await?;
+    let owner = keystore::keypair(&seed_phrase).context("Invalid contract owner key")?;
+    let deployer = Deployer::connect(&rpc_url, owner).await?;
+    println!("Network:      {} ({}, genesis {})", network, rpc_url, deployer.genesis_hash());
+
+    let database_config = DatabaseConfig::from_settings(&settings).context("Invalid database configuration")?;
+
+    if let Some(address) = options.verify {
+        let address = AccountId32::from_str(&address).map_err(|e| anyhow!("Invalid address {}: {}", address, e))?;
+        let verified = verify(&deployer, &address, &artifact).await?;
+
+        let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+        if DeploymentRepository::new(pool.pg).set_verified(&network, &address.to_string(), verified).await?.is_none() {
+            println!("No deployment of {} is recorded on {}", address, network);
+        }
+
+        if !verified {
+            bail!("The contract at {} doesn't match the local build", address);
+        }
+        return Ok(());
+    }
+
+    let salt = deterministic_salt(&network, &options.salt);
+    let address = contract_address(&deployer.account(), &artifact.code_hash, &artifact.constructor, &salt);
+    println!("Deployer:     {}", deployer.account());
+    println!("Salt:         {} ('{}')", hex_string(&salt), options.salt);
+    println!("Address:      {}", address);
+
+    if let Some(code_hash) = deployer.onchain_code_hash(&address).await? {
+        if code_hash == artifact.code_hash {
+            println!("\nThis build is already deployed at {}", address);
+            return Ok(());
+        }
+        bail!("A different build is deployed at {}; pass another --salt", address);
+    }
+
+    let estimate = deployer.dry_run(&artifact, &salt).await?;
+    println!(
+        "Dry run:      gas {} ref time / {} proof size, storage deposit {}",
+        estimate.gas_required.ref_time, estimate.gas_required.proof_size, estimate.storage_deposit
+    );
+    if estimate.address != address {
+        bail!("The node would instantiate at {}, not the expected {}", estimate.address, address);
+    }
+
+    if options.dry_run {
+        return Ok(());
+    }
+
+    let instantiated = deployer.instantiate(&artifact, &salt, &estimate).await?;
+    println!(
+        "\nInstantiated: {} in block {} ({})",
+        instantiated.address, instantiated.block_number, instantiated.transaction_hash
+    );
+    let verified = verify(&deployer, &instantiated.address, &artifact).await?;
+
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+    let deployment = DeploymentRepository::new(pool.pg)
+        .record(&NewContractDeployment {
+            network,
+            chain_genesis_hash: deployer.genesis_hash(),
+            contract_address: instantiated.address.to_string(),
+            code_hash: hex_string(&artifact.code_hash),
+            salt: hex_string(&salt),
+            deployer: deployer.account().to_string(),
+            transaction_hash: instantiated.transaction_hash,
+            block_number: instantiated.block_number as i64,
+            gas_ref_time: estimate.gas_required.ref_time as i64,
+            gas_proof_size: estimate.gas_required.proof_size as i64,
+            storage_deposit: BigDecimal::from_str(&estimate.storage_deposit.to_string())?,
+            verified,
+        })
+        .await?;
+    println!("Recorded deployment {} on {}", deployment.id, deployment.network);
+
+    if !verified {
+        bail!("The deployed code doesn't match the local build");
+    }
+
+    println!(
+        "\n{} is now live on {}; services there use it unless CONTRACT_ADDRESS is set",
+        deployment.contract_address, deployment.network
+    );
     Ok(())
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct DeploymentInfo {
-    contract_address: String,
-    code_hash: String,
-    block_number: u32,
-    transaction_hash: String,
-    timestamp: String,
-} 
\ No newline at end of file
+/// Compares the code hash stored for the contract at `address` with the local build
+async fn verify(deployer: &Deployer, address: &AccountId32, artifact: &ContractArtifact) -> Result<bool> {
+    let code_hash = deployer.onchain_code_hash(address)
+        .await?
+        .with_context(|| format!("No contract is instantiated at {}", address))?;
+
+    let verified = code_hash == artifact.code_hash;
+    if verified {
+        println!("Verified:     on-chain code hash matches the local build");
+    } else {
+        println!(
+            "Mismatch:     on-chain code hash {} differs from the local build's {}",
+            hex_string(&code_hash),
+            hex_string(&artifact.code_hash)
+        );
+    }
+
+    Ok(verified)
+}
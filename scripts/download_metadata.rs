@@ -1,53 +1,79 @@
-use std::{fs, env};
+//! Downloads the chain's runtime metadata into the per-network cache.
+//!
+//! Usage: `cargo run --bin download_metadata -- [--network NETWORK] [--rpc-url URL] [--check]`
+//!
+//! The metadata of the node at `SUBSTRATE_RPC_URL` is written to `METADATA_DIR/<network>.scale`,
+//! `CHAIN_NETWORK`'s file unless `--network` names another, with its checksum next to it. When
+//! the cached metadata already matches the node's, nothing is written.
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
 use subxt::{OnlineClient, PolkadotConfig};
-use anyhow::{Result, Context};
+
+use lsrwa_express_rust::config::{BlockchainConfig, ChainNetwork, Settings};
+use lsrwa_express_rust::services::chain_metadata;
+
+/// Downloads runtime metadata
+#[derive(Parser)]
+#[command(name = "download_metadata")]
+struct Options {
+    /// Network the metadata is cached for; defaults to CHAIN_NETWORK
+    #[arg(long)]
+    network: Option<ChainNetwork>,
+
+    /// Node to download from; defaults to SUBSTRATE_RPC_URL
+    #[arg(long)]
+    rpc_url: Option<String>,
+
+    /// Only check the cached metadata is current, failing if it isn't
+    #[arg(long)]
+    check: bool,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Get the RPC URL from environment or use default
-    let rpc_url = std::env::var("SUBSTRATE_RPC_URL")
-        .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io:443".to_string());
-    
-    println!("Connecting to {}", rpc_url);
-    
-    // Try to connect to the node
-    let client_result = OnlineClient::<PolkadotConfig>::from_url(rpc_url).await;
-    
-    // If connection fails, just create mock files
-    if let Err(e) = client_result {
-        println!("Failed to connect to blockchain node: {}", e);
-        println!("Creating mock metadata files instead");
+    dotenv::from_filename(".env.local").ok();
+    dotenv::dotenv().ok();
+
+    let options = Options::parse();
+
+    let settings = Settings::load().context("Failed to load configuration files")?;
+    let network = match options.network {
+        Some(network) => network,
+        None => settings.get_or("CHAIN_NETWORK", ChainNetwork::Local)?,
+    };
+    let rpc_url = match options.rpc_url {
+        Some(rpc_url) => rpc_url,
+        None => settings.var("SUBSTRATE_RPC_URL")
+            .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string()),
+    };
+    let path = BlockchainConfig::metadata_path(&settings, network);
+
+    println!("Connecting to {} ({})", rpc_url, network);
+    let client = OnlineClient::<PolkadotConfig>::from_url(&rpc_url)
+        .await
+        .with_context(|| format!("Failed to connect to the node at {}", rpc_url))?;
+    let runtime = client.runtime_version();
+    let metadata = chain_metadata::fetch(&client).await?;
+    let checksum = chain_metadata::checksum(&metadata);
+    println!("Runtime spec version {}, metadata checksum {}", runtime.spec_version, checksum);
+
+    if options.check {
+        return match chain_metadata::load(&path)? {
+            Some(cached) if cached.checksum == checksum => {
+                println!("{} is current", path.display());
+                Ok(())
+            },
+            Some(cached) => bail!("{} is out of date (checksum {})", path.display(), cached.checksum),
+            None => bail!("{} is missing", path.display()),
+        };
+    }
+
+    if chain_metadata::store(&path, &metadata)? {
+        println!("Metadata written to {} ({} bytes)", path.display(), metadata.len());
     } else {
-        println!("Successfully connected to blockchain node");
+        println!("{} is already current", path.display());
     }
-    
-    // Create a mock metadata file for development
-    let metadata_bytes = b"mock_metadata_for_development";
-    
-    // Determine project root directory
-    let project_root = env::current_dir()
-        .context("Failed to get current directory")?;
-    
-    println!("Project root: {:?}", project_root);
-    
-    // Create the metadata directory if it doesn't exist
-    let metadata_dir = project_root.join("metadata");
-    fs::create_dir_all(&metadata_dir)
-        .context("Failed to create metadata directory")?;
-    
-    // Write the metadata to a file
-    let metadata_path = metadata_dir.join("metadata.scale");
-    fs::write(&metadata_path, metadata_bytes)
-        .context("Failed to write metadata file")?;
-    
-    println!("Metadata written to {:?}", metadata_path);
-    
-    // Also copy to the root directory for subxt macros
-    let root_metadata_path = project_root.join("metadata.scale");
-    fs::write(&root_metadata_path, metadata_bytes)
-        .context("Failed to write root metadata file")?;
-    
-    println!("Metadata also written to {:?}", root_metadata_path);
-    
+
     Ok(())
-} 
\ No newline at end of file
+}
@@ -0,0 +1,241 @@
+//! Upgrades the deployed LSRWA Express contract to a new build and records it in Postgres.
+//!
+//! Usage: `cargo run --bin upgrade_contract -- --notes NOTES [--bundle PATH] [--contract ADDRESS] [--dry-run]`
+//!
+//! The contract defaults to `CONTRACT_ADDRESS`, or else the network's live deployment in the
+//! deployment registry, whose code hash is updated once the upgrade is verified.
+//!
+//! The contract's code is replaced through its timelocked upgrade: `schedule_upgrade(code_hash)`
+//! returns when the upgrade may be applied, and `upgrade()` then switches the contract to the
+//! scheduled code. Both are called by `CONTRACT_OWNER_SEED_PHRASE` on the node at
+//! `SUBSTRATE_RPC_URL`, which also uploads the new code. A contract without those messages is
+//! refused before anything is submitted.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use clap::Parser;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use subxt::utils::AccountId32;
+
+use lsrwa_express_rust::config::{ChainNetwork, DatabaseConfig, SecretsConfig, Settings};
+use lsrwa_express_rust::contract::deployment::{hex_string, ContractArtifact, Deployer};
+use lsrwa_express_rust::contract::{self, message_input, LsrwaExpressContract};
+use lsrwa_express_rust::db::{self, DeploymentRepository};
+use lsrwa_express_rust::models::deployment::NewContractVersion;
+use lsrwa_express_rust::services::keystore;
+use lsrwa_express_rust::services::secrets::SecretStore;
+
+/// Message scheduling an upgrade to a code hash, returning when it may be applied
+const SCHEDULE_UPGRADE: &str = "schedule_upgrade";
+
+/// Message applying the scheduled upgrade once its timelock has passed
+const UPGRADE: &str = "upgrade";
+
+/// Longest wait between checks of the timelock
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Times `upgrade` is retried, a block apart, once the estimated timelock has passed
+const DUE_RETRIES: u32 = 10;
+
+/// Time between blocks, roughly
+const BLOCK_TIME: Duration = Duration::from_secs(6);
+
+/// Upgrades the LSRWA Express contract
+#[derive(Parser)]
+#[command(name = "upgrade_contract")]
+struct Options {
+    /// Contract bundle of the new build, written by `cargo contract build --release`
+    #[arg(long, default_value = "contracts/target/ink/lsrwa_express_contract.contract")]
+    bundle: PathBuf,
+
+    /// Contract to upgrade; defaults to CONTRACT_ADDRESS, then the network's live deployment
+    #[arg(long)]
+    contract: Option<String>,
+
+    /// What changed in the new build and any migration it needs, recorded with the upgrade
+    #[arg(long)]
+    notes: String,
+
+    /// Only check the contract can be upgraded to the new build
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::from_filename(".env.local").ok();
+    dotenv::dotenv().ok();
+
+    let options = Options::parse();
+
+    println!("LSRWA Express Contract Upgrade");
+    println!("==============================");
+
+    let mut settings = Settings::load().context("Failed to load configuration files")?;
+    let secrets_config = SecretsConfig::from_settings(&settings).context("Invalid secrets configuration")?;
+    let secrets = SecretStore::from_config(&secrets_config, &settings).context("Failed to initialize secrets backend")?;
+    secrets.resolve_settings(&mut settings).await.context("Failed to read secrets")?;
+
+    let network = settings.get_or("CHAIN_NETWORK", ChainNetwork::Local)?.to_string();
+    let rpc_url = settings.var("SUBSTRATE_RPC_URL")
+        .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string());
+    let database_config = DatabaseConfig::from_settings(&settings).context("Invalid database configuration")?;
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+    let deployments = DeploymentRepository::new(pool.pg);
+
+    let address = match options.contract.or_else(|| settings.var("CONTRACT_ADDRESS").ok()) {
+        Some(address) => address,
+        None => deployments
+            .live(&network)
+            .await?
+            .map(|deployment| deployment.contract_address)
+            .with_context(|| format!("No live {} deployment is registered; pass --contract or set CONTRACT_ADDRESS", network))?,
+    };
+    let address = AccountId32::from_str(&address).map_err(|e| anyhow!("Invalid address {}: {}", address, e))?;
+
+    let artifact = ContractArtifact::load(&options.bundle)?;
+    println!("New build:    code hash {}", hex_string(&artifact.code_hash));
+    // ink! derives selectors from labels, so the new build's selectors are the deployed one's too
+    let (schedule_selector, upgrade_selector) = match (artifact.messages.get(SCHEDULE_UPGRADE), artifact.messages.get(UPGRADE)) {
+        (Some(schedule), Some(upgrade)) => (*schedule, *upgrade),
+        _ => bail!("The new build has no '{}' and '{}' messages, so it couldn't be upgraded again", SCHEDULE_UPGRADE, UPGRADE),
+    };
+
+    let seed_phrase = secrets.require("CONTRACT_OWNER_SEED_PHRASE").await?;
+    let owner = keystore::keypair(&seed_phrase).context("Invalid contract owner key")?;
+    let deployer = Deployer::connect(&rpc_url, owner).await?;
+    println!("Network:      {} ({}, genesis {})", network, rpc_url, deployer.genesis_hash());
+
+    let previous_code_hash = deployer.onchain_code_hash(&address)
+        .await?
+        .with_context(|| format!("No contract is instantiated at {}", address))?;
+    println!("Contract:     {}, code hash {}", address, hex_string(&previous_code_hash));
+    if previous_code_hash == artifact.code_hash {
+        println!("\nThe contract already runs this build");
+        return Ok(());
+    }
+
+    let contract = deployer.contract(address.clone());
+    let schedule_input = message_input(schedule_selector, (artifact.code_hash,));
+    let executable_at = contract_result(
+        contract
+            .dry_run::<Result<u64, u8>>(&deployer.account(), schedule_input.clone())
+            .await
+            .with_context(|| format!("The contract can't be upgraded; it needs the timelocked '{}' message", SCHEDULE_UPGRADE))?,
+        SCHEDULE_UPGRADE,
+    )?;
+    let executable_at = Utc
+        .timestamp_millis_opt(executable_at as i64)
+        .single()
+        .context("Invalid upgrade time")?;
+    println!("Timelock:     the upgrade could be applied from {}", executable_at);
+
+    if options.dry_run {
+        return Ok(());
+    }
+
+    let upload_transaction_hash = deployer.upload_code(&artifact).await?;
+    match &upload_transaction_hash {
+        Some(transaction_hash) => println!("\nUploaded:     {}", transaction_hash),
+        None => println!("\nUploaded:     the code is already on-chain"),
+    }
+
+    let signer = deployer.signer();
+    let scheduled = contract
+        .submit_finalized(&signer, schedule_input, contract::estimate_gas_for_upgrade_schedule())
+        .await
+        .context("Failed to schedule the upgrade")?;
+    let schedule_transaction_hash = format!("{:?}", scheduled.extrinsic_hash());
+    println!("Scheduled:    {}", schedule_transaction_hash);
+
+    wait_for_timelock(&deployer, executable_at.timestamp_millis() as u64).await?;
+
+    let upgrade_input = message_input(upgrade_selector, ());
+    wait_until_due(&contract, &deployer.account(), &upgrade_input).await?;
+    let upgraded = contract
+        .submit_finalized(&signer, upgrade_input, contract::estimate_gas_for_upgrade())
+        .await
+        .context("Failed to apply the upgrade")?;
+    let upgrade_transaction_hash = format!("{:?}", upgraded.extrinsic_hash());
+    let block_number = deployer.block_number(upgraded.block_hash()).await?;
+    println!("Upgraded:     in block {} ({})", block_number, upgrade_transaction_hash);
+
+    let code_hash = deployer.onchain_code_hash(&address)
+        .await?
+        .with_context(|| format!("No contract is instantiated at {} after the upgrade", address))?;
+    let verified = code_hash == artifact.code_hash;
+    if verified {
+        println!("Verified:     on-chain code hash matches the new build");
+    } else {
+        println!(
+            "Mismatch:     on-chain code hash {} differs from the new build's {}",
+            hex_string(&code_hash),
+            hex_string(&artifact.code_hash)
+        );
+    }
+
+    let version = deployments
+        .record_version(&NewContractVersion {
+            network,
+            contract_address: address.to_string(),
+            previous_code_hash: hex_string(&previous_code_hash),
+            code_hash: hex_string(&artifact.code_hash),
+            upload_transaction_hash,
+            schedule_transaction_hash,
+            executable_at,
+            upgrade_transaction_hash,
+            block_number: block_number as i64,
+            upgraded_by: deployer.account().to_string(),
+            migration_notes: options.notes,
+            verified,
+        })
+        .await?;
+    println!("Recorded version {} of {} on {}", version.id, version.contract_address, version.network);
+
+    if !verified {
+        bail!("The upgraded code doesn't match the new build");
+    }
+
+    if deployments.set_code_hash(&version.network, &version.contract_address, &version.code_hash).await?.is_none() {
+        println!("No deployment of {} is recorded on {}", version.contract_address, version.network);
+    }
+
+    Ok(())
+}
+
+/// What a message returned, or an error naming the contract error it failed with. Contract
+/// errors are fieldless, so they're decoded as their variant index.
+fn contract_result<T>(returned: Result<T, u8>, message: &str) -> Result<T> {
+    returned.map_err(|error| anyhow!("'{}' fails with variant {} of the contract's Error", message, error))
+}
+
+/// Waits until the chain's time reaches `due`, in milliseconds since the Unix epoch
+async fn wait_for_timelock(deployer: &Deployer, due: u64) -> Result<()> {
+    loop {
+        let now = deployer.chain_time().await?;
+        if now >= due {
+            return Ok(());
+        }
+
+        let remaining = Duration::from_millis(due - now);
+        println!("Waiting:      {}s left on the timelock", remaining.as_secs());
+        tokio::time::sleep(remaining.min(MAX_POLL_INTERVAL)).await;
+    }
+}
+
+/// Waits until `upgrade` would succeed. The schedule was included a little after the dry run
+/// the timelock was estimated from, so it can take a few more blocks.
+async fn wait_until_due(contract: &LsrwaExpressContract, origin: &AccountId32, upgrade_input: &[u8]) -> Result<()> {
+    let mut retries = 0;
+    loop {
+        let returned = contract.dry_run::<Result<(), u8>>(origin, upgrade_input.to_vec()).await?;
+        if returned.is_ok() || retries == DUE_RETRIES {
+            return contract_result(returned, UPGRADE);
+        }
+
+        retries += 1;
+        tokio::time::sleep(BLOCK_TIME).await;
+    }
+}
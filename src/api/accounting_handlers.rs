@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::{streaming, AppState};
+use crate::db::DbAccess;
+use crate::models::accounting::{AccountingExportQuery, AccountingFormat};
+use crate::services::accounting::{csv_rows, render_ofx, AccountingService, CSV_HEADER};
+
+/// Longest period exported at once, in days
+const MAX_EXPORT_DAYS: i64 = 366;
+
+/// Export the double-entry journal of a period as JSON or CSV, or the cash account's movements
+/// as an OFX statement. The journal is streamed as it's read from the database.
+pub async fn export_journal(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<AccountingExportQuery>,
+) -> ApiResult<Response> {
+    if query.to < query.from {
+        return Err(ApiError::Validation("The period must end on or after its start".to_string()));
+    }
+    if (query.to - query.from).num_days() >= MAX_EXPORT_DAYS {
+        return Err(ApiError::Validation(format!("Periods longer than {} days can't be exported", MAX_EXPORT_DAYS)));
+    }
+
+    let accounting = AccountingService::new(state.db.pool(DbAccess::Read));
+    let filename = |extension: &str| {
+        format!("attachment; filename=\"journal-{}-{}.{}\"", query.from, query.to, extension)
+    };
+    let (from, to) = (query.from, query.to);
+    let journal = || {
+        let accounting = accounting.clone();
+        streaming::spawn(move |sender| async move {
+            match accounting.stream_journal(from, to) {
+                Ok(entries) => streaming::forward(entries, &sender).await,
+                Err(e) => {
+                    let _ = sender.send(Err(e)).await;
+                },
+            }
+        })
+    };
+
+    Ok(match query.format {
+        AccountingFormat::Json => streaming::json(journal()),
+        AccountingFormat::Csv => (
+            [(header::CONTENT_DISPOSITION, filename("csv"))],
+            streaming::csv(journal(), CSV_HEADER, csv_rows),
+        )
+            .into_response(),
+        AccountingFormat::Ofx => {
+            let statement = accounting.cash_statement(query.from, query.to).await?;
+            (
+                [(header::CONTENT_TYPE, "application/x-ofx".to_string()), (header::CONTENT_DISPOSITION, filename("ofx"))],
+                render_ofx(&statement, Utc::now()),
+            )
+                .into_response()
+        },
+    })
+}
@@ -0,0 +1,133 @@
+//! Admission control for submissions
+//!
+//! Every submission ends up as events for the indexer to handle, so while the event queue is
+//! deeper than the configured limit, new submissions are refused with a 503 and a
+//! `Retry-After` instead of adding to the backlog. Reads are always admitted.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use metrics::increment_counter;
+use serde::Serialize;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::config::IndexerConfig;
+use crate::services::indexer::QueueDepth;
+
+/// Whether submissions are admitted, judged by the depth of the event queue
+#[derive(Clone)]
+pub struct Admission {
+    depth: QueueDepth,
+    /// 0 admits every submission
+    max_queue_depth: usize,
+    retry_after_secs: u64,
+}
+
+/// Depth of the event queue and whether submissions are admitted
+#[derive(Debug, Serialize)]
+pub struct QueueStatus {
+    pub depth: usize,
+    pub max_depth: usize,
+    pub admitting: bool,
+}
+
+impl Admission {
+    /// Admits submissions while `depth` is within the configured limit
+    pub fn new(depth: QueueDepth, config: &IndexerConfig) -> Self {
+        Self {
+            depth,
+            max_queue_depth: config.admission_queue_depth,
+            retry_after_secs: config.admission_retry_after_secs,
+        }
+    }
+
+    /// The queue's depth and whether submissions are admitted
+    pub fn status(&self) -> QueueStatus {
+        let depth = self.depth.get();
+        QueueStatus {
+            depth,
+            max_depth: self.max_queue_depth,
+            admitting: self.max_queue_depth == 0 || depth < self.max_queue_depth,
+        }
+    }
+
+    /// The 503 a refused submission gets
+    fn rejection(&self) -> Response {
+        let mut response = ApiError::ServiceUnavailable(
+            "The indexer is catching up; retry the submission shortly".to_string(),
+        )
+        .into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(self.retry_after_secs));
+        response
+    }
+}
+
+/// Refuses submissions while the event queue is too deep
+pub async fn shed_load<B>(State(admission): State<Admission>, request: Request<B>, next: Next<B>) -> Response {
+    if !admission.status().admitting {
+        increment_counter!("submissions_shed_total");
+        return admission.rejection();
+    }
+
+    next.run(request).await
+}
+
+/// Reports the event queue's depth and whether submissions are admitted
+pub async fn get_queue_status(_admin: AdminAuth, State(state): State<AppState>) -> ApiResult<Json<QueueStatus>> {
+    Ok(Json(state.admission.status()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    fn admission(max_queue_depth: usize) -> (Admission, QueueDepth) {
+        let depth = QueueDepth::new();
+        let admission = Admission { depth: depth.clone(), max_queue_depth, retry_after_secs: 15 };
+        (admission, depth)
+    }
+
+    async fn submit(admission: Admission) -> Response {
+        let router = Router::new()
+            .route("/deposit", post(|| async { StatusCode::ACCEPTED }))
+            .route_layer(from_fn_with_state(admission, shed_load));
+        let request = Request::post("/deposit").body(Body::empty()).unwrap();
+        router.oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn submissions_are_refused_while_the_queue_is_too_deep() {
+        let (admission, depth) = admission(2);
+        depth.enqueued();
+        assert_eq!(submit(admission.clone()).await.status(), StatusCode::ACCEPTED);
+
+        depth.enqueued();
+        let refused = submit(admission.clone()).await;
+        assert_eq!(refused.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(refused.headers()[header::RETRY_AFTER], "15");
+
+        depth.handled();
+        assert_eq!(submit(admission).await.status(), StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn a_limit_of_zero_admits_every_submission() {
+        let (admission, depth) = admission(0);
+        for _ in 0..1000 {
+            depth.enqueued();
+        }
+        assert_eq!(submit(admission).await.status(), StatusCode::ACCEPTED);
+    }
+}
@@ -0,0 +1,31 @@
+use axum::{extract::State, Json};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::alert::{ActiveAlert, TestAlertRequest};
+
+/// List alerts that have been raised and not yet resolved
+pub async fn list_active_alerts(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<ActiveAlert>>> {
+    Ok(Json(state.alerts.active()))
+}
+
+/// Send a test alert to every channel accepting its severity
+pub async fn send_test_alert(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(payload): Json<TestAlertRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let failed = state.alerts.send_test(payload.severity).await;
+    if !failed.is_empty() {
+        return Err(ApiError::ServiceUnavailable(format!(
+            "Failed to send test alert to {}",
+            failed.join(", ")
+        )));
+    }
+
+    Ok(Json(serde_json::json!({ "sent": true, "severity": payload.severity })))
+}
@@ -0,0 +1,27 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::ApiResult;
+use crate::api::AppState;
+use crate::db::{ArchiveRepository, DbAccess};
+use crate::models::archive::{EventArchive, EventArchiveFilter};
+
+/// List the days of indexed events exported to object storage
+pub async fn list_event_archives(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<EventArchiveFilter>,
+) -> ApiResult<Json<Vec<EventArchive>>> {
+    let filter = EventArchiveFilter {
+        limit: Some(filter.limit.unwrap_or(100).clamp(1, 1000)),
+        offset: Some(filter.offset.unwrap_or(0).max(0)),
+        ..filter
+    };
+
+    let archives = ArchiveRepository::new(state.db.pool(DbAccess::Read)).list_event_archives(&filter).await?;
+
+    Ok(Json(archives))
+}
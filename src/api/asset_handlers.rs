@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::{AssetRepository, DbAccess};
+use crate::models::asset::{Asset, UpsertAssetRequest};
+use crate::models::audit::{AuditAction, NewAuditEntry};
+
+/// List the assets amounts can be denominated in, the contract's first
+pub async fn list_assets(State(state): State<AppState>) -> ApiResult<Json<Vec<Asset>>> {
+    let assets = AssetRepository::new(state.db.pool(DbAccess::Read)).list().await?;
+
+    Ok(Json(assets))
+}
+
+/// Register an asset or change a registered one
+///
+/// Making an asset the default changes the asset the contract is taken to hold, which the
+/// service reads when it starts.
+pub async fn upsert_asset(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(symbol): Path<String>,
+    Json(payload): Json<UpsertAssetRequest>,
+) -> ApiResult<Json<Asset>> {
+    payload.validate(&symbol).map_err(ApiError::Validation)?;
+
+    let assets = AssetRepository::new(state.db.pg.clone());
+    let before = assets.get(&symbol).await?;
+    let updated = assets.upsert(&symbol, &payload).await?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::ParameterChange, format!("asset:{}", symbol))
+                .with_change(before.as_ref(), Some(&updated)),
+        )
+        .await;
+
+    Ok(Json(updated))
+}
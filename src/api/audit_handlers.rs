@@ -0,0 +1,20 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::ApiResult;
+use crate::api::AppState;
+use crate::models::audit::{AuditEntry, AuditFilter};
+
+/// List recorded privileged operations, newest first
+pub async fn list_audit_entries(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<AuditFilter>,
+) -> ApiResult<Json<Vec<AuditEntry>>> {
+    let entries = state.audit.list(&filter).await?;
+
+    Ok(Json(entries))
+}
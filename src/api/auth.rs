@@ -0,0 +1,111 @@
+//! Authentication extractors
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use chrono::Utc;
+use subxt::ext::sp_core::{sr25519, Pair};
+
+use crate::api::error::ApiError;
+use crate::api::AppState;
+use crate::models::wallet::WalletAddress;
+
+/// Extractor guarding admin endpoints with the configured admin API key
+///
+/// Expects `Authorization: Bearer <ADMIN_API_KEY>`. When no key is configured every admin
+/// request is rejected.
+pub struct AdminAuth;
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let expected = state.admin_api_key.as_deref()
+            .ok_or_else(|| ApiError::Unauthorized("Admin API is disabled".to_string()))?;
+
+        let provided = parts.headers.get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::Unauthorized("Missing admin credentials".to_string()))?;
+
+        ring::constant_time::verify_slices_are_equal(provided.as_bytes(), expected.as_bytes())
+            .map_err(|_| ApiError::Unauthorized("Invalid admin credentials".to_string()))?;
+
+        Ok(AdminAuth)
+    }
+}
+
+/// How far the signed timestamp may drift from the server clock
+const WALLET_SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+/// Extractor authenticating the caller as the owner of a Substrate wallet
+///
+/// Expects `X-Wallet-Address` (SS58), `X-Wallet-Timestamp` (unix seconds) and
+/// `X-Wallet-Signature`, the hex sr25519 signature of `lsrwa-express:<address>:<timestamp>`.
+/// Signatures made by browser extensions, which wrap the message in `<Bytes>...</Bytes>`, are
+/// accepted too. Yields the wallet address, normalized.
+pub struct WalletAuth(pub WalletAddress);
+
+impl WalletAuth {
+    /// Message a wallet signs to authenticate at `timestamp`
+    pub fn message(address: &str, timestamp: i64) -> String {
+        format!("lsrwa-express:{}:{}", address, timestamp)
+    }
+
+    /// Refuses callers acting for a wallet other than the one they authenticated as
+    pub fn ensure_is(&self, wallet_address: &WalletAddress) -> Result<(), ApiError> {
+        if self.0 != *wallet_address {
+            return Err(ApiError::Forbidden {
+                code: "WALLET_MISMATCH",
+                message: format!("Authenticated as {}, not {}", self.0, wallet_address),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for WalletAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &AppState) -> Result<Self, Self::Rejection> {
+        let header_value = |name: &str| {
+            parts.headers.get(name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| ApiError::Unauthorized(format!("Missing {} header", name)))
+        };
+
+        let address = header_value("X-Wallet-Address")?;
+        let timestamp = header_value("X-Wallet-Timestamp")?
+            .parse::<i64>()
+            .map_err(|_| ApiError::Unauthorized("Invalid wallet timestamp".to_string()))?;
+        let signature = header_value("X-Wallet-Signature")?;
+
+        if (Utc::now().timestamp() - timestamp).abs() > WALLET_SIGNATURE_TOLERANCE_SECS {
+            return Err(ApiError::Unauthorized("Wallet signature has expired".to_string()));
+        }
+
+        let wallet = WalletAddress::parse(address)
+            .map_err(|_| ApiError::Unauthorized("Invalid wallet address".to_string()))?;
+        let public = sr25519::Public::from_raw(*wallet.public_key());
+        let signature = hex::decode(signature.trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .map(sr25519::Signature::from_raw)
+            .ok_or_else(|| ApiError::Unauthorized("Invalid wallet signature".to_string()))?;
+
+        let message = Self::message(address, timestamp);
+        let wrapped = format!("<Bytes>{}</Bytes>", message);
+        if !sr25519::Pair::verify(&signature, &message, &public)
+            && !sr25519::Pair::verify(&signature, &wrapped, &public)
+        {
+            return Err(ApiError::Unauthorized("Invalid wallet signature".to_string()));
+        }
+
+        Ok(WalletAuth(wallet))
+    }
+}
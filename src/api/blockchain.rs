@@ -1,20 +1,44 @@
+use axum::{
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::error;
 use chrono::{DateTime, Utc};
 
 use crate::models::blockchain_request::RequestType;
+use crate::models::epoch::{EpochStatus, EpochSummary};
+use crate::models::request_status::{RequestStatus, StatusChange};
 use crate::api::error::{ApiError, ApiResult};
+use crate::services::blockchain_service::BlockchainEvent;
 
 /// Represents the current state of the blockchain
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The indexer keeps it current by applying each contract event as it is indexed. Requests are
+/// indexed by wallet and type as well as by ID, so lookups only touch the requests they return.
+#[derive(Debug, Clone, Serialize)]
 pub struct BlockchainState {
     /// Current epoch ID
     pub current_epoch_id: u128,
     
     /// Mapping of request ID to on-chain request
-    pub requests: HashMap<u128, OnChainRequest>,
+    requests: HashMap<u128, OnChainRequest>,
+    
+    /// IDs of each wallet's requests
+    #[serde(skip)]
+    requests_by_wallet: HashMap<String, BTreeSet<u128>>,
+    
+    /// IDs of the requests of each type
+    #[serde(skip)]
+    requests_by_type: HashMap<RequestType, BTreeSet<u128>>,
+    
+    /// Number of requests no longer waiting to be processed
+    #[serde(skip)]
+    processed_count: usize,
     
     /// Mapping of wallet address to user details
     pub users: HashMap<String, OnChainUser>,
@@ -31,6 +55,9 @@ impl Default for BlockchainState {
         Self {
             current_epoch_id: 1,
             requests: HashMap::new(),
+            requests_by_wallet: HashMap::new(),
+            requests_by_type: HashMap::new(),
+            processed_count: 0,
             users: HashMap::new(),
             epochs: HashMap::new(),
             last_updated: Utc::now(),
@@ -38,6 +65,199 @@ impl Default for BlockchainState {
     }
 }
 
+impl BlockchainState {
+    /// Request with the given ID
+    pub fn request(&self, request_id: u128) -> Option<&OnChainRequest> {
+        self.requests.get(&request_id)
+    }
+    
+    /// Every request, in no particular order
+    pub fn requests(&self) -> impl Iterator<Item = &OnChainRequest> {
+        self.requests.values()
+    }
+    
+    /// A wallet's requests, oldest first
+    pub fn requests_by_wallet(&self, wallet_address: &str) -> impl Iterator<Item = &OnChainRequest> {
+        self.indexed(self.requests_by_wallet.get(wallet_address))
+    }
+    
+    /// Requests of a type, oldest first
+    pub fn requests_by_type(&self, request_type: &RequestType) -> impl Iterator<Item = &OnChainRequest> {
+        self.indexed(self.requests_by_type.get(request_type))
+    }
+    
+    fn indexed<'a>(&'a self, ids: Option<&'a BTreeSet<u128>>) -> impl Iterator<Item = &'a OnChainRequest> {
+        ids.into_iter().flatten().filter_map(|id| self.requests.get(id))
+    }
+    
+    /// Number of requests waiting to be processed
+    pub fn active_requests_count(&self) -> usize {
+        self.requests.len() - self.processed_count
+    }
+    
+    /// Number of processed requests
+    pub fn processed_requests_count(&self) -> usize {
+        self.processed_count
+    }
+    
+    /// Adds a request, or replaces the one with the same ID
+    pub fn upsert_request(&mut self, request: OnChainRequest) {
+        if let Some(previous) = self.requests.remove(&request.id) {
+            self.unindex(&previous);
+        }
+        
+        self.requests_by_wallet.entry(request.wallet_address.clone()).or_default().insert(request.id);
+        self.requests_by_type.entry(request.request_type.clone()).or_default().insert(request.id);
+        if !request.status.is_pending() {
+            self.processed_count += 1;
+        }
+        self.requests.insert(request.id, request);
+    }
+    
+    fn unindex(&mut self, request: &OnChainRequest) {
+        if let Some(ids) = self.requests_by_wallet.get_mut(&request.wallet_address) {
+            ids.remove(&request.id);
+            if ids.is_empty() {
+                self.requests_by_wallet.remove(&request.wallet_address);
+            }
+        }
+        if let Some(ids) = self.requests_by_type.get_mut(&request.request_type) {
+            ids.remove(&request.id);
+        }
+        if !request.status.is_pending() {
+            self.processed_count -= 1;
+        }
+    }
+    
+    /// Moves a request to `status`. Returns whether it was known and moved; moves its lifecycle
+    /// doesn't allow are logged and ignored.
+    pub fn advance(&mut self, request_id: u128, status: RequestStatus) -> bool {
+        let Some(request) = self.requests.get_mut(&request_id) else {
+            return false;
+        };
+        
+        match request.status.change_to(status) {
+            Ok(StatusChange::Moved(from)) => {
+                request.status = status;
+                if from.is_pending() && !status.is_pending() {
+                    self.processed_count += 1;
+                }
+                true
+            },
+            Ok(StatusChange::Unchanged) => false,
+            Err(illegal) => {
+                error!("Refused status change of request {}: {}", request_id, illegal);
+                false
+            },
+        }
+    }
+    
+    /// Applies an indexed contract event to the state. Returns whether the state changed.
+    pub fn apply(&mut self, event: &BlockchainEvent) -> bool {
+        let data = &event.data;
+        let text = |field: &str| data.get(field).and_then(Value::as_str);
+        let request_id = || text("request_id").and_then(|id| id.parse::<u128>().ok());
+        let wallet_address = text("wallet_address");
+        
+        let changed = match event.event_type.as_str() {
+            "DepositRequested" | "WithdrawalRequested" | "BorrowRequested" => {
+                let request_type = match event.event_type.as_str() {
+                    "DepositRequested" => RequestType::Deposit,
+                    "WithdrawalRequested" => RequestType::Withdrawal,
+                    _ => RequestType::Borrow,
+                };
+                let (Some(id), Some(wallet_address), Some(amount)) = (request_id(), wallet_address, text("amount")) else {
+                    return false;
+                };
+                
+                self.upsert_request(OnChainRequest {
+                    id,
+                    request_type,
+                    wallet_address: wallet_address.to_string(),
+                    amount: amount.to_string(),
+                    collateral_amount: text("collateral").map(str::to_string),
+                    timestamp: event.timestamp,
+                    // Re-indexing a request, e.g. in a backfill, keeps what later events did to it
+                    status: self.request(id).map_or(RequestStatus::Confirmed, |request| request.status),
+                    block_number: event.block_number,
+                    transaction_hash: event.transaction_hash.clone(),
+                });
+                true
+            },
+            "RequestProcessed" => request_id().is_some_and(|id| self.advance(id, RequestStatus::Processed)),
+            "WithdrawalExecuted" => request_id().is_some_and(|id| self.advance(id, RequestStatus::Executed)),
+            "UserRegistered" => match wallet_address {
+                Some(wallet_address) => {
+                    self.user_mut(wallet_address).is_registered = true;
+                    true
+                },
+                None => false,
+            },
+            "KycStatusUpdated" => match (wallet_address, data.get("approved").and_then(Value::as_bool)) {
+                (Some(wallet_address), Some(approved)) => {
+                    self.user_mut(wallet_address).is_kyc_approved = approved;
+                    true
+                },
+                _ => false,
+            },
+            "EpochClosed" => {
+                let timestamp = |field: &str| {
+                    data.get(field).and_then(Value::as_i64).and_then(DateTime::<Utc>::from_timestamp_millis)
+                };
+                let count = |field: &str| {
+                    data.get(field).and_then(Value::as_u64).and_then(|count| u32::try_from(count).ok()).unwrap_or(0)
+                };
+                let (Some(epoch_id), Some(start_timestamp), Some(end_timestamp)) = (
+                    data.get("epoch_id").and_then(Value::as_u64).map(u128::from),
+                    timestamp("start_timestamp"),
+                    timestamp("end_timestamp"),
+                ) else {
+                    return false;
+                };
+                
+                self.epochs.insert(epoch_id, OnChainEpoch {
+                    id: epoch_id,
+                    start_timestamp,
+                    end_timestamp: Some(end_timestamp),
+                    is_active: false,
+                    processed_deposit_count: count("processed_deposit_count"),
+                    processed_withdrawal_count: count("processed_withdrawal_count"),
+                    processed_borrow_count: count("processed_borrow_count"),
+                });
+                self.current_epoch_id = epoch_id + 1;
+                self.epochs.entry(epoch_id + 1).or_insert(OnChainEpoch {
+                    id: epoch_id + 1,
+                    start_timestamp: end_timestamp,
+                    end_timestamp: None,
+                    is_active: true,
+                    processed_deposit_count: 0,
+                    processed_withdrawal_count: 0,
+                    processed_borrow_count: 0,
+                });
+                true
+            },
+            _ => false,
+        };
+        
+        if changed {
+            self.last_updated = Utc::now();
+        }
+        changed
+    }
+    
+    fn user_mut(&mut self, wallet_address: &str) -> &mut OnChainUser {
+        self.users.entry(wallet_address.to_string()).or_insert_with(|| OnChainUser {
+            wallet_address: wallet_address.to_string(),
+            is_registered: false,
+            is_kyc_approved: false,
+            active_balance: "0".to_string(),
+            pending_deposits: "0".to_string(),
+            pending_withdrawals: "0".to_string(),
+            total_rewards: "0".to_string(),
+        })
+    }
+}
+
 /// Represents an on-chain request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OnChainRequest {
@@ -59,8 +279,8 @@ pub struct OnChainRequest {
     /// Submission timestamp
     pub timestamp: DateTime<Utc>,
     
-    /// Whether the request has been processed
-    pub is_processed: bool,
+    /// Where the request is in its lifecycle
+    pub status: RequestStatus,
     
     /// Block number when the request was submitted
     pub block_number: u64,
@@ -108,6 +328,34 @@ pub struct OnChainEpoch {
     
     /// Whether the epoch is active
     pub is_active: bool,
+    
+    /// Deposits the contract processed in the epoch
+    pub processed_deposit_count: u32,
+    
+    /// Withdrawals the contract processed in the epoch
+    pub processed_withdrawal_count: u32,
+    
+    /// Borrows the contract processed in the epoch
+    pub processed_borrow_count: u32,
+}
+
+impl From<&OnChainEpoch> for EpochSummary {
+    /// The epoch as the contract reports it; a closed epoch is completed on-chain
+    fn from(epoch: &OnChainEpoch) -> Self {
+        EpochSummary {
+            id: epoch.id,
+            status: if epoch.is_active { EpochStatus::Active } else { EpochStatus::Completed },
+            start_timestamp: epoch.start_timestamp,
+            end_timestamp: epoch.end_timestamp,
+            duration_secs: epoch.end_timestamp.map(|end| (end - epoch.start_timestamp).num_seconds()),
+            processed_deposit_count: epoch.processed_deposit_count,
+            processed_withdrawal_count: epoch.processed_withdrawal_count,
+            processed_borrow_count: epoch.processed_borrow_count,
+            processing_tx_hash: None,
+            processed_at: None,
+            progress_percent: None,
+        }
+    }
 }
 
 /// Interface for blockchain state operations
@@ -130,21 +378,16 @@ impl BlockchainStateManager {
     pub async fn get_request(&self, request_id: u128) -> ApiResult<OnChainRequest> {
         let state = self.state.read().await;
         
-        state.requests.get(&request_id)
+        state.request(request_id)
             .cloned()
             .ok_or_else(|| ApiError::NotFound(format!("Request with ID {} not found", request_id)))
     }
     
-    /// Get all requests for a wallet address
-    pub async fn get_requests_by_wallet(&self, wallet_address: &str) -> ApiResult<Vec<OnChainRequest>> {
+    /// All requests for a wallet address, as a JSON array serialized straight from the state
+    pub async fn requests_by_wallet_json(&self, wallet_address: &str) -> Response {
         let state = self.state.read().await;
         
-        let wallet_requests = state.requests.values()
-            .filter(|r| r.wallet_address == wallet_address)
-            .cloned()
-            .collect::<Vec<_>>();
-            
-        Ok(wallet_requests)
+        requests_json(state.requests_by_wallet(wallet_address))
     }
     
     /// Get user by wallet address
@@ -167,21 +410,16 @@ impl BlockchainStateManager {
     
     /// Get current epoch
     pub async fn get_current_epoch(&self) -> ApiResult<OnChainEpoch> {
-        let state = self.state.read().await;
+        let current_epoch_id = self.state.read().await.current_epoch_id;
         
-        self.get_epoch(state.current_epoch_id).await
+        self.get_epoch(current_epoch_id).await
     }
     
-    /// Get requests of a specific type
-    pub async fn get_requests_by_type(&self, request_type: RequestType) -> ApiResult<Vec<OnChainRequest>> {
+    /// Requests of a specific type, as a JSON array serialized straight from the state
+    pub async fn requests_by_type_json(&self, request_type: RequestType) -> Response {
         let state = self.state.read().await;
         
-        let filtered_requests = state.requests.values()
-            .filter(|r| r.request_type == request_type)
-            .cloned()
-            .collect::<Vec<_>>();
-            
-        Ok(filtered_requests)
+        requests_json(state.requests_by_type(&request_type))
     }
     
     /// Refresh the blockchain state (would be implemented to communicate with the smart contract)
@@ -195,6 +433,11 @@ impl BlockchainStateManager {
     }
 }
 
+/// Serializes requests while the state is borrowed, so large lists aren't copied to be returned
+fn requests_json<'a>(requests: impl Iterator<Item = &'a OnChainRequest>) -> Response {
+    Json(requests.collect::<Vec<_>>()).into_response()
+}
+
 /// Response containing the current blockchain state summary
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BlockchainStateSummary {
@@ -212,4 +455,161 @@ pub struct BlockchainStateSummary {
     
     /// Last updated timestamp
     pub last_updated: DateTime<Utc>,
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::epoch::Epoch;
+    use serde_json::json;
+
+    fn event(event_type: &str, data: Value) -> BlockchainEvent {
+        BlockchainEvent {
+            event_type: event_type.to_string(),
+            transaction_hash: "0x01".to_string(),
+            block_number: 7,
+            timestamp: Utc::now(),
+            data,
+        }
+    }
+
+    fn requested(event_type: &str, request_id: u128, wallet_address: &str) -> BlockchainEvent {
+        event(event_type, json!({ "request_id": request_id.to_string(), "wallet_address": wallet_address, "amount": "10" }))
+    }
+
+    fn ids<'a>(requests: impl Iterator<Item = &'a OnChainRequest>) -> Vec<u128> {
+        requests.map(|request| request.id).collect()
+    }
+
+    #[test]
+    fn requests_are_indexed_by_wallet_and_type() {
+        let mut state = BlockchainState::default();
+        assert!(state.apply(&requested("DepositRequested", 3, "alice")));
+        assert!(state.apply(&requested("WithdrawalRequested", 1, "alice")));
+        assert!(state.apply(&requested("DepositRequested", 2, "bob")));
+
+        assert_eq!(ids(state.requests_by_wallet("alice")), [1, 3]);
+        assert_eq!(ids(state.requests_by_wallet("carol")), Vec::<u128>::new());
+        assert_eq!(ids(state.requests_by_type(&RequestType::Deposit)), [2, 3]);
+        assert_eq!(state.request(1).unwrap().request_type, RequestType::Withdrawal);
+
+        // A request seen again under another wallet moves between the indexes
+        state.upsert_request(OnChainRequest { wallet_address: "bob".to_string(), ..state.request(3).unwrap().clone() });
+        assert_eq!(ids(state.requests_by_wallet("alice")), [1]);
+        assert_eq!(ids(state.requests_by_wallet("bob")), [2, 3]);
+    }
+
+    #[test]
+    fn borrows_keep_their_collateral() {
+        let mut state = BlockchainState::default();
+        state.apply(&event(
+            "BorrowRequested",
+            json!({ "request_id": "1", "wallet_address": "alice", "amount": "10", "collateral": "15" }),
+        ));
+        state.apply(&requested("DepositRequested", 2, "alice"));
+
+        assert_eq!(state.request(1).unwrap().collateral_amount.as_deref(), Some("15"));
+        assert_eq!(state.request(2).unwrap().collateral_amount, None);
+    }
+
+    #[test]
+    fn processing_events_are_applied_as_deltas() {
+        let mut state = BlockchainState::default();
+        state.apply(&requested("DepositRequested", 1, "alice"));
+        state.apply(&requested("WithdrawalRequested", 2, "alice"));
+        assert_eq!((state.active_requests_count(), state.processed_requests_count()), (2, 0));
+
+        assert!(state.apply(&requested("RequestProcessed", 1, "alice")));
+        assert!(state.apply(&requested("WithdrawalExecuted", 2, "alice")));
+        assert!(!state.apply(&requested("RequestProcessed", 1, "alice")));
+        assert_eq!((state.active_requests_count(), state.processed_requests_count()), (0, 2));
+
+        // Executed requests can't go back to processed
+        assert!(!state.apply(&requested("RequestProcessed", 2, "alice")));
+        assert_eq!(state.request(2).unwrap().status, RequestStatus::Executed);
+
+        // Backfilling the request keeps its status
+        state.apply(&requested("DepositRequested", 1, "alice"));
+        assert_eq!(state.request(1).unwrap().status, RequestStatus::Processed);
+        assert_eq!(state.processed_requests_count(), 2);
+    }
+
+    #[test]
+    fn users_and_epochs_follow_their_events() {
+        let mut state = BlockchainState::default();
+        state.apply(&event("UserRegistered", json!({ "wallet_address": "alice" })));
+        state.apply(&event("KycStatusUpdated", json!({ "wallet_address": "alice", "approved": true })));
+        let alice = &state.users["alice"];
+        assert!(alice.is_registered && alice.is_kyc_approved);
+
+        state.apply(&event(
+            "EpochClosed",
+            json!({ "epoch_id": 1, "start_timestamp": 1_700_000_000_000u64, "end_timestamp": 1_700_600_000_000u64 }),
+        ));
+        assert_eq!(state.current_epoch_id, 2);
+        assert!(!state.epochs[&1].is_active);
+        assert!(state.epochs[&2].is_active);
+        assert_eq!(Some(state.epochs[&2].start_timestamp), state.epochs[&1].end_timestamp);
+
+        assert!(!state.apply(&event("FeeCollected", json!({}))));
+    }
+
+    #[test]
+    fn epoch_summaries_combine_the_contract_and_the_database() {
+        let mut state = BlockchainState::default();
+        state.apply(&event(
+            "EpochClosed",
+            json!({
+                "epoch_id": 1,
+                "start_timestamp": 1_700_000_000_000u64,
+                "end_timestamp": 1_700_600_000_000u64,
+                "processed_deposit_count": 4,
+                "processed_withdrawal_count": 2,
+                "processed_borrow_count": 1,
+            }),
+        ));
+
+        let summary = EpochSummary::from(&state.epochs[&1]);
+        assert_eq!(summary.status, EpochStatus::Completed);
+        assert_eq!(summary.duration_secs, Some(600_000));
+        assert_eq!(
+            (summary.processed_deposit_count, summary.processed_withdrawal_count, summary.processed_borrow_count),
+            (4, 2, 1)
+        );
+        assert_eq!(EpochSummary::from(&state.epochs[&2]).duration_secs, None);
+
+        let record = Epoch {
+            id: 1,
+            start_timestamp: summary.start_timestamp,
+            end_timestamp: summary.end_timestamp,
+            status: EpochStatus::Processing,
+            processed_at: None,
+            processing_tx_hash: Some("0xabc".to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let merged = summary.clone().with_record(&record);
+        assert_eq!(merged.status, EpochStatus::Processing);
+        assert_eq!(merged.processing_tx_hash.as_deref(), Some("0xabc"));
+        assert_eq!(merged.processed_deposit_count, 4);
+        assert_eq!(EpochSummary::from_record(&record, None).duration_secs, summary.duration_secs);
+    }
+
+    #[tokio::test]
+    async fn request_lists_serialize_as_the_requests_would() {
+        use axum::body::HttpBody;
+
+        let mut state = BlockchainState::default();
+        state.apply(&requested("DepositRequested", 2, "alice"));
+        state.apply(&requested("WithdrawalRequested", 1, "alice"));
+        state.apply(&requested("DepositRequested", 3, "bob"));
+        let owned = state.requests_by_wallet("alice").cloned().collect::<Vec<_>>();
+        let manager = BlockchainStateManager::new(Arc::new(RwLock::new(state)));
+
+        let mut body = manager.requests_by_wallet_json("alice").await.into_body();
+        let mut json = Vec::new();
+        while let Some(chunk) = body.data().await {
+            json.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(serde_json::from_slice::<Value>(&json).unwrap(), serde_json::to_value(&owned).unwrap());
+    }
+}
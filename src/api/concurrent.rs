@@ -0,0 +1,77 @@
+//! Running a handler's independent reads at once
+//!
+//! Reads of different types are combined with `tokio::try_join!`; [`join_all`] covers a list of
+//! reads of the same type. Both poll the reads on the handler's own task, so they borrow from the
+//! handler freely, and the database reads among them take a pool connection each.
+
+use std::future::{poll_fn, Future};
+use std::task::Poll;
+
+/// Runs the reads concurrently, returning each one's output in the order given
+pub async fn join_all<F: Future>(reads: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut pending = reads.into_iter().map(|read| Some(Box::pin(read))).collect::<Vec<_>>();
+    let mut outputs = pending.iter().map(|_| None).collect::<Vec<_>>();
+
+    poll_fn(|cx| {
+        let mut finished = true;
+        for (read, output) in pending.iter_mut().zip(outputs.iter_mut()) {
+            if let Some(future) = read {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => {
+                        *output = Some(value);
+                        *read = None;
+                    },
+                    Poll::Pending => finished = false,
+                }
+            }
+        }
+
+        if finished {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+
+    outputs.into_iter().map(|output| output.expect("every read has finished")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn outputs_keep_the_order_of_their_reads() {
+        let delays = [30, 10, 20, 0];
+        let outputs = join_all(delays.iter().map(|&delay| async move {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            delay
+        }))
+        .await;
+
+        assert_eq!(outputs, delays);
+        assert!(join_all(Vec::<std::future::Ready<()>>::new()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reads_run_at_the_same_time() {
+        // Each read waits for the other, so running them one after another never finishes
+        let (first_sender, first_receiver) = oneshot::channel();
+        let (second_sender, second_receiver) = oneshot::channel();
+        let first = async move {
+            first_sender.send(1).unwrap();
+            second_receiver.await.unwrap()
+        };
+        let second = async move {
+            second_sender.send(2).unwrap();
+            first_receiver.await.unwrap()
+        };
+        let reads: Vec<std::pin::Pin<Box<dyn Future<Output = i32> + Send>>> = vec![Box::pin(first), Box::pin(second)];
+
+        let outputs = tokio::time::timeout(Duration::from_secs(1), join_all(reads)).await.unwrap();
+        assert_eq!(outputs, [2, 1]);
+    }
+}
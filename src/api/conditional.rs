@@ -0,0 +1,103 @@
+//! Conditional request (ETag / If-None-Match) support for read endpoints
+
+use anyhow::anyhow;
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::api::error::{ApiError, ApiResult};
+
+/// Computes a weak ETag from a resource's serialized representation
+pub fn etag_for(representation: &[u8]) -> String {
+    let digest = Sha256::digest(representation);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether the request's `If-None-Match` header matches the given ETag
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || weak_eq(candidate, etag))
+}
+
+/// Weak comparison: ignores the `W/` prefix on either side
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// Returns `304 Not Modified` when the client already has the body, otherwise the JSON body
+///
+/// The ETag is taken over the serialized body, so it changes whenever any field of the response
+/// does. Both responses carry it so clients can revalidate on the next request.
+pub fn conditional_json<T: Serialize>(headers: &HeaderMap, body: T) -> ApiResult<Response> {
+    let representation = to_json(&body)?;
+    let etag = etag_for(&representation);
+
+    respond(headers, &etag, representation)
+}
+
+/// Like [`conditional_json`], but with the ETag taken over `validator` rather than the body
+///
+/// For bodies with fields derived from the current time, such as an epoch's progress, which would
+/// otherwise make every response new. `validator` is the body without those fields, plus whatever
+/// they're derived from besides the time.
+pub fn conditional_json_with_validator<V: Serialize, T: Serialize>(
+    headers: &HeaderMap,
+    validator: &V,
+    body: T,
+) -> ApiResult<Response> {
+    let etag = etag_for(&to_json(validator)?);
+
+    respond(headers, &etag, to_json(&body)?)
+}
+
+fn to_json<T: Serialize>(value: &T) -> ApiResult<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|e| ApiError::Internal(anyhow!("Failed to serialize response: {}", e)))
+}
+
+fn respond(headers: &HeaderMap, etag: &str, representation: Vec<u8>) -> ApiResult<Response> {
+    let etag_header = HeaderValue::from_str(etag).expect("ETag is always a valid header value");
+
+    if if_none_match(headers, etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag_header)]).into_response());
+    }
+
+    Ok((
+        [
+            (header::ETAG, etag_header),
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+        ],
+        representation,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bodies_with_the_same_validator_are_not_modified() {
+        let validator = json!({ "id": 3, "status": "active" });
+        let first = conditional_json_with_validator(&HeaderMap::new(), &validator, json!({ "progress_percent": 10.0 }))
+            .unwrap();
+        let etag = first.headers()[header::ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let later = conditional_json_with_validator(&headers, &validator, json!({ "progress_percent": 10.5 })).unwrap();
+        assert_eq!(later.status(), StatusCode::NOT_MODIFIED);
+
+        let closed = json!({ "id": 3, "status": "closed" });
+        let changed = conditional_json_with_validator(&headers, &closed, json!({ "progress_percent": null })).unwrap();
+        assert_eq!(changed.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::blockchain_request::{CorrectRequestPayload, RequestCorrection, RequestCorrectionPreview};
+use crate::services::corrections::{CorrectionError, RequestCorrectionService};
+
+/// Compare a recorded request with the contract's copy
+pub async fn preview_request_correction(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(request_id): Path<i64>,
+) -> ApiResult<Json<RequestCorrectionPreview>> {
+    let preview = corrections(&state).preview(request_id).await.map_err(correction_error)?;
+
+    Ok(Json(preview))
+}
+
+/// Correct a recorded request from the contract's copy
+///
+/// The payload names the fields to correct, which must be exactly those the preview reports,
+/// and the reason recorded in the audit log. Values are never taken from the payload.
+pub async fn correct_request(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(request_id): Path<i64>,
+    Json(payload): Json<CorrectRequestPayload>,
+) -> ApiResult<Json<RequestCorrection>> {
+    let correction = corrections(&state).apply(request_id, &payload).await.map_err(correction_error)?;
+
+    Ok(Json(correction))
+}
+
+fn corrections(state: &AppState) -> RequestCorrectionService {
+    RequestCorrectionService::new(state.db.pg.clone(), state.cache.clone(), state.chain.clone())
+}
+
+fn correction_error(e: anyhow::Error) -> ApiError {
+    match e.downcast_ref::<CorrectionError>() {
+        Some(CorrectionError::NotRecorded(_) | CorrectionError::NotOnChain(_)) => ApiError::NotFound(e.to_string()),
+        Some(CorrectionError::MissingReason | CorrectionError::FieldsMismatch { .. }) => ApiError::Validation(e.to_string()),
+        Some(_) => ApiError::Conflict(e.to_string()),
+        None => ApiError::from(e),
+    }
+}
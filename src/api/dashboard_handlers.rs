@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{Duration, Utc};
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::{BalanceRepository, BlockchainRequestRepository, DbAccess, EpochRepository, KycRepository, RewardRepository, UserRepository};
+use crate::models::dashboard::{ClaimableRewards, DashboardKyc, EpochCountdown, UserDashboard};
+use crate::models::wallet::WalletAddress;
+use crate::services::cache::keys;
+
+/// Get everything a wallet's dashboard shows in one response
+///
+/// Once the user is found, the sections are loaded concurrently from the read pool.
+pub async fn get_dashboard(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Json<UserDashboard>> {
+    let pool = state.db.pool(DbAccess::Read);
+    let user = UserRepository::new(pool.clone()).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let balances = BalanceRepository::new(pool.clone());
+    let requests = BlockchainRequestRepository::new(pool.clone());
+    let rewards = RewardRepository::new(pool.clone());
+    let epochs = EpochRepository::new(pool.clone());
+    let kyc = KycRepository::new(pool);
+    let balance_key = keys::user_balance(user.id);
+    let asset = state.chain.asset();
+
+    let (balance, open_requests, claimable, summary, epoch, parameters, approved_level, latest_verification) = tokio::try_join!(
+        state.cache.get_or_load(&balance_key, || async { balances.get(user.id, &asset.symbol).await }),
+        requests.list_open_by_wallet(&user.wallet_address),
+        rewards.list_claimable(user.id),
+        rewards.get_summary(user.id),
+        epochs.active(),
+        state.parameters.parameters(),
+        kyc.approved_level(user.id),
+        kyc.latest_for_user(user.id),
+    )?;
+
+    let now = Utc::now();
+    let current_epoch = epoch.map(|epoch| {
+        let closes_at = epoch.start_timestamp + Duration::seconds(parameters.epoch_duration_seconds);
+        EpochCountdown {
+            id: epoch.id,
+            status: epoch.status,
+            start_timestamp: epoch.start_timestamp,
+            closes_at,
+            seconds_remaining: (closes_at - now).num_seconds().max(0),
+        }
+    });
+
+    Ok(Json(UserDashboard {
+        balance,
+        open_requests,
+        claimable_rewards: ClaimableRewards {
+            total: summary.total_pending,
+            rewards: claimable,
+        },
+        current_epoch,
+        kyc: DashboardKyc {
+            status: user.kyc_status.clone(),
+            approved_level,
+            latest_verification,
+        },
+        user,
+        generated_at: now,
+    }))
+}
@@ -0,0 +1,27 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::ApiResult;
+use crate::api::AppState;
+use crate::db::{DbAccess, DeploymentRepository};
+use crate::models::deployment::{ContractDeployment, DeploymentFilter};
+
+/// List recorded contract deployments, newest first, optionally by network and status
+pub async fn list_deployments(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<DeploymentFilter>,
+) -> ApiResult<Json<Vec<ContractDeployment>>> {
+    let filter = DeploymentFilter {
+        limit: Some(filter.limit.unwrap_or(100).clamp(1, 1000)),
+        offset: Some(filter.offset.unwrap_or(0).max(0)),
+        ..filter
+    };
+
+    let deployments = DeploymentRepository::new(state.db.pool(DbAccess::Read)).list(&filter).await?;
+
+    Ok(Json(deployments))
+}
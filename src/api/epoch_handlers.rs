@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::epoch::EpochProcessingRun;
+use crate::services::epochs::EpochProcessingError;
+
+/// Start closing an epoch, or resume a close that failed. Progress is reported by
+/// [`get_processing_status`].
+pub async fn process_epoch(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(epoch_id): Path<i32>,
+) -> ApiResult<(StatusCode, Json<EpochProcessingRun>)> {
+    let run = state.epochs.start(epoch_id).await.map_err(|e| match e.downcast_ref::<EpochProcessingError>() {
+        Some(EpochProcessingError::EpochNotFound(_)) => ApiError::NotFound(e.to_string()),
+        Some(_) => ApiError::Validation(e.to_string()),
+        None => ApiError::from(e),
+    })?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::Reconciliation, format!("epoch:{}:process", epoch_id))
+                .with_change(None::<&EpochProcessingRun>, Some(&run)),
+        )
+        .await;
+
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+/// Get the progress of an epoch's close sequence
+pub async fn get_processing_status(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(epoch_id): Path<i32>,
+) -> ApiResult<Json<EpochProcessingRun>> {
+    let run = state.epochs.status(epoch_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Epoch {} has not been processed", epoch_id)))?;
+
+    Ok(Json(run))
+}
+
+/// Refuses new requests while an epoch is being closed
+pub(crate) async fn ensure_accepting_submissions(state: &AppState) -> ApiResult<()> {
+    if state.epochs.submissions_paused().await? {
+        return Err(ApiError::Forbidden {
+            code: "EPOCH_PROCESSING",
+            message: "The current epoch is closing; submit again once the next epoch opens".to_string(),
+        });
+    }
+
+    Ok(())
+}
@@ -1,70 +1,273 @@
+//! Errors returned by the API
+//!
+//! Every error is answered as `{"error": {"code", "message", "status"}}`. Codes are stable,
+//! SCREAMING_SNAKE_CASE and what clients should match on; messages are for people and may change.
+//! Server-side failures keep the error they came from, so the full context chain is logged while
+//! the response only carries its outermost message.
+
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
 
-/// Custom API error types
+/// An error answered by the API
 #[derive(Error, Debug)]
 pub enum ApiError {
-    #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    /// The request is malformed or fails a business rule. `VALIDATION_FAILED`, 400.
+    #[error("Invalid input: {0}")]
+    Validation(String),
+
+    /// Credentials are missing or wrong. `UNAUTHORIZED`, 401.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The caller may not do this. Carries its own code, e.g. `KYC_LIMIT_EXCEEDED`; 403.
+    #[error("Forbidden: {message}")]
+    Forbidden {
+        /// Machine-readable reason, e.g. `KYC_LIMIT_EXCEEDED`
+        code: &'static str,
+        message: String,
+    },
 
+    /// The resource doesn't exist. `NOT_FOUND`, 404.
     #[error("Not found: {0}")]
     NotFound(String),
 
-    #[error("Invalid input: {0}")]
-    InvalidInput(String),
+    /// The request conflicts with the resource's current state. `CONFLICT`, 409.
+    #[error("Conflict: {0}")]
+    Conflict(String),
 
-    #[error("Blockchain error: {0}")]
-    Blockchain(String),
+    /// The caller sent too many requests. `RATE_LIMITED`, 429 with `Retry-After`.
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        /// Seconds before the caller should try again
+        retry_after_secs: u64,
+    },
 
-    #[error("Failed to submit blockchain request")]
-    BlockchainRequestFailed,
+    /// The database failed. `DATABASE_ERROR`, 500; the response doesn't carry the cause.
+    #[error("Database error: {0}")]
+    Database(anyhow::Error),
 
+    /// Anything else that failed on the server. `INTERNAL_ERROR`, 500.
     #[error("Internal server error: {0}")]
-    Internal(String),
+    Internal(anyhow::Error),
 
-    #[error("Internal server error")]
-    InternalServerError,
+    /// The blockchain node failed or refused a call. `BLOCKCHAIN_ERROR`, 502.
+    #[error("Blockchain error: {0}")]
+    Blockchain(anyhow::Error),
 
-    #[error("Unauthorized: {0}")]
-    Unauthorized(String),
+    /// A feature or dependency is unavailable for now. `SERVICE_UNAVAILABLE`, 503.
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    /// A dependency didn't answer in time. `UPSTREAM_TIMEOUT`, 504.
+    #[error("Upstream timeout: {0}")]
+    UpstreamTimeout(String),
+}
+
+impl ApiError {
+    /// Stable code clients match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Validation(_) => "VALIDATION_FAILED",
+            ApiError::Unauthorized(_) => "UNAUTHORIZED",
+            ApiError::Forbidden { code, .. } => code,
+            ApiError::NotFound(_) => "NOT_FOUND",
+            ApiError::Conflict(_) => "CONFLICT",
+            ApiError::RateLimited { .. } => "RATE_LIMITED",
+            ApiError::Database(_) => "DATABASE_ERROR",
+            ApiError::Internal(_) => "INTERNAL_ERROR",
+            ApiError::Blockchain(_) => "BLOCKCHAIN_ERROR",
+            ApiError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
+            ApiError::UpstreamTimeout(_) => "UPSTREAM_TIMEOUT",
+        }
+    }
+
+    /// HTTP status of the response
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Database(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Blockchain(_) => StatusCode::BAD_GATEWAY,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::UpstreamTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// A failed blockchain call, or a timeout when the node didn't answer in time
+    pub fn blockchain(err: anyhow::Error) -> ApiError {
+        match Cause::of(&err) {
+            Cause::Timeout => ApiError::UpstreamTimeout(err.to_string()),
+            _ => ApiError::Blockchain(err),
+        }
+    }
+
+    /// Message of the response
+    fn message(&self) -> String {
+        match self {
+            ApiError::Validation(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::Forbidden { message, .. }
+            | ApiError::NotFound(message)
+            | ApiError::Conflict(message)
+            | ApiError::RateLimited { message, .. }
+            | ApiError::ServiceUnavailable(message)
+            | ApiError::UpstreamTimeout(message) => message.clone(),
+            ApiError::Database(_) => "Database error".to_string(),
+            ApiError::Internal(err) | ApiError::Blockchain(err) => err.to_string(),
+        }
+    }
+}
+
+/// What an error chain says went wrong, as far as the response is concerned
+enum Cause<'a> {
+    Timeout,
+    Database(&'a sqlx::Error),
+    Blockchain,
+    Other,
+}
+
+impl<'a> Cause<'a> {
+    /// The first cause in the chain that decides the response
+    fn of(err: &'a anyhow::Error) -> Cause<'a> {
+        for cause in err.chain() {
+            if cause.is::<tokio::time::error::Elapsed>()
+                || cause.downcast_ref::<reqwest::Error>().is_some_and(reqwest::Error::is_timeout)
+            {
+                return Cause::Timeout;
+            }
+            if let Some(db) = cause.downcast_ref::<sqlx::Error>() {
+                return Cause::Database(db);
+            }
+            if cause.is::<subxt::Error>() {
+                return Cause::Blockchain;
+            }
+        }
+        Cause::Other
+    }
 }
 
 /// Implementation to convert API errors into HTTP responses
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            ApiError::Database(ref err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-            ApiError::NotFound(ref message) => (StatusCode::NOT_FOUND, message.clone()),
-            ApiError::InvalidInput(ref message) => (StatusCode::BAD_REQUEST, message.clone()),
-            ApiError::Blockchain(ref message) => (StatusCode::BAD_GATEWAY, message.clone()),
-            ApiError::BlockchainRequestFailed => (StatusCode::BAD_GATEWAY, "Failed to submit blockchain request".to_string()),
-            ApiError::Internal(ref message) => (StatusCode::INTERNAL_SERVER_ERROR, message.clone()),
-            ApiError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string()),
-            ApiError::Unauthorized(ref message) => (StatusCode::UNAUTHORIZED, message.clone()),
-        };
-
-        let body = Json(json!({
+        let status = self.status();
+        if status.is_server_error() {
+            match &self {
+                ApiError::Database(err) | ApiError::Internal(err) | ApiError::Blockchain(err) => {
+                    tracing::error!(code = self.code(), "{:#}", err);
+                },
+                _ => tracing::warn!(code = self.code(), "{}", self),
+            }
+        }
+
+        let body = json!({
             "error": {
-                "message": error_message,
+                "code": self.code(),
+                "message": self.message(),
                 "status": status.as_u16()
             }
-        }));
+        });
 
-        (status, body).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let ApiError::RateLimited { retry_after_secs, .. } = self {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
     }
 }
 
-/// For convenience, implement From for anyhow::Error
+/// Classifies an error by the first cause in its chain that decides the response, keeping the
+/// whole chain: timeouts become [`UpstreamTimeout`](ApiError::UpstreamTimeout), database and
+/// node failures [`Database`](ApiError::Database) and [`Blockchain`](ApiError::Blockchain), and
+/// anything else [`Internal`](ApiError::Internal)
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
-        ApiError::Internal(err.to_string())
+        match Cause::of(&err) {
+            Cause::Timeout => ApiError::UpstreamTimeout(err.to_string()),
+            Cause::Database(sqlx::Error::RowNotFound) => ApiError::NotFound("Record not found".to_string()),
+            Cause::Database(sqlx::Error::Database(db)) if db.is_unique_violation() => {
+                ApiError::Conflict("Record already exists".to_string())
+            },
+            Cause::Database(sqlx::Error::PoolTimedOut) => {
+                ApiError::UpstreamTimeout("Timed out waiting for a database connection".to_string())
+            },
+            Cause::Database(_) => ApiError::Database(err),
+            Cause::Blockchain => ApiError::Blockchain(err),
+            Cause::Other => ApiError::Internal(err),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        anyhow::Error::from(err).into()
+    }
+}
+
+impl From<subxt::Error> for ApiError {
+    fn from(err: subxt::Error) -> Self {
+        anyhow::Error::from(err).into()
     }
 }
 
 /// Result type for API handlers
-pub type ApiResult<T> = Result<T, ApiError>; 
\ No newline at end of file
+pub type ApiResult<T> = Result<T, ApiError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    async fn respond(error: ApiError) -> (StatusCode, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn errors_are_answered_with_their_code_and_outermost_message() {
+        let (status, body) = respond(ApiError::Validation("Amount must be positive".to_string())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "VALIDATION_FAILED");
+        assert_eq!(body["error"]["message"], "Amount must be positive");
+        assert_eq!(body["error"]["status"], 400);
+
+        let failed = Err::<(), _>(anyhow::anyhow!("connection reset")).context("Failed to load prices").unwrap_err();
+        let (status, body) = respond(failed.into()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"]["code"], "INTERNAL_ERROR");
+        assert_eq!(body["error"]["message"], "Failed to load prices");
+
+        let limited = ApiError::RateLimited { message: "Slow down".to_string(), retry_after_secs: 30 }.into_response();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers()[header::RETRY_AFTER], "30");
+    }
+
+    #[test]
+    fn errors_are_classified_by_their_cause() {
+        let missing = Err::<(), _>(sqlx::Error::RowNotFound).context("Failed to get user").unwrap_err();
+        assert!(matches!(ApiError::from(missing), ApiError::NotFound(_)));
+
+        let exhausted = Err::<(), _>(sqlx::Error::PoolTimedOut).context("Failed to list users").unwrap_err();
+        assert_eq!(ApiError::from(exhausted).code(), "UPSTREAM_TIMEOUT");
+
+        let closed = Err::<(), _>(sqlx::Error::PoolClosed).context("Failed to list users").unwrap_err();
+        let error = ApiError::from(closed);
+        assert_eq!(error.code(), "DATABASE_ERROR");
+        let ApiError::Database(err) = &error else { unreachable!() };
+        assert_eq!(format!("{:#}", err), format!("Failed to list users: {}", sqlx::Error::PoolClosed));
+
+        let node = anyhow::Error::from(subxt::Error::Other("node is syncing".to_string())).context("Failed to read epoch");
+        assert_eq!(ApiError::from(node).code(), "BLOCKCHAIN_ERROR");
+    }
+}
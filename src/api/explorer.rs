@@ -0,0 +1,228 @@
+//! Block explorer deep links for transaction hashes and block numbers in API responses
+//!
+//! Responses aren't built with links by each handler. Instead, [`add_explorer_links`] rewrites
+//! every JSON response: any object with a `transaction_hash`, `*_tx_hash`,
+//! `*_transaction_hash` or `block_number` field gets an `explorer` object next to it, keyed by
+//! field name.
+
+use axum::{
+    body::{self, BoxBody, Full, HttpBody},
+    extract::State,
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::config::ExplorerConfig;
+
+/// Key the links are added under
+const EXPLORER_KEY: &str = "explorer";
+
+/// Explorer pages for a single transaction or block
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExplorerUrls {
+    /// `None` when Subscan doesn't index the network
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscan: Option<String>,
+    /// polkadot.js apps can only look up blocks, so this is `None` for transactions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polkadot_js: Option<String>,
+}
+
+/// Builds explorer URLs for the configured network
+#[derive(Debug, Clone)]
+pub struct ExplorerLinks {
+    subscan_url: Option<String>,
+    rpc_url: String,
+}
+
+impl ExplorerLinks {
+    pub fn new(config: &ExplorerConfig) -> Self {
+        Self {
+            subscan_url: config.subscan_url.as_ref().map(|url| url.trim_end_matches('/').to_string()),
+            rpc_url: config.rpc_url.clone(),
+        }
+    }
+
+    /// Links for an extrinsic hash
+    pub fn transaction(&self, hash: &str) -> ExplorerUrls {
+        ExplorerUrls {
+            subscan: self.subscan_url.as_ref().map(|base| format!("{}/extrinsic/{}", base, hash)),
+            polkadot_js: None,
+        }
+    }
+
+    /// Links for a block number
+    pub fn block(&self, number: u64) -> ExplorerUrls {
+        ExplorerUrls {
+            subscan: self.subscan_url.as_ref().map(|base| format!("{}/block/{}", base, number)),
+            polkadot_js: Some(self.polkadot_js(&format!("/explorer/query/{}", number))),
+        }
+    }
+
+    /// polkadot.js apps URL connected to the configured RPC node
+    fn polkadot_js(&self, route: &str) -> String {
+        let mut url = Url::parse("https://polkadot.js.org/apps/").expect("polkadot.js apps URL is valid");
+        url.query_pairs_mut().append_pair("rpc", &self.rpc_url);
+        url.set_fragment(Some(route));
+        url.to_string()
+    }
+
+    /// Adds links to every object in `value` that has a transaction hash or block number
+    ///
+    /// Returns whether anything was added.
+    pub fn annotate(&self, value: &mut Value) -> bool {
+        match value {
+            Value::Array(items) => items.iter_mut().fold(false, |added, item| self.annotate(item) || added),
+            Value::Object(object) => {
+                let nested = object.values_mut().fold(false, |added, item| self.annotate(item) || added);
+                self.annotate_object(object) || nested
+            },
+            _ => false,
+        }
+    }
+
+    fn annotate_object(&self, object: &mut Map<String, Value>) -> bool {
+        if object.contains_key(EXPLORER_KEY) {
+            return false;
+        }
+
+        let links: Map<String, Value> = object
+            .iter()
+            .filter_map(|(key, value)| {
+                let urls = match value {
+                    Value::String(hash) if is_transaction_field(key) && !hash.is_empty() => self.transaction(hash),
+                    Value::Number(number) if key == "block_number" => self.block(number.as_u64()?),
+                    _ => return None,
+                };
+                if urls.subscan.is_none() && urls.polkadot_js.is_none() {
+                    return None;
+                }
+                Some((key.clone(), serde_json::to_value(urls).ok()?))
+            })
+            .collect();
+
+        if links.is_empty() {
+            return false;
+        }
+
+        object.insert(EXPLORER_KEY.to_string(), Value::Object(links));
+        true
+    }
+}
+
+fn is_transaction_field(key: &str) -> bool {
+    key == "transaction_hash" || key.ends_with("_tx_hash") || key.ends_with("_transaction_hash")
+}
+
+/// Middleware adding explorer links to successful JSON responses
+///
+/// Other responses, including event streams and CSV exports, pass through untouched.
+pub async fn add_explorer_links<B>(
+    State(links): State<ExplorerLinks>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.starts_with("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response;
+    }
+
+    let (mut parts, mut body) = response.into_parts();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        match chunk {
+            Ok(chunk) => bytes.extend_from_slice(&chunk),
+            Err(err) => {
+                tracing::warn!("Failed to read response body for explorer links: {}", err);
+                return Response::from_parts(parts, boxed_bytes(bytes));
+            },
+        }
+    }
+
+    let bytes = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(mut value) => {
+            if links.annotate(&mut value) {
+                let annotated = serde_json::to_vec(&value).unwrap_or(bytes);
+                parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(annotated.len()));
+                annotated
+            } else {
+                bytes
+            }
+        },
+        Err(_) => bytes,
+    };
+
+    Response::from_parts(parts, boxed_bytes(bytes))
+}
+
+fn boxed_bytes(bytes: Vec<u8>) -> BoxBody {
+    body::boxed(Full::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChainNetwork;
+    use serde_json::json;
+
+    fn links(network: ChainNetwork, subscan_url: Option<&str>) -> ExplorerLinks {
+        ExplorerLinks::new(&ExplorerConfig {
+            network,
+            subscan_url: subscan_url.map(str::to_string),
+            rpc_url: "wss://rpc.polkadot.io".to_string(),
+        })
+    }
+
+    #[test]
+    fn builds_network_urls() {
+        let links = links(ChainNetwork::Polkadot, Some("https://polkadot.subscan.io/"));
+
+        assert_eq!(
+            links.transaction("0xabc").subscan.as_deref(),
+            Some("https://polkadot.subscan.io/extrinsic/0xabc")
+        );
+        let block = links.block(42);
+        assert_eq!(block.subscan.as_deref(), Some("https://polkadot.subscan.io/block/42"));
+        assert_eq!(
+            block.polkadot_js.as_deref(),
+            Some("https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc.polkadot.io#/explorer/query/42")
+        );
+    }
+
+    #[test]
+    fn annotates_nested_objects() {
+        let links = links(ChainNetwork::Local, None);
+        let mut value = json!({
+            "requests": [{ "transaction_hash": "0xabc", "block_number": 7 }],
+            "epoch": { "close_tx_hash": null, "processing_tx_hash": "0xdef" },
+            "user": { "wallet_address": "5Grw" },
+        });
+
+        assert!(links.annotate(&mut value));
+        // No explorer indexes local transactions
+        assert!(value["requests"][0]["explorer"].get("transaction_hash").is_none());
+        assert!(value["requests"][0]["explorer"]["block_number"]["polkadot_js"].is_string());
+        assert!(value["epoch"]["explorer"].get("close_tx_hash").is_none());
+        assert!(value["epoch"]["explorer"].get("processing_tx_hash").is_some());
+        assert!(value["user"].get("explorer").is_none());
+    }
+
+    #[test]
+    fn leaves_existing_explorer_fields_alone() {
+        let links = links(ChainNetwork::Polkadot, Some("https://polkadot.subscan.io"));
+        let mut value = json!({ "transaction_hash": "0xabc", "explorer": "custom" });
+
+        assert!(!links.annotate(&mut value));
+        assert_eq!(value["explorer"], "custom");
+    }
+}
@@ -0,0 +1,85 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use std::collections::BTreeMap;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::config::Environment;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::feature_flag::{
+    FeatureFlag, FeatureFlagFilter, FeatureFlagQuery, FeatureFlagState, UpdateFeatureFlagRequest,
+};
+
+/// Get whether each flag is on in this environment, for a wallet when one is given
+pub async fn get_feature_flags(
+    State(state): State<AppState>,
+    Query(query): Query<FeatureFlagQuery>,
+) -> ApiResult<Json<BTreeMap<&'static str, bool>>> {
+    let flags = state.flags.flags().await?;
+
+    let enabled = FeatureFlag::ALL
+        .into_iter()
+        .map(|flag| {
+            let enabled = match &query.wallet {
+                Some(wallet) => flags.is_enabled_for(flag, wallet),
+                None => flags.is_enabled(flag),
+            };
+            (flag.name(), enabled)
+        })
+        .collect();
+
+    Ok(Json(enabled))
+}
+
+/// List every flag's state in an environment
+pub async fn list_feature_flags(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<FeatureFlagFilter>,
+) -> ApiResult<Json<Vec<FeatureFlagState>>> {
+    let environment = environment_or_current(&state, filter.environment.as_deref())?;
+
+    let flags = state.flags.list(environment).await?;
+
+    Ok(Json(flags))
+}
+
+/// Toggle a flag or change its rollout in an environment; takes effect without a redeploy
+pub async fn update_feature_flag(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateFeatureFlagRequest>,
+) -> ApiResult<Json<FeatureFlagState>> {
+    let flag = name.parse::<FeatureFlag>().map_err(ApiError::NotFound)?;
+    let environment = environment_or_current(&state, payload.environment.as_deref())?;
+
+    if let Some(percentage) = payload.rollout_percentage {
+        if !(0..=100).contains(&percentage) {
+            return Err(ApiError::Validation("rollout_percentage must be between 0 and 100".to_string()));
+        }
+    }
+
+    let before = state.flags.list(environment).await?.into_iter().find(|current| current.name == flag.name());
+    let updated = state.flags.update(flag, environment, &payload).await?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::ParameterChange, format!("feature_flag:{}:{}", flag, environment))
+                .with_change(before.as_ref(), Some(&updated)),
+        )
+        .await;
+
+    Ok(Json(updated))
+}
+
+/// Parses a requested environment, defaulting to the one the server runs in
+fn environment_or_current(state: &AppState, environment: Option<&str>) -> ApiResult<Environment> {
+    match environment {
+        Some(environment) => environment.parse().map_err(|e| ApiError::Validation(format!("{}", e))),
+        None => Ok(state.flags.environment()),
+    }
+}
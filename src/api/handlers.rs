@@ -1,27 +1,75 @@
+use anyhow::{anyhow, Context};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
 use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use std::collections::HashMap;
 
-use crate::api::blockchain::{BlockchainStateManager, BlockchainStateSummary, OnChainRequest, OnChainUser, OnChainEpoch};
-use crate::api::error::ApiResult;
+use crate::api::blockchain::{BlockchainState, BlockchainStateManager, BlockchainStateSummary, OnChainRequest, OnChainUser, OnChainEpoch};
+use crate::db::{AssetRepository, BlockchainRequestRepository, DbAccess, EpochProcessingRepository, EpochRepository};
+use crate::api::concurrent::join_all;
+use crate::api::conditional::{conditional_json, conditional_json_with_validator};
+use crate::services::cache::keys;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::epoch_handlers::ensure_accepting_submissions;
+use crate::api::limits::{enforce_submission_limits, SubmissionLimits, SubmissionLock};
+use crate::api::screening_handlers::screening_error;
+use crate::api::stats_handlers::oracle_error;
+use crate::api::withdrawals::WithdrawalAllowance;
 use crate::api::AppState;
-use crate::models::blockchain_request::RequestType;
-use crate::services::BlockchainService;
+use crate::models::amount::{decimal_string, Amount};
+use crate::models::asset::Asset;
+use crate::models::blockchain_request::{RequestTimeline, RequestType};
+use crate::models::epoch::{EpochFilter, EpochSummary};
+use crate::models::feature_flag::FeatureFlag;
+use crate::models::screening::ScreeningTrigger;
+use crate::models::wallet::WalletAddress;
+use crate::services::epochs::EpochAutoCloseJob;
+use crate::services::scheduler::EpochSchedule;
+use crate::services::screening::ScreeningSubject;
+use crate::services::BatchSubmissionItem;
+use crate::logging::record_wallet;
+
+/// Maximum number of items accepted by the batch submission endpoint
+const MAX_BATCH_ITEMS: usize = 50;
 
 /// Deposit request data
 #[derive(Debug, Deserialize)]
 pub struct DepositRequestData {
-    wallet_address: String,
-    amount: f64,
+    wallet_address: WalletAddress,
+    #[serde(deserialize_with = "decimal_string")]
+    amount: String,
+    /// Symbol of the asset `amount` is in; the contract's asset when omitted
+    #[serde(default)]
+    asset: Option<String>,
 }
 
 /// Withdrawal request data
 #[derive(Debug, Deserialize)]
 pub struct WithdrawalRequestData {
-    wallet_address: String,
-    amount: f64,
+    wallet_address: WalletAddress,
+    #[serde(deserialize_with = "decimal_string")]
+    amount: String,
+    /// Symbol of the asset `amount` is in; the contract's asset when omitted
+    #[serde(default)]
+    asset: Option<String>,
+}
+
+/// Borrow request data
+#[derive(Debug, Deserialize)]
+pub struct BorrowRequestData {
+    wallet_address: WalletAddress,
+    #[serde(deserialize_with = "decimal_string")]
+    amount: String,
+    /// Symbol of the asset `amount` is in; the contract's asset when omitted
+    #[serde(default)]
+    asset: Option<String>,
+    #[serde(deserialize_with = "decimal_string")]
+    collateral_amount: String,
 }
 
 /// Deposit request response
@@ -34,21 +82,160 @@ pub struct DepositRequestResponse {
     transaction_hash: String,
 }
 
-/// Get blockchain state summary
-pub async fn get_blockchain_state_summary(
-    State(state): State<AppState>,
-) -> ApiResult<Json<BlockchainStateSummary>> {
-    let blockchain_state = state.blockchain_state.read().await;
-    
-    let summary = BlockchainStateSummary {
+impl From<OnChainRequest> for DepositRequestResponse {
+    fn from(request: OnChainRequest) -> Self {
+        Self {
+            request_id: request.id,
+            wallet_address: request.wallet_address,
+            amount: request.amount,
+            timestamp: request.timestamp,
+            transaction_hash: request.transaction_hash,
+        }
+    }
+}
+
+/// Single item of a batch submission
+#[derive(Debug, Deserialize)]
+pub struct BatchRequestItem {
+    request_type: RequestType,
+    wallet_address: WalletAddress,
+    #[serde(deserialize_with = "decimal_string")]
+    amount: String,
+    /// Symbol of the asset `amount` is in; the contract's asset when omitted
+    #[serde(default)]
+    asset: Option<String>,
+}
+
+/// Batch submission request data
+#[derive(Debug, Deserialize)]
+pub struct BatchSubmissionData {
+    items: Vec<BatchRequestItem>,
+    /// Submit all valid items as one `utility.batch` extrinsic. It has a single signer, so every
+    /// item must name the same wallet.
+    #[serde(default)]
+    use_utility_batch: bool,
+}
+
+/// Outcome of a single batch item
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchItemStatus {
+    /// Submitted on-chain
+    Submitted,
+    /// Failed validation and was not submitted
+    Rejected,
+    /// Passed validation but the submission failed
+    Failed,
+}
+
+/// Result of a single batch item
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    index: usize,
+    status: BatchItemStatus,
+    request: Option<DepositRequestResponse>,
+    error: Option<String>,
+}
+
+/// Batch submission response
+#[derive(Debug, Serialize)]
+pub struct BatchSubmissionResponse {
+    submitted: usize,
+    failed: usize,
+    results: Vec<BatchItemResult>,
+}
+
+/// The asset a submission is in: the contract's asset unless it names another registered one
+async fn submitted_asset(state: &AppState, symbol: Option<&str>) -> ApiResult<Asset> {
+    let contract_asset = state.chain.asset();
+    let Some(symbol) = symbol.filter(|symbol| *symbol != contract_asset.symbol) else {
+        return Ok(contract_asset);
+    };
+
+    AssetRepository::new(state.db.pool(DbAccess::Read))
+        .get(symbol)
+        .await?
+        .ok_or_else(|| ApiError::Validation(format!("Asset {} is not registered", symbol)))
+}
+
+/// Reads an amount submitted in tokens of an asset, refusing zero
+fn submitted_amount(field: &str, value: &str, asset: &Asset) -> ApiResult<Amount> {
+    let amount = asset.parse_amount(value).map_err(|err| ApiError::Validation(format!("{} {}", field, err)))?;
+    if amount.is_zero() {
+        return Err(ApiError::Validation(format!("{} must be a positive number", field)));
+    }
+    Ok(amount)
+}
+
+/// Validates a single batch item, returning its asset and amount or the reason it was rejected
+async fn validate_batch_item(state: &AppState, item: &BatchRequestItem) -> Result<(Asset, Amount), String> {
+    if item.request_type == RequestType::Borrow {
+        return Err("Borrow requests cannot be submitted in a batch".to_string());
+    }
+
+    let asset = submitted_asset(state, item.asset.as_deref()).await.map_err(|err| err.to_string())?;
+    let amount = submitted_amount("Amount", &item.amount, &asset).map_err(|err| err.to_string())?;
+    Ok((asset, amount))
+}
+
+/// Refuses items from blocked wallets and screens withdrawals, returning the reason an item
+/// was rejected
+async fn screen_batch_item(state: &AppState, item: &BatchRequestItem) -> Result<(), String> {
+    let result = match item.request_type {
+        RequestType::Withdrawal => {
+            state.screening
+                .check(&ScreeningSubject::wallet(&item.wallet_address), ScreeningTrigger::Withdrawal)
+                .await
+        },
+        _ => state.screening.ensure_not_blocked(&item.wallet_address).await,
+    };
+
+    result.map_err(|err| screening_error(err).to_string())
+}
+
+/// Builds the summary of a blockchain state snapshot
+fn build_summary(blockchain_state: &BlockchainState) -> BlockchainStateSummary {
+    BlockchainStateSummary {
         current_epoch_id: blockchain_state.current_epoch_id,
-        active_requests_count: blockchain_state.requests.values().filter(|r| !r.is_processed).count(),
-        processed_requests_count: blockchain_state.requests.values().filter(|r| r.is_processed).count(),
+        active_requests_count: blockchain_state.active_requests_count(),
+        processed_requests_count: blockchain_state.processed_requests_count(),
         registered_users_count: blockchain_state.users.len(),
         last_updated: blockchain_state.last_updated,
+    }
+}
+
+/// Summary of an on-chain epoch, with the processing details of its database record if it has one
+async fn epoch_summary(state: &AppState, epoch: &OnChainEpoch) -> ApiResult<EpochSummary> {
+    let summary = EpochSummary::from(epoch);
+    let Ok(id) = i32::try_from(epoch.id) else {
+        return Ok(summary);
     };
+
+    Ok(match EpochRepository::new(state.db.pool(DbAccess::Read)).get(id).await? {
+        Some(record) => summary.with_record(&record),
+        None => summary,
+    })
+}
+
+/// The configured epoch duration, which active epochs report their progress against
+async fn epoch_duration(state: &AppState) -> ApiResult<chrono::Duration> {
+    let duration = state.parameters.epoch_duration().await?;
+    chrono::Duration::from_std(duration)
+        .map_err(|e| ApiError::Internal(anyhow!("Invalid epoch duration: {}", e)))
+}
+
+/// Get blockchain state summary
+pub async fn get_blockchain_state_summary(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let summary = state.cache
+        .get_or_load(keys::blockchain_summary(), || async {
+            Ok(build_summary(&*state.blockchain_state.read().await))
+        })
+        .await?;
     
-    Ok(Json(summary))
+    conditional_json(&headers, summary)
 }
 
 /// Get request by ID
@@ -62,21 +249,35 @@ pub async fn get_request_by_id(
     Ok(Json(request))
 }
 
+/// Get the history of a request recorded in the database, oldest first
+pub async fn get_request_timeline(
+    State(state): State<AppState>,
+    Path(request_id): Path<u128>,
+) -> ApiResult<Json<RequestTimeline>> {
+    let not_found = || ApiError::NotFound(format!("Request with ID {} not found", request_id));
+    let on_chain_id = i64::try_from(request_id).map_err(|_| not_found())?;
+    
+    let requests = BlockchainRequestRepository::new(state.db.pool(DbAccess::Read));
+    let request = requests.get_by_on_chain_id(on_chain_id).await?.ok_or_else(not_found)?;
+    let entries = requests.timeline(&request).await?;
+    
+    Ok(Json(RequestTimeline { request, entries }))
+}
+
 /// Get requests by wallet address
 pub async fn get_requests_by_wallet(
     State(state): State<AppState>,
-    Path(wallet_address): Path<String>,
-) -> ApiResult<Json<Vec<OnChainRequest>>> {
+    Path(wallet_address): Path<WalletAddress>,
+) -> Response {
     let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
-    let requests = blockchain_manager.get_requests_by_wallet(&wallet_address).await?;
     
-    Ok(Json(requests))
+    blockchain_manager.requests_by_wallet_json(&wallet_address).await
 }
 
 /// Get user by wallet address
 pub async fn get_user_by_wallet(
     State(state): State<AppState>,
-    Path(wallet_address): Path<String>,
+    Path(wallet_address): Path<WalletAddress>,
 ) -> ApiResult<Json<OnChainUser>> {
     let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
     let user = blockchain_manager.get_user(&wallet_address).await?;
@@ -88,51 +289,101 @@ pub async fn get_user_by_wallet(
 pub async fn get_epoch_by_id(
     State(state): State<AppState>,
     Path(epoch_id): Path<u128>,
-) -> ApiResult<Json<OnChainEpoch>> {
-    let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
-    let epoch = blockchain_manager.get_epoch(epoch_id).await?;
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let blockchain_manager = BlockchainStateManager::new(state.blockchain_state.clone());
+    let epoch = epoch_summary(&state, &blockchain_manager.get_epoch(epoch_id).await?).await?;
     
-    Ok(Json(epoch))
+    conditional_json(&headers, epoch)
 }
 
 /// Get current epoch
+///
+/// Its progress moves on with time, so it's left out of the ETag; clients revalidate against
+/// the rest of the epoch and the configured duration.
 pub async fn get_current_epoch(
     State(state): State<AppState>,
-) -> ApiResult<Json<OnChainEpoch>> {
-    let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
-    let epoch = blockchain_manager.get_current_epoch().await?;
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let blockchain_manager = BlockchainStateManager::new(state.blockchain_state.clone());
+    let epoch = epoch_summary(&state, &blockchain_manager.get_current_epoch().await?).await?;
+    let epoch_duration = epoch_duration(&state).await?;
+    let validator = (epoch.clone(), epoch_duration.num_seconds());
+    
+    conditional_json_with_validator(&headers, &validator, epoch.with_progress(epoch_duration, chrono::Utc::now()))
+}
+
+/// Get the active epoch's schedule: its estimated end, when processing is expected to start and
+/// until when submissions are still included in it
+pub async fn get_epoch_schedule(State(state): State<AppState>) -> ApiResult<Json<EpochSchedule>> {
+    let epochs = EpochRepository::new(state.db.pool(DbAccess::Read));
+    let (epoch, epoch_duration, submissions_paused) = tokio::try_join!(
+        async { Ok::<_, ApiError>(epochs.active().await?) },
+        epoch_duration(&state),
+        async { Ok::<_, ApiError>(state.epochs.submissions_paused().await?) },
+    )?;
+    let auto_close = state.scheduler.status(EpochAutoCloseJob::NAME);
     
-    Ok(Json(epoch))
+    Ok(Json(EpochSchedule::compute(
+        epoch.as_ref(),
+        epoch_duration,
+        auto_close.as_ref(),
+        submissions_paused,
+        chrono::Utc::now(),
+    )))
+}
+
+/// List the epochs recorded in the database, newest first, optionally only those with a status
+///
+/// Like the current epoch, the ETag leaves out the active epoch's progress.
+pub async fn list_epochs(
+    State(state): State<AppState>,
+    Query(filter): Query<EpochFilter>,
+    headers: HeaderMap,
+) -> ApiResult<Response> {
+    let pool = state.db.pool(DbAccess::Read);
+    let epochs = EpochRepository::new(pool.clone()).list(&filter).await?;
+    let epoch_ids: Vec<i32> = epochs.iter().map(|epoch| epoch.id).collect();
+    let runs = EpochProcessingRepository::new(pool).for_epochs(&epoch_ids).await?;
+    let epoch_duration = epoch_duration(&state).await?;
+    let now = chrono::Utc::now();
+    
+    let summaries: Vec<EpochSummary> = epochs
+        .iter()
+        .map(|epoch| EpochSummary::from_record(epoch, runs.iter().find(|run| run.epoch_id == epoch.id)))
+        .collect();
+    let validator = (&summaries, epoch_duration.num_seconds());
+    let with_progress: Vec<EpochSummary> =
+        summaries.iter().cloned().map(|summary| summary.with_progress(epoch_duration, now)).collect();
+    
+    conditional_json_with_validator(&headers, &validator, with_progress)
 }
 
 /// Get deposit requests
 pub async fn get_deposit_requests(
     State(state): State<AppState>,
-) -> ApiResult<Json<Vec<OnChainRequest>>> {
+) -> Response {
     let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
-    let requests = blockchain_manager.get_requests_by_type(RequestType::Deposit).await?;
     
-    Ok(Json(requests))
+    blockchain_manager.requests_by_type_json(RequestType::Deposit).await
 }
 
 /// Get withdrawal requests
 pub async fn get_withdrawal_requests(
     State(state): State<AppState>,
-) -> ApiResult<Json<Vec<OnChainRequest>>> {
+) -> Response {
     let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
-    let requests = blockchain_manager.get_requests_by_type(RequestType::Withdrawal).await?;
     
-    Ok(Json(requests))
+    blockchain_manager.requests_by_type_json(RequestType::Withdrawal).await
 }
 
 /// Get borrow requests
 pub async fn get_borrow_requests(
     State(state): State<AppState>,
-) -> ApiResult<Json<Vec<OnChainRequest>>> {
+) -> Response {
     let blockchain_manager = BlockchainStateManager::new(state.blockchain_state);
-    let requests = blockchain_manager.get_requests_by_type(RequestType::Borrow).await?;
     
-    Ok(Json(requests))
+    blockchain_manager.requests_by_type_json(RequestType::Borrow).await
 }
 
 /// Refresh blockchain state
@@ -143,9 +394,10 @@ pub async fn refresh_blockchain_state(
     
     // Refresh the state
     blockchain_manager.refresh_state().await?;
+    state.cache.invalidate(keys::blockchain_summary()).await;
     
     // Return the updated summary
-    get_blockchain_state_summary(State(state)).await
+    Ok(Json(build_summary(&*state.blockchain_state.read().await)))
 }
 
 /// Submit a deposit request
@@ -153,63 +405,229 @@ pub async fn submit_deposit_request(
     State(state): State<AppState>,
     Json(payload): Json<DepositRequestData>,
 ) -> ApiResult<Json<DepositRequestResponse>> {
-    // Create blockchain service
-    let blockchain_service = BlockchainService::new(state.db.clone(), state.blockchain_state.clone())
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to create blockchain service: {}", e);
-            crate::api::error::ApiError::InternalServerError
-        })?;
+    record_wallet(&payload.wallet_address);
+    ensure_accepting_submissions(&state).await?;
+    state.screening.ensure_not_blocked(&payload.wallet_address).await.map_err(screening_error)?;
+    let asset = submitted_asset(&state, payload.asset.as_deref()).await?;
+    let amount = submitted_amount("Amount", &payload.amount, &asset)?;
+    let lock = SubmissionLock::acquire(&state, std::slice::from_ref(&payload.wallet_address)).await?;
+    enforce_submission_limits(&state, &payload.wallet_address, &RequestType::Deposit, &asset.to_decimal(amount)).await?;
     
     // Submit the deposit request
-    let request = blockchain_service.submit_deposit_request(&payload.wallet_address, payload.amount)
+    let request = state.chain.submit_deposit_request(&payload.wallet_address, &asset, amount)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to submit deposit request: {}", e);
-            crate::api::error::ApiError::BlockchainRequestFailed
-        })?;
-    
-    // Create the response
-    let response = DepositRequestResponse {
-        request_id: request.id,
-        wallet_address: request.wallet_address,
-        amount: request.amount.clone(),
-        timestamp: request.timestamp,
-        transaction_hash: request.transaction_hash,
-    };
+        .context("Failed to submit blockchain request")
+        .map_err(ApiError::blockchain)?;
+    lock.release().await;
     
-    Ok(Json(response))
+    Ok(Json(request.into()))
 }
 
 /// Submit a withdrawal request
+///
+/// The amount must fit the wallet's withdrawal allowance, as `GET /users/:wallet/withdrawable`
+/// reports it.
 pub async fn submit_withdrawal_request(
     State(state): State<AppState>,
     Json(payload): Json<WithdrawalRequestData>,
 ) -> ApiResult<Json<DepositRequestResponse>> {
-    // Create blockchain service
-    let blockchain_service = BlockchainService::new(state.db.clone(), state.blockchain_state.clone())
+    record_wallet(&payload.wallet_address);
+    ensure_accepting_submissions(&state).await?;
+    
+    // Screen the wallet before funds can leave the protocol
+    state.screening
+        .check(&ScreeningSubject::wallet(&payload.wallet_address), ScreeningTrigger::Withdrawal)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to create blockchain service: {}", e);
-            crate::api::error::ApiError::InternalServerError
-        })?;
+        .map_err(screening_error)?;
+    let asset = submitted_asset(&state, payload.asset.as_deref()).await?;
+    let amount = submitted_amount("Amount", &payload.amount, &asset)?;
+    let lock = SubmissionLock::acquire(&state, std::slice::from_ref(&payload.wallet_address)).await?;
+    WithdrawalAllowance::load(&state, &payload.wallet_address, &asset).await?.check(amount)?;
     
     // Submit the withdrawal request
-    let request = blockchain_service.submit_withdrawal_request(&payload.wallet_address, payload.amount)
+    let request = state.chain.submit_withdrawal_request(&payload.wallet_address, &asset, amount)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to submit withdrawal request: {}", e);
-            crate::api::error::ApiError::BlockchainRequestFailed
-        })?;
-    
-    // Create the response
-    let response = DepositRequestResponse {
-        request_id: request.id,
-        wallet_address: request.wallet_address,
-        amount: request.amount.clone(),
-        timestamp: request.timestamp,
-        transaction_hash: request.transaction_hash,
-    };
+        .context("Failed to submit blockchain request")
+        .map_err(ApiError::blockchain)?;
+    lock.release().await;
     
-    Ok(Json(response))
-} 
\ No newline at end of file
+    Ok(Json(request.into()))
+}
+
+/// Submit a borrow request
+///
+/// The collateral is valued at the oracle price and must cover the borrowed amount at the
+/// `collateral_ratio_bps` risk parameter.
+pub async fn submit_borrow_request(
+    State(state): State<AppState>,
+    Json(payload): Json<BorrowRequestData>,
+) -> ApiResult<Json<DepositRequestResponse>> {
+    record_wallet(&payload.wallet_address);
+    let asset = submitted_asset(&state, payload.asset.as_deref()).await?;
+    let amount = submitted_amount("Amount", &payload.amount, &asset)?;
+    let collateral_amount = submitted_amount("Collateral amount", &payload.collateral_amount, &asset)?;
+    
+    if !state.flags.is_enabled_for(FeatureFlag::Borrows, &payload.wallet_address).await? {
+        return Err(ApiError::ServiceUnavailable("Borrowing is not available".to_string()));
+    }
+    
+    ensure_accepting_submissions(&state).await?;
+    state.screening.ensure_not_blocked(&payload.wallet_address).await.map_err(screening_error)?;
+    
+    let risk = state.risk.current().await?;
+    
+    // The minimum is kept in on-chain units
+    let min_borrow_amount = risk.min_borrow_amount.parse::<Amount>()
+        .map_err(|e| ApiError::Internal(anyhow!("Invalid min_borrow_amount: {}", e)))?;
+    if amount < min_borrow_amount {
+        return Err(ApiError::Validation(format!(
+            "Amount must be at least {}",
+            asset.format(min_borrow_amount)
+        )));
+    }
+    
+    let collateral_price = state.prices.collateral_price().await.map_err(oracle_error)?;
+    let collateral_ratio_bps = risk.collateral_ratio_bps;
+    let collateral_value = asset.to_decimal(collateral_amount) * &collateral_price;
+    let required_value = asset.to_decimal(amount) * BigDecimal::from(collateral_ratio_bps) / BigDecimal::from(10_000);
+    if collateral_value < required_value {
+        return Err(ApiError::Validation(format!(
+            "Collateral worth {} covers less than the required {} ({}% of the borrowed amount at a collateral price of {})",
+            collateral_value.with_scale(6),
+            required_value.with_scale(6),
+            collateral_ratio_bps as f64 / 100.0,
+            collateral_price,
+        )));
+    }
+    
+    let lock = SubmissionLock::acquire(&state, std::slice::from_ref(&payload.wallet_address)).await?;
+    enforce_submission_limits(&state, &payload.wallet_address, &RequestType::Borrow, &asset.to_decimal(amount)).await?;
+    
+    // Submit the borrow request
+    let request = state.chain
+        .submit_borrow_request(&payload.wallet_address, &asset, amount, collateral_amount)
+        .await
+        .context("Failed to submit blockchain request")
+        .map_err(ApiError::blockchain)?;
+    lock.release().await;
+    
+    Ok(Json(request.into()))
+}
+
+/// Submit several deposit/withdrawal requests in one call
+///
+/// Invalid items are rejected individually while the remaining items are still submitted;
+/// the response carries a result per item and uses 207 when any item did not go through.
+pub async fn submit_batch_requests(
+    State(state): State<AppState>,
+    Json(payload): Json<BatchSubmissionData>,
+) -> ApiResult<(StatusCode, Json<BatchSubmissionResponse>)> {
+    if payload.items.is_empty() {
+        return Err(ApiError::Validation("Batch must contain at least one item".to_string()));
+    }
+    
+    if payload.items.len() > MAX_BATCH_ITEMS {
+        return Err(ApiError::Validation(format!("Batch cannot contain more than {} items", MAX_BATCH_ITEMS)));
+    }
+    
+    if payload.use_utility_batch
+        && payload.items.iter().any(|item| item.wallet_address != payload.items[0].wallet_address)
+    {
+        return Err(ApiError::Validation(
+            "A utility batch is signed by one wallet, so every item must name the same wallet".to_string(),
+        ));
+    }
+    
+    ensure_accepting_submissions(&state).await?;
+    
+    // Validate and screen every item up front, screening them all at once
+    let screened = &state;
+    let screenings = join_all(payload.items.iter().map(|item| async move {
+        let submitted = validate_batch_item(screened, item).await?;
+        screen_batch_item(screened, item).await?;
+        Ok::<_, String>(submitted)
+    }))
+    .await;
+    
+    // Only submit the items that pass, and fit their submission limits, read for every wallet at once
+    let mut wallet_addresses: Vec<WalletAddress> = payload.items.iter().map(|item| item.wallet_address.clone()).collect();
+    wallet_addresses.sort();
+    wallet_addresses.dedup();
+    let lock = SubmissionLock::acquire(&state, &wallet_addresses).await?;
+    let limits = SubmissionLimits::load(&state, &wallet_addresses).await?;
+    
+    let mut results: Vec<Option<BatchItemResult>> = Vec::with_capacity(payload.items.len());
+    let mut valid_indices = Vec::new();
+    let mut valid_items = Vec::new();
+    // Tokens accepted so far per wallet and type, counted against submission limits
+    let mut accepted: HashMap<(WalletAddress, RequestType), BigDecimal> = HashMap::new();
+    
+    let use_utility_batch = payload.use_utility_batch;
+    for (index, (item, screening)) in payload.items.into_iter().zip(screenings).enumerate() {
+        let key = (item.wallet_address.clone(), item.request_type.clone());
+        let pending = accepted.get(&key).cloned().unwrap_or_default();
+        
+        let validation = screening.and_then(|(asset, amount)| {
+            let tokens = asset.to_decimal(amount);
+            limits
+                .check(&item.wallet_address, &item.request_type, &tokens, &pending)
+                .map(|()| (asset, amount, &pending + tokens))
+                .map_err(|err| err.to_string())
+        });
+        
+        match validation {
+            Ok((asset, amount, total)) => {
+                accepted.insert(key, total);
+                valid_indices.push(index);
+                valid_items.push(BatchSubmissionItem {
+                    request_type: item.request_type,
+                    wallet_address: item.wallet_address,
+                    asset,
+                    amount,
+                });
+                results.push(None);
+            },
+            Err(reason) => results.push(Some(BatchItemResult {
+                index,
+                status: BatchItemStatus::Rejected,
+                request: None,
+                error: Some(reason),
+            })),
+        }
+    }
+    
+    if !valid_items.is_empty() {
+        let outcomes = state.chain
+            .submit_batch_requests(&valid_items, use_utility_batch)
+            .await;
+        
+        for (index, outcome) in valid_indices.into_iter().zip(outcomes) {
+            results[index] = Some(match outcome {
+                Ok(request) => BatchItemResult {
+                    index,
+                    status: BatchItemStatus::Submitted,
+                    request: Some(request.into()),
+                    error: None,
+                },
+                Err(err) => {
+                    tracing::error!("Failed to submit batch item {}: {}", index, err);
+                    BatchItemResult {
+                        index,
+                        status: BatchItemStatus::Failed,
+                        request: None,
+                        error: Some(err.to_string()),
+                    }
+                },
+            });
+        }
+    }
+    lock.release().await;
+    
+    let results: Vec<BatchItemResult> = results.into_iter().flatten().collect();
+    let submitted = results.iter().filter(|r| r.status == BatchItemStatus::Submitted).count();
+    let failed = results.len() - submitted;
+    
+    let status = if failed == 0 { StatusCode::OK } else { StatusCode::MULTI_STATUS };
+    
+    Ok((status, Json(BatchSubmissionResponse { submitted, failed, results })))
+}
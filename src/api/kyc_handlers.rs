@@ -0,0 +1,271 @@
+use axum::{
+    body::Bytes,
+    extract::{multipart::Field, Multipart, Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::api::auth::{AdminAuth, WalletAuth};
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::screening_handlers::{screen_registration, screening_error};
+use crate::api::AppState;
+use crate::db::UserRepository;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::kyc::{CreateKycVerificationRequest, KycDocument, KycDocumentLink, KycProvider, KycVerification};
+use crate::models::user::{CreateUserRequest, KycStatus};
+use crate::models::wallet::WalletAddress;
+use crate::services::kyc::{sniff_content_type, DocumentUpload, KycDocumentStore, KycError, KycSession};
+
+/// Start (or resume) KYC verification for the authenticated wallet
+pub async fn create_verification(
+    WalletAuth(wallet_address): WalletAuth,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateKycVerificationRequest>,
+) -> ApiResult<(StatusCode, Json<KycSession>)> {
+    let users = UserRepository::new(state.db.pg.clone());
+
+    let user = match users.get_by_wallet(&wallet_address).await? {
+        Some(user) => {
+            // Flagged wallets can't go on to verify
+            state.screening.ensure_not_blocked(&wallet_address).await.map_err(screening_error)?;
+            user
+        },
+        None => {
+            screen_registration(&state, &wallet_address).await?;
+
+            users.create(&CreateUserRequest {
+                wallet_address: wallet_address.clone(),
+                email: payload.email.clone(),
+                referrer_wallet: None,
+            }).await?
+        },
+    };
+
+    if user.kyc_status == KycStatus::Approved {
+        if let Some(latest) = state.kyc.latest_for_user(user.id).await? {
+            if latest.status == KycStatus::Approved && latest.level >= payload.level {
+                return Err(ApiError::Validation(format!(
+                    "Wallet {} is already verified at the {} level",
+                    wallet_address, latest.level
+                )));
+            }
+        }
+    }
+
+    let session = state.kyc.initiate(&user, &payload).await?;
+
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+/// Get one of the authenticated wallet's verifications
+pub async fn get_verification(
+    WalletAuth(wallet_address): WalletAuth,
+    State(state): State<AppState>,
+    Path(verification_id): Path<Uuid>,
+) -> ApiResult<Json<KycVerification>> {
+    Ok(Json(owned_verification(&state, &wallet_address, verification_id).await?))
+}
+
+/// Tell the provider the authenticated wallet has finished the SDK flow
+pub async fn submit_verification(
+    WalletAuth(wallet_address): WalletAuth,
+    State(state): State<AppState>,
+    Path(verification_id): Path<Uuid>,
+) -> ApiResult<Json<KycVerification>> {
+    let verification = owned_verification(&state, &wallet_address, verification_id).await?;
+
+    if verification.status != KycStatus::Pending {
+        return Err(ApiError::Validation(format!(
+            "KYC verification {} has already been reviewed",
+            verification_id
+        )));
+    }
+
+    state.kyc.submit(&verification).await?;
+
+    Ok(Json(verification))
+}
+
+/// Upload an identity document for the authenticated wallet's internal verification
+///
+/// Expects a multipart form with `document_type`, an optional `side` and the `file` itself
+/// (JPEG, PNG or PDF).
+pub async fn upload_document(
+    WalletAuth(wallet_address): WalletAuth,
+    State(state): State<AppState>,
+    Path(verification_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> ApiResult<(StatusCode, Json<KycDocument>)> {
+    let documents = document_store(&state)?;
+    let verification = owned_verification(&state, &wallet_address, verification_id).await?;
+
+    if verification.provider != KycProvider::Internal {
+        return Err(ApiError::Validation(format!(
+            "KYC verification {} is handled by {}; upload documents through its SDK",
+            verification_id, verification.provider
+        )));
+    }
+    if verification.status != KycStatus::Pending {
+        return Err(ApiError::Validation(format!(
+            "KYC verification {} has already been reviewed",
+            verification_id
+        )));
+    }
+
+    let invalid = |e: axum::extract::multipart::MultipartError| ApiError::Validation(format!("Invalid upload: {}", e));
+
+    let mut document_type = None;
+    let mut side = None;
+    let mut file = None;
+    while let Some(field) = multipart.next_field().await.map_err(invalid)? {
+        match field.name() {
+            Some("document_type") => {
+                let value = field.text().await.map_err(invalid)?;
+                document_type = Some(value.parse().map_err(|e: anyhow::Error| ApiError::Validation(e.to_string()))?);
+            },
+            Some("side") => {
+                let value = field.text().await.map_err(invalid)?;
+                side = Some(value.parse().map_err(|e: anyhow::Error| ApiError::Validation(e.to_string()))?);
+            },
+            Some("file") => {
+                let file_name = field.file_name().map(str::to_string);
+                file = Some((file_name, read_limited(field, documents.max_bytes()).await?));
+            },
+            _ => {},
+        }
+    }
+
+    let document_type = document_type
+        .ok_or_else(|| ApiError::Validation("document_type is required".to_string()))?;
+    let (file_name, bytes) = file
+        .ok_or_else(|| ApiError::Validation("file is required".to_string()))?;
+    let content_type = sniff_content_type(&bytes)
+        .ok_or_else(|| ApiError::Validation("Documents must be JPEG, PNG or PDF files".to_string()))?;
+
+    let document = documents.upload(&verification, DocumentUpload {
+        document_type,
+        side,
+        file_name,
+        content_type,
+        bytes,
+    }).await?;
+
+    Ok((StatusCode::CREATED, Json(document)))
+}
+
+/// List the documents uploaded for one of the authenticated wallet's verifications
+pub async fn list_documents(
+    WalletAuth(wallet_address): WalletAuth,
+    State(state): State<AppState>,
+    Path(verification_id): Path<Uuid>,
+) -> ApiResult<Json<Vec<KycDocument>>> {
+    let documents = document_store(&state)?;
+    let verification = owned_verification(&state, &wallet_address, verification_id).await?;
+
+    Ok(Json(documents.list(verification.id).await?))
+}
+
+/// List a verification's documents with time-limited download links for reviewers
+pub async fn review_documents(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(verification_id): Path<Uuid>,
+) -> ApiResult<Json<Vec<KycDocumentLink>>> {
+    let documents = document_store(&state)?;
+
+    state.kyc.get_verification(verification_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("KYC verification {} not found", verification_id)))?;
+
+    let links = documents.links(verification_id).await?;
+    tracing::info!("Issued {} KYC document links for verification {}", links.len(), verification_id);
+
+    Ok(Json(links))
+}
+
+/// Queue a verification whose on-chain KYC sync gave up for another round of attempts
+pub async fn retry_onchain_sync(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(verification_id): Path<Uuid>,
+) -> ApiResult<Json<KycVerification>> {
+    let verification = state.kyc.requeue_onchain_sync(verification_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!(
+            "KYC verification {} not found or its on-chain sync has not failed",
+            verification_id
+        )))?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::Reconciliation, format!("kyc_verification:{}:onchain_sync", verification_id))
+                .with_change(None::<&KycVerification>, Some(&verification)),
+        )
+        .await;
+
+    Ok(Json(verification))
+}
+
+/// Document storage, when configured
+fn document_store(state: &AppState) -> ApiResult<&KycDocumentStore> {
+    state.kyc_documents
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("KYC document storage is not configured".to_string()))
+}
+
+/// Reads an uploaded file, refusing it once it grows past `max_bytes`
+async fn read_limited(mut field: Field<'_>, max_bytes: usize) -> ApiResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = field.chunk().await
+        .map_err(|e| ApiError::Validation(format!("Invalid upload: {}", e)))?
+    {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(ApiError::Validation(format!("Documents can be at most {} bytes", max_bytes)));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+/// Loads a verification belonging to the wallet. Other users' verifications are reported as
+/// missing rather than forbidden.
+async fn owned_verification(state: &AppState, wallet_address: &WalletAddress, verification_id: Uuid) -> ApiResult<KycVerification> {
+    let not_found = || ApiError::NotFound(format!("KYC verification {} not found", verification_id));
+
+    let user = UserRepository::new(state.db.pg.clone())
+        .get_by_wallet(wallet_address)
+        .await?
+        .ok_or_else(not_found)?;
+
+    state.kyc.get_verification(verification_id).await?
+        .filter(|verification| verification.user_id == user.id)
+        .ok_or_else(not_found)
+}
+
+/// Receive a KYC provider webhook
+pub async fn receive_webhook(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<StatusCode> {
+    let provider: KycProvider = provider.parse()
+        .map_err(|_| ApiError::NotFound(format!("Unknown KYC provider {}", provider)))?;
+
+    let service = state.kyc.service(provider)
+        .ok_or_else(|| ApiError::NotFound(format!("KYC provider {} is not configured", provider)))?;
+
+    let payload = state.kyc.authenticate_webhook(service.as_ref(), &headers, &body).await
+        .map_err(|e| match e.downcast_ref::<KycError>() {
+            Some(KycError::WebhookRejected { .. }) => ApiError::Unauthorized(format!("Unverified {} webhook", provider)),
+            // Fetching referenced objects failed; the provider will redeliver
+            Some(_) => ApiError::Internal(e),
+            _ => ApiError::Validation(format!("Invalid {} webhook", provider)),
+        })?;
+
+    // Unknown applicants are acknowledged so the provider doesn't keep retrying them
+    state.kyc.process_webhook(payload).await?;
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,288 @@
+//! Submission limits
+//!
+//! Users may only submit requests once their KYC is approved, and each request type is capped
+//! per epoch by the `kyc_*_epoch_limit` system parameter of the level they are verified at.
+//! Deposits and withdrawals are also capped by the wallet's volume limits, per epoch and per
+//! rolling 24 hours, which default to the `*_volume_limit` system parameters and can be
+//! overridden per user.
+//!
+//! Limits are checked against the requests already stored, so submissions hold a
+//! [`SubmissionLock`] on their wallets from before the limits are read until the request is
+//! stored. Concurrent submissions from a wallet are then checked one after another.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sqlx::types::BigDecimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::{BlockchainRequestRepository, UnitOfWork, UserLimitRepository, UserRepository};
+use crate::models::blockchain_request::RequestType;
+use crate::models::kyc::KycLevel;
+use crate::models::risk::RiskParameters;
+use crate::models::system_parameter::SystemParametersCache;
+use crate::models::user::{KycStatus, User};
+use crate::models::user_limit::{LimitWindow, UserLimitOverrides, VolumeLimits, VolumeUsage};
+use crate::models::wallet::WalletAddress;
+
+/// Request types with volume limits, and the windows they are counted over
+const VOLUME_LIMITED: [(RequestType, LimitWindow); 4] = [
+    (RequestType::Deposit, LimitWindow::Epoch),
+    (RequestType::Deposit, LimitWindow::Day),
+    (RequestType::Withdrawal, LimitWindow::Epoch),
+    (RequestType::Withdrawal, LimitWindow::Day),
+];
+
+/// What submissions from a set of wallets are checked against, read for all of them at once
+pub(crate) struct SubmissionLimits {
+    users: HashMap<WalletAddress, User>,
+    levels: HashMap<Uuid, KycLevel>,
+    /// Amount requested during the epoch, per wallet and request type
+    volumes: HashMap<(WalletAddress, RequestType), BigDecimal>,
+    /// Amount requested in the last 24 hours, per wallet and request type
+    daily_volumes: HashMap<(WalletAddress, RequestType), BigDecimal>,
+    /// Volume limit overrides, per user
+    overrides: HashMap<Uuid, UserLimitOverrides>,
+    parameters: SystemParametersCache,
+    risk: RiskParameters,
+}
+
+impl SubmissionLimits {
+    /// Reads the users, KYC levels, volume limit overrides and volumes of `wallet_addresses`,
+    /// one query each
+    pub(crate) async fn load(state: &AppState, wallet_addresses: &[WalletAddress]) -> ApiResult<Self> {
+        // Only used when no epoch is active
+        let epoch_start = ChronoDuration::from_std(state.parameters.epoch_duration().await?)
+            .ok()
+            .and_then(|duration| Utc::now().checked_sub_signed(duration))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+        let users = UserRepository::new(state.db.pg.clone()).get_by_wallets(wallet_addresses).await?;
+        let user_ids: Vec<Uuid> = users.iter().map(|user| user.id).collect();
+        let requests = BlockchainRequestRepository::new(state.db.pg.clone());
+        let user_limits = UserLimitRepository::new(state.db.pg.clone());
+        let (levels, volumes, daily_volumes, overrides, parameters, risk) = tokio::try_join!(
+            state.kyc.approved_levels(&user_ids),
+            requests.epoch_volumes(wallet_addresses, epoch_start),
+            requests.volumes_since(wallet_addresses, Utc::now() - ChronoDuration::hours(24)),
+            user_limits.get_many(&user_ids),
+            state.parameters.parameters(),
+            state.risk.current(),
+        )?;
+
+        Ok(Self {
+            users: users.into_iter().map(|user| (user.wallet_address.clone(), user)).collect(),
+            levels,
+            volumes,
+            daily_volumes,
+            overrides,
+            parameters,
+            risk,
+        })
+    }
+
+    /// Checks a submission of `amount` tokens against the wallet's KYC level and per-epoch
+    /// limit, then its volume limits. `pending` is the amount of earlier items in the same batch
+    /// that have been accepted but not yet stored.
+    pub(crate) fn check(
+        &self,
+        wallet_address: &WalletAddress,
+        request_type: &RequestType,
+        amount: &BigDecimal,
+        pending: &BigDecimal,
+    ) -> ApiResult<()> {
+        let level = self.level(wallet_address, request_type)?;
+
+        if let Some(limit) = self.risk.kyc_epoch_limit(level).and_then(limit_decimal) {
+            let used = self.used(wallet_address, request_type) + pending;
+            if &used + amount > limit {
+                return Err(ApiError::Forbidden {
+                    code: "KYC_LIMIT_EXCEEDED",
+                    message: format!(
+                        "Wallet {} is verified at the {} level, which allows {} USDC of {} requests per epoch; {} USDC remaining",
+                        wallet_address,
+                        level,
+                        limit.normalized(),
+                        request_type,
+                        remaining(&limit, &used).normalized()
+                    ),
+                });
+            }
+        }
+
+        let limits = self.volume_limits(wallet_address);
+        for window in [LimitWindow::Epoch, LimitWindow::Day] {
+            let Some(limit) = limits.get(request_type, window).and_then(|limit| limit.limit.as_ref()) else {
+                continue;
+            };
+            let used = self.used_in(wallet_address, request_type, window) + pending;
+            if &used + amount > *limit {
+                return Err(ApiError::Forbidden {
+                    code: "VOLUME_LIMIT_EXCEEDED",
+                    message: format!(
+                        "Wallet {} may request {} of {} requests {}; {} remaining",
+                        wallet_address,
+                        limit.normalized(),
+                        request_type,
+                        window_name(window),
+                        remaining(limit, &used).normalized()
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The wallet's volume limits, with its user's overrides applied
+    pub(crate) fn volume_limits(&self, wallet_address: &WalletAddress) -> VolumeLimits {
+        let overrides = self.users.get(wallet_address).and_then(|user| self.overrides.get(&user.id));
+
+        VolumeLimits::resolve(&self.parameters, overrides)
+    }
+
+    /// What the wallet has used of each of its volume limits
+    pub(crate) fn volume_usage(&self, wallet_address: &WalletAddress) -> Vec<VolumeUsage> {
+        let limits = self.volume_limits(wallet_address);
+
+        VOLUME_LIMITED
+            .into_iter()
+            .filter_map(|(request_type, window)| {
+                let limit = limits.get(&request_type, window)?;
+                let used = self.used_in(wallet_address, &request_type, window);
+                Some(VolumeUsage {
+                    request_type,
+                    window,
+                    remaining: limit.limit.as_ref().map(|limit| remaining(limit, &used)),
+                    limit: limit.limit.clone(),
+                    source: limit.source,
+                    used,
+                })
+            })
+            .collect()
+    }
+
+    /// What's left of the tightest of the wallet's volume limits on a request type, `None` when
+    /// none limits it
+    pub(crate) fn volume_remaining(
+        &self,
+        wallet_address: &WalletAddress,
+        request_type: &RequestType,
+    ) -> Option<BigDecimal> {
+        let limits = self.volume_limits(wallet_address);
+
+        [LimitWindow::Epoch, LimitWindow::Day]
+            .into_iter()
+            .filter_map(|window| {
+                let limit = limits.get(request_type, window)?.limit.as_ref()?;
+                Some(remaining(limit, &self.used_in(wallet_address, request_type, window)))
+            })
+            .min()
+    }
+
+    /// What's left of the wallet's per-epoch limit for a request type, `None` when its level
+    /// has no limit
+    pub(crate) fn remaining(
+        &self,
+        wallet_address: &WalletAddress,
+        request_type: &RequestType,
+    ) -> ApiResult<Option<BigDecimal>> {
+        let level = self.level(wallet_address, request_type)?;
+
+        Ok(self
+            .risk
+            .kyc_epoch_limit(level)
+            .and_then(limit_decimal)
+            .map(|limit| remaining(&limit, &self.used(wallet_address, request_type))))
+    }
+
+    /// The user registered for a wallet
+    pub(crate) fn user(&self, wallet_address: &WalletAddress) -> Option<&User> {
+        self.users.get(wallet_address)
+    }
+
+    /// The level the wallet is verified at, refusing wallets that haven't completed KYC
+    fn level(&self, wallet_address: &WalletAddress, request_type: &RequestType) -> ApiResult<KycLevel> {
+        let kyc_required = || ApiError::Forbidden {
+            code: "KYC_REQUIRED",
+            message: format!("Wallet {} must complete KYC before submitting {} requests", wallet_address, request_type),
+        };
+
+        let user = self.users.get(wallet_address).ok_or_else(kyc_required)?;
+        if user.kyc_status != KycStatus::Approved {
+            return Err(kyc_required());
+        }
+
+        // Users approved on-chain without a verification here are treated as Basic
+        Ok(self.levels.get(&user.id).copied().unwrap_or(KycLevel::Basic))
+    }
+
+    /// Amount of a request type the wallet has requested during the epoch
+    fn used(&self, wallet_address: &WalletAddress, request_type: &RequestType) -> BigDecimal {
+        self.used_in(wallet_address, request_type, LimitWindow::Epoch)
+    }
+
+    /// Amount of a request type the wallet has requested within a window
+    fn used_in(&self, wallet_address: &WalletAddress, request_type: &RequestType, window: LimitWindow) -> BigDecimal {
+        let volumes = match window {
+            LimitWindow::Epoch => &self.volumes,
+            LimitWindow::Day => &self.daily_volumes,
+        };
+
+        volumes.get(&(wallet_address.clone(), request_type.clone())).cloned().unwrap_or_default()
+    }
+}
+
+/// Holds the submission locks of a set of wallets, released once their requests are stored
+pub(crate) struct SubmissionLock(UnitOfWork);
+
+impl SubmissionLock {
+    /// Waits for the submissions in flight from any of `wallet_addresses` to be stored
+    pub(crate) async fn acquire(state: &AppState, wallet_addresses: &[WalletAddress]) -> ApiResult<Self> {
+        let mut uow = UnitOfWork::begin(&state.db.pg).await?;
+        BlockchainRequestRepository::lock_submissions_in(uow.conn(), wallet_addresses).await?;
+
+        Ok(Self(uow))
+    }
+
+    /// Lets the next submissions from the wallets through. Dropping the lock releases it too.
+    pub(crate) async fn release(self) {
+        if let Err(err) = self.0.rollback().await {
+            warn!("Failed to release submission lock: {:#}", err);
+        }
+    }
+}
+
+/// A KYC level's limit as a decimal; limits that aren't finite don't limit anything
+fn limit_decimal(limit: f64) -> Option<BigDecimal> {
+    BigDecimal::from_str(&limit.to_string()).ok()
+}
+
+/// What's left of `limit` once `used` is taken off it, never negative
+fn remaining(limit: &BigDecimal, used: &BigDecimal) -> BigDecimal {
+    (limit - used).max(BigDecimal::default())
+}
+
+/// How a window reads in refusals
+fn window_name(window: LimitWindow) -> &'static str {
+    match window {
+        LimitWindow::Epoch => "per epoch",
+        LimitWindow::Day => "in any 24 hours",
+    }
+}
+
+/// Checks a single submission of `amount` tokens against the wallet's KYC level, per-epoch
+/// limit and volume limits
+pub(crate) async fn enforce_submission_limits(
+    state: &AppState,
+    wallet_address: &WalletAddress,
+    request_type: &RequestType,
+    amount: &BigDecimal,
+) -> ApiResult<()> {
+    SubmissionLimits::load(state, std::slice::from_ref(wallet_address))
+        .await?
+        .check(wallet_address, request_type, amount, &BigDecimal::default())
+}
@@ -0,0 +1,21 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::ApiResult;
+use crate::api::AppState;
+use crate::db::{DbAccess, LiquidationRepository};
+use crate::models::liquidation::{Liquidation, LiquidationFilter};
+
+/// List flagged and liquidated borrows with their liquidation logs, most recently flagged first
+pub async fn list_liquidations(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<LiquidationFilter>,
+) -> ApiResult<Json<Vec<Liquidation>>> {
+    let liquidations = LiquidationRepository::new(state.db.pool(DbAccess::Read)).list(&filter).await?;
+
+    Ok(Json(liquidations))
+}
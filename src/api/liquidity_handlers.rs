@@ -0,0 +1,14 @@
+use axum::{extract::State, Json};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::ApiResult;
+use crate::api::AppState;
+use crate::models::liquidity::LiquidityReport;
+
+/// Shortfall report for pending withdrawals
+pub async fn get_liquidity_report(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<LiquidityReport>> {
+    Ok(Json(state.liquidity.report().await?))
+}
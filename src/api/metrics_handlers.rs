@@ -0,0 +1,11 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+
+use crate::api::AppState;
+
+/// Render all recorded metrics in the Prometheus text format
+pub async fn render_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
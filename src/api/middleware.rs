@@ -0,0 +1,243 @@
+//! HTTP middleware configuration
+
+use axum::extract::{ConnectInfo, MatchedPath, State};
+use axum::http::{header, HeaderName, Method, Request, Response};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::Router;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::timeout::TimeoutLayer;
+use tower_http::trace::{MakeSpan, OnResponse, TraceLayer};
+use tracing::field::Empty;
+use tracing::Span;
+
+use crate::api::explorer::{add_explorer_links, ExplorerLinks};
+use crate::api::AppState;
+use crate::config::{CorsOrigins, HttpConfig, LogSamplingConfig};
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::services::audit::{AuditContext, AuditLog};
+
+/// Route segments whose value is the wallet a request concerns
+const WALLET_SEGMENTS: &[&str] = &[":wallet_address"];
+
+/// Header operators name themselves in on admin requests, for the audit log
+const ADMIN_ACTOR_HEADER: &str = "x-admin-actor";
+
+/// Builds the CORS layer for the configured environment
+pub fn cors_layer(config: &HttpConfig) -> CorsLayer {
+    let layer = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            HeaderName::from_static(ADMIN_ACTOR_HEADER),
+            // Sent by wallet-authenticated endpoints, see `WalletAuth`
+            HeaderName::from_static("x-wallet-address"),
+            HeaderName::from_static("x-wallet-timestamp"),
+            HeaderName::from_static("x-wallet-signature"),
+        ]);
+
+    match &config.cors_origins {
+        CorsOrigins::Any => layer.allow_origin(Any),
+        CorsOrigins::List(origins) => layer.allow_origin(origins.clone()),
+    }
+}
+
+/// Creates a span per request carrying its request ID, route and wallet
+///
+/// Requests to sampled paths get a span only once every `every` requests; the others are not
+/// logged at all, though failures are still reported by the trace layer.
+#[derive(Clone)]
+pub struct RequestSpans {
+    sampling: Arc<LogSamplingConfig>,
+    sampled_requests: Arc<AtomicU64>,
+}
+
+impl RequestSpans {
+    /// Creates request spans with the given sampling
+    pub fn new(sampling: &LogSamplingConfig) -> Self {
+        Self {
+            sampling: Arc::new(sampling.clone()),
+            sampled_requests: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Whether a request to this path should be logged
+    fn should_log(&self, path: &str) -> bool {
+        if !self.sampling.paths.iter().any(|sampled| sampled == path) {
+            return true;
+        }
+
+        self.sampled_requests.fetch_add(1, Ordering::Relaxed) % self.sampling.every == 0
+    }
+}
+
+impl<B> MakeSpan<B> for RequestSpans {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let path = request.uri().path();
+        if !self.should_log(path) {
+            return Span::none();
+        }
+
+        let request_id = request
+            .headers()
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let route = request.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
+
+        let span = tracing::info_span!(
+            "http_request",
+            method = %request.method(),
+            path,
+            route = route.unwrap_or(path),
+            request_id,
+            wallet = Empty,
+        );
+
+        if let Some(wallet) = route.and_then(|route| wallet_from_path(route, path)) {
+            span.record("wallet", wallet);
+        }
+
+        span
+    }
+}
+
+impl<B> OnResponse<B> for RequestSpans {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        if span.is_none() {
+            return;
+        }
+
+        tracing::info!(
+            status = response.status().as_u16(),
+            latency_ms = latency.as_millis() as u64,
+            "Finished request"
+        );
+    }
+}
+
+/// Finds the path segment matched by a wallet parameter of the route
+fn wallet_from_path<'a>(route: &str, path: &'a str) -> Option<&'a str> {
+    route
+        .split('/')
+        .zip(path.split('/'))
+        .find(|(pattern, _)| WALLET_SEGMENTS.contains(pattern))
+        .map(|(_, segment)| segment)
+}
+
+/// Records every admin API call in the audit log
+///
+/// The handler runs in an audit context naming the operator from `X-Admin-Actor`, so changes it
+/// records are attributed to them. Rejected calls are recorded too.
+pub async fn audit_admin_requests<B>(
+    State(audit): State<AuditLog>,
+    request: Request<B>,
+    next: Next<B>,
+) -> axum::response::Response {
+    let header_value = |name: &str| {
+        request.headers().get(name).and_then(|value| value.to_str().ok()).map(str::trim).filter(|value| !value.is_empty())
+    };
+    let context = AuditContext {
+        actor: header_value(ADMIN_ACTOR_HEADER).unwrap_or("admin").chars().take(255).collect(),
+        ip_address: client_ip(&request),
+        request_id: header_value("x-request-id").map(str::to_string),
+    };
+
+    let target = format!(
+        "{} {}",
+        request.method(),
+        request.extensions().get::<MatchedPath>().map_or(request.uri().path(), MatchedPath::as_str)
+    );
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().map(str::to_string);
+
+    let started = Instant::now();
+    let response = context.clone().scope(next.run(request)).await;
+
+    let mut entry = NewAuditEntry::new(AuditAction::AdminRequest, target).with_details(json!({
+        "path": path,
+        "query": query,
+        "duration_ms": started.elapsed().as_millis() as u64,
+    }));
+    entry.status_code = Some(response.status().as_u16() as i16);
+    context.scope(audit.record(entry)).await;
+
+    response
+}
+
+/// Address of the client, as reported by the proxy in front of the service or else the peer
+fn client_ip<B>(request: &Request<B>) -> Option<String> {
+    let forwarded = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+
+    match forwarded {
+        Some(ip) => Some(ip.chars().take(45).collect()),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| peer.ip().to_string()),
+    }
+}
+
+/// Applies the global middleware stack (explorer links, timeouts, compression, CORS, request
+/// logging and request IDs) to the router
+pub fn apply(router: Router<AppState>, config: &HttpConfig) -> Router<AppState> {
+    // Innermost, so links are added before the body is compressed
+    let router = router
+        .layer(from_fn_with_state(ExplorerLinks::new(&config.explorer), add_explorer_links))
+        .layer(TimeoutLayer::new(Duration::from_secs(config.request_timeout_secs)));
+
+    let router = if config.compression_enabled {
+        router.layer(CompressionLayer::new().gzip(true).br(true))
+    } else {
+        router
+    };
+
+    let request_spans = RequestSpans::new(&config.log_sampling);
+
+    // Request IDs are set outermost, so every other layer and the request span can see them
+    router
+        .layer(cors_layer(config))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(request_spans.clone())
+                .on_response(request_spans),
+        )
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_wallet_segment_of_route() {
+        assert_eq!(
+            wallet_from_path("/api/v1/users/:wallet_address/balance", "/api/v1/users/5Grwva/balance"),
+            Some("5Grwva")
+        );
+        assert_eq!(wallet_from_path("/api/v1/epochs/:epoch_id", "/api/v1/epochs/4"), None);
+    }
+
+    #[test]
+    fn samples_noisy_paths() {
+        let spans = RequestSpans::new(&LogSamplingConfig { paths: vec!["/metrics".to_string()], every: 3 });
+
+        let logged = (0..6).filter(|_| spans.should_log("/metrics")).count();
+        assert_eq!(logged, 2);
+        assert!(spans.should_log("/api/v1/stats"));
+    }
+}
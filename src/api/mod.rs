@@ -1,14 +1,69 @@
 use axum::Router;
+use metrics_exporter_prometheus::PrometheusHandle;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+pub mod accounting_handlers;
+pub mod admission;
+pub mod alert_handlers;
+pub mod archive_handlers;
+pub mod asset_handlers;
+pub mod audit_handlers;
+pub mod auth;
 pub mod blockchain;
+pub mod concurrent;
+pub mod conditional;
+pub mod correction_handlers;
+pub mod dashboard_handlers;
+pub mod deployment_handlers;
+pub mod epoch_handlers;
 pub mod error;
+pub mod explorer;
+pub mod feature_flag_handlers;
 pub mod handlers;
+pub mod kyc_handlers;
+pub mod limits;
+pub mod liquidation_handlers;
+pub mod liquidity_handlers;
+pub mod metrics_handlers;
+pub mod middleware;
+pub mod notification_handlers;
+pub mod parameter_handlers;
+pub mod receipt_handlers;
+pub mod reward_handlers;
+pub mod risk_handlers;
 pub mod routes;
+pub mod scheduler_handlers;
+pub mod screening_handlers;
+pub mod statement_handlers;
+pub mod stats_handlers;
+pub mod stream_handlers;
+pub mod streaming;
+pub mod treasury_handlers;
+pub mod user_handlers;
+pub mod webhook_handlers;
+pub mod withdrawals;
 
+use admission::Admission;
 use blockchain::BlockchainState;
-use crate::db::DbPools;
+use crate::config::HttpConfig;
+use crate::db::{DbPools, FeatureFlagRepository, SystemParameterRepository};
+use crate::services::ChainClient;
+use crate::services::audit::AuditLog;
+use crate::services::cache::Cache;
+use crate::services::changes::ChangeFeed;
+use crate::services::epochs::EpochProcessingService;
+use crate::services::interest::{DebtStatementService, InterestAccrualService};
+use crate::services::kyc::{KycDocumentStore, KycManager};
+use crate::services::alerting::Alerter;
+use crate::services::liquidity::LiquidityPlanningService;
+use crate::services::oracle::PriceFeed;
+use crate::services::rewards::RewardCalculationService;
+use crate::services::risk::RiskParameterService;
+use crate::services::scheduler::Scheduler;
+use crate::services::screening::ScreeningService;
+use crate::services::secrets::SecretStore;
+use crate::services::treasury::TreasuryService;
 
 /// Application state shared across all routes
 #[derive(Clone)]
@@ -18,9 +73,79 @@ pub struct AppState {
     
     /// Blockchain state
     pub blockchain_state: Arc<RwLock<BlockchainState>>,
+    
+    /// Node and contract calls, shared by every request
+    pub chain: Arc<dyn ChainClient>,
+    
+    /// Seed phrases and API keys
+    pub secrets: SecretStore,
+    
+    /// API key required by admin endpoints (admin API is disabled when unset)
+    pub admin_api_key: Option<String>,
+    
+    /// Cached system parameters
+    pub parameters: SystemParameterRepository,
+    
+    /// Cached feature flags of this environment
+    pub flags: FeatureFlagRepository,
+    
+    /// Validated, versioned risk parameters
+    pub risk: RiskParameterService,
+    
+    /// Cache for hot reads
+    pub cache: Cache,
+    
+    /// Live database changes for streaming clients
+    pub changes: ChangeFeed,
+    
+    /// KYC verifications and provider webhooks
+    pub kyc: KycManager,
+    
+    /// Identity documents for manual KYC review, when object storage is configured
+    pub kyc_documents: Option<KycDocumentStore>,
+    
+    /// Epoch reward calculation
+    pub rewards: RewardCalculationService,
+    
+    /// Borrower interest accrual
+    pub interest: InterestAccrualService,
+    
+    /// Monthly debt statements
+    pub statements: DebtStatementService,
+    
+    /// Withdrawal liquidity planning
+    pub liquidity: LiquidityPlanningService,
+    
+    /// Collateral and vault asset prices
+    pub prices: PriceFeed,
+    
+    /// Epoch close sequence
+    pub epochs: EpochProcessingService,
+    
+    /// Protocol fee accounting
+    pub treasury: TreasuryService,
+    
+    /// Recurring background jobs
+    pub scheduler: Scheduler,
+    
+    /// Sanctions screening and wallet blocks
+    pub screening: ScreeningService,
+    
+    /// Operator alerting
+    pub alerts: Alerter,
+    
+    /// Record of privileged operations
+    pub audit: AuditLog,
+    
+    /// Prometheus recorder rendered by the metrics endpoint
+    pub metrics: PrometheusHandle,
+    
+    /// Refuses submissions while the event queue is too deep
+    pub admission: Admission,
 }
 
 /// Create the application router
-pub fn create_router(state: AppState) -> Router {
-    routes::api_router().with_state(state)
+pub fn create_router(state: AppState, http_config: &HttpConfig) -> Router {
+    let (audit, admission) = (state.audit.clone(), state.admission.clone());
+    middleware::apply(routes::api_router(http_config, audit, admission), http_config).with_state(state)
 } 
\ No newline at end of file
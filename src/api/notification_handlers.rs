@@ -0,0 +1,62 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+
+use crate::api::auth::{AdminAuth, WalletAuth};
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::{DbAccess, UserRepository};
+use crate::models::notification::{
+    EmailNotification, EmailNotificationFilter, NotificationPreference, UpdateNotificationPreferencesRequest,
+};
+use crate::models::wallet::WalletAddress;
+use crate::services::notifications::NotificationStore;
+
+/// Get a wallet's email notification preferences
+pub async fn get_notification_preferences(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Json<Vec<NotificationPreference>>> {
+    let user = UserRepository::new(state.db.pool(DbAccess::Read)).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let preferences = NotificationStore::new(state.db.pool(DbAccess::Read)).get_preferences(user.id).await?;
+
+    Ok(Json(preferences))
+}
+
+/// Turn some of a wallet's email notifications on or off. Only the wallet itself may.
+pub async fn update_notification_preferences(
+    caller: WalletAuth,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+    Json(payload): Json<UpdateNotificationPreferencesRequest>,
+) -> ApiResult<Json<Vec<NotificationPreference>>> {
+    caller.ensure_is(&wallet_address)?;
+
+    let user = UserRepository::new(state.db.pg.clone()).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let store = NotificationStore::new(state.db.pg);
+    store.set_preferences(user.id, &payload.preferences).await?;
+
+    Ok(Json(store.get_preferences(user.id).await?))
+}
+
+/// List the email delivery log
+pub async fn list_email_notifications(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<EmailNotificationFilter>,
+) -> ApiResult<Json<Vec<EmailNotification>>> {
+    let filter = EmailNotificationFilter {
+        limit: Some(filter.limit.unwrap_or(50).clamp(1, 500)),
+        offset: Some(filter.offset.unwrap_or(0).max(0)),
+        ..filter
+    };
+
+    let emails = NotificationStore::new(state.db.pool(DbAccess::Read)).list_emails(&filter).await?;
+
+    Ok(Json(emails))
+}
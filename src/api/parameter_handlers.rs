@@ -0,0 +1,63 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::risk::RiskParameters;
+use crate::models::system_parameter::{SystemParameter, SystemParametersCache, UpdateSystemParameterRequest};
+
+/// Get the effective protocol parameters
+pub async fn get_parameters(
+    State(state): State<AppState>,
+) -> ApiResult<Json<SystemParametersCache>> {
+    let parameters = state.parameters.parameters().await?;
+
+    Ok(Json(parameters))
+}
+
+/// List the stored protocol parameters
+pub async fn list_parameters(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<SystemParameter>>> {
+    let parameters = state.parameters.list().await?;
+
+    Ok(Json(parameters))
+}
+
+/// Update a protocol parameter. Risk parameters are changed together through the risk
+/// parameter endpoint so their invariants are checked and the change is versioned.
+pub async fn update_parameter(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateSystemParameterRequest>,
+) -> ApiResult<Json<SystemParameter>> {
+    if RiskParameters::NAMES.contains(&name.as_str()) {
+        return Err(ApiError::Validation(format!(
+            "{} is a risk parameter; update it through /api/v1/admin/risk-parameters",
+            name
+        )));
+    }
+
+    SystemParametersCache::default()
+        .apply(&name, &payload.parameter_value)
+        .map_err(ApiError::Validation)?;
+
+    let before = state.parameters.get(&name).await?;
+    let parameter = state.parameters.update(&name, &payload).await?
+        .ok_or_else(|| ApiError::NotFound(format!("System parameter {} not found", name)))?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::ParameterChange, format!("system_parameter:{}", name))
+                .with_change(before.as_ref(), Some(&parameter)),
+        )
+        .await;
+
+    Ok(Json(parameter))
+}
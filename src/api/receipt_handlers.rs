@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::DbAccess;
+use crate::models::receipt::{ReceiptKeys, SignedReceipt};
+use crate::services::receipts::{ReceiptError, ReceiptService};
+
+/// Get a signed receipt of an executed request
+///
+/// The signature covers `payload` wrapped as `<Bytes>…</Bytes>` and verifies with the key
+/// published at `GET /api/v1/receipts/public-key`.
+pub async fn get_request_receipt(
+    State(state): State<AppState>,
+    Path(request_id): Path<i64>,
+) -> ApiResult<Json<SignedReceipt>> {
+    let receipt = receipts(&state).issue(request_id).await.map_err(receipt_error)?;
+
+    Ok(Json(receipt))
+}
+
+/// Get the public keys receipts are signed with
+pub async fn get_receipt_public_key(State(state): State<AppState>) -> ApiResult<Json<ReceiptKeys>> {
+    let keys = receipts(&state).keys().await.map_err(receipt_error)?;
+
+    Ok(Json(keys))
+}
+
+fn receipts(state: &AppState) -> ReceiptService {
+    ReceiptService::new(state.db.pool(DbAccess::Read), state.secrets.clone())
+}
+
+fn receipt_error(e: anyhow::Error) -> ApiError {
+    match e.downcast_ref::<ReceiptError>() {
+        Some(ReceiptError::NotFound(_)) => ApiError::NotFound(e.to_string()),
+        Some(ReceiptError::NotExecuted { .. }) => ApiError::Conflict(e.to_string()),
+        Some(ReceiptError::NoSigningKey) => ApiError::ServiceUnavailable(e.to_string()),
+        None => ApiError::from(e),
+    }
+}
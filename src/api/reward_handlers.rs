@@ -0,0 +1,45 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::reward::EpochRewardReport;
+use crate::services::rewards::RewardError;
+
+/// Calculate the rewards of a closed epoch. Epochs already calculated return their stored report.
+pub async fn calculate_epoch_rewards(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(epoch_id): Path<i32>,
+) -> ApiResult<Json<EpochRewardReport>> {
+    let report = state.rewards.calculate_epoch(epoch_id).await.map_err(|e| match e.downcast_ref::<RewardError>() {
+        Some(RewardError::EpochNotFound(_)) => ApiError::NotFound(e.to_string()),
+        Some(RewardError::EpochNotClosed(_)) => ApiError::Validation(e.to_string()),
+        None => ApiError::from(e),
+    })?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::Reconciliation, format!("epoch:{}:rewards", epoch_id))
+                .with_change(None::<&EpochRewardReport>, Some(&report)),
+        )
+        .await;
+
+    Ok(Json(report))
+}
+
+/// Get the reward distribution report of an epoch
+pub async fn get_epoch_reward_report(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(epoch_id): Path<i32>,
+) -> ApiResult<Json<EpochRewardReport>> {
+    let report = state.rewards.report(epoch_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Rewards for epoch {} have not been calculated", epoch_id)))?;
+
+    Ok(Json(report))
+}
@@ -0,0 +1,80 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::risk::{RiskParameterVersion, RiskParameters, UpdateRiskParametersRequest};
+use crate::services::risk::RiskError;
+
+/// Paging for risk parameter versions
+#[derive(Debug, Deserialize)]
+pub struct VersionQuery {
+    limit: Option<i64>,
+}
+
+/// Get the current risk parameters
+pub async fn get_risk_parameters(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<RiskParameters>> {
+    Ok(Json(state.risk.current().await?))
+}
+
+/// Replace the risk parameters, recording the change as a new version
+pub async fn update_risk_parameters(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateRiskParametersRequest>,
+) -> ApiResult<Json<RiskParameterVersion>> {
+    let before = state.risk.current().await?;
+    let version = state.risk.update(&payload).await.map_err(|e| match e.downcast_ref::<RiskError>() {
+        Some(RiskError::Invalid(_)) => ApiError::Validation(e.to_string()),
+        Some(RiskError::VersionConflict { .. }) => ApiError::Conflict(e.to_string()),
+        None => ApiError::from(e),
+    })?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::ParameterChange, "risk_parameters")
+                .with_change(Some(&before), Some(&version.parameters))
+                .with_transaction(version.onchain_transaction_hash.clone())
+                .with_details(json!({ "version": version.version, "changed": version.changed, "reason": version.reason })),
+        )
+        .await;
+
+    Ok(Json(version))
+}
+
+/// List risk parameter versions, newest first
+pub async fn list_risk_parameter_versions(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<VersionQuery>,
+) -> ApiResult<Json<Vec<RiskParameterVersion>>> {
+    Ok(Json(state.risk.versions(query.limit).await?))
+}
+
+/// Push the latest risk parameters version to the contract if it hasn't been yet
+pub async fn sync_risk_parameters(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<RiskParameterVersion>> {
+    let version = state.risk.sync_onchain().await?
+        .ok_or_else(|| ApiError::NotFound("No risk parameter changes have been recorded".to_string()))?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::Reconciliation, "risk_parameters:onchain_sync")
+                .with_transaction(version.onchain_transaction_hash.clone())
+                .with_details(json!({ "version": version.version, "onchain_status": version.onchain_status })),
+        )
+        .await;
+
+    Ok(Json(version))
+}
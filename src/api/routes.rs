@@ -1,41 +1,185 @@
 use axum::{
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    http::header,
+    middleware::from_fn_with_state,
+    routing::{get, patch, post, put},
     Router,
 };
+use tower_http::set_header::SetResponseHeaderLayer;
 
-use crate::api::handlers;
+use crate::api::{accounting_handlers, admission, alert_handlers, archive_handlers, asset_handlers, audit_handlers, correction_handlers, dashboard_handlers, deployment_handlers, epoch_handlers, feature_flag_handlers, handlers, kyc_handlers, liquidation_handlers, liquidity_handlers, metrics_handlers, middleware, notification_handlers, parameter_handlers, receipt_handlers, reward_handlers, risk_handlers, scheduler_handlers, screening_handlers, statement_handlers, stats_handlers, stream_handlers, treasury_handlers, user_handlers, webhook_handlers};
+use crate::api::admission::Admission;
 use crate::api::AppState;
+use crate::config::HttpConfig;
+use crate::services::audit::AuditLog;
 
-/// Create the API router with all routes
-pub fn api_router() -> Router<AppState> {
+/// Create the API router with all routes; every admin call is recorded in `audit`, and
+/// submissions are refused while `admission` says the event queue is too deep
+pub fn api_router(http_config: &HttpConfig, audit: AuditLog, admission: Admission) -> Router<AppState> {
     // Blockchain state endpoints
     let blockchain_routes = Router::new()
-        .route("/summary", get(handlers::get_blockchain_state_summary))
+        .route(
+            "/summary",
+            get(handlers::get_blockchain_state_summary)
+                .layer(cache_control(http_config.cache_control.summary.clone())),
+        )
         .route("/refresh", post(handlers::refresh_blockchain_state));
     
     // Request endpoints
     let request_routes = Router::new()
         .route("/:request_id", get(handlers::get_request_by_id))
+        .route("/:request_id/receipt", get(receipt_handlers::get_request_receipt))
+        .route("/:request_id/timeline", get(handlers::get_request_timeline))
         .route("/wallet/:wallet_address", get(handlers::get_requests_by_wallet))
         .route("/deposits", get(handlers::get_deposit_requests))
         .route("/withdrawals", get(handlers::get_withdrawal_requests))
-        .route("/borrows", get(handlers::get_borrow_requests))
+        .route("/borrows", get(handlers::get_borrow_requests));
+    
+    // Submission endpoints get a tighter body limit than the axum default
+    let submission_routes = Router::new()
         .route("/deposit", post(handlers::submit_deposit_request))
-        .route("/withdraw", post(handlers::submit_withdrawal_request));
+        .route("/withdraw", post(handlers::submit_withdrawal_request))
+        .route("/borrow", post(handlers::submit_borrow_request))
+        .layer(DefaultBodyLimit::max(http_config.submission_body_limit_bytes))
+        .route_layer(from_fn_with_state(admission.clone(), admission::shed_load));
+    
+    let batch_routes = Router::new()
+        .route("/batch", post(handlers::submit_batch_requests))
+        .layer(DefaultBodyLimit::max(http_config.batch_body_limit_bytes))
+        .route_layer(from_fn_with_state(admission, admission::shed_load));
     
     // User endpoints
     let user_routes = Router::new()
-        .route("/:wallet_address", get(handlers::get_user_by_wallet));
+        .route("/", post(user_handlers::create_user))
+        .route("/:wallet_address", get(handlers::get_user_by_wallet))
+        .route("/:wallet_address/profile", get(user_handlers::get_user_profile))
+        .route("/:wallet_address/balance", get(user_handlers::get_user_balance))
+        .route("/:wallet_address/withdrawable", get(user_handlers::get_user_withdrawable))
+        .route("/:wallet_address/referrals", get(user_handlers::get_user_referrals))
+        .route("/:wallet_address/requests", get(user_handlers::get_user_requests))
+        .route(
+            "/:wallet_address/notification-preferences",
+            get(notification_handlers::get_notification_preferences)
+                .put(notification_handlers::update_notification_preferences),
+        )
+        .route("/:wallet_address/interest", get(statement_handlers::list_interest_accruals))
+        .route("/:wallet_address/statements", get(statement_handlers::list_statements))
+        .route("/:wallet_address/statements/:month", get(statement_handlers::get_statement));
     
-    // Epoch endpoints
+    // Epoch endpoints; literal paths go before the parameterised one
     let epoch_routes = Router::new()
+        .route("/", get(handlers::list_epochs))
+        .route("/current", get(handlers::get_current_epoch))
+        .route("/schedule", get(handlers::get_epoch_schedule))
         .route("/:epoch_id", get(handlers::get_epoch_by_id))
-        .route("/current", get(handlers::get_current_epoch));
+        .route_layer(cache_control(http_config.cache_control.epochs.clone()));
+    
+    // KYC endpoints
+    let kyc_routes = Router::new()
+        .route("/verifications", post(kyc_handlers::create_verification))
+        .route("/verifications/:verification_id", get(kyc_handlers::get_verification))
+        .route("/verifications/:verification_id/submit", post(kyc_handlers::submit_verification))
+        .route("/webhooks/:provider", post(kyc_handlers::receive_webhook));
+    
+    let document_routes = Router::new()
+        .route(
+            "/verifications/:verification_id/documents",
+            get(kyc_handlers::list_documents).post(kyc_handlers::upload_document),
+        )
+        .layer(DefaultBodyLimit::max(http_config.document_body_limit_bytes));
+    
+    // Admin endpoints
+    let admin_routes = Router::new()
+        .route("/parameters", get(parameter_handlers::list_parameters))
+        .route("/parameters/:name", put(parameter_handlers::update_parameter))
+        .route("/feature-flags", get(feature_flag_handlers::list_feature_flags))
+        .route("/feature-flags/:name", put(feature_flag_handlers::update_feature_flag))
+        .route("/assets/:symbol", put(asset_handlers::upsert_asset))
+        .route(
+            "/risk-parameters",
+            get(risk_handlers::get_risk_parameters).put(risk_handlers::update_risk_parameters),
+        )
+        .route("/risk-parameters/versions", get(risk_handlers::list_risk_parameter_versions))
+        .route("/risk-parameters/sync", post(risk_handlers::sync_risk_parameters))
+        .route("/users", get(user_handlers::list_users))
+        .route("/users/export", get(user_handlers::export_users))
+        .route("/users/:wallet_address", patch(user_handlers::update_user))
+        .route(
+            "/users/:wallet_address/limits",
+            get(user_handlers::get_user_limits)
+                .put(user_handlers::update_user_limits)
+                .delete(user_handlers::reset_user_limits),
+        )
+        .route(
+            "/requests/:request_id/correction",
+            get(correction_handlers::preview_request_correction).post(correction_handlers::correct_request),
+        )
+        .route(
+            "/epochs/:epoch_id/rewards",
+            get(reward_handlers::get_epoch_reward_report).post(reward_handlers::calculate_epoch_rewards),
+        )
+        .route("/epochs/:epoch_id/process", post(epoch_handlers::process_epoch))
+        .route("/epochs/:epoch_id/processing-status", get(epoch_handlers::get_processing_status))
+        .route("/liquidity", get(liquidity_handlers::get_liquidity_report))
+        .route("/liquidations", get(liquidation_handlers::list_liquidations))
+        .route("/treasury/report", get(treasury_handlers::get_treasury_report))
+        .route("/kyc/verifications/:verification_id/documents", get(kyc_handlers::review_documents))
+        .route("/kyc/verifications/:verification_id/onchain-sync/retry", post(kyc_handlers::retry_onchain_sync))
+        .route("/scheduler/jobs", get(scheduler_handlers::list_jobs))
+        .route("/scheduler/jobs/:name/run", post(scheduler_handlers::run_job))
+        .route("/indexer/queue", get(admission::get_queue_status))
+        .route("/alerts", get(alert_handlers::list_active_alerts))
+        .route("/alerts/test", post(alert_handlers::send_test_alert))
+        .route("/archives/events", get(archive_handlers::list_event_archives))
+        .route("/deployments", get(deployment_handlers::list_deployments))
+        .route("/accounting/journal", get(accounting_handlers::export_journal))
+        .route(
+            "/screenings",
+            get(screening_handlers::list_screenings).post(screening_handlers::create_screening),
+        )
+        .route("/blocked-wallets", get(screening_handlers::list_blocked_wallets))
+        .route("/blocked-wallets/:wallet_address/clear", post(screening_handlers::clear_blocked_wallet))
+        .route(
+            "/webhooks",
+            get(webhook_handlers::list_webhook_endpoints).post(webhook_handlers::create_webhook_endpoint),
+        )
+        .route(
+            "/webhooks/:endpoint_id",
+            get(webhook_handlers::get_webhook_endpoint)
+                .patch(webhook_handlers::update_webhook_endpoint)
+                .delete(webhook_handlers::delete_webhook_endpoint),
+        )
+        .route("/webhooks/:endpoint_id/deliveries", get(webhook_handlers::list_webhook_deliveries))
+        .route("/webhooks/:endpoint_id/rotate-secret", post(webhook_handlers::rotate_webhook_secret))
+        .route("/webhooks/deliveries/:delivery_id/retry", post(webhook_handlers::retry_webhook_delivery))
+        .route("/notifications", get(notification_handlers::list_email_notifications))
+        .route("/audit", get(audit_handlers::list_audit_entries))
+        .route_layer(from_fn_with_state(audit, middleware::audit_admin_requests));
     
     // Combine all routes
     Router::new()
         .nest("/api/v1/blockchain", blockchain_routes)
-        .nest("/api/v1/requests", request_routes)
+        .nest("/api/v1/requests", request_routes.merge(submission_routes).merge(batch_routes))
         .nest("/api/v1/users", user_routes)
         .nest("/api/v1/epochs", epoch_routes)
-} 
\ No newline at end of file
+        .nest("/api/v1/kyc", kyc_routes.merge(document_routes))
+        .route("/api/v1/dashboard/:wallet_address", get(dashboard_handlers::get_dashboard))
+        .route("/api/v1/parameters", get(parameter_handlers::get_parameters))
+        .route("/api/v1/feature-flags", get(feature_flag_handlers::get_feature_flags))
+        .route("/api/v1/assets", get(asset_handlers::list_assets))
+        .route("/api/v1/receipts/public-key", get(receipt_handlers::get_receipt_public_key))
+        .route(
+            "/api/v1/stats",
+            get(stats_handlers::get_stats).layer(cache_control(http_config.cache_control.stats.clone())),
+        )
+        .route("/api/v1/stats/apy/simulate", get(stats_handlers::simulate_apy))
+        .route("/api/v1/stream/changes", get(stream_handlers::stream_changes))
+        .route("/api/v1/webhooks/:endpoint_id/deliveries", get(webhook_handlers::replay_webhook_deliveries))
+        .nest("/api/v1/admin", admin_routes)
+        .route("/metrics", get(metrics_handlers::render_metrics))
+}
+
+/// Sets `Cache-Control` on responses that don't already carry one
+fn cache_control(value: header::HeaderValue) -> SetResponseHeaderLayer<header::HeaderValue> {
+    SetResponseHeaderLayer::if_not_present(header::CACHE_CONTROL, value)
+}
@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::services::scheduler::JobStatus;
+
+/// List scheduled jobs with their schedule and last run
+pub async fn list_jobs(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<JobStatus>>> {
+    Ok(Json(state.scheduler.statuses()))
+}
+
+/// Run a scheduled job now. The run happens in the background; its outcome shows up in
+/// [`list_jobs`].
+pub async fn run_job(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> ApiResult<(StatusCode, Json<JobStatus>)> {
+    if !state.scheduler.statuses().iter().any(|job| job.name == name) {
+        return Err(ApiError::NotFound(format!("Unknown scheduled job {}", name)));
+    }
+
+    let status = state.scheduler.trigger(&name).map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    state.audit
+        .record(NewAuditEntry::new(AuditAction::Reconciliation, format!("scheduled_job:{}:run", name)))
+        .await;
+
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::{DbAccess, ScreeningRepository};
+use crate::models::screening::{BlockedWallet, ClearBlockRequest, Screening, ScreeningFilter, ScreeningTrigger};
+use crate::models::wallet::WalletAddress;
+use crate::services::screening::{ScreeningError, ScreeningSubject};
+
+/// Filter for blocked wallet lists
+#[derive(Debug, Deserialize)]
+pub struct BlockedWalletQuery {
+    #[serde(default)]
+    include_cleared: bool,
+}
+
+/// Maps a failed screening check onto the error returned to the caller. Block reasons are kept
+/// out of the response so flagged users aren't told which list they matched.
+pub(crate) fn screening_error(err: anyhow::Error) -> ApiError {
+    match err.downcast_ref::<ScreeningError>() {
+        Some(ScreeningError::Blocked { wallet_address, .. }) => {
+            ApiError::Forbidden {
+                code: "WALLET_BLOCKED",
+                message: format!("Wallet {} is not permitted to use this service", wallet_address),
+            }
+        },
+        _ => ApiError::from(err),
+    }
+}
+
+/// Screens a wallet that is registering. Provider outages don't hold registration up; the
+/// wallet is screened again before it can withdraw.
+pub(crate) async fn screen_registration(state: &AppState, wallet_address: &WalletAddress) -> ApiResult<()> {
+    let subject = ScreeningSubject::wallet(wallet_address);
+
+    match state.screening.check(&subject, ScreeningTrigger::Registration).await {
+        Ok(()) => Ok(()),
+        Err(err) if matches!(err.downcast_ref::<ScreeningError>(), Some(ScreeningError::Unavailable(_))) => {
+            tracing::warn!("Registering {} without a screening: {}", wallet_address, err);
+            Ok(())
+        },
+        Err(err) => Err(screening_error(err)),
+    }
+}
+
+/// List screenings, newest first
+pub async fn list_screenings(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<ScreeningFilter>,
+) -> ApiResult<Json<Vec<Screening>>> {
+    let screenings = ScreeningRepository::new(state.db.pool(DbAccess::Read)).list(&filter).await?;
+
+    Ok(Json(screenings))
+}
+
+/// Screen a wallet on demand
+pub async fn create_screening(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(subject): Json<ScreeningSubject>,
+) -> ApiResult<(StatusCode, Json<Screening>)> {
+    let screening = state.screening.screen(&subject, ScreeningTrigger::Manual).await?
+        .ok_or_else(|| ApiError::Validation("No screening provider is configured".to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(screening)))
+}
+
+/// List blocked wallets
+pub async fn list_blocked_wallets(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<BlockedWalletQuery>,
+) -> ApiResult<Json<Vec<BlockedWallet>>> {
+    let blocks = ScreeningRepository::new(state.db.pool(DbAccess::Read))
+        .list_blocks(query.include_cleared)
+        .await?;
+
+    Ok(Json(blocks))
+}
+
+/// Lift the block on a wallet, e.g. after a false positive has been reviewed
+pub async fn clear_blocked_wallet(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+    Json(payload): Json<ClearBlockRequest>,
+) -> ApiResult<Json<BlockedWallet>> {
+    if payload.reason.trim().is_empty() {
+        return Err(ApiError::Validation("A reason is required to clear a block".to_string()));
+    }
+
+    let block = state.screening.clear_block(&wallet_address, &payload.reason).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Wallet {} is not blocked", wallet_address)))?;
+
+    Ok(Json(block))
+}
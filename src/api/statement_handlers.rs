@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::models::interest::{DebtStatement, InterestAccrual, StatementFormat};
+use crate::services::interest::render_csv;
+
+/// Download options for a debt statement
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    #[serde(default)]
+    format: StatementFormat,
+}
+
+/// Parses a `YYYY-MM` statement month
+fn parse_month(month: &str) -> ApiResult<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map_err(|_| ApiError::Validation(format!("Invalid statement month '{}', expected YYYY-MM", month)))
+}
+
+/// List a wallet's debt statements, most recent first
+pub async fn list_statements(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<String>,
+) -> ApiResult<Json<Vec<DebtStatement>>> {
+    Ok(Json(state.statements.list(&wallet_address).await?))
+}
+
+/// Download a wallet's debt statement for a month as JSON or CSV
+pub async fn get_statement(
+    State(state): State<AppState>,
+    Path((wallet_address, month)): Path<(String, String)>,
+    Query(query): Query<StatementQuery>,
+) -> ApiResult<Response> {
+    let month = parse_month(&month)?;
+
+    let statement = state.statements
+        .get(&wallet_address, month)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("No statement for {} in {}", wallet_address, month.format("%Y-%m")))
+        })?;
+
+    Ok(match query.format {
+        StatementFormat::Json => Json(statement).into_response(),
+        StatementFormat::Csv => {
+            let filename = format!(
+                "attachment; filename=\"statement-{}-{}.csv\"",
+                statement.wallet_address, statement.period_start.format("%Y-%m")
+            );
+            (
+                [(header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()), (header::CONTENT_DISPOSITION, filename)],
+                render_csv(&statement),
+            )
+                .into_response()
+        },
+    })
+}
+
+/// List the interest accrued by a wallet's borrows, most recent first
+pub async fn list_interest_accruals(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<String>,
+) -> ApiResult<Json<Vec<InterestAccrual>>> {
+    Ok(Json(state.interest.list(&wallet_address).await?))
+}
@@ -0,0 +1,103 @@
+use anyhow::anyhow;
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Response,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+
+use crate::api::conditional::conditional_json;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::{BalanceRepository, DbAccess, EpochRepository};
+use crate::models::stats::{ApyProjection, ProtocolStats};
+use crate::services::oracle::OracleError;
+use crate::services::rewards::apy::{self, ProjectionError, ProjectionInput};
+
+/// What to project in an APY simulation
+#[derive(Debug, Deserialize)]
+pub struct ApySimulationQuery {
+    amount: String,
+    /// Seconds, or a number with an `s`, `h`, `d`, `w` or `y` suffix
+    duration: String,
+    /// Add each epoch's reward to the balance before the next
+    #[serde(default)]
+    compound: bool,
+}
+
+/// Maps a failed price lookup onto the error returned to the caller. Missing or stale prices
+/// make the endpoint unavailable rather than answering with a wrong value.
+pub(crate) fn oracle_error(err: anyhow::Error) -> ApiError {
+    match err.downcast_ref::<OracleError>() {
+        Some(err) => {
+            tracing::warn!("Price oracle error: {:#}", err);
+            ApiError::ServiceUnavailable("Asset prices are temporarily unavailable".to_string())
+        },
+        None => ApiError::from(err),
+    }
+}
+
+/// Protocol totals with the value locked in USD
+pub async fn get_stats(State(state): State<AppState>, headers: HeaderMap) -> ApiResult<Response> {
+    let balances = BalanceRepository::new(state.db.pool(DbAccess::Read));
+    let (totals, price) = tokio::try_join!(
+        async { Ok::<_, ApiError>(balances.protocol_totals().await?) },
+        async { state.prices.vault_price_usd().await.map_err(oracle_error) },
+    )?;
+
+    let total_value_locked = BigDecimal::from_str(&totals.total_value_locked)
+        .map_err(|e| ApiError::Internal(anyhow!("Invalid total value locked: {}", e)))?;
+    let total_value_locked_usd = (total_value_locked * &price.price).with_scale(2);
+
+    let stats = ProtocolStats {
+        totals,
+        vault_asset: state.prices.vault_asset().to_string(),
+        vault_price_usd: price.price.to_string(),
+        total_value_locked_usd: total_value_locked_usd.to_string(),
+        price_observed_at: price.observed_at,
+    };
+
+    conditional_json(&headers, stats)
+}
+
+/// Project the rewards of depositing an amount now and holding it for a duration, at the current
+/// APR and epoch length
+pub async fn simulate_apy(
+    State(state): State<AppState>,
+    Query(query): Query<ApySimulationQuery>,
+) -> ApiResult<Json<ApyProjection>> {
+    let projection_error = |err: ProjectionError| match err {
+        ProjectionError::InvalidEpochDuration => ApiError::Internal(err.into()),
+        _ => ApiError::Validation(err.to_string()),
+    };
+
+    let amount = apy::parse_amount(&query.amount).map_err(projection_error)?;
+    let duration_secs = apy::parse_duration(&query.duration).map_err(projection_error)?;
+    let epochs = EpochRepository::new(state.db.pool(DbAccess::Read));
+    let (parameters, active_epoch) = tokio::try_join!(state.parameters.parameters(), epochs.active())?;
+
+    // A deposit submitted now starts earning once the active epoch closes
+    let activation_delay_secs = match active_epoch {
+        Some(epoch) => {
+            let closes_at = epoch.start_timestamp + Duration::seconds(parameters.epoch_duration_seconds);
+            (closes_at - Utc::now()).num_seconds().max(0)
+        },
+        None => 0,
+    };
+
+    let projection = apy::project(&ProjectionInput {
+        amount,
+        duration_secs,
+        apr_bps: parameters.reward_apr_bps,
+        epoch_duration_secs: parameters.epoch_duration_seconds,
+        activation_delay_secs,
+        compounding: query.compound,
+    })
+    .map_err(projection_error)?;
+
+    Ok(Json(projection))
+}
@@ -0,0 +1,21 @@
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use std::convert::Infallible;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use crate::api::AppState;
+
+/// Stream database changes to the client as server-sent events
+pub async fn stream_changes(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Lagged receivers skip the missed changes rather than closing the stream
+    let stream = BroadcastStream::new(state.changes.subscribe()).filter_map(|change| {
+        let change = change.ok()?;
+        Event::default().event(change.entity()).json_data(&change).ok().map(Ok)
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,147 @@
+//! Streamed response bodies for exports too large to buffer
+//!
+//! Rows are read from a database cursor in a task of their own and handed to the response
+//! through a bounded channel, so a slow client pauses the cursor instead of rows piling up in
+//! memory, and a client that disconnects stops it. An error after the response has started
+//! aborts the body, so a failed export shows up as a truncated transfer rather than a
+//! well-formed partial file.
+
+use anyhow::{Error, Result};
+use axum::{
+    body::{Bytes, StreamBody},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Rows the cursor may read ahead of the client
+const BUFFERED_ROWS: usize = 256;
+
+/// Rows sent by `produce`, which runs in a task of its own with the sending half of a bounded
+/// channel; see [`forward`]
+pub fn spawn<T, F, Fut>(produce: F) -> ReceiverStream<Result<T>>
+where
+    T: Send + 'static,
+    F: FnOnce(mpsc::Sender<Result<T>>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(BUFFERED_ROWS);
+    tokio::spawn(produce(sender));
+    ReceiverStream::new(receiver)
+}
+
+/// Sends each row on, waiting while the channel is full, until the rows run out, one fails or
+/// the response is dropped
+pub async fn forward<T>(rows: impl Stream<Item = Result<T>>, sender: &mpsc::Sender<Result<T>>) {
+    tokio::pin!(rows);
+    while let Some(row) = rows.next().await {
+        let failed = row.is_err();
+        if sender.send(row).await.is_err() || failed {
+            break;
+        }
+    }
+}
+
+/// Rows as a JSON array
+pub fn json<T>(rows: impl Stream<Item = Result<T>> + Send + 'static) -> Response
+where
+    T: Serialize,
+{
+    let mut first = true;
+    let items = rows.map(move |row| {
+        let mut chunk = if std::mem::take(&mut first) { Vec::new() } else { vec![b','] };
+        serde_json::to_writer(&mut chunk, &row?)?;
+        Ok::<_, Error>(Bytes::from(chunk))
+    });
+    let body = tokio_stream::once(Ok(Bytes::from_static(b"[")))
+        .chain(items)
+        .chain(tokio_stream::once(Ok(Bytes::from_static(b"]"))));
+
+    ([(header::CONTENT_TYPE, "application/json")], StreamBody::new(body)).into_response()
+}
+
+/// Rows as CSV under `header_row`, each rendered to one or more lines by `render`
+pub fn csv<T>(
+    rows: impl Stream<Item = Result<T>> + Send + 'static,
+    header_row: &'static str,
+    mut render: impl FnMut(&T) -> String + Send + 'static,
+) -> Response {
+    let lines = rows.map(move |row| row.map(|row| Bytes::from(render(&row))));
+    let body = tokio_stream::once(Ok(Bytes::from_static(header_row.as_bytes()))).chain(lines);
+
+    ([(header::CONTENT_TYPE, "text/csv; charset=utf-8")], StreamBody::new(body)).into_response()
+}
+
+/// A CSV field, quoted when it holds a separator, quote or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use axum::body::HttpBody;
+
+    async fn collect(response: Response) -> Result<String> {
+        let mut body = response.into_body();
+        let mut collected = Vec::new();
+        while let Some(chunk) = body.data().await {
+            collected.extend_from_slice(&chunk.map_err(|err| anyhow!("{}", err))?);
+        }
+        Ok(String::from_utf8(collected)?)
+    }
+
+    fn numbers(count: u32) -> ReceiverStream<Result<u32>> {
+        spawn(move |sender| async move {
+            forward(tokio_stream::iter((1..=count).map(Ok)), &sender).await;
+        })
+    }
+
+    #[tokio::test]
+    async fn rows_are_rendered_as_json_arrays() {
+        assert_eq!(collect(json(numbers(0))).await.unwrap(), "[]");
+        assert_eq!(collect(json(numbers(3))).await.unwrap(), "[1,2,3]");
+    }
+
+    #[tokio::test]
+    async fn rows_are_rendered_as_csv_under_their_header() {
+        let csv = collect(csv(numbers(2), "n,square\n", |n| format!("{},{}\n", n, n * n))).await.unwrap();
+        assert_eq!(csv, "n,square\n1,1\n2,4\n");
+    }
+
+    #[tokio::test]
+    async fn a_failed_row_aborts_the_body() {
+        let rows = spawn(|sender| async move {
+            forward(tokio_stream::iter([Ok(1), Err(anyhow!("cursor lost")), Ok(3)]), &sender).await;
+        });
+        assert!(collect(json(rows)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn a_dropped_response_stops_the_cursor() {
+        let (read, mut reads) = mpsc::unbounded_channel();
+        let rows = spawn(move |sender| async move {
+            let counted = tokio_stream::iter(0..).map(move |n| {
+                let _ = read.send(n);
+                Ok(n)
+            });
+            forward(counted, &sender).await;
+        });
+        drop(rows);
+
+        let mut last = 0;
+        while let Some(n) = reads.recv().await {
+            last = n;
+        }
+        assert!(last <= BUFFERED_ROWS as u64 + 1, "read {} rows for a dropped response", last);
+    }
+}
@@ -0,0 +1,28 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::ApiResult;
+use crate::api::AppState;
+use crate::models::treasury::{ReportPeriod, TreasuryReport};
+
+/// Options for the treasury report
+#[derive(Debug, Deserialize)]
+pub struct TreasuryReportQuery {
+    #[serde(default)]
+    period: ReportPeriod,
+    /// Number of most recent periods to include
+    periods: Option<i64>,
+}
+
+/// Fee revenue per period and type, with the treasury balance recorded here and on-chain
+pub async fn get_treasury_report(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<TreasuryReportQuery>,
+) -> ApiResult<Json<TreasuryReport>> {
+    Ok(Json(state.treasury.report(query.period, query.periods).await?))
+}
@@ -0,0 +1,311 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::api::auth::{AdminAuth, WalletAuth};
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::limits::SubmissionLimits;
+use crate::api::screening_handlers::screen_registration;
+use crate::api::withdrawals::WithdrawalAllowance;
+use crate::api::{streaming, AppState};
+use crate::db::{
+    BalanceRepository, BlockchainRequestRepository, DbAccess, ReferralRepository, UnitOfWork, UserLimitRepository,
+    UserRepository,
+};
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::balance::{UserBalance, WithdrawableBalance};
+use crate::models::blockchain_request::{BlockchainRequest, RequestHistoryFilter};
+use crate::models::referral::ReferralSummary;
+use crate::models::user::{
+    CreateUserRequest, KycStatus, PublicUserProfile, UpdateUserRequest, User, UserExportFormat, UserExportQuery,
+    UserFilter,
+};
+use crate::models::user_limit::{UpdateUserLimitsRequest, UserLimitOverrides, UserLimitReport};
+use crate::models::wallet::WalletAddress;
+use crate::services::cache::keys;
+use crate::services::webhooks::WebhookDispatcher;
+
+/// Register a user profile for the authenticated wallet
+pub async fn create_user(
+    caller: WalletAuth,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateUserRequest>,
+) -> ApiResult<(StatusCode, Json<User>)> {
+    caller.ensure_is(&payload.wallet_address)?;
+
+    let users = UserRepository::new(state.db.pg.clone());
+
+    if users.get_by_wallet(&payload.wallet_address).await?.is_some() {
+        return Err(ApiError::Validation(format!(
+            "A user is already registered for wallet {}",
+            payload.wallet_address
+        )));
+    }
+
+    let referrer = match &payload.referrer_wallet {
+        Some(referrer_wallet) if *referrer_wallet == payload.wallet_address => {
+            return Err(ApiError::Validation("A wallet can't refer itself".to_string()));
+        }
+        Some(referrer_wallet) => Some(
+            users.get_by_wallet(referrer_wallet).await?
+                .ok_or_else(|| ApiError::Validation(format!("Referrer {} is not registered", referrer_wallet)))?,
+        ),
+        None => None,
+    };
+
+    screen_registration(&state, &payload.wallet_address).await?;
+
+    let mut uow = UnitOfWork::begin(&state.db.pg).await?;
+    let user = UserRepository::create_in(uow.conn(), &payload).await?;
+    if let Some(referrer) = referrer {
+        ReferralRepository::create_in(uow.conn(), user.id, referrer.id).await?;
+    }
+    uow.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+/// Get the users a wallet referred and the referral bonuses they have earned it
+pub async fn get_user_referrals(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Json<ReferralSummary>> {
+    let user = UserRepository::new(state.db.pool(DbAccess::Read)).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let referrals = ReferralRepository::new(state.db.pool(DbAccess::Read));
+    let (referred_by, total_earned, referees) = tokio::try_join!(
+        referrals.get_referrer_wallet(user.id),
+        referrals.total_earned(user.id),
+        referrals.list_referees(user.id),
+    )?;
+
+    Ok(Json(ReferralSummary {
+        referred_by,
+        total_earned,
+        referees,
+        wallet_address: user.wallet_address,
+    }))
+}
+
+/// Get the stored profile of a wallet
+///
+/// The wallet itself, authenticated, gets its full profile; anyone else gets the public view.
+pub async fn get_user_profile(
+    caller: Option<WalletAuth>,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Response> {
+    let user = UserRepository::new(state.db.pool(DbAccess::Read)).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    Ok(match caller {
+        Some(WalletAuth(caller)) if caller == wallet_address => Json(user).into_response(),
+        _ => Json(PublicUserProfile::from(user)).into_response(),
+    })
+}
+
+/// Get the off-chain balance of a wallet in the contract's asset
+pub async fn get_user_balance(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Json<UserBalance>> {
+    let user = UserRepository::new(state.db.pool(DbAccess::Read)).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let balances = BalanceRepository::new(state.db.pool(DbAccess::Read));
+    let asset = state.chain.asset();
+
+    let balance = state.cache
+        .get_or_load(&keys::user_balance(user.id), || async { balances.get(user.id, &asset.symbol).await })
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("No balance recorded for {}", wallet_address)))?;
+
+    Ok(Json(balance))
+}
+
+/// Preview how much a wallet could withdraw right now, as a withdrawal submission would be
+/// checked
+pub async fn get_user_withdrawable(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Json<WithdrawableBalance>> {
+    let allowance = WithdrawalAllowance::load(&state, &wallet_address, &state.chain.asset()).await?;
+    if !allowance.is_registered() {
+        return Err(ApiError::NotFound(format!("User {} not found", wallet_address)));
+    }
+
+    Ok(Json(allowance.preview()))
+}
+
+/// List a wallet's requests, newest first, optionally filtered by type and status
+pub async fn get_user_requests(
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+    Query(filter): Query<RequestHistoryFilter>,
+) -> ApiResult<Json<Vec<BlockchainRequest>>> {
+    let user = UserRepository::new(state.db.pool(DbAccess::Read)).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let requests = BlockchainRequestRepository::new(state.db.pool(DbAccess::Read))
+        .list_by_user(user.id, &filter)
+        .await?;
+
+    Ok(Json(requests))
+}
+
+/// List users, optionally filtered by KYC status and creation time
+pub async fn list_users(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(filter): Query<UserFilter>,
+) -> ApiResult<Json<Vec<User>>> {
+    let users = UserRepository::new(state.db.pool(DbAccess::Read)).list(&filter).await?;
+
+    Ok(Json(users))
+}
+
+/// Header row of the user export CSV
+const USER_CSV_HEADER: &str =
+    "ID,Wallet Address,Email,KYC Status,KYC Timestamp,KYC Reference,Created At,Updated At\n";
+
+/// Export every user matching the filter as JSON or CSV, streamed as they're read from the
+/// database
+pub async fn export_users(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Query(query): Query<UserExportQuery>,
+) -> ApiResult<Response> {
+    let users = UserRepository::new(state.db.pool(DbAccess::Read));
+    let filter = query.filter();
+    let rows = streaming::spawn(move |sender| async move {
+        streaming::forward(users.stream(&filter), &sender).await;
+    });
+
+    Ok(match query.format {
+        UserExportFormat::Json => streaming::json(rows),
+        UserExportFormat::Csv => (
+            [(header::CONTENT_DISPOSITION, "attachment; filename=\"users.csv\"")],
+            streaming::csv(rows, USER_CSV_HEADER, user_csv_row),
+        )
+            .into_response(),
+    })
+}
+
+fn user_csv_row(user: &User) -> String {
+    let kyc_status = match user.kyc_status {
+        KycStatus::Pending => "pending",
+        KycStatus::Approved => "approved",
+        KycStatus::Rejected => "rejected",
+    };
+
+    format!(
+        "{},{},{},{},{},{},{},{}\n",
+        user.id,
+        streaming::csv_field(&user.wallet_address),
+        streaming::csv_field(user.email.as_deref().unwrap_or("")),
+        kyc_status,
+        user.kyc_timestamp.map(|at| at.to_rfc3339()).unwrap_or_default(),
+        streaming::csv_field(user.kyc_reference.as_deref().unwrap_or("")),
+        user.created_at.to_rfc3339(),
+        user.updated_at.to_rfc3339(),
+    )
+}
+
+/// Update a user's profile or KYC state
+///
+/// A change of KYC state is an operator's review decision and is published to webhook
+/// subscribers.
+pub async fn update_user(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+    Json(payload): Json<UpdateUserRequest>,
+) -> ApiResult<Json<User>> {
+    let users = UserRepository::new(state.db.pg.clone());
+
+    let existing = users.get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    let user = users.update(existing.id, &payload).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+
+    if user.kyc_status != existing.kyc_status {
+        let published = WebhookDispatcher::new(state.db.pg.clone())
+            .publish_kyc_status_change(&user, &existing.kyc_status, None)
+            .await;
+        if let Err(err) = published {
+            tracing::warn!("Failed to publish KYC status change of {}: {}", wallet_address, err);
+        }
+    }
+
+    Ok(Json(user))
+}
+
+/// Get a wallet's volume limits, where each comes from, and what it has used of them
+pub async fn get_user_limits(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<Json<UserLimitReport>> {
+    let limits = SubmissionLimits::load(&state, std::slice::from_ref(&wallet_address)).await?;
+    let user = limits.user(&wallet_address)
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+    let overrides = UserLimitRepository::new(state.db.pg.clone()).get(user.id).await?;
+
+    Ok(Json(UserLimitReport {
+        usage: limits.volume_usage(&wallet_address),
+        asset: state.chain.asset().symbol,
+        overrides,
+        wallet_address,
+    }))
+}
+
+/// Override a user's volume limits; limits left out fall back to the system parameters
+pub async fn update_user_limits(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+    Json(payload): Json<UpdateUserLimitsRequest>,
+) -> ApiResult<Json<UserLimitOverrides>> {
+    payload.validate().map_err(ApiError::Validation)?;
+
+    let user = UserRepository::new(state.db.pg.clone()).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+    let limits = UserLimitRepository::new(state.db.pg.clone());
+    let before = limits.get(user.id).await?;
+    let updated = limits.upsert(user.id, &payload).await?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::ParameterChange, format!("user_limits:{}", wallet_address))
+                .with_change(before.as_ref(), Some(&updated)),
+        )
+        .await;
+
+    Ok(Json(updated))
+}
+
+/// Remove a user's volume limit overrides, so the system parameters apply again
+pub async fn reset_user_limits(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(wallet_address): Path<WalletAddress>,
+) -> ApiResult<StatusCode> {
+    let user = UserRepository::new(state.db.pg.clone()).get_by_wallet(&wallet_address).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", wallet_address)))?;
+    let removed = UserLimitRepository::new(state.db.pg.clone()).delete(user.id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} has no limit overrides", wallet_address)))?;
+
+    state.audit
+        .record(
+            NewAuditEntry::new(AuditAction::ParameterChange, format!("user_limits:{}", wallet_address))
+                .with_change(Some(&removed), None::<&UserLimitOverrides>),
+        )
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,235 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::auth::AdminAuth;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::AppState;
+use crate::db::DbAccess;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::webhook::{
+    CreateWebhookEndpointRequest, CreatedWebhookEndpoint, RotateWebhookSecretRequest, RotatedWebhookSecret, UpdateWebhookEndpointRequest,
+    WebhookDelivery, WebhookEndpoint, WebhookEnvelope, WebhookReplayPage, WebhookReplayQuery,
+};
+use crate::services::webhooks::{verify_signature, WebhookSecrets, WebhookStore, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// How long a rotated-out secret keeps signing deliveries unless the request says otherwise
+const DEFAULT_ROTATION_GRACE_SECS: i64 = 24 * 60 * 60;
+
+/// Pagination parameters for delivery logs
+#[derive(Debug, Deserialize)]
+pub struct DeliveryLogQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+fn validate_endpoint_url(url: &str) -> ApiResult<()> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| ApiError::Validation(format!("Invalid webhook URL: {}", e)))?;
+
+    if parsed.scheme() != "https" && parsed.scheme() != "http" {
+        return Err(ApiError::Validation("Webhook URL must use http or https".to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_secret(secret: &str) -> ApiResult<()> {
+    if secret.len() < 16 {
+        return Err(ApiError::Validation("Webhook secret must be at least 16 characters".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Checks an admin-chosen secret, or generates one
+fn secret_or_generated(secret: Option<String>) -> ApiResult<String> {
+    match secret {
+        Some(secret) => validate_secret(&secret).map(|_| secret),
+        None => Ok(format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())),
+    }
+}
+
+/// List registered webhook endpoints
+pub async fn list_webhook_endpoints(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+) -> ApiResult<Json<Vec<WebhookEndpoint>>> {
+    let endpoints = WebhookStore::new(state.db.pool(DbAccess::Read)).list_endpoints().await?;
+
+    Ok(Json(endpoints))
+}
+
+/// Register a new webhook endpoint, returning its secret
+pub async fn create_webhook_endpoint(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateWebhookEndpointRequest>,
+) -> ApiResult<(StatusCode, Json<CreatedWebhookEndpoint>)> {
+    validate_endpoint_url(&payload.url)?;
+    let secret = secret_or_generated(payload.secret.clone())?;
+
+    let sealed = WebhookSecrets::new(state.secrets).seal(&secret).await?;
+    let endpoint = WebhookStore::new(state.db.pg).create_endpoint(&payload, &sealed).await?;
+
+    Ok((StatusCode::CREATED, Json(CreatedWebhookEndpoint { endpoint, secret })))
+}
+
+/// Get a webhook endpoint
+pub async fn get_webhook_endpoint(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(endpoint_id): Path<Uuid>,
+) -> ApiResult<Json<WebhookEndpoint>> {
+    let endpoint = WebhookStore::new(state.db.pg).get_endpoint(endpoint_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Webhook endpoint {} not found", endpoint_id)))?;
+
+    Ok(Json(endpoint))
+}
+
+/// Update a webhook endpoint
+pub async fn update_webhook_endpoint(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(endpoint_id): Path<Uuid>,
+    Json(payload): Json<UpdateWebhookEndpointRequest>,
+) -> ApiResult<Json<WebhookEndpoint>> {
+    if let Some(url) = &payload.url {
+        validate_endpoint_url(url)?;
+    }
+    let sealed = match &payload.secret {
+        Some(secret) => {
+            validate_secret(secret)?;
+            Some(WebhookSecrets::new(state.secrets).seal(secret).await?)
+        },
+        None => None,
+    };
+
+    let endpoint = WebhookStore::new(state.db.pg).update_endpoint(endpoint_id, &payload, sealed.as_deref()).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Webhook endpoint {} not found", endpoint_id)))?;
+
+    Ok(Json(endpoint))
+}
+
+/// Replace a webhook endpoint's secret, signing with both secrets during the grace period
+pub async fn rotate_webhook_secret(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(endpoint_id): Path<Uuid>,
+    payload: Option<Json<RotateWebhookSecretRequest>>,
+) -> ApiResult<Json<RotatedWebhookSecret>> {
+    let Json(payload) = payload.unwrap_or_default();
+
+    let secret = secret_or_generated(payload.secret)?;
+
+    let grace_period_secs = payload.grace_period_secs.unwrap_or(DEFAULT_ROTATION_GRACE_SECS);
+    if !(0..=7 * DEFAULT_ROTATION_GRACE_SECS).contains(&grace_period_secs) {
+        return Err(ApiError::Validation("Grace period must be between 0 and 7 days".to_string()));
+    }
+    let previous_expires_at = (grace_period_secs > 0).then(|| Utc::now() + Duration::seconds(grace_period_secs));
+
+    let sealed = WebhookSecrets::new(state.secrets).seal(&secret).await?;
+    let endpoint = WebhookStore::new(state.db.pg).rotate_secret(endpoint_id, &sealed, previous_expires_at).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Webhook endpoint {} not found", endpoint_id)))?;
+
+    Ok(Json(RotatedWebhookSecret {
+        endpoint_id: endpoint.id,
+        secret,
+        previous_secret_expires_at: endpoint.previous_secret_expires_at,
+    }))
+}
+
+/// Delete a webhook endpoint
+pub async fn delete_webhook_endpoint(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(endpoint_id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    if !WebhookStore::new(state.db.pg).delete_endpoint(endpoint_id).await? {
+        return Err(ApiError::NotFound(format!("Webhook endpoint {} not found", endpoint_id)));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List the delivery log of a webhook endpoint
+pub async fn list_webhook_deliveries(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(endpoint_id): Path<Uuid>,
+    Query(query): Query<DeliveryLogQuery>,
+) -> ApiResult<Json<Vec<WebhookDelivery>>> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let deliveries = WebhookStore::new(state.db.pool(DbAccess::Read))
+        .list_deliveries(endpoint_id, limit, offset)
+        .await?;
+
+    Ok(Json(deliveries))
+}
+
+/// Requeue a delivery for immediate redelivery
+pub async fn retry_webhook_delivery(
+    _admin: AdminAuth,
+    State(state): State<AppState>,
+    Path(delivery_id): Path<Uuid>,
+) -> ApiResult<StatusCode> {
+    if !WebhookStore::new(state.db.pg).requeue_delivery(delivery_id).await? {
+        return Err(ApiError::NotFound(format!("Webhook delivery {} not found", delivery_id)));
+    }
+
+    state.audit
+        .record(NewAuditEntry::new(AuditAction::Reconciliation, format!("webhook_delivery:{}:retry", delivery_id)))
+        .await;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Fetch the deliveries an endpoint missed, for its consumer
+///
+/// The consumer signs `"{endpoint_id}:{after}"` with the endpoint secret the same way deliveries
+/// are signed, sending the timestamp and signature headers.
+pub async fn replay_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(endpoint_id): Path<Uuid>,
+    Query(query): Query<WebhookReplayQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Json<WebhookReplayPage>> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(signature), Some(timestamp)) = (header(SIGNATURE_HEADER), header(TIMESTAMP_HEADER)) else {
+        return Err(ApiError::Unauthorized("Missing webhook signature headers".to_string()));
+    };
+    let timestamp: i64 = timestamp.parse()
+        .map_err(|_| ApiError::Unauthorized("Invalid webhook timestamp".to_string()))?;
+
+    // Unknown and disabled endpoints are rejected like bad signatures so ids can't be probed
+    let store = WebhookStore::new(state.db.pool(DbAccess::Read));
+    let endpoint = store.get_endpoint(endpoint_id).await?
+        .filter(|endpoint| endpoint.is_active)
+        .ok_or_else(|| ApiError::Unauthorized("Signature does not match".to_string()))?;
+
+    let now = Utc::now();
+    let secrets = WebhookSecrets::new(state.secrets).signing_secrets(&endpoint, now).await?;
+    let secrets: Vec<&str> = secrets.iter().map(|secret| secret.expose_secret().as_str()).collect();
+    let signed = format!("{}:{}", endpoint_id, query.after);
+    verify_signature(&secrets, signature, timestamp, signed.as_bytes(), now.timestamp())
+        .map_err(|err| ApiError::Unauthorized(err.to_string()))?;
+
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    let deliveries = store.list_deliveries_after(endpoint_id, query.after, limit + 1).await?;
+    let has_more = deliveries.len() as i64 > limit;
+    let deliveries: Vec<WebhookEnvelope> = deliveries.iter().take(limit as usize).map(WebhookEnvelope::from).collect();
+
+    Ok(Json(WebhookReplayPage {
+        next_cursor: deliveries.last().map_or(query.after, |delivery| delivery.sequence),
+        has_more,
+        deliveries,
+    }))
+}
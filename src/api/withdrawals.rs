@@ -0,0 +1,193 @@
+//! Withdrawal allowances
+//!
+//! A wallet can withdraw its active balance less the withdrawals it already has pending, as far
+//! as the liquidity not yet promised to other withdrawals, its KYC level's epoch limit and its
+//! volume limits allow, and nothing while the epoch is closing. Withdrawal submissions are checked against the same
+//! allowance the preview endpoint reports, so a form validated against the preview isn't refused.
+
+use anyhow::Context;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::limits::SubmissionLimits;
+use crate::api::AppState;
+use crate::db::BalanceRepository;
+use crate::models::amount::Amount;
+use crate::models::asset::Asset;
+use crate::models::balance::{WithdrawableBalance, WithdrawalLimit};
+use crate::models::blockchain_request::RequestType;
+use crate::models::wallet::WalletAddress;
+
+/// What a wallet's withdrawals are checked against
+pub(crate) struct WithdrawalAllowance {
+    wallet_address: WalletAddress,
+    limits: SubmissionLimits,
+    submissions_paused: bool,
+    active_balance: BigDecimal,
+    pending_withdrawals: BigDecimal,
+    /// Liquidity left for new withdrawals across the protocol
+    available_liquidity: BigDecimal,
+    /// Asset the balance and submitted amounts are in
+    asset: Asset,
+}
+
+impl WithdrawalAllowance {
+    /// Reads the wallet's balance in `asset` and submission limits, the liquidity left for
+    /// withdrawals and whether the epoch is closing
+    pub(crate) async fn load(state: &AppState, wallet_address: &WalletAddress, asset: &Asset) -> ApiResult<Self> {
+        let (limits, submissions_paused, available_liquidity) = tokio::try_join!(
+            SubmissionLimits::load(state, std::slice::from_ref(wallet_address)),
+            async { Ok::<_, ApiError>(state.epochs.submissions_paused().await?) },
+            async { Ok::<_, ApiError>(state.liquidity.uncommitted().await?) },
+        )?;
+
+        let balance = match limits.user(wallet_address) {
+            Some(user) => BalanceRepository::new(state.db.pg.clone()).get(user.id, &asset.symbol).await?,
+            None => None,
+        };
+        let (active_balance, pending_withdrawals) = match balance {
+            Some(balance) => (
+                BigDecimal::from_str(&balance.active_balance).context("Invalid active balance")?,
+                BigDecimal::from_str(&balance.pending_withdrawals).context("Invalid pending withdrawals")?,
+            ),
+            None => (BigDecimal::from(0), BigDecimal::from(0)),
+        };
+
+        Ok(Self {
+            wallet_address: wallet_address.clone(),
+            limits,
+            submissions_paused,
+            active_balance,
+            pending_withdrawals,
+            available_liquidity,
+            asset: asset.clone(),
+        })
+    }
+
+    /// Whether a user is registered for the wallet
+    pub(crate) fn is_registered(&self) -> bool {
+        self.limits.user(&self.wallet_address).is_some()
+    }
+
+    /// The most the wallet can withdraw right now, and what caps it
+    pub(crate) fn withdrawable(&self) -> (BigDecimal, WithdrawalLimit) {
+        if self.submissions_paused {
+            return (BigDecimal::from(0), WithdrawalLimit::EpochClosing);
+        }
+        let Ok(kyc_remaining) = self.kyc_remaining() else {
+            return (BigDecimal::from(0), WithdrawalLimit::KycRequired);
+        };
+
+        let free_balance = &self.active_balance - &self.pending_withdrawals;
+        withdrawable(&free_balance, &self.available_liquidity, kyc_remaining.as_ref(), self.volume_remaining().as_ref())
+    }
+
+    /// Checks a withdrawal of `amount`, refusing it with the error its submission gets
+    pub(crate) fn check(&self, amount: Amount) -> ApiResult<()> {
+        // KYC refusals read the same as those of other request types
+        let amount = self.asset.to_decimal(amount);
+        self.limits.check(&self.wallet_address, &RequestType::Withdrawal, &amount, &BigDecimal::default())?;
+
+        let (withdrawable, limited_by) = self.withdrawable();
+        if amount <= withdrawable {
+            return Ok(());
+        }
+
+        let (code, limit) = match limited_by {
+            WithdrawalLimit::Balance => ("INSUFFICIENT_BALANCE", "its active balance less pending withdrawals"),
+            WithdrawalLimit::Liquidity => ("INSUFFICIENT_LIQUIDITY", "the liquidity left for withdrawals"),
+            WithdrawalLimit::KycLimit => ("KYC_LIMIT_EXCEEDED", "its KYC level's limit for the epoch"),
+            WithdrawalLimit::VolumeLimit => ("VOLUME_LIMIT_EXCEEDED", "its withdrawal volume limits"),
+            WithdrawalLimit::KycRequired => ("KYC_REQUIRED", "its KYC status"),
+            WithdrawalLimit::EpochClosing => ("EPOCH_PROCESSING", "the closing epoch"),
+        };
+        Err(ApiError::Forbidden {
+            code,
+            message: format!(
+                "Wallet {} can withdraw at most {} right now, limited by {}",
+                self.wallet_address, withdrawable, limit
+            ),
+        })
+    }
+
+    /// The allowance as the preview endpoint reports it
+    pub(crate) fn preview(&self) -> WithdrawableBalance {
+        let (withdrawable, limited_by) = self.withdrawable();
+
+        WithdrawableBalance {
+            wallet_address: self.wallet_address.clone(),
+            asset: self.asset.symbol.clone(),
+            active_balance: self.active_balance.to_string(),
+            pending_withdrawals: self.pending_withdrawals.to_string(),
+            available_liquidity: self.available_liquidity.to_string(),
+            kyc_epoch_remaining: self.kyc_remaining().ok().flatten().map(|remaining| remaining.to_string()),
+            volume_remaining: self.volume_remaining().map(|remaining| remaining.to_string()),
+            withdrawable: withdrawable.to_string(),
+            limited_by,
+        }
+    }
+
+    /// What's left of the wallet's withdrawal limit for the epoch, `None` when its KYC level has
+    /// no limit
+    fn kyc_remaining(&self) -> ApiResult<Option<BigDecimal>> {
+        self.limits.remaining(&self.wallet_address, &RequestType::Withdrawal)
+    }
+
+    /// What's left of the tightest of the wallet's withdrawal volume limits, `None` when none
+    /// limits it
+    fn volume_remaining(&self) -> Option<BigDecimal> {
+        self.limits.volume_remaining(&self.wallet_address, &RequestType::Withdrawal)
+    }
+}
+
+/// The smallest of the caps on a withdrawal; ties go to the balance, then liquidity, then the
+/// KYC limit
+fn withdrawable(
+    free_balance: &BigDecimal,
+    available_liquidity: &BigDecimal,
+    kyc_remaining: Option<&BigDecimal>,
+    volume_remaining: Option<&BigDecimal>,
+) -> (BigDecimal, WithdrawalLimit) {
+    let caps = [
+        Some((free_balance, WithdrawalLimit::Balance)),
+        Some((available_liquidity, WithdrawalLimit::Liquidity)),
+        kyc_remaining.map(|remaining| (remaining, WithdrawalLimit::KycLimit)),
+        volume_remaining.map(|remaining| (remaining, WithdrawalLimit::VolumeLimit)),
+    ];
+    let (cap, limited_by) = caps
+        .into_iter()
+        .flatten()
+        .min_by(|(a, _), (b, _)| a.cmp(b))
+        .expect("balance and liquidity always cap withdrawals");
+
+    (cap.max(&BigDecimal::from(0)).clone(), limited_by)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn the_smallest_cap_limits_withdrawals() {
+        let (amount, limit) = withdrawable(&tokens("500"), &tokens("10000"), Some(&tokens("2500")), None);
+        assert_eq!((amount, limit), (tokens("500"), WithdrawalLimit::Balance));
+
+        let (amount, limit) = withdrawable(&tokens("500"), &tokens("120.5"), None, None);
+        assert_eq!((amount, limit), (tokens("120.5"), WithdrawalLimit::Liquidity));
+
+        let (amount, limit) = withdrawable(&tokens("500"), &tokens("500"), Some(&tokens("75")), Some(&tokens("75")));
+        assert_eq!((amount, limit), (tokens("75"), WithdrawalLimit::KycLimit));
+
+        let (amount, limit) = withdrawable(&tokens("500"), &tokens("500"), Some(&tokens("75")), Some(&tokens("40")));
+        assert_eq!((amount, limit), (tokens("40"), WithdrawalLimit::VolumeLimit));
+
+        // Pending withdrawals can exceed the balance after a correction
+        let (amount, limit) = withdrawable(&tokens("-20"), &tokens("500"), None, None);
+        assert_eq!((amount, limit), (tokens("0"), WithdrawalLimit::Balance));
+    }
+}
@@ -0,0 +1,283 @@
+//! `lsrwa-cli devnet`: a working local environment in one command
+//!
+//! Connects to a substrate-contracts-node, starting one when nothing is listening, deploys the
+//! contract from Alice, funds test accounts and adds them to the contract's KYC allowlist, seeds
+//! the database with those accounts as its first users and writes the settings the server needs
+//! to `.env.local`. Running it again reuses the node, the contract and the funds already there.
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use sqlx::types::BigDecimal;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use subxt::ext::sp_core::{sr25519, Pair as PairTrait};
+use subxt::utils::AccountId32;
+
+use lsrwa_express_rust::config::{DatabaseConfig, Settings};
+use lsrwa_express_rust::contract::deployment::{
+    contract_address, deterministic_salt, hex_string, ContractArtifact, Deployer,
+};
+use lsrwa_express_rust::contract::{self, message_input};
+use lsrwa_express_rust::db::seed::SeedOptions;
+use lsrwa_express_rust::db::{self, DeploymentRepository};
+use lsrwa_express_rust::models::deployment::NewContractDeployment;
+use lsrwa_express_rust::models::wallet::WalletAddress;
+
+/// Network devnet deployments are recorded under
+const NETWORK: &str = "local";
+
+/// Dev account the contract is deployed from, endowed on every `--dev` chain
+const OWNER_SEED: &str = "//Alice";
+
+/// Key devnet receipts are signed with; it sends no transactions, so it needs no endowment
+const RECEIPT_SEED: &str = "//Receipts";
+
+/// Base units per token
+const UNIT: u128 = 1_000_000_000_000;
+
+/// Directory a started node logs to
+const NODE_DIR: &str = "target/devnet";
+
+/// How long a started node gets to accept connections
+const NODE_STARTUP: Duration = Duration::from_secs(60);
+
+/// Options of `lsrwa-cli devnet`
+#[derive(Args)]
+pub struct DevnetOptions {
+    /// Node to use; a substrate-contracts-node is started when nothing is listening there
+    #[arg(long, default_value = "ws://127.0.0.1:9944")]
+    rpc_url: String,
+
+    /// substrate-contracts-node binary to start
+    #[arg(long, default_value = "substrate-contracts-node")]
+    node_binary: PathBuf,
+
+    /// Contract bundle to deploy, written by `cargo contract build --release`
+    #[arg(long, default_value = "contracts/target/ink/lsrwa_express_contract.contract")]
+    bundle: PathBuf,
+
+    /// Number of test accounts, derived as `//user//1`, `//user//2`, ...
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    accounts: u32,
+
+    /// Tokens each test account is topped up to
+    #[arg(long, default_value_t = 1_000)]
+    funds: u64,
+
+    /// Seed of the demo data
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    /// File the settings are written to
+    #[arg(long, default_value = ".env.local")]
+    env_file: PathBuf,
+}
+
+/// A dev account derived from a seed phrase
+struct TestAccount {
+    seed_phrase: String,
+    account: AccountId32,
+}
+
+/// Brings up the devnet described by `options`
+pub async fn bootstrap(settings: &Settings, options: DevnetOptions) -> Result<()> {
+    let artifact = ContractArtifact::load(&options.bundle)
+        .context("Build the contract first with `cargo contract build --release --manifest-path contracts/Cargo.toml`")?;
+
+    // The database goes first, so nothing is deployed when it can't be reached
+    let database_config = DatabaseConfig::from_settings(settings).context("Invalid database configuration")?;
+    db::migration::ensure_database_exists(&database_config.url).await.context("Failed to ensure database exists")?;
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+
+    let owner = sr25519::Pair::from_string(OWNER_SEED, None).expect("dev seed phrases are valid");
+    let deployer = connect_or_start(&options, owner).await?;
+    println!("Node:         {} (genesis {})", options.rpc_url, deployer.genesis_hash());
+
+    let address = deploy(&deployer, &artifact, &DeploymentRepository::new(pool.pg.clone())).await?;
+    println!("Contract:     {}", address);
+
+    let accounts = (1..=options.accounts).map(test_account).collect::<Vec<_>>();
+    let funds = options.funds as u128 * UNIT;
+    for test_account in &accounts {
+        let balance = deployer.free_balance(&test_account.account).await?;
+        if balance < funds {
+            deployer.transfer(&test_account.account, funds - balance).await?;
+        }
+        println!("Funded:       {} ({})", test_account.account, test_account.seed_phrase);
+    }
+
+    let wallets = accounts.iter().map(|test_account| test_account.account.clone()).collect::<Vec<_>>();
+    let selector = artifact
+        .messages
+        .get("set_kyc_approvals")
+        .context("The contract has no 'set_kyc_approvals' message")?;
+    deployer
+        .contract(address.clone())
+        .submit(
+            &deployer.signer(),
+            message_input(*selector, (wallets.clone(), true)),
+            contract::estimate_gas_for_kyc_update(wallets.len()),
+        )
+        .await
+        .context("Failed to add the test accounts to the KYC allowlist")?;
+    println!("Approved:     {} test accounts on the contract's KYC allowlist", wallets.len());
+
+    let seed_options = SeedOptions {
+        seed: options.seed,
+        reset: true,
+        wallets: wallets.iter().cloned().map(WalletAddress::from).collect(),
+        ..SeedOptions::default()
+    };
+    let summary = db::seed::seed(&pool.pg, &seed_options).await?;
+    println!(
+        "Seeded:       {} users, {} epochs and {} requests (seed {})",
+        summary.users, summary.epochs, summary.requests, seed_options.seed
+    );
+
+    write_env_file(&options, &deployer, &address, &accounts, &database_config.url)?;
+    println!("\n✅ Devnet ready; its settings are in {}", options.env_file.display());
+    Ok(())
+}
+
+/// Connects to the node at `--rpc-url`, starting one when nothing is listening there
+async fn connect_or_start(options: &DevnetOptions, owner: sr25519::Pair) -> Result<Deployer> {
+    if let Ok(deployer) = Deployer::connect(&options.rpc_url, owner.clone()).await {
+        return Ok(deployer);
+    }
+
+    let node = start_node(options)?;
+    println!(
+        "Started:      {} as process {}, logging to {}/node.log; it keeps running afterwards",
+        options.node_binary.display(),
+        node.id(),
+        NODE_DIR
+    );
+
+    let started = Instant::now();
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        match Deployer::connect(&options.rpc_url, owner.clone()).await {
+            Ok(deployer) => return Ok(deployer),
+            Err(e) if started.elapsed() > NODE_STARTUP => {
+                return Err(e.context(format!("The started node isn't listening at {}", options.rpc_url)))
+            },
+            Err(_) => {},
+        }
+    }
+}
+
+/// Starts a dev chain node listening on `--rpc-url`'s port
+fn start_node(options: &DevnetOptions) -> Result<Child> {
+    let port = options
+        .rpc_url
+        .trim_end_matches('/')
+        .rsplit(':')
+        .next()
+        .and_then(|port| port.parse::<u16>().ok())
+        .with_context(|| format!("Nothing is listening at {}, which has no port to start a node on", options.rpc_url))?;
+
+    fs::create_dir_all(NODE_DIR).with_context(|| format!("Failed to create {}", NODE_DIR))?;
+    let log = fs::File::create(Path::new(NODE_DIR).join("node.log")).context("Failed to create the node log")?;
+
+    // The node logs to stderr
+    Command::new(&options.node_binary)
+        .args(["--dev", "--rpc-port", &port.to_string()])
+        .stdout(Stdio::null())
+        .stderr(log)
+        .spawn()
+        .with_context(|| {
+            format!(
+                "Nothing is listening at {} and {} couldn't be started",
+                options.rpc_url,
+                options.node_binary.display()
+            )
+        })
+}
+
+/// Deploys `artifact` from the owner, unless it's already deployed. Each build gets its own
+/// address, so a rebuilt contract starts with fresh storage.
+async fn deploy(deployer: &Deployer, artifact: &ContractArtifact, deployments: &DeploymentRepository) -> Result<AccountId32> {
+    let salt = deterministic_salt(NETWORK, &format!("devnet:{}", hex_string(&artifact.code_hash)));
+    let address = contract_address(&deployer.account(), &artifact.code_hash, &artifact.constructor, &salt);
+
+    match deployer.onchain_code_hash(&address).await? {
+        Some(code_hash) if code_hash == artifact.code_hash => return Ok(address),
+        Some(_) => bail!("A different build is deployed at {}; restart the node to start over", address),
+        None => {},
+    }
+
+    let estimate = deployer.dry_run(artifact, &salt).await?;
+    let instantiated = deployer.instantiate(artifact, &salt, &estimate).await?;
+    let verified = deployer.onchain_code_hash(&instantiated.address).await? == Some(artifact.code_hash);
+    if !verified {
+        bail!("The code deployed at {} doesn't match the local build", instantiated.address);
+    }
+
+    deployments
+        .record(&NewContractDeployment {
+            network: NETWORK.to_string(),
+            chain_genesis_hash: deployer.genesis_hash(),
+            contract_address: instantiated.address.to_string(),
+            code_hash: hex_string(&artifact.code_hash),
+            salt: hex_string(&salt),
+            deployer: deployer.account().to_string(),
+            transaction_hash: instantiated.transaction_hash,
+            block_number: instantiated.block_number as i64,
+            gas_ref_time: estimate.gas_required.ref_time as i64,
+            gas_proof_size: estimate.gas_required.proof_size as i64,
+            storage_deposit: BigDecimal::from_str(&estimate.storage_deposit.to_string())?,
+            verified,
+        })
+        .await?;
+
+    Ok(instantiated.address)
+}
+
+/// Test account `index`, derived as `//user//<index>`
+fn test_account(index: u32) -> TestAccount {
+    let seed_phrase = format!("//user//{}", index);
+    let pair = sr25519::Pair::from_string(&seed_phrase, None).expect("dev seed phrases are valid");
+
+    TestAccount {
+        seed_phrase,
+        account: AccountId32::from(pair.public()),
+    }
+}
+
+/// Writes the settings the server and the other binaries need to run against the devnet
+fn write_env_file(
+    options: &DevnetOptions,
+    deployer: &Deployer,
+    address: &AccountId32,
+    accounts: &[TestAccount],
+    database_url: &str,
+) -> Result<()> {
+    let mut contents = String::from("# Written by `lsrwa-cli devnet`; loaded before .env, so these settings win\n");
+    contents.push_str("# Test accounts, all funded and KYC-approved:\n");
+    for test_account in accounts {
+        contents.push_str(&format!("#   {} {}\n", test_account.seed_phrase, test_account.account));
+    }
+    contents.push_str(&format!(
+        "CHAIN_NETWORK={}\n\
+         SUBSTRATE_RPC_URL={}\n\
+         CHAIN_GENESIS_HASH={}\n\
+         CONTRACT_ADDRESS={}\n\
+         CONTRACT_OWNER_SEED_PHRASE={}\n\
+         WALLET_SEED_PHRASE={}\n\
+         RECEIPT_SIGNING_SEED_PHRASE={}\n\
+         DATABASE_URL={}\n",
+        NETWORK,
+        options.rpc_url,
+        deployer.genesis_hash(),
+        address,
+        OWNER_SEED,
+        accounts[0].seed_phrase,
+        RECEIPT_SEED,
+        database_url,
+    ));
+
+    fs::write(&options.env_file, contents).with_context(|| format!("Failed to write {}", options.env_file.display()))
+}
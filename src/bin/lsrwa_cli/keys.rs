@@ -0,0 +1,302 @@
+//! `lsrwa-cli keys`: operator signing keys in the secrets backend
+//!
+//! A key is generated or imported into a role's staged slot, funded from outside, then rotated
+//! in once it can sign: the staged account must hold the minimum signer balance and, for the
+//! contract owner, already own the contract, which has no message to hand ownership over. The
+//! receipt signing key sends no transactions, so it needs no funds and is rotated in directly;
+//! the key it replaces stays published so earlier receipts still verify.
+//! Services read keys from the backend on every signature, so a rotation takes effect within
+//! `SECRETS_REFRESH_SECS` without a restart.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Subcommand};
+use secrecy::SecretString;
+use sqlx::types::BigDecimal;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::str::FromStr;
+use subxt::ext::sp_core::{sr25519, Pair as PairTrait};
+use subxt::utils::AccountId32;
+
+use lsrwa_express_rust::config::{ChainNetwork, DatabaseConfig, Settings};
+use lsrwa_express_rust::contract::deployment::Deployer;
+use lsrwa_express_rust::contract::{message_input, selector};
+use lsrwa_express_rust::db::{self, DeploymentRepository};
+use lsrwa_express_rust::models::amount::Amount;
+use lsrwa_express_rust::models::audit::{AuditAction, NewAuditEntry};
+use lsrwa_express_rust::services::audit::AuditLog;
+use lsrwa_express_rust::services::keystore::{self, KeyRole};
+use lsrwa_express_rust::services::secrets::SecretStore;
+
+/// Roles `list` reports on
+const ROLES: [KeyRole; 3] = [KeyRole::Owner, KeyRole::Wallet, KeyRole::Receipt];
+
+/// Contract message returning the contract's owner
+const GET_OWNER: &str = "get_owner";
+
+#[derive(Subcommand)]
+pub enum KeysCommand {
+    /// Lists each role's active, staged and previous keys with their addresses, and their
+    /// balances when the node is reachable
+    List(NetworkOption),
+    /// Generates a key and stages it to replace a role's active key
+    Generate {
+        /// owner, wallet or receipt
+        role: KeyRole,
+        #[command(flatten)]
+        network: NetworkOption,
+    },
+    /// Imports a key and stages it to replace a role's active key
+    Import {
+        /// owner, wallet or receipt
+        role: KeyRole,
+        /// Read a seed phrase from stdin
+        #[arg(long, required_unless_present = "json", conflicts_with = "json")]
+        mnemonic_stdin: bool,
+        /// Import a polkadot.js JSON export; its password is read from stdin
+        #[arg(long)]
+        json: Option<PathBuf>,
+        #[command(flatten)]
+        network: NetworkOption,
+    },
+    /// Makes a role's staged key the active one, keeping the replaced key as its previous one
+    Rotate {
+        /// owner, wallet or receipt
+        role: KeyRole,
+        /// Tokens the staged account must hold; defaults to SELF_CHECK_MIN_SIGNER_BALANCE
+        #[arg(long)]
+        min_balance: Option<BigDecimal>,
+    },
+}
+
+#[derive(Args)]
+pub struct NetworkOption {
+    /// Network addresses are printed for; defaults to CHAIN_NETWORK
+    #[arg(long)]
+    network: Option<ChainNetwork>,
+}
+
+pub async fn run(settings: &Settings, secrets: &SecretStore, command: KeysCommand) -> Result<()> {
+    let keys = Keys { settings, secrets };
+
+    match command {
+        KeysCommand::List(NetworkOption { network }) => keys.list(keys.network(network)?).await,
+        KeysCommand::Generate { role, network } => {
+            let (pair, secret) = keystore::generate();
+            keys.stage(role, &pair, &secret, keys.network(network.network)?).await
+        },
+        KeysCommand::Import { role, mnemonic_stdin, json, network } => {
+            let (pair, secret) = match json {
+                Some(path) => {
+                    let export = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+                    let password = read_stdin_line("Password")?;
+                    keystore::import_json(&export, &password)?
+                },
+                None if mnemonic_stdin => {
+                    let phrase = read_stdin_line("Seed phrase")?;
+                    (keystore::keypair(&phrase).context("Invalid seed phrase")?, phrase)
+                },
+                None => bail!("Pass --mnemonic-stdin or --json"),
+            };
+            keys.stage(role, &pair, &secret, keys.network(network.network)?).await
+        },
+        KeysCommand::Rotate { role, min_balance } => keys.rotate(role, min_balance).await,
+    }
+}
+
+struct Keys<'a> {
+    settings: &'a Settings,
+    secrets: &'a SecretStore,
+}
+
+impl Keys<'_> {
+    fn network(&self, network: Option<ChainNetwork>) -> Result<ChainNetwork> {
+        match network {
+            Some(network) => Ok(network),
+            None => self.settings.get_or("CHAIN_NETWORK", ChainNetwork::Local),
+        }
+    }
+
+    fn rpc_url(&self) -> String {
+        self.settings.var("SUBSTRATE_RPC_URL")
+            .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string())
+    }
+
+    /// The key held under `name`, if any
+    async fn key(&self, name: &str) -> Result<Option<sr25519::Pair>> {
+        match self.secrets.get(name).await? {
+            Some(secret) => keystore::keypair(&secret).with_context(|| format!("{} is not a valid key", name)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self, network: ChainNetwork) -> Result<()> {
+        // Balances are a convenience; keys are listed whether or not the node answers. Nothing
+        // is signed, so any signer does.
+        let node = match Deployer::connect(&self.rpc_url(), sr25519::Pair::generate().0).await {
+            Ok(node) => Some(node),
+            Err(err) => {
+                println!("Balances unavailable: {:#}", err);
+                None
+            },
+        };
+
+        println!("Secrets backend: {}, network: {}", self.secrets.backend_name(), network);
+        for role in ROLES {
+            println!("\n{}", role);
+            for (slot, name) in [
+                ("active", role.secret_name().to_string()),
+                ("staged", role.next_secret_name()),
+                ("previous", role.previous_secret_name()),
+            ] {
+                let Some(pair) = self.key(&name).await? else {
+                    println!("  {:<9} not set ({})", slot, name);
+                    continue;
+                };
+
+                let balance = match &node {
+                    Some(node) if role.signs_transactions() => format!(", {} tokens", tokens(node.free_balance(&AccountId32::from(pair.public())).await?, network)),
+                    _ => String::new(),
+                };
+                println!("  {:<9} {}{}", slot, keystore::address(&pair, network), balance);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stores a key in a role's staged slot
+    async fn stage(&self, role: KeyRole, pair: &sr25519::Pair, secret: &SecretString, network: ChainNetwork) -> Result<()> {
+        let name = role.next_secret_name();
+        self.secrets.store(&name, secret).await?;
+        self.audit()
+            .await?
+            .record(NewAuditEntry::new(AuditAction::KeyChange, format!("key:{}:staged", role)).with_details(serde_json::json!({
+                "account": AccountId32::from(pair.public()).to_string(),
+            })))
+            .await;
+
+        println!("✅ Staged a new {} key as {}", role, name);
+        println!("   {} address: {}", network, keystore::address(pair, network));
+        if role.signs_transactions() {
+            println!("   Fund it, then run `lsrwa-cli keys rotate {}`", role);
+        } else {
+            println!("   Run `lsrwa-cli keys rotate {}` to make it active", role);
+        }
+        Ok(())
+    }
+
+    /// Checks the staged key can sign for the role, then makes it the active key
+    async fn rotate(&self, role: KeyRole, min_balance: Option<BigDecimal>) -> Result<()> {
+        let staged = self.secrets.get(&role.next_secret_name())
+            .await?
+            .with_context(|| format!("No {} key is staged; generate or import one first", role))?;
+        let staged_pair = keystore::keypair(&staged).with_context(|| format!("{} is not a valid key", role.next_secret_name()))?;
+        let staged_account = AccountId32::from(staged_pair.public());
+
+        let active = self.secrets.get(role.secret_name()).await?;
+        let active_account = match &active {
+            Some(active) => Some(AccountId32::from(keystore::keypair(active)?.public())),
+            None => None,
+        };
+        if active_account.as_ref() == Some(&staged_account) {
+            println!("The staged {} key {} is already active", role, staged_account);
+            return Ok(());
+        }
+
+        // Only keys that send transactions need funds to pay their fees
+        let details = if role.signs_transactions() {
+            let node = Deployer::connect(&self.rpc_url(), staged_pair).await?;
+            let minimum = match min_balance {
+                Some(minimum) => minimum,
+                None => self.settings.get_or("SELF_CHECK_MIN_SIGNER_BALANCE", BigDecimal::from(1))?,
+            };
+            let balance = tokens(node.free_balance(&staged_account).await?, self.network(None)?);
+            if balance < minimum {
+                bail!("{} holds {} tokens, below the minimum of {}; fund it before rotating", staged_account, balance, minimum);
+            }
+            println!("Balance:  {} holds {} tokens", staged_account, balance);
+
+            if role == KeyRole::Owner {
+                let contract = node.contract(self.contract_address().await?);
+                let owner: AccountId32 = contract
+                    .dry_run(&staged_account, message_input(selector(GET_OWNER), ()))
+                    .await
+                    .context("Failed to read the contract's owner")?;
+                if owner != staged_account {
+                    bail!(
+                        "The contract at {} is owned by {}, not the staged key {}; only the contract's owner can sign for it",
+                        contract.address, owner, staged_account
+                    );
+                }
+                println!("Owner:    {} owns {}", staged_account, contract.address);
+            }
+            serde_json::json!({ "balance": balance.to_string() })
+        } else {
+            serde_json::json!({})
+        };
+
+        if let Some(active) = &active {
+            self.secrets.store(&role.previous_secret_name(), active).await?;
+        }
+        self.secrets.store(role.secret_name(), &staged).await?;
+
+        self.audit()
+            .await?
+            .record(
+                NewAuditEntry::new(AuditAction::KeyChange, format!("key:{}", role))
+                    .with_change(active_account.map(|account| account.to_string()).as_ref(), Some(&staged_account.to_string()))
+                    .with_details(details),
+            )
+            .await;
+
+        println!("✅ {} is now the active {} key", staged_account, role);
+        if active.is_some() {
+            println!("   The replaced key is kept as {}", role.previous_secret_name());
+        }
+        Ok(())
+    }
+
+    /// Contract address from CONTRACT_ADDRESS, or else the network's live deployment
+    async fn contract_address(&self) -> Result<AccountId32> {
+        let address = match self.settings.var("CONTRACT_ADDRESS").ok().filter(|address| !address.is_empty()) {
+            Some(address) => address,
+            None => {
+                let network = self.network(None)?.to_string();
+                DeploymentRepository::new(self.pool().await?)
+                    .live(&network)
+                    .await?
+                    .map(|deployment| deployment.contract_address)
+                    .with_context(|| format!("No live {} deployment is registered; set CONTRACT_ADDRESS", network))?
+            },
+        };
+        AccountId32::from_str(&address).map_err(|e| anyhow!("Invalid contract address {}: {}", address, e))
+    }
+
+    async fn pool(&self) -> Result<sqlx::PgPool> {
+        let database_config = DatabaseConfig::from_settings(self.settings).context("Invalid database configuration")?;
+        let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+        Ok(pool.pg)
+    }
+
+    async fn audit(&self) -> Result<AuditLog> {
+        Ok(AuditLog::new(self.pool().await?))
+    }
+}
+
+/// Free balance in tokens of the network
+fn tokens(free: u128, network: ChainNetwork) -> BigDecimal {
+    Amount::from_units(free).to_decimal(network.token_decimals())
+}
+
+/// Reads one line from stdin, trimmed
+fn read_stdin_line(what: &str) -> Result<SecretString> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line).with_context(|| format!("Failed to read {} from stdin", what))?;
+    let line = line.trim_end_matches(['\r', '\n']).to_string();
+    if line.is_empty() {
+        bail!("{} is empty", what);
+    }
+    Ok(SecretString::new(line))
+}
@@ -0,0 +1,652 @@
+//! Operator command line for one-off tasks, built on the same services as the API server.
+//!
+//! Usage: `cargo run --bin lsrwa-cli -- <COMMAND>`; `--help` lists the commands and their options.
+//!
+//! Configuration, secrets and logging are loaded exactly as the server loads them. Changes made
+//! from here are recorded in the admin audit log under `cli:<login user>`, or `--actor`.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use lsrwa_express_rust::api::blockchain::BlockchainState;
+use lsrwa_express_rust::config::{Config, DatabaseConfig, Environment, LoggingConfig, SecretsConfig, Settings};
+use lsrwa_express_rust::db::{self, DbPools};
+use lsrwa_express_rust::db::seed::SeedOptions;
+use lsrwa_express_rust::logging::{self, Redactor};
+use lsrwa_express_rust::models::audit::{AuditAction, NewAuditEntry};
+use lsrwa_express_rust::models::blockchain_request::RequestType;
+use lsrwa_express_rust::models::system_parameter::UpdateSystemParameterRequest;
+use lsrwa_express_rust::models::treasury::ReportPeriod;
+use lsrwa_express_rust::models::user::{KycStatus, UpdateUserRequest};
+use lsrwa_express_rust::models::wallet::WalletAddress;
+use lsrwa_express_rust::services::alerting::Alerter;
+use lsrwa_express_rust::services::audit::{AuditContext, AuditLog};
+use lsrwa_express_rust::services::cache::Cache;
+use lsrwa_express_rust::services::epochs::{EpochProcessingDeps, EpochProcessingService};
+use lsrwa_express_rust::services::event_bus::{self, EventPublisher};
+use lsrwa_express_rust::services::indexer;
+use lsrwa_express_rust::services::interest::InterestAccrualService;
+use lsrwa_express_rust::services::kyc::KycSyncWorker;
+use lsrwa_express_rust::services::liquidity::LiquidityPlanningService;
+use lsrwa_express_rust::services::rewards::RewardCalculationService;
+use lsrwa_express_rust::services::risk::RiskParameterService;
+use lsrwa_express_rust::services::secrets::SecretStore;
+use lsrwa_express_rust::services::shutdown::{wait_for_signal, Shutdown};
+use lsrwa_express_rust::services::treasury::TreasuryService;
+use lsrwa_express_rust::services::BlockchainService;
+
+mod devnet;
+mod keys;
+
+/// LSRWA Express operator tasks
+#[derive(Parser)]
+#[command(name = "lsrwa-cli", version)]
+struct Cli {
+    /// Operator the audit log attributes changes to
+    #[arg(long, global = true)]
+    actor: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Applies pending database migrations
+    Migrate {
+        /// Only report pending, failed, modified and unknown migrations
+        #[arg(long)]
+        status: bool,
+    },
+    /// Populates the database with deterministic demo data
+    Seed {
+        /// Seed of the generator; the same seed always produces the same data
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+        #[arg(long, default_value_t = 25)]
+        users: u32,
+        #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(1..))]
+        epochs: u32,
+        /// Clear previously seeded data first
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Contract event indexing
+    #[command(subcommand)]
+    Index(IndexCommand),
+    /// Epoch lifecycle
+    #[command(subcommand)]
+    Epoch(EpochCommand),
+    /// Deposit, withdrawal and borrow requests
+    #[command(subcommand)]
+    Requests(RequestsCommand),
+    /// System parameters
+    #[command(subcommand)]
+    Params(ParamsCommand),
+    /// KYC approvals
+    #[command(subcommand)]
+    Kyc(KycCommand),
+    /// Pushes pending KYC approvals and risk parameters on-chain and reports treasury drift
+    Reconcile,
+    /// Sets up a local node, contract, funded test accounts and seeded database, and writes
+    /// their settings to .env.local
+    Devnet(devnet::DevnetOptions),
+    /// Operator signing keys: generate, import, list and rotate
+    #[command(subcommand)]
+    Keys(keys::KeysCommand),
+    /// Snapshots of protocol state for refreshing other environments
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// Re-indexes a range of blocks; events already recorded are skipped
+    Backfill {
+        #[arg(long)]
+        from: u64,
+        /// Last block to index; defaults to the chain head
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Records the contract's events in a range of blocks as decoder test fixtures, each next to
+    /// the event the indexer makes of it
+    Capture {
+        #[arg(long)]
+        from: u64,
+        /// Last block to read; defaults to --from
+        #[arg(long)]
+        to: Option<u64>,
+        /// Directory the recordings are written to
+        #[arg(long, default_value = "tests/fixtures/events")]
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum EpochCommand {
+    /// Closes an epoch, or resumes a failed close, and waits for the sequence to finish
+    Close {
+        /// Epoch to close; defaults to the active epoch
+        #[arg(long)]
+        epoch: Option<i32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RequestsCommand {
+    /// Batch-processes the active epoch's pending requests of a type without closing it
+    Process {
+        #[arg(long = "type", value_enum)]
+        request_type: RequestKind,
+    },
+}
+
+#[derive(Subcommand)]
+enum ParamsCommand {
+    /// Sets a system parameter
+    Set {
+        name: String,
+        value: String,
+        #[arg(long)]
+        description: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum KycCommand {
+    /// Approves registered wallets and adds them to the contract's KYC allowlist
+    Approve {
+        #[arg(required = true)]
+        wallets: Vec<WalletAddress>,
+        /// Reference recorded on the approved users
+        #[arg(long, default_value = "manual")]
+        reference: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// Writes the core tables, last indexed block and contract state to a directory, with
+    /// personal data scrubbed
+    Export {
+        dir: PathBuf,
+        /// Keep emails, KYC references and screening matches
+        #[arg(long)]
+        keep_pii: bool,
+    },
+    /// Replaces the core tables' contents with a snapshot; refused in production
+    Restore {
+        dir: PathBuf,
+        /// Restore a snapshot that wasn't scrubbed
+        #[arg(long)]
+        allow_pii: bool,
+    },
+}
+
+/// Request type as named on the command line
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RequestKind {
+    Deposit,
+    Withdrawal,
+    Borrow,
+}
+
+impl From<RequestKind> for RequestType {
+    fn from(kind: RequestKind) -> Self {
+        match kind {
+            RequestKind::Deposit => RequestType::Deposit,
+            RequestKind::Withdrawal => RequestType::Withdrawal,
+            RequestKind::Borrow => RequestType::Borrow,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables from .env.local, written by `lsrwa-cli devnet`, then .env
+    dotenv::from_filename(".env.local").ok();
+    dotenv::dotenv().ok();
+
+    let Cli { actor, command } = Cli::parse();
+
+    // Read secrets and set up logging the way the server does
+    let mut settings = Settings::load().context("Failed to load configuration files")?;
+    let secrets_config = SecretsConfig::from_settings(&settings).context("Invalid secrets configuration")?;
+    let secrets = SecretStore::from_config(&secrets_config, &settings).context("Failed to initialize secrets backend")?;
+    secrets.resolve_settings(&mut settings).await.context("Failed to read secrets")?;
+    let logging_config = LoggingConfig::from_settings(&settings).context("Invalid logging configuration")?;
+    logging::init(&logging_config, settings.environment(), Redactor::new(settings.secret_values()))
+        .context("Failed to initialize logging")?;
+
+    let context = AuditContext {
+        actor: actor.unwrap_or_else(|| format!("cli:{}", std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()))),
+        ip_address: None,
+        request_id: None,
+    };
+
+    context.scope(run(command, settings, secrets)).await
+}
+
+async fn run(command: Command, settings: Settings, secrets: SecretStore) -> Result<()> {
+    match command {
+        Command::Migrate { status } => migrate(&settings, status).await,
+        Command::Seed { seed, users, epochs, reset } => {
+            let options = SeedOptions {
+                seed,
+                users,
+                epochs,
+                reset,
+                ..SeedOptions::default()
+            };
+            let database_config = DatabaseConfig::from_settings(&settings).context("Invalid database configuration")?;
+            db::migration::ensure_database_exists(&database_config.url).await.context("Failed to ensure database exists")?;
+            let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+
+            let summary = db::seed::seed(&pool.pg, &options).await?;
+            println!(
+                "✅ Seeded {} users, {} epochs and {} requests (seed {})",
+                summary.users, summary.epochs, summary.requests, options.seed
+            );
+            Ok(())
+        },
+        Command::Devnet(options) => devnet::bootstrap(&settings, options).await,
+        Command::Keys(command) => keys::run(&settings, &secrets, command).await,
+        Command::Snapshot(SnapshotCommand::Restore { dir, allow_pii }) => restore_snapshot(&settings, &dir, allow_pii).await,
+        command => {
+            let config = Config::from_settings(&settings).context("Invalid configuration")?;
+            let services = Services::connect(config, secrets).await?;
+
+            match command {
+                Command::Index(IndexCommand::Backfill { from, to }) => services.backfill(from, to).await,
+                Command::Index(IndexCommand::Capture { from, to, dir }) => {
+                    services.capture_events(from, to.unwrap_or(from), &dir).await
+                },
+                Command::Epoch(EpochCommand::Close { epoch }) => services.close_epoch(epoch).await,
+                Command::Requests(RequestsCommand::Process { request_type }) => {
+                    services.process_requests(request_type.into()).await
+                },
+                Command::Params(ParamsCommand::Set { name, value, description }) => {
+                    services.set_parameter(&name, value, description).await
+                },
+                Command::Kyc(KycCommand::Approve { wallets, reference }) => services.approve_kyc(&wallets, &reference).await,
+                Command::Reconcile => services.reconcile().await,
+                Command::Snapshot(SnapshotCommand::Export { dir, keep_pii }) => services.export_snapshot(&dir, !keep_pii).await,
+                Command::Migrate { .. }
+                | Command::Seed { .. }
+                | Command::Devnet(_)
+                | Command::Keys(_)
+                | Command::Snapshot(SnapshotCommand::Restore { .. }) => {
+                    unreachable!("handled without connecting the services")
+                },
+            }
+        },
+    }
+}
+
+/// Applies pending migrations, or only reports on them
+async fn migrate(settings: &Settings, status_only: bool) -> Result<()> {
+    let database_config = DatabaseConfig::from_settings(settings).context("Invalid database configuration")?;
+    db::migration::ensure_database_exists(&database_config.url).await.context("Failed to ensure database exists")?;
+    let pool = db::pg::create_pg_pool(&database_config.url, &database_config.pool)
+        .await
+        .context("Failed to connect to Postgres")?;
+
+    let status = db::migration::migration_status(&pool).await?;
+    for (label, versions) in [
+        ("Pending", &status.pending),
+        ("Failed", &status.failed),
+        ("Modified", &status.modified),
+        ("Unknown", &status.unknown),
+    ] {
+        if !versions.is_empty() {
+            println!("{}: {:?}", label, versions);
+        }
+    }
+
+    if status_only {
+        return Ok(());
+    }
+    if !status.is_runnable() {
+        bail!("Migrations can't be applied until the failed, modified or unknown versions are resolved");
+    }
+    if status.pending.is_empty() {
+        println!("✅ Database is up to date");
+        return Ok(());
+    }
+
+    db::migration::run_migrations(&pool).await?;
+    println!("✅ Applied {} migrations", status.pending.len());
+    Ok(())
+}
+
+/// Restores a snapshot into this environment's database
+async fn restore_snapshot(settings: &Settings, dir: &Path, allow_pii: bool) -> Result<()> {
+    if settings.environment() == Environment::Production {
+        bail!("Snapshots can't be restored into production");
+    }
+
+    let manifest = db::snapshot::load_manifest(dir)?;
+    if !manifest.scrubbed && !allow_pii {
+        bail!("The snapshot holds personal data; pass --allow-pii to restore it anyway");
+    }
+
+    let database_config = DatabaseConfig::from_settings(settings).context("Invalid database configuration")?;
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+    let rows = db::snapshot::restore(&pool.pg, dir, &manifest).await?;
+
+    AuditLog::new(pool.pg.clone())
+        .record(NewAuditEntry::new(AuditAction::Reconciliation, "cli:snapshot:restore").with_details(serde_json::json!({
+            "snapshot_created_at": manifest.created_at,
+            "schema_version": manifest.schema_version,
+            "scrubbed": manifest.scrubbed,
+            "rows": rows,
+        })))
+        .await;
+
+    println!("✅ Restored {} rows from the snapshot taken at {}", rows, manifest.created_at);
+    match manifest.last_indexed_block {
+        Some(block) => println!("   Events were indexed up to block {}; backfill from block {} to catch up", block, block + 1),
+        None => println!("   The snapshot has no indexed events"),
+    }
+    if let Some(state) = &manifest.contract_state {
+        println!(
+            "   The contract {} held {} at block {}, with {} in pending deposits and {} in pending withdrawals",
+            state.contract_address, state.balance, state.block_number, state.total_pending_deposits, state.total_pending_withdrawals
+        );
+    }
+    Ok(())
+}
+
+/// The services the commands share, connected as the server connects them
+struct Services {
+    config: Config,
+    pool: DbPools,
+    cache: Cache,
+    alerts: Alerter,
+    blockchain: Arc<BlockchainService>,
+    parameters: db::SystemParameterRepository,
+    flags: db::FeatureFlagRepository,
+    rewards: RewardCalculationService,
+    events: EventPublisher,
+    audit: AuditLog,
+}
+
+impl Services {
+    async fn connect(config: Config, secrets: SecretStore) -> Result<Self> {
+        let pool = db::init_db(&config.database).await.context("Failed to initialize database")?;
+        let cache = Cache::from_config(&config.cache).await.context("Failed to initialize cache")?;
+        let alerts = Alerter::from_config(&config.alerts).context("Failed to initialize alert channels")?;
+
+        let blockchain = Arc::new(
+            BlockchainService::new(
+                pool.clone(),
+                config.blockchain.clone(),
+                secrets,
+            )
+            .await
+            .context("Failed to initialize blockchain service")?,
+        );
+
+        let parameters = db::SystemParameterRepository::new(pool.pg.clone(), Duration::from_secs(60), cache.clone());
+        let flags = db::FeatureFlagRepository::new(pool.pg.clone(), config.environment(), Duration::from_secs(30), cache.clone());
+
+        let bus = event_bus::bus_from_config(&config.event_bus).await.context("Failed to connect to event bus")?;
+        let events = EventPublisher::new(bus, config.event_bus.topic_prefix.clone());
+        let rewards = RewardCalculationService::new(pool.pg.clone(), parameters.clone(), flags.clone(), events.clone());
+        let audit = AuditLog::new(pool.pg.clone());
+
+        Ok(Self {
+            config,
+            pool,
+            cache,
+            alerts,
+            blockchain,
+            parameters,
+            flags,
+            rewards,
+            events,
+            audit,
+        })
+    }
+
+    fn epochs(&self) -> EpochProcessingService {
+        let liquidity = LiquidityPlanningService::new(self.pool.pg.clone(), self.blockchain.clone(), self.alerts.clone());
+        EpochProcessingService::new(
+            self.pool.pg.clone(),
+            EpochProcessingDeps {
+                cache: self.cache.clone(),
+                blockchain: self.blockchain.clone(),
+                rewards: self.rewards.clone(),
+                interest: InterestAccrualService::new(self.pool.pg.clone(), self.parameters.clone()),
+                liquidity,
+                alerts: self.alerts.clone(),
+                events: self.events.clone(),
+            },
+        )
+    }
+
+    /// Re-indexes `from..=to`, stopping between blocks on Ctrl-C
+    async fn backfill(&self, from: u64, to: Option<u64>) -> Result<()> {
+        let to = match to {
+            Some(to) => to,
+            None => self.blockchain.get_current_block_number().await.context("Failed to read the chain head")?,
+        };
+        if from > to {
+            bail!("--from {} is after --to {}", from, to);
+        }
+
+        let event_queue = indexer::EventQueue::new(
+            self.pool.pg.clone(),
+            self.cache.clone(),
+            self.rewards.clone(),
+            self.events.clone(),
+            self.config.indexer.batch_size,
+            Duration::from_millis(self.config.indexer.flush_interval_ms),
+            indexer::QueueDepth::new(),
+        );
+        let processor = indexer::EventProcessor::new(
+            &self.pool,
+            event_queue,
+            self.blockchain.clone(),
+            Arc::new(RwLock::new(BlockchainState::default())),
+            self.alerts.clone(),
+            &self.config.alerts,
+            self.config.indexer.workers,
+        )
+        .await
+        .context("Failed to initialize event processor")?;
+
+        let shutdown = Shutdown::new();
+        let signal_shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            signal_shutdown.trigger();
+        });
+
+        let events = processor.backfill(from, to, self.config.indexer.backfill_queue_depth, &shutdown).await?;
+        println!("✅ Indexed {} events from blocks {} to {}", events, from, to);
+        Ok(())
+    }
+
+    /// Writes each contract event in `from..=to` to `dir` as `<block>-<position>-<event>.json`
+    async fn capture_events(&self, from: u64, to: u64, dir: &Path) -> Result<()> {
+        if from > to {
+            bail!("--from {} is after --to {}", from, to);
+        }
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let mut captured = 0;
+        for block_number in from..=to {
+            let emitted = self.blockchain.contract_events_at(block_number).await?;
+            for (position, emitted) in emitted.iter().enumerate() {
+                let decimals = self.blockchain.token_decimals();
+                let decoded = (indexer::decode_contract_event(emitted, decimals)?, indexer::event_recording(emitted, decimals)?);
+                let (Some(event), Some(recording)) = decoded else {
+                    println!("⚠️  Skipped an unknown event in block {} with topics {:?}", block_number, emitted.topics);
+                    continue;
+                };
+
+                let path = dir.join(format!("{:06}-{}-{}.json", block_number, position, event.event_type));
+                let json = serde_json::to_string_pretty(&recording).context("Failed to serialize recording")?;
+                std::fs::write(&path, json + "\n").with_context(|| format!("Failed to write {}", path.display()))?;
+                captured += 1;
+            }
+        }
+
+        println!("✅ Recorded {} events from blocks {} to {} in {}", captured, from, to, dir.display());
+        Ok(())
+    }
+
+    async fn close_epoch(&self, epoch_id: Option<i32>) -> Result<()> {
+        let epoch_id = match epoch_id {
+            Some(epoch_id) => epoch_id,
+            None => db::EpochRepository::new(self.pool.pg.clone())
+                .active()
+                .await?
+                .context("There is no active epoch")?
+                .id,
+        };
+
+        let result = self.epochs().close(epoch_id).await?;
+        println!(
+            "✅ Closed epoch {}: {} deposits, {} withdrawals and {} borrows processed",
+            result.epoch_id, result.deposits_processed, result.withdrawals_processed, result.borrows_processed
+        );
+        Ok(())
+    }
+
+    async fn process_requests(&self, request_type: RequestType) -> Result<()> {
+        self.epochs().process_pending(request_type.clone()).await?;
+        println!("✅ Processed pending {} requests", request_type);
+        Ok(())
+    }
+
+    async fn set_parameter(&self, name: &str, value: String, description: Option<String>) -> Result<()> {
+        let before = self.parameters.get(name).await?;
+        let request = UpdateSystemParameterRequest {
+            parameter_value: value,
+            description,
+            updated_by: None,
+        };
+        let parameter = self.parameters.update(name, &request).await?
+            .with_context(|| format!("System parameter {} not found", name))?;
+
+        self.audit
+            .record(
+                NewAuditEntry::new(AuditAction::ParameterChange, format!("system_parameter:{}", name))
+                    .with_change(before.as_ref(), Some(&parameter)),
+            )
+            .await;
+
+        println!("✅ {} = {}", parameter.parameter_name, parameter.parameter_value);
+        Ok(())
+    }
+
+    /// Approves the wallets' users, then submits them to the allowlist in one call
+    async fn approve_kyc(&self, wallets: &[WalletAddress], reference: &str) -> Result<()> {
+        let users = db::UserRepository::new(self.pool.pg.clone());
+
+        for wallet in wallets {
+            let user = users.get_by_wallet(wallet).await?
+                .with_context(|| format!("Wallet {} is not registered", wallet))?;
+            let request = UpdateUserRequest {
+                email: None,
+                kyc_status: Some(KycStatus::Approved),
+                kyc_timestamp: Some(Utc::now()),
+                kyc_reference: Some(reference.to_string()),
+            };
+            let approved = users.update(user.id, &request).await?
+                .with_context(|| format!("User {} disappeared", user.id))?;
+
+            self.audit
+                .record(
+                    NewAuditEntry::new(AuditAction::Reconciliation, format!("user:{}:kyc", wallet))
+                        .with_change(Some(&user), Some(&approved)),
+                )
+                .await;
+        }
+
+        let wallets: Vec<String> = wallets.iter().map(WalletAddress::to_string).collect();
+        let tx_hash = self.blockchain.submit_kyc_approvals(&wallets).await?;
+        println!("✅ Approved {} wallets on-chain in {}", wallets.len(), tx_hash);
+        Ok(())
+    }
+
+    /// Exports a snapshot, with the contract's state read alongside
+    async fn export_snapshot(&self, dir: &Path, scrub: bool) -> Result<()> {
+        let contract_state = self.blockchain.contract_state().await?;
+        let manifest = db::snapshot::export(&self.pool.pg, dir, scrub, Some(contract_state)).await?;
+
+        self.audit
+            .record(NewAuditEntry::new(AuditAction::Reconciliation, "cli:snapshot:export").with_details(serde_json::json!({
+                "dir": dir.display().to_string(),
+                "schema_version": manifest.schema_version,
+                "scrubbed": manifest.scrubbed,
+            })))
+            .await;
+
+        let rows = manifest.tables.iter().map(|file| file.rows).sum::<u64>();
+        println!("✅ Exported {} rows of {} tables to {}", rows, manifest.tables.len(), dir.display());
+        if !scrub {
+            println!("   The snapshot holds personal data; keep it out of shared storage");
+        }
+        Ok(())
+    }
+
+    /// Drains the KYC allowlist backlog, pushes unsynced risk parameters and reports treasury drift
+    async fn reconcile(&self) -> Result<()> {
+        let kyc_sync = KycSyncWorker::new(
+            self.pool.pg.clone(),
+            self.blockchain.clone(),
+            self.flags.clone(),
+            self.config.kyc.onchain_sync.clone(),
+        );
+        let mut kyc_synced = 0;
+        loop {
+            let synced = kyc_sync.run_once().await?;
+            if synced == 0 {
+                break;
+            }
+            kyc_synced += synced;
+        }
+        println!("KYC allowlist: {} approvals synced", kyc_synced);
+
+        let risk = RiskParameterService::new(self.pool.pg.clone(), self.parameters.clone(), self.blockchain.clone());
+        let risk_version = risk.sync_onchain().await?;
+        match &risk_version {
+            Some(version) => println!("Risk parameters: version {} is {:?}", version.version, version.onchain_status),
+            None => println!("Risk parameters: no versions recorded"),
+        }
+
+        let treasury = TreasuryService::new(
+            self.pool.pg.clone(),
+            self.blockchain.clone(),
+            self.config.treasury.clone(),
+            self.alerts.clone(),
+        );
+        let report = treasury.report(ReportPeriod::Month, Some(1)).await?;
+        println!(
+            "Treasury: {} recorded, {} on-chain, drift {}",
+            report.recorded_balance,
+            report.on_chain_balance.as_deref().unwrap_or("unknown"),
+            report.drift.as_deref().unwrap_or("unknown")
+        );
+
+        self.audit
+            .record(NewAuditEntry::new(AuditAction::Reconciliation, "cli:reconcile").with_details(serde_json::json!({
+                "kyc_synced": kyc_synced,
+                "risk_parameters_version": risk_version.as_ref().map(|version| version.version),
+                "treasury_drift": report.drift,
+            })))
+            .await;
+
+        println!("✅ Reconciliation complete");
+        Ok(())
+    }
+}
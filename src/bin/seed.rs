@@ -0,0 +1,68 @@
+//! Populates the database with realistic demo data for staging and local frontend development.
+//!
+//! Usage: `cargo run --bin seed -- [--seed N] [--users N] [--epochs N] [--reset]`
+//!
+//! The same seed always produces the same users, requests and rewards; timestamps are laid out
+//! relative to the time of seeding so the most recent epoch is the active one.
+
+use anyhow::{anyhow, bail, Context, Result};
+use lsrwa_express_rust::config::{DatabaseConfig, Settings};
+use lsrwa_express_rust::db;
+use lsrwa_express_rust::db::seed::SeedOptions;
+
+fn parse_options() -> Result<SeedOptions> {
+    let mut options = SeedOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => options.seed = parse_value(&arg, args.next())?,
+            "--users" => options.users = parse_value(&arg, args.next())?,
+            "--epochs" => options.epochs = parse_value(&arg, args.next())?,
+            "--reset" => options.reset = true,
+            other => bail!("Unknown argument '{}'", other),
+        }
+    }
+
+    if options.epochs == 0 {
+        bail!("--epochs must be at least 1");
+    }
+
+    Ok(options)
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T> {
+    value
+        .ok_or_else(|| anyhow!("{} requires a value", flag))?
+        .parse()
+        .map_err(|_| anyhow!("{} must be a number", flag))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load environment variables from .env.local, written by `lsrwa-cli devnet`, then .env
+    dotenv::from_filename(".env.local").ok();
+    dotenv::dotenv().ok();
+
+    let options = parse_options()?;
+
+    println!("=== LSRWA Express Database Seed ===");
+
+    let settings = Settings::load().context("Failed to load configuration files")?;
+    let database_config = DatabaseConfig::from_settings(&settings).context("Invalid database configuration")?;
+
+    db::migration::ensure_database_exists(&database_config.url).await.context("Failed to ensure database exists")?;
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
+
+    let summary = db::seed::seed(&pool.pg, &options).await?;
+
+    println!(
+        "✅ Seeded {} users, {} epochs and {} requests (seed {})",
+        summary.users,
+        summary.epochs,
+        summary.requests,
+        options.seed
+    );
+
+    Ok(())
+}
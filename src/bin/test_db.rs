@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
+use lsrwa_express_rust::config::{DatabaseConfig, Settings};
 use lsrwa_express_rust::db;
 use sqlx::PgPool;
 
@@ -10,13 +11,16 @@ async fn main() -> Result<()> {
 
     println!("=== LSRWA Express Database Schema Test ===");
     
+    let settings = Settings::load().context("Failed to load configuration files")?;
+    let database_config = DatabaseConfig::from_settings(&settings).context("Invalid database configuration")?;
+    
     // Ensure database exists
-    db::migration::ensure_database_exists().await.context("Failed to ensure database exists")?;
+    db::migration::ensure_database_exists(&database_config.url).await.context("Failed to ensure database exists")?;
     
     println!("✅ Database exists or was created");
     
     // Get database connection pool
-    let pool = db::init_db().await.context("Failed to create database pool")?;
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
     
     println!("✅ Database migrations applied successfully");
     
@@ -136,6 +140,85 @@ async fn insert_test_data(pool: &PgPool) -> Result<()> {
     
     println!("📝 Created test batch processing event with ID: {}", processing_id);
     
+    // Queue an indexed event
+    sqlx::query(
+        r#"
+        INSERT INTO lsrwa_express.event_queue (
+            id, event_type, block_number, transaction_hash, request_id,
+            wallet_address, amount, request_type, timestamp, raw_data, status
+        )
+        VALUES ($1, 0, 12345678, $2, 1, $3, '500.0', 'deposit', NOW(), '{}', 0)
+        "#,
+    )
+    .bind("test-event-1")
+    .bind("0xabcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890")
+    .bind("0x1234567890123456789012345678901234567890")
+    .execute(pool)
+    .await
+    .context("Failed to insert queued event")?;
+    
+    println!("📝 Queued test event");
+    
+    // Track the submitted extrinsic
+    sqlx::query(
+        r#"
+        INSERT INTO lsrwa_express.blockchain_transactions (
+            transaction_hash, call_name, wallet_address, user_id, blockchain_request_id, status, block_number
+        )
+        VALUES ($1, 'create_deposit_request', $2, $3, $4, 'finalized', 12345678)
+        "#,
+    )
+    .bind("0xabcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890")
+    .bind("0x1234567890123456789012345678901234567890")
+    .bind(user_id)
+    .bind(request_id as i32)
+    .execute(pool)
+    .await
+    .context("Failed to insert blockchain transaction")?;
+    
+    println!("📝 Recorded test blockchain transaction");
+    
+    // Register a webhook endpoint with one delivery
+    let endpoint_id = sqlx::query_as::<_, (uuid::Uuid,)>(
+        r#"
+        INSERT INTO lsrwa_express.webhook_endpoints (url, secret, event_types)
+        VALUES ('https://example.com/hooks', 'test-secret-0123456789', ARRAY['deposit_processed'])
+        RETURNING id
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to insert webhook endpoint")?
+    .0;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO lsrwa_express.webhook_deliveries (endpoint_id, event_type, payload)
+        VALUES ($1, 'deposit_processed', '{"request_id": 1}')
+        "#,
+    )
+    .bind(endpoint_id)
+    .execute(pool)
+    .await
+    .context("Failed to insert webhook delivery")?;
+    
+    println!("📝 Created test webhook endpoint with ID: {}", endpoint_id);
+    
+    // Issue an API key
+    sqlx::query(
+        r#"
+        INSERT INTO lsrwa_express.api_keys (name, key_prefix, key_hash, scopes, created_by)
+        VALUES ('test key', 'lsrwa_te', $1, ARRAY['read'], $2)
+        "#,
+    )
+    .bind("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .context("Failed to insert API key")?;
+    
+    println!("📝 Created test API key");
+    
     Ok(())
 }
 
@@ -200,5 +283,86 @@ async fn validate_test_data(pool: &PgPool) -> Result<()> {
     println!("🔍 Found batch item for request ID: {}, status: {}", 
              batch_item.0, batch_item.1);
     
+    // Check that the queued event exists
+    let queued = sqlx::query_as::<_, (String, i32)>(
+        r#"
+        SELECT id, status
+        FROM lsrwa_express.event_queue
+        WHERE id = $1
+        "#,
+    )
+    .bind("test-event-1")
+    .fetch_one(pool)
+    .await
+    .context("Failed to fetch queued event")?;
+    
+    println!("🔍 Found queued event: {}, status: {}", queued.0, queued.1);
+    
+    // Check that the event processor has a resume point
+    let last_block = sqlx::query_as::<_, (String,)>(
+        r#"
+        SELECT value
+        FROM lsrwa_express.system_settings
+        WHERE key = 'last_processed_block'
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to fetch last processed block")?;
+    
+    println!("🔍 Found last processed block: {}", last_block.0);
+    
+    // Check that the transaction is linked to its request
+    let transaction = sqlx::query_as::<_, (String, i64)>(
+        r#"
+        SELECT t.status, r.on_chain_id
+        FROM lsrwa_express.blockchain_transactions t
+        JOIN lsrwa_express.blockchain_requests r ON r.id = t.blockchain_request_id
+        WHERE t.call_name = 'create_deposit_request'
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to fetch blockchain transaction")?;
+    
+    println!("🔍 Found {} transaction for request ID: {}", transaction.0, transaction.1);
+    
+    // Check that the API key is active
+    let api_key = sqlx::query_as::<_, (String, Vec<String>)>(
+        r#"
+        SELECT name, scopes
+        FROM lsrwa_express.api_keys
+        WHERE revoked_at IS NULL
+        LIMIT 1
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to fetch API key")?;
+    
+    println!("🔍 Found API key '{}' with scopes: {:?}", api_key.0, api_key.1);
+    
+    // Deleting a webhook endpoint must cascade to its deliveries
+    sqlx::query("DELETE FROM lsrwa_express.webhook_endpoints WHERE url = 'https://example.com/hooks'")
+        .execute(pool)
+        .await
+        .context("Failed to delete webhook endpoint")?;
+    
+    let orphaned = sqlx::query_as::<_, (i64,)>(
+        r#"
+        SELECT COUNT(*)
+        FROM lsrwa_express.webhook_deliveries d
+        LEFT JOIN lsrwa_express.webhook_endpoints e ON e.id = d.endpoint_id
+        WHERE e.id IS NULL
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count orphaned webhook deliveries")?;
+    
+    anyhow::ensure!(orphaned.0 == 0, "Found {} orphaned webhook deliveries", orphaned.0);
+    
+    println!("🔍 Webhook deliveries cascade with their endpoint");
+    
     Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,2209 @@
+//! Application configuration
+//!
+//! [`Config`] is loaded once at startup from layered [`Settings`] and handed to the services
+//! that need it; nothing else reads the environment.
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::http::HeaderValue;
+use secrecy::zeroize::Zeroize;
+use serde_json::Value;
+use sqlx::types::BigDecimal;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::models::alert::AlertSeverity;
+use crate::models::kyc::{KycLevel, KycProvider};
+use crate::models::screening::RiskLevel;
+
+/// Deployment environment the service is running in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    Development,
+    Staging,
+    Production,
+}
+
+impl FromStr for Environment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "development" | "dev" | "local" => Ok(Environment::Development),
+            "staging" | "stage" => Ok(Environment::Staging),
+            "production" | "prod" => Ok(Environment::Production),
+            other => Err(anyhow!("Unknown environment '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Environment::Development => write!(f, "development"),
+            Environment::Staging => write!(f, "staging"),
+            Environment::Production => write!(f, "production"),
+        }
+    }
+}
+
+impl Environment {
+    /// Reads the environment from `APP_ENV`, defaulting to development
+    ///
+    /// Read from the process environment only, since it selects which profile file is loaded.
+    pub fn from_env() -> Result<Self> {
+        match env::var("APP_ENV") {
+            Ok(value) => value.parse().context("APP_ENV is invalid"),
+            Err(_) => Ok(Environment::Development),
+        }
+    }
+}
+
+/// Directory profile files are read from unless `CONFIG_DIR` is set
+const DEFAULT_CONFIG_DIR: &str = "config";
+
+/// A raw configuration value and where it came from
+#[derive(Debug, Clone)]
+struct Setting {
+    value: String,
+    /// File the value was read from; `None` for environment variables and secrets
+    file: Option<String>,
+}
+
+impl Drop for Setting {
+    /// Settings include seed phrases and API keys, so values are wiped rather than left in freed memory
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Raw configuration values, layered from TOML profile files and environment variables
+///
+/// Values are looked up by their environment variable name. In the TOML files, a key's table
+/// path joined with `_` gives that name: a top-level `port = 3000` sets `PORT`, and
+/// `batch_size = 25` under `[kyc.onchain_sync]` sets `KYC_ONCHAIN_SYNC_BATCH_SIZE`.
+///
+/// Later layers override earlier ones:
+/// 1. `config/default.toml`
+/// 2. `config/<environment>.toml`, e.g. `config/production.toml`
+/// 3. `config/local.toml`, for untracked developer overrides
+/// 4. environment variables
+#[derive(Debug, Clone)]
+pub struct Settings {
+    environment: Environment,
+    values: HashMap<String, Setting>,
+}
+
+/// A setting that isn't set in any layer
+#[derive(Debug, thiserror::Error)]
+#[error("{0} is not set")]
+pub struct MissingSetting(String);
+
+impl Settings {
+    /// Loads the `APP_ENV` profile from `CONFIG_DIR` (default `config`) with environment overrides
+    pub fn load() -> Result<Self> {
+        let environment = Environment::from_env()?;
+        let dir = env::var("CONFIG_DIR").unwrap_or_else(|_| DEFAULT_CONFIG_DIR.to_string());
+
+        let mut settings = Self::from_files(environment, Path::new(&dir))?;
+        settings.values.extend(env::vars().map(|(key, value)| (key, Setting { value, file: None })));
+
+        Ok(settings)
+    }
+
+    /// Loads the profile files for `environment` from `dir`, skipping files that don't exist
+    pub fn from_files(environment: Environment, dir: &Path) -> Result<Self> {
+        let mut values = HashMap::new();
+
+        for profile in ["default".to_string(), environment.to_string(), "local".to_string()] {
+            let path = dir.join(format!("{}.toml", profile));
+            if !path.exists() {
+                continue;
+            }
+
+            let file = path.display().to_string();
+            let table: Value = ::config::Config::builder()
+                .add_source(::config::File::from(path.as_path()).format(::config::FileFormat::Toml))
+                .build()
+                .and_then(|config| config.try_deserialize())
+                .with_context(|| format!("Failed to read {}", file))?;
+            flatten_settings(&table, "", &file, &mut values)?;
+        }
+
+        Ok(Self { environment, values })
+    }
+
+    /// Environment whose profile was loaded
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    /// Values of settings that hold credentials, for redaction from logs
+    ///
+    /// Covers settings named like a secret (seed phrases, API keys, passwords, tokens) and the
+    /// password part of any `*_URL` setting.
+    pub fn secret_values(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .filter_map(|(key, setting)| {
+                if crate::logging::is_secret_name(key) {
+                    return Some(setting.value.clone());
+                }
+
+                if key.ends_with("_URL") {
+                    return reqwest::Url::parse(&setting.value)
+                        .ok()
+                        .and_then(|url| url.password().map(str::to_string));
+                }
+
+                None
+            })
+            .collect()
+    }
+
+    /// Sets a value, overriding every layer; used for values read from a secrets backend
+    pub fn set(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_string(), Setting { value, file: None });
+    }
+
+    /// Gets a raw value
+    pub fn var(&self, key: &str) -> std::result::Result<String, MissingSetting> {
+        self.values
+            .get(key)
+            .map(|setting| setting.value.clone())
+            .ok_or_else(|| MissingSetting(key.to_string()))
+    }
+
+    /// Gets and parses a value, falling back to a default when unset
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let Some(setting) = self.values.get(key) else {
+            return Ok(default);
+        };
+
+        setting.value.parse::<T>().map_err(|e| match &setting.file {
+            Some(file) => anyhow!("{} must be a valid value: {} (set in {})", key, e, file),
+            None => anyhow!("{} must be a valid value: {}", key, e),
+        })
+    }
+}
+
+/// Adds the scalar values of a TOML table under their environment variable names
+fn flatten_settings(value: &Value, key: &str, file: &str, values: &mut HashMap<String, Setting>) -> Result<()> {
+    let value = match value {
+        Value::Object(table) => {
+            for (name, value) in table {
+                let name = name.to_ascii_uppercase();
+                let key = if key.is_empty() { name } else { format!("{}_{}", key, name) };
+                flatten_settings(value, &key, file, values)?;
+            }
+            return Ok(());
+        },
+        Value::String(value) => value.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Null => return Ok(()),
+        Value::Array(_) => bail!("{} in {} must be a string, number or boolean", key, file),
+    };
+
+    values.insert(key.to_string(), Setting { value, file: Some(file.to_string()) });
+    Ok(())
+}
+
+/// CORS origin policy
+#[derive(Debug, Clone)]
+pub enum CorsOrigins {
+    /// Any origin is accepted (development only)
+    Any,
+    /// Only the listed origins are accepted
+    List(Vec<HeaderValue>),
+}
+
+/// `Cache-Control` values applied to cacheable read routes
+#[derive(Debug, Clone)]
+pub struct CacheControlConfig {
+    /// Blockchain state summary
+    pub summary: HeaderValue,
+    /// Epoch lookups
+    pub epochs: HeaderValue,
+    /// Protocol stats
+    pub stats: HeaderValue,
+}
+
+impl CacheControlConfig {
+    /// Loads per-route `Cache-Control` values from `HTTP_CACHE_CONTROL_<ROUTE>` variables
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            summary: cache_control(settings, "HTTP_CACHE_CONTROL_SUMMARY", "public, max-age=5")?,
+            epochs: cache_control(settings, "HTTP_CACHE_CONTROL_EPOCHS", "public, max-age=30")?,
+            stats: cache_control(settings, "HTTP_CACHE_CONTROL_STATS", "public, max-age=15")?,
+        })
+    }
+}
+
+fn cache_control(settings: &Settings, key: &str, default: &str) -> Result<HeaderValue> {
+    let value = settings.var(key).unwrap_or_else(|_| default.to_string());
+    HeaderValue::from_str(&value).with_context(|| format!("{} is not a valid header value", key))
+}
+
+/// Substrate network the contract is deployed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainNetwork {
+    Polkadot,
+    Kusama,
+    Westend,
+    Rococo,
+    /// A local or contracts development node, which Subscan doesn't index
+    Local,
+}
+
+impl ChainNetwork {
+    /// SS58 prefix of the network's addresses
+    pub fn ss58_prefix(self) -> u16 {
+        match self {
+            ChainNetwork::Polkadot => 0,
+            ChainNetwork::Kusama => 2,
+            ChainNetwork::Westend | ChainNetwork::Rococo | ChainNetwork::Local => 42,
+        }
+    }
+
+    /// Decimals of the network's token, which on-chain amounts are fixed point with
+    pub fn token_decimals(self) -> u32 {
+        match self {
+            ChainNetwork::Polkadot => 10,
+            ChainNetwork::Kusama | ChainNetwork::Westend | ChainNetwork::Rococo | ChainNetwork::Local => 12,
+        }
+    }
+}
+
+impl FromStr for ChainNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "polkadot" => Ok(ChainNetwork::Polkadot),
+            "kusama" => Ok(ChainNetwork::Kusama),
+            "westend" => Ok(ChainNetwork::Westend),
+            "rococo" => Ok(ChainNetwork::Rococo),
+            "local" | "development" | "dev" => Ok(ChainNetwork::Local),
+            other => Err(anyhow!("Unknown chain network '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for ChainNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainNetwork::Polkadot => write!(f, "polkadot"),
+            ChainNetwork::Kusama => write!(f, "kusama"),
+            ChainNetwork::Westend => write!(f, "westend"),
+            ChainNetwork::Rococo => write!(f, "rococo"),
+            ChainNetwork::Local => write!(f, "local"),
+        }
+    }
+}
+
+/// Block explorer links added to API responses
+#[derive(Debug, Clone)]
+pub struct ExplorerConfig {
+    pub network: ChainNetwork,
+    /// Subscan base URL, e.g. `https://polkadot.subscan.io`. `None` for networks Subscan doesn't index.
+    pub subscan_url: Option<String>,
+    /// RPC endpoint polkadot.js apps connects to when a link is opened
+    pub rpc_url: String,
+}
+
+impl ExplorerConfig {
+    /// Loads the explorer configuration from `CHAIN_NETWORK` and `EXPLORER_*`
+    ///
+    /// Subscan and RPC URLs default to the public endpoints of the configured network.
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let network = settings.get_or("CHAIN_NETWORK", ChainNetwork::Local)?;
+
+        let subscan_url = match settings.var("EXPLORER_SUBSCAN_URL") {
+            Ok(url) if url.is_empty() => None,
+            Ok(url) => Some(url),
+            Err(_) => match network {
+                ChainNetwork::Local => None,
+                network => Some(format!("https://{}.subscan.io", network)),
+            },
+        };
+        if let Some(url) = &subscan_url {
+            reqwest::Url::parse(url).with_context(|| format!("EXPLORER_SUBSCAN_URL '{}' is not a valid URL", url))?;
+        }
+
+        let rpc_url = match settings.var("EXPLORER_RPC_URL") {
+            Ok(url) if !url.is_empty() => url,
+            _ => match network {
+                ChainNetwork::Polkadot => "wss://rpc.polkadot.io".to_string(),
+                ChainNetwork::Kusama => "wss://kusama-rpc.polkadot.io".to_string(),
+                ChainNetwork::Westend => "wss://westend-rpc.polkadot.io".to_string(),
+                ChainNetwork::Rococo => "wss://rococo-rpc.polkadot.io".to_string(),
+                // Same node the blockchain service connects to
+                ChainNetwork::Local => settings.var("SUBSTRATE_RPC_URL")
+                    .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string()),
+            },
+        };
+
+        Ok(Self { network, subscan_url, rpc_url })
+    }
+}
+
+/// HTTP server and middleware configuration
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Environment the server runs in
+    pub environment: Environment,
+    /// Port to listen on
+    pub port: u16,
+    /// Origins allowed to make cross-origin requests
+    pub cors_origins: CorsOrigins,
+    /// Whether gzip/brotli response compression is enabled
+    pub compression_enabled: bool,
+    /// Maximum request body size accepted by submission endpoints
+    pub submission_body_limit_bytes: usize,
+    /// Maximum request body size accepted by the batch submission endpoint
+    pub batch_body_limit_bytes: usize,
+    /// Maximum request body size accepted by KYC document uploads
+    pub document_body_limit_bytes: usize,
+    /// Maximum time a request may take before a 408 is returned
+    pub request_timeout_secs: u64,
+    /// Bearer token required by admin endpoints
+    pub admin_api_key: Option<String>,
+    /// Per-route `Cache-Control` values
+    pub cache_control: CacheControlConfig,
+    /// Block explorer links added to responses
+    pub explorer: ExplorerConfig,
+    /// Sampling of request logs for noisy paths
+    pub log_sampling: LogSamplingConfig,
+}
+
+impl HttpConfig {
+    /// Loads the HTTP configuration from settings
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let environment = settings.environment();
+
+        let port = settings.get_or("PORT", 3000)?;
+        let compression_enabled = settings.get_or("HTTP_COMPRESSION_ENABLED", true)?;
+        let submission_body_limit_bytes = settings.get_or("HTTP_SUBMISSION_BODY_LIMIT_BYTES", 16 * 1024)?;
+        let batch_body_limit_bytes = settings.get_or("HTTP_BATCH_BODY_LIMIT_BYTES", 256 * 1024)?;
+        let document_body_limit_bytes = settings.get_or("HTTP_DOCUMENT_BODY_LIMIT_BYTES", 12 * 1024 * 1024)?;
+        let request_timeout_secs = settings.get_or("HTTP_REQUEST_TIMEOUT_SECS", 30)?;
+
+        // Environment-specific allowlists take precedence over the shared one
+        let origins_var = format!("{}_ALLOWED_ORIGINS", environment.to_string().to_ascii_uppercase());
+        let raw_origins = settings.var(&origins_var)
+            .or_else(|_| settings.var("ALLOWED_ORIGINS"))
+            .unwrap_or_default();
+
+        let cors_origins = parse_cors_origins(environment, &raw_origins)?;
+
+        let admin_api_key = settings.var("ADMIN_API_KEY").ok().filter(|key| !key.is_empty());
+
+        Ok(Self {
+            environment,
+            port,
+            cors_origins,
+            compression_enabled,
+            submission_body_limit_bytes,
+            batch_body_limit_bytes,
+            document_body_limit_bytes,
+            request_timeout_secs,
+            admin_api_key,
+            cache_control: CacheControlConfig::from_settings(settings)?,
+            explorer: ExplorerConfig::from_settings(settings)?,
+            log_sampling: LogSamplingConfig::from_settings(settings)?,
+        })
+    }
+}
+
+/// The client calls to KYC providers and webhook endpoints are made with
+#[derive(Debug, Clone)]
+pub struct OutboundHttpConfig {
+    /// Seconds a request may take, response included
+    pub timeout_secs: u64,
+    /// Seconds connecting may take
+    pub connect_timeout_secs: u64,
+    /// Idle connections kept open per host
+    pub pool_max_idle_per_host: usize,
+    /// Seconds an idle connection is kept open
+    pub pool_idle_timeout_secs: u64,
+    /// Times a failed request is retried, when retrying it is safe
+    pub max_retries: u32,
+    /// Milliseconds before the first retry, doubled for each one after it
+    pub retry_base_delay_ms: u64,
+    /// Proxy every request goes through
+    pub proxy_url: Option<String>,
+    /// Consecutive failures after which a destination's requests are paused; 0 never pauses them
+    pub circuit_failure_threshold: u32,
+    /// Seconds a failing destination's requests are paused for before it's probed
+    pub circuit_open_secs: u64,
+}
+
+impl Default for OutboundHttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 15,
+            connect_timeout_secs: 5,
+            pool_max_idle_per_host: 16,
+            pool_idle_timeout_secs: 90,
+            max_retries: 2,
+            retry_base_delay_ms: 200,
+            proxy_url: None,
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+        }
+    }
+}
+
+impl OutboundHttpConfig {
+    /// Loads the pool, timeouts, retries, proxy and circuit breakers from `OUTBOUND_HTTP_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let defaults = Self::default();
+        let config = Self {
+            timeout_secs: settings.get_or("OUTBOUND_HTTP_TIMEOUT_SECS", defaults.timeout_secs)?,
+            connect_timeout_secs: settings.get_or("OUTBOUND_HTTP_CONNECT_TIMEOUT_SECS", defaults.connect_timeout_secs)?,
+            pool_max_idle_per_host: settings.get_or("OUTBOUND_HTTP_POOL_MAX_IDLE_PER_HOST", defaults.pool_max_idle_per_host)?,
+            pool_idle_timeout_secs: settings.get_or("OUTBOUND_HTTP_POOL_IDLE_TIMEOUT_SECS", defaults.pool_idle_timeout_secs)?,
+            max_retries: settings.get_or("OUTBOUND_HTTP_MAX_RETRIES", defaults.max_retries)?,
+            retry_base_delay_ms: settings.get_or("OUTBOUND_HTTP_RETRY_BASE_DELAY_MS", defaults.retry_base_delay_ms)?,
+            proxy_url: settings.var("OUTBOUND_HTTP_PROXY_URL").ok().filter(|url| !url.is_empty()),
+            circuit_failure_threshold: settings.get_or("OUTBOUND_HTTP_CIRCUIT_FAILURE_THRESHOLD", defaults.circuit_failure_threshold)?,
+            circuit_open_secs: settings.get_or("OUTBOUND_HTTP_CIRCUIT_OPEN_SECS", defaults.circuit_open_secs)?,
+        };
+        if config.timeout_secs == 0 || config.connect_timeout_secs == 0 {
+            bail!("OUTBOUND_HTTP_TIMEOUT_SECS and OUTBOUND_HTTP_CONNECT_TIMEOUT_SECS must be at least 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// How long shutdown waits for in-flight work
+#[derive(Debug, Clone)]
+pub struct ShutdownConfig {
+    /// Seconds in-flight HTTP requests get to finish once the server stops accepting connections
+    pub http_drain_secs: u64,
+    /// Seconds background workers get to finish their current unit of work
+    pub worker_drain_secs: u64,
+}
+
+impl ShutdownConfig {
+    /// Loads the drain timeouts from `SHUTDOWN_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            http_drain_secs: settings.get_or("SHUTDOWN_HTTP_DRAIN_SECS", 30)?,
+            worker_drain_secs: settings.get_or("SHUTDOWN_WORKER_DRAIN_SECS", 60)?,
+        })
+    }
+}
+
+/// Election of the one instance that runs singleton background work
+#[derive(Debug, Clone)]
+pub struct LeaderElectionConfig {
+    /// Whether to elect a leader; when off, every instance runs the singleton work
+    pub enabled: bool,
+    /// Name of the advisory lock the leader holds; instances sharing a database and name compete
+    pub lock_name: String,
+    /// Seconds between attempts to take the lock while another instance leads
+    pub retry_secs: u64,
+    /// Seconds between checks that the leader's lock connection is still alive
+    pub check_secs: u64,
+}
+
+impl LeaderElectionConfig {
+    /// Loads the leader election configuration from `LEADER_ELECTION_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let config = Self {
+            enabled: settings.get_or("LEADER_ELECTION_ENABLED", true)?,
+            lock_name: settings.var("LEADER_ELECTION_LOCK_NAME").unwrap_or_else(|_| "lsrwa-express:leader".to_string()),
+            retry_secs: settings.get_or("LEADER_ELECTION_RETRY_SECS", 10)?,
+            check_secs: settings.get_or("LEADER_ELECTION_CHECK_SECS", 5)?,
+        };
+        if config.retry_secs == 0 || config.check_secs == 0 {
+            bail!("LEADER_ELECTION_RETRY_SECS and LEADER_ELECTION_CHECK_SECS must be at least 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// How the event indexer writes the events it indexes
+#[derive(Debug, Clone)]
+pub struct IndexerConfig {
+    /// Indexed events written to the event queue table in one statement
+    pub batch_size: usize,
+    /// Milliseconds an indexed event may wait for its batch to fill before it's written anyway
+    pub flush_interval_ms: u64,
+    /// Tasks handling queued events, each taking the events of a share of the wallets
+    pub workers: usize,
+    /// Queued events past which submissions are refused with a 503; 0 admits them regardless
+    pub admission_queue_depth: usize,
+    /// Seconds refused submissions are told to wait in `Retry-After`
+    pub admission_retry_after_secs: u64,
+    /// Queued events past which a backfill waits before indexing its next block
+    pub backfill_queue_depth: usize,
+}
+
+impl IndexerConfig {
+    /// Loads the indexer's write batching, event workers and queue limits from `INDEXER_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let config = Self {
+            batch_size: settings.get_or("INDEXER_BATCH_SIZE", 500)?,
+            flush_interval_ms: settings.get_or("INDEXER_FLUSH_INTERVAL_MS", 1000)?,
+            workers: settings.get_or("INDEXER_WORKERS", 4)?,
+            admission_queue_depth: settings.get_or("INDEXER_ADMISSION_QUEUE_DEPTH", 500)?,
+            admission_retry_after_secs: settings.get_or("INDEXER_ADMISSION_RETRY_AFTER_SECS", 30)?,
+            backfill_queue_depth: settings.get_or("INDEXER_BACKFILL_QUEUE_DEPTH", 200)?,
+        };
+        if config.batch_size == 0 {
+            bail!("INDEXER_BATCH_SIZE must be at least 1");
+        }
+        if config.workers == 0 {
+            bail!("INDEXER_WORKERS must be at least 1");
+        }
+        if config.backfill_queue_depth == 0 {
+            bail!("INDEXER_BACKFILL_QUEUE_DEPTH must be at least 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Checks run against the environment before the service starts
+#[derive(Debug, Clone)]
+pub struct SelfCheckConfig {
+    /// Whether to run the checks at all
+    pub enabled: bool,
+    /// Seconds each check gets before it's reported as failed
+    pub timeout_secs: u64,
+    /// Genesis hash the node must report, `0x`-prefixed hex; unchecked when unset
+    pub genesis_hash: Option<String>,
+    /// Free balance, in tokens, each signing account needs to pay fees
+    pub min_signer_balance: BigDecimal,
+}
+
+impl SelfCheckConfig {
+    /// Loads the self-check configuration from `SELF_CHECK_*` and `CHAIN_GENESIS_HASH`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let genesis_hash = match settings.var("CHAIN_GENESIS_HASH") {
+            Ok(hash) if hash.is_empty() => None,
+            Ok(hash) => {
+                let hex = hash.strip_prefix("0x").unwrap_or(&hash);
+                if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    bail!("CHAIN_GENESIS_HASH must be a 32-byte hex hash");
+                }
+                Some(format!("0x{}", hex.to_ascii_lowercase()))
+            },
+            Err(_) => None,
+        };
+
+        let config = Self {
+            enabled: settings.get_or("SELF_CHECK_ENABLED", true)?,
+            timeout_secs: settings.get_or("SELF_CHECK_TIMEOUT_SECS", 10)?,
+            genesis_hash,
+            min_signer_balance: settings.get_or("SELF_CHECK_MIN_SIGNER_BALANCE", BigDecimal::from(1))?,
+        };
+        if config.timeout_secs == 0 {
+            bail!("SELF_CHECK_TIMEOUT_SECS must be at least 1");
+        }
+
+        Ok(config)
+    }
+}
+
+/// Log output format selected by `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines, for local development
+    Text,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "pretty" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow!("Unknown log format '{}'", other)),
+        }
+    }
+}
+
+/// Tracing output configuration
+#[derive(Debug, Clone)]
+pub struct LoggingConfig {
+    /// Output format; JSON outside development unless overridden
+    pub format: LogFormat,
+    /// `EnvFilter` directives, from `RUST_LOG`
+    pub filter: String,
+    /// Service name stamped on every JSON log line
+    pub service_name: String,
+}
+
+impl LoggingConfig {
+    /// Loads the logging configuration from `LOG_*` and `RUST_LOG`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let default_format = match settings.environment() {
+            Environment::Development => LogFormat::Text,
+            Environment::Staging | Environment::Production => LogFormat::Json,
+        };
+
+        Ok(Self {
+            format: settings.get_or("LOG_FORMAT", default_format)?,
+            filter: settings.var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
+            service_name: settings.var("LOG_SERVICE_NAME").unwrap_or_else(|_| "lsrwa-express".to_string()),
+        })
+    }
+}
+
+/// Sampling of request logs for noisy, high-frequency paths
+#[derive(Debug, Clone)]
+pub struct LogSamplingConfig {
+    /// Request paths that are sampled rather than logged every time
+    pub paths: Vec<String>,
+    /// One in this many requests to a sampled path is logged
+    pub every: u64,
+}
+
+impl LogSamplingConfig {
+    /// Loads the sampled paths from `LOG_SAMPLED_PATHS` (comma-separated) and the rate from `LOG_SAMPLE_EVERY`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let paths = settings
+            .var("LOG_SAMPLED_PATHS")
+            .unwrap_or_else(|_| "/metrics".to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let every = settings.get_or("LOG_SAMPLE_EVERY", 100)?;
+        if every == 0 {
+            bail!("LOG_SAMPLE_EVERY must be at least 1");
+        }
+
+        Ok(Self { paths, every })
+    }
+}
+
+/// Cache backend selected by `CACHE_BACKEND`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheBackend {
+    Disabled,
+    Memory,
+    /// Redis at the given URL
+    Redis(String),
+}
+
+impl fmt::Display for CacheBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheBackend::Disabled => write!(f, "disabled"),
+            CacheBackend::Memory => write!(f, "memory"),
+            CacheBackend::Redis(_) => write!(f, "redis"),
+        }
+    }
+}
+
+/// Cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Where cached values are stored
+    pub backend: CacheBackend,
+    /// TTL applied when a caller doesn't pass one
+    pub default_ttl_secs: u64,
+    /// Capacity of the in-memory backend
+    pub memory_max_entries: usize,
+}
+
+impl CacheConfig {
+    /// Loads the cache configuration from settings
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let backend = match settings.var("CACHE_BACKEND").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "none" | "disabled" => CacheBackend::Disabled,
+            "memory" => CacheBackend::Memory,
+            "redis" => CacheBackend::Redis(
+                settings.var("REDIS_URL").context("REDIS_URL must be set when CACHE_BACKEND=redis")?,
+            ),
+            other => return Err(anyhow!("Unknown cache backend '{}'", other)),
+        };
+
+        Ok(Self {
+            backend,
+            default_ttl_secs: settings.get_or("CACHE_DEFAULT_TTL_SECS", 30)?,
+            memory_max_entries: settings.get_or("CACHE_MEMORY_MAX_ENTRIES", 10_000)?,
+        })
+    }
+}
+
+/// Postgres connection pool settings, shared by the primary and read pools
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Connections kept open even when idle
+    pub min_connections: u32,
+    /// Upper bound on open connections
+    pub max_connections: u32,
+    /// Seconds to wait for a free connection before failing
+    pub acquire_timeout_secs: u64,
+    /// Seconds an idle connection is kept above `min_connections`
+    pub idle_timeout_secs: Option<u64>,
+    /// Server-side `statement_timeout` applied to every connection
+    pub statement_timeout_ms: Option<u64>,
+}
+
+impl PoolConfig {
+    /// Loads the pool configuration from `PG_*` variables, where a 0 timeout disables it
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let config = Self {
+            min_connections: settings.get_or("PG_MIN_CONNECTIONS", 0)?,
+            max_connections: settings.get_or("PG_MAX_CONNECTIONS", 10)?,
+            acquire_timeout_secs: settings.get_or("PG_ACQUIRE_TIMEOUT_SECS", 5)?,
+            idle_timeout_secs: Some(settings.get_or("PG_IDLE_TIMEOUT_SECS", 600)?).filter(|secs| *secs > 0),
+            statement_timeout_ms: Some(settings.get_or("PG_STATEMENT_TIMEOUT_MS", 0)?).filter(|ms| *ms > 0),
+        };
+
+        if config.max_connections == 0 || config.min_connections > config.max_connections {
+            return Err(anyhow!(
+                "PG_MAX_CONNECTIONS must be at least 1 and at least PG_MIN_CONNECTIONS ({})",
+                config.min_connections
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+/// Database connection configuration
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    /// Primary database
+    pub url: String,
+    /// Read replica that replica-safe reads are routed to
+    pub read_url: Option<String>,
+    pub pool: PoolConfig,
+}
+
+impl DatabaseConfig {
+    /// Loads the database configuration from `DATABASE_URL`, `DATABASE_READ_URL` and `PG_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let url = settings.var("DATABASE_URL").context("DATABASE_URL must be set")?;
+        if url.rsplit_once('/').map_or(true, |(_, name)| name.is_empty()) {
+            bail!("DATABASE_URL must end with the database name");
+        }
+
+        Ok(Self {
+            url,
+            read_url: settings.var("DATABASE_READ_URL").ok().filter(|url| !url.is_empty()),
+            pool: PoolConfig::from_settings(settings)?,
+        })
+    }
+}
+
+/// Substrate node and contract configuration
+///
+/// The signing accounts' seed phrases are read from the
+/// [`SecretStore`](crate::services::secrets::SecretStore) when a call is signed, so they can be
+/// rotated without a restart.
+#[derive(Debug, Clone)]
+pub struct BlockchainConfig {
+    /// WebSocket RPC endpoint of the node
+    pub rpc_url: String,
+    /// Network the node is on, which selects its live deployment and cached metadata
+    pub network: ChainNetwork,
+    /// SS58 address of the deployed LSRWA Express contract. `None` to use the network's live
+    /// deployment from the deployment registry.
+    pub contract_address: Option<String>,
+    /// Cached runtime metadata of the configured network
+    pub metadata_path: PathBuf,
+    /// Chain reads kept in the RPC cache; 0 disables it
+    pub rpc_cache_entries: usize,
+}
+
+impl BlockchainConfig {
+    /// Loads the blockchain configuration from `SUBSTRATE_RPC_URL`, `CONTRACT_ADDRESS`,
+    /// `CHAIN_NETWORK`, `METADATA_DIR` and `RPC_CACHE_ENTRIES`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let network = settings.get_or("CHAIN_NETWORK", ChainNetwork::Local)?;
+
+        Ok(Self {
+            rpc_url: settings.var("SUBSTRATE_RPC_URL")
+                .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string()),
+            network,
+            contract_address: settings.var("CONTRACT_ADDRESS").ok().filter(|address| !address.is_empty()),
+            metadata_path: Self::metadata_path(settings, network),
+            rpc_cache_entries: settings.get_or("RPC_CACHE_ENTRIES", 1024)?,
+        })
+    }
+
+    /// Where the runtime metadata of `network` is cached: `METADATA_DIR/<network>.scale`, with
+    /// `METADATA_DIR` defaulting to `metadata`
+    pub fn metadata_path(settings: &Settings, network: ChainNetwork) -> PathBuf {
+        let dir = settings.var("METADATA_DIR").unwrap_or_else(|_| "metadata".to_string());
+        Path::new(&dir).join(format!("{}.scale", network))
+    }
+}
+
+/// AWS Secrets Manager access
+#[derive(Debug, Clone)]
+pub struct AwsSecretsConfig {
+    /// Service endpoint; defaults to the regional AWS endpoint
+    pub endpoint: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Set when using temporary credentials
+    pub session_token: Option<String>,
+    /// Prepended to a setting's name to form the secret ID, e.g. `lsrwa/` + `WALLET_SEED_PHRASE`
+    pub prefix: String,
+}
+
+/// HashiCorp Vault KV v2 access
+#[derive(Debug, Clone)]
+pub struct VaultSecretsConfig {
+    /// Vault address, e.g. `https://vault.internal:8200`
+    pub address: String,
+    pub token: String,
+    /// Enterprise namespace, if any
+    pub namespace: Option<String>,
+    /// Mount point of the KV v2 engine
+    pub mount: String,
+    /// Path of the secret whose keys are setting names
+    pub path: String,
+}
+
+/// Encrypted secrets file
+#[derive(Debug, Clone)]
+pub struct EncryptedFileSecretsConfig {
+    /// File holding the base64 AES-256-GCM encrypted JSON object of setting names to values
+    pub path: String,
+    /// Base64 32-byte decryption key
+    pub key: String,
+}
+
+/// Where seed phrases and API keys are read from, selected by `SECRETS_BACKEND`
+#[derive(Debug, Clone)]
+pub enum SecretsBackendConfig {
+    /// Plain settings and environment variables only
+    Env,
+    Aws(AwsSecretsConfig),
+    Vault(VaultSecretsConfig),
+    EncryptedFile(EncryptedFileSecretsConfig),
+}
+
+/// Secrets backend configuration
+#[derive(Debug, Clone)]
+pub struct SecretsConfig {
+    pub backend: SecretsBackendConfig,
+    /// Seconds a secret is reused before it's fetched again, picking up rotations
+    pub refresh_secs: u64,
+}
+
+impl SecretsConfig {
+    /// Loads the secrets backend from `SECRETS_*`; backend credentials can't come from the
+    /// backend itself, so they're always plain settings
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let backend = match settings.var("SECRETS_BACKEND").unwrap_or_else(|_| "env".to_string()).to_ascii_lowercase().as_str() {
+            "env" | "" => SecretsBackendConfig::Env,
+            "aws" => {
+                let region = settings.var("SECRETS_AWS_REGION")
+                    .or_else(|_| settings.var("AWS_REGION"))
+                    .unwrap_or_else(|_| "us-east-1".to_string());
+
+                SecretsBackendConfig::Aws(AwsSecretsConfig {
+                    endpoint: settings.var("SECRETS_AWS_ENDPOINT")
+                        .unwrap_or_else(|_| format!("https://secretsmanager.{}.amazonaws.com", region)),
+                    region,
+                    access_key_id: settings.var("SECRETS_AWS_ACCESS_KEY_ID")
+                        .or_else(|_| settings.var("AWS_ACCESS_KEY_ID"))
+                        .context("SECRETS_AWS_ACCESS_KEY_ID or AWS_ACCESS_KEY_ID must be set for the aws secrets backend")?,
+                    secret_access_key: settings.var("SECRETS_AWS_SECRET_ACCESS_KEY")
+                        .or_else(|_| settings.var("AWS_SECRET_ACCESS_KEY"))
+                        .context("SECRETS_AWS_SECRET_ACCESS_KEY or AWS_SECRET_ACCESS_KEY must be set for the aws secrets backend")?,
+                    session_token: settings.var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty()),
+                    prefix: settings.var("SECRETS_AWS_PREFIX").unwrap_or_else(|_| "lsrwa/".to_string()),
+                })
+            },
+            "vault" => SecretsBackendConfig::Vault(VaultSecretsConfig {
+                address: settings.var("SECRETS_VAULT_ADDR")
+                    .or_else(|_| settings.var("VAULT_ADDR"))
+                    .context("SECRETS_VAULT_ADDR or VAULT_ADDR must be set for the vault secrets backend")?,
+                token: settings.var("SECRETS_VAULT_TOKEN")
+                    .or_else(|_| settings.var("VAULT_TOKEN"))
+                    .context("SECRETS_VAULT_TOKEN or VAULT_TOKEN must be set for the vault secrets backend")?,
+                namespace: settings.var("SECRETS_VAULT_NAMESPACE").ok().filter(|namespace| !namespace.is_empty()),
+                mount: settings.var("SECRETS_VAULT_MOUNT").unwrap_or_else(|_| "secret".to_string()),
+                path: settings.var("SECRETS_VAULT_PATH").unwrap_or_else(|_| "lsrwa-express".to_string()),
+            }),
+            "file" => SecretsBackendConfig::EncryptedFile(EncryptedFileSecretsConfig {
+                path: settings.var("SECRETS_FILE_PATH").context("SECRETS_FILE_PATH must be set for the file secrets backend")?,
+                key: settings.var("SECRETS_FILE_KEY").context("SECRETS_FILE_KEY must be set for the file secrets backend")?,
+            }),
+            other => bail!("Unknown SECRETS_BACKEND '{}'; expected env, aws, vault or file", other),
+        };
+
+        Ok(Self {
+            backend,
+            refresh_secs: settings.get_or("SECRETS_REFRESH_SECS", 300)?,
+        })
+    }
+}
+
+/// Retention windows for high-volume tables; `None` keeps rows forever
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// Days indexed events stay in `event_queue`
+    pub event_queue_days: Option<u32>,
+    /// Days entries stay in `activity_logs`
+    pub activity_log_days: Option<u32>,
+    /// Days processed requests stay in `blockchain_requests`
+    pub blockchain_request_days: Option<u32>,
+    /// Months of partitions created ahead of time
+    pub partitions_ahead_months: u32,
+    /// Seconds between archival runs
+    pub archival_interval_secs: u64,
+}
+
+impl RetentionConfig {
+    /// Loads the retention configuration from `RETENTION_*` variables, where 0 disables archival
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            event_queue_days: retention_days(settings, "RETENTION_EVENT_QUEUE_DAYS", 90)?,
+            activity_log_days: retention_days(settings, "RETENTION_ACTIVITY_LOG_DAYS", 365)?,
+            blockchain_request_days: retention_days(settings, "RETENTION_BLOCKCHAIN_REQUEST_DAYS", 0)?,
+            partitions_ahead_months: settings.get_or("RETENTION_PARTITIONS_AHEAD_MONTHS", 3)?,
+            archival_interval_secs: settings.get_or("RETENTION_ARCHIVAL_INTERVAL_SECS", 6 * 60 * 60)?,
+        })
+    }
+
+    /// Retention window of the given table
+    pub fn retention_days(&self, table: &str) -> Option<u32> {
+        match table {
+            "event_queue" => self.event_queue_days,
+            "activity_logs" => self.activity_log_days,
+            "blockchain_requests" => self.blockchain_request_days,
+            _ => None,
+        }
+    }
+}
+
+fn retention_days(settings: &Settings, key: &str, default: u32) -> Result<Option<u32>> {
+    Ok(Some(settings.get_or(key, default)?).filter(|days| *days > 0))
+}
+
+/// Schedule of a recurring job
+#[derive(Debug, Clone)]
+pub struct JobScheduleConfig {
+    /// Whether the job runs on its own; disabled jobs can still be run from the admin API
+    pub enabled: bool,
+    /// Seconds between the starts of two runs
+    pub interval_secs: u64,
+    /// Up to this many seconds of random delay before each run, so instances don't run in step
+    pub jitter_secs: u64,
+}
+
+impl JobScheduleConfig {
+    /// Loads a job's schedule from `SCHEDULER_<JOB>_ENABLED`, `SCHEDULER_<JOB>_INTERVAL_SECS`
+    /// and `SCHEDULER_<JOB>_JITTER_SECS`
+    pub fn from_settings(settings: &Settings, job: &str, default_interval_secs: u64) -> Result<Self> {
+        let prefix = format!("SCHEDULER_{}", job.to_ascii_uppercase());
+
+        let interval_secs = settings.get_or(&format!("{}_INTERVAL_SECS", prefix), default_interval_secs)?;
+        if interval_secs < 1 {
+            bail!("{}_INTERVAL_SECS must be at least 1", prefix);
+        }
+
+        Ok(Self {
+            enabled: settings.get_or(&format!("{}_ENABLED", prefix), true)?,
+            interval_secs,
+            jitter_secs: settings.get_or(&format!("{}_JITTER_SECS", prefix), 0)?,
+        })
+    }
+}
+
+/// Schedules of the built-in recurring jobs
+#[derive(Debug, Clone)]
+pub struct JobSchedules {
+    pub epoch_auto_close: JobScheduleConfig,
+    pub liquidation_monitor: JobScheduleConfig,
+    pub debt_statements: JobScheduleConfig,
+    pub alert_monitor: JobScheduleConfig,
+    pub event_archive: JobScheduleConfig,
+    pub request_owner_backfill: JobScheduleConfig,
+}
+
+impl JobSchedules {
+    /// Loads every job's schedule from `SCHEDULER_<JOB>_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            epoch_auto_close: JobScheduleConfig::from_settings(settings, "epoch_auto_close", 60)?,
+            liquidation_monitor: JobScheduleConfig::from_settings(settings, "liquidation_monitor", 300)?,
+            debt_statements: JobScheduleConfig::from_settings(settings, "debt_statements", 86400)?,
+            alert_monitor: JobScheduleConfig::from_settings(settings, "alert_monitor", 300)?,
+            event_archive: JobScheduleConfig::from_settings(settings, "event_archive", 3600)?,
+            request_owner_backfill: JobScheduleConfig::from_settings(settings, "request_owner_backfill", 3600)?,
+        })
+    }
+}
+
+/// Whether KYC providers are called with sandbox or live credentials
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KycEnvironment {
+    Sandbox,
+    Live,
+}
+
+impl FromStr for KycEnvironment {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sandbox" | "test" => Ok(KycEnvironment::Sandbox),
+            "live" | "production" | "prod" => Ok(KycEnvironment::Live),
+            other => Err(anyhow!("Unknown KYC environment '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for KycEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycEnvironment::Sandbox => write!(f, "sandbox"),
+            KycEnvironment::Live => write!(f, "live"),
+        }
+    }
+}
+
+/// SumSub credentials and verification levels
+#[derive(Debug, Clone)]
+pub struct SumSubConfig {
+    /// API base URL
+    pub base_url: String,
+    /// App token sent as `X-App-Token`
+    pub app_token: String,
+    /// Secret used to sign requests
+    pub secret_key: String,
+    /// Secret SumSub signs webhooks with
+    pub webhook_secret: Option<String>,
+    /// SumSub level names for basic, advanced and full verification
+    pub level_names: [String; 3],
+    /// Lifetime of SDK access tokens
+    pub access_token_ttl_secs: u64,
+}
+
+impl SumSubConfig {
+    /// Loads the SumSub configuration, or `None` when no app token is set
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let app_token = match settings.var("SUMSUB_API_KEY") {
+            Ok(token) if !token.is_empty() => token,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            base_url: settings.var("SUMSUB_API_URL").unwrap_or_else(|_| "https://api.sumsub.com".to_string()),
+            app_token,
+            secret_key: settings.var("SUMSUB_SECRET_KEY").context("SUMSUB_SECRET_KEY must be set with SUMSUB_API_KEY")?,
+            webhook_secret: settings.var("SUMSUB_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty()),
+            level_names: [
+                settings.var("SUMSUB_LEVEL_BASIC").unwrap_or_else(|_| "basic-kyc-level".to_string()),
+                settings.var("SUMSUB_LEVEL_ADVANCED").unwrap_or_else(|_| "advanced-kyc-level".to_string()),
+                settings.var("SUMSUB_LEVEL_FULL").unwrap_or_else(|_| "full-kyc-level".to_string()),
+            ],
+            access_token_ttl_secs: settings.get_or("SUMSUB_ACCESS_TOKEN_TTL_SECS", 600)?,
+        }))
+    }
+
+    /// SumSub level name for a verification level
+    pub fn level_name(&self, level: KycLevel) -> &str {
+        match level {
+            KycLevel::Basic => &self.level_names[0],
+            KycLevel::Advanced => &self.level_names[1],
+            KycLevel::Full => &self.level_names[2],
+        }
+    }
+}
+
+/// Onfido credentials and the reports run at each verification level
+#[derive(Debug, Clone)]
+pub struct OnfidoConfig {
+    /// API base URL, including the region and API version
+    pub base_url: String,
+    /// API token sent as `Authorization: Token token=...`
+    pub api_token: String,
+    /// Token Onfido signs webhooks with
+    pub webhook_token: Option<String>,
+    /// Referrer pattern SDK tokens are restricted to (web SDK only)
+    pub sdk_referrer: Option<String>,
+    /// Report names for basic, advanced and full verification
+    pub report_names: [Vec<String>; 3],
+}
+
+impl OnfidoConfig {
+    /// Loads the Onfido configuration, or `None` when no API token is set
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let api_token = match settings.var("ONFIDO_API_TOKEN") {
+            Ok(token) if !token.is_empty() => token,
+            _ => return Ok(None),
+        };
+
+        let reports = |key: &str, default: &str| -> Vec<String> {
+            settings.var(key)
+                .unwrap_or_else(|_| default.to_string())
+                .split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect()
+        };
+
+        Ok(Some(Self {
+            base_url: settings.var("ONFIDO_API_URL").unwrap_or_else(|_| "https://api.eu.onfido.com/v3.6".to_string()),
+            api_token,
+            webhook_token: settings.var("ONFIDO_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty()),
+            sdk_referrer: settings.var("ONFIDO_SDK_REFERRER").ok().filter(|referrer| !referrer.is_empty()),
+            report_names: [
+                reports("ONFIDO_REPORTS_BASIC", "document"),
+                reports("ONFIDO_REPORTS_ADVANCED", "document,facial_similarity_photo"),
+                reports("ONFIDO_REPORTS_FULL", "document,facial_similarity_photo,watchlist_standard"),
+            ],
+        }))
+    }
+
+    /// Onfido reports run for a verification level
+    pub fn report_names(&self, level: KycLevel) -> &[String] {
+        match level {
+            KycLevel::Basic => &self.report_names[0],
+            KycLevel::Advanced => &self.report_names[1],
+            KycLevel::Full => &self.report_names[2],
+        }
+    }
+}
+
+/// Persona credentials and the inquiry template used at each verification level
+#[derive(Debug, Clone)]
+pub struct PersonaConfig {
+    /// API base URL
+    pub base_url: String,
+    /// API key sent as a bearer token
+    pub api_key: String,
+    /// Secret Persona signs webhooks with
+    pub webhook_secret: Option<String>,
+    /// Inquiry template ids for basic, advanced and full verification
+    pub template_ids: [String; 3],
+}
+
+impl PersonaConfig {
+    /// Loads the Persona configuration, or `None` when no API key is set
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let api_key = match settings.var("PERSONA_API_KEY") {
+            Ok(key) if !key.is_empty() => key,
+            _ => return Ok(None),
+        };
+
+        let template = |key: &str| settings.var(key).with_context(|| format!("{} must be set with PERSONA_API_KEY", key));
+
+        Ok(Some(Self {
+            base_url: settings.var("PERSONA_API_URL").unwrap_or_else(|_| "https://withpersona.com/api/v1".to_string()),
+            api_key,
+            webhook_secret: settings.var("PERSONA_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty()),
+            template_ids: [
+                template("PERSONA_TEMPLATE_BASIC")?,
+                template("PERSONA_TEMPLATE_ADVANCED")?,
+                template("PERSONA_TEMPLATE_FULL")?,
+            ],
+        }))
+    }
+
+    /// Inquiry template for a verification level
+    pub fn template_id(&self, level: KycLevel) -> &str {
+        match level {
+            KycLevel::Basic => &self.template_ids[0],
+            KycLevel::Advanced => &self.template_ids[1],
+            KycLevel::Full => &self.template_ids[2],
+        }
+    }
+}
+
+/// Shufti Pro credentials
+#[derive(Debug, Clone)]
+pub struct ShuftiConfig {
+    /// API base URL
+    pub base_url: String,
+    /// Client id, the basic auth user
+    pub client_id: String,
+    /// Secret key, the basic auth password; also signs callbacks
+    pub secret_key: String,
+    /// Where Shufti Pro sends verification callbacks
+    pub callback_url: Option<String>,
+    /// How long a verification link stays usable
+    pub verification_ttl_mins: u32,
+}
+
+impl ShuftiConfig {
+    /// Loads the Shufti Pro configuration, or `None` when no client id is set
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let client_id = match settings.var("SHUFTI_CLIENT_ID") {
+            Ok(id) if !id.is_empty() => id,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            base_url: settings.var("SHUFTI_API_URL").unwrap_or_else(|_| "https://api.shuftipro.com".to_string()),
+            client_id,
+            secret_key: settings.var("SHUFTI_SECRET_KEY").context("SHUFTI_SECRET_KEY must be set with SHUFTI_CLIENT_ID")?,
+            callback_url: settings.var("SHUFTI_CALLBACK_URL").ok().filter(|url| !url.is_empty()),
+            verification_ttl_mins: settings.get_or("SHUFTI_VERIFICATION_TTL_MINS", 60)?,
+        }))
+    }
+}
+
+/// Providers to try, in order, for verifications matching a country and level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KycRoute {
+    /// ISO 3166-1 alpha-3 codes; empty matches any country
+    pub countries: Vec<String>,
+    /// Empty matches any level
+    pub levels: Vec<KycLevel>,
+    pub providers: Vec<KycProvider>,
+}
+
+impl FromStr for KycRoute {
+    type Err = anyhow::Error;
+
+    /// Parses `<countries>/<levels>=<providers>`, each a comma-separated list, with `*` for any
+    /// country or level, e.g. `USA,CAN/*=persona,onfido`
+    fn from_str(s: &str) -> Result<Self> {
+        let (matcher, providers) = s.split_once('=')
+            .ok_or_else(|| anyhow!("KYC route '{}' has no '=<providers>'", s))?;
+        let (countries, levels) = matcher.split_once('/').unwrap_or((matcher, "*"));
+
+        let list = |value: &str| -> Vec<String> {
+            match value.trim() {
+                "*" => Vec::new(),
+                value => value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect(),
+            }
+        };
+
+        let route = Self {
+            countries: list(countries).into_iter().map(|country| country.to_ascii_uppercase()).collect(),
+            levels: list(levels).iter().map(|level| level.parse()).collect::<Result<_>>()?,
+            providers: list(providers).iter().map(|provider| provider.parse()).collect::<Result<_>>()?,
+        };
+        if route.providers.is_empty() {
+            bail!("KYC route '{}' names no providers", s);
+        }
+
+        Ok(route)
+    }
+}
+
+impl KycRoute {
+    /// Whether the route applies to a verification
+    pub fn matches(&self, country: Option<&str>, level: KycLevel) -> bool {
+        let country_matches = self.countries.is_empty()
+            || country.is_some_and(|country| self.countries.iter().any(|c| c.eq_ignore_ascii_case(country)));
+        let level_matches = self.levels.is_empty() || self.levels.contains(&level);
+
+        country_matches && level_matches
+    }
+}
+
+/// How verifications are spread across providers
+#[derive(Debug, Clone)]
+pub struct KycRoutingConfig {
+    /// Routes checked in order; the first match decides the providers
+    pub routes: Vec<KycRoute>,
+    /// Providers tried after the default one when no route matches
+    pub fallback: Vec<KycProvider>,
+    /// Consecutive provider outages before a provider is skipped
+    pub failure_threshold: u32,
+    /// How long a provider is skipped once it crosses the threshold
+    pub cooldown_secs: u64,
+}
+
+impl KycRoutingConfig {
+    /// Loads the routing policy from `KYC_ROUTES`, `KYC_FALLBACK_PROVIDERS` and `KYC_FAILOVER_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let routes = settings.var("KYC_ROUTES")
+            .unwrap_or_default()
+            .split(';')
+            .filter(|route| !route.trim().is_empty())
+            .map(|route| route.trim().parse().context("KYC_ROUTES is invalid"))
+            .collect::<Result<_>>()?;
+
+        let fallback = settings.var("KYC_FALLBACK_PROVIDERS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|provider| !provider.trim().is_empty())
+            .map(|provider| provider.trim().parse().context("KYC_FALLBACK_PROVIDERS is invalid"))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            routes,
+            fallback,
+            failure_threshold: settings.get_or("KYC_FAILOVER_THRESHOLD", 3)?,
+            cooldown_secs: settings.get_or("KYC_FAILOVER_COOLDOWN_SECS", 300)?,
+        })
+    }
+}
+
+/// On-chain KYC allowlist sync settings
+#[derive(Debug, Clone)]
+pub struct KycSyncConfig {
+    /// Polling interval of the sync worker
+    pub interval_secs: u64,
+    /// Wallets submitted per contract call
+    pub batch_size: i64,
+    /// Submissions of a verification before it is left for an admin to retry
+    pub max_attempts: u32,
+    /// Base retry delay, doubled on each failed attempt
+    pub retry_delay_secs: u64,
+}
+
+impl KycSyncConfig {
+    /// Loads the sync settings from `KYC_ONCHAIN_SYNC_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let batch_size = settings.get_or("KYC_ONCHAIN_SYNC_BATCH_SIZE", 50)?;
+        if batch_size < 1 {
+            bail!("KYC_ONCHAIN_SYNC_BATCH_SIZE must be at least 1");
+        }
+
+        Ok(Self {
+            interval_secs: settings.get_or("KYC_ONCHAIN_SYNC_INTERVAL_SECS", 60)?,
+            batch_size,
+            max_attempts: settings.get_or("KYC_ONCHAIN_SYNC_MAX_ATTEMPTS", 10)?,
+            retry_delay_secs: settings.get_or("KYC_ONCHAIN_SYNC_RETRY_DELAY_SECS", 60)?,
+        })
+    }
+}
+
+/// KYC configuration
+#[derive(Debug, Clone)]
+pub struct KycConfig {
+    /// Provider new verifications are started with
+    pub provider: KycProvider,
+    /// Sandbox or live credentials
+    pub environment: KycEnvironment,
+    /// SumSub settings, when configured
+    pub sumsub: Option<SumSubConfig>,
+    /// Onfido settings, when configured
+    pub onfido: Option<OnfidoConfig>,
+    /// Persona settings, when configured
+    pub persona: Option<PersonaConfig>,
+    /// Shufti Pro settings, when configured
+    pub shufti: Option<ShuftiConfig>,
+    /// Document storage for manual review by the internal provider, when configured
+    pub documents: Option<KycDocumentConfig>,
+    /// Per-country and per-level provider selection and failover
+    pub routing: KycRoutingConfig,
+    /// Syncing approved wallets to the contract's KYC allowlist
+    pub onchain_sync: KycSyncConfig,
+    /// How far a webhook's event time may be from now before it is rejected as a replay
+    /// (0 disables the check)
+    pub webhook_tolerance_secs: u64,
+}
+
+impl KycConfig {
+    /// Loads the KYC configuration from `KYC_*` and provider-specific variables
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let provider = match settings.var("KYC_PROVIDER") {
+            Ok(value) => value.parse().context("KYC_PROVIDER is invalid")?,
+            Err(_) => KycProvider::SumSub,
+        };
+
+        let environment = match settings.var("KYC_ENVIRONMENT") {
+            Ok(value) => value.parse().context("KYC_ENVIRONMENT is invalid")?,
+            Err(_) => KycEnvironment::Sandbox,
+        };
+
+        Ok(Self {
+            provider,
+            environment,
+            sumsub: SumSubConfig::from_settings(settings)?,
+            onfido: OnfidoConfig::from_settings(settings)?,
+            persona: PersonaConfig::from_settings(settings)?,
+            shufti: ShuftiConfig::from_settings(settings)?,
+            documents: KycDocumentConfig::from_settings(settings)?,
+            routing: KycRoutingConfig::from_settings(settings)?,
+            onchain_sync: KycSyncConfig::from_settings(settings)?,
+            webhook_tolerance_secs: settings.get_or("KYC_WEBHOOK_TOLERANCE_SECS", 86400)?,
+        })
+    }
+}
+
+/// S3-compatible object storage credentials
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    /// Service endpoint, e.g. `https://s3.eu-west-1.amazonaws.com` or a MinIO URL
+    pub endpoint: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Address buckets as `<endpoint>/<bucket>` rather than `<bucket>.<endpoint>`, as most
+    /// self-hosted stores require
+    pub path_style: bool,
+}
+
+impl S3Config {
+    /// Loads object storage settings from `S3_*` variables; `None` when `S3_ACCESS_KEY_ID` is unset
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let access_key_id = match settings.var("S3_ACCESS_KEY_ID") {
+            Ok(key) if !key.is_empty() => key,
+            _ => return Ok(None),
+        };
+
+        let region = settings.var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+
+        Ok(Some(Self {
+            endpoint: settings.var("S3_ENDPOINT").unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region)),
+            region,
+            access_key_id,
+            secret_access_key: settings.var("S3_SECRET_ACCESS_KEY").context("S3_SECRET_ACCESS_KEY must be set")?,
+            path_style: settings.get_or("S3_FORCE_PATH_STYLE", false)?,
+        }))
+    }
+}
+
+/// Storage for identity documents uploaded for manual KYC review
+#[derive(Debug, Clone)]
+pub struct KycDocumentConfig {
+    pub s3: S3Config,
+    pub bucket: String,
+    /// KMS key documents are encrypted with; the store's own keys (SSE-S3) are used when unset
+    pub kms_key_id: Option<String>,
+    /// Lifetime of the download links handed to reviewers
+    pub url_ttl_secs: u64,
+    /// Largest document accepted
+    pub max_bytes: usize,
+}
+
+impl KycDocumentConfig {
+    /// Loads document storage settings; `None` when `KYC_DOCUMENTS_BUCKET` is unset
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let bucket = match settings.var("KYC_DOCUMENTS_BUCKET") {
+            Ok(bucket) if !bucket.is_empty() => bucket,
+            _ => return Ok(None),
+        };
+
+        let s3 = S3Config::from_settings(settings)?
+            .context("KYC_DOCUMENTS_BUCKET is set but object storage isn't; set S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY")?;
+
+        Ok(Some(Self {
+            s3,
+            bucket,
+            kms_key_id: settings.var("KYC_DOCUMENTS_KMS_KEY_ID").ok().filter(|key| !key.is_empty()),
+            url_ttl_secs: settings.get_or("KYC_DOCUMENT_URL_TTL_SECS", 300)?,
+            max_bytes: settings.get_or("KYC_DOCUMENT_MAX_BYTES", 10 * 1024 * 1024)?,
+        }))
+    }
+}
+
+/// Object storage the raw indexed events are exported to
+#[derive(Debug, Clone)]
+pub struct EventArchiveConfig {
+    pub s3: S3Config,
+    pub bucket: String,
+    /// Key prefix of the exported files
+    pub prefix: String,
+    /// KMS key the files are encrypted with; the store's own keys (SSE-S3) are used when unset
+    pub kms_key_id: Option<String>,
+}
+
+impl EventArchiveConfig {
+    /// Loads event archive settings; `None` when `EVENT_ARCHIVE_BUCKET` is unset
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let bucket = match settings.var("EVENT_ARCHIVE_BUCKET") {
+            Ok(bucket) if !bucket.is_empty() => bucket,
+            _ => return Ok(None),
+        };
+
+        let s3 = S3Config::from_settings(settings)?
+            .context("EVENT_ARCHIVE_BUCKET is set but object storage isn't; set S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY")?;
+
+        Ok(Some(Self {
+            s3,
+            bucket,
+            prefix: settings.var("EVENT_ARCHIVE_PREFIX")
+                .map(|prefix| prefix.trim_matches('/').to_string())
+                .unwrap_or_else(|_| "indexed-events".to_string()),
+            kms_key_id: settings.var("EVENT_ARCHIVE_KMS_KEY_ID").ok().filter(|key| !key.is_empty()),
+        }))
+    }
+}
+
+/// Chainalysis sanctions screening API credentials
+#[derive(Debug, Clone)]
+pub struct ChainalysisConfig {
+    /// API base URL
+    pub base_url: String,
+    /// API key sent as `X-API-Key`
+    pub api_key: String,
+}
+
+impl ChainalysisConfig {
+    /// Loads Chainalysis settings; `None` when `CHAINALYSIS_API_KEY` is unset
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let api_key = match settings.var("CHAINALYSIS_API_KEY") {
+            Ok(key) if !key.is_empty() => key,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            base_url: settings.var("CHAINALYSIS_API_URL")
+                .unwrap_or_else(|_| "https://public.chainalysis.com/api/v1".to_string()),
+            api_key,
+        }))
+    }
+}
+
+/// Sanctions and AML screening configuration
+#[derive(Debug, Clone)]
+pub struct ScreeningConfig {
+    /// Chainalysis settings; screening only checks the block list when unset
+    pub chainalysis: Option<ChainalysisConfig>,
+    /// Lowest risk level that blocks a wallet
+    pub block_risk: RiskLevel,
+    /// How long a clean screening is trusted before a withdrawal screens the wallet again
+    /// (0 screens every withdrawal)
+    pub max_age_secs: u64,
+    /// How long a wallet goes between periodic re-screens
+    pub rescreen_interval_secs: u64,
+    /// Wallets re-screened per run of the re-screen worker
+    pub rescreen_batch_size: i64,
+}
+
+impl ScreeningConfig {
+    /// Loads the screening configuration from `SCREENING_*` and provider-specific variables
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let block_risk = match settings.var("SCREENING_BLOCK_RISK") {
+            Ok(value) => value.parse().context("SCREENING_BLOCK_RISK is invalid")?,
+            Err(_) => RiskLevel::High,
+        };
+        if block_risk == RiskLevel::None {
+            bail!("SCREENING_BLOCK_RISK must be above 'none', or every wallet would be blocked");
+        }
+
+        Ok(Self {
+            chainalysis: ChainalysisConfig::from_settings(settings)?,
+            block_risk,
+            max_age_secs: settings.get_or("SCREENING_MAX_AGE_SECS", 3600)?,
+            rescreen_interval_secs: settings.get_or("SCREENING_RESCREEN_INTERVAL_SECS", 86400)?,
+            rescreen_batch_size: settings.get_or("SCREENING_RESCREEN_BATCH_SIZE", 100)?,
+        })
+    }
+}
+
+/// Where asset prices come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OracleProviderKind {
+    /// Prices set in configuration, for development
+    Fixed,
+    /// A JSON price API
+    Http,
+    /// An oracle pallet on the connected chain
+    OnChain,
+}
+
+impl FromStr for OracleProviderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "fixed" => Ok(OracleProviderKind::Fixed),
+            "http" => Ok(OracleProviderKind::Http),
+            "onchain" | "on_chain" | "on-chain" => Ok(OracleProviderKind::OnChain),
+            other => Err(anyhow!("Unknown oracle provider '{}'", other)),
+        }
+    }
+}
+
+/// JSON price API settings
+#[derive(Debug, Clone)]
+pub struct HttpOracleConfig {
+    /// Price URL, with `{asset}` replaced by the asset symbol
+    pub url: String,
+    /// JSON pointer to the USD price in the response
+    pub price_pointer: String,
+    /// JSON pointer to the price's Unix timestamp in seconds; the fetch time is used when unset
+    pub timestamp_pointer: Option<String>,
+    /// API key sent as `X-API-Key`
+    pub api_key: Option<String>,
+}
+
+impl HttpOracleConfig {
+    /// Loads the price API settings, or `None` when `ORACLE_HTTP_URL` is unset
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        let url = match settings.var("ORACLE_HTTP_URL") {
+            Ok(url) if !url.is_empty() => url,
+            _ => return Ok(None),
+        };
+        if !url.contains("{asset}") {
+            bail!("ORACLE_HTTP_URL must contain an {{asset}} placeholder");
+        }
+
+        Ok(Some(Self {
+            url,
+            price_pointer: settings.var("ORACLE_HTTP_PRICE_POINTER").unwrap_or_else(|_| "/price".to_string()),
+            timestamp_pointer: settings.var("ORACLE_HTTP_TIMESTAMP_POINTER").ok().filter(|p| !p.is_empty()),
+            api_key: settings.var("ORACLE_HTTP_API_KEY").ok().filter(|key| !key.is_empty()),
+        }))
+    }
+}
+
+/// On-chain oracle settings. Prices are read from `<pallet>.<storage_entry>`, keyed by the asset
+/// symbol, as a `{ value, timestamp }` pair in the layout of ORML's oracle pallet.
+#[derive(Debug, Clone)]
+pub struct OnChainOracleConfig {
+    pub pallet: String,
+    pub storage_entry: String,
+    /// Fixed-point decimals of the stored value
+    pub decimals: u32,
+}
+
+impl OnChainOracleConfig {
+    /// Loads the on-chain oracle settings from `ORACLE_ONCHAIN_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            pallet: settings.var("ORACLE_ONCHAIN_PALLET").unwrap_or_else(|_| "Oracle".to_string()),
+            storage_entry: settings.var("ORACLE_ONCHAIN_STORAGE").unwrap_or_else(|_| "Values".to_string()),
+            decimals: settings.get_or("ORACLE_ONCHAIN_DECIMALS", 18)?,
+        })
+    }
+}
+
+/// Price oracle configuration
+#[derive(Debug, Clone)]
+pub struct OracleConfig {
+    pub provider: OracleProviderKind,
+    /// Symbol of the asset pledged as borrow collateral
+    pub collateral_asset: String,
+    /// Symbol of the asset deposited into the vault and borrowed from it
+    pub vault_asset: String,
+    /// USD prices by asset symbol for the fixed provider
+    pub fixed_prices: Vec<(String, String)>,
+    /// Price API settings, required by the HTTP provider
+    pub http: Option<HttpOracleConfig>,
+    pub onchain: OnChainOracleConfig,
+    /// How long a fetched price is reused
+    pub cache_ttl_secs: u64,
+    /// Age after which a price is stale and refused
+    pub max_age_secs: u64,
+}
+
+impl OracleConfig {
+    /// Loads the oracle configuration from `ORACLE_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let provider = match settings.var("ORACLE_PROVIDER") {
+            Ok(value) => value.parse().context("ORACLE_PROVIDER is invalid")?,
+            Err(_) => OracleProviderKind::Fixed,
+        };
+
+        let fixed_prices = settings.var("ORACLE_FIXED_PRICES")
+            .unwrap_or_else(|_| "LSRWA=1,USDC=1".to_string())
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (asset, price) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("ORACLE_FIXED_PRICES entry '{}' must be ASSET=PRICE", entry))?;
+                match price.trim().parse::<f64>() {
+                    Ok(value) if value.is_finite() && value > 0.0 => {},
+                    _ => bail!("ORACLE_FIXED_PRICES has an invalid price for {}", asset.trim()),
+                }
+                Ok((asset.trim().to_string(), price.trim().to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let http = HttpOracleConfig::from_settings(settings)?;
+        if provider == OracleProviderKind::Http && http.is_none() {
+            bail!("ORACLE_HTTP_URL must be set when ORACLE_PROVIDER is http");
+        }
+
+        let max_age_secs: u64 = settings.get_or("ORACLE_MAX_AGE_SECS", 300)?;
+        if max_age_secs == 0 {
+            bail!("ORACLE_MAX_AGE_SECS must be at least 1");
+        }
+
+        Ok(Self {
+            provider,
+            collateral_asset: settings.var("ORACLE_COLLATERAL_ASSET").unwrap_or_else(|_| "LSRWA".to_string()),
+            vault_asset: settings.var("ORACLE_VAULT_ASSET").unwrap_or_else(|_| "USDC".to_string()),
+            fixed_prices,
+            http,
+            onchain: OnChainOracleConfig::from_settings(settings)?,
+            cache_ttl_secs: settings.get_or("ORACLE_CACHE_TTL_SECS", 30)?,
+            max_age_secs,
+        })
+    }
+}
+
+/// Treasury configuration
+#[derive(Debug, Clone, Default)]
+pub struct TreasuryConfig {
+    /// SS58 address of the account protocol fees are paid to. Without it the treasury report
+    /// can't be compared with the on-chain balance.
+    pub address: Option<String>,
+    /// Largest difference between the on-chain and recorded balance that doesn't raise an alert
+    pub drift_alert_threshold: BigDecimal,
+}
+
+impl TreasuryConfig {
+    /// Loads the treasury configuration from `TREASURY_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            address: settings.var("TREASURY_ADDRESS").ok().filter(|address| !address.is_empty()),
+            drift_alert_threshold: settings.get_or("TREASURY_DRIFT_ALERT_THRESHOLD", BigDecimal::from(0))?,
+        })
+    }
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Plain connection, for local relays only
+    None,
+    /// Plain connection upgraded with `STARTTLS` (usually port 587)
+    StartTls,
+    /// TLS from the start (usually port 465)
+    Tls,
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "plain" => Ok(SmtpSecurity::None),
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" | "ssl" => Ok(SmtpSecurity::Tls),
+            other => Err(anyhow!("Unknown SMTP security '{}'", other)),
+        }
+    }
+}
+
+/// SMTP relay settings
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    /// Credentials for `AUTH PLAIN`; the relay is used unauthenticated when unset
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl SmtpConfig {
+    /// Loads the SMTP relay settings from `SMTP_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let security = match settings.var("SMTP_SECURITY") {
+            Ok(value) => value.parse().context("SMTP_SECURITY is invalid")?,
+            Err(_) => SmtpSecurity::StartTls,
+        };
+        let default_port = match security {
+            SmtpSecurity::Tls => 465,
+            _ => 587,
+        };
+
+        let username = settings.var("SMTP_USERNAME").ok().filter(|username| !username.is_empty());
+        let password = settings.var("SMTP_PASSWORD").ok().filter(|password| !password.is_empty());
+        if username.is_some() != password.is_some() {
+            bail!("SMTP_USERNAME and SMTP_PASSWORD must be set together");
+        }
+
+        Ok(Self {
+            host: settings.var("SMTP_HOST").context("SMTP_HOST must be set when EMAIL_PROVIDER=smtp")?,
+            port: settings.get_or("SMTP_PORT", default_port)?,
+            security,
+            username,
+            password,
+        })
+    }
+}
+
+/// SendGrid API credentials
+#[derive(Debug, Clone)]
+pub struct SendGridConfig {
+    /// API base URL
+    pub base_url: String,
+    /// API key sent as a bearer token
+    pub api_key: String,
+}
+
+impl SendGridConfig {
+    /// Loads the SendGrid settings from `SENDGRID_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            base_url: settings.var("SENDGRID_API_URL").unwrap_or_else(|_| "https://api.sendgrid.com".to_string()),
+            api_key: settings.var("SENDGRID_API_KEY").context("SENDGRID_API_KEY must be set when EMAIL_PROVIDER=sendgrid")?,
+        })
+    }
+}
+
+/// Service emails are sent through
+#[derive(Debug, Clone)]
+pub enum EmailProvider {
+    Smtp(SmtpConfig),
+    SendGrid(SendGridConfig),
+}
+
+/// Email notification configuration
+#[derive(Debug, Clone)]
+pub struct NotificationConfig {
+    /// Where emails are sent through; without one, emails are queued but not sent
+    pub provider: Option<EmailProvider>,
+    /// `From` address, optionally with a display name (`LSRWA Express <no-reply@example.com>`)
+    pub from_address: String,
+    /// Sends of an email before it is marked as failed
+    pub max_attempts: u32,
+    /// Base retry delay, doubled on each failed attempt
+    pub retry_delay_secs: u64,
+    /// Polling interval of the email worker
+    pub polling_interval_secs: u64,
+    /// Emails sent per poll
+    pub batch_size: i64,
+}
+
+impl NotificationConfig {
+    /// Loads the notification configuration from `EMAIL_*` and provider-specific variables
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let provider = match settings.var("EMAIL_PROVIDER").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "none" | "disabled" => None,
+            "smtp" => Some(EmailProvider::Smtp(SmtpConfig::from_settings(settings)?)),
+            "sendgrid" => Some(EmailProvider::SendGrid(SendGridConfig::from_settings(settings)?)),
+            other => return Err(anyhow!("Unknown email provider '{}'", other)),
+        };
+
+        let from_address = settings.var("EMAIL_FROM").unwrap_or_default();
+        if provider.is_some() && !from_address.contains('@') {
+            bail!("EMAIL_FROM must be set to a valid address when EMAIL_PROVIDER is set");
+        }
+
+        let batch_size = settings.get_or("EMAIL_BATCH_SIZE", 50)?;
+        if batch_size < 1 {
+            bail!("EMAIL_BATCH_SIZE must be at least 1");
+        }
+
+        Ok(Self {
+            provider,
+            from_address,
+            max_attempts: settings.get_or("EMAIL_MAX_ATTEMPTS", 8)?,
+            retry_delay_secs: settings.get_or("EMAIL_RETRY_DELAY_SECS", 60)?,
+            polling_interval_secs: settings.get_or("EMAIL_POLLING_INTERVAL_SECS", 10)?,
+            batch_size,
+        })
+    }
+}
+
+/// Slack incoming webhook alerts are posted to
+#[derive(Debug, Clone)]
+pub struct SlackAlertConfig {
+    pub webhook_url: String,
+    /// Lowest severity sent to Slack
+    pub min_severity: AlertSeverity,
+}
+
+/// Telegram bot and chat alerts are sent to
+#[derive(Debug, Clone)]
+pub struct TelegramAlertConfig {
+    /// Bot API base URL
+    pub base_url: String,
+    pub bot_token: String,
+    pub chat_id: String,
+    /// Lowest severity sent to Telegram
+    pub min_severity: AlertSeverity,
+}
+
+/// PagerDuty Events API v2 integration alerts are sent to
+#[derive(Debug, Clone)]
+pub struct PagerDutyAlertConfig {
+    /// Events API base URL
+    pub base_url: String,
+    /// Integration key of the PagerDuty service
+    pub routing_key: String,
+    /// Lowest severity that pages
+    pub min_severity: AlertSeverity,
+}
+
+/// Operator alerting configuration
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    pub slack: Option<SlackAlertConfig>,
+    pub telegram: Option<TelegramAlertConfig>,
+    pub pagerduty: Option<PagerDutyAlertConfig>,
+    /// How long repeats of an alert that is still active are held back
+    pub throttle_secs: u64,
+    /// Blocks the indexer may fall behind the chain before alerting
+    pub indexer_lag_blocks: u64,
+    /// Consecutive failed chain head lookups before the RPC node is reported down
+    pub rpc_failure_threshold: u32,
+}
+
+impl AlertConfig {
+    /// Loads the alerting configuration from `ALERT_*`; channels without credentials are disabled
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let min_severity = |key: &str, default: AlertSeverity| -> Result<AlertSeverity> {
+            match settings.var(key) {
+                Ok(value) if !value.is_empty() => value.parse().with_context(|| format!("{} is invalid", key)),
+                _ => Ok(default),
+            }
+        };
+
+        let slack = match settings.var("ALERT_SLACK_WEBHOOK_URL") {
+            Ok(webhook_url) if !webhook_url.is_empty() => Some(SlackAlertConfig {
+                webhook_url,
+                min_severity: min_severity("ALERT_SLACK_MIN_SEVERITY", AlertSeverity::Warning)?,
+            }),
+            _ => None,
+        };
+
+        let telegram = match settings.var("ALERT_TELEGRAM_BOT_TOKEN") {
+            Ok(bot_token) if !bot_token.is_empty() => Some(TelegramAlertConfig {
+                base_url: settings.var("ALERT_TELEGRAM_API_URL").unwrap_or_else(|_| "https://api.telegram.org".to_string()),
+                bot_token,
+                chat_id: settings.var("ALERT_TELEGRAM_CHAT_ID")
+                    .context("ALERT_TELEGRAM_CHAT_ID must be set with ALERT_TELEGRAM_BOT_TOKEN")?,
+                min_severity: min_severity("ALERT_TELEGRAM_MIN_SEVERITY", AlertSeverity::Warning)?,
+            }),
+            _ => None,
+        };
+
+        let pagerduty = match settings.var("ALERT_PAGERDUTY_ROUTING_KEY") {
+            Ok(routing_key) if !routing_key.is_empty() => Some(PagerDutyAlertConfig {
+                base_url: settings.var("ALERT_PAGERDUTY_API_URL")
+                    .unwrap_or_else(|_| "https://events.pagerduty.com".to_string()),
+                routing_key,
+                min_severity: min_severity("ALERT_PAGERDUTY_MIN_SEVERITY", AlertSeverity::Critical)?,
+            }),
+            _ => None,
+        };
+
+        let rpc_failure_threshold = settings.get_or("ALERT_RPC_FAILURE_THRESHOLD", 3)?;
+        if rpc_failure_threshold < 1 {
+            bail!("ALERT_RPC_FAILURE_THRESHOLD must be at least 1");
+        }
+
+        Ok(Self {
+            slack,
+            telegram,
+            pagerduty,
+            throttle_secs: settings.get_or("ALERT_THROTTLE_SECS", 900)?,
+            indexer_lag_blocks: settings.get_or("ALERT_INDEXER_LAG_BLOCKS", 100)?,
+            rpc_failure_threshold,
+        })
+    }
+}
+
+/// Kafka cluster events are published to
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    /// Comma-separated `host:port` bootstrap brokers
+    pub brokers: String,
+    pub client_id: String,
+    /// How long a message may wait for delivery before the publish fails
+    pub message_timeout_ms: u64,
+}
+
+impl KafkaConfig {
+    /// Loads the Kafka producer settings from `KAFKA_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            brokers: settings.var("KAFKA_BROKERS").context("KAFKA_BROKERS must be set when EVENT_BUS_PROVIDER=kafka")?,
+            client_id: settings.var("KAFKA_CLIENT_ID").unwrap_or_else(|_| "lsrwa-express".to_string()),
+            message_timeout_ms: settings.get_or("KAFKA_MESSAGE_TIMEOUT_MS", 5000)?,
+        })
+    }
+}
+
+/// NATS server events are published to
+#[derive(Debug, Clone)]
+pub struct NatsConfig {
+    pub url: String,
+    /// Authentication token, when the server requires one
+    pub token: Option<String>,
+}
+
+impl NatsConfig {
+    /// Loads the NATS connection settings from `NATS_*`
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            url: settings.var("NATS_URL").context("NATS_URL must be set when EVENT_BUS_PROVIDER=nats")?,
+            token: settings.var("NATS_TOKEN").ok().filter(|token| !token.is_empty()),
+        })
+    }
+}
+
+/// Message bus protocol events are published to
+#[derive(Debug, Clone)]
+pub enum EventBusProvider {
+    Kafka(KafkaConfig),
+    Nats(NatsConfig),
+}
+
+/// Event bus configuration
+#[derive(Debug, Clone)]
+pub struct EventBusConfig {
+    /// Where events are published; without one, nothing is published
+    pub provider: Option<EventBusProvider>,
+    /// Prefix of every topic or subject, e.g. `lsrwa` publishes on `lsrwa.requests`
+    pub topic_prefix: String,
+}
+
+impl EventBusConfig {
+    /// Loads the event bus configuration from `EVENT_BUS_*` and provider-specific variables
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let provider = match settings.var("EVENT_BUS_PROVIDER").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "none" | "disabled" => None,
+            "kafka" => Some(EventBusProvider::Kafka(KafkaConfig::from_settings(settings)?)),
+            "nats" => Some(EventBusProvider::Nats(NatsConfig::from_settings(settings)?)),
+            other => return Err(anyhow!("Unknown event bus provider '{}'", other)),
+        };
+
+        let topic_prefix = settings.var("EVENT_BUS_TOPIC_PREFIX").unwrap_or_else(|_| "lsrwa".to_string());
+        if topic_prefix.is_empty() || topic_prefix.contains(char::is_whitespace) {
+            bail!("EVENT_BUS_TOPIC_PREFIX must be non-empty and contain no whitespace");
+        }
+
+        Ok(Self { provider, topic_prefix })
+    }
+}
+
+/// Complete service configuration, loaded and validated once at startup
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub logging: LoggingConfig,
+    pub http: HttpConfig,
+    pub outbound_http: OutboundHttpConfig,
+    pub database: DatabaseConfig,
+    pub blockchain: BlockchainConfig,
+    pub secrets: SecretsConfig,
+    pub cache: CacheConfig,
+    pub alerts: AlertConfig,
+    pub oracle: OracleConfig,
+    pub event_bus: EventBusConfig,
+    pub treasury: TreasuryConfig,
+    /// `None` when no archive bucket is configured
+    pub event_archive: Option<EventArchiveConfig>,
+    pub kyc: KycConfig,
+    pub screening: ScreeningConfig,
+    pub notifications: NotificationConfig,
+    pub retention: RetentionConfig,
+    pub jobs: JobSchedules,
+    pub shutdown: ShutdownConfig,
+    pub self_check: SelfCheckConfig,
+    pub leader_election: LeaderElectionConfig,
+    pub indexer: IndexerConfig,
+}
+
+impl Config {
+    /// Loads the configuration from the `APP_ENV` profile and environment overrides
+    pub fn load() -> Result<Self> {
+        let settings = Settings::load().context("Failed to load configuration files")?;
+        Self::from_settings(&settings)
+    }
+
+    /// Loads and validates every section, naming the section that failed
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            logging: LoggingConfig::from_settings(settings).context("Invalid logging configuration")?,
+            http: HttpConfig::from_settings(settings).context("Invalid HTTP configuration")?,
+            outbound_http: OutboundHttpConfig::from_settings(settings).context("Invalid outbound HTTP configuration")?,
+            database: DatabaseConfig::from_settings(settings).context("Invalid database configuration")?,
+            blockchain: BlockchainConfig::from_settings(settings).context("Invalid blockchain configuration")?,
+            secrets: SecretsConfig::from_settings(settings).context("Invalid secrets configuration")?,
+            cache: CacheConfig::from_settings(settings).context("Invalid cache configuration")?,
+            alerts: AlertConfig::from_settings(settings).context("Invalid alert configuration")?,
+            oracle: OracleConfig::from_settings(settings).context("Invalid oracle configuration")?,
+            event_bus: EventBusConfig::from_settings(settings).context("Invalid event bus configuration")?,
+            treasury: TreasuryConfig::from_settings(settings).context("Invalid treasury configuration")?,
+            event_archive: EventArchiveConfig::from_settings(settings).context("Invalid event archive configuration")?,
+            kyc: KycConfig::from_settings(settings).context("Invalid KYC configuration")?,
+            screening: ScreeningConfig::from_settings(settings).context("Invalid screening configuration")?,
+            notifications: NotificationConfig::from_settings(settings).context("Invalid notification configuration")?,
+            retention: RetentionConfig::from_settings(settings).context("Invalid retention configuration")?,
+            jobs: JobSchedules::from_settings(settings).context("Invalid scheduler configuration")?,
+            shutdown: ShutdownConfig::from_settings(settings).context("Invalid shutdown configuration")?,
+            self_check: SelfCheckConfig::from_settings(settings).context("Invalid self-check configuration")?,
+            leader_election: LeaderElectionConfig::from_settings(settings).context("Invalid leader election configuration")?,
+            indexer: IndexerConfig::from_settings(settings).context("Invalid indexer configuration")?,
+        })
+    }
+
+    /// Environment the service runs in
+    pub fn environment(&self) -> Environment {
+        self.http.environment
+    }
+}
+
+/// Parses a comma-separated origin allowlist for the given environment
+fn parse_cors_origins(environment: Environment, raw: &str) -> Result<CorsOrigins> {
+    let origins: Vec<&str> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .collect();
+
+    if origins.is_empty() || origins == ["*"] {
+        if environment == Environment::Development {
+            return Ok(CorsOrigins::Any);
+        }
+
+        return Err(anyhow!(
+            "An explicit CORS origin allowlist is required in the {} environment",
+            environment
+        ));
+    }
+
+    let origins = origins
+        .into_iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin).with_context(|| format!("Invalid CORS origin '{}'", origin))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CorsOrigins::List(origins))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn layers_profile_files_by_environment_variable_name() {
+        let dir = env::temp_dir().join(format!("lsrwa-config-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("default.toml"), "port = 3000\n[pg]\nmax_connections = 10\nmin_connections = 1\n").unwrap();
+        fs::write(dir.join("production.toml"), "[pg]\nmax_connections = 20\n").unwrap();
+        fs::write(dir.join("staging.toml"), "port = 4000\n").unwrap();
+
+        let settings = Settings::from_files(Environment::Production, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(settings.get_or("PORT", 0u16).unwrap(), 3000);
+        assert_eq!(settings.get_or("PG_MAX_CONNECTIONS", 0u32).unwrap(), 20);
+        assert_eq!(settings.get_or("PG_MIN_CONNECTIONS", 0u32).unwrap(), 1);
+        assert_eq!(settings.get_or("PG_IDLE_TIMEOUT_SECS", 600u64).unwrap(), 600);
+        assert!(settings.var("DATABASE_URL").is_err());
+    }
+
+    #[test]
+    fn names_the_file_an_invalid_value_came_from() {
+        let dir = env::temp_dir().join(format!("lsrwa-config-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("default.toml"), "[http]\nrequest_timeout_secs = \"soon\"\n").unwrap();
+
+        let settings = Settings::from_files(Environment::Development, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let err = settings.get_or("HTTP_REQUEST_TIMEOUT_SECS", 30u64).unwrap_err().to_string();
+        assert!(err.contains("HTTP_REQUEST_TIMEOUT_SECS"), "{}", err);
+        assert!(err.contains("default.toml"), "{}", err);
+    }
+}
@@ -0,0 +1,234 @@
+//! Weight benchmarks of the contract's messages
+//!
+//! Each message is dry-run with arguments of a few sizes, the number of items in its list
+//! argument or the tokens in its amount, and what it needed is recorded in a JSON baseline
+//! checked in next to the contract. A new build is benchmarked the same way and compared with
+//! the baseline, so a change that makes messages heavier is noticed before it's deployed.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use scale::Encode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use subxt::utils::AccountId32;
+
+use super::CallEstimate;
+
+/// Sizes messages with a list or amount argument are benchmarked at
+pub const SIZES: [u32; 3] = [1, 10, 50];
+
+/// Base units per token
+const UNIT: u128 = 1_000_000_000_000;
+
+/// Arguments of one benchmarked call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkCase {
+    pub size: u32,
+    /// SCALE-encoded arguments, which follow the message's selector
+    pub arguments: Vec<u8>,
+}
+
+/// The calls `message` is benchmarked with when called by `caller`, or `None` for a message
+/// the harness doesn't know the arguments of
+pub fn cases(message: &str, caller: &AccountId32) -> Option<Vec<BenchmarkCase>> {
+    let once = |arguments: Vec<u8>| Some(vec![BenchmarkCase { size: 1, arguments }]);
+    let sized = |encode: &dyn Fn(u32) -> Vec<u8>| {
+        Some(SIZES.iter().map(|&size| BenchmarkCase { size, arguments: encode(size) }).collect())
+    };
+    let request_ids = |size: u32| (1..=size as u128).collect::<Vec<_>>();
+    let tokens = |size: u32| size as u128 * UNIT;
+
+    match message {
+        "get_owner" | "get_treasury" | "get_contract_balance" | "get_total_pending_deposits"
+        | "get_total_pending_withdrawals" | "get_current_epoch" | "get_risk_parameters" | "close_current_epoch" => {
+            once(Vec::new())
+        },
+        "get_request" | "is_borrow_liquidated" | "process_deposit_request" | "process_withdrawal_request"
+        | "process_borrow_request" | "liquidate_borrow" | "execute_withdrawal" => once(1u128.encode()),
+        "get_user" | "get_user_deposit_requests" | "get_user_withdrawal_requests" | "get_user_borrow_requests"
+        | "is_kyc_approved" | "set_treasury" => once(caller.encode()),
+        "get_epoch" => once(1u32.encode()),
+        // FeeType::Withdrawal
+        "get_fee_bps" => once(0u8.encode()),
+        "set_fee_bps" => once((0u8, 50u32).encode()),
+        "set_risk_parameters" => once((UNIT, UNIT, 150u128).encode()),
+        "create_deposit_request" | "create_withdrawal_request" | "emergency_withdraw" => {
+            sized(&|size| tokens(size).encode())
+        },
+        "create_borrow_request" => sized(&|size| (tokens(size), tokens(size) * 2).encode()),
+        "batch_process_deposit_requests" | "batch_process_withdrawal_requests" | "batch_process_borrow_requests" => {
+            sized(&|size| request_ids(size).encode())
+        },
+        "set_kyc_approvals" => sized(&|size| {
+            let wallets = (0..size).map(|index| AccountId32([index as u8; 32])).collect::<Vec<_>>();
+            (wallets, true).encode()
+        }),
+        _ => None,
+    }
+}
+
+/// What one benchmarked call needed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub message: String,
+    pub size: u32,
+    pub ref_time: u64,
+    pub proof_size: u64,
+    /// In base units; negative when the call frees storage
+    pub storage_deposit: i128,
+    /// Whether the message returned an error; a reverted call usually does less work
+    pub reverted: bool,
+}
+
+impl BenchmarkResult {
+    pub fn new(message: &str, size: u32, estimate: &CallEstimate) -> Self {
+        Self {
+            message: message.to_string(),
+            size,
+            ref_time: estimate.gas_required.ref_time,
+            proof_size: estimate.gas_required.proof_size,
+            storage_deposit: estimate.storage_deposit,
+            reverted: estimate.reverted,
+        }
+    }
+}
+
+/// Benchmarks of one contract build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Code hash of the build that was benchmarked
+    pub code_hash: String,
+    pub recorded_at: DateTime<Utc>,
+    pub results: Vec<BenchmarkResult>,
+}
+
+impl Baseline {
+    /// Reads a baseline, or `None` if none has been recorded at `path`
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("{} is not a benchmark baseline", path.display()))
+            .map(Some)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).context("Failed to serialize benchmark baseline")?;
+        fs::write(path, contents + "\n").with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// A benchmarked call that got heavier than the baseline allows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub message: String,
+    pub size: u32,
+    pub metric: &'static str,
+    pub baseline: i128,
+    pub current: i128,
+}
+
+impl Regression {
+    /// Increase over the baseline in percent; `None` when the baseline was zero
+    pub fn increase_percent(&self) -> Option<f64> {
+        (self.baseline > 0).then(|| (self.current - self.baseline) as f64 * 100.0 / self.baseline as f64)
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (size {}) {}: {} -> {}", self.message, self.size, self.metric, self.baseline, self.current)?;
+        match self.increase_percent() {
+            Some(increase) => write!(f, " (+{:.1}%)", increase),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Calls whose ref time, proof size or storage deposit grew more than `threshold_percent` over
+/// the baseline. Calls the baseline doesn't have are new, not regressions.
+pub fn regressions(baseline: &[BenchmarkResult], current: &[BenchmarkResult], threshold_percent: f64) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+
+    for result in current {
+        let Some(previous) = baseline
+            .iter()
+            .find(|previous| previous.message == result.message && previous.size == result.size)
+        else {
+            continue;
+        };
+
+        for (metric, baseline, current) in [
+            ("ref_time", previous.ref_time as i128, result.ref_time as i128),
+            ("proof_size", previous.proof_size as i128, result.proof_size as i128),
+            ("storage_deposit", previous.storage_deposit, result.storage_deposit),
+        ] {
+            let allowed = baseline.max(0) as f64 * (1.0 + threshold_percent / 100.0);
+            if current > baseline && current as f64 > allowed {
+                regressions.push(Regression {
+                    message: result.message.clone(),
+                    size: result.size,
+                    metric,
+                    baseline,
+                    current,
+                });
+            }
+        }
+    }
+
+    regressions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(message: &str, size: u32, ref_time: u64, storage_deposit: i128) -> BenchmarkResult {
+        BenchmarkResult {
+            message: message.to_string(),
+            size,
+            ref_time,
+            proof_size: 10_000,
+            storage_deposit,
+            reverted: false,
+        }
+    }
+
+    #[test]
+    fn only_growth_beyond_the_threshold_regresses() {
+        let baseline = [
+            result("create_deposit_request", 1, 1_000_000, 500),
+            result("batch_process_deposit_requests", 10, 2_000_000, 0),
+        ];
+        let current = [
+            result("create_deposit_request", 1, 1_090_000, 500),
+            result("batch_process_deposit_requests", 10, 2_400_000, 100),
+            result("get_owner", 1, 9_000_000, 0),
+        ];
+
+        let found = regressions(&baseline, &current, 10.0);
+        let metrics = found.iter().map(|regression| (regression.message.as_str(), regression.metric)).collect::<Vec<_>>();
+        assert_eq!(
+            metrics,
+            [("batch_process_deposit_requests", "ref_time"), ("batch_process_deposit_requests", "storage_deposit")]
+        );
+        assert_eq!(found[0].increase_percent(), Some(20.0));
+        assert_eq!(found[1].increase_percent(), None);
+    }
+
+    #[test]
+    fn list_arguments_grow_with_the_size() {
+        let caller = AccountId32([1; 32]);
+        let kyc = cases("set_kyc_approvals", &caller).unwrap();
+
+        assert_eq!(kyc.iter().map(|case| case.size).collect::<Vec<_>>(), SIZES);
+        assert!(kyc.windows(2).all(|pair| pair[0].arguments.len() < pair[1].arguments.len()));
+        assert_eq!(cases("get_owner", &caller).unwrap().len(), 1);
+        assert!(cases("not_a_message", &caller).is_none());
+    }
+}
@@ -0,0 +1,480 @@
+//! Deploying the LSRWA Express contract
+//!
+//! A deployment is dry-run first, so the gas limit and storage deposit it's submitted with are
+//! what the node says instantiation needs. The contract's address is derived from a salt named
+//! after the network and a label, so deploying the same build with the same label always lands
+//! at the same address, and that address is known before anything is submitted. After
+//! instantiation the code hash stored on-chain is checked against the local build.
+//!
+//! Upgrades replace a deployed contract's code through its own timelocked upgrade messages, so
+//! the deployer only uploads the new code and reads the chain's time for them.
+
+use anyhow::{anyhow, bail, Context, Result};
+use scale::{Decode, Encode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::{blake2_256, sr25519, Pair as PairTrait};
+use subxt::tx::PairSigner;
+use subxt::utils::{AccountId32, H256};
+use subxt::{OnlineClient, PolkadotConfig};
+
+use super::{ContractSigner, LsrwaExpressContract};
+
+/// Label of the constructor deployments call
+const CONSTRUCTOR: &str = "new";
+
+/// Flag set on the return value of a constructor or message that reverted
+pub(crate) const REVERT_FLAG: u32 = 1;
+
+/// A contract built by `cargo contract build`
+#[derive(Debug, Clone)]
+pub struct ContractArtifact {
+    pub code: Vec<u8>,
+    pub code_hash: [u8; 32],
+    /// Selector of the constructor, which is the whole input as it takes no arguments
+    pub constructor: Vec<u8>,
+    /// Selectors of the messages, by label
+    pub messages: BTreeMap<String, [u8; 4]>,
+}
+
+/// The parts of a `.contract` bundle deployment needs
+#[derive(Deserialize)]
+struct Bundle {
+    source: BundleSource,
+    spec: BundleSpec,
+}
+
+#[derive(Deserialize)]
+struct BundleSource {
+    hash: String,
+    wasm: String,
+}
+
+#[derive(Deserialize)]
+struct BundleSpec {
+    constructors: Vec<BundleConstructor>,
+    messages: Vec<BundleMessage>,
+}
+
+#[derive(Deserialize)]
+struct BundleConstructor {
+    label: String,
+    selector: String,
+}
+
+#[derive(Deserialize)]
+struct BundleMessage {
+    label: String,
+    selector: String,
+}
+
+impl ContractArtifact {
+    /// Loads a `.contract` bundle, which holds both the Wasm code and the metadata
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let bundle: Bundle = serde_json::from_str(&contents)
+            .with_context(|| format!("{} is not a contract bundle", path.display()))?;
+
+        let code = decode_hex(&bundle.source.wasm).context("Invalid Wasm in contract bundle")?;
+        let code_hash = blake2_256(&code);
+        if hex_string(&code_hash) != bundle.source.hash.to_ascii_lowercase() {
+            bail!("The bundle's code hash {} doesn't match its Wasm; rebuild the contract", bundle.source.hash);
+        }
+
+        let constructor = bundle
+            .spec
+            .constructors
+            .iter()
+            .find(|constructor| constructor.label == CONSTRUCTOR)
+            .ok_or_else(|| anyhow!("The contract has no '{}' constructor", CONSTRUCTOR))?;
+        let constructor = decode_hex(&constructor.selector).context("Invalid constructor selector")?;
+
+        let messages = bundle
+            .spec
+            .messages
+            .into_iter()
+            .map(|message| {
+                let selector = decode_hex(&message.selector)
+                    .ok()
+                    .and_then(|selector| <[u8; 4]>::try_from(selector).ok())
+                    .ok_or_else(|| anyhow!("Invalid selector for message '{}'", message.label))?;
+                Ok((message.label, selector))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            code,
+            code_hash,
+            constructor,
+            messages,
+        })
+    }
+}
+
+/// Salt for deploying under `label` on `network`
+pub fn deterministic_salt(network: &str, label: &str) -> [u8; 32] {
+    blake2_256(format!("lsrwa-express:{}:{}", network, label).as_bytes())
+}
+
+/// Address pallet-contracts gives a contract instantiated with these parameters
+pub fn contract_address(deployer: &AccountId32, code_hash: &[u8; 32], input: &[u8], salt: &[u8]) -> AccountId32 {
+    AccountId32((b"contract_addr_v1", deployer.0, code_hash, input, salt).using_encoded(blake2_256))
+}
+
+/// `0x`-prefixed lowercase hex of `bytes`
+pub fn hex_string(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|e| anyhow!("Invalid hex: {}", e))
+}
+
+/// Computation and proof size limit of a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub struct Weight {
+    #[codec(compact)]
+    pub ref_time: u64,
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+/// What a dry-run instantiation needed
+#[derive(Debug, Clone)]
+pub struct DeploymentEstimate {
+    pub gas_required: Weight,
+    /// Deposit charged for the code and the contract's storage, in base units
+    pub storage_deposit: u128,
+    pub address: AccountId32,
+}
+
+/// A contract instantiated on-chain
+#[derive(Debug, Clone)]
+pub struct Instantiated {
+    pub address: AccountId32,
+    pub transaction_hash: String,
+    pub block_number: u64,
+}
+
+/// Code to instantiate, as `ContractsApi_instantiate` takes it
+#[derive(Encode)]
+enum Code {
+    #[codec(index = 0)]
+    Upload(Vec<u8>),
+}
+
+/// `StorageDeposit` of pallet-contracts
+#[derive(Decode)]
+pub(crate) enum StorageDeposit {
+    #[codec(index = 0)]
+    Refund(u128),
+    #[codec(index = 1)]
+    Charge(u128),
+}
+
+/// The start of pallet-contracts' `ContractInfo`, up to the code hash
+#[derive(Decode)]
+struct ContractInfoPrefix {
+    _trie_id: Vec<u8>,
+    code_hash: [u8; 32],
+}
+
+/// Deploys contracts signed by one account
+pub struct Deployer {
+    client: OnlineClient<PolkadotConfig>,
+    signer: sr25519::Pair,
+}
+
+impl Deployer {
+    /// Connects to the node at `rpc_url`, deploying as `signer`
+    pub async fn connect(rpc_url: &str, signer: sr25519::Pair) -> Result<Self> {
+        let client = OnlineClient::<PolkadotConfig>::from_url(rpc_url)
+            .await
+            .context("Failed to connect to blockchain node")?;
+
+        Ok(Self { client, signer })
+    }
+
+    /// Account the contracts are deployed from
+    pub fn account(&self) -> AccountId32 {
+        AccountId32::from(self.signer.public())
+    }
+
+    /// Genesis hash of the chain, identifying which chain a deployment is on
+    pub fn genesis_hash(&self) -> String {
+        format!("{:?}", self.client.genesis_hash())
+    }
+
+    /// Dry-runs instantiating `artifact` with `salt`, failing with the node's reason if it would fail
+    pub async fn dry_run(&self, artifact: &ContractArtifact, salt: &[u8; 32]) -> Result<DeploymentEstimate> {
+        let origin = self.account();
+        let args = (
+            origin.0,
+            0u128,             // value
+            None::<Weight>,    // gas limit: as much as it takes
+            None::<u128>,      // storage deposit limit: as much as it takes
+            Code::Upload(artifact.code.clone()),
+            artifact.constructor.clone(),
+            salt.to_vec(),
+        )
+            .encode();
+
+        let response: Vec<u8> = self
+            .client
+            .rpc()
+            .state_call("ContractsApi_instantiate", Some(&args), None)
+            .await
+            .context("Failed to dry-run the instantiation")?;
+
+        // ContractResult: gas consumed, gas required, storage deposit, debug message, result, ...
+        let input = &mut &response[..];
+        let decode_error = |e: scale::Error| anyhow!("Failed to decode the dry-run result: {}", e);
+        let _gas_consumed = Weight::decode(input).map_err(decode_error)?;
+        let gas_required = Weight::decode(input).map_err(decode_error)?;
+        let storage_deposit = match StorageDeposit::decode(input).map_err(decode_error)? {
+            StorageDeposit::Charge(amount) => amount,
+            StorageDeposit::Refund(_) => 0,
+        };
+        let debug_message = String::from_utf8_lossy(&Vec::<u8>::decode(input).map_err(decode_error)?).into_owned();
+
+        match u8::decode(input).map_err(decode_error)? {
+            0 => {
+                let flags = u32::decode(input).map_err(decode_error)?;
+                let _data = Vec::<u8>::decode(input).map_err(decode_error)?;
+                let address = AccountId32(<[u8; 32]>::decode(input).map_err(decode_error)?);
+
+                if flags & REVERT_FLAG != 0 {
+                    bail!("The constructor reverted: {}", debug_message);
+                }
+
+                Ok(DeploymentEstimate {
+                    gas_required,
+                    storage_deposit,
+                    address,
+                })
+            },
+            _ => bail!("Instantiation would fail with {}: {}", describe_dispatch_error(&self.client, *input), debug_message),
+        }
+    }
+
+    /// Instantiates `artifact` with `salt` within the estimate's limits, waiting for finalization
+    pub async fn instantiate(
+        &self,
+        artifact: &ContractArtifact,
+        salt: &[u8; 32],
+        estimate: &DeploymentEstimate,
+    ) -> Result<Instantiated> {
+        let call = subxt::dynamic::tx(
+            "Contracts",
+            "instantiate_with_code",
+            vec![
+                Value::u128(0),
+                Value::named_composite([
+                    ("ref_time", Value::u128(estimate.gas_required.ref_time as u128)),
+                    ("proof_size", Value::u128(estimate.gas_required.proof_size as u128)),
+                ]),
+                Value::unnamed_variant("Some", [Value::u128(estimate.storage_deposit)]),
+                Value::from_bytes(&artifact.code),
+                Value::from_bytes(&artifact.constructor),
+                Value::from_bytes(salt),
+            ],
+        );
+
+        let events = self
+            .client
+            .tx()
+            .sign_and_submit_then_watch_default(&call, &self.signer())
+            .await
+            .context("Failed to submit the instantiation")?
+            .wait_for_finalized_success()
+            .await
+            .context("Instantiation failed")?;
+
+        let instantiated = events
+            .iter()
+            .filter_map(|event| event.ok())
+            .find(|event| event.pallet_name() == "Contracts" && event.variant_name() == "Instantiated")
+            .context("The instantiation emitted no Instantiated event")?;
+        // Instantiated { deployer, contract }
+        let fields = instantiated.field_bytes();
+        if fields.len() < 64 {
+            bail!("Unexpected Instantiated event layout");
+        }
+        let mut address = [0u8; 32];
+        address.copy_from_slice(&fields[32..64]);
+
+        Ok(Instantiated {
+            address: AccountId32(address),
+            transaction_hash: format!("{:?}", events.extrinsic_hash()),
+            block_number: self.block_number(events.block_hash()).await?,
+        })
+    }
+
+    /// The contract at `address`, called as this deployer's account
+    pub fn contract(&self, address: AccountId32) -> LsrwaExpressContract {
+        LsrwaExpressContract::new(self.client.clone(), address)
+    }
+
+    /// Signer for calls from the deployer's account
+    pub fn signer(&self) -> ContractSigner {
+        PairSigner::new(self.signer.clone())
+    }
+
+    /// Uploads `artifact`'s code without instantiating it, waiting for finalization. Returns the
+    /// upload's transaction hash, or `None` if the code is already on-chain.
+    pub async fn upload_code(&self, artifact: &ContractArtifact) -> Result<Option<String>> {
+        let query = subxt::dynamic::storage("Contracts", "PristineCode", vec![Value::from_bytes(artifact.code_hash)]);
+        let uploaded = self
+            .client
+            .storage()
+            .at_latest()
+            .await
+            .context("Failed to get latest block")?
+            .fetch(&query)
+            .await
+            .context("Failed to look up uploaded code")?;
+        if uploaded.is_some() {
+            return Ok(None);
+        }
+
+        let call = subxt::dynamic::tx(
+            "Contracts",
+            "upload_code",
+            vec![
+                Value::from_bytes(&artifact.code),
+                Value::unnamed_variant("None", []),     // storage deposit limit: as much as it takes
+                Value::unnamed_variant("Enforced", []), // determinism
+            ],
+        );
+
+        let events = self
+            .client
+            .tx()
+            .sign_and_submit_then_watch_default(&call, &self.signer())
+            .await
+            .context("Failed to submit the code upload")?
+            .wait_for_finalized_success()
+            .await
+            .context("Code upload failed")?;
+
+        Ok(Some(format!("{:?}", events.extrinsic_hash())))
+    }
+
+    /// Free balance of `account`, in base units
+    pub async fn free_balance(&self, account: &AccountId32) -> Result<u128> {
+        crate::services::blockchain_service::free_balance(&self.client, account.0).await
+    }
+
+    /// Transfers `amount` base units from the deployer's account to `to`, waiting for
+    /// finalization. Returns the transaction hash.
+    pub async fn transfer(&self, to: &AccountId32, amount: u128) -> Result<String> {
+        let call = subxt::dynamic::tx(
+            "Balances",
+            "transfer_keep_alive",
+            vec![Value::unnamed_variant("Id", [Value::from_bytes(to.0)]), Value::u128(amount)],
+        );
+
+        let events = self
+            .client
+            .tx()
+            .sign_and_submit_then_watch_default(&call, &self.signer())
+            .await
+            .context("Failed to submit the transfer")?
+            .wait_for_finalized_success()
+            .await
+            .context("Transfer failed")?;
+
+        Ok(format!("{:?}", events.extrinsic_hash()))
+    }
+
+    /// Time of the latest block, in milliseconds since the Unix epoch
+    pub async fn chain_time(&self) -> Result<u64> {
+        let query = subxt::dynamic::storage("Timestamp", "Now", Vec::<Value>::new());
+        let now = self
+            .client
+            .storage()
+            .at_latest()
+            .await
+            .context("Failed to get latest block")?
+            .fetch(&query)
+            .await
+            .context("Failed to fetch the chain's time")?
+            .context("The chain has no timestamp")?;
+
+        u64::decode(&mut now.encoded()).map_err(|e| anyhow!("Failed to decode the chain's time: {}", e))
+    }
+
+    /// Number of the block with hash `block_hash`
+    pub async fn block_number(&self, block_hash: H256) -> Result<u64> {
+        let block = self
+            .client
+            .blocks()
+            .at(block_hash)
+            .await
+            .context("Failed to get block")?;
+
+        Ok(block.number() as u64)
+    }
+
+    /// Code hash of the contract at `address`, or `None` if no contract is instantiated there
+    pub async fn onchain_code_hash(&self, address: &AccountId32) -> Result<Option<[u8; 32]>> {
+        let query = subxt::dynamic::storage("Contracts", "ContractInfoOf", vec![Value::from_bytes(address.0)]);
+        let stored = self
+            .client
+            .storage()
+            .at_latest()
+            .await
+            .context("Failed to get latest block")?
+            .fetch(&query)
+            .await
+            .context("Failed to fetch contract info")?;
+
+        stored
+            .map(|info| {
+                ContractInfoPrefix::decode(&mut info.encoded())
+                    .map(|info| info.code_hash)
+                    .map_err(|e| anyhow!("Failed to decode contract info: {}", e))
+            })
+            .transpose()
+    }
+}
+
+/// Names a SCALE-encoded `DispatchError` from its module error, where it has one
+pub(crate) fn describe_dispatch_error(client: &OnlineClient<PolkadotConfig>, encoded: &[u8]) -> String {
+    // DispatchError::Module(ModuleError { index, error, .. })
+    if let [3, pallet_index, error_index, ..] = encoded {
+        let metadata = client.metadata();
+        if let Some(pallet) = metadata.pallet_by_index(*pallet_index) {
+            if let Some(variant) = pallet.error_variant_by_index(*error_index) {
+                return format!("{}::{}", pallet.name(), variant.name);
+            }
+        }
+    }
+
+    format!("dispatch error {}", hex_string(encoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_salt_gives_the_same_address() {
+        let deployer = AccountId32([7; 32]);
+        let code_hash = [9; 32];
+        let constructor = [0x9b, 0xae, 0x9d, 0x5e];
+
+        let salt = deterministic_salt("rococo", "1");
+        assert_eq!(salt, deterministic_salt("rococo", "1"));
+        assert_ne!(salt, deterministic_salt("westend", "1"));
+        assert_ne!(salt, deterministic_salt("rococo", "2"));
+
+        let address = contract_address(&deployer, &code_hash, &constructor, &salt);
+        assert_eq!(address, contract_address(&deployer, &code_hash, &constructor, &salt));
+        assert_ne!(address, contract_address(&deployer, &code_hash, &constructor, &deterministic_salt("rococo", "2")));
+        assert_ne!(address, contract_address(&deployer, &[8; 32], &constructor, &salt));
+    }
+}
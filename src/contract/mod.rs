@@ -1,10 +1,211 @@
 //! Contract interface module for LSRWA Express
+//!
+//! The methods calling the contract's messages are generated by `build.rs` from the contract's
+//! ink! metadata, so a message added to the contract is callable here once the contract is
+//! rebuilt. They encode their arguments after the message's selector and go through
+//! [`LsrwaExpressContract::submit`] and [`LsrwaExpressContract::dry_run`].
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use scale::{Decode, Encode};
+use subxt::blocks::ExtrinsicEvents;
+use subxt::dynamic::Value;
+use subxt::ext::sp_core::{blake2_256, sr25519, Pair as PairTrait, H256};
+use subxt::tx::PairSigner;
+use subxt::utils::AccountId32;
+use subxt::{OnlineClient, PolkadotConfig};
+
+use self::deployment::{describe_dispatch_error, StorageDeposit, Weight, REVERT_FLAG};
+
+pub mod benchmark;
+pub mod deployment;
 
 // Include the generated contract bindings
 include!(concat!(env!("OUT_DIR"), "/generated/contract_bindings.rs"));
 
+/// Proof size limit contract calls are submitted with, alongside their gas limit
+const PROOF_SIZE_LIMIT: u64 = 1024 * 1024;
+
+/// Account contract calls are signed by
+pub type ContractSigner = PairSigner<PolkadotConfig, sr25519::Pair>;
+
+/// The deployed LSRWA Express contract
+pub struct LsrwaExpressContract {
+    pub client: OnlineClient<PolkadotConfig>,
+    pub address: AccountId32,
+}
+
+impl LsrwaExpressContract {
+    pub fn new(client: OnlineClient<PolkadotConfig>, address: AccountId32) -> Self {
+        Self { client, address }
+    }
+
+    /// Submits a call of the contract with `input`, a selector and its encoded arguments, and
+    /// waits for it to be finalized. Returns the transaction hash.
+    pub async fn submit(&self, signer: &ContractSigner, input: Vec<u8>, gas_limit: u64) -> Result<H256> {
+        let events = self.submit_finalized(signer, input, gas_limit).await?;
+
+        Ok(events.extrinsic_hash())
+    }
+
+    /// Like [`submit`](Self::submit), but returns the events of the finalized call, which also
+    /// name the block it was included in
+    pub async fn submit_finalized(
+        &self,
+        signer: &ContractSigner,
+        input: Vec<u8>,
+        gas_limit: u64,
+    ) -> Result<ExtrinsicEvents<PolkadotConfig>> {
+        let call = subxt::dynamic::tx("Contracts", "call", self.call_fields(&input, gas_limit));
+
+        self.client
+            .tx()
+            .sign_and_submit_then_watch_default(&call, signer)
+            .await
+            .context("Failed to submit the contract call")?
+            .wait_for_finalized_success()
+            .await
+            .context("The contract call failed")
+    }
+
+    /// Submits calls of the contract, each an input and its gas limit, as one `Utility::batch`
+    /// extrinsic and waits for it to be finalized. The batch stops at the first call that fails,
+    /// which its `Utility::BatchInterrupted` event names, while the calls before it stand.
+    pub async fn submit_batch_finalized(
+        &self,
+        signer: &ContractSigner,
+        calls: Vec<(Vec<u8>, u64)>,
+    ) -> Result<ExtrinsicEvents<PolkadotConfig>> {
+        let calls = calls
+            .iter()
+            .map(|(input, gas_limit)| {
+                Value::unnamed_variant("Contracts", [Value::unnamed_variant("call", self.call_fields(input, *gas_limit))])
+            })
+            .collect::<Vec<_>>();
+        let batch = subxt::dynamic::tx("Utility", "batch", vec![Value::unnamed_composite(calls)]);
+
+        self.client
+            .tx()
+            .sign_and_submit_then_watch_default(&batch, signer)
+            .await
+            .context("Failed to submit the batch")?
+            .wait_for_finalized_success()
+            .await
+            .context("The batch failed")
+    }
+
+    /// Fields of a `Contracts::call` of the contract with `input`
+    fn call_fields(&self, input: &[u8], gas_limit: u64) -> Vec<Value> {
+        vec![
+            Value::unnamed_variant("Id", [Value::from_bytes(self.address.0)]),
+            Value::u128(0),
+            Value::named_composite([
+                ("ref_time", Value::u128(gas_limit as u128)),
+                ("proof_size", Value::u128(PROOF_SIZE_LIMIT as u128)),
+            ]),
+            Value::unnamed_variant("None", []),
+            Value::from_bytes(input),
+        ]
+    }
+
+    /// Dry-runs a call of the contract with `input` from `origin`, and decodes what the message
+    /// returns. Nothing is submitted, so this is also how read-only messages are called.
+    pub async fn dry_run<R: Decode>(&self, origin: &AccountId32, input: Vec<u8>) -> Result<R> {
+        let (_, data, debug_message) = self.dry_run_call(origin, input).await?;
+
+        // ink!'s MessageResult, whose error is the contract failing to read the input. A message
+        // returning `Err` reverts, but still returns the error, so the return value is decoded
+        // whether or not the call was reverted.
+        let data = &mut &data[..];
+        match u8::decode(data).map_err(|e| anyhow!("Failed to decode the dry-run result: {}", e))? {
+            0 => R::decode(data).map_err(|e| anyhow!("Failed to decode what the message returned: {}", e)),
+            _ => Err(anyhow!("The contract couldn't read the call's input: {}", debug_message)),
+        }
+    }
+
+    /// Dry-runs a call of the contract with `input` from `origin`, and returns the weight and
+    /// storage deposit it needed
+    pub async fn estimate(&self, origin: &AccountId32, input: Vec<u8>) -> Result<CallEstimate> {
+        let (estimate, _, _) = self.dry_run_call(origin, input).await?;
+        Ok(estimate)
+    }
+
+    /// Dry-runs a call, returning what it needed, the data the message returned and the
+    /// contract's debug message. Fails if the call couldn't be dispatched at all.
+    async fn dry_run_call(&self, origin: &AccountId32, input: Vec<u8>) -> Result<(CallEstimate, Vec<u8>, String)> {
+        let args = (
+            origin.0,
+            self.address.0,
+            0u128,          // value
+            None::<Weight>, // gas limit: as much as it takes
+            None::<u128>,   // storage deposit limit: as much as it takes
+            input,
+        )
+            .encode();
+
+        let response: Vec<u8> = self
+            .client
+            .rpc()
+            .state_call("ContractsApi_call", Some(&args), None)
+            .await
+            .context("Failed to dry-run the contract call")?;
+
+        // ContractResult: gas consumed, gas required, storage deposit, debug message, result, ...
+        let input = &mut &response[..];
+        let decode_error = |e: scale::Error| anyhow!("Failed to decode the dry-run result: {}", e);
+        let gas_consumed = Weight::decode(input).map_err(decode_error)?;
+        let gas_required = Weight::decode(input).map_err(decode_error)?;
+        let storage_deposit = match StorageDeposit::decode(input).map_err(decode_error)? {
+            StorageDeposit::Charge(amount) => amount as i128,
+            StorageDeposit::Refund(amount) => -(amount as i128),
+        };
+        let debug_message = String::from_utf8_lossy(&Vec::<u8>::decode(input).map_err(decode_error)?).into_owned();
+
+        match u8::decode(input).map_err(decode_error)? {
+            0 => {
+                let flags = u32::decode(input).map_err(decode_error)?;
+                let data = Vec::<u8>::decode(input).map_err(decode_error)?;
+                let estimate = CallEstimate {
+                    gas_consumed,
+                    gas_required,
+                    storage_deposit,
+                    reverted: flags & REVERT_FLAG != 0,
+                };
+                Ok((estimate, data, debug_message))
+            },
+            _ => Err(anyhow!("The call would fail with {}: {}", describe_dispatch_error(&self.client, *input), debug_message)),
+        }
+    }
+}
+
+/// What a dry-run call of a message needed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallEstimate {
+    pub gas_consumed: Weight,
+    pub gas_required: Weight,
+    /// Deposit charged for storage the call adds, in base units; negative when it frees storage
+    pub storage_deposit: i128,
+    /// Whether the message returned an error, so its changes would be rolled back
+    pub reverted: bool,
+}
+
+/// Selector ink! derives for a message declared without an explicit one
+pub fn selector(label: &str) -> [u8; 4] {
+    let hash = blake2_256(label.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Input of a call of the message with `selector`: the selector, then its arguments
+pub fn message_input<A: Encode>(selector: [u8; 4], args: A) -> Vec<u8> {
+    let mut input = selector.to_vec();
+    args.encode_to(&mut input);
+    input
+}
+
+/// Account `signer` signs for
+pub fn signer_account(signer: &ContractSigner) -> AccountId32 {
+    AccountId32::from(signer.signer().public())
+}
+
 // A simple gas estimator
 pub fn estimate_gas_for_deposit_request(amount: u128) -> u64 {
     // In a production environment, this would use the dry-run API to estimate gas
@@ -32,42 +233,101 @@ pub fn estimate_gas_for_withdrawal_request(amount: u128) -> u64 {
     base_gas + (amount_digits * 100_000_000)
 }
 
-// Helper to create the contract interface with proper configuration
-#[cfg(not(target_arch = "wasm32"))]
-pub async fn create_contract_interface(
-    _client: subxt::OnlineClient<subxt::PolkadotConfig>,
-    contract_address: &str,
-) -> Result<LsrwaExpressContract, Box<dyn std::error::Error>> {
-    use subxt::utils::AccountId32;
-    use std::str::FromStr;
+// Gas estimator for borrow requests
+pub fn estimate_gas_for_borrow_request(amount: u128, collateral: u128) -> u64 {
+    // Borrow requests also check the collateral against the minimum ratio
+    let base_gas: u64 = 6_500_000_000;
     
-    // Parse the contract address
-    let account_id = AccountId32::from_str(contract_address)?;
-    let mut address = [0u8; 32];
-    address.copy_from_slice(account_id.as_ref());
+    let digits = |value: u128| if value == 0 { 1 } else { (value as f64).log10() as u64 + 1 };
+    
+    // Adjust gas based on input size; the collateral is stored with the request
+    base_gas + ((digits(amount) + digits(collateral)) * 100_000_000)
+}
+
+// Gas estimator for KYC allowlist updates
+pub fn estimate_gas_for_kyc_update(wallet_count: usize) -> u64 {
+    // Each wallet is one storage write and one event
+    let base_gas: u64 = 3_000_000_000;
     
-    // For non-wasm32 targets, we use a placeholder client
-    Ok(LsrwaExpressContract::new((), address))
+    base_gas + (wallet_count as u64 * 500_000_000)
+}
+
+// Gas estimator for batch processing of requests
+pub fn estimate_gas_for_batch_processing(request_count: usize) -> u64 {
+    // Each request moves balances and updates the request and epoch counters
+    let base_gas: u64 = 5_000_000_000;
+    
+    base_gas + (request_count as u64 * 1_500_000_000)
+}
+
+// Gas estimator for closing the current epoch
+pub fn estimate_gas_for_epoch_close() -> u64 {
+    // Stores the closed epoch and opens the next one
+    8_000_000_000
+}
+
+// Gas estimator for updating the on-chain risk parameters
+pub fn estimate_gas_for_risk_parameters_update() -> u64 {
+    // Three storage writes and an event
+    3_000_000_000
+}
+
+// Gas estimator for liquidating a borrow
+pub fn estimate_gas_for_liquidation() -> u64 {
+    // Updates the borrower's balance and the liquidation record
+    4_000_000_000
+}
+
+// Gas estimator for scheduling a code upgrade
+pub fn estimate_gas_for_upgrade_schedule() -> u64 {
+    // Stores the code hash and when it may be applied, and an event
+    3_000_000_000
+}
+
+// Gas estimator for applying a scheduled code upgrade
+pub fn estimate_gas_for_upgrade() -> u64 {
+    // Replaces the contract's code hash, clears the schedule and emits an event
+    5_000_000_000
 }
 
-// For wasm32 target, use the original implementation
-#[cfg(target_arch = "wasm32")]
+// Helper to create the contract interface with proper configuration
 pub async fn create_contract_interface(
-    client: subxt::OnlineClient<subxt::PolkadotConfig>,
+    client: OnlineClient<PolkadotConfig>,
     contract_address: &str,
 ) -> Result<LsrwaExpressContract, Box<dyn std::error::Error>> {
+    use std::str::FromStr;
+    
     // Parse the contract address
-    let address = ink::primitives::AccountId::try_from(
-        subxt::utils::AccountId32::from_str(contract_address)?.as_ref()
-    )?;
+    let address = AccountId32::from_str(contract_address)?;
     
     // Create the contract interface
     Ok(LsrwaExpressContract::new(client, address))
 }
 
 // Helper function to parse Substrate events for deposit request results
-pub fn parse_deposit_request_result(_events: &subxt::events::Events<subxt::PolkadotConfig>) -> Option<u128> {
+pub fn parse_deposit_request_result(_events: &subxt::events::Events<PolkadotConfig>) -> Option<u128> {
     // In a full implementation, we would search for the contract event in the events
     // For now, just return None as a placeholder
     None
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_input_is_the_selector_then_the_encoded_arguments() {
+        let selector = [0x77, 0x3f, 0x01, 0x86];
+
+        assert_eq!(message_input(selector, ()), selector.to_vec());
+
+        let mut expected = selector.to_vec();
+        expected.extend(5u128.to_le_bytes());
+        expected.push(1);
+        assert_eq!(message_input(selector, (5u128, true)), expected);
+    }
+
+    #[test]
+    fn selectors_are_the_start_of_the_label_hash() {
+        assert_eq!(selector("flip"), [0x63, 0x3a, 0xa5, 0x51]);
+    }
+}
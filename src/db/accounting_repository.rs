@@ -0,0 +1,90 @@
+//! Reads the protocol events that move money, for the accounting ledger
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::models::accounting::LedgerEvent;
+
+/// Ledger events in `[$1, $2)`, from the beginning when `$1` is NULL
+const LEDGER_EVENTS: &str = r#"
+    WITH events AS (
+        SELECT updated_at AT TIME ZONE 'UTC' AS occurred_at, request_type::TEXT AS kind,
+               on_chain_id::TEXT AS reference, wallet_address::TEXT AS wallet_address, amount
+        FROM lsrwa_express.blockchain_requests
+        WHERE is_processed
+        UNION ALL
+        SELECT updated_at AT TIME ZONE 'UTC', request_type::TEXT, on_chain_id::TEXT, wallet_address::TEXT, amount
+        FROM lsrwa_express_archive.blockchain_requests
+        WHERE is_processed
+        UNION ALL
+        SELECT liquidated_at, 'liquidation', request_id::TEXT, wallet_address, borrow_amount
+        FROM lsrwa_express.borrow_liquidations
+        WHERE status = 'liquidated' AND liquidated_at IS NOT NULL
+        UNION ALL
+        SELECT collected_at, 'fee', fee_type || '-' || request_id, wallet_address::TEXT, amount
+        FROM lsrwa_express.protocol_fees
+        UNION ALL
+        SELECT period_end, 'interest', request_id || '-' || epoch_id, wallet_address::TEXT, interest
+        FROM lsrwa_express.borrow_interest_accruals
+        WHERE interest > 0
+        UNION ALL
+        SELECT r.created_at AT TIME ZONE 'UTC', 'reward', r.id::TEXT, u.wallet_address::TEXT, r.amount
+        FROM lsrwa_express.user_rewards r
+        LEFT JOIN lsrwa_express.users u ON u.id = r.user_id
+        UNION ALL
+        SELECT r.claim_timestamp AT TIME ZONE 'UTC', 'reward_claim', r.id::TEXT, u.wallet_address::TEXT, r.amount
+        FROM lsrwa_express.user_rewards r
+        LEFT JOIN lsrwa_express.users u ON u.id = r.user_id
+        WHERE r.status = 'claimed'
+        UNION ALL
+        SELECT r.updated_at AT TIME ZONE 'UTC', 'reward_expiry', r.id::TEXT, u.wallet_address::TEXT, r.amount
+        FROM lsrwa_express.user_rewards r
+        LEFT JOIN lsrwa_express.users u ON u.id = r.user_id
+        WHERE r.status = 'expired'
+    )
+    SELECT occurred_at, kind, reference, wallet_address, amount
+    FROM events
+    WHERE ($1::TIMESTAMPTZ IS NULL OR occurred_at >= $1) AND occurred_at < $2 AND amount > 0
+    ORDER BY occurred_at, kind, reference
+    "#;
+
+/// Database access for the accounting ledger
+#[derive(Clone)]
+pub struct AccountingRepository {
+    db: PgPool,
+}
+
+impl AccountingRepository {
+    /// Creates a new accounting repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Events with a monetary effect that happened in `[from, to)`, oldest first; from the
+    /// beginning when `from` is `None`. Processed requests are dated when they were marked
+    /// processed, and include those moved to the archive schema.
+    pub async fn list_ledger_events(&self, from: Option<DateTime<Utc>>, to: DateTime<Utc>) -> Result<Vec<LedgerEvent>> {
+        sqlx::query_as::<_, LedgerEvent>(LEDGER_EVENTS)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.db)
+            .await
+            .context("Failed to list ledger events")
+    }
+
+    /// Same as [`list_ledger_events`](Self::list_ledger_events), read from a cursor as the
+    /// stream is polled
+    pub fn stream_ledger_events(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: DateTime<Utc>,
+    ) -> impl Stream<Item = Result<LedgerEvent>> + Send + '_ {
+        sqlx::query_as::<_, LedgerEvent>(LEDGER_EVENTS)
+            .bind(from)
+            .bind(to)
+            .fetch(&self.db)
+            .map(|event| event.context("Failed to read ledger events"))
+    }
+}
@@ -0,0 +1,76 @@
+//! Persistence for the activity log
+
+use anyhow::{Context, Result};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::activity_log::{ActivityLog, ActivityLogFilter, CreateActivityLogRequest};
+
+/// Column list for `activity_logs` - legacy VARCHAR/TIMESTAMP columns are normalised to the model's types
+const ACTIVITY_COLUMNS: &str =
+    "id, user_id, activity_type, description, data, ip_address, created_at AT TIME ZONE 'UTC' AS created_at";
+
+/// Database access for the activity log
+#[derive(Clone)]
+pub struct ActivityLogRepository {
+    db: PgPool,
+}
+
+impl ActivityLogRepository {
+    /// Creates a new activity log repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records an activity
+    pub async fn record(&self, request: &CreateActivityLogRequest) -> Result<ActivityLog> {
+        Self::record_in(&self.db, request).await
+    }
+
+    /// Same as [`record`](Self::record), on the given executor
+    pub async fn record_in<'e>(executor: impl PgExecutor<'e>, request: &CreateActivityLogRequest) -> Result<ActivityLog> {
+        sqlx::query_as::<_, ActivityLog>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.activity_logs (user_id, activity_type, description, data, ip_address)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            ACTIVITY_COLUMNS
+        ))
+        .bind(request.user_id)
+        .bind(&request.activity_type)
+        .bind(&request.description)
+        .bind(&request.data)
+        .bind(&request.ip_address)
+        .fetch_one(executor)
+        .await
+        .context("Failed to record activity")
+    }
+
+    /// Lists activities matching the filter, newest first
+    pub async fn list(&self, filter: &ActivityLogFilter) -> Result<Vec<ActivityLog>> {
+        let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, ActivityLog>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.activity_logs
+            WHERE ($1::UUID IS NULL OR user_id = $1)
+              AND ($2::TEXT IS NULL OR activity_type = $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3 AT TIME ZONE 'UTC')
+              AND ($4::TIMESTAMPTZ IS NULL OR created_at < $4 AT TIME ZONE 'UTC')
+            ORDER BY created_at DESC
+            LIMIT $5 OFFSET $6
+            "#,
+            ACTIVITY_COLUMNS
+        ))
+        .bind(filter.user_id)
+        .bind(&filter.activity_type)
+        .bind(filter.start_date)
+        .bind(filter.end_date)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list activities")
+    }
+}
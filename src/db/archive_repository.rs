@@ -0,0 +1,169 @@
+//! Partition maintenance and archival of high-volume tables
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::models::archive::{EventArchive, EventArchiveFilter, StoredEvent};
+
+/// Tables partitioned by month on `created_at`
+pub const PARTITIONED_TABLES: [&str; 2] = ["event_queue", "activity_logs"];
+
+const EVENT_ARCHIVE_COLUMNS: &str =
+    "day, bucket, object_key, format, event_count, first_block, last_block, byte_size, checksum, created_at";
+
+/// Database access for partition maintenance and archival
+#[derive(Clone)]
+pub struct ArchiveRepository {
+    db: PgPool,
+}
+
+impl ArchiveRepository {
+    /// Creates a new archive repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Creates the monthly partitions of `table` for this month and the next `months_ahead`
+    pub async fn ensure_partitions(&self, table: &str, months_ahead: u32) -> Result<()> {
+        sqlx::query("SELECT lsrwa_express.ensure_monthly_partitions($1, $2)")
+            .bind(table)
+            .bind(months_ahead as i32)
+            .execute(&self.db)
+            .await
+            .with_context(|| format!("Failed to create partitions for {}", table))?;
+
+        Ok(())
+    }
+
+    /// Moves monthly partitions of `table` that ended before `older_than` to the archive schema,
+    /// returning how many were moved
+    pub async fn archive_partitions(&self, table: &str, older_than: DateTime<Utc>) -> Result<u32> {
+        let archived: i32 = sqlx::query_scalar("SELECT lsrwa_express.archive_monthly_partitions($1, $2)")
+            .bind(table)
+            .bind(older_than)
+            .fetch_one(&self.db)
+            .await
+            .with_context(|| format!("Failed to archive partitions of {}", table))?;
+
+        Ok(archived as u32)
+    }
+
+    /// Moves up to `batch_size` requests in a final status last updated before `older_than` to
+    /// the archive schema, returning how many were moved
+    pub async fn archive_blockchain_requests(&self, older_than: DateTime<Utc>, batch_size: u32) -> Result<u32> {
+        let archived: i32 = sqlx::query_scalar("SELECT lsrwa_express.archive_blockchain_requests($1, $2)")
+            .bind(older_than)
+            .bind(batch_size as i32)
+            .fetch_one(&self.db)
+            .await
+            .context("Failed to archive blockchain requests")?;
+
+        Ok(archived as u32)
+    }
+
+    /// Earliest day before `before` with indexed events that comes after the last exported day
+    pub async fn next_unexported_event_day(&self, before: NaiveDate) -> Result<Option<NaiveDate>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT (MIN(created_at) AT TIME ZONE 'UTC')::DATE
+            FROM lsrwa_express.event_queue
+            WHERE created_at >= COALESCE(
+                    (SELECT MAX(day) + 1 FROM lsrwa_express.event_archives)::TIMESTAMP AT TIME ZONE 'UTC',
+                    '-infinity'
+                )
+              AND created_at < $1::TIMESTAMP AT TIME ZONE 'UTC'
+            "#,
+        )
+        .bind(before)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to find the next day of events to export")
+    }
+
+    /// Up to `limit` events indexed on `day`, in indexing order, after the given position
+    pub async fn list_events_indexed_on(
+        &self,
+        day: NaiveDate,
+        after: Option<(DateTime<Utc>, String)>,
+        limit: i64,
+    ) -> Result<Vec<StoredEvent>> {
+        let (after_created_at, after_id) = after.unzip();
+
+        sqlx::query_as::<_, StoredEvent>(
+            r#"
+            SELECT id, event_type, block_number, transaction_hash, request_id, wallet_address, amount,
+                   request_type::TEXT AS request_type, timestamp, raw_data, created_at
+            FROM lsrwa_express.event_queue
+            WHERE created_at >= $1::TIMESTAMP AT TIME ZONE 'UTC'
+              AND created_at < ($1 + 1)::TIMESTAMP AT TIME ZONE 'UTC'
+              AND ($2::TIMESTAMPTZ IS NULL OR (created_at, id) > ($2, $3))
+            ORDER BY created_at, id
+            LIMIT $4
+            "#,
+        )
+        .bind(day)
+        .bind(after_created_at)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .with_context(|| format!("Failed to list events indexed on {}", day))
+    }
+
+    /// Records an exported day in the manifest, replacing an earlier export of the same day
+    pub async fn record_event_archive(&self, archive: &EventArchive) -> Result<EventArchive> {
+        sqlx::query_as::<_, EventArchive>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.event_archives
+                (day, bucket, object_key, format, event_count, first_block, last_block, byte_size, checksum)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (day) DO UPDATE SET
+                bucket = EXCLUDED.bucket,
+                object_key = EXCLUDED.object_key,
+                format = EXCLUDED.format,
+                event_count = EXCLUDED.event_count,
+                first_block = EXCLUDED.first_block,
+                last_block = EXCLUDED.last_block,
+                byte_size = EXCLUDED.byte_size,
+                checksum = EXCLUDED.checksum,
+                created_at = NOW()
+            RETURNING {}
+            "#,
+            EVENT_ARCHIVE_COLUMNS
+        ))
+        .bind(archive.day)
+        .bind(&archive.bucket)
+        .bind(&archive.object_key)
+        .bind(&archive.format)
+        .bind(archive.event_count)
+        .bind(archive.first_block)
+        .bind(archive.last_block)
+        .bind(archive.byte_size)
+        .bind(&archive.checksum)
+        .fetch_one(&self.db)
+        .await
+        .with_context(|| format!("Failed to record event archive of {}", archive.day))
+    }
+
+    /// Lists exported days, most recent first
+    pub async fn list_event_archives(&self, filter: &EventArchiveFilter) -> Result<Vec<EventArchive>> {
+        sqlx::query_as::<_, EventArchive>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.event_archives
+            WHERE ($1::DATE IS NULL OR day >= $1)
+              AND ($2::DATE IS NULL OR day <= $2)
+            ORDER BY day DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            EVENT_ARCHIVE_COLUMNS
+        ))
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.limit.unwrap_or(100))
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list event archives")
+    }
+}
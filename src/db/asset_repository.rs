@@ -0,0 +1,136 @@
+//! Persistence for assets
+//!
+//! Exactly one asset is the default at a time; making another the default clears the flag on the
+//! previous one in the same transaction.
+
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+
+use crate::models::asset::{Asset, UpsertAssetRequest};
+
+/// Column list for `assets` - VARCHAR kinds are normalised to the model's type
+const ASSET_COLUMNS: &str = "symbol, name, decimals, kind::TEXT AS kind, address, is_default, created_at, updated_at";
+
+/// Database access for assets
+#[derive(Clone)]
+pub struct AssetRepository {
+    db: PgPool,
+}
+
+impl AssetRepository {
+    /// Creates a new asset repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Lists every asset, the default first
+    pub async fn list(&self) -> Result<Vec<Asset>> {
+        sqlx::query_as::<_, Asset>(&format!(
+            "SELECT {} FROM lsrwa_express.assets ORDER BY is_default DESC, symbol",
+            ASSET_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list assets")
+    }
+
+    /// Gets an asset by its symbol
+    pub async fn get(&self, symbol: &str) -> Result<Option<Asset>> {
+        sqlx::query_as::<_, Asset>(&format!("SELECT {} FROM lsrwa_express.assets WHERE symbol = $1", ASSET_COLUMNS))
+            .bind(symbol)
+            .fetch_optional(&self.db)
+            .await
+            .context("Failed to fetch asset")
+    }
+
+    /// Gets the asset the contract holds
+    pub async fn default_asset(&self) -> Result<Asset> {
+        sqlx::query_as::<_, Asset>(&format!("SELECT {} FROM lsrwa_express.assets WHERE is_default", ASSET_COLUMNS))
+            .fetch_optional(&self.db)
+            .await
+            .context("Failed to fetch the default asset")?
+            .context("No default asset is registered")
+    }
+
+    /// Registers an asset or changes a registered one
+    pub async fn upsert(&self, symbol: &str, request: &UpsertAssetRequest) -> Result<Asset> {
+        let mut tx = self.db.begin().await.context("Failed to begin transaction")?;
+
+        if request.is_default {
+            sqlx::query("UPDATE lsrwa_express.assets SET is_default = FALSE WHERE is_default AND symbol <> $1")
+                .bind(symbol)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to clear the default asset")?;
+        }
+
+        // The default can only be moved, by making another asset the default
+        let asset = sqlx::query_as::<_, Asset>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.assets (symbol, name, decimals, kind, address, is_default)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (symbol) DO UPDATE
+            SET name = EXCLUDED.name,
+                decimals = EXCLUDED.decimals,
+                kind = EXCLUDED.kind,
+                address = EXCLUDED.address,
+                is_default = assets.is_default OR EXCLUDED.is_default
+            RETURNING {}
+            "#,
+            ASSET_COLUMNS
+        ))
+        .bind(symbol)
+        .bind(&request.name)
+        .bind(request.decimals)
+        .bind(request.kind)
+        .bind(&request.address)
+        .bind(request.is_default)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to save asset")?;
+
+        tx.commit().await.context("Failed to commit transaction")?;
+
+        Ok(asset)
+    }
+
+    /// Keeps the network token's decimals in step with the network the service runs on
+    pub async fn sync_native_decimals(&self, decimals: u32) -> Result<()> {
+        sqlx::query("UPDATE lsrwa_express.assets SET decimals = $1 WHERE kind = 'native' AND decimals <> $1")
+            .bind(decimals as i16)
+            .execute(&self.db)
+            .await
+            .context("Failed to sync the network token's decimals")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::{AssetKind, NATIVE_SYMBOL};
+
+    #[sqlx::test]
+    async fn making_an_asset_the_default_moves_the_default(pool: PgPool) {
+        let assets = AssetRepository::new(pool);
+        assets.sync_native_decimals(10).await.unwrap();
+        let native = assets.default_asset().await.unwrap();
+        assert_eq!((native.symbol.as_str(), native.decimals), (NATIVE_SYMBOL, 10));
+
+        let mut usdc = UpsertAssetRequest {
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            kind: AssetKind::Psp22,
+            address: Some("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string()),
+            is_default: false,
+        };
+        assets.upsert("USDC", &usdc).await.unwrap();
+        assert_eq!(assets.default_asset().await.unwrap().symbol, NATIVE_SYMBOL);
+
+        usdc.is_default = true;
+        assets.upsert("USDC", &usdc).await.unwrap();
+        let listed: Vec<_> = assets.list().await.unwrap().into_iter().map(|asset| (asset.symbol, asset.is_default)).collect();
+        assert_eq!(listed, [("USDC".to_string(), true), (NATIVE_SYMBOL.to_string(), false)]);
+    }
+}
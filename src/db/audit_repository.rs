@@ -0,0 +1,134 @@
+//! Persistence for the admin audit log
+//!
+//! The table is append-only: triggers reject updates, deletes and truncation, so this
+//! repository only ever inserts and reads.
+
+use anyhow::{Context, Result};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::audit::{AuditEntry, AuditFilter, NewAuditEntry};
+use crate::services::audit::AuditContext;
+
+/// Column list for `admin_audit_log`
+const AUDIT_COLUMNS: &str = "id, actor, action::TEXT AS action, target, before_state, after_state, ip_address, request_id, \
+    status_code, transaction_hash, details, created_at";
+
+/// Database access for the admin audit log
+#[derive(Clone)]
+pub struct AuditRepository {
+    db: PgPool,
+}
+
+impl AuditRepository {
+    /// Creates a new audit repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records an operation performed in `context`
+    pub async fn record(&self, context: &AuditContext, entry: &NewAuditEntry) -> Result<AuditEntry> {
+        Self::record_in(&self.db, context, entry).await
+    }
+
+    /// Same as [`record`](Self::record), on the given executor, so the entry is only kept if
+    /// the operation it records commits
+    pub async fn record_in<'e>(
+        executor: impl PgExecutor<'e>,
+        context: &AuditContext,
+        entry: &NewAuditEntry,
+    ) -> Result<AuditEntry> {
+        sqlx::query_as::<_, AuditEntry>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.admin_audit_log
+                (actor, action, target, before_state, after_state, ip_address, request_id,
+                 status_code, transaction_hash, details)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING {}
+            "#,
+            AUDIT_COLUMNS
+        ))
+        .bind(&context.actor)
+        .bind(entry.action)
+        .bind(&entry.target)
+        .bind(&entry.before_state)
+        .bind(&entry.after_state)
+        .bind(&context.ip_address)
+        .bind(&context.request_id)
+        .bind(entry.status_code)
+        .bind(&entry.transaction_hash)
+        .bind(&entry.details)
+        .fetch_one(executor)
+        .await
+        .context("Failed to record audit entry")
+    }
+
+    /// Lists entries matching the filter, newest first
+    pub async fn list(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, AuditEntry>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.admin_audit_log
+            WHERE ($1::TEXT IS NULL OR actor = $1)
+              AND ($2::TEXT IS NULL OR action = $2)
+              AND ($3::TEXT IS NULL OR starts_with(target, $3))
+              AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+              AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+            ORDER BY id DESC
+            LIMIT $6 OFFSET $7
+            "#,
+            AUDIT_COLUMNS
+        ))
+        .bind(&filter.actor)
+        .bind(filter.action)
+        .bind(&filter.target)
+        .bind(filter.start_date)
+        .bind(filter.end_date)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list audit entries")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::audit::AuditAction;
+    use serde_json::json;
+
+    #[sqlx::test]
+    async fn entries_can_be_listed_but_never_changed(pool: PgPool) {
+        let repo = AuditRepository::new(pool.clone());
+        let context = AuditContext {
+            actor: "alice".to_string(),
+            ip_address: Some("10.0.0.7".to_string()),
+            request_id: Some("req-1".to_string()),
+        };
+
+        let entry = NewAuditEntry::new(AuditAction::ParameterChange, "system_parameter:reward_apr_bps")
+            .with_change(Some(&json!("500")), Some(&json!("650")));
+        let recorded = repo.record(&context, &entry).await.unwrap();
+        repo.record(&context, &NewAuditEntry::new(AuditAction::AdminRequest, "GET /api/v1/admin/audit"))
+            .await
+            .unwrap();
+
+        let changes = repo
+            .list(&AuditFilter { action: Some(AuditAction::ParameterChange), ..Default::default() })
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].actor, "alice");
+        assert_eq!(changes[0].after_state, Some(json!("650")));
+
+        let update = sqlx::query("UPDATE lsrwa_express.admin_audit_log SET actor = 'mallory' WHERE id = $1")
+            .bind(recorded.id)
+            .execute(&pool)
+            .await;
+        assert!(update.is_err());
+        assert!(sqlx::query("DELETE FROM lsrwa_express.admin_audit_log").execute(&pool).await.is_err());
+        assert!(sqlx::query("TRUNCATE lsrwa_express.admin_audit_log").execute(&pool).await.is_err());
+    }
+}
@@ -0,0 +1,421 @@
+//! Persistence for user balances
+//!
+//! A user has one balance per asset, and every method is given the asset it reads or changes.
+//! Every mutation is a single guarded statement, so concurrent writers (indexer
+//! handlers, admin corrections) serialise on the row lock instead of racing a
+//! read-modify-write.
+
+use anyhow::{Context, Result};
+use sqlx::types::BigDecimal;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::balance::UserBalance;
+use crate::models::blockchain_request::RequestType;
+use crate::models::stats::ProtocolTotals;
+
+/// Column list for `user_balances` - legacy NUMERIC/TIMESTAMP columns are normalised to the model's types
+const BALANCE_COLUMNS: &str = "id, user_id, asset, active_balance::TEXT AS active_balance, \
+     pending_deposits::TEXT AS pending_deposits, pending_withdrawals::TEXT AS pending_withdrawals, \
+     total_deposited::TEXT AS total_deposited, total_withdrawn::TEXT AS total_withdrawn, \
+     total_rewards::TEXT AS total_rewards, \
+     last_reward_claim_timestamp AT TIME ZONE 'UTC' AS last_reward_claim_timestamp, \
+     created_at AT TIME ZONE 'UTC' AS created_at, updated_at AT TIME ZONE 'UTC' AS updated_at";
+
+/// Database access for user balances
+#[derive(Clone)]
+pub struct BalanceRepository {
+    db: PgPool,
+}
+
+impl BalanceRepository {
+    /// Creates a new balance repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Gets a user's balance in an asset
+    pub async fn get(&self, user_id: Uuid, asset: &str) -> Result<Option<UserBalance>> {
+        Self::get_in(&self.db, user_id, asset).await
+    }
+
+    /// Same as [`get`](Self::get), on the given executor
+    pub async fn get_in<'e>(executor: impl PgExecutor<'e>, user_id: Uuid, asset: &str) -> Result<Option<UserBalance>> {
+        sqlx::query_as::<_, UserBalance>(&format!(
+            "SELECT {} FROM lsrwa_express.user_balances WHERE user_id = $1 AND asset = $2",
+            BALANCE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(asset)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to fetch user balance")
+    }
+
+    /// Reserves a pending deposit or withdrawal.
+    ///
+    /// Withdrawals can only reserve what is left of the active balance after other
+    /// pending withdrawals; `None` is returned when that doesn't cover the amount.
+    pub async fn reserve_pending(
+        &self,
+        user_id: Uuid,
+        asset: &str,
+        request_type: &RequestType,
+        amount: &BigDecimal,
+    ) -> Result<Option<UserBalance>> {
+        Self::reserve_pending_in(&self.db, user_id, asset, request_type, amount).await
+    }
+
+    /// Same as [`reserve_pending`](Self::reserve_pending), on the given executor
+    pub async fn reserve_pending_in<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+        asset: &str,
+        request_type: &RequestType,
+        amount: &BigDecimal,
+    ) -> Result<Option<UserBalance>> {
+        let query = match request_type {
+            RequestType::Deposit => format!(
+                r#"
+                INSERT INTO lsrwa_express.user_balances (user_id, pending_deposits, asset)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, asset) DO UPDATE
+                SET pending_deposits = user_balances.pending_deposits + EXCLUDED.pending_deposits
+                RETURNING {}
+                "#,
+                BALANCE_COLUMNS
+            ),
+            RequestType::Withdrawal => format!(
+                r#"
+                UPDATE lsrwa_express.user_balances
+                SET pending_withdrawals = pending_withdrawals + $2
+                WHERE user_id = $1 AND asset = $3 AND active_balance - pending_withdrawals >= $2
+                RETURNING {}
+                "#,
+                BALANCE_COLUMNS
+            ),
+            RequestType::Borrow => return Ok(None),
+        };
+
+        sqlx::query_as::<_, UserBalance>(&query)
+            .bind(user_id)
+            .bind(amount)
+            .bind(asset)
+            .fetch_optional(executor)
+            .await
+            .context("Failed to reserve pending balance")
+    }
+
+    /// Shifts a reserved deposit by `delta`, when its recorded amount is corrected
+    pub async fn adjust_pending_deposit_in<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+        asset: &str,
+        delta: &BigDecimal,
+    ) -> Result<Option<UserBalance>> {
+        sqlx::query_as::<_, UserBalance>(&format!(
+            r#"
+            UPDATE lsrwa_express.user_balances
+            SET pending_deposits = GREATEST(pending_deposits + $2, 0)
+            WHERE user_id = $1 AND asset = $3
+            RETURNING {}
+            "#,
+            BALANCE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(delta)
+        .bind(asset)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to adjust pending deposit")
+    }
+
+    /// Moves a processed deposit from pending into the active balance
+    pub async fn apply_deposit(&self, user_id: Uuid, asset: &str, amount: &BigDecimal) -> Result<UserBalance> {
+        Self::apply_deposit_in(&self.db, user_id, asset, amount).await
+    }
+
+    /// Same as [`apply_deposit`](Self::apply_deposit), on the given executor
+    pub async fn apply_deposit_in<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+        asset: &str,
+        amount: &BigDecimal,
+    ) -> Result<UserBalance> {
+        sqlx::query_as::<_, UserBalance>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.user_balances (user_id, active_balance, total_deposited, asset)
+            VALUES ($1, $2, $2, $3)
+            ON CONFLICT (user_id, asset) DO UPDATE
+            SET active_balance = user_balances.active_balance + $2,
+                total_deposited = user_balances.total_deposited + $2,
+                pending_deposits = GREATEST(user_balances.pending_deposits - $2, 0)
+            RETURNING {}
+            "#,
+            BALANCE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(amount)
+        .bind(asset)
+        .fetch_one(executor)
+        .await
+        .context("Failed to apply deposit")
+    }
+
+    /// Moves several processed deposits, each a user, asset and amount, into their users' active
+    /// balances in one statement; deposits of the same user and asset are added up first.
+    /// Returns the number of balances changed.
+    pub async fn apply_deposits_in<'e>(
+        executor: impl PgExecutor<'e>,
+        deposits: &[(Uuid, String, BigDecimal)],
+    ) -> Result<u64> {
+        if deposits.is_empty() {
+            return Ok(0);
+        }
+
+        let user_ids: Vec<Uuid> = deposits.iter().map(|(user_id, _, _)| *user_id).collect();
+        let assets: Vec<&str> = deposits.iter().map(|(_, asset, _)| asset.as_str()).collect();
+        let amounts: Vec<BigDecimal> = deposits.iter().map(|(_, _, amount)| amount.clone()).collect();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.user_balances (user_id, asset, active_balance, total_deposited)
+            SELECT user_id, asset, SUM(amount), SUM(amount)
+            FROM UNNEST($1::UUID[], $2::VARCHAR[], $3::NUMERIC[]) AS deposit(user_id, asset, amount)
+            GROUP BY user_id, asset
+            ON CONFLICT (user_id, asset) DO UPDATE
+            SET active_balance = user_balances.active_balance + EXCLUDED.active_balance,
+                total_deposited = user_balances.total_deposited + EXCLUDED.total_deposited,
+                pending_deposits = GREATEST(user_balances.pending_deposits - EXCLUDED.active_balance, 0)
+            "#,
+        )
+        .bind(&user_ids)
+        .bind(&assets)
+        .bind(&amounts)
+        .execute(executor)
+        .await
+        .context("Failed to apply deposits")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Debits an executed withdrawal, returning `None` if the active balance doesn't cover it
+    pub async fn apply_withdrawal(&self, user_id: Uuid, asset: &str, amount: &BigDecimal) -> Result<Option<UserBalance>> {
+        Self::apply_withdrawal_in(&self.db, user_id, asset, amount).await
+    }
+
+    /// Same as [`apply_withdrawal`](Self::apply_withdrawal), on the given executor
+    pub async fn apply_withdrawal_in<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+        asset: &str,
+        amount: &BigDecimal,
+    ) -> Result<Option<UserBalance>> {
+        sqlx::query_as::<_, UserBalance>(&format!(
+            r#"
+            UPDATE lsrwa_express.user_balances
+            SET active_balance = active_balance - $2,
+                total_withdrawn = total_withdrawn + $2,
+                pending_withdrawals = GREATEST(pending_withdrawals - $2, 0)
+            WHERE user_id = $1 AND asset = $3 AND active_balance >= $2
+            RETURNING {}
+            "#,
+            BALANCE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(amount)
+        .bind(asset)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to apply withdrawal")
+    }
+
+    /// Credits a claimed reward to the active balance
+    pub async fn apply_reward(&self, user_id: Uuid, asset: &str, amount: &BigDecimal) -> Result<UserBalance> {
+        Self::apply_reward_in(&self.db, user_id, asset, amount).await
+    }
+
+    /// Same as [`apply_reward`](Self::apply_reward), on the given executor
+    pub async fn apply_reward_in<'e>(
+        executor: impl PgExecutor<'e>,
+        user_id: Uuid,
+        asset: &str,
+        amount: &BigDecimal,
+    ) -> Result<UserBalance> {
+        sqlx::query_as::<_, UserBalance>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.user_balances (user_id, active_balance, total_rewards, last_reward_claim_timestamp, asset)
+            VALUES ($1, $2, $2, NOW() AT TIME ZONE 'UTC', $3)
+            ON CONFLICT (user_id, asset) DO UPDATE
+            SET active_balance = user_balances.active_balance + $2,
+                total_rewards = user_balances.total_rewards + $2,
+                last_reward_claim_timestamp = NOW() AT TIME ZONE 'UTC'
+            RETURNING {}
+            "#,
+            BALANCE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(amount)
+        .bind(asset)
+        .fetch_one(executor)
+        .await
+        .context("Failed to apply reward")
+    }
+
+    /// Balance totals across all users, in the default asset
+    pub async fn protocol_totals(&self) -> Result<ProtocolTotals> {
+        sqlx::query_as::<_, ProtocolTotals>(
+            r#"
+            SELECT
+                COALESCE(SUM(active_balance), 0)::TEXT AS total_value_locked,
+                COUNT(*) FILTER (WHERE active_balance > 0) AS active_depositors,
+                COALESCE(SUM(pending_deposits), 0)::TEXT AS pending_deposits,
+                COALESCE(SUM(pending_withdrawals), 0)::TEXT AS pending_withdrawals
+            FROM lsrwa_express.user_balances
+            WHERE asset = lsrwa_express.default_asset()
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to sum protocol balances")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::AssetRepository;
+    use crate::models::asset::{AssetKind, UpsertAssetRequest, NATIVE_SYMBOL as NATIVE};
+    use crate::test_support::UserBuilder;
+    use std::str::FromStr;
+
+    fn amount(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    fn native() -> String {
+        NATIVE.to_string()
+    }
+
+    #[sqlx::test]
+    async fn withdrawals_reserve_only_what_other_withdrawals_left(pool: PgPool) {
+        let user = UserBuilder::new().insert(&pool).await.unwrap();
+        let balances = BalanceRepository::new(pool);
+        let withdrawal = RequestType::Withdrawal;
+
+        // Nothing to withdraw from before the first deposit
+        assert!(balances.reserve_pending(user.id, NATIVE, &withdrawal, &amount("1")).await.unwrap().is_none());
+
+        balances.apply_deposit(user.id, NATIVE, &amount("100")).await.unwrap();
+        assert!(balances.reserve_pending(user.id, NATIVE, &withdrawal, &amount("60")).await.unwrap().is_some());
+        assert!(balances.reserve_pending(user.id, NATIVE, &withdrawal, &amount("50")).await.unwrap().is_none());
+
+        let reserved = balances.reserve_pending(user.id, NATIVE, &withdrawal, &amount("40")).await.unwrap().unwrap();
+        assert_eq!(amount(&reserved.pending_withdrawals), amount("100"));
+        assert_eq!(amount(&reserved.active_balance), amount("100"));
+    }
+
+    #[sqlx::test]
+    async fn reserved_deposits_follow_their_corrected_amount(pool: PgPool) {
+        let user = UserBuilder::new().insert(&pool).await.unwrap();
+        let balances = BalanceRepository::new(pool.clone());
+
+        // Nothing reserved, nothing to shift
+        assert!(BalanceRepository::adjust_pending_deposit_in(&pool, user.id, NATIVE, &amount("5")).await.unwrap().is_none());
+
+        balances.reserve_pending(user.id, NATIVE, &RequestType::Deposit, &amount("25")).await.unwrap();
+        let adjusted = BalanceRepository::adjust_pending_deposit_in(&pool, user.id, NATIVE, &amount("225")).await.unwrap().unwrap();
+        assert_eq!(amount(&adjusted.pending_deposits), amount("250"));
+        assert_eq!(amount(&adjusted.active_balance), amount("0"));
+
+        let adjusted = BalanceRepository::adjust_pending_deposit_in(&pool, user.id, NATIVE, &amount("-300")).await.unwrap().unwrap();
+        assert_eq!(amount(&adjusted.pending_deposits), amount("0"));
+    }
+
+    #[sqlx::test]
+    async fn applied_amounts_clear_pending_without_going_negative(pool: PgPool) {
+        let user = UserBuilder::new().insert(&pool).await.unwrap();
+        let other = UserBuilder::new().insert(&pool).await.unwrap();
+        let balances = BalanceRepository::new(pool.clone());
+
+        balances.reserve_pending(user.id, NATIVE, &RequestType::Deposit, &amount("50")).await.unwrap();
+        let deposited = balances.apply_deposit(user.id, NATIVE, &amount("80")).await.unwrap();
+        assert_eq!(amount(&deposited.pending_deposits), amount("0"));
+        assert_eq!(amount(&deposited.active_balance), amount("80"));
+
+        // Deposits of the same user are added up before they clear what was reserved
+        balances.reserve_pending(other.id, NATIVE, &RequestType::Deposit, &amount("10")).await.unwrap();
+        let deposits = [(other.id, native(), amount("30")), (other.id, native(), amount("20")), (user.id, native(), amount("20"))];
+        let changed = BalanceRepository::apply_deposits_in(&pool, &deposits).await.unwrap();
+        assert_eq!(changed, 2);
+        let other_balance = balances.get(other.id, NATIVE).await.unwrap().unwrap();
+        assert_eq!(amount(&other_balance.pending_deposits), amount("0"));
+        assert_eq!(amount(&other_balance.active_balance), amount("50"));
+
+        balances.reserve_pending(user.id, NATIVE, &RequestType::Withdrawal, &amount("20")).await.unwrap();
+        let withdrawn = balances.apply_withdrawal(user.id, NATIVE, &amount("50")).await.unwrap().unwrap();
+        assert_eq!(amount(&withdrawn.pending_withdrawals), amount("0"));
+        assert_eq!(amount(&withdrawn.active_balance), amount("50"));
+        assert_eq!(amount(&withdrawn.total_withdrawn), amount("50"));
+
+        // A withdrawal the active balance doesn't cover changes nothing
+        assert!(balances.apply_withdrawal(user.id, NATIVE, &amount("50.000000000000000001")).await.unwrap().is_none());
+        let unchanged = balances.get(user.id, NATIVE).await.unwrap().unwrap();
+        assert_eq!(amount(&unchanged.active_balance), amount("50"));
+    }
+
+    #[sqlx::test]
+    async fn concurrent_withdrawals_never_reserve_more_than_the_balance(pool: PgPool) {
+        let user = UserBuilder::new().insert(&pool).await.unwrap();
+        let balances = BalanceRepository::new(pool);
+        balances.apply_deposit(user.id, NATIVE, &amount("100")).await.unwrap();
+
+        let reservations: Vec<_> = (0..10)
+            .map(|_| {
+                let balances = balances.clone();
+                tokio::spawn(async move {
+                    balances.reserve_pending(user.id, NATIVE, &RequestType::Withdrawal, &amount("30")).await
+                })
+            })
+            .collect();
+        let mut reserved = 0;
+        for reservation in reservations {
+            if reservation.await.unwrap().unwrap().is_some() {
+                reserved += 1;
+            }
+        }
+
+        assert_eq!(reserved, 3);
+        let balance = balances.get(user.id, NATIVE).await.unwrap().unwrap();
+        assert_eq!(amount(&balance.pending_withdrawals), amount("90"));
+    }
+
+    #[sqlx::test]
+    async fn each_asset_has_its_own_balance(pool: PgPool) {
+        let user = UserBuilder::new().insert(&pool).await.unwrap();
+        let usdc = UpsertAssetRequest {
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            kind: AssetKind::Psp22,
+            address: Some("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string()),
+            is_default: false,
+        };
+        AssetRepository::new(pool.clone()).upsert("USDC", &usdc).await.unwrap();
+        let balances = BalanceRepository::new(pool.clone());
+
+        balances.apply_deposit(user.id, NATIVE, &amount("100")).await.unwrap();
+        let deposits = [(user.id, "USDC".to_string(), amount("30")), (user.id, native(), amount("5"))];
+        assert_eq!(BalanceRepository::apply_deposits_in(&pool, &deposits).await.unwrap(), 2);
+        balances.apply_reward(user.id, "USDC", &amount("2")).await.unwrap();
+
+        // A withdrawal is only covered by the balance in its own asset
+        assert!(balances.reserve_pending(user.id, "USDC", &RequestType::Withdrawal, &amount("40")).await.unwrap().is_none());
+        assert!(balances.apply_withdrawal(user.id, "USDC", &amount("40")).await.unwrap().is_none());
+        balances.apply_withdrawal(user.id, "USDC", &amount("12")).await.unwrap().unwrap();
+
+        let native_balance = balances.get(user.id, NATIVE).await.unwrap().unwrap();
+        let usdc_balance = balances.get(user.id, "USDC").await.unwrap().unwrap();
+        assert_eq!((native_balance.asset.as_str(), amount(&native_balance.active_balance)), (NATIVE, amount("105")));
+        assert_eq!((usdc_balance.asset.as_str(), amount(&usdc_balance.active_balance)), ("USDC", amount("20")));
+        assert_eq!(amount(&usdc_balance.total_rewards), amount("2"));
+    }
+}
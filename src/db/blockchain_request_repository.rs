@@ -0,0 +1,826 @@
+//! Persistence for on-chain requests
+//!
+//! Status changes go through [`transition_in`](BlockchainRequestRepository::transition_in) and
+//! [`transition_batch_in`](BlockchainRequestRepository::transition_batch_in), which only apply the
+//! moves [`RequestStatus`] allows and log the ones it doesn't.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::blockchain_request::{
+    BatchItemStatus, BlockchainRequest, NewBlockchainRequest, RequestHistoryFilter, RequestType, TimelineEntry,
+};
+use crate::models::dashboard::OpenRequest;
+use crate::models::liquidity::PendingRequestTotals;
+use crate::models::request_status::{RequestStatus, StatusChange};
+use crate::models::wallet::WalletAddress;
+use crate::services::indexer::EventType;
+
+/// Column list for `blockchain_requests` - legacy VARCHAR/NUMERIC/TIMESTAMP columns are normalised to the model's types
+const REQUEST_COLUMNS: &str = "id, request_type::TEXT AS request_type, on_chain_id, wallet_address, user_id, \
+     amount::TEXT AS amount, collateral_amount::TEXT AS collateral_amount, asset, \
+     submission_timestamp AT TIME ZONE 'UTC' AS submission_timestamp, is_processed, status, block_number, transaction_hash, \
+     created_at AT TIME ZONE 'UTC' AS created_at, updated_at AT TIME ZONE 'UTC' AS updated_at";
+
+/// Default page size for request histories
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+/// Maximum page size for request histories
+const MAX_HISTORY_LIMIT: i64 = 500;
+
+/// Amount requested this epoch per wallet in `$1` and type, counting from `$2` without an
+/// active epoch
+pub(super) const EPOCH_VOLUMES: &str = r#"
+    SELECT wallet_address, request_type::TEXT AS request_type, COALESCE(SUM(amount), 0) AS volume
+    FROM lsrwa_express.blockchain_requests
+    WHERE wallet_address = ANY($1)
+      AND status NOT IN ('cancelled', 'expired')
+      AND submission_timestamp >= COALESCE(
+          (
+              SELECT start_timestamp FROM lsrwa_express.epochs
+              WHERE status = 'active'
+              ORDER BY id DESC
+              LIMIT 1
+          ),
+          $2 AT TIME ZONE 'UTC'
+      )
+    GROUP BY wallet_address, request_type
+"#;
+
+/// Pending requests of type `$1` submitted by `$2` that no batch includes yet, oldest first
+pub(super) fn unbatched_requests() -> &'static str {
+    static SQL: OnceLock<String> = OnceLock::new();
+    SQL.get_or_init(|| {
+        format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blockchain_requests r
+            WHERE request_type = $1
+              AND status IN ('submitted', 'confirmed')
+              AND submission_timestamp <= $2 AT TIME ZONE 'UTC'
+              AND NOT EXISTS (
+                  SELECT 1 FROM lsrwa_express.batch_processing_items i
+                  WHERE i.request_type = r.request_type AND i.request_id = r.on_chain_id
+              )
+            ORDER BY on_chain_id ASC
+            LIMIT $3
+            "#,
+            REQUEST_COLUMNS
+        )
+    })
+}
+
+/// Database access for on-chain requests
+#[derive(Clone)]
+pub struct BlockchainRequestRepository {
+    db: PgPool,
+}
+
+impl BlockchainRequestRepository {
+    /// Creates a new blockchain request repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records a request, creating a user for its wallet if there is none yet; recording an
+    /// already known request returns the existing row, whose status only changes through
+    /// [`transition_in`](Self::transition_in)
+    pub async fn insert(&self, request: &NewBlockchainRequest) -> Result<BlockchainRequest> {
+        Self::insert_in(&self.db, request).await
+    }
+
+    /// Same as [`insert`](Self::insert), on the given executor
+    pub async fn insert_in<'e>(
+        executor: impl PgExecutor<'e>,
+        request: &NewBlockchainRequest,
+    ) -> Result<BlockchainRequest> {
+        let amount = to_decimal(request.amount)?;
+        let collateral_amount = request.collateral_amount.map(to_decimal).transpose()?;
+
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            WITH created AS (
+                INSERT INTO lsrwa_express.users (wallet_address)
+                VALUES ($3)
+                ON CONFLICT (wallet_address) DO NOTHING
+                RETURNING id
+            ), owner AS (
+                SELECT id FROM created
+                UNION ALL
+                SELECT id FROM lsrwa_express.users WHERE wallet_address = $3
+            )
+            INSERT INTO lsrwa_express.blockchain_requests (
+                request_type, on_chain_id, wallet_address, user_id, amount, collateral_amount,
+                submission_timestamp, is_processed, status, block_number, transaction_hash, asset
+            )
+            VALUES (
+                $1, $2, $3, (SELECT id FROM owner LIMIT 1), $4, $5, $6, $7, $8, $9, $10,
+                COALESCE($11, lsrwa_express.default_asset())
+            )
+            ON CONFLICT (request_type, on_chain_id) DO UPDATE
+            SET user_id = COALESCE(blockchain_requests.user_id, EXCLUDED.user_id),
+                collateral_amount = COALESCE(EXCLUDED.collateral_amount, blockchain_requests.collateral_amount),
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(&request.request_type)
+        .bind(request.on_chain_id)
+        .bind(&request.wallet_address)
+        .bind(amount)
+        .bind(collateral_amount)
+        .bind(request.timestamp)
+        .bind(request.status == RequestStatus::Executed)
+        .bind(request.status)
+        .bind(request.block_number)
+        .bind(&request.transaction_hash)
+        .bind(&request.asset)
+        .fetch_one(executor)
+        .await
+        .context("Failed to insert blockchain request")
+    }
+
+    /// Moves a request to `to`, returning how its status changed
+    ///
+    /// Returns `None` when the request isn't recorded or its lifecycle doesn't allow the move,
+    /// which is logged and leaves the status as it was.
+    pub async fn transition(
+        &self,
+        request_type: &RequestType,
+        on_chain_id: i64,
+        to: RequestStatus,
+    ) -> Result<Option<StatusChange>> {
+        Self::transition_in(&self.db, request_type, on_chain_id, to).await
+    }
+
+    /// Same as [`transition`](Self::transition), on the given executor
+    pub async fn transition_in<'e>(
+        executor: impl PgExecutor<'e>,
+        request_type: &RequestType,
+        on_chain_id: i64,
+        to: RequestStatus,
+    ) -> Result<Option<StatusChange>> {
+        let previous = Self::transition_batch_in(executor, request_type, &[on_chain_id], to).await?;
+
+        Ok(previous.into_iter().next().map(|(_, change)| change))
+    }
+
+    /// Moves several requests of a type to `to` in one statement, returning how the status of
+    /// each recorded request that was allowed to move changed
+    pub async fn transition_batch_in<'e>(
+        executor: impl PgExecutor<'e>,
+        request_type: &RequestType,
+        on_chain_ids: &[i64],
+        to: RequestStatus,
+    ) -> Result<Vec<(i64, StatusChange)>> {
+        let sources: Vec<String> = RequestStatus::sources(to).iter().map(ToString::to_string).collect();
+
+        let previous = sqlx::query_as::<_, (i64, RequestStatus)>(
+            r#"
+            WITH current AS (
+                SELECT id, on_chain_id, status FROM lsrwa_express.blockchain_requests
+                WHERE request_type = $1 AND on_chain_id = ANY($2)
+                FOR UPDATE
+            ), moved AS (
+                UPDATE lsrwa_express.blockchain_requests r
+                SET status = $3, is_processed = $4, updated_at = NOW()
+                FROM current
+                WHERE r.id = current.id AND current.status = ANY($5)
+            )
+            SELECT on_chain_id, status FROM current
+            ORDER BY on_chain_id
+            "#,
+        )
+        .bind(request_type)
+        .bind(on_chain_ids)
+        .bind(to)
+        .bind(to == RequestStatus::Executed)
+        .bind(sources)
+        .fetch_all(executor)
+        .await
+        .context("Failed to update blockchain request status")?;
+
+        Ok(previous
+            .into_iter()
+            .filter_map(|(on_chain_id, from)| match from.change_to(to) {
+                Ok(change) => Some((on_chain_id, change)),
+                Err(illegal) => {
+                    error!("Refused status change of {} request {}: {}", request_type, on_chain_id, illegal);
+                    None
+                },
+            })
+            .collect())
+    }
+
+    /// Finds a request by its on-chain identifier
+    pub async fn find_by_on_chain_id(
+        &self,
+        request_type: &RequestType,
+        on_chain_id: i64,
+    ) -> Result<Option<BlockchainRequest>> {
+        Self::find_by_on_chain_id_in(&self.db, request_type, on_chain_id).await
+    }
+
+    /// Same as [`find_by_on_chain_id`](Self::find_by_on_chain_id), on the given executor
+    pub async fn find_by_on_chain_id_in<'e>(
+        executor: impl PgExecutor<'e>,
+        request_type: &RequestType,
+        on_chain_id: i64,
+    ) -> Result<Option<BlockchainRequest>> {
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blockchain_requests
+            WHERE request_type = $1 AND on_chain_id = $2
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(request_type)
+        .bind(on_chain_id)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to fetch blockchain request")
+    }
+
+    /// Locks the request with an on-chain ID for the rest of the transaction and returns it
+    pub async fn lock_by_on_chain_id_in<'e>(
+        executor: impl PgExecutor<'e>,
+        on_chain_id: i64,
+    ) -> Result<Option<BlockchainRequest>> {
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blockchain_requests
+            WHERE on_chain_id = $1
+            ORDER BY id
+            LIMIT 1
+            FOR UPDATE
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(on_chain_id)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to lock blockchain request")
+    }
+
+    /// Overwrites a request's recorded amounts, for corrections from the contract's copy
+    pub async fn correct_amounts_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: i32,
+        amount: &BigDecimal,
+        collateral_amount: Option<&BigDecimal>,
+    ) -> Result<BlockchainRequest> {
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            UPDATE lsrwa_express.blockchain_requests
+            SET amount = $2, collateral_amount = $3, updated_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(id)
+        .bind(amount)
+        .bind(collateral_amount)
+        .fetch_one(executor)
+        .await
+        .context("Failed to correct blockchain request amounts")
+    }
+
+    /// Gets a request by its on-chain ID alone; the contract numbers requests of every type
+    /// from one counter
+    pub async fn get_by_on_chain_id(&self, on_chain_id: i64) -> Result<Option<BlockchainRequest>> {
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blockchain_requests
+            WHERE on_chain_id = $1
+            ORDER BY id
+            LIMIT 1
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(on_chain_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch blockchain request")
+    }
+
+    /// A request's history, oldest first: its submission, the indexed events about it, the
+    /// batches that included it, its execution and how it ended if it was called off. An
+    /// execution both indexed and recorded as an execution event appears once.
+    pub async fn timeline(&self, request: &BlockchainRequest) -> Result<Vec<TimelineEntry>> {
+        let request_events = [EventType::DepositRequest, EventType::WithdrawalRequest, EventType::BorrowRequest]
+            .map(|event_type| event_type as i32);
+
+        sqlx::query_as::<_, TimelineEntry>(
+            r#"
+            SELECT stage, timestamp, transaction_hash, block_number, epoch_id, batch_status
+            FROM (
+                SELECT DISTINCT ON (stage, transaction_hash) *
+                FROM (
+                    SELECT 'submitted' AS stage, r.submission_timestamp AT TIME ZONE 'UTC' AS timestamp,
+                           r.transaction_hash::TEXT AS transaction_hash, r.block_number,
+                           NULL::INTEGER AS epoch_id, NULL::TEXT AS batch_status, 0 AS source
+                    FROM lsrwa_express.blockchain_requests r
+                    WHERE r.id = $1
+                    UNION ALL
+                    SELECT CASE WHEN e.event_type = ANY($5) THEN 'confirmed' ELSE 'executed' END,
+                           e.timestamp, e.transaction_hash::TEXT, e.block_number, NULL, NULL, 1
+                    FROM lsrwa_express.event_queue e
+                    WHERE e.request_id = $3
+                      AND (e.event_type = ANY($5) OR e.event_type = $6)
+                      AND (e.request_type IS NULL OR e.request_type = $2)
+                    UNION ALL
+                    SELECT 'batched', p.processing_timestamp AT TIME ZONE 'UTC', p.transaction_hash::TEXT,
+                           p.block_number, p.epoch_id, i.status::TEXT, 0
+                    FROM lsrwa_express.batch_processing_items i
+                    JOIN lsrwa_express.request_processing_events p ON p.id = i.processing_event_id
+                    WHERE i.request_type = $2 AND i.request_id = $3
+                    UNION ALL
+                    SELECT 'executed', x.execution_timestamp AT TIME ZONE 'UTC', x.transaction_hash::TEXT,
+                           x.block_number, NULL, NULL, 0
+                    FROM lsrwa_express.request_execution_events x
+                    WHERE x.request_id = $3 AND x.wallet_address = $4
+                    UNION ALL
+                    SELECT r.status, r.updated_at AT TIME ZONE 'UTC', NULL, NULL, NULL, NULL, 0
+                    FROM lsrwa_express.blockchain_requests r
+                    WHERE r.id = $1 AND r.status IN ('cancelled', 'expired')
+                ) entries
+                ORDER BY stage, transaction_hash, source, timestamp
+            ) distinct_entries
+            ORDER BY timestamp,
+                     ARRAY_POSITION(
+                         ARRAY['submitted', 'confirmed', 'batched', 'executed', 'cancelled', 'expired'],
+                         stage
+                     )
+            "#,
+        )
+        .bind(request.id)
+        .bind(&request.request_type)
+        .bind(request.on_chain_id)
+        .bind(&request.wallet_address)
+        .bind(&request_events[..])
+        .bind(EventType::RequestExecution as i32)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to fetch request timeline")
+    }
+
+    /// Lists requests of a type waiting to be processed, oldest first
+    pub async fn list_unprocessed(&self, request_type: &RequestType, limit: i64) -> Result<Vec<BlockchainRequest>> {
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blockchain_requests
+            WHERE request_type = $1 AND status IN ('submitted', 'confirmed')
+            ORDER BY on_chain_id ASC
+            LIMIT $2
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(request_type)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list unprocessed blockchain requests")
+    }
+
+    /// Lists a wallet's open requests with whether they have been batched, oldest first
+    pub async fn list_open_by_wallet(&self, wallet_address: &WalletAddress) -> Result<Vec<OpenRequest>> {
+        sqlx::query_as::<_, OpenRequest>(
+            r#"
+            SELECT r.on_chain_id, r.request_type::TEXT AS request_type, r.amount::TEXT AS amount,
+                   r.collateral_amount::TEXT AS collateral_amount,
+                   CASE WHEN EXISTS (
+                       SELECT 1 FROM lsrwa_express.batch_processing_items i
+                       WHERE i.request_type = r.request_type AND i.request_id = r.on_chain_id
+                   ) THEN 'batched' ELSE 'pending' END AS status,
+                   r.submission_timestamp AT TIME ZONE 'UTC' AS submission_timestamp, r.transaction_hash
+            FROM lsrwa_express.blockchain_requests r
+            WHERE r.wallet_address = $1 AND r.status IN ('submitted', 'confirmed', 'processed')
+            ORDER BY r.submission_timestamp, r.on_chain_id
+            "#,
+        )
+        .bind(wallet_address)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list open blockchain requests")
+    }
+
+    /// Lists unprocessed requests of a type submitted up to `submitted_before` that haven't been
+    /// included in a processing batch yet, oldest first
+    pub async fn list_unbatched(
+        &self,
+        request_type: &RequestType,
+        submitted_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<BlockchainRequest>> {
+        sqlx::query_as::<_, BlockchainRequest>(unbatched_requests())
+            .bind(request_type)
+            .bind(submitted_before)
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await
+            .context("Failed to list unbatched blockchain requests")
+    }
+
+    /// Records a batch processing transaction and the requests it included, returning the
+    /// processing event ID
+    pub async fn record_batch_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        request_type: &RequestType,
+        on_chain_ids: &[i64],
+        status: &BatchItemStatus,
+        transaction_hash: &str,
+        block_number: i64,
+    ) -> Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            r#"
+            WITH event AS (
+                INSERT INTO lsrwa_express.request_processing_events (
+                    epoch_id, processing_type, processed_count, transaction_hash, block_number, processing_timestamp
+                )
+                VALUES ($1, $2, CARDINALITY($3::BIGINT[]), $5, $6, NOW() AT TIME ZONE 'UTC')
+                RETURNING id
+            ), items AS (
+                INSERT INTO lsrwa_express.batch_processing_items (processing_event_id, request_id, request_type, status)
+                SELECT event.id, request_id, $2, $4
+                FROM event, UNNEST($3::BIGINT[]) AS request_id
+            )
+            SELECT id FROM event
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(request_type)
+        .bind(on_chain_ids)
+        .bind(status)
+        .bind(transaction_hash)
+        .bind(block_number)
+        .fetch_one(executor)
+        .await
+        .context("Failed to record batch processing")
+    }
+
+    /// Total amount each of several wallets has requested of each type during the active
+    /// epoch, in one query. Without an active epoch, requests since `fallback_start` are
+    /// counted. Wallets and types without requests are left out.
+    pub async fn epoch_volumes(
+        &self,
+        wallet_addresses: &[WalletAddress],
+        fallback_start: DateTime<Utc>,
+    ) -> Result<HashMap<(WalletAddress, RequestType), BigDecimal>> {
+        let volumes = sqlx::query_as::<_, (WalletAddress, RequestType, BigDecimal)>(EPOCH_VOLUMES)
+            .bind(wallet_addresses)
+            .bind(fallback_start)
+            .fetch_all(&self.db)
+            .await
+            .context("Failed to sum epoch request volumes")?;
+
+        Ok(volumes
+            .into_iter()
+            .map(|(wallet_address, request_type, volume)| ((wallet_address, request_type), volume))
+            .collect())
+    }
+
+    /// Takes the submission lock of each wallet, waiting for whoever holds it, until the
+    /// transaction `conn` is on ends. Locks are taken in order so that overlapping sets of
+    /// wallets can't deadlock.
+    pub async fn lock_submissions_in(conn: &mut PgConnection, wallet_addresses: &[WalletAddress]) -> Result<()> {
+        let mut wallet_addresses = wallet_addresses.to_vec();
+        wallet_addresses.sort();
+        wallet_addresses.dedup();
+
+        for wallet_address in &wallet_addresses {
+            sqlx::query("SELECT pg_advisory_xact_lock(hashtext('submission:' || $1))")
+                .bind(wallet_address)
+                .execute(&mut *conn)
+                .await
+                .with_context(|| format!("Failed to lock submissions from {}", wallet_address))?;
+        }
+
+        Ok(())
+    }
+
+    /// Total amount each of several wallets has requested of each type since `since`, in one
+    /// query, for rolling windows. Wallets and types without requests are left out.
+    pub async fn volumes_since(
+        &self,
+        wallet_addresses: &[WalletAddress],
+        since: DateTime<Utc>,
+    ) -> Result<HashMap<(WalletAddress, RequestType), BigDecimal>> {
+        let volumes = sqlx::query_as::<_, (WalletAddress, RequestType, BigDecimal)>(
+            r#"
+            SELECT wallet_address, request_type::TEXT AS request_type, COALESCE(SUM(amount), 0) AS volume
+            FROM lsrwa_express.blockchain_requests
+            WHERE wallet_address = ANY($1)
+              AND status NOT IN ('cancelled', 'expired')
+              AND submission_timestamp >= $2 AT TIME ZONE 'UTC'
+            GROUP BY wallet_address, request_type
+            "#,
+        )
+        .bind(wallet_addresses)
+        .bind(since)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to sum request volumes")?;
+
+        Ok(volumes
+            .into_iter()
+            .map(|(wallet_address, request_type, volume)| ((wallet_address, request_type), volume))
+            .collect())
+    }
+
+    /// Totals of the deposits and withdrawals not yet settled
+    pub async fn pending_totals(&self) -> Result<PendingRequestTotals> {
+        sqlx::query_as::<_, PendingRequestTotals>(
+            r#"
+            SELECT
+                COALESCE(SUM(amount) FILTER (WHERE request_type = 'deposit'), 0)::TEXT AS deposit_total,
+                COUNT(*) FILTER (WHERE request_type = 'deposit') AS deposit_count,
+                COALESCE(SUM(amount) FILTER (WHERE request_type = 'withdrawal'), 0)::TEXT AS withdrawal_total,
+                COUNT(*) FILTER (WHERE request_type = 'withdrawal') AS withdrawal_count
+            FROM lsrwa_express.blockchain_requests
+            WHERE status IN ('submitted', 'confirmed', 'processed')
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to sum pending blockchain requests")
+    }
+
+    /// Lists a user's requests matching the filter, newest first
+    pub async fn list_by_user(&self, user_id: Uuid, filter: &RequestHistoryFilter) -> Result<Vec<BlockchainRequest>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, BlockchainRequest>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blockchain_requests
+            WHERE user_id = $1
+              AND ($2::TEXT IS NULL OR request_type = $2)
+              AND ($3::TEXT IS NULL OR status = $3)
+            ORDER BY submission_timestamp DESC, id DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            REQUEST_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&filter.request_type)
+        .bind(filter.status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list user's blockchain requests")
+    }
+
+    /// Creates a user for every wallet with requests that aren't linked to one, returning how
+    /// many were created
+    pub async fn create_missing_owners_in<'e>(executor: impl PgExecutor<'e>) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.users (wallet_address)
+            SELECT DISTINCT wallet_address FROM lsrwa_express.blockchain_requests
+            WHERE user_id IS NULL
+            ON CONFLICT (wallet_address) DO NOTHING
+            "#,
+        )
+        .execute(executor)
+        .await
+        .context("Failed to create users for unlinked blockchain requests")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Links every unlinked request to the user of its wallet, returning how many were linked
+    pub async fn link_unowned_in<'e>(executor: impl PgExecutor<'e>) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE lsrwa_express.blockchain_requests r
+            SET user_id = u.id
+            FROM lsrwa_express.users u
+            WHERE r.user_id IS NULL AND u.wallet_address = r.wallet_address
+            "#,
+        )
+        .execute(executor)
+        .await
+        .context("Failed to link blockchain requests to users")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Links a wallet's unlinked requests to a user, returning how many were linked
+    pub async fn link_to_user(&self, wallet_address: &WalletAddress, user_id: Uuid) -> Result<u64> {
+        Self::link_to_user_in(&self.db, wallet_address, user_id).await
+    }
+
+    /// Same as [`link_to_user`](Self::link_to_user), on the given executor
+    pub async fn link_to_user_in<'e>(
+        executor: impl PgExecutor<'e>,
+        wallet_address: &WalletAddress,
+        user_id: Uuid,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE lsrwa_express.blockchain_requests
+            SET user_id = $2
+            WHERE wallet_address = $1 AND user_id IS NULL
+            "#,
+        )
+        .bind(wallet_address)
+        .bind(user_id)
+        .execute(executor)
+        .await
+        .context("Failed to link blockchain requests to user")?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+fn to_decimal(amount: f64) -> Result<BigDecimal> {
+    BigDecimal::from_str(&amount.to_string()).with_context(|| format!("Invalid amount {}", amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::UserRepository;
+    use crate::models::blockchain_request::TimelineStage;
+    use crate::test_support::{fake, EpochBuilder, EventBuilder, RequestBuilder};
+
+    #[sqlx::test]
+    async fn requests_only_take_the_moves_their_lifecycle_allows(pool: PgPool) {
+        let repo = BlockchainRequestRepository::new(pool.clone());
+        let first = RequestBuilder::withdrawal().status(RequestStatus::Submitted).insert(&pool).await.unwrap();
+        let second = RequestBuilder::withdrawal().insert(&pool).await.unwrap();
+        let withdrawal = &RequestType::Withdrawal;
+
+        assert_eq!(
+            repo.transition(withdrawal, first.on_chain_id, RequestStatus::Confirmed).await.unwrap(),
+            Some(StatusChange::Moved(RequestStatus::Submitted))
+        );
+        let moved = BlockchainRequestRepository::transition_batch_in(
+            &pool,
+            withdrawal,
+            &[first.on_chain_id, second.on_chain_id],
+            RequestStatus::Executed,
+        )
+        .await
+        .unwrap();
+        assert_eq!(moved.len(), 2);
+
+        // Replays leave the request alone, and executed requests can't go back
+        assert_eq!(
+            repo.transition(withdrawal, first.on_chain_id, RequestStatus::Executed).await.unwrap(),
+            Some(StatusChange::Unchanged)
+        );
+        assert_eq!(repo.transition(withdrawal, first.on_chain_id, RequestStatus::Processed).await.unwrap(), None);
+        assert_eq!(repo.transition(withdrawal, -1, RequestStatus::Processed).await.unwrap(), None);
+
+        let read = repo.find_by_on_chain_id(withdrawal, first.on_chain_id).await.unwrap().unwrap();
+        assert_eq!(read.status, RequestStatus::Executed);
+        assert!(read.is_processed);
+    }
+
+    #[sqlx::test]
+    async fn requests_are_linked_to_a_user_created_for_their_wallet(pool: PgPool) {
+        let repo = BlockchainRequestRepository::new(pool.clone());
+        let users = UserRepository::new(pool.clone());
+        let wallet = fake::wallet_address();
+
+        let deposit = RequestBuilder::deposit().wallet(&wallet).insert(&pool).await.unwrap();
+        let withdrawal = RequestBuilder::withdrawal().wallet(&wallet).insert(&pool).await.unwrap();
+        let user = users.get_by_wallet(&wallet).await.unwrap().unwrap();
+        assert_eq!(deposit.user_id, Some(user.id));
+        assert_eq!(withdrawal.user_id, Some(user.id));
+
+        let history = repo.list_by_user(user.id, &RequestHistoryFilter::default()).await.unwrap();
+        assert_eq!(history.len(), 2);
+        let deposits = RequestHistoryFilter { request_type: Some(RequestType::Deposit), ..Default::default() };
+        let history = repo.list_by_user(user.id, &deposits).await.unwrap();
+        assert_eq!(history.iter().map(|request| request.id).collect::<Vec<_>>(), [deposit.id]);
+    }
+
+    #[sqlx::test]
+    async fn borrow_events_fill_in_the_collateral(pool: PgPool) {
+        let mut recorded = RequestBuilder::borrow().amount(100.0).build();
+        recorded.collateral_amount = None;
+        let request = BlockchainRequestRepository::insert_in(&pool, &recorded).await.unwrap();
+        assert_eq!(request.collateral_amount, None);
+
+        let indexed = NewBlockchainRequest { collateral_amount: Some(150.0), ..recorded.clone() };
+        let request = BlockchainRequestRepository::insert_in(&pool, &indexed).await.unwrap();
+        assert_eq!(request.collateral_amount.as_deref(), Some("150.000000000000000000"));
+
+        // Recording the request again without collateral keeps what the event said
+        let request = BlockchainRequestRepository::insert_in(&pool, &recorded).await.unwrap();
+        assert_eq!(request.collateral_amount.as_deref(), Some("150.000000000000000000"));
+    }
+
+    #[sqlx::test]
+    async fn backfill_links_requests_recorded_without_a_user(pool: PgPool) {
+        let users = UserRepository::new(pool.clone());
+        let request = RequestBuilder::deposit().insert(&pool).await.unwrap();
+        sqlx::query("UPDATE lsrwa_express.blockchain_requests SET user_id = NULL")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM lsrwa_express.users").execute(&pool).await.unwrap();
+
+        assert_eq!(BlockchainRequestRepository::create_missing_owners_in(&pool).await.unwrap(), 1);
+        assert_eq!(BlockchainRequestRepository::link_unowned_in(&pool).await.unwrap(), 1);
+        assert_eq!(BlockchainRequestRepository::link_unowned_in(&pool).await.unwrap(), 0);
+
+        let user = users.get_by_wallet(&request.wallet_address).await.unwrap().unwrap();
+        let read = BlockchainRequestRepository::new(pool.clone())
+            .find_by_on_chain_id(&RequestType::Deposit, request.on_chain_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.user_id, Some(user.id));
+    }
+
+    #[sqlx::test]
+    async fn timelines_follow_a_request_from_submission_to_execution(pool: PgPool) {
+        let repo = BlockchainRequestRepository::new(pool.clone());
+        let submitted_at = (Utc::now() - chrono::Duration::hours(1)).naive_utc();
+        let request = RequestBuilder::deposit().submitted_at(submitted_at).insert(&pool).await.unwrap();
+        EventBuilder::request(RequestType::Deposit, request.on_chain_id as u128).insert(&pool).await.unwrap();
+        let epoch = EpochBuilder::new().insert(&pool).await.unwrap();
+        let batch_tx = fake::transaction_hash();
+        BlockchainRequestRepository::record_batch_in(
+            &pool,
+            epoch.id,
+            &RequestType::Deposit,
+            &[request.on_chain_id],
+            &BatchItemStatus::Processed,
+            &batch_tx,
+            fake::block_number(),
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.request_execution_events (
+                request_id, wallet_address, amount, transaction_hash, block_number, execution_timestamp
+            )
+            VALUES ($1, $2, $3, $4, $5, (NOW() + INTERVAL '1 minute') AT TIME ZONE 'UTC')
+            "#,
+        )
+        .bind(request.on_chain_id)
+        .bind(&request.wallet_address)
+        .bind(BigDecimal::from(10))
+        .bind(fake::transaction_hash())
+        .bind(fake::block_number())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let found = repo.get_by_on_chain_id(request.on_chain_id).await.unwrap().unwrap();
+        let timeline = repo.timeline(&found).await.unwrap();
+        let stages: Vec<TimelineStage> = timeline.iter().map(|entry| entry.stage).collect();
+        assert_eq!(
+            stages,
+            [TimelineStage::Submitted, TimelineStage::Confirmed, TimelineStage::Batched, TimelineStage::Executed]
+        );
+        assert_eq!(timeline[0].transaction_hash.as_deref(), Some(request.transaction_hash.as_str()));
+        assert_eq!(timeline[2].transaction_hash.as_deref(), Some(batch_tx.as_str()));
+        assert_eq!((timeline[2].epoch_id, timeline[2].batch_status.as_deref()), (Some(epoch.id), Some("processed")));
+
+        let cancelled = RequestBuilder::withdrawal().status(RequestStatus::Cancelled).insert(&pool).await.unwrap();
+        let stages: Vec<TimelineStage> = repo.timeline(&cancelled).await.unwrap().iter().map(|entry| entry.stage).collect();
+        assert_eq!(stages, [TimelineStage::Submitted, TimelineStage::Cancelled]);
+    }
+
+    #[sqlx::test]
+    async fn submissions_from_a_wallet_wait_for_each_other(pool: PgPool) {
+        let wallet = fake::wallet_address();
+        let other = fake::wallet_address();
+        let mut first = pool.begin().await.unwrap();
+        BlockchainRequestRepository::lock_submissions_in(&mut first, std::slice::from_ref(&wallet)).await.unwrap();
+
+        // Other wallets go ahead
+        let mut unrelated = pool.begin().await.unwrap();
+        BlockchainRequestRepository::lock_submissions_in(&mut unrelated, std::slice::from_ref(&other)).await.unwrap();
+        unrelated.rollback().await.unwrap();
+
+        let waiting = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                let mut second = pool.begin().await.unwrap();
+                BlockchainRequestRepository::lock_submissions_in(&mut second, &[other, wallet]).await.unwrap();
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!waiting.is_finished());
+
+        first.rollback().await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), waiting).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+//! Persistence for contract deployments and upgrades
+//!
+//! The deployments double as a registry of which contract is live on each network: a verified
+//! deployment goes live and supersedes the network's previous live one.
+
+use anyhow::{Context, Result};
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::deployment::{
+    ContractDeployment, ContractVersion, DeploymentFilter, DeploymentStatus, NewContractDeployment, NewContractVersion,
+};
+
+/// Column list for `contract_deployments`
+const DEPLOYMENT_COLUMNS: &str = "id, network, chain_genesis_hash, contract_address, code_hash, salt, deployer, \
+    transaction_hash, block_number, gas_ref_time, gas_proof_size, storage_deposit, verified, status, created_at, \
+    updated_at";
+
+/// Column list for `contract_versions`
+const VERSION_COLUMNS: &str = "id, network, contract_address, previous_code_hash, code_hash, upload_transaction_hash, \
+    schedule_transaction_hash, executable_at, upgrade_transaction_hash, block_number, upgraded_by, migration_notes, \
+    verified, created_at";
+
+/// Database access for contract deployments and upgrades
+#[derive(Clone)]
+pub struct DeploymentRepository {
+    db: PgPool,
+}
+
+impl DeploymentRepository {
+    /// Creates a new deployment repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records a deployment, live if it's verified and pending otherwise. Redeploying to an
+    /// address already recorded on the network, as a reused salt does, replaces the earlier record.
+    pub async fn record(&self, deployment: &NewContractDeployment) -> Result<ContractDeployment> {
+        let mut tx = self.db.begin().await.context("Failed to begin transaction")?;
+
+        let recorded = sqlx::query_as::<_, ContractDeployment>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.contract_deployments
+                (network, chain_genesis_hash, contract_address, code_hash, salt, deployer, transaction_hash,
+                 block_number, gas_ref_time, gas_proof_size, storage_deposit, verified)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (network, contract_address) DO UPDATE
+            SET chain_genesis_hash = EXCLUDED.chain_genesis_hash,
+                code_hash = EXCLUDED.code_hash,
+                salt = EXCLUDED.salt,
+                deployer = EXCLUDED.deployer,
+                transaction_hash = EXCLUDED.transaction_hash,
+                block_number = EXCLUDED.block_number,
+                gas_ref_time = EXCLUDED.gas_ref_time,
+                gas_proof_size = EXCLUDED.gas_proof_size,
+                storage_deposit = EXCLUDED.storage_deposit,
+                verified = EXCLUDED.verified,
+                status = 'pending',
+                created_at = NOW(),
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            DEPLOYMENT_COLUMNS
+        ))
+        .bind(&deployment.network)
+        .bind(&deployment.chain_genesis_hash)
+        .bind(&deployment.contract_address)
+        .bind(&deployment.code_hash)
+        .bind(&deployment.salt)
+        .bind(&deployment.deployer)
+        .bind(&deployment.transaction_hash)
+        .bind(deployment.block_number)
+        .bind(deployment.gas_ref_time)
+        .bind(deployment.gas_proof_size)
+        .bind(&deployment.storage_deposit)
+        .bind(deployment.verified)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to record contract deployment")?;
+
+        let recorded = if recorded.verified { go_live(&mut tx, recorded.id).await? } else { recorded };
+
+        tx.commit().await.context("Failed to commit contract deployment")?;
+        Ok(recorded)
+    }
+
+    /// Marks a recorded deployment as verified, or not, against a local build. A pending
+    /// deployment that verifies goes live.
+    pub async fn set_verified(&self, network: &str, contract_address: &str, verified: bool) -> Result<Option<ContractDeployment>> {
+        let mut tx = self.db.begin().await.context("Failed to begin transaction")?;
+
+        let updated = sqlx::query_as::<_, ContractDeployment>(&format!(
+            r#"
+            UPDATE lsrwa_express.contract_deployments
+            SET verified = $3, updated_at = NOW()
+            WHERE network = $1 AND contract_address = $2
+            RETURNING {}
+            "#,
+            DEPLOYMENT_COLUMNS
+        ))
+        .bind(network)
+        .bind(contract_address)
+        .bind(verified)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to update contract deployment")?;
+
+        let updated = match updated {
+            Some(deployment) if deployment.verified && deployment.status == DeploymentStatus::Pending => {
+                Some(go_live(&mut tx, deployment.id).await?)
+            },
+            updated => updated,
+        };
+
+        tx.commit().await.context("Failed to commit contract deployment")?;
+        Ok(updated)
+    }
+
+    /// The live deployment of a network, if any
+    pub async fn live(&self, network: &str) -> Result<Option<ContractDeployment>> {
+        sqlx::query_as::<_, ContractDeployment>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.contract_deployments
+            WHERE network = $1 AND status = 'live'
+            "#,
+            DEPLOYMENT_COLUMNS
+        ))
+        .bind(network)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch live contract deployment")
+    }
+
+    /// Records that an upgrade replaced a deployed contract's code
+    pub async fn set_code_hash(&self, network: &str, contract_address: &str, code_hash: &str) -> Result<Option<ContractDeployment>> {
+        sqlx::query_as::<_, ContractDeployment>(&format!(
+            r#"
+            UPDATE lsrwa_express.contract_deployments
+            SET code_hash = $3, updated_at = NOW()
+            WHERE network = $1 AND contract_address = $2
+            RETURNING {}
+            "#,
+            DEPLOYMENT_COLUMNS
+        ))
+        .bind(network)
+        .bind(contract_address)
+        .bind(code_hash)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to update contract deployment")
+    }
+
+    /// Deployments matching a filter, newest first
+    pub async fn list(&self, filter: &DeploymentFilter) -> Result<Vec<ContractDeployment>> {
+        sqlx::query_as::<_, ContractDeployment>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.contract_deployments
+            WHERE ($1::TEXT IS NULL OR network = $1)
+              AND ($2::TEXT IS NULL OR status = $2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            DEPLOYMENT_COLUMNS
+        ))
+        .bind(&filter.network)
+        .bind(filter.status)
+        .bind(filter.limit.unwrap_or(100))
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list contract deployments")
+    }
+
+    /// Records an upgrade of a deployed contract's code
+    pub async fn record_version(&self, version: &NewContractVersion) -> Result<ContractVersion> {
+        sqlx::query_as::<_, ContractVersion>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.contract_versions
+                (network, contract_address, previous_code_hash, code_hash, upload_transaction_hash,
+                 schedule_transaction_hash, executable_at, upgrade_transaction_hash, block_number, upgraded_by,
+                 migration_notes, verified)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING {}
+            "#,
+            VERSION_COLUMNS
+        ))
+        .bind(&version.network)
+        .bind(&version.contract_address)
+        .bind(&version.previous_code_hash)
+        .bind(&version.code_hash)
+        .bind(&version.upload_transaction_hash)
+        .bind(&version.schedule_transaction_hash)
+        .bind(version.executable_at)
+        .bind(&version.upgrade_transaction_hash)
+        .bind(version.block_number)
+        .bind(&version.upgraded_by)
+        .bind(&version.migration_notes)
+        .bind(version.verified)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to record contract version")
+    }
+
+    /// Upgrades of a contract, newest first
+    pub async fn versions(&self, network: &str, contract_address: &str) -> Result<Vec<ContractVersion>> {
+        sqlx::query_as::<_, ContractVersion>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.contract_versions
+            WHERE network = $1 AND contract_address = $2
+            ORDER BY created_at DESC, id DESC
+            "#,
+            VERSION_COLUMNS
+        ))
+        .bind(network)
+        .bind(contract_address)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list contract versions")
+    }
+}
+
+/// Makes a deployment its network's live one, superseding the previous live deployment
+async fn go_live(conn: &mut PgConnection, id: i64) -> Result<ContractDeployment> {
+    sqlx::query(
+        r#"
+        UPDATE lsrwa_express.contract_deployments
+        SET status = 'superseded', updated_at = NOW()
+        WHERE status = 'live' AND id <> $1
+          AND network = (SELECT network FROM lsrwa_express.contract_deployments WHERE id = $1)
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *conn)
+    .await
+    .context("Failed to supersede the live contract deployment")?;
+
+    sqlx::query_as::<_, ContractDeployment>(&format!(
+        r#"
+        UPDATE lsrwa_express.contract_deployments
+        SET status = 'live', updated_at = NOW()
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        DEPLOYMENT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await
+    .context("Failed to make the contract deployment live")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use sqlx::types::BigDecimal;
+
+    fn deployment(address: &str, transaction_hash: &str) -> NewContractDeployment {
+        NewContractDeployment {
+            network: "local".to_string(),
+            chain_genesis_hash: format!("0x{}", "11".repeat(32)),
+            contract_address: address.to_string(),
+            code_hash: format!("0x{}", "22".repeat(32)),
+            salt: format!("0x{}", "33".repeat(32)),
+            deployer: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            transaction_hash: transaction_hash.to_string(),
+            block_number: 42,
+            gas_ref_time: 1_500_000_000,
+            gas_proof_size: 65_536,
+            storage_deposit: BigDecimal::from_str("1000000000000").unwrap(),
+            verified: true,
+        }
+    }
+
+    #[sqlx::test]
+    async fn redeploying_to_the_same_address_replaces_the_record(pool: PgPool) {
+        let repo = DeploymentRepository::new(pool);
+        let address = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+        repo.record(&deployment(address, "0xaa")).await.unwrap();
+        let redeployed = repo.record(&deployment(address, "0xbb")).await.unwrap();
+        assert_eq!(redeployed.transaction_hash, "0xbb");
+
+        let local = DeploymentFilter { network: Some("local".to_string()), ..DeploymentFilter::default() };
+        let recorded = repo.list(&local).await.unwrap();
+        assert_eq!(recorded.len(), 1);
+        let rococo = DeploymentFilter { network: Some("rococo".to_string()), ..DeploymentFilter::default() };
+        assert!(repo.list(&rococo).await.unwrap().is_empty());
+
+        let unverified = repo.set_verified("local", address, false).await.unwrap().unwrap();
+        assert!(!unverified.verified);
+    }
+
+    #[sqlx::test]
+    async fn a_verified_deployment_supersedes_the_live_one(pool: PgPool) {
+        let repo = DeploymentRepository::new(pool);
+        let first = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+        let second = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+
+        assert_eq!(repo.record(&deployment(first, "0xaa")).await.unwrap().status, DeploymentStatus::Live);
+
+        let pending = repo.record(&NewContractDeployment { verified: false, ..deployment(second, "0xbb") }).await.unwrap();
+        assert_eq!(pending.status, DeploymentStatus::Pending);
+        assert_eq!(repo.live("local").await.unwrap().unwrap().contract_address, first);
+
+        let verified = repo.set_verified("local", second, true).await.unwrap().unwrap();
+        assert_eq!(verified.status, DeploymentStatus::Live);
+        assert_eq!(repo.live("local").await.unwrap().unwrap().contract_address, second);
+        assert!(repo.live("rococo").await.unwrap().is_none());
+
+        let superseded = DeploymentFilter { status: Some(DeploymentStatus::Superseded), ..DeploymentFilter::default() };
+        let superseded = repo.list(&superseded).await.unwrap();
+        assert_eq!(superseded.len(), 1);
+        assert_eq!(superseded[0].contract_address, first);
+
+        let upgraded = repo.set_code_hash("local", second, "0x44").await.unwrap().unwrap();
+        assert_eq!(upgraded.code_hash, "0x44");
+        assert_eq!(upgraded.status, DeploymentStatus::Live);
+    }
+
+    fn version(previous_code_hash: &str, code_hash: &str) -> NewContractVersion {
+        NewContractVersion {
+            network: "local".to_string(),
+            contract_address: "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string(),
+            previous_code_hash: previous_code_hash.to_string(),
+            code_hash: code_hash.to_string(),
+            upload_transaction_hash: None,
+            schedule_transaction_hash: "0xaa".to_string(),
+            executable_at: chrono::Utc::now(),
+            upgrade_transaction_hash: "0xbb".to_string(),
+            block_number: 100,
+            upgraded_by: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            migration_notes: "No storage changes".to_string(),
+            verified: true,
+        }
+    }
+
+    #[sqlx::test]
+    async fn versions_are_listed_newest_first(pool: PgPool) {
+        let repo = DeploymentRepository::new(pool);
+
+        repo.record_version(&version("0x01", "0x02")).await.unwrap();
+        repo.record_version(&version("0x02", "0x03")).await.unwrap();
+
+        let versions = repo.versions("local", "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty").await.unwrap();
+        let code_hashes = versions.iter().map(|version| version.code_hash.as_str()).collect::<Vec<_>>();
+        assert_eq!(code_hashes, ["0x03", "0x02"]);
+        assert!(repo.versions("rococo", "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+//! Persistence for epoch close sequences
+
+use anyhow::{Context, Result};
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::blockchain_request::RequestType;
+use crate::models::epoch::{EpochProcessingRun, EpochProcessingStep, EpochStatsSnapshot, ProcessEpochResult};
+
+/// Column list for `epoch_processing_runs`
+const RUN_COLUMNS: &str = "epoch_id, status::TEXT AS status, step::TEXT AS step, deposits_processed, \
+     withdrawals_processed, borrows_processed, close_tx_hash::TEXT AS close_tx_hash, next_epoch_id, stats, result, \
+     error, started_at, updated_at, completed_at";
+
+/// Database access for epoch close sequences
+#[derive(Clone)]
+pub struct EpochProcessingRepository {
+    db: PgPool,
+}
+
+impl EpochProcessingRepository {
+    /// Creates a new epoch processing repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Gets the close sequence of an epoch
+    pub async fn get(&self, epoch_id: i32) -> Result<Option<EpochProcessingRun>> {
+        sqlx::query_as::<_, EpochProcessingRun>(&format!(
+            "SELECT {} FROM lsrwa_express.epoch_processing_runs WHERE epoch_id = $1",
+            RUN_COLUMNS
+        ))
+        .bind(epoch_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch epoch processing run")
+    }
+
+    /// Gets the close sequences of the given epochs that have one
+    pub async fn for_epochs(&self, epoch_ids: &[i32]) -> Result<Vec<EpochProcessingRun>> {
+        sqlx::query_as::<_, EpochProcessingRun>(&format!(
+            "SELECT {} FROM lsrwa_express.epoch_processing_runs WHERE epoch_id = ANY($1)",
+            RUN_COLUMNS
+        ))
+        .bind(epoch_ids)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to fetch epoch processing runs")
+    }
+
+    /// Starts an epoch's close sequence, or resumes it at the step it failed. A running sequence
+    /// that hasn't made progress for `stale_secs` (e.g. because the server restarted) is resumed
+    /// too. Returns `None` when the sequence is already running or has completed.
+    pub async fn start_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        stale_secs: i64,
+    ) -> Result<Option<EpochProcessingRun>> {
+        sqlx::query_as::<_, EpochProcessingRun>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.epoch_processing_runs (epoch_id)
+            VALUES ($1)
+            ON CONFLICT (epoch_id) DO UPDATE
+            SET status = 'running', error = NULL
+            WHERE epoch_processing_runs.status = 'failed'
+               OR (epoch_processing_runs.status = 'running'
+                   AND epoch_processing_runs.updated_at < NOW() - make_interval(secs => $2))
+            RETURNING {}
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(epoch_id)
+        .bind(stale_secs as f64)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to start epoch processing run")
+    }
+
+    /// Moves a running sequence on to the given step
+    pub async fn advance(&self, epoch_id: i32, step: EpochProcessingStep) -> Result<()> {
+        Self::advance_in(&self.db, epoch_id, step).await
+    }
+
+    /// Same as [`advance`](Self::advance), on the given executor
+    pub async fn advance_in<'e>(executor: impl PgExecutor<'e>, epoch_id: i32, step: EpochProcessingStep) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.epoch_processing_runs
+            SET step = $2
+            WHERE epoch_id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(step)
+        .execute(executor)
+        .await
+        .context("Failed to advance epoch processing run")?;
+
+        Ok(())
+    }
+
+    /// Adds processed requests of a type to the sequence's counts
+    pub async fn add_processed_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        request_type: &RequestType,
+        count: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.epoch_processing_runs
+            SET deposits_processed = deposits_processed + CASE WHEN $2 = 'deposit' THEN $3 ELSE 0 END,
+                withdrawals_processed = withdrawals_processed + CASE WHEN $2 = 'withdrawal' THEN $3 ELSE 0 END,
+                borrows_processed = borrows_processed + CASE WHEN $2 = 'borrow' THEN $3 ELSE 0 END
+            WHERE epoch_id = $1
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(request_type)
+        .bind(count)
+        .execute(executor)
+        .await
+        .context("Failed to count processed requests")?;
+
+        Ok(())
+    }
+
+    /// Records the on-chain close of the epoch and the epoch opened after it
+    pub async fn record_close_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        close_tx_hash: &str,
+        next_epoch_id: i32,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.epoch_processing_runs
+            SET close_tx_hash = $2, next_epoch_id = $3
+            WHERE epoch_id = $1
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(close_tx_hash)
+        .bind(next_epoch_id)
+        .execute(executor)
+        .await
+        .context("Failed to record epoch close")?;
+
+        Ok(())
+    }
+
+    /// Stops a running sequence at its current step
+    pub async fn fail(&self, epoch_id: i32, error: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.epoch_processing_runs
+            SET status = 'failed', error = $2
+            WHERE epoch_id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(error)
+        .execute(&self.db)
+        .await
+        .context("Failed to record epoch processing failure")?;
+
+        Ok(())
+    }
+
+    /// Finishes a sequence with its stats snapshot and result
+    pub async fn complete_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        stats: &EpochStatsSnapshot,
+        result: &ProcessEpochResult,
+    ) -> Result<EpochProcessingRun> {
+        sqlx::query_as::<_, EpochProcessingRun>(&format!(
+            r#"
+            UPDATE lsrwa_express.epoch_processing_runs
+            SET status = 'completed', step = 'completed', stats = $2, result = $3, error = NULL,
+                completed_at = NOW()
+            WHERE epoch_id = $1
+            RETURNING {}
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(epoch_id)
+        .bind(Json(stats))
+        .bind(Json(result))
+        .fetch_one(executor)
+        .await
+        .context("Failed to complete epoch processing run")
+    }
+
+    /// Whether an epoch is being closed, in which case new requests must wait for the next one
+    pub async fn submissions_paused(&self) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM lsrwa_express.epoch_processing_runs
+                WHERE status <> 'completed'
+                  AND step IN ('closing_submissions', 'processing_deposits', 'processing_withdrawals', 'closing_epoch')
+            )
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to check for epochs being closed")
+    }
+
+    /// Snapshot of protocol totals in the default asset, with the volumes batch-processed in the
+    /// given epoch
+    pub async fn snapshot_stats_in<'e>(executor: impl PgExecutor<'e>, epoch_id: i32) -> Result<EpochStatsSnapshot> {
+        sqlx::query_as::<_, EpochStatsSnapshot>(
+            r#"
+            WITH balances AS (
+                SELECT * FROM lsrwa_express.user_balances WHERE asset = lsrwa_express.default_asset()
+            )
+            SELECT
+                (SELECT COALESCE(SUM(active_balance), 0)::TEXT FROM balances) AS total_value_locked,
+                (SELECT COUNT(*) FROM balances WHERE active_balance > 0) AS active_depositors,
+                (SELECT COALESCE(SUM(pending_withdrawals), 0)::TEXT FROM balances) AS pending_withdrawals,
+                COALESCE(SUM(r.amount) FILTER (WHERE i.request_type = 'deposit'), 0)::TEXT AS deposit_volume,
+                COALESCE(SUM(r.amount) FILTER (WHERE i.request_type = 'withdrawal'), 0)::TEXT AS withdrawal_volume,
+                (
+                    SELECT COALESCE(SUM(total_rewards), 0)::TEXT FROM lsrwa_express.epoch_reward_reports
+                    WHERE epoch_id = $1
+                ) AS rewards_distributed
+            FROM lsrwa_express.request_processing_events e
+            JOIN lsrwa_express.batch_processing_items i ON i.processing_event_id = e.id
+            JOIN lsrwa_express.blockchain_requests r
+              ON r.request_type = i.request_type AND r.on_chain_id = i.request_id
+             AND r.asset = lsrwa_express.default_asset()
+            WHERE e.epoch_id = $1
+            "#,
+        )
+        .bind(epoch_id)
+        .fetch_one(executor)
+        .await
+        .context("Failed to snapshot epoch stats")
+    }
+}
@@ -0,0 +1,177 @@
+//! Persistence for epochs
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::epoch::{Epoch, EpochFilter};
+
+/// Page size when a listing doesn't ask for one
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Largest page a listing can ask for
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Column list for `epochs` - legacy VARCHAR/TIMESTAMP columns are normalised to the model's types
+const EPOCH_COLUMNS: &str = "id, start_timestamp AT TIME ZONE 'UTC' AS start_timestamp, \
+     end_timestamp AT TIME ZONE 'UTC' AS end_timestamp, status::TEXT AS status, \
+     processed_at AT TIME ZONE 'UTC' AS processed_at, processing_tx_hash, \
+     created_at AT TIME ZONE 'UTC' AS created_at, updated_at AT TIME ZONE 'UTC' AS updated_at";
+
+/// Database access for epochs
+#[derive(Clone)]
+pub struct EpochRepository {
+    db: PgPool,
+}
+
+impl EpochRepository {
+    /// Creates a new epoch repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Gets an epoch by ID
+    pub async fn get(&self, id: i32) -> Result<Option<Epoch>> {
+        sqlx::query_as::<_, Epoch>(&format!(
+            "SELECT {} FROM lsrwa_express.epochs WHERE id = $1",
+            EPOCH_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch epoch")
+    }
+
+    /// Gets the most recent active epoch
+    pub async fn active(&self) -> Result<Option<Epoch>> {
+        sqlx::query_as::<_, Epoch>(&format!(
+            "SELECT {} FROM lsrwa_express.epochs WHERE status = 'active' ORDER BY id DESC LIMIT 1",
+            EPOCH_COLUMNS
+        ))
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch active epoch")
+    }
+
+    /// Lists epochs, newest first, optionally only those with a status
+    pub async fn list(&self, filter: &EpochFilter) -> Result<Vec<Epoch>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, Epoch>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.epochs
+            WHERE ($1::TEXT IS NULL OR status = $1)
+            ORDER BY id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            EPOCH_COLUMNS
+        ))
+        .bind(&filter.status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list epochs")
+    }
+
+    /// Gets an epoch and locks it until the transaction ends
+    pub async fn lock_in<'e>(executor: impl PgExecutor<'e>, id: i32) -> Result<Option<Epoch>> {
+        sqlx::query_as::<_, Epoch>(&format!(
+            "SELECT {} FROM lsrwa_express.epochs WHERE id = $1 FOR UPDATE",
+            EPOCH_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to lock epoch")
+    }
+
+    /// Ends an active epoch and moves it to processing. Epochs that already ended keep their end
+    /// time and status.
+    pub async fn close(&self, id: i32, end_timestamp: DateTime<Utc>) -> Result<Option<Epoch>> {
+        Self::close_in(&self.db, id, end_timestamp).await
+    }
+
+    /// Same as [`close`](Self::close), on the given executor
+    pub async fn close_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: i32,
+        end_timestamp: DateTime<Utc>,
+    ) -> Result<Option<Epoch>> {
+        sqlx::query_as::<_, Epoch>(&format!(
+            r#"
+            UPDATE lsrwa_express.epochs
+            SET end_timestamp = COALESCE(end_timestamp, $2 AT TIME ZONE 'UTC'),
+                status = CASE WHEN status = 'active' THEN 'processing' ELSE status END
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            EPOCH_COLUMNS
+        ))
+        .bind(id)
+        .bind(end_timestamp)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to close epoch")
+    }
+
+    /// Opens a new active epoch unless one is already open, returning the active epoch's ID
+    pub async fn open_next_in<'e>(executor: impl PgExecutor<'e>) -> Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT COALESCE(lsrwa_express.get_active_epoch_id(), lsrwa_express.create_new_epoch())
+            "#,
+        )
+        .fetch_one(executor)
+        .await
+        .context("Failed to open next epoch")
+    }
+
+    /// Marks a closed epoch as completed with the transaction that closed it on-chain
+    pub async fn complete_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: i32,
+        processing_tx_hash: Option<&str>,
+    ) -> Result<Option<Epoch>> {
+        sqlx::query_as::<_, Epoch>(&format!(
+            r#"
+            UPDATE lsrwa_express.epochs
+            SET status = 'completed',
+                processed_at = COALESCE(processed_at, NOW() AT TIME ZONE 'UTC'),
+                processing_tx_hash = COALESCE($2, processing_tx_hash)
+            WHERE id = $1 AND status <> 'active'
+            RETURNING {}
+            "#,
+            EPOCH_COLUMNS
+        ))
+        .bind(id)
+        .bind(processing_tx_hash)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to complete epoch")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::epoch::EpochStatus;
+    use crate::test_support::EpochBuilder;
+
+    #[sqlx::test]
+    async fn epochs_are_listed_newest_first_by_status(pool: PgPool) {
+        let first = EpochBuilder::new().completed().insert(&pool).await.unwrap();
+        let second = EpochBuilder::new().completed().insert(&pool).await.unwrap();
+        let active = EpochBuilder::new().insert(&pool).await.unwrap();
+        let epochs = EpochRepository::new(pool);
+
+        let completed = EpochFilter { status: Some(EpochStatus::Completed), ..EpochFilter::default() };
+        let ids = |listed: Vec<Epoch>| listed.into_iter().map(|epoch| epoch.id).collect::<Vec<_>>();
+        assert_eq!(ids(epochs.list(&completed).await.unwrap()), [second.id, first.id]);
+        assert_eq!(ids(epochs.list(&EpochFilter::default()).await.unwrap()), [active.id, second.id, first.id]);
+
+        let page = EpochFilter { limit: Some(1), offset: Some(1), ..EpochFilter::default() };
+        assert_eq!(ids(epochs.list(&page).await.unwrap()), [second.id]);
+    }
+}
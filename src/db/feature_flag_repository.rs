@@ -0,0 +1,217 @@
+//! Persistence for feature flags
+//!
+//! Flags are checked on hot paths, so the current environment's flags are cached the same way
+//! as system parameters: a local TTL cache in front of the shared application cache. Updates
+//! through the repository invalidate both, so a toggle takes effect on this replica at once and
+//! on the others within the TTL.
+
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::config::Environment;
+use crate::models::feature_flag::{FeatureFlag, FeatureFlagState, FeatureFlags, UpdateFeatureFlagRequest};
+use crate::services::cache::{keys, Cache};
+
+/// Column list for `feature_flags`
+const FLAG_COLUMNS: &str = "name, environment, enabled, rollout_percentage, description, updated_by, updated_at";
+
+/// Cached flags and when they were loaded
+type CachedFlags = Option<(Instant, FeatureFlags)>;
+
+/// Database access for feature flags
+#[derive(Clone)]
+pub struct FeatureFlagRepository {
+    db: PgPool,
+    /// Environment whose flags this service checks
+    environment: Environment,
+    cache: Arc<RwLock<CachedFlags>>,
+    ttl: Duration,
+    shared_cache: Cache,
+}
+
+impl FeatureFlagRepository {
+    /// Creates a repository checking `environment`'s flags, whose local cache expires after `ttl`
+    pub fn new(db: PgPool, environment: Environment, ttl: Duration, shared_cache: Cache) -> Self {
+        Self {
+            db,
+            environment,
+            cache: Arc::new(RwLock::new(None)),
+            ttl,
+            shared_cache,
+        }
+    }
+
+    /// Environment whose flags this service checks
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    /// Lists every known flag in an environment, with defaults for flags that have no stored state
+    pub async fn list(&self, environment: Environment) -> Result<Vec<FeatureFlagState>> {
+        let stored = self.stored(environment).await?;
+
+        Ok(FeatureFlag::ALL
+            .into_iter()
+            .map(|flag| {
+                stored
+                    .iter()
+                    .find(|state| state.name == flag.name())
+                    .cloned()
+                    .unwrap_or_else(|| FeatureFlagState {
+                        name: flag.name().to_string(),
+                        environment: environment.to_string(),
+                        enabled: flag.default_enabled(),
+                        rollout_percentage: 100,
+                        description: Some(flag.default_description().to_string()),
+                        updated_by: None,
+                        updated_at: None,
+                    })
+            })
+            .collect())
+    }
+
+    /// Changes a flag in an environment, starting from its default if it has no stored state
+    pub async fn update(
+        &self,
+        flag: FeatureFlag,
+        environment: Environment,
+        request: &UpdateFeatureFlagRequest,
+    ) -> Result<FeatureFlagState> {
+        let state = sqlx::query_as::<_, FeatureFlagState>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.feature_flags
+                (name, environment, enabled, rollout_percentage, description, updated_by)
+            VALUES ($1, $2, COALESCE($3, $4), COALESCE($5, 100), COALESCE($6, $7), $8)
+            ON CONFLICT (name, environment) DO UPDATE
+            SET enabled = COALESCE($3, feature_flags.enabled),
+                rollout_percentage = COALESCE($5, feature_flags.rollout_percentage),
+                description = COALESCE($6, feature_flags.description),
+                updated_by = $8,
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            FLAG_COLUMNS
+        ))
+        .bind(flag.name())
+        .bind(environment.to_string())
+        .bind(request.enabled)
+        .bind(flag.default_enabled())
+        .bind(request.rollout_percentage)
+        .bind(&request.description)
+        .bind(flag.default_description())
+        .bind(request.updated_by)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to update feature flag")?;
+
+        if environment == self.environment {
+            self.invalidate().await;
+        }
+
+        Ok(state)
+    }
+
+    /// Drops the cached flags so the next check reloads them
+    pub async fn invalidate(&self) {
+        *self.cache.write().await = None;
+        self.shared_cache.invalidate(&keys::feature_flags(self.environment)).await;
+    }
+
+    /// Whether a flag is on for everyone in this environment
+    pub async fn is_enabled(&self, flag: FeatureFlag) -> Result<bool> {
+        Ok(self.flags().await?.is_enabled(flag))
+    }
+
+    /// Whether a flag is on for a wallet in this environment
+    pub async fn is_enabled_for(&self, flag: FeatureFlag, wallet: &str) -> Result<bool> {
+        Ok(self.flags().await?.is_enabled_for(flag, wallet))
+    }
+
+    /// Returns this environment's flags, reloading them once the cache has expired.
+    ///
+    /// If a reload fails the stale flags are served rather than failing the caller.
+    pub async fn flags(&self) -> Result<FeatureFlags> {
+        if let Some((loaded_at, flags)) = self.cache.read().await.as_ref() {
+            if loaded_at.elapsed() < self.ttl {
+                return Ok(flags.clone());
+            }
+        }
+
+        let mut cache = self.cache.write().await;
+
+        // Another task may have reloaded while we waited for the lock
+        if let Some((loaded_at, flags)) = cache.as_ref() {
+            if loaded_at.elapsed() < self.ttl {
+                return Ok(flags.clone());
+            }
+        }
+
+        let loaded = self.shared_cache
+            .get_or_load(&keys::feature_flags(self.environment), || async {
+                Ok(FeatureFlags::from_states(&self.stored(self.environment).await?))
+            })
+            .await;
+
+        match loaded {
+            Ok(flags) => {
+                *cache = Some((Instant::now(), flags.clone()));
+                Ok(flags)
+            }
+            Err(err) => match cache.as_ref() {
+                Some((_, stale)) => {
+                    warn!("Failed to reload feature flags, serving stale values: {}", err);
+                    Ok(stale.clone())
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    /// Stored flag states in an environment
+    async fn stored(&self, environment: Environment) -> Result<Vec<FeatureFlagState>> {
+        sqlx::query_as::<_, FeatureFlagState>(&format!(
+            "SELECT {} FROM lsrwa_express.feature_flags WHERE environment = $1 ORDER BY name",
+            FLAG_COLUMNS
+        ))
+        .bind(environment.to_string())
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list feature flags")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(enabled: Option<bool>, rollout_percentage: Option<i16>) -> UpdateFeatureFlagRequest {
+        UpdateFeatureFlagRequest {
+            environment: None,
+            enabled,
+            rollout_percentage,
+            description: None,
+            updated_by: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn toggles_apply_per_environment(pool: PgPool) {
+        let repo = FeatureFlagRepository::new(pool, Environment::Production, Duration::from_secs(60), Cache::disabled());
+        assert!(repo.is_enabled(FeatureFlag::Borrows).await.unwrap());
+
+        repo.update(FeatureFlag::Borrows, Environment::Production, &update(Some(false), None)).await.unwrap();
+        repo.update(FeatureFlag::Borrows, Environment::Staging, &update(None, Some(25))).await.unwrap();
+
+        assert!(!repo.is_enabled(FeatureFlag::Borrows).await.unwrap());
+
+        let staging = repo.list(Environment::Staging).await.unwrap();
+        let borrows = staging.iter().find(|state| state.name == "borrows").unwrap();
+        assert!(borrows.enabled);
+        assert_eq!(borrows.rollout_percentage, 25);
+        assert!(staging.iter().any(|state| state.name == "new_reward_engine" && state.updated_at.is_none()));
+    }
+}
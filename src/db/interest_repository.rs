@@ -0,0 +1,226 @@
+//! Persistence for borrow interest and debt statements
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::interest::{DebtStatement, InterestAccrual};
+
+/// Column list for `borrow_interest_accruals`
+const ACCRUAL_COLUMNS: &str = "id, request_id, epoch_id, wallet_address::TEXT AS wallet_address, user_id, \
+     principal::TEXT AS principal, rate_bps, period_start, period_end, interest::TEXT AS interest, created_at";
+
+/// Column list for `debt_statements`
+const STATEMENT_COLUMNS: &str = "id, wallet_address::TEXT AS wallet_address, user_id, period_start, period_end, \
+     opening_debt::TEXT AS opening_debt, new_borrows::TEXT AS new_borrows, \
+     interest_accrued::TEXT AS interest_accrued, liquidated_debt::TEXT AS liquidated_debt, \
+     closing_debt::TEXT AS closing_debt, positions, generated_at";
+
+/// Seconds in the 365-day year interest rates are quoted over
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// Database access for borrow interest and debt statements
+#[derive(Clone)]
+pub struct InterestRepository {
+    db: PgPool,
+}
+
+impl InterestRepository {
+    /// Creates a new interest repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Accrues simple interest at `rate_bps` on every processed borrow for the part of the epoch
+    /// it was open, from its submission until the epoch ended or the borrow was liquidated.
+    /// Positions that already accrued for the epoch are skipped, so only new rows are returned.
+    pub async fn accrue_epoch_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        epoch_start: DateTime<Utc>,
+        epoch_end: DateTime<Utc>,
+        rate_bps: i32,
+    ) -> Result<Vec<InterestAccrual>> {
+        sqlx::query_as::<_, InterestAccrual>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.borrow_interest_accruals (
+                request_id, epoch_id, wallet_address, user_id, principal, rate_bps, period_start, period_end, interest
+            )
+            SELECT
+                p.request_id, $1, p.wallet_address, p.user_id, p.principal, $4, p.period_start, p.period_end,
+                ROUND(
+                    p.principal * $4 / 10000 * EXTRACT(EPOCH FROM (p.period_end - p.period_start)) / {},
+                    18
+                )
+            FROM (
+                SELECT
+                    r.on_chain_id AS request_id,
+                    r.wallet_address,
+                    r.user_id,
+                    r.amount AS principal,
+                    GREATEST($2, r.submission_timestamp AT TIME ZONE 'UTC') AS period_start,
+                    LEAST($3, COALESCE(l.liquidated_at, $3)) AS period_end
+                FROM lsrwa_express.blockchain_requests r
+                LEFT JOIN lsrwa_express.borrow_liquidations l
+                  ON l.request_id = r.on_chain_id AND l.status = 'liquidated'
+                WHERE r.request_type = 'borrow'
+                  AND r.is_processed = TRUE
+                  AND r.amount > 0
+            ) p
+            WHERE p.period_end > p.period_start
+            ON CONFLICT (request_id, epoch_id) DO NOTHING
+            RETURNING {}
+            "#,
+            SECONDS_PER_YEAR, ACCRUAL_COLUMNS
+        ))
+        .bind(epoch_id)
+        .bind(epoch_start)
+        .bind(epoch_end)
+        .bind(rate_bps)
+        .fetch_all(executor)
+        .await
+        .context("Failed to accrue borrow interest")
+    }
+
+    /// Interest accrued by a wallet's borrows, most recent first
+    pub async fn list_accruals(&self, wallet_address: &str) -> Result<Vec<InterestAccrual>> {
+        sqlx::query_as::<_, InterestAccrual>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.borrow_interest_accruals
+            WHERE wallet_address = $1
+            ORDER BY period_end DESC, request_id ASC
+            "#,
+            ACCRUAL_COLUMNS
+        ))
+        .bind(wallet_address)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list interest accruals")
+    }
+
+    /// Writes the statements of every wallet with borrow debt in the period, replacing
+    /// statements generated for it before. Returns the number of statements written.
+    pub async fn generate_statements_in<'e>(
+        executor: impl PgExecutor<'e>,
+        period_start: NaiveDate,
+        period_end: NaiveDate,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            WITH bounds AS (
+                SELECT $1::DATE::TIMESTAMP AT TIME ZONE 'UTC' AS starts,
+                       $2::DATE::TIMESTAMP AT TIME ZONE 'UTC' AS ends
+            ),
+            positions AS (
+                SELECT
+                    r.wallet_address,
+                    r.user_id,
+                    r.on_chain_id AS request_id,
+                    r.amount AS principal,
+                    r.submission_timestamp AT TIME ZONE 'UTC' AS borrowed_at,
+                    l.liquidated_at < b.ends AS liquidated,
+                    r.submission_timestamp AT TIME ZONE 'UTC' < b.starts AS carried_over,
+                    COALESCE(SUM(a.interest) FILTER (WHERE a.period_end <= b.starts), 0) AS interest_before,
+                    COALESCE(SUM(a.interest) FILTER (WHERE a.period_end > b.starts AND a.period_end <= b.ends), 0)
+                        AS interest_during
+                FROM bounds b
+                JOIN lsrwa_express.blockchain_requests r
+                  ON r.request_type = 'borrow'
+                 AND r.is_processed = TRUE
+                 AND r.submission_timestamp AT TIME ZONE 'UTC' < b.ends
+                LEFT JOIN lsrwa_express.borrow_liquidations l
+                  ON l.request_id = r.on_chain_id AND l.status = 'liquidated'
+                LEFT JOIN lsrwa_express.borrow_interest_accruals a ON a.request_id = r.on_chain_id
+                WHERE l.liquidated_at IS NULL OR l.liquidated_at >= b.starts
+                GROUP BY r.wallet_address, r.user_id, r.on_chain_id, r.amount, r.submission_timestamp,
+                         l.liquidated_at, b.starts, b.ends
+            ),
+            lines AS (
+                SELECT
+                    wallet_address,
+                    user_id,
+                    request_id,
+                    principal,
+                    borrowed_at,
+                    CASE WHEN liquidated THEN 'liquidated' ELSE 'open' END AS status,
+                    CASE WHEN carried_over THEN principal + interest_before ELSE 0::NUMERIC(36, 18) END AS opening_debt,
+                    CASE WHEN carried_over THEN 0::NUMERIC(36, 18) ELSE principal END AS new_borrow,
+                    interest_during AS interest_accrued,
+                    CASE WHEN liquidated THEN principal + interest_before + interest_during ELSE 0::NUMERIC(36, 18) END
+                        AS liquidated_debt,
+                    CASE WHEN liquidated THEN 0::NUMERIC(36, 18) ELSE principal + interest_before + interest_during END
+                        AS closing_debt
+                FROM positions
+            )
+            INSERT INTO lsrwa_express.debt_statements (
+                wallet_address, user_id, period_start, period_end, opening_debt, new_borrows, interest_accrued,
+                liquidated_debt, closing_debt, positions
+            )
+            SELECT
+                wallet_address,
+                (ARRAY_AGG(user_id) FILTER (WHERE user_id IS NOT NULL))[1],
+                $1,
+                $2,
+                SUM(opening_debt),
+                SUM(new_borrow),
+                SUM(interest_accrued),
+                SUM(liquidated_debt),
+                SUM(closing_debt),
+                jsonb_agg(jsonb_build_object(
+                    'request_id', request_id,
+                    'principal', principal::TEXT,
+                    'borrowed_at', borrowed_at,
+                    'status', status,
+                    'opening_debt', opening_debt::TEXT,
+                    'new_borrow', new_borrow::TEXT,
+                    'interest_accrued', interest_accrued::TEXT,
+                    'liquidated_debt', liquidated_debt::TEXT,
+                    'closing_debt', closing_debt::TEXT
+                ) ORDER BY request_id)
+            FROM lines
+            GROUP BY wallet_address
+            ON CONFLICT (wallet_address, period_start) DO UPDATE
+            SET user_id = EXCLUDED.user_id, period_end = EXCLUDED.period_end,
+                opening_debt = EXCLUDED.opening_debt, new_borrows = EXCLUDED.new_borrows,
+                interest_accrued = EXCLUDED.interest_accrued, liquidated_debt = EXCLUDED.liquidated_debt,
+                closing_debt = EXCLUDED.closing_debt, positions = EXCLUDED.positions, generated_at = NOW()
+            "#,
+        )
+        .bind(period_start)
+        .bind(period_end)
+        .execute(executor)
+        .await
+        .context("Failed to generate debt statements")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Gets a wallet's statement for the month starting on `period_start`
+    pub async fn get_statement(&self, wallet_address: &str, period_start: NaiveDate) -> Result<Option<DebtStatement>> {
+        sqlx::query_as::<_, DebtStatement>(&format!(
+            "SELECT {} FROM lsrwa_express.debt_statements WHERE wallet_address = $1 AND period_start = $2",
+            STATEMENT_COLUMNS
+        ))
+        .bind(wallet_address)
+        .bind(period_start)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch debt statement")
+    }
+
+    /// A wallet's statements, most recent first
+    pub async fn list_statements(&self, wallet_address: &str) -> Result<Vec<DebtStatement>> {
+        sqlx::query_as::<_, DebtStatement>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.debt_statements
+            WHERE wallet_address = $1
+            ORDER BY period_start DESC
+            "#,
+            STATEMENT_COLUMNS
+        ))
+        .bind(wallet_address)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list debt statements")
+    }
+}
@@ -0,0 +1,431 @@
+//! Persistence for KYC verifications and provider webhooks
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgExecutor, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::kyc::{
+    KycDocument, KycLevel, KycProvider, KycVerification, KycWebhookEvent, NewKycDocument, PendingKycSync,
+};
+use crate::models::user::KycStatus;
+
+/// Column list for `kyc_verifications`
+const VERIFICATION_COLUMNS: &str = "id, user_id, provider, level, applicant_id, status, review_answer, \
+     rejection_reasons, completed_at, onchain_sync_status, onchain_tx_hash, onchain_synced_at, \
+     onchain_sync_attempts, onchain_sync_error, created_at, updated_at";
+
+/// Column list for `kyc_webhook_events`
+const WEBHOOK_EVENT_COLUMNS: &str = "id, provider, event_type, applicant_id, verification_id, payload, \
+     processed_at, error_message, received_at";
+
+/// Column list for `kyc_documents`
+const DOCUMENT_COLUMNS: &str = "id, verification_id, document_type, side, file_name, content_type, size_bytes, \
+     sha256, storage_key, created_at";
+
+/// Level of the latest approved verification of each user in `$1`
+pub(super) const APPROVED_LEVELS: &str = r#"
+    SELECT DISTINCT ON (user_id) user_id, level FROM lsrwa_express.kyc_verifications
+    WHERE user_id = ANY($1) AND status = 'approved'
+    ORDER BY user_id, completed_at DESC NULLS LAST, created_at DESC
+"#;
+
+/// Database access for KYC verifications
+#[derive(Clone)]
+pub struct KycRepository {
+    db: PgPool,
+}
+
+impl KycRepository {
+    /// Creates a new KYC repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records a pending verification once the provider that handles it has registered the applicant
+    pub async fn create_verification(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        provider: KycProvider,
+        level: KycLevel,
+        applicant_id: &str,
+    ) -> Result<KycVerification> {
+        sqlx::query_as::<_, KycVerification>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.kyc_verifications (id, user_id, provider, level, applicant_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            VERIFICATION_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .bind(provider)
+        .bind(level)
+        .bind(applicant_id)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to create KYC verification")
+    }
+
+    /// Looks up a verification by id
+    pub async fn get_verification(&self, id: Uuid) -> Result<Option<KycVerification>> {
+        sqlx::query_as::<_, KycVerification>(&format!(
+            "SELECT {} FROM lsrwa_express.kyc_verifications WHERE id = $1",
+            VERIFICATION_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch KYC verification")
+    }
+
+    /// Looks up a verification by the provider's applicant id
+    pub async fn find_by_applicant(&self, provider: KycProvider, applicant_id: &str) -> Result<Option<KycVerification>> {
+        Self::find_by_applicant_in(&self.db, provider, applicant_id).await
+    }
+
+    /// Same as [`find_by_applicant`](Self::find_by_applicant), on the given executor
+    pub async fn find_by_applicant_in<'e>(
+        executor: impl PgExecutor<'e>,
+        provider: KycProvider,
+        applicant_id: &str,
+    ) -> Result<Option<KycVerification>> {
+        sqlx::query_as::<_, KycVerification>(&format!(
+            "SELECT {} FROM lsrwa_express.kyc_verifications WHERE provider = $1 AND applicant_id = $2",
+            VERIFICATION_COLUMNS
+        ))
+        .bind(provider)
+        .bind(applicant_id)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to fetch KYC verification by applicant")
+    }
+
+    /// Latest verification started by a user
+    pub async fn latest_for_user(&self, user_id: Uuid) -> Result<Option<KycVerification>> {
+        sqlx::query_as::<_, KycVerification>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.kyc_verifications
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            VERIFICATION_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch latest KYC verification")
+    }
+
+    /// Level of the user's most recently approved verification
+    pub async fn approved_level(&self, user_id: Uuid) -> Result<Option<KycLevel>> {
+        sqlx::query_scalar::<_, KycLevel>(
+            r#"
+            SELECT level FROM lsrwa_express.kyc_verifications
+            WHERE user_id = $1 AND status = 'approved'
+            ORDER BY completed_at DESC NULLS LAST, created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch approved KYC level")
+    }
+
+    /// Level of each user's most recently completed approved verification, for several users
+    /// in one query; users without one are left out
+    pub async fn approved_levels(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, KycLevel>> {
+        let levels = sqlx::query_as::<_, (Uuid, KycLevel)>(APPROVED_LEVELS)
+            .bind(user_ids)
+            .fetch_all(&self.db)
+            .await
+            .context("Failed to fetch approved KYC levels")?;
+
+        Ok(levels.into_iter().collect())
+    }
+
+    /// Records a review outcome; `completed_at` is set once the outcome is final. An approval
+    /// queues the wallet for the on-chain KYC allowlist.
+    pub async fn update_status_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: Uuid,
+        status: &KycStatus,
+        review_answer: Option<&str>,
+        rejection_reasons: &[String],
+    ) -> Result<Option<KycVerification>> {
+        sqlx::query_as::<_, KycVerification>(&format!(
+            r#"
+            UPDATE lsrwa_express.kyc_verifications
+            SET status = $2,
+                review_answer = $3,
+                rejection_reasons = $4,
+                completed_at = CASE WHEN $2 = 'pending' THEN NULL ELSE COALESCE(completed_at, NOW()) END,
+                onchain_sync_status = CASE
+                    WHEN $2 = 'approved' THEN COALESCE(onchain_sync_status, 'pending')
+                    ELSE onchain_sync_status
+                END,
+                onchain_next_attempt_at = CASE
+                    WHEN $2 = 'approved' AND onchain_sync_status IS NULL THEN NOW()
+                    ELSE onchain_next_attempt_at
+                END
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            VERIFICATION_COLUMNS
+        ))
+        .bind(id)
+        .bind(status)
+        .bind(review_answer)
+        .bind(rejection_reasons)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to update KYC verification status")
+    }
+
+    /// Claims approved verifications whose on-chain sync is due, pushing their next attempt back so
+    /// other instances skip them while they are submitted
+    pub async fn claim_due_onchain_syncs(&self, limit: i64) -> Result<Vec<PendingKycSync>> {
+        sqlx::query_as::<_, PendingKycSync>(
+            r#"
+            WITH claimed AS (
+                UPDATE lsrwa_express.kyc_verifications
+                SET onchain_next_attempt_at = NOW() + INTERVAL '5 minutes'
+                WHERE id IN (
+                    SELECT id FROM lsrwa_express.kyc_verifications
+                    WHERE onchain_sync_status = 'pending' AND onchain_next_attempt_at <= NOW()
+                    ORDER BY onchain_next_attempt_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, user_id, onchain_sync_attempts
+            )
+            SELECT claimed.id AS verification_id, u.wallet_address, claimed.onchain_sync_attempts
+            FROM claimed
+            JOIN lsrwa_express.users u ON u.id = claimed.user_id
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to claim due KYC on-chain syncs")
+    }
+
+    /// Records the transaction that added the verifications' wallets to the KYC allowlist
+    pub async fn mark_onchain_synced(&self, ids: &[Uuid], tx_hash: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.kyc_verifications
+            SET onchain_sync_status = 'synced',
+                onchain_tx_hash = $2,
+                onchain_synced_at = NOW(),
+                onchain_sync_attempts = onchain_sync_attempts + 1,
+                onchain_sync_error = NULL,
+                onchain_next_attempt_at = NULL
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .bind(tx_hash)
+        .execute(&self.db)
+        .await
+        .context("Failed to mark KYC verifications as synced on-chain")?;
+
+        Ok(())
+    }
+
+    /// Records a failed on-chain sync; without a next attempt the sync is given up
+    pub async fn record_onchain_sync_failure(
+        &self,
+        id: Uuid,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.kyc_verifications
+            SET onchain_sync_attempts = onchain_sync_attempts + 1,
+                onchain_sync_error = $2,
+                onchain_sync_status = CASE WHEN $3::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+                onchain_next_attempt_at = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.db)
+        .await
+        .context("Failed to record KYC on-chain sync failure")?;
+
+        Ok(())
+    }
+
+    /// Queues a failed on-chain sync again with a fresh set of attempts
+    pub async fn requeue_onchain_sync(&self, id: Uuid) -> Result<Option<KycVerification>> {
+        sqlx::query_as::<_, KycVerification>(&format!(
+            r#"
+            UPDATE lsrwa_express.kyc_verifications
+            SET onchain_sync_status = 'pending',
+                onchain_sync_attempts = 0,
+                onchain_next_attempt_at = NOW()
+            WHERE id = $1 AND onchain_sync_status = 'failed'
+            RETURNING {}
+            "#,
+            VERIFICATION_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to requeue KYC on-chain sync")
+    }
+
+    /// Stores a received provider webhook before it is processed
+    pub async fn save_webhook_event(
+        &self,
+        provider: KycProvider,
+        event_type: &str,
+        applicant_id: &str,
+        payload: &Value,
+    ) -> Result<KycWebhookEvent> {
+        sqlx::query_as::<_, KycWebhookEvent>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.kyc_webhook_events (provider, event_type, applicant_id, payload)
+            VALUES ($1, $2, $3, $4)
+            RETURNING {}
+            "#,
+            WEBHOOK_EVENT_COLUMNS
+        ))
+        .bind(provider)
+        .bind(event_type)
+        .bind(applicant_id)
+        .bind(payload)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to save KYC webhook event")
+    }
+
+    /// Whether an identical webhook was already applied; one that failed or named an unknown
+    /// applicant doesn't count, so its redelivery is still processed
+    pub async fn webhook_applied(&self, provider: KycProvider, applicant_id: &str, payload: &Value) -> Result<bool> {
+        sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM lsrwa_express.kyc_webhook_events
+                WHERE provider = $1 AND applicant_id = $2 AND payload = $3
+                  AND processed_at IS NOT NULL AND error_message IS NULL
+            )
+            "#,
+        )
+        .bind(provider)
+        .bind(applicant_id)
+        .bind(payload)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to look up applied KYC webhook events")
+    }
+
+    /// Marks a stored webhook as processed, recording the verification it applied to or the error
+    pub async fn mark_webhook_processed(&self, id: Uuid, verification_id: Option<Uuid>, error: Option<&str>) -> Result<()> {
+        Self::mark_webhook_processed_in(&self.db, id, verification_id, error).await
+    }
+
+    /// Same as [`mark_webhook_processed`](Self::mark_webhook_processed), on the given executor
+    pub async fn mark_webhook_processed_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: Uuid,
+        verification_id: Option<Uuid>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.kyc_webhook_events
+            SET processed_at = NOW(), verification_id = $2, error_message = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(verification_id)
+        .bind(error)
+        .execute(executor)
+        .await
+        .context("Failed to mark KYC webhook event processed")?;
+
+        Ok(())
+    }
+
+    /// Records a document stored for a verification
+    pub async fn create_document(&self, document: &NewKycDocument) -> Result<KycDocument> {
+        sqlx::query_as::<_, KycDocument>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.kyc_documents (
+                id, verification_id, document_type, side, file_name, content_type, size_bytes, sha256, storage_key
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING {}
+            "#,
+            DOCUMENT_COLUMNS
+        ))
+        .bind(document.id)
+        .bind(document.verification_id)
+        .bind(document.document_type)
+        .bind(document.side)
+        .bind(&document.file_name)
+        .bind(&document.content_type)
+        .bind(document.size_bytes)
+        .bind(&document.sha256)
+        .bind(&document.storage_key)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to record KYC document")
+    }
+
+    /// Documents uploaded for a verification, oldest first
+    pub async fn list_documents(&self, verification_id: Uuid) -> Result<Vec<KycDocument>> {
+        sqlx::query_as::<_, KycDocument>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.kyc_documents
+            WHERE verification_id = $1
+            ORDER BY created_at
+            "#,
+            DOCUMENT_COLUMNS
+        ))
+        .bind(verification_id)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list KYC documents")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[sqlx::test]
+    async fn only_applied_webhooks_count_as_replays(pool: PgPool) {
+        let kyc = KycRepository::new(pool);
+        let provider = KycProvider::Shufti;
+        let body = json!({ "event": "verification.accepted", "reference": "ref-1" });
+
+        let event = kyc.save_webhook_event(provider, "verification.accepted", "ref-1", &body).await.unwrap();
+        assert!(!kyc.webhook_applied(provider, "ref-1", &body).await.unwrap());
+
+        // A redelivery of one that failed is still worth processing
+        kyc.mark_webhook_processed(event.id, None, Some("Unknown applicant")).await.unwrap();
+        assert!(!kyc.webhook_applied(provider, "ref-1", &body).await.unwrap());
+
+        let event = kyc.save_webhook_event(provider, "verification.accepted", "ref-1", &body).await.unwrap();
+        kyc.mark_webhook_processed(event.id, None, None).await.unwrap();
+        assert!(kyc.webhook_applied(provider, "ref-1", &body).await.unwrap());
+
+        let declined = json!({ "event": "verification.declined", "reference": "ref-1" });
+        assert!(!kyc.webhook_applied(provider, "ref-1", &declined).await.unwrap());
+        assert!(!kyc.webhook_applied(KycProvider::SumSub, "ref-1", &body).await.unwrap());
+    }
+}
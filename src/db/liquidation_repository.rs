@@ -0,0 +1,251 @@
+//! Persistence for borrow liquidations
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::liquidation::{BorrowPosition, Liquidation, LiquidationActionType, LiquidationFilter};
+
+/// Column list for `borrow_liquidations` (aliased `l`), with each liquidation's actions
+const LIQUIDATION_COLUMNS: &str = "l.id, l.request_id, l.wallet_address, l.user_id, l.status::TEXT AS status, \
+     l.borrow_amount::TEXT AS borrow_amount, l.collateral_amount::TEXT AS collateral_amount, \
+     l.collateral_price::TEXT AS collateral_price, l.collateral_ratio_bps, l.flagged_at, l.grace_ends_at, \
+     l.notified_at, l.liquidated_at, l.liquidation_tx_hash::TEXT AS liquidation_tx_hash, l.attempts, l.last_error, \
+     l.created_at, l.updated_at, \
+     COALESCE(( \
+         SELECT jsonb_agg(jsonb_build_object( \
+             'id', a.id, 'action', a.action, 'collateral_ratio_bps', a.collateral_ratio_bps, \
+             'details', a.details, 'created_at', a.created_at \
+         ) ORDER BY a.created_at, a.id) \
+         FROM lsrwa_express.liquidation_actions a WHERE a.liquidation_id = l.id \
+     ), '[]'::JSONB) AS actions";
+
+/// Default page size for liquidation lists
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Largest page size accepted for liquidation lists
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Database access for borrow liquidations
+#[derive(Clone)]
+pub struct LiquidationRepository {
+    db: PgPool,
+}
+
+impl LiquidationRepository {
+    /// Creates a new liquidation repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Processed borrows that haven't been liquidated, with their collateral ratio at
+    /// `collateral_price`. Borrows without a recorded collateral amount can't be assessed and are
+    /// left out.
+    pub async fn open_positions(&self, collateral_price: &str) -> Result<Vec<BorrowPosition>> {
+        sqlx::query_as::<_, BorrowPosition>(
+            r#"
+            SELECT
+                r.on_chain_id AS request_id,
+                r.wallet_address::TEXT AS wallet_address,
+                r.user_id,
+                r.amount::TEXT AS borrow_amount,
+                r.collateral_amount::TEXT AS collateral_amount,
+                LEAST(FLOOR(r.collateral_amount * $1::NUMERIC * 10000 / r.amount), 2147483647)::INTEGER
+                    AS collateral_ratio_bps,
+                l.id AS liquidation_id,
+                l.status::TEXT AS liquidation_status,
+                l.grace_ends_at,
+                l.notified_at
+            FROM lsrwa_express.blockchain_requests r
+            LEFT JOIN lsrwa_express.borrow_liquidations l ON l.request_id = r.on_chain_id
+            WHERE r.request_type = 'borrow'
+              AND r.is_processed = TRUE
+              AND r.collateral_amount IS NOT NULL
+              AND r.amount > 0
+              AND (l.status IS NULL OR l.status <> 'liquidated')
+            ORDER BY r.on_chain_id ASC
+            "#,
+        )
+        .bind(collateral_price)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list open borrow positions")
+    }
+
+    /// Flags a position as at risk, starting a grace period of `grace_secs`. A position that
+    /// recovered earlier is flagged again with a fresh grace period. Returns the liquidation ID, or
+    /// `None` when the position is already flagged.
+    pub async fn flag_in<'e>(
+        executor: impl PgExecutor<'e>,
+        position: &BorrowPosition,
+        collateral_price: &str,
+        grace_secs: i64,
+    ) -> Result<Option<i64>> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO lsrwa_express.borrow_liquidations (
+                request_id, wallet_address, user_id, borrow_amount, collateral_amount, collateral_price,
+                collateral_ratio_bps, grace_ends_at
+            )
+            VALUES ($1, $2, $3, $4::NUMERIC, $5::NUMERIC, $6::NUMERIC, $7, NOW() + make_interval(secs => $8))
+            ON CONFLICT (request_id) DO UPDATE
+            SET status = 'at_risk', collateral_price = EXCLUDED.collateral_price,
+                collateral_ratio_bps = EXCLUDED.collateral_ratio_bps, flagged_at = NOW(),
+                grace_ends_at = EXCLUDED.grace_ends_at, notified_at = NULL, attempts = 0, last_error = NULL
+            WHERE borrow_liquidations.status = 'recovered'
+            RETURNING id
+            "#,
+        )
+        .bind(position.request_id)
+        .bind(&position.wallet_address)
+        .bind(position.user_id)
+        .bind(&position.borrow_amount)
+        .bind(&position.collateral_amount)
+        .bind(collateral_price)
+        .bind(position.collateral_ratio_bps)
+        .bind(grace_secs as f64)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to flag borrow position")
+    }
+
+    /// Records the latest collateral price and ratio of a flagged position
+    pub async fn refresh(&self, id: i64, collateral_price: &str, collateral_ratio_bps: i32) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.borrow_liquidations
+            SET collateral_price = $2::NUMERIC, collateral_ratio_bps = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(collateral_price)
+        .bind(collateral_ratio_bps)
+        .execute(&self.db)
+        .await
+        .context("Failed to refresh liquidation")?;
+
+        Ok(())
+    }
+
+    /// Moves an at-risk position back to recovered. Returns `false` when it wasn't at risk.
+    pub async fn recover_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: i64,
+        collateral_price: &str,
+        collateral_ratio_bps: i32,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE lsrwa_express.borrow_liquidations
+            SET status = 'recovered', collateral_price = $2::NUMERIC, collateral_ratio_bps = $3
+            WHERE id = $1 AND status = 'at_risk'
+            "#,
+        )
+        .bind(id)
+        .bind(collateral_price)
+        .bind(collateral_ratio_bps)
+        .execute(executor)
+        .await
+        .context("Failed to mark borrow position recovered")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records that the borrower was warned
+    pub async fn mark_notified_in<'e>(executor: impl PgExecutor<'e>, id: i64) -> Result<()> {
+        sqlx::query("UPDATE lsrwa_express.borrow_liquidations SET notified_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(executor)
+            .await
+            .context("Failed to mark liquidation notified")?;
+
+        Ok(())
+    }
+
+    /// Records the on-chain liquidation of a position
+    pub async fn mark_liquidated_in<'e>(executor: impl PgExecutor<'e>, id: i64, tx_hash: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.borrow_liquidations
+            SET status = 'liquidated', liquidated_at = NOW(), liquidation_tx_hash = $2, last_error = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(tx_hash)
+        .execute(executor)
+        .await
+        .context("Failed to mark borrow position liquidated")?;
+
+        Ok(())
+    }
+
+    /// Records a failed liquidation attempt; the position stays at risk and is retried
+    pub async fn record_failure_in<'e>(executor: impl PgExecutor<'e>, id: i64, error: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.borrow_liquidations
+            SET attempts = attempts + 1, last_error = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(executor)
+        .await
+        .context("Failed to record liquidation failure")?;
+
+        Ok(())
+    }
+
+    /// Appends an entry to a position's liquidation log
+    pub async fn record_action_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: i64,
+        action: LiquidationActionType,
+        collateral_ratio_bps: Option<i32>,
+        details: Option<&Value>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.liquidation_actions (liquidation_id, action, collateral_ratio_bps, details)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(id)
+        .bind(action)
+        .bind(collateral_ratio_bps)
+        .bind(details.map(Json))
+        .execute(executor)
+        .await
+        .context("Failed to record liquidation action")?;
+
+        Ok(())
+    }
+
+    /// Lists liquidations matching the filter with their actions, most recently flagged first
+    pub async fn list(&self, filter: &LiquidationFilter) -> Result<Vec<Liquidation>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, Liquidation>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.borrow_liquidations l
+            WHERE ($1::TEXT IS NULL OR l.status = $1)
+              AND ($2::TEXT IS NULL OR l.wallet_address = $2)
+            ORDER BY l.flagged_at DESC, l.id DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            LIQUIDATION_COLUMNS
+        ))
+        .bind(filter.status)
+        .bind(&filter.wallet_address)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list liquidations")
+    }
+}
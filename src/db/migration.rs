@@ -1,7 +1,32 @@
 use anyhow::{Context, Result};
 use log::info;
-use sqlx::{migrate::MigrateDatabase, PgPool, Postgres};
-use std::env;
+use sqlx::{
+    migrate::{MigrateDatabase, Migrator},
+    PgPool, Postgres,
+};
+
+/// Migrations embedded in the binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// How the applied migrations compare to the ones embedded in the binary
+#[derive(Debug, Clone, Default)]
+pub struct MigrationStatus {
+    /// Versions the binary has that aren't applied yet
+    pub pending: Vec<i64>,
+    /// Versions that started but didn't complete, and need fixing by hand
+    pub failed: Vec<i64>,
+    /// Versions whose file changed after they were applied
+    pub modified: Vec<i64>,
+    /// Applied versions the binary doesn't know, e.g. after rolling back a deploy
+    pub unknown: Vec<i64>,
+}
+
+impl MigrationStatus {
+    /// Whether running the migrations would succeed
+    pub fn is_runnable(&self) -> bool {
+        self.failed.is_empty() && self.modified.is_empty() && self.unknown.is_empty()
+    }
+}
 
 /// Runs all migrations
 pub async fn run_migrations(pg_pool: &PgPool) -> Result<()> {
@@ -14,7 +39,7 @@ pub async fn run_migrations(pg_pool: &PgPool) -> Result<()> {
         .context("Failed to create schema")?;
     
     // Run migrations
-    sqlx::migrate!("./migrations")
+    MIGRATOR
         .run(pg_pool)
         .await
         .context("Failed to run migrations")?;
@@ -25,9 +50,7 @@ pub async fn run_migrations(pg_pool: &PgPool) -> Result<()> {
 }
 
 /// Initialize the database if it doesn't exist
-pub async fn ensure_database_exists() -> Result<()> {
-    let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
-    
+pub async fn ensure_database_exists(database_url: &str) -> Result<()> {
     // Extract the database name and server URL
     let parts: Vec<&str> = database_url.rsplitn(2, '/').collect();
     let (db_name, _server_url) = match parts.as_slice() {
@@ -35,11 +58,11 @@ pub async fn ensure_database_exists() -> Result<()> {
         _ => return Err(anyhow::anyhow!("Invalid DATABASE_URL format")),
     };
 
-    if !Postgres::database_exists(&database_url).await? {
+    if !Postgres::database_exists(database_url).await? {
         info!("Database '{}' does not exist, creating it", db_name);
         
         // Connect to the postgres database to create the new one
-        Postgres::create_database(&database_url).await?;
+        Postgres::create_database(database_url).await?;
         
         info!("Database '{}' created successfully", db_name);
     } else {
@@ -47,4 +70,41 @@ pub async fn ensure_database_exists() -> Result<()> {
     }
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Compares the migrations applied to a database with the ones embedded in the binary
+pub async fn migration_status(pg_pool: &PgPool) -> Result<MigrationStatus> {
+    let has_table: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pg_pool)
+        .await
+        .context("Failed to look for the migrations table")?;
+
+    // A fresh database has nothing applied yet
+    let applied: Vec<(i64, bool, Vec<u8>)> = if has_table {
+        sqlx::query_as("SELECT version, success, checksum FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pg_pool)
+            .await
+            .context("Failed to list applied migrations")?
+    } else {
+        Vec::new()
+    };
+
+    let embedded: Vec<_> = MIGRATOR.iter().filter(|migration| !migration.migration_type.is_down_migration()).collect();
+    let mut status = MigrationStatus::default();
+
+    for migration in &embedded {
+        match applied.iter().find(|(version, _, _)| *version == migration.version) {
+            None => status.pending.push(migration.version),
+            Some((version, false, _)) => status.failed.push(*version),
+            Some((version, true, checksum)) if checksum[..] != migration.checksum[..] => status.modified.push(*version),
+            Some(_) => {},
+        }
+    }
+    status.unknown = applied
+        .iter()
+        .map(|(version, _, _)| *version)
+        .filter(|version| !embedded.iter().any(|migration| migration.version == *version))
+        .collect();
+
+    Ok(status)
+}
@@ -1,34 +1,119 @@
 use anyhow::{Context, Result};
-use sqlx::postgres::PgPoolOptions;
-use std::env;
-use std::time::Duration;
+use tracing::info;
 
+use crate::config::DatabaseConfig;
+
+pub mod accounting_repository;
+pub mod activity_log_repository;
+pub mod archive_repository;
+pub mod asset_repository;
+pub mod audit_repository;
+pub mod balance_repository;
+pub mod blockchain_request_repository;
+pub mod deployment_repository;
+pub mod epoch_processing_repository;
+pub mod epoch_repository;
+pub mod feature_flag_repository;
+pub mod interest_repository;
+pub mod kyc_repository;
+pub mod liquidation_repository;
 pub mod migration;
 pub mod pg;
+pub mod pool_metrics;
+pub mod referral_repository;
+pub mod reward_repository;
+pub mod risk_parameter_repository;
+pub mod screening_repository;
+pub mod seed;
+pub mod snapshot;
+pub mod system_parameter_repository;
+pub mod treasury_repository;
+pub mod unit_of_work;
+pub mod user_limit_repository;
+pub mod user_repository;
+
+#[cfg(test)]
+mod query_plans;
+
+pub use accounting_repository::AccountingRepository;
+pub use activity_log_repository::ActivityLogRepository;
+pub use archive_repository::ArchiveRepository;
+pub use asset_repository::AssetRepository;
+pub use audit_repository::AuditRepository;
+pub use balance_repository::BalanceRepository;
+pub use blockchain_request_repository::BlockchainRequestRepository;
+pub use deployment_repository::DeploymentRepository;
+pub use epoch_processing_repository::EpochProcessingRepository;
+pub use epoch_repository::EpochRepository;
+pub use feature_flag_repository::FeatureFlagRepository;
+pub use interest_repository::InterestRepository;
+pub use kyc_repository::KycRepository;
+pub use liquidation_repository::LiquidationRepository;
+pub use pool_metrics::PoolMetricsReporter;
+pub use referral_repository::ReferralRepository;
+pub use reward_repository::RewardRepository;
+pub use risk_parameter_repository::RiskParameterRepository;
+pub use screening_repository::ScreeningRepository;
+pub use system_parameter_repository::SystemParameterRepository;
+pub use treasury_repository::TreasuryRepository;
+pub use unit_of_work::UnitOfWork;
+pub use user_limit_repository::UserLimitRepository;
+pub use user_repository::UserRepository;
+
+/// Which database role a query needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbAccess {
+    /// Reads that tolerate replication lag (lists, reports)
+    Read,
+    /// Writes, and reads that must see the latest writes
+    Write,
+}
 
 /// Database pools
 #[derive(Clone)]
 pub struct DbPools {
+    /// Primary database
     pub pg: sqlx::PgPool,
+    /// Read replica, when one is configured
+    pub read: Option<sqlx::PgPool>,
+}
+
+impl DbPools {
+    /// Pool to use for the given kind of access
+    pub fn pool(&self, access: DbAccess) -> sqlx::PgPool {
+        match access {
+            DbAccess::Read => self.read.as_ref().unwrap_or(&self.pg).clone(),
+            DbAccess::Write => self.pg.clone(),
+        }
+    }
 }
 
 /// Initialize database connections
-pub async fn init_db() -> Result<DbPools> {
-    // Get database URL from environment
-    let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
-    
+pub async fn init_db(config: &DatabaseConfig) -> Result<DbPools> {
     // Create connection pool
-    let pg_pool = PgPoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(Duration::from_secs(3))
-        .connect(&database_url)
+    let pg_pool = pg::create_pg_pool(&config.url, &config.pool)
         .await
         .context("Failed to connect to Postgres")?;
     
     // Run migrations
     migration::run_migrations(&pg_pool).await?;
     
+    // Route replica-safe reads to the read replica when one is configured
+    let read_pool = match &config.read_url {
+        Some(read_url) => {
+            info!("Routing read queries to the read replica");
+            
+            Some(
+                pg::create_pg_pool(read_url, &config.pool)
+                    .await
+                    .context("Failed to connect to the Postgres read replica")?,
+            )
+        },
+        None => None,
+    };
+    
     Ok(DbPools {
         pg: pg_pool,
+        read: read_pool,
     })
 } 
\ No newline at end of file
@@ -1,27 +1,33 @@
 use anyhow::{Context, Result};
-use log::{info, warn};
-use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::env;
+use log::info;
+use sqlx::{postgres::PgPoolOptions, Executor, PgPool};
 use std::time::Duration;
 
-/// Create a PostgreSQL connection pool from environment variables
-pub async fn create_pg_pool() -> Result<PgPool> {
-    let database_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
-    
-    let max_connections = env::var("PG_MAX_CONNECTIONS")
-        .unwrap_or_else(|_| {
-            warn!("PG_MAX_CONNECTIONS not set, using default value of 5");
-            "5".to_string()
-        })
-        .parse::<u32>()
-        .context("PG_MAX_CONNECTIONS must be a number")?;
+use crate::config::PoolConfig;
 
-    info!("Connecting to PostgreSQL with up to {} connections", max_connections);
+/// Create a PostgreSQL connection pool for the given URL
+pub async fn create_pg_pool(database_url: &str, config: &PoolConfig) -> Result<PgPool> {
+    info!(
+        "Connecting to PostgreSQL with {}-{} connections",
+        config.min_connections, config.max_connections
+    );
+    
+    let statement_timeout_ms = config.statement_timeout_ms;
     
     let pool = PgPoolOptions::new()
-        .max_connections(max_connections)
-        .acquire_timeout(Duration::from_secs(30))
-        .connect(&database_url)
+        .min_connections(config.min_connections)
+        .max_connections(config.max_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout(config.idle_timeout_secs.map(Duration::from_secs))
+        .after_connect(move |conn, _meta| {
+            Box::pin(async move {
+                if let Some(timeout_ms) = statement_timeout_ms {
+                    conn.execute(format!("SET statement_timeout = {}", timeout_ms).as_str()).await?;
+                }
+                Ok(())
+            })
+        })
+        .connect(database_url)
         .await
         .context("Failed to create PostgreSQL connection pool")?;
     
@@ -40,4 +46,4 @@ pub async fn test_connection(pool: &PgPool) -> Result<()> {
     info!("Database connection successful, server time: {:?}", result.time);
     
     Ok(())
-} 
\ No newline at end of file
+}
@@ -0,0 +1,69 @@
+//! Connection pool gauges for the metrics endpoint
+
+use metrics::{describe_gauge, describe_histogram, gauge, histogram, Unit};
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tokio::time;
+use tracing::warn;
+
+use super::DbPools;
+use crate::services::shutdown::Shutdown;
+
+/// Periodically samples pool usage into the metrics recorder
+pub struct PoolMetricsReporter {
+    /// Pools to sample
+    pools: DbPools,
+    /// Sampling interval
+    interval: Duration,
+}
+
+impl PoolMetricsReporter {
+    /// Creates a new reporter sampling every `interval_secs` seconds
+    pub fn new(pools: DbPools, interval_secs: u64) -> Self {
+        describe_gauge!("db_pool_connections", "Open connections in the pool");
+        describe_gauge!("db_pool_connections_idle", "Idle connections in the pool");
+        describe_gauge!("db_pool_connections_in_use", "Connections checked out of the pool");
+        describe_histogram!(
+            "db_pool_acquire_wait_seconds",
+            Unit::Seconds,
+            "Time a sampling probe waited to acquire a connection"
+        );
+
+        Self {
+            pools,
+            interval: Duration::from_secs(interval_secs),
+        }
+    }
+
+    /// Runs the sampling loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) {
+        let mut interval = time::interval(self.interval);
+
+        while shutdown.tick(&mut interval).await {
+
+            sample("primary", &self.pools.pg).await;
+
+            if let Some(read) = &self.pools.read {
+                sample("read", read).await;
+            }
+        }
+    }
+}
+
+/// Records the pool's gauges and times a probe acquire as a proxy for queueing delay
+async fn sample(pool_name: &'static str, pool: &PgPool) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+
+    gauge!("db_pool_connections", size as f64, "pool" => pool_name);
+    gauge!("db_pool_connections_idle", idle as f64, "pool" => pool_name);
+    gauge!("db_pool_connections_in_use", size.saturating_sub(idle) as f64, "pool" => pool_name);
+
+    let started = Instant::now();
+    match pool.acquire().await {
+        Ok(_conn) => {
+            histogram!("db_pool_acquire_wait_seconds", started.elapsed().as_secs_f64(), "pool" => pool_name);
+        },
+        Err(err) => warn!("Failed to acquire a {} pool connection: {}", pool_name, err),
+    }
+}
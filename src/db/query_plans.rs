@@ -0,0 +1,97 @@
+//! Query plans of the heaviest repository queries
+//!
+//! The test tables are nearly empty, so the planner would happily scan them whole. Each plan is
+//! therefore taken with sequential scans disabled, which Postgres only falls back to when no
+//! index can serve a scan; a sequential scan left in the plan means an index is missing.
+
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+use uuid::Uuid;
+
+use super::blockchain_request_repository::{unbatched_requests, EPOCH_VOLUMES};
+use super::kyc_repository::APPROVED_LEVELS;
+use super::user_repository::users_by_wallets;
+use crate::models::blockchain_request::RequestType;
+
+/// A connection on which plans avoid sequential scans wherever an index allows
+async fn without_seq_scans(pool: &PgPool) -> PoolConnection<Postgres> {
+    let mut conn = pool.acquire().await.unwrap();
+    sqlx::query("SET enable_seqscan = off").execute(&mut *conn).await.unwrap();
+    conn
+}
+
+fn explain(sql: &str) -> String {
+    format!("EXPLAIN (FORMAT JSON) {}", sql)
+}
+
+/// Tables the plan scans sequentially
+fn seq_scans(plan: &Value) -> Vec<String> {
+    let mut tables = Vec::new();
+    let mut nodes = vec![plan];
+    while let Some(node) = nodes.pop() {
+        match node {
+            Value::Array(items) => nodes.extend(items),
+            Value::Object(fields) => {
+                if fields.get("Node Type").and_then(Value::as_str) == Some("Seq Scan") {
+                    tables.push(fields.get("Relation Name").and_then(Value::as_str).unwrap_or_default().to_string());
+                }
+                nodes.extend(fields.values());
+            },
+            _ => {},
+        }
+    }
+    tables
+}
+
+#[sqlx::test]
+async fn users_are_looked_up_by_wallet_index(pool: PgPool) {
+    let mut conn = without_seq_scans(&pool).await;
+    let plan: Value = sqlx::query_scalar(&explain(users_by_wallets()))
+        .bind(vec!["5Grwva".to_string(), "5FHneW".to_string()])
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    assert_eq!(seq_scans(&plan), Vec::<String>::new());
+}
+
+#[sqlx::test]
+async fn approved_levels_are_looked_up_by_user_index(pool: PgPool) {
+    let mut conn = without_seq_scans(&pool).await;
+    let plan: Value = sqlx::query_scalar(&explain(APPROVED_LEVELS))
+        .bind(vec![Uuid::new_v4()])
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    assert_eq!(seq_scans(&plan), Vec::<String>::new());
+}
+
+#[sqlx::test]
+async fn epoch_volumes_are_summed_from_wallet_index(pool: PgPool) {
+    let mut conn = without_seq_scans(&pool).await;
+    let plan: Value = sqlx::query_scalar(&explain(EPOCH_VOLUMES))
+        .bind(vec!["5Grwva".to_string()])
+        .bind(Utc::now())
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    assert_eq!(seq_scans(&plan), Vec::<String>::new());
+}
+
+#[sqlx::test]
+async fn unbatched_requests_are_found_by_index(pool: PgPool) {
+    let mut conn = without_seq_scans(&pool).await;
+    let plan: Value = sqlx::query_scalar(&explain(unbatched_requests()))
+        .bind(RequestType::Deposit)
+        .bind(Utc::now())
+        .bind(500_i64)
+        .fetch_one(&mut *conn)
+        .await
+        .unwrap();
+
+    assert_eq!(seq_scans(&plan), Vec::<String>::new());
+}
@@ -0,0 +1,89 @@
+//! Persistence for referral relationships and the bonuses they earn
+
+use anyhow::{Context, Result};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::referral::RefereeEarnings;
+use crate::models::wallet::WalletAddress;
+
+/// Database access for referrals
+#[derive(Clone)]
+pub struct ReferralRepository {
+    db: PgPool,
+}
+
+impl ReferralRepository {
+    /// Creates a new referral repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records that `referrer_user_id` referred `referee_user_id`
+    pub async fn create_in<'e>(
+        executor: impl PgExecutor<'e>,
+        referee_user_id: Uuid,
+        referrer_user_id: Uuid,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO lsrwa_express.referrals (referee_user_id, referrer_user_id) VALUES ($1, $2)",
+        )
+        .bind(referee_user_id)
+        .bind(referrer_user_id)
+        .execute(executor)
+        .await
+        .context("Failed to insert referral")?;
+
+        Ok(())
+    }
+
+    /// Wallet of the user who referred `user_id`
+    pub async fn get_referrer_wallet(&self, user_id: Uuid) -> Result<Option<WalletAddress>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT u.wallet_address
+            FROM lsrwa_express.referrals r
+            JOIN lsrwa_express.users u ON u.id = r.referrer_user_id
+            WHERE r.referee_user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch referrer")
+    }
+
+    /// Lists the users `referrer_user_id` referred with the bonuses each has earned them,
+    /// most recent referral first
+    pub async fn list_referees(&self, referrer_user_id: Uuid) -> Result<Vec<RefereeEarnings>> {
+        sqlx::query_as::<_, RefereeEarnings>(
+            r#"
+            SELECT u.wallet_address, r.created_at AS referred_at,
+                   COUNT(b.id) AS epochs_paid,
+                   COALESCE(SUM(b.amount), 0)::TEXT AS total_earned
+            FROM lsrwa_express.referrals r
+            JOIN lsrwa_express.users u ON u.id = r.referee_user_id
+            LEFT JOIN lsrwa_express.referral_bonuses b
+                   ON b.referrer_user_id = r.referrer_user_id AND b.referee_user_id = r.referee_user_id
+            WHERE r.referrer_user_id = $1
+            GROUP BY u.wallet_address, r.created_at
+            ORDER BY r.created_at DESC, u.wallet_address
+            "#,
+        )
+        .bind(referrer_user_id)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list referees")
+    }
+
+    /// Total referral bonuses earned by `referrer_user_id`
+    pub async fn total_earned(&self, referrer_user_id: Uuid) -> Result<String> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(SUM(amount), 0)::TEXT FROM lsrwa_express.referral_bonuses WHERE referrer_user_id = $1",
+        )
+        .bind(referrer_user_id)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to total referral bonuses")
+    }
+}
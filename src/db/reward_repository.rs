@@ -0,0 +1,584 @@
+//! Persistence for user rewards
+//!
+//! Status transitions (pending -> claimed | expired) are guarded both by the
+//! statements below and by a trigger on `user_rewards`, so a claimed or expired
+//! reward can't be moved again even by ad-hoc SQL.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::reward::{CreateUserRewardRequest, EpochRewardLine, EpochRewardReport, UserReward, UserRewardsSummary};
+
+/// Column list for `user_rewards` - legacy VARCHAR/NUMERIC/TIMESTAMP columns are normalised to the model's types
+const REWARD_COLUMNS: &str = "id, user_id, epoch_id, amount::TEXT AS amount, asset, apr_bps, \
+     reward_type::TEXT AS reward_type, status::TEXT AS status, \
+     claim_timestamp AT TIME ZONE 'UTC' AS claim_timestamp, claim_transaction_hash, \
+     created_at AT TIME ZONE 'UTC' AS created_at, updated_at AT TIME ZONE 'UTC' AS updated_at";
+
+/// Column list for `epoch_reward_reports`
+const REPORT_COLUMNS: &str = "epoch_id, apr_bps, epoch_start, epoch_end, recipient_count, \
+     total_time_weighted_balance::TEXT AS total_time_weighted_balance, total_rewards::TEXT AS total_rewards, \
+     distribution, created_at";
+
+/// Seconds in the 365-day year APRs are quoted over
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// Database access for user rewards
+#[derive(Clone)]
+pub struct RewardRepository {
+    db: PgPool,
+}
+
+impl RewardRepository {
+    /// Creates a new reward repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Inserts the rewards of an epoch in one statement.
+    ///
+    /// Users already rewarded for the epoch are skipped, so recomputing an epoch is
+    /// safe. Returns the number of rewards inserted.
+    pub async fn insert_epoch_rewards(&self, epoch_id: i32, rewards: &[CreateUserRewardRequest]) -> Result<u64> {
+        Self::insert_epoch_rewards_in(&self.db, epoch_id, rewards).await
+    }
+
+    /// Same as [`insert_epoch_rewards`](Self::insert_epoch_rewards), on the given executor
+    pub async fn insert_epoch_rewards_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        rewards: &[CreateUserRewardRequest],
+    ) -> Result<u64> {
+        if let Some(reward) = rewards.iter().find(|r| r.epoch_id != epoch_id) {
+            anyhow::bail!("Reward for user {} belongs to epoch {}, not {}", reward.user_id, reward.epoch_id, epoch_id);
+        }
+
+        let user_ids: Vec<Uuid> = rewards.iter().map(|r| r.user_id).collect();
+        let amounts: Vec<String> = rewards.iter().map(|r| r.amount.clone()).collect();
+        let apr_bps: Vec<i32> = rewards.iter().map(|r| r.apr_bps).collect();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.user_rewards (user_id, epoch_id, amount, apr_bps)
+            SELECT user_id, $1, amount::NUMERIC, apr_bps
+            FROM UNNEST($2::UUID[], $3::TEXT[], $4::INTEGER[]) AS r(user_id, amount, apr_bps)
+            ON CONFLICT (user_id, epoch_id, reward_type) DO NOTHING
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(&user_ids)
+        .bind(&amounts)
+        .bind(&apr_bps)
+        .execute(executor)
+        .await
+        .context("Failed to insert epoch rewards")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Pays referrers `bonus_bps` of their referees' epoch rewards.
+    ///
+    /// Each referee's bonus is recorded in `referral_bonuses`, and a referrer's bonuses are summed
+    /// into one `referral` reward row for the epoch. Epoch rewards must already be inserted.
+    /// Referees already paid on are skipped, so this is safe to repeat. Returns the number of
+    /// referral rewards inserted.
+    pub async fn insert_referral_rewards_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        apr_bps: i32,
+        bonus_bps: i32,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            WITH bonuses AS (
+                INSERT INTO lsrwa_express.referral_bonuses (
+                    epoch_id, referrer_user_id, referee_user_id, referee_reward, bonus_bps, amount
+                )
+                SELECT ur.epoch_id, r.referrer_user_id, ur.user_id, ur.amount, $3,
+                       ROUND(ur.amount * $3 / 10000, 18)
+                FROM lsrwa_express.user_rewards ur
+                JOIN lsrwa_express.referrals r ON r.referee_user_id = ur.user_id
+                WHERE ur.epoch_id = $1
+                  AND ur.reward_type = 'epoch'
+                  AND ROUND(ur.amount * $3 / 10000, 18) > 0
+                ON CONFLICT (epoch_id, referee_user_id) DO NOTHING
+                RETURNING referrer_user_id, amount
+            )
+            INSERT INTO lsrwa_express.user_rewards (user_id, epoch_id, amount, apr_bps, reward_type)
+            SELECT referrer_user_id, $1, SUM(amount), $2, 'referral'
+            FROM bonuses
+            GROUP BY referrer_user_id
+            ON CONFLICT (user_id, epoch_id, reward_type) DO NOTHING
+            "#,
+        )
+        .bind(epoch_id)
+        .bind(apr_bps)
+        .bind(bonus_bps)
+        .execute(executor)
+        .await
+        .context("Failed to insert referral rewards")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Calculates each user's reward for a period from their active balance history.
+    ///
+    /// Balances are weighted by how long they were held between `start` and `end`, and accrue
+    /// `apr_bps` pro rata over a 365-day year. Users whose reward rounds to zero are left out.
+    pub async fn calculate_epoch_rewards_in<'e>(
+        executor: impl PgExecutor<'e>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        apr_bps: i32,
+    ) -> Result<Vec<EpochRewardLine>> {
+        sqlx::query_as::<_, EpochRewardLine>(
+            r#"
+            WITH opening AS (
+                SELECT DISTINCT ON (user_id) id, user_id, $1::TIMESTAMPTZ AS changed_at, active_balance
+                FROM lsrwa_express.active_balance_history
+                WHERE changed_at <= $1
+                ORDER BY user_id, changed_at DESC, id DESC
+            ),
+            changes AS (
+                SELECT id, user_id, changed_at, active_balance FROM opening
+                UNION ALL
+                SELECT id, user_id, changed_at, active_balance
+                FROM lsrwa_express.active_balance_history
+                WHERE changed_at > $1 AND changed_at < $2
+            ),
+            segments AS (
+                SELECT user_id, active_balance,
+                       EXTRACT(EPOCH FROM
+                           LEAD(changed_at, 1, $2) OVER (PARTITION BY user_id ORDER BY changed_at, id) - changed_at
+                       )::NUMERIC AS held_secs
+                FROM changes
+            ),
+            weighted AS (
+                SELECT user_id,
+                       SUM(active_balance * held_secs) AS balance_secs
+                FROM segments
+                GROUP BY user_id
+            ),
+            rewards AS (
+                SELECT user_id,
+                       ROUND(balance_secs / EXTRACT(EPOCH FROM $2 - $1)::NUMERIC, 18) AS time_weighted_balance,
+                       ROUND(balance_secs * $3 / (10000 * $4::NUMERIC), 18) AS amount
+                FROM weighted
+            )
+            SELECT r.user_id, u.wallet_address,
+                   r.time_weighted_balance::TEXT AS time_weighted_balance, r.amount::TEXT AS amount
+            FROM rewards r
+            JOIN lsrwa_express.users u ON u.id = r.user_id
+            WHERE r.amount > 0
+            ORDER BY u.wallet_address
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(apr_bps)
+        .bind(SECONDS_PER_YEAR)
+        .fetch_all(executor)
+        .await
+        .context("Failed to calculate epoch rewards")
+    }
+
+    /// Calculates each user's reward for a period from their active balance at its end.
+    ///
+    /// The pre-time-weighting rule, kept behind the `new_reward_engine` flag: the closing
+    /// balance accrues `apr_bps` for the whole period. It's reported as the time-weighted balance.
+    pub async fn calculate_closing_balance_rewards_in<'e>(
+        executor: impl PgExecutor<'e>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        apr_bps: i32,
+    ) -> Result<Vec<EpochRewardLine>> {
+        sqlx::query_as::<_, EpochRewardLine>(
+            r#"
+            WITH closing AS (
+                SELECT DISTINCT ON (user_id) user_id, active_balance
+                FROM lsrwa_express.active_balance_history
+                WHERE changed_at <= $2
+                ORDER BY user_id, changed_at DESC, id DESC
+            ),
+            rewards AS (
+                SELECT user_id,
+                       active_balance AS closing_balance,
+                       ROUND(active_balance * EXTRACT(EPOCH FROM $2 - $1)::NUMERIC * $3 / (10000 * $4::NUMERIC), 18) AS amount
+                FROM closing
+            )
+            SELECT r.user_id, u.wallet_address,
+                   r.closing_balance::TEXT AS time_weighted_balance, r.amount::TEXT AS amount
+            FROM rewards r
+            JOIN lsrwa_express.users u ON u.id = r.user_id
+            WHERE r.amount > 0
+            ORDER BY u.wallet_address
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(apr_bps)
+        .bind(SECONDS_PER_YEAR)
+        .fetch_all(executor)
+        .await
+        .context("Failed to calculate closing balance rewards")
+    }
+
+    /// Gets the distribution report of an epoch's rewards
+    pub async fn get_report(&self, epoch_id: i32) -> Result<Option<EpochRewardReport>> {
+        Self::get_report_in(&self.db, epoch_id).await
+    }
+
+    /// Same as [`get_report`](Self::get_report), on the given executor
+    pub async fn get_report_in<'e>(executor: impl PgExecutor<'e>, epoch_id: i32) -> Result<Option<EpochRewardReport>> {
+        sqlx::query_as::<_, EpochRewardReport>(&format!(
+            "SELECT {} FROM lsrwa_express.epoch_reward_reports WHERE epoch_id = $1",
+            REPORT_COLUMNS
+        ))
+        .bind(epoch_id)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to fetch epoch reward report")
+    }
+
+    /// Stores the distribution report of an epoch's rewards, totalling its lines
+    pub async fn create_report_in<'e>(
+        executor: impl PgExecutor<'e>,
+        epoch_id: i32,
+        apr_bps: i32,
+        epoch_start: DateTime<Utc>,
+        epoch_end: DateTime<Utc>,
+        distribution: &[EpochRewardLine],
+    ) -> Result<EpochRewardReport> {
+        let balances: Vec<String> = distribution.iter().map(|line| line.time_weighted_balance.clone()).collect();
+        let amounts: Vec<String> = distribution.iter().map(|line| line.amount.clone()).collect();
+
+        sqlx::query_as::<_, EpochRewardReport>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.epoch_reward_reports (
+                epoch_id, apr_bps, epoch_start, epoch_end, recipient_count,
+                total_time_weighted_balance, total_rewards, distribution
+            )
+            SELECT $1, $2, $3, $4, $5,
+                   COALESCE((SELECT SUM(b::NUMERIC) FROM UNNEST($6::TEXT[]) AS b), 0),
+                   COALESCE((SELECT SUM(a::NUMERIC) FROM UNNEST($7::TEXT[]) AS a), 0),
+                   $8
+            RETURNING {}
+            "#,
+            REPORT_COLUMNS
+        ))
+        .bind(epoch_id)
+        .bind(apr_bps)
+        .bind(epoch_start)
+        .bind(epoch_end)
+        .bind(distribution.len() as i32)
+        .bind(&balances)
+        .bind(&amounts)
+        .bind(sqlx::types::Json(distribution))
+        .fetch_one(executor)
+        .await
+        .context("Failed to store epoch reward report")
+    }
+
+    /// Gets a reward by ID
+    pub async fn get(&self, id: Uuid) -> Result<Option<UserReward>> {
+        sqlx::query_as::<_, UserReward>(&format!(
+            "SELECT {} FROM lsrwa_express.user_rewards WHERE id = $1",
+            REWARD_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch reward")
+    }
+
+    /// Lists a user's rewards, newest epoch first
+    pub async fn list_by_user(&self, user_id: Uuid) -> Result<Vec<UserReward>> {
+        sqlx::query_as::<_, UserReward>(&format!(
+            "SELECT {} FROM lsrwa_express.user_rewards WHERE user_id = $1 ORDER BY epoch_id DESC",
+            REWARD_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list user rewards")
+    }
+
+    /// Lists a user's pending rewards, oldest epoch first
+    pub async fn list_claimable(&self, user_id: Uuid) -> Result<Vec<UserReward>> {
+        sqlx::query_as::<_, UserReward>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.user_rewards
+            WHERE user_id = $1 AND status = 'pending'
+            ORDER BY epoch_id, created_at
+            "#,
+            REWARD_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list claimable user rewards")
+    }
+
+    /// Aggregates a user's pending, claimed and lifetime rewards
+    pub async fn get_summary(&self, user_id: Uuid) -> Result<UserRewardsSummary> {
+        sqlx::query_as::<_, UserRewardsSummary>(
+            r#"
+            SELECT
+                $1::UUID AS user_id,
+                COALESCE(SUM(amount) FILTER (WHERE status = 'pending'), 0)::TEXT AS total_pending,
+                COALESCE(SUM(amount) FILTER (WHERE status = 'claimed'), 0)::TEXT AS total_claimed,
+                COALESCE(SUM(amount) FILTER (WHERE status <> 'expired'), 0)::TEXT AS total_lifetime,
+                MAX(claim_timestamp) AT TIME ZONE 'UTC' AS last_claim_timestamp
+            FROM lsrwa_express.user_rewards
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to summarise user rewards")
+    }
+
+    /// Marks a pending reward as claimed, returning `None` if it isn't pending
+    pub async fn claim(&self, id: Uuid, claim_transaction_hash: &str) -> Result<Option<UserReward>> {
+        Self::claim_in(&self.db, id, claim_transaction_hash).await
+    }
+
+    /// Same as [`claim`](Self::claim), on the given executor
+    pub async fn claim_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: Uuid,
+        claim_transaction_hash: &str,
+    ) -> Result<Option<UserReward>> {
+        sqlx::query_as::<_, UserReward>(&format!(
+            r#"
+            UPDATE lsrwa_express.user_rewards
+            SET status = 'claimed',
+                claim_timestamp = NOW() AT TIME ZONE 'UTC',
+                claim_transaction_hash = $2
+            WHERE id = $1 AND status = 'pending'
+            RETURNING {}
+            "#,
+            REWARD_COLUMNS
+        ))
+        .bind(id)
+        .bind(claim_transaction_hash)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to claim reward")
+    }
+
+    /// Marks a pending reward as expired, returning `None` if it isn't pending
+    pub async fn expire(&self, id: Uuid) -> Result<Option<UserReward>> {
+        Self::expire_in(&self.db, id).await
+    }
+
+    /// Same as [`expire`](Self::expire), on the given executor
+    pub async fn expire_in<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> Result<Option<UserReward>> {
+        sqlx::query_as::<_, UserReward>(&format!(
+            r#"
+            UPDATE lsrwa_express.user_rewards
+            SET status = 'expired'
+            WHERE id = $1 AND status = 'pending'
+            RETURNING {}
+            "#,
+            REWARD_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to expire reward")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::reward::{RewardStatus, RewardType};
+    use crate::test_support::{RewardBuilder, UserBuilder};
+
+    async fn create_user(pool: &PgPool) -> Uuid {
+        UserBuilder::new().insert(pool).await.unwrap().id
+    }
+
+    fn reward(user_id: Uuid, amount: &str) -> CreateUserRewardRequest {
+        RewardBuilder::new(user_id).amount(amount).build()
+    }
+
+    #[sqlx::test]
+    async fn insert_epoch_rewards_is_idempotent(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+        let bob = create_user(&pool).await;
+
+        let rewards = vec![reward(alice, "1.5"), reward(bob, "2")];
+
+        assert_eq!(repo.insert_epoch_rewards(1, &rewards).await.unwrap(), 2);
+        assert_eq!(repo.insert_epoch_rewards(1, &rewards).await.unwrap(), 0);
+        assert_eq!(repo.list_by_user(alice).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn insert_epoch_rewards_rejects_foreign_epoch(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+
+        assert!(repo.insert_epoch_rewards(2, &[reward(alice, "1")]).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn pending_reward_can_be_claimed_once(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+        repo.insert_epoch_rewards(1, &[reward(alice, "3")]).await.unwrap();
+        let id = repo.list_by_user(alice).await.unwrap()[0].id;
+
+        let claimed = repo.claim(id, "0xclaim").await.unwrap().unwrap();
+        assert_eq!(claimed.status, RewardStatus::Claimed);
+        assert_eq!(claimed.claim_transaction_hash.as_deref(), Some("0xclaim"));
+        assert!(claimed.claim_timestamp.is_some());
+
+        assert!(repo.claim(id, "0xagain").await.unwrap().is_none());
+        assert!(repo.expire(id).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn expired_reward_cannot_be_claimed(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+        repo.insert_epoch_rewards(1, &[reward(alice, "3")]).await.unwrap();
+        let id = repo.list_by_user(alice).await.unwrap()[0].id;
+
+        assert_eq!(repo.expire(id).await.unwrap().unwrap().status, RewardStatus::Expired);
+        assert!(repo.claim(id, "0xclaim").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn trigger_rejects_transitions_out_of_final_states(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+        repo.insert_epoch_rewards(1, &[reward(alice, "3")]).await.unwrap();
+        let id = repo.list_by_user(alice).await.unwrap()[0].id;
+        repo.expire(id).await.unwrap();
+
+        let result = sqlx::query("UPDATE lsrwa_express.user_rewards SET status = 'pending' WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[sqlx::test]
+    async fn claim_requires_transaction_hash(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+        repo.insert_epoch_rewards(1, &[reward(alice, "3")]).await.unwrap();
+        let id = repo.list_by_user(alice).await.unwrap()[0].id;
+
+        let result = sqlx::query("UPDATE lsrwa_express.user_rewards SET status = 'claimed' WHERE id = $1")
+            .bind(id)
+            .execute(&pool)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[sqlx::test]
+    async fn summary_aggregates_by_status(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+
+        sqlx::query("SELECT lsrwa_express.create_new_epoch()").execute(&pool).await.unwrap();
+        sqlx::query("SELECT lsrwa_express.create_new_epoch()").execute(&pool).await.unwrap();
+
+        for (epoch_id, amount) in [(1, "1"), (2, "2"), (3, "4")] {
+            let mut request = reward(alice, amount);
+            request.epoch_id = epoch_id;
+            repo.insert_epoch_rewards(epoch_id, &[request]).await.unwrap();
+        }
+
+        let rewards = repo.list_by_user(alice).await.unwrap();
+        let by_epoch = |epoch_id: i32| rewards.iter().find(|r| r.epoch_id == epoch_id).unwrap().id;
+        repo.claim(by_epoch(1), "0xclaim").await.unwrap();
+        repo.expire(by_epoch(3)).await.unwrap();
+
+        let summary = repo.get_summary(alice).await.unwrap();
+        assert_eq!(summary.user_id, alice);
+        assert_eq!(summary.total_pending.parse::<f64>().unwrap(), 2.0);
+        assert_eq!(summary.total_claimed.parse::<f64>().unwrap(), 1.0);
+        assert_eq!(summary.total_lifetime.parse::<f64>().unwrap(), 3.0);
+        assert!(summary.last_claim_timestamp.is_some());
+    }
+
+    #[sqlx::test]
+    async fn referral_rewards_pay_referrers_a_share_once(pool: PgPool) {
+        let repo = RewardRepository::new(pool.clone());
+        let alice = create_user(&pool).await;
+        let bob = create_user(&pool).await;
+        let carol = create_user(&pool).await;
+        let dave = create_user(&pool).await;
+
+        for referee in [bob, carol] {
+            sqlx::query("INSERT INTO lsrwa_express.referrals (referee_user_id, referrer_user_id) VALUES ($1, $2)")
+                .bind(referee)
+                .bind(alice)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let rewards = vec![reward(alice, "1"), reward(bob, "10"), reward(carol, "5"), reward(dave, "7")];
+        repo.insert_epoch_rewards(1, &rewards).await.unwrap();
+
+        assert_eq!(RewardRepository::insert_referral_rewards_in(&pool, 1, 500, 1000).await.unwrap(), 1);
+        assert_eq!(RewardRepository::insert_referral_rewards_in(&pool, 1, 500, 1000).await.unwrap(), 0);
+
+        let alice_rewards = repo.list_by_user(alice).await.unwrap();
+        assert_eq!(alice_rewards.len(), 2);
+        let referral = alice_rewards.iter().find(|r| r.reward_type == RewardType::Referral).unwrap();
+        assert_eq!(referral.amount.parse::<f64>().unwrap(), 1.5);
+        assert_eq!(repo.list_by_user(dave).await.unwrap().len(), 1);
+    }
+
+    async fn record_balance(pool: &PgPool, user_id: Uuid, active_balance: &str, changed_at: DateTime<Utc>) {
+        sqlx::query(
+            "INSERT INTO lsrwa_express.active_balance_history (user_id, active_balance, changed_at) VALUES ($1, $2::NUMERIC, $3)",
+        )
+        .bind(user_id)
+        .bind(active_balance)
+        .bind(changed_at)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test]
+    async fn epoch_rewards_weight_balances_by_time_held(pool: PgPool) {
+        let alice_user = UserBuilder::new().insert(&pool).await.unwrap();
+        let alice = alice_user.id;
+        let bob = create_user(&pool).await;
+        let carol = create_user(&pool).await;
+
+        let start = "2023-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end = start + chrono::Duration::days(10);
+
+        // Alice holds 1000 for the first half and 3000 for the second
+        record_balance(&pool, alice, "500", start - chrono::Duration::days(3)).await;
+        record_balance(&pool, alice, "1000", start - chrono::Duration::days(1)).await;
+        record_balance(&pool, alice, "3000", start + chrono::Duration::days(5)).await;
+        // Bob withdrew everything before the epoch started
+        record_balance(&pool, bob, "0", start - chrono::Duration::days(1)).await;
+        // Carol only deposits after the epoch ended
+        record_balance(&pool, carol, "1000", end + chrono::Duration::days(1)).await;
+
+        let lines = RewardRepository::calculate_epoch_rewards_in(&pool, start, end, 500).await.unwrap();
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].user_id, alice);
+        assert_eq!(lines[0].wallet_address, alice_user.wallet_address.as_str());
+        assert_eq!(lines[0].time_weighted_balance, "2000.000000000000000000");
+        // 2000 at 5% APR for 10 of 365 days
+        assert_eq!(lines[0].amount, "2.739726027397260274");
+    }
+}
@@ -0,0 +1,160 @@
+//! Persistence for risk parameter versions
+//!
+//! The risk parameters themselves live in `system_parameters`; each change also records the
+//! full set in `risk_parameter_versions`, in the same transaction.
+
+use anyhow::{Context, Result};
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::risk::{OnchainSyncStatus, RiskParameterVersion, RiskParameters};
+
+/// Column list for `risk_parameter_versions`
+const VERSION_COLUMNS: &str = "version, parameters, changed, reason, updated_by, onchain_status::TEXT AS onchain_status, \
+     onchain_transaction_hash, onchain_error, created_at, synced_at";
+
+/// Default number of versions listed
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Maximum number of versions listed
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Database access for risk parameter versions
+#[derive(Clone)]
+pub struct RiskParameterRepository {
+    db: PgPool,
+}
+
+impl RiskParameterRepository {
+    /// Creates a new risk parameter repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Locks the `system_parameters` rows backing the risk parameters until the transaction ends,
+    /// so concurrent changes are applied one after the other
+    pub async fn lock_in<'e>(executor: impl PgExecutor<'e>) -> Result<()> {
+        sqlx::query(
+            "SELECT id FROM lsrwa_express.system_parameters WHERE parameter_name = ANY($1) FOR UPDATE",
+        )
+        .bind(&RiskParameters::NAMES[..])
+        .fetch_all(executor)
+        .await
+        .context("Failed to lock risk parameters")?;
+
+        Ok(())
+    }
+
+    /// Gets the most recent version
+    pub async fn latest(&self) -> Result<Option<RiskParameterVersion>> {
+        Self::latest_in(&self.db).await
+    }
+
+    /// Same as [`latest`](Self::latest), on the given executor
+    pub async fn latest_in<'e>(executor: impl PgExecutor<'e>) -> Result<Option<RiskParameterVersion>> {
+        sqlx::query_as::<_, RiskParameterVersion>(&format!(
+            "SELECT {} FROM lsrwa_express.risk_parameter_versions ORDER BY version DESC LIMIT 1",
+            VERSION_COLUMNS
+        ))
+        .fetch_optional(executor)
+        .await
+        .context("Failed to fetch latest risk parameter version")
+    }
+
+    /// Lists versions, newest first
+    pub async fn list(&self, limit: Option<i64>) -> Result<Vec<RiskParameterVersion>> {
+        sqlx::query_as::<_, RiskParameterVersion>(&format!(
+            "SELECT {} FROM lsrwa_express.risk_parameter_versions ORDER BY version DESC LIMIT $1",
+            VERSION_COLUMNS
+        ))
+        .bind(limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list risk parameter versions")
+    }
+
+    /// Writes the risk parameters to `system_parameters`, returning the names whose value changed
+    pub async fn store_values_in<'e>(
+        executor: impl PgExecutor<'e>,
+        parameters: &RiskParameters,
+        updated_by: Option<Uuid>,
+    ) -> Result<Vec<String>> {
+        let (names, values): (Vec<&str>, Vec<String>) = parameters.stored_values().into_iter().unzip();
+
+        sqlx::query_scalar(
+            r#"
+            UPDATE lsrwa_express.system_parameters p
+            SET parameter_value = v.value,
+                updated_by = $3
+            FROM UNNEST($1::TEXT[], $2::TEXT[]) AS v(name, value)
+            WHERE p.parameter_name = v.name
+              AND p.parameter_value IS DISTINCT FROM v.value
+            RETURNING p.parameter_name::TEXT
+            "#,
+        )
+        .bind(&names)
+        .bind(&values)
+        .bind(updated_by)
+        .fetch_all(executor)
+        .await
+        .context("Failed to store risk parameters")
+    }
+
+    /// Records a new version
+    pub async fn create_version_in<'e>(
+        executor: impl PgExecutor<'e>,
+        parameters: &RiskParameters,
+        changed: &[String],
+        reason: Option<&str>,
+        updated_by: Option<Uuid>,
+        onchain_status: OnchainSyncStatus,
+    ) -> Result<RiskParameterVersion> {
+        sqlx::query_as::<_, RiskParameterVersion>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.risk_parameter_versions (parameters, changed, reason, updated_by, onchain_status)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            VERSION_COLUMNS
+        ))
+        .bind(Json(parameters))
+        .bind(changed)
+        .bind(reason)
+        .bind(updated_by)
+        .bind(onchain_status)
+        .fetch_one(executor)
+        .await
+        .context("Failed to record risk parameter version")
+    }
+
+    /// Records the outcome of pushing a version to the contract
+    pub async fn record_sync(
+        &self,
+        version: i64,
+        transaction_hash: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<RiskParameterVersion> {
+        let status = if error.is_some() { OnchainSyncStatus::Failed } else { OnchainSyncStatus::Synced };
+
+        sqlx::query_as::<_, RiskParameterVersion>(&format!(
+            r#"
+            UPDATE lsrwa_express.risk_parameter_versions
+            SET onchain_status = $2,
+                onchain_transaction_hash = COALESCE($3, onchain_transaction_hash),
+                onchain_error = $4,
+                synced_at = CASE WHEN $4::TEXT IS NULL THEN NOW() ELSE synced_at END
+            WHERE version = $1
+            RETURNING {}
+            "#,
+            VERSION_COLUMNS
+        ))
+        .bind(version)
+        .bind(status)
+        .bind(transaction_hash)
+        .bind(error)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to record risk parameter sync")
+    }
+}
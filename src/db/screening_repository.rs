@@ -0,0 +1,239 @@
+//! Persistence for sanctions screenings and blocked wallets
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::models::screening::{BlockedWallet, RiskLevel, Screening, ScreeningFilter, ScreeningMatch, ScreeningTrigger};
+use crate::models::wallet::WalletAddress;
+
+/// Column list for `screenings`
+const SCREENING_COLUMNS: &str = "id, wallet_address, user_id, trigger, provider, risk_level, flagged, matches, created_at";
+
+/// Column list for `blocked_wallets`
+const BLOCKED_WALLET_COLUMNS: &str = "wallet_address, reason, screening_id, blocked_at, cleared_at, cleared_reason";
+
+/// Default page size for screening lists
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Largest page size accepted for screening lists
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Result of a provider check, before it is stored
+#[derive(Debug, Clone)]
+pub struct NewScreening<'a> {
+    pub wallet_address: &'a str,
+    pub user_id: Option<Uuid>,
+    pub trigger: ScreeningTrigger,
+    pub provider: &'a str,
+    pub risk_level: RiskLevel,
+    pub flagged: bool,
+    pub matches: &'a [ScreeningMatch],
+}
+
+/// Database access for screenings and wallet blocks
+#[derive(Clone)]
+pub struct ScreeningRepository {
+    db: PgPool,
+}
+
+impl ScreeningRepository {
+    /// Creates a new screening repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records the result of a screening
+    pub async fn record_screening(&self, screening: &NewScreening<'_>) -> Result<Screening> {
+        Self::record_screening_in(&self.db, screening).await
+    }
+
+    /// Same as [`record_screening`](Self::record_screening), on the given executor
+    pub async fn record_screening_in<'e>(executor: impl PgExecutor<'e>, screening: &NewScreening<'_>) -> Result<Screening> {
+        sqlx::query_as::<_, Screening>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.screenings (wallet_address, user_id, trigger, provider, risk_level, flagged, matches)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING {}
+            "#,
+            SCREENING_COLUMNS
+        ))
+        .bind(screening.wallet_address)
+        .bind(screening.user_id)
+        .bind(screening.trigger)
+        .bind(screening.provider)
+        .bind(screening.risk_level)
+        .bind(screening.flagged)
+        .bind(Json(screening.matches))
+        .fetch_one(executor)
+        .await
+        .context("Failed to record screening")
+    }
+
+    /// Most recent screening of a wallet
+    pub async fn latest_for_wallet(&self, wallet_address: &str) -> Result<Option<Screening>> {
+        sqlx::query_as::<_, Screening>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.screenings
+            WHERE wallet_address = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            SCREENING_COLUMNS
+        ))
+        .bind(wallet_address)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch latest screening")
+    }
+
+    /// Lists screenings matching the filter, newest first
+    pub async fn list(&self, filter: &ScreeningFilter) -> Result<Vec<Screening>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, Screening>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.screenings
+            WHERE ($1::TEXT IS NULL OR wallet_address = $1)
+              AND ($2::BOOLEAN IS NULL OR flagged = $2)
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            SCREENING_COLUMNS
+        ))
+        .bind(&filter.wallet_address)
+        .bind(filter.flagged)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list screenings")
+    }
+
+    /// Blocks a wallet, re-activating an earlier block that was cleared
+    pub async fn block_wallet_in<'e>(
+        executor: impl PgExecutor<'e>,
+        wallet_address: &str,
+        reason: &str,
+        screening_id: Option<Uuid>,
+    ) -> Result<BlockedWallet> {
+        sqlx::query_as::<_, BlockedWallet>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.blocked_wallets (wallet_address, reason, screening_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (wallet_address) DO UPDATE
+            SET reason = EXCLUDED.reason,
+                screening_id = EXCLUDED.screening_id,
+                blocked_at = NOW(),
+                cleared_at = NULL,
+                cleared_reason = NULL
+            RETURNING {}
+            "#,
+            BLOCKED_WALLET_COLUMNS
+        ))
+        .bind(wallet_address)
+        .bind(reason)
+        .bind(screening_id)
+        .fetch_one(executor)
+        .await
+        .context("Failed to block wallet")
+    }
+
+    /// Active block on a wallet, if any
+    pub async fn active_block(&self, wallet_address: &str) -> Result<Option<BlockedWallet>> {
+        sqlx::query_as::<_, BlockedWallet>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blocked_wallets
+            WHERE wallet_address = $1 AND cleared_at IS NULL
+            "#,
+            BLOCKED_WALLET_COLUMNS
+        ))
+        .bind(wallet_address)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch wallet block")
+    }
+
+    /// Matches of the screening behind the wallet's last block, if an admin has cleared it
+    pub async fn cleared_matches(&self, wallet_address: &str) -> Result<Option<Vec<ScreeningMatch>>> {
+        let matches = sqlx::query_scalar::<_, Json<Vec<ScreeningMatch>>>(
+            r#"
+            SELECT s.matches
+            FROM lsrwa_express.blocked_wallets b
+            JOIN lsrwa_express.screenings s ON s.id = b.screening_id
+            WHERE b.wallet_address = $1 AND b.cleared_at IS NOT NULL
+            "#,
+        )
+        .bind(wallet_address)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch cleared screening matches")?;
+
+        Ok(matches.map(|Json(matches)| matches))
+    }
+
+    /// Lists blocks, newest first, optionally including cleared ones
+    pub async fn list_blocks(&self, include_cleared: bool) -> Result<Vec<BlockedWallet>> {
+        sqlx::query_as::<_, BlockedWallet>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.blocked_wallets
+            WHERE $1 OR cleared_at IS NULL
+            ORDER BY blocked_at DESC
+            "#,
+            BLOCKED_WALLET_COLUMNS
+        ))
+        .bind(include_cleared)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list blocked wallets")
+    }
+
+    /// Lifts an active block. Returns `None` when the wallet isn't blocked.
+    pub async fn clear_block(&self, wallet_address: &str, reason: &str) -> Result<Option<BlockedWallet>> {
+        sqlx::query_as::<_, BlockedWallet>(&format!(
+            r#"
+            UPDATE lsrwa_express.blocked_wallets
+            SET cleared_at = NOW(), cleared_reason = $2
+            WHERE wallet_address = $1 AND cleared_at IS NULL
+            RETURNING {}
+            "#,
+            BLOCKED_WALLET_COLUMNS
+        ))
+        .bind(wallet_address)
+        .bind(reason)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to clear wallet block")
+    }
+
+    /// Registered wallets not screened since `screened_before`, least recently screened first.
+    /// Blocked wallets are skipped; they stay blocked until an admin clears them.
+    pub async fn wallets_due_for_rescreen(&self, screened_before: DateTime<Utc>, limit: i64) -> Result<Vec<WalletAddress>> {
+        sqlx::query_scalar::<_, WalletAddress>(
+            r#"
+            SELECT u.wallet_address
+            FROM lsrwa_express.users u
+            LEFT JOIN LATERAL (
+                SELECT MAX(s.created_at) AS screened_at
+                FROM lsrwa_express.screenings s
+                WHERE s.wallet_address = u.wallet_address
+            ) last ON TRUE
+            WHERE (last.screened_at IS NULL OR last.screened_at < $1)
+              AND NOT EXISTS (
+                  SELECT 1 FROM lsrwa_express.blocked_wallets b
+                  WHERE b.wallet_address = u.wallet_address AND b.cleared_at IS NULL
+              )
+            ORDER BY last.screened_at ASC NULLS FIRST
+            LIMIT $2
+            "#,
+        )
+        .bind(screened_before)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list wallets due for re-screening")
+    }
+}
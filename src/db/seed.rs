@@ -0,0 +1,552 @@
+//! Realistic demo data for staging and local frontend development
+//!
+//! The same seed always produces the same users, requests and rewards; timestamps are laid out
+//! relative to the time of seeding so the most recent epoch is the active one.
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use tracing::info;
+
+use crate::models::request_status::RequestStatus;
+use crate::models::wallet::WalletAddress;
+
+/// Length of a demo epoch
+const EPOCH_DAYS: i64 = 7;
+
+/// First block number used by seeded events
+const BASE_BLOCK: i64 = 1_000_000;
+
+/// Tables the seed writes to, cleared by a reset
+const SEEDED_TABLES: &str = "lsrwa_express.users, lsrwa_express.epochs, lsrwa_express.blockchain_requests, \
+    lsrwa_express.request_processing_events, lsrwa_express.request_execution_events, \
+    lsrwa_express.batch_processing_items, lsrwa_express.user_balances, lsrwa_express.user_rewards, \
+    lsrwa_express.activity_logs";
+
+/// How much demo data to create
+#[derive(Debug, Clone)]
+pub struct SeedOptions {
+    /// Seed of the generator; the same seed always produces the same data
+    pub seed: u64,
+    pub users: u32,
+    pub epochs: u32,
+    /// Clear previously seeded data instead of refusing to seed over existing users
+    pub reset: bool,
+    /// Wallets of the first users, who are KYC-approved, instead of generated ones
+    pub wallets: Vec<WalletAddress>,
+}
+
+impl Default for SeedOptions {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            users: 25,
+            epochs: 6,
+            reset: false,
+            wallets: Vec::new(),
+        }
+    }
+}
+
+/// What a seed run created
+#[derive(Debug, Clone)]
+pub struct SeedSummary {
+    pub users: usize,
+    pub epochs: usize,
+    pub requests: u32,
+}
+
+/// Step between SplitMix64 states
+pub(crate) const SPLITMIX_INCREMENT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// SplitMix64 output for a state; distinct states give distinct outputs
+pub(crate) fn splitmix(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Deterministic generator (SplitMix64), so seeded data doesn't change with dependency upgrades
+struct SeedRng(u64);
+
+impl SeedRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(SPLITMIX_INCREMENT);
+        splitmix(self.0)
+    }
+
+    /// Uniform value in `low..=high`
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next_u64() % (high - low + 1)
+    }
+
+    /// True with the given probability in percent
+    fn percent(&mut self, probability: u64) -> bool {
+        self.range(1, 100) <= probability
+    }
+
+    /// `0x`-prefixed hex string of `bytes` random bytes
+    fn hex(&mut self, bytes: usize) -> String {
+        let mut out = String::from("0x");
+        while out.len() < 2 + bytes * 2 {
+            out.push_str(&format!("{:016x}", self.next_u64()));
+        }
+        out.truncate(2 + bytes * 2);
+        out
+    }
+
+    /// Address of a random account
+    fn wallet(&mut self) -> WalletAddress {
+        let mut public_key = [0; 32];
+        for chunk in public_key.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+        }
+        WalletAddress::from_public_key(public_key)
+    }
+}
+
+/// Formats an amount held in hundredths as a NUMERIC literal
+fn amount(cents: u64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+/// A seeded epoch
+struct Epoch {
+    id: i32,
+    start: NaiveDateTime,
+    completed: bool,
+}
+
+/// A seeded user
+struct DemoUser {
+    id: uuid::Uuid,
+    wallet: WalletAddress,
+    approved: bool,
+}
+
+/// Running balance of a seeded user, in hundredths
+#[derive(Default)]
+struct Balance {
+    active: u64,
+    pending_deposits: u64,
+    pending_withdrawals: u64,
+    deposited: u64,
+    withdrawn: u64,
+    rewards: u64,
+}
+
+/// Counters shared across the whole seed run
+struct Seeder {
+    rng: SeedRng,
+    now: NaiveDateTime,
+    next_on_chain_id: [i64; 3],
+}
+
+/// Populates the database with demo data in a single transaction
+pub async fn seed(pool: &PgPool, options: &SeedOptions) -> Result<SeedSummary> {
+    if options.epochs == 0 {
+        bail!("At least one epoch must be seeded");
+    }
+
+    prepare(pool, options.reset).await?;
+
+    let mut tx = pool.begin().await.context("Failed to start seed transaction")?;
+    let mut seeder = Seeder {
+        rng: SeedRng(options.seed),
+        now: Utc::now().naive_utc(),
+        next_on_chain_id: [1, 1, 1],
+    };
+
+    let epochs = seeder.seed_epochs(&mut tx, options.epochs).await?;
+    let users = seeder.seed_users(&mut tx, options.users, &options.wallets).await?;
+
+    let mut requests = 0;
+    for user in users.iter().filter(|user| user.approved) {
+        let mut balance = Balance::default();
+
+        requests += seeder.seed_requests(&mut tx, user, &epochs, &mut balance).await?;
+        seeder.seed_rewards(&mut tx, user, &epochs, &mut balance).await?;
+        seeder.seed_balance(&mut tx, user, &balance).await?;
+    }
+
+    seeder.seed_batches(&mut tx, &epochs).await?;
+
+    tx.commit().await.context("Failed to commit seed data")?;
+
+    Ok(SeedSummary {
+        users: users.len(),
+        epochs: epochs.len(),
+        requests,
+    })
+}
+
+/// Clears previously seeded data, or refuses to seed over existing users
+async fn prepare(pool: &PgPool, reset: bool) -> Result<()> {
+    if reset {
+        sqlx::query(&format!("TRUNCATE {} RESTART IDENTITY CASCADE", SEEDED_TABLES))
+            .execute(pool)
+            .await
+            .context("Failed to reset seeded tables")?;
+
+        info!("Cleared existing data");
+        return Ok(());
+    }
+
+    let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM lsrwa_express.users")
+        .fetch_one(pool)
+        .await
+        .context("Failed to count users")?;
+
+    if existing > 0 {
+        bail!("The database already has {} users; pass --reset to replace them", existing);
+    }
+
+    Ok(())
+}
+
+impl Seeder {
+    /// Creates completed weekly epochs ending in the currently active one
+    async fn seed_epochs(&mut self, conn: &mut PgConnection, count: u32) -> Result<Vec<Epoch>> {
+        let mut epochs = Vec::new();
+
+        for index in 0..count {
+            let start = self.now - Duration::days(EPOCH_DAYS * (count - index) as i64 - 1);
+            let completed = index + 1 < count;
+            let end = completed.then(|| start + Duration::days(EPOCH_DAYS));
+            let processing_tx_hash = completed.then(|| self.rng.hex(32));
+
+            let id: i32 = sqlx::query_scalar(
+                r#"
+                INSERT INTO lsrwa_express.epochs (start_timestamp, end_timestamp, status, processed_at, processing_tx_hash)
+                VALUES ($1, $2, $3, $2, $4)
+                RETURNING id
+                "#,
+            )
+            .bind(start)
+            .bind(end)
+            .bind(if completed { "completed" } else { "active" })
+            .bind(processing_tx_hash)
+            .fetch_one(&mut *conn)
+            .await
+            .context("Failed to insert epoch")?;
+
+            epochs.push(Epoch { id, start, completed });
+        }
+
+        info!("Created {} epochs", epochs.len());
+
+        Ok(epochs)
+    }
+
+    /// Creates users with a realistic spread of KYC outcomes
+    async fn seed_users(&mut self, conn: &mut PgConnection, count: u32, wallets: &[WalletAddress]) -> Result<Vec<DemoUser>> {
+        let mut users = Vec::new();
+
+        for index in 0..count.max(wallets.len() as u32) {
+            // Drawn for given wallets too, so they don't change the rest of the data
+            let generated_wallet = self.rng.wallet();
+            let generated_status = match self.rng.range(1, 10) {
+                1..=7 => "approved",
+                8..=9 => "pending",
+                _ => "rejected",
+            };
+            let (wallet, kyc_status) = match wallets.get(index as usize) {
+                Some(wallet) => (wallet.clone(), "approved"),
+                None => (generated_wallet, generated_status),
+            };
+            let created_at = self.now - Duration::days(self.rng.range(30, 120) as i64);
+            let kyc_timestamp = (kyc_status != "pending").then(|| created_at + Duration::hours(self.rng.range(1, 72) as i64));
+
+            let id: uuid::Uuid = sqlx::query_scalar(
+                r#"
+                INSERT INTO lsrwa_express.users (wallet_address, email, kyc_status, kyc_timestamp, kyc_reference, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id
+                "#,
+            )
+            .bind(&wallet)
+            .bind(format!("user{}@demo.lsrwa.test", index + 1))
+            .bind(kyc_status)
+            .bind(kyc_timestamp)
+            .bind(kyc_timestamp.map(|_| format!("demo-kyc-{:04}", index + 1)))
+            .bind(created_at)
+            .fetch_one(&mut *conn)
+            .await
+            .context("Failed to insert user")?;
+
+            users.push(DemoUser {
+                id,
+                wallet,
+                approved: kyc_status == "approved",
+            });
+        }
+
+        info!("Created {} users", users.len());
+
+        Ok(users)
+    }
+
+    /// Creates the user's deposits, withdrawals and borrows, processed in completed epochs and
+    /// pending in the active one. Returns the number of requests created.
+    async fn seed_requests(
+        &mut self,
+        conn: &mut PgConnection,
+        user: &DemoUser,
+        epochs: &[Epoch],
+        balance: &mut Balance,
+    ) -> Result<u32> {
+        let mut created = 0;
+
+        for (index, epoch) in epochs.iter().enumerate() {
+            if !self.rng.percent(60) {
+                continue;
+            }
+
+            let kind = match self.rng.range(1, 10) {
+                1..=6 => "deposit",
+                7..=9 => "withdrawal",
+                _ => "borrow",
+            };
+
+            let cents = match kind {
+                "withdrawal" if balance.active == 0 => continue,
+                "withdrawal" => self.rng.range(1, balance.active),
+                _ => self.rng.range(100, 50_000) * 100,
+            };
+            let collateral = (kind == "borrow").then(|| amount(cents * 3 / 2));
+
+            let submitted = epoch.start + Duration::minutes(self.rng.range(10, EPOCH_DAYS as u64 * 24 * 60 - 10) as i64);
+            let block_number = BASE_BLOCK + index as i64 * 100_000 + self.rng.range(0, 99_999) as i64;
+            let on_chain_id = self.next_on_chain_id(kind);
+            let status = if epoch.completed { RequestStatus::Executed } else { RequestStatus::Confirmed };
+
+            let request_id: i32 = sqlx::query_scalar(
+                r#"
+                INSERT INTO lsrwa_express.blockchain_requests (
+                    request_type, on_chain_id, wallet_address, user_id, amount, collateral_amount,
+                    submission_timestamp, is_processed, status, block_number, transaction_hash, created_at
+                )
+                VALUES ($1, $2, $3, $4, $5::numeric, $6::numeric, $7, $8, $9, $10, $11, $7)
+                RETURNING id
+                "#,
+            )
+            .bind(kind)
+            .bind(on_chain_id)
+            .bind(&user.wallet)
+            .bind(user.id)
+            .bind(amount(cents))
+            .bind(collateral)
+            .bind(submitted)
+            .bind(status == RequestStatus::Executed)
+            .bind(status)
+            .bind(block_number)
+            .bind(self.rng.hex(32))
+            .fetch_one(&mut *conn)
+            .await
+            .context("Failed to insert blockchain request")?;
+
+            match (kind, epoch.completed) {
+                ("deposit", true) => {
+                    balance.active += cents;
+                    balance.deposited += cents;
+                },
+                ("deposit", false) => balance.pending_deposits += cents,
+                ("withdrawal", true) => {
+                    balance.active -= cents;
+                    balance.withdrawn += cents;
+                    self.seed_execution(conn, user, on_chain_id, cents, submitted).await?;
+                },
+                ("withdrawal", false) => balance.pending_withdrawals += cents,
+                _ => {},
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO lsrwa_express.activity_logs (user_id, activity_type, description, data, created_at)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(user.id)
+            .bind(format!("{}_requested", kind))
+            .bind(format!("Submitted {} request #{}", kind, on_chain_id))
+            .bind(serde_json::json!({ "request_id": request_id, "on_chain_id": on_chain_id, "amount": amount(cents) }))
+            .bind(submitted)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to insert activity log")?;
+
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    /// Records the execution of a processed withdrawal
+    async fn seed_execution(
+        &mut self,
+        conn: &mut PgConnection,
+        user: &DemoUser,
+        on_chain_id: i64,
+        cents: u64,
+        submitted: NaiveDateTime,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.request_execution_events (
+                request_id, wallet_address, amount, transaction_hash, block_number, execution_timestamp
+            )
+            VALUES ($1, $2, $3::numeric, $4, $5, $6)
+            "#,
+        )
+        .bind(on_chain_id)
+        .bind(&user.wallet)
+        .bind(amount(cents))
+        .bind(self.rng.hex(32))
+        .bind(BASE_BLOCK + self.rng.range(0, 1_000_000) as i64)
+        .bind(submitted + Duration::days(EPOCH_DAYS))
+        .execute(&mut *conn)
+        .await
+        .context("Failed to insert execution event")?;
+
+        Ok(())
+    }
+
+    /// Creates the user's reward for every completed epoch they held a balance in
+    async fn seed_rewards(
+        &mut self,
+        conn: &mut PgConnection,
+        user: &DemoUser,
+        epochs: &[Epoch],
+        balance: &mut Balance,
+    ) -> Result<()> {
+        let completed: Vec<&Epoch> = epochs.iter().filter(|epoch| epoch.completed).collect();
+
+        for (index, epoch) in completed.iter().enumerate() {
+            // 5% APR accrued over one epoch
+            let cents = balance.active * 500 * EPOCH_DAYS as u64 / (10_000 * 365);
+            if cents == 0 {
+                continue;
+            }
+
+            // Older rewards have mostly been claimed, the latest one is still pending
+            let is_latest = index + 1 == completed.len();
+            let status = match (is_latest, self.rng.range(1, 10)) {
+                (true, _) => "pending",
+                (false, 1) => "expired",
+                (false, 2..=3) => "pending",
+                _ => "claimed",
+            };
+
+            let claimed_at = epoch.start + Duration::days(EPOCH_DAYS + 1);
+            let claimed = status == "claimed";
+
+            sqlx::query(
+                r#"
+                INSERT INTO lsrwa_express.user_rewards (
+                    user_id, epoch_id, amount, apr_bps, status, claim_timestamp, claim_transaction_hash
+                )
+                VALUES ($1, $2, $3::numeric, 500, $4, $5, $6)
+                "#,
+            )
+            .bind(user.id)
+            .bind(epoch.id)
+            .bind(amount(cents))
+            .bind(status)
+            .bind(claimed.then_some(claimed_at))
+            .bind(claimed.then(|| self.rng.hex(32)))
+            .execute(&mut *conn)
+            .await
+            .context("Failed to insert user reward")?;
+
+            if claimed {
+                balance.active += cents;
+                balance.rewards += cents;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stores the user's balance as accumulated from their requests and rewards
+    async fn seed_balance(&mut self, conn: &mut PgConnection, user: &DemoUser, balance: &Balance) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.user_balances (
+                user_id, active_balance, pending_deposits, pending_withdrawals,
+                total_deposited, total_withdrawn, total_rewards
+            )
+            VALUES ($1, $2::numeric, $3::numeric, $4::numeric, $5::numeric, $6::numeric, $7::numeric)
+            "#,
+        )
+        .bind(user.id)
+        .bind(amount(balance.active))
+        .bind(amount(balance.pending_deposits))
+        .bind(amount(balance.pending_withdrawals))
+        .bind(amount(balance.deposited))
+        .bind(amount(balance.withdrawn))
+        .bind(amount(balance.rewards))
+        .execute(&mut *conn)
+        .await
+        .context("Failed to insert user balance")?;
+
+        Ok(())
+    }
+
+    /// Records one processing batch per request type for every completed epoch
+    async fn seed_batches(&mut self, conn: &mut PgConnection, epochs: &[Epoch]) -> Result<()> {
+        for (index, epoch) in epochs.iter().enumerate().filter(|(_, epoch)| epoch.completed) {
+            let processed_at = epoch.start + Duration::days(EPOCH_DAYS);
+
+            for kind in ["deposit", "withdrawal", "borrow"] {
+                let request_ids: Vec<i64> = sqlx::query_scalar(
+                    r#"
+                    SELECT on_chain_id FROM lsrwa_express.blockchain_requests
+                    WHERE request_type = $1 AND submission_timestamp >= $2 AND submission_timestamp < $3
+                    ORDER BY on_chain_id
+                    "#,
+                )
+                .bind(kind)
+                .bind(epoch.start)
+                .bind(processed_at)
+                .fetch_all(&mut *conn)
+                .await
+                .context("Failed to load epoch requests")?;
+
+                if request_ids.is_empty() {
+                    continue;
+                }
+
+                sqlx::query(
+                    r#"
+                    SELECT lsrwa_express.record_batch_processing($1, $2, $3, $4, $5, $6)
+                    "#,
+                )
+                .bind(epoch.id)
+                .bind(kind)
+                .bind(&request_ids)
+                .bind(self.rng.hex(32))
+                .bind(BASE_BLOCK + (index as i64 + 1) * 100_000 - 1)
+                .bind(processed_at)
+                .execute(&mut *conn)
+                .await
+                .context("Failed to record batch processing")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Next on-chain id for the request type; each type has its own sequence on chain
+    fn next_on_chain_id(&mut self, kind: &str) -> i64 {
+        let slot = match kind {
+            "deposit" => 0,
+            "withdrawal" => 1,
+            _ => 2,
+        };
+
+        let id = self.next_on_chain_id[slot];
+        self.next_on_chain_id[slot] += 1;
+        id
+    }
+}
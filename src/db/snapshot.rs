@@ -0,0 +1,371 @@
+//! Snapshots of protocol state, for refreshing staging or local environments from production
+//!
+//! A snapshot is a directory holding one gzipped JSON Lines file per core table and a
+//! `manifest.json`. The tables are read in one repeatable-read transaction, so they agree with
+//! each other, and the manifest records the schema version they were read at, the last indexed
+//! block and the contract state read alongside, and each file's row count and checksum.
+//!
+//! Unless told to keep it, personal data is scrubbed as rows are read, so it never leaves the
+//! database: emails are replaced by placeholders derived from the user ID, and KYC provider
+//! references and screening matches are dropped. Wallet addresses are public on-chain and kept.
+//!
+//! Restoring replaces the contents of the snapshot's tables, and of tables referencing them, in
+//! one transaction and moves their sequences past the restored rows.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use tracing::info;
+
+use crate::services::ContractState;
+
+/// File describing a snapshot
+const MANIFEST: &str = "manifest.json";
+
+/// Rows read or written per round trip
+const BATCH_SIZE: usize = 1000;
+
+/// A table in a snapshot, and how its personal data is scrubbed
+struct SnapshotTable {
+    name: &'static str,
+    /// `jsonb` expression over the row `t` whose keys replace the row's columns when scrubbing
+    scrub: Option<&'static str>,
+}
+
+/// Core tables, parents before the tables referencing them
+const TABLES: &[SnapshotTable] = &[
+    SnapshotTable {
+        name: "users",
+        scrub: Some(
+            "jsonb_build_object('email', CASE WHEN t.email IS NULL THEN NULL \
+             ELSE 'user-' || t.id || '@example.invalid' END, 'kyc_reference', NULL)",
+        ),
+    },
+    SnapshotTable { name: "epochs", scrub: None },
+    SnapshotTable { name: "system_parameters", scrub: None },
+    SnapshotTable { name: "feature_flags", scrub: None },
+    SnapshotTable { name: "risk_parameter_versions", scrub: None },
+    SnapshotTable { name: "blockchain_requests", scrub: None },
+    SnapshotTable { name: "request_processing_events", scrub: None },
+    SnapshotTable { name: "request_execution_events", scrub: None },
+    SnapshotTable { name: "batch_processing_items", scrub: None },
+    SnapshotTable { name: "user_balances", scrub: None },
+    SnapshotTable { name: "user_rewards", scrub: None },
+    SnapshotTable { name: "active_balance_history", scrub: None },
+    SnapshotTable { name: "epoch_reward_reports", scrub: None },
+    SnapshotTable { name: "epoch_processing_runs", scrub: None },
+    SnapshotTable { name: "borrow_liquidations", scrub: None },
+    SnapshotTable { name: "liquidation_actions", scrub: None },
+    SnapshotTable { name: "borrow_interest_accruals", scrub: None },
+    SnapshotTable { name: "debt_statements", scrub: None },
+    SnapshotTable { name: "protocol_fees", scrub: None },
+    SnapshotTable { name: "referrals", scrub: None },
+    SnapshotTable { name: "referral_bonuses", scrub: None },
+    SnapshotTable { name: "blockchain_transactions", scrub: None },
+    SnapshotTable {
+        name: "kyc_verifications",
+        scrub: Some("jsonb_build_object('applicant_id', NULL, 'review_answer', NULL, 'rejection_reasons', '{}'::text[])"),
+    },
+    SnapshotTable { name: "screenings", scrub: Some("jsonb_build_object('matches', '[]'::jsonb)") },
+    SnapshotTable { name: "blocked_wallets", scrub: None },
+    SnapshotTable { name: "event_queue", scrub: None },
+    SnapshotTable { name: "contract_deployments", scrub: None },
+    SnapshotTable { name: "contract_versions", scrub: None },
+];
+
+/// Describes a snapshot and the files it's made of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub created_at: DateTime<Utc>,
+    /// Latest migration applied to the database the snapshot was taken from
+    pub schema_version: i64,
+    /// Highest block of an indexed event; indexing resumes after it
+    pub last_indexed_block: Option<i64>,
+    /// Whether personal data was scrubbed
+    pub scrubbed: bool,
+    pub contract_state: Option<ContractState>,
+    pub tables: Vec<SnapshotFile>,
+}
+
+/// One table's file in a snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub table: String,
+    pub file: String,
+    pub rows: u64,
+    /// Hex SHA-256 of the file
+    pub checksum: String,
+}
+
+/// Writes a snapshot of the core tables to `dir`, which must not hold one already
+pub async fn export(
+    pool: &PgPool,
+    dir: &Path,
+    scrub: bool,
+    contract_state: Option<ContractState>,
+) -> Result<SnapshotManifest> {
+    if dir.join(MANIFEST).exists() {
+        bail!("{} already holds a snapshot", dir.display());
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut tx = pool.begin().await.context("Failed to begin snapshot transaction")?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await
+        .context("Failed to start a consistent snapshot")?;
+
+    let schema_version = schema_version(&mut tx).await?;
+    let last_indexed_block: Option<i64> = sqlx::query_scalar("SELECT MAX(block_number) FROM lsrwa_express.event_queue")
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to read the last indexed block")?;
+
+    let mut tables = Vec::new();
+    for table in TABLES {
+        let file = export_table(&mut tx, table, dir, scrub).await?;
+        info!("Exported {} rows of {}", file.rows, file.table);
+        tables.push(file);
+    }
+    tx.commit().await.context("Failed to end snapshot transaction")?;
+
+    let manifest = SnapshotManifest {
+        created_at: Utc::now(),
+        schema_version,
+        last_indexed_block,
+        scrubbed: scrub,
+        contract_state,
+        tables,
+    };
+    let contents = serde_json::to_string_pretty(&manifest).context("Failed to serialize snapshot manifest")?;
+    fs::write(dir.join(MANIFEST), contents + "\n").context("Failed to write snapshot manifest")?;
+
+    Ok(manifest)
+}
+
+/// Writes one table's rows as gzipped JSON Lines
+async fn export_table(conn: &mut PgConnection, table: &SnapshotTable, dir: &Path, scrub: bool) -> Result<SnapshotFile> {
+    let overrides = match table.scrub {
+        Some(scrub_expression) if scrub => scrub_expression,
+        _ => "'{}'::jsonb",
+    };
+    let file_name = format!("{}.jsonl.gz", table.name);
+    let path = dir.join(&file_name);
+    let mut encoder = GzEncoder::new(
+        File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?,
+        Compression::default(),
+    );
+
+    sqlx::query(&format!(
+        "DECLARE snapshot_rows NO SCROLL CURSOR FOR SELECT (to_jsonb(t) || {})::text FROM lsrwa_express.{} t",
+        overrides, table.name
+    ))
+    .execute(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to read {}", table.name))?;
+
+    let mut rows = 0;
+    loop {
+        let batch: Vec<String> = sqlx::query_scalar(&format!("FETCH {} FROM snapshot_rows", BATCH_SIZE))
+            .fetch_all(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to read {}", table.name))?;
+
+        for row in &batch {
+            encoder.write_all(row.as_bytes()).and_then(|_| encoder.write_all(b"\n"))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        rows += batch.len() as u64;
+
+        if batch.len() < BATCH_SIZE {
+            break;
+        }
+    }
+
+    sqlx::query("CLOSE snapshot_rows").execute(&mut *conn).await.context("Failed to close snapshot cursor")?;
+    encoder.finish().with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(SnapshotFile {
+        table: table.name.to_string(),
+        file: file_name,
+        rows,
+        checksum: file_checksum(&path)?,
+    })
+}
+
+/// Reads the manifest of the snapshot in `dir`, checking every file against its checksum
+pub fn load_manifest(dir: &Path) -> Result<SnapshotManifest> {
+    let path = dir.join(MANIFEST);
+    let contents = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest: SnapshotManifest = serde_json::from_str(&contents)
+        .with_context(|| format!("{} is not a snapshot manifest", path.display()))?;
+
+    for file in &manifest.tables {
+        if !TABLES.iter().any(|table| table.name == file.table) {
+            bail!("The snapshot holds {}, which isn't a snapshot table", file.table);
+        }
+        if file_checksum(&dir.join(&file.file))? != file.checksum {
+            bail!("{} doesn't match its checksum; the snapshot is corrupt", file.file);
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Replaces the contents of the snapshot's tables with the snapshot in `dir`. The database must
+/// be migrated to the schema version the snapshot was taken at.
+pub async fn restore(pool: &PgPool, dir: &Path, manifest: &SnapshotManifest) -> Result<u64> {
+    let mut tx = pool.begin().await.context("Failed to begin restore transaction")?;
+
+    let schema_version = schema_version(&mut tx).await?;
+    if schema_version != manifest.schema_version {
+        bail!(
+            "The snapshot was taken at schema version {}, but the database is at {}; migrate both to the same version",
+            manifest.schema_version,
+            schema_version
+        );
+    }
+
+    let names = manifest.tables.iter().map(|file| format!("lsrwa_express.{}", file.table)).collect::<Vec<_>>();
+    sqlx::query(&format!("TRUNCATE {} CASCADE", names.join(", ")))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to clear the tables being restored")?;
+
+    // Files are listed parents first, so references resolve as rows are inserted
+    let mut restored = 0;
+    for file in &manifest.tables {
+        let rows = restore_table(&mut tx, &file.table, &dir.join(&file.file)).await?;
+        if rows != file.rows {
+            bail!("{} holds {} rows, but the manifest lists {}", file.file, rows, file.rows);
+        }
+        reset_sequences(&mut tx, &file.table).await?;
+        info!("Restored {} rows of {}", rows, file.table);
+        restored += rows;
+    }
+
+    tx.commit().await.context("Failed to commit restored snapshot")?;
+    Ok(restored)
+}
+
+/// Inserts a table's rows from its gzipped JSON Lines file
+async fn restore_table(conn: &mut PgConnection, table: &str, path: &Path) -> Result<u64> {
+    let reader = BufReader::new(GzDecoder::new(
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+    ));
+    let insert = format!(
+        "INSERT INTO lsrwa_express.{table} SELECT * FROM jsonb_populate_recordset(NULL::lsrwa_express.{table}, $1)",
+        table = table
+    );
+
+    let mut rows = 0;
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut lines = reader.lines();
+    loop {
+        let line = lines.next().transpose().with_context(|| format!("Failed to read {}", path.display()))?;
+        if let Some(line) = &line {
+            batch.push(serde_json::from_str::<serde_json::Value>(line).with_context(|| format!("Invalid row in {}", path.display()))?);
+        }
+
+        if batch.len() == BATCH_SIZE || (line.is_none() && !batch.is_empty()) {
+            rows += batch.len() as u64;
+            sqlx::query(&insert)
+                .bind(serde_json::Value::Array(std::mem::take(&mut batch)))
+                .execute(&mut *conn)
+                .await
+                .with_context(|| format!("Failed to restore rows of {}", table))?;
+        }
+        if line.is_none() {
+            break;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Moves a table's sequences past its highest restored values
+async fn reset_sequences(conn: &mut PgConnection, table: &str) -> Result<()> {
+    let qualified = format!("lsrwa_express.{}", table);
+    let columns: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT attname::text, pg_get_serial_sequence($1, attname)
+        FROM pg_attribute
+        WHERE attrelid = $1::regclass AND attnum > 0 AND NOT attisdropped
+          AND pg_get_serial_sequence($1, attname) IS NOT NULL
+        "#,
+    )
+    .bind(&qualified)
+    .fetch_all(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to find the sequences of {}", table))?;
+
+    for (column, sequence) in columns {
+        sqlx::query(&format!(
+            "SELECT setval($1, COALESCE((SELECT MAX({}) FROM {}), 0) + 1, false)",
+            column, qualified
+        ))
+        .bind(&sequence)
+        .execute(&mut *conn)
+        .await
+        .with_context(|| format!("Failed to reset {}", sequence))?;
+    }
+
+    Ok(())
+}
+
+/// Latest successfully applied migration
+async fn schema_version(conn: &mut PgConnection) -> Result<i64> {
+    sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success")
+        .fetch_one(conn)
+        .await
+        .context("Failed to read the schema version")
+}
+
+/// Hex SHA-256 of a file
+fn file_checksum(path: &Path) -> Result<String> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(hex::encode(Sha256::digest(&contents)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test]
+    async fn restores_what_was_exported_without_personal_data(pool: PgPool) {
+        let dir = std::env::temp_dir().join(format!("lsrwa-snapshot-{}", uuid::Uuid::new_v4()));
+        sqlx::query(
+            "INSERT INTO lsrwa_express.users (wallet_address, email, kyc_status, kyc_reference) \
+             VALUES ('5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY', 'alice@example.com', 'approved', 'applicant-1')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let manifest = export(&pool, &dir, true, None).await.unwrap();
+        assert!(manifest.scrubbed);
+        assert!(export(&pool, &dir, true, None).await.is_err());
+
+        sqlx::query("DELETE FROM lsrwa_express.users").execute(&pool).await.unwrap();
+        let restored = restore(&pool, &dir, &load_manifest(&dir).unwrap()).await.unwrap();
+        assert_eq!(restored, manifest.tables.iter().map(|file| file.rows).sum::<u64>());
+
+        let (email, kyc_reference): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT email, kyc_reference FROM lsrwa_express.users")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(email.unwrap().ends_with("@example.invalid"));
+        assert_eq!(kyc_reference, None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+//! Persistence for system parameters
+//!
+//! Parameters are read far more often than they change, so typed reads go through
+//! a TTL cache shared by every clone of the repository, backed by the shared
+//! application cache so replicas don't all hit the database. Writes through the
+//! repository invalidate both immediately.
+
+use anyhow::{anyhow, Context, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::models::system_parameter::{SystemParameter, SystemParametersCache, UpdateSystemParameterRequest};
+use crate::services::cache::{keys, Cache};
+
+/// Column list for `system_parameters` - legacy TIMESTAMP columns are normalised to the model's types
+const PARAMETER_COLUMNS: &str = "id, parameter_name, parameter_value, description, updated_by, \
+     created_at AT TIME ZONE 'UTC' AS created_at, updated_at AT TIME ZONE 'UTC' AS updated_at";
+
+/// Cached parameters and when they were loaded
+type CachedParameters = Option<(Instant, SystemParametersCache)>;
+
+/// Database access for system parameters
+#[derive(Clone)]
+pub struct SystemParameterRepository {
+    db: PgPool,
+    cache: Arc<RwLock<CachedParameters>>,
+    ttl: Duration,
+    shared_cache: Cache,
+}
+
+impl SystemParameterRepository {
+    /// Creates a new system parameter repository whose local cache expires after `ttl`
+    pub fn new(db: PgPool, ttl: Duration, shared_cache: Cache) -> Self {
+        Self {
+            db,
+            cache: Arc::new(RwLock::new(None)),
+            ttl,
+            shared_cache,
+        }
+    }
+
+    /// Lists all stored parameters
+    pub async fn list(&self) -> Result<Vec<SystemParameter>> {
+        sqlx::query_as::<_, SystemParameter>(&format!(
+            "SELECT {} FROM lsrwa_express.system_parameters ORDER BY parameter_name",
+            PARAMETER_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list system parameters")
+    }
+
+    /// Gets a stored parameter by name
+    pub async fn get(&self, name: &str) -> Result<Option<SystemParameter>> {
+        sqlx::query_as::<_, SystemParameter>(&format!(
+            "SELECT {} FROM lsrwa_express.system_parameters WHERE parameter_name = $1",
+            PARAMETER_COLUMNS
+        ))
+        .bind(name)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch system parameter")
+    }
+
+    /// Updates a parameter after checking the value parses as the parameter's type
+    pub async fn update(&self, name: &str, request: &UpdateSystemParameterRequest) -> Result<Option<SystemParameter>> {
+        SystemParametersCache::default()
+            .apply(name, &request.parameter_value)
+            .map_err(|e| anyhow!(e))?;
+
+        let parameter = sqlx::query_as::<_, SystemParameter>(&format!(
+            r#"
+            UPDATE lsrwa_express.system_parameters
+            SET parameter_value = $2,
+                description = COALESCE($3, description),
+                updated_by = $4
+            WHERE parameter_name = $1
+            RETURNING {}
+            "#,
+            PARAMETER_COLUMNS
+        ))
+        .bind(name)
+        .bind(&request.parameter_value)
+        .bind(&request.description)
+        .bind(request.updated_by)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to update system parameter")?;
+
+        self.invalidate().await;
+
+        Ok(parameter)
+    }
+
+    /// Drops the cached parameters so the next read reloads them
+    pub async fn invalidate(&self) {
+        *self.cache.write().await = None;
+        self.shared_cache.invalidate(keys::system_parameters()).await;
+    }
+
+    /// Returns the current parameters, reloading them once the cache has expired.
+    ///
+    /// If a reload fails the stale values are served rather than failing the caller.
+    pub async fn parameters(&self) -> Result<SystemParametersCache> {
+        if let Some((loaded_at, parameters)) = self.cache.read().await.as_ref() {
+            if loaded_at.elapsed() < self.ttl {
+                return Ok(parameters.clone());
+            }
+        }
+
+        let mut cache = self.cache.write().await;
+
+        // Another task may have reloaded while we waited for the lock
+        if let Some((loaded_at, parameters)) = cache.as_ref() {
+            if loaded_at.elapsed() < self.ttl {
+                return Ok(parameters.clone());
+            }
+        }
+
+        let loaded = self.shared_cache
+            .get_or_load(keys::system_parameters(), || self.load())
+            .await;
+
+        match loaded {
+            Ok(parameters) => {
+                *cache = Some((Instant::now(), parameters.clone()));
+                Ok(parameters)
+            }
+            Err(err) => match cache.as_ref() {
+                Some((_, stale)) => {
+                    warn!("Failed to reload system parameters, serving stale values: {}", err);
+                    Ok(stale.clone())
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    /// Reward APR in basis points
+    pub async fn reward_apr_bps(&self) -> Result<i32> {
+        Ok(self.parameters().await?.reward_apr_bps)
+    }
+
+    /// Share of a referee's epoch rewards paid to their referrer, in basis points
+    pub async fn referral_bonus_bps(&self) -> Result<i32> {
+        Ok(self.parameters().await?.referral_bonus_bps)
+    }
+
+    /// Annual interest rate charged on borrows, in basis points
+    pub async fn borrow_interest_rate_bps(&self) -> Result<i32> {
+        Ok(self.parameters().await?.borrow_interest_rate_bps)
+    }
+
+    /// Length of an epoch
+    pub async fn epoch_duration(&self) -> Result<Duration> {
+        let seconds = self.parameters().await?.epoch_duration_seconds;
+        Ok(Duration::from_secs(seconds.max(0) as u64))
+    }
+
+    /// Loads the parameters from the database, keeping defaults for missing or invalid rows
+    async fn load(&self) -> Result<SystemParametersCache> {
+        let mut parameters = SystemParametersCache::default();
+
+        for parameter in self.list().await? {
+            if let Err(err) = parameters.apply(&parameter.parameter_name, &parameter.parameter_value) {
+                warn!("{}, keeping the default", err);
+            }
+        }
+
+        Ok(parameters)
+    }
+}
+
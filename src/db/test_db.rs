@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
+use lsrwa_express_rust::config::{DatabaseConfig, Settings};
 use lsrwa_express_rust::db;
 use sqlx::PgPool;
 
@@ -10,13 +11,16 @@ async fn main() -> Result<()> {
 
     println!("=== LSRWA Express Database Schema Test ===");
     
+    let settings = Settings::load().context("Failed to load configuration files")?;
+    let database_config = DatabaseConfig::from_settings(&settings).context("Invalid database configuration")?;
+    
     // Ensure database exists
-    db::migration::ensure_database_exists().await.context("Failed to ensure database exists")?;
+    db::migration::ensure_database_exists(&database_config.url).await.context("Failed to ensure database exists")?;
     
     println!("✅ Database exists or was created");
     
     // Get database connection pool
-    let pool = db::init_db().await.context("Failed to create database pool")?;
+    let pool = db::init_db(&database_config).await.context("Failed to create database pool")?;
     
     println!("✅ Database migrations applied successfully");
     
@@ -0,0 +1,132 @@
+//! Persistence for protocol fees
+
+use anyhow::{Context, Result};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::treasury::{NewProtocolFee, ProtocolFee, ReportPeriod, RevenueLine};
+
+/// Column list for `protocol_fees`
+const FEE_COLUMNS: &str = "id, fee_type::TEXT AS fee_type, request_id, wallet_address::TEXT AS wallet_address, \
+     user_id, epoch_id, amount::TEXT AS amount, block_number, transaction_hash::TEXT AS transaction_hash, \
+     collected_at, created_at";
+
+/// Default number of periods in a revenue report
+const DEFAULT_PERIODS: i64 = 12;
+
+/// Largest number of periods in a revenue report
+const MAX_PERIODS: i64 = 120;
+
+/// Database access for protocol fees
+#[derive(Clone)]
+pub struct TreasuryRepository {
+    db: PgPool,
+}
+
+impl TreasuryRepository {
+    /// Creates a new treasury repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Records a collected fee against the wallet's user and the epoch active at the time.
+    /// Returns `None` when the fee was already recorded.
+    pub async fn record_fee_in<'e>(executor: impl PgExecutor<'e>, fee: &NewProtocolFee) -> Result<Option<ProtocolFee>> {
+        sqlx::query_as::<_, ProtocolFee>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.protocol_fees (
+                fee_type, request_id, wallet_address, user_id, epoch_id, amount, block_number, transaction_hash,
+                collected_at
+            )
+            VALUES (
+                $1, $2, $3,
+                (SELECT id FROM lsrwa_express.users WHERE wallet_address = $3),
+                (
+                    SELECT id FROM lsrwa_express.epochs
+                    WHERE start_timestamp <= $7 AT TIME ZONE 'UTC'
+                      AND (end_timestamp IS NULL OR end_timestamp > $7 AT TIME ZONE 'UTC')
+                    ORDER BY start_timestamp DESC
+                    LIMIT 1
+                ),
+                $4::NUMERIC, $5, $6, $7
+            )
+            ON CONFLICT (fee_type, request_id) DO NOTHING
+            RETURNING {}
+            "#,
+            FEE_COLUMNS
+        ))
+        .bind(fee.fee_type)
+        .bind(fee.request_id)
+        .bind(&fee.wallet_address)
+        .bind(&fee.amount)
+        .bind(fee.block_number)
+        .bind(&fee.transaction_hash)
+        .bind(fee.collected_at)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to record protocol fee")
+    }
+
+    /// Revenue per fee type over the most recent `periods` months or epochs
+    pub async fn revenue(&self, period: ReportPeriod, periods: Option<i64>) -> Result<Vec<RevenueLine>> {
+        let periods = periods.unwrap_or(DEFAULT_PERIODS).clamp(1, MAX_PERIODS);
+
+        let query = match period {
+            ReportPeriod::Month => {
+                r#"
+                WITH months AS (
+                    SELECT DISTINCT date_trunc('month', collected_at AT TIME ZONE 'UTC') AS month
+                    FROM lsrwa_express.protocol_fees
+                    ORDER BY month DESC
+                    LIMIT $1
+                )
+                SELECT
+                    m.month AT TIME ZONE 'UTC' AS period_start,
+                    NULL::INTEGER AS epoch_id,
+                    f.fee_type::TEXT AS fee_type,
+                    COUNT(*) AS fee_count,
+                    SUM(f.amount)::TEXT AS total
+                FROM months m
+                JOIN lsrwa_express.protocol_fees f
+                  ON date_trunc('month', f.collected_at AT TIME ZONE 'UTC') = m.month
+                GROUP BY m.month, f.fee_type
+                ORDER BY m.month DESC, f.fee_type
+                "#
+            },
+            ReportPeriod::Epoch => {
+                r#"
+                WITH periods AS (
+                    SELECT DISTINCT f.epoch_id
+                    FROM lsrwa_express.protocol_fees f
+                    ORDER BY f.epoch_id DESC NULLS LAST
+                    LIMIT $1
+                )
+                SELECT
+                    COALESCE(e.start_timestamp AT TIME ZONE 'UTC', MIN(f.collected_at)) AS period_start,
+                    f.epoch_id,
+                    f.fee_type::TEXT AS fee_type,
+                    COUNT(*) AS fee_count,
+                    SUM(f.amount)::TEXT AS total
+                FROM periods p
+                JOIN lsrwa_express.protocol_fees f ON f.epoch_id IS NOT DISTINCT FROM p.epoch_id
+                LEFT JOIN lsrwa_express.epochs e ON e.id = f.epoch_id
+                GROUP BY f.epoch_id, e.start_timestamp, f.fee_type
+                ORDER BY f.epoch_id DESC NULLS LAST, f.fee_type
+                "#
+            },
+        };
+
+        sqlx::query_as::<_, RevenueLine>(query)
+            .bind(periods)
+            .fetch_all(&self.db)
+            .await
+            .context("Failed to aggregate treasury revenue")
+    }
+
+    /// Total of all recorded fees
+    pub async fn recorded_balance(&self) -> Result<String> {
+        sqlx::query_scalar::<_, String>("SELECT COALESCE(SUM(amount), 0)::TEXT FROM lsrwa_express.protocol_fees")
+            .fetch_one(&self.db)
+            .await
+            .context("Failed to sum protocol fees")
+    }
+}
@@ -0,0 +1,45 @@
+//! Transactional boundary spanning several repositories
+//!
+//! Repository methods have `_in` variants that take an executor. Passing
+//! [`UnitOfWork::conn`] to them runs every statement on the same transaction:
+//!
+//! ```ignore
+//! let mut uow = UnitOfWork::begin(&pool).await?;
+//! BlockchainRequestRepository::transition_in(uow.conn(), &request_type, id, RequestStatus::Executed).await?;
+//! BalanceRepository::apply_withdrawal_in(uow.conn(), user_id, &amount).await?;
+//! ActivityLogRepository::record_in(uow.conn(), &activity).await?;
+//! uow.commit().await?;
+//! ```
+//!
+//! A unit of work that is dropped without [`UnitOfWork::commit`] rolls back.
+
+use anyhow::{Context, Result};
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+
+/// A database transaction shared by the repositories taking part in one operation
+pub struct UnitOfWork {
+    tx: Transaction<'static, Postgres>,
+}
+
+impl UnitOfWork {
+    /// Starts a new unit of work
+    pub async fn begin(pool: &PgPool) -> Result<Self> {
+        let tx = pool.begin().await.context("Failed to begin transaction")?;
+        Ok(Self { tx })
+    }
+
+    /// Connection to run repository statements on
+    pub fn conn(&mut self) -> &mut PgConnection {
+        &mut self.tx
+    }
+
+    /// Commits every statement run in this unit of work
+    pub async fn commit(self) -> Result<()> {
+        self.tx.commit().await.context("Failed to commit transaction")
+    }
+
+    /// Discards every statement run in this unit of work
+    pub async fn rollback(self) -> Result<()> {
+        self.tx.rollback().await.context("Failed to roll back transaction")
+    }
+}
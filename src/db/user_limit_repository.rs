@@ -0,0 +1,130 @@
+//! Persistence for users' volume limit overrides
+
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::models::user_limit::{UpdateUserLimitsRequest, UserLimitOverrides};
+
+/// Column list for `user_limits`
+const USER_LIMIT_COLUMNS: &str = "user_id, deposit_epoch_limit, withdrawal_epoch_limit, deposit_daily_limit, \
+     withdrawal_daily_limit, reason, created_at, updated_at";
+
+/// Database access for users' volume limit overrides
+#[derive(Clone)]
+pub struct UserLimitRepository {
+    db: PgPool,
+}
+
+impl UserLimitRepository {
+    /// Creates a new user limit repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Gets a user's overrides, if any
+    pub async fn get(&self, user_id: Uuid) -> Result<Option<UserLimitOverrides>> {
+        sqlx::query_as::<_, UserLimitOverrides>(&format!(
+            "SELECT {} FROM lsrwa_express.user_limits WHERE user_id = $1",
+            USER_LIMIT_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch user limits")
+    }
+
+    /// Gets the overrides of several users in one query; users without any are left out
+    pub async fn get_many(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, UserLimitOverrides>> {
+        let overrides = sqlx::query_as::<_, UserLimitOverrides>(&format!(
+            "SELECT {} FROM lsrwa_express.user_limits WHERE user_id = ANY($1)",
+            USER_LIMIT_COLUMNS
+        ))
+        .bind(user_ids)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to fetch user limits")?;
+
+        Ok(overrides.into_iter().map(|overrides| (overrides.user_id, overrides)).collect())
+    }
+
+    /// Replaces a user's overrides
+    pub async fn upsert(&self, user_id: Uuid, request: &UpdateUserLimitsRequest) -> Result<UserLimitOverrides> {
+        sqlx::query_as::<_, UserLimitOverrides>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.user_limits (
+                user_id, deposit_epoch_limit, withdrawal_epoch_limit, deposit_daily_limit, withdrawal_daily_limit, reason
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id) DO UPDATE
+            SET deposit_epoch_limit = EXCLUDED.deposit_epoch_limit,
+                withdrawal_epoch_limit = EXCLUDED.withdrawal_epoch_limit,
+                deposit_daily_limit = EXCLUDED.deposit_daily_limit,
+                withdrawal_daily_limit = EXCLUDED.withdrawal_daily_limit,
+                reason = EXCLUDED.reason
+            RETURNING {}
+            "#,
+            USER_LIMIT_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&request.deposit_epoch_limit)
+        .bind(&request.withdrawal_epoch_limit)
+        .bind(&request.deposit_daily_limit)
+        .bind(&request.withdrawal_daily_limit)
+        .bind(request.reason.trim())
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to save user limits")
+    }
+
+    /// Removes a user's overrides, returning them if there were any
+    pub async fn delete(&self, user_id: Uuid) -> Result<Option<UserLimitOverrides>> {
+        sqlx::query_as::<_, UserLimitOverrides>(&format!(
+            "DELETE FROM lsrwa_express.user_limits WHERE user_id = $1 RETURNING {}",
+            USER_LIMIT_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to delete user limits")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UserBuilder;
+    use sqlx::types::BigDecimal;
+    use std::str::FromStr;
+
+    fn tokens(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn overrides_are_replaced_as_a_whole(pool: PgPool) {
+        let user = UserBuilder::new().insert(&pool).await.unwrap();
+        let limits = UserLimitRepository::new(pool);
+
+        let mut request = UpdateUserLimitsRequest {
+            deposit_epoch_limit: Some(tokens("2500.000000000000000001")),
+            withdrawal_epoch_limit: None,
+            deposit_daily_limit: Some(tokens("1000")),
+            withdrawal_daily_limit: None,
+            reason: " Raised after review ".to_string(),
+        };
+        let saved = limits.upsert(user.id, &request).await.unwrap();
+        // Limits are kept to the last digit
+        assert_eq!(saved.deposit_epoch_limit, Some(tokens("2500.000000000000000001")));
+        assert_eq!(saved.reason, "Raised after review");
+
+        request.deposit_daily_limit = None;
+        limits.upsert(user.id, &request).await.unwrap();
+        let overrides = limits.get_many(&[user.id]).await.unwrap();
+        assert_eq!(overrides[&user.id].deposit_daily_limit, None);
+
+        assert!(limits.delete(user.id).await.unwrap().is_some());
+        assert!(limits.get(user.id).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,225 @@
+//! Persistence for users
+
+use anyhow::{Context, Result};
+use sqlx::{PgExecutor, PgPool};
+use std::sync::OnceLock;
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::models::user::{CreateUserRequest, KycStatus, UpdateUserRequest, User, UserFilter};
+use crate::models::wallet::WalletAddress;
+
+/// Column list for `users` - legacy VARCHAR/TIMESTAMP columns are normalised to the model's types
+const USER_COLUMNS: &str = "id, wallet_address, email, kyc_status::TEXT AS kyc_status, \
+     kyc_timestamp AT TIME ZONE 'UTC' AS kyc_timestamp, kyc_reference, \
+     created_at AT TIME ZONE 'UTC' AS created_at, updated_at AT TIME ZONE 'UTC' AS updated_at";
+
+/// Users of the wallets in `$1`
+pub(super) fn users_by_wallets() -> &'static str {
+    static SQL: OnceLock<String> = OnceLock::new();
+    SQL.get_or_init(|| format!("SELECT {} FROM lsrwa_express.users WHERE wallet_address = ANY($1)", USER_COLUMNS))
+}
+
+/// Default page size for user listings
+const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Maximum page size for user listings
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Database access for users
+#[derive(Clone)]
+pub struct UserRepository {
+    db: PgPool,
+}
+
+impl UserRepository {
+    /// Creates a new user repository
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Creates a user with pending KYC
+    pub async fn create(&self, request: &CreateUserRequest) -> Result<User> {
+        Self::create_in(&self.db, request).await
+    }
+
+    /// Same as [`create`](Self::create), on the given executor
+    pub async fn create_in<'e>(executor: impl PgExecutor<'e>, request: &CreateUserRequest) -> Result<User> {
+        sqlx::query_as::<_, User>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.users (wallet_address, email)
+            VALUES ($1, $2)
+            RETURNING {}
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(&request.wallet_address)
+        .bind(&request.email)
+        .fetch_one(executor)
+        .await
+        .context("Failed to insert user")
+    }
+
+    /// Gets a user by ID
+    pub async fn get_by_id(&self, id: Uuid) -> Result<Option<User>> {
+        sqlx::query_as::<_, User>(&format!(
+            "SELECT {} FROM lsrwa_express.users WHERE id = $1",
+            USER_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch user")
+    }
+
+    /// Gets a user by wallet address
+    pub async fn get_by_wallet(&self, wallet_address: &WalletAddress) -> Result<Option<User>> {
+        Self::get_by_wallet_in(&self.db, wallet_address).await
+    }
+
+    /// Same as [`get_by_wallet`](Self::get_by_wallet), on the given executor
+    pub async fn get_by_wallet_in<'e>(
+        executor: impl PgExecutor<'e>,
+        wallet_address: &WalletAddress,
+    ) -> Result<Option<User>> {
+        sqlx::query_as::<_, User>(&format!(
+            "SELECT {} FROM lsrwa_express.users WHERE wallet_address = $1",
+            USER_COLUMNS
+        ))
+        .bind(wallet_address)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to fetch user by wallet")
+    }
+
+    /// Gets the users of several wallets in one query; wallets without a user are left out
+    pub async fn get_by_wallets(&self, wallet_addresses: &[WalletAddress]) -> Result<Vec<User>> {
+        sqlx::query_as::<_, User>(users_by_wallets())
+            .bind(wallet_addresses)
+            .fetch_all(&self.db)
+            .await
+            .context("Failed to fetch users by wallet")
+    }
+
+    /// Updates a user, leaving unspecified fields unchanged
+    pub async fn update(&self, id: Uuid, request: &UpdateUserRequest) -> Result<Option<User>> {
+        Self::update_in(&self.db, id, request).await
+    }
+
+    /// Same as [`update`](Self::update), on the given executor
+    pub async fn update_in<'e>(
+        executor: impl PgExecutor<'e>,
+        id: Uuid,
+        request: &UpdateUserRequest,
+    ) -> Result<Option<User>> {
+        sqlx::query_as::<_, User>(&format!(
+            r#"
+            UPDATE lsrwa_express.users
+            SET email = COALESCE($2, email),
+                kyc_status = COALESCE($3, kyc_status),
+                kyc_timestamp = COALESCE($4 AT TIME ZONE 'UTC', kyc_timestamp),
+                kyc_reference = COALESCE($5, kyc_reference)
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(id)
+        .bind(&request.email)
+        .bind(&request.kyc_status)
+        .bind(request.kyc_timestamp)
+        .bind(&request.kyc_reference)
+        .fetch_optional(executor)
+        .await
+        .context("Failed to update user")
+    }
+
+    /// Lists users matching the filter, newest first
+    pub async fn list(&self, filter: &UserFilter) -> Result<Vec<User>> {
+        let limit = filter.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, User>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.users
+            WHERE ($1::TEXT IS NULL OR kyc_status = $1)
+              AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2 AT TIME ZONE 'UTC')
+              AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3 AT TIME ZONE 'UTC')
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(&filter.kyc_status)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list users")
+    }
+
+    /// Every user matching the filter, newest first, read from a cursor as the stream is polled.
+    /// The filter's limit and offset are ignored.
+    pub fn stream<'a>(&'a self, filter: &'a UserFilter) -> impl Stream<Item = Result<User>> + Send + 'a {
+        static SQL: OnceLock<String> = OnceLock::new();
+        let sql = SQL.get_or_init(|| {
+            format!(
+                r#"
+                SELECT {} FROM lsrwa_express.users
+                WHERE ($1::TEXT IS NULL OR kyc_status = $1)
+                  AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2 AT TIME ZONE 'UTC')
+                  AND ($3::TIMESTAMPTZ IS NULL OR created_at < $3 AT TIME ZONE 'UTC')
+                ORDER BY created_at DESC
+                "#,
+                USER_COLUMNS
+            )
+        });
+
+        sqlx::query_as::<_, User>(sql)
+            .bind(&filter.kyc_status)
+            .bind(filter.created_after)
+            .bind(filter.created_before)
+            .fetch(&self.db)
+            .map(|user| user.context("Failed to read users"))
+    }
+
+    /// Creates or refreshes a user from an on-chain registration event.
+    ///
+    /// On-chain KYC approval is only ever promoted, never revoked, since the
+    /// off-chain provider remains the source of truth for rejections.
+    pub async fn upsert_from_chain_event(&self, wallet_address: &WalletAddress, kyc_approved: bool) -> Result<User> {
+        Self::upsert_from_chain_event_in(&self.db, wallet_address, kyc_approved).await
+    }
+
+    /// Same as [`upsert_from_chain_event`](Self::upsert_from_chain_event), on the given executor
+    pub async fn upsert_from_chain_event_in<'e>(
+        executor: impl PgExecutor<'e>,
+        wallet_address: &WalletAddress,
+        kyc_approved: bool,
+    ) -> Result<User> {
+        let kyc_status = kyc_approved.then_some(KycStatus::Approved);
+
+        sqlx::query_as::<_, User>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.users (wallet_address, kyc_status, kyc_timestamp)
+            VALUES ($1, COALESCE($2, 'pending'), CASE WHEN $2 IS NULL THEN NULL ELSE NOW() END)
+            ON CONFLICT (wallet_address) DO UPDATE
+            SET kyc_status = COALESCE($2, users.kyc_status),
+                kyc_timestamp = CASE
+                    WHEN $2 IS NOT NULL AND users.kyc_status <> $2 THEN NOW()
+                    ELSE users.kyc_timestamp
+                END,
+                updated_at = NOW()
+            RETURNING {}
+            "#,
+            USER_COLUMNS
+        ))
+        .bind(wallet_address)
+        .bind(kyc_status)
+        .fetch_one(executor)
+        .await
+        .context("Failed to upsert user from chain event")
+    }
+}
@@ -1,5 +1,9 @@
 pub mod api;
+pub mod config;
 pub mod contract;
 pub mod db;
+pub mod logging;
 pub mod models;
-pub mod services; 
\ No newline at end of file
+pub mod services;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support; 
\ No newline at end of file
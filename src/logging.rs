@@ -0,0 +1,243 @@
+//! Tracing subscriber setup
+//!
+//! Text output is meant for local development. JSON output writes one object per line with the
+//! service name, the environment, the fields of every enclosing span (so request logs carry
+//! `request_id`, and `wallet` where the request names one) and a `close` line with each span's
+//! busy and idle time. Values of secret settings and secret-named fields are redacted from
+//! JSON output.
+
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::{FmtSpan, JsonFields, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::{Environment, LogFormat, LoggingConfig};
+
+/// Replacement for redacted values
+const REDACTED: &str = "[REDACTED]";
+
+/// Secrets shorter than this aren't redacted from free text, since they'd match too much
+const MIN_SECRET_LEN: usize = 8;
+
+/// Name fragments that mark a setting or log field as holding a secret
+const SECRET_NAME_MARKERS: &[&str] = &[
+    "SEED",
+    "MNEMONIC",
+    "SECRET",
+    "PASSWORD",
+    "API_KEY",
+    "APIKEY",
+    "PRIVATE_KEY",
+    "TOKEN",
+    "AUTHORIZATION",
+];
+
+/// Whether a setting or field name indicates that its value is a secret
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase().replace(['.', '-'], "_");
+    SECRET_NAME_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// Installs the global tracing subscriber
+pub fn init(config: &LoggingConfig, environment: Environment, redactor: Redactor) -> Result<()> {
+    let filter = EnvFilter::try_new(&config.filter)
+        .with_context(|| format!("RUST_LOG '{}' is not a valid filter", config.filter))?;
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match config.format {
+        LogFormat::Text => registry.with(tracing_subscriber::fmt::layer()).try_init()?,
+        LogFormat::Json => {
+            let format = JsonFormat {
+                service: config.service_name.clone(),
+                environment: environment.to_string(),
+                redactor,
+            };
+
+            registry
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .fmt_fields(JsonFields::new())
+                        .with_span_events(FmtSpan::CLOSE)
+                        .event_format(format),
+                )
+                .try_init()?
+        },
+    }
+
+    Ok(())
+}
+
+/// Records the wallet a request concerns on the current request span
+///
+/// For handlers that only learn the wallet from the request body; wallets in the path are
+/// recorded when the span is created.
+pub fn record_wallet(wallet: &str) {
+    tracing::Span::current().record("wallet", wallet);
+}
+
+/// Removes secrets from log records
+#[derive(Clone, Default)]
+pub struct Redactor {
+    /// Longest first, so a secret containing another is replaced whole
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    /// Creates a redactor for the given secret values
+    pub fn new(secrets: impl IntoIterator<Item = String>) -> Self {
+        let mut secrets: Vec<String> = secrets
+            .into_iter()
+            .filter(|secret| secret.len() >= MIN_SECRET_LEN)
+            .collect();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        secrets.dedup();
+
+        Self { secrets }
+    }
+
+    /// Redacts secret-named fields and any known secret appearing in a string field
+    pub fn redact(&self, record: &mut Map<String, Value>) {
+        for (key, value) in record.iter_mut() {
+            if is_secret_name(key) {
+                *value = Value::String(REDACTED.to_string());
+            } else if let Value::String(text) = value {
+                if let Some(redacted) = self.redact_text(text) {
+                    *text = redacted;
+                }
+            }
+        }
+    }
+
+    /// Returns the text with known secrets replaced, or `None` if it contains none
+    fn redact_text(&self, text: &str) -> Option<String> {
+        let mut redacted: Option<String> = None;
+
+        for secret in &self.secrets {
+            let current = redacted.as_deref().unwrap_or(text);
+            if current.contains(secret.as_str()) {
+                redacted = Some(current.replace(secret.as_str(), REDACTED));
+            }
+        }
+
+        redacted
+    }
+}
+
+/// Formats events as single-line JSON objects
+struct JsonFormat {
+    service: String,
+    environment: String,
+    redactor: Redactor,
+}
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let metadata = event.metadata();
+
+        let mut record = Map::new();
+        record.insert("timestamp".into(), Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true).into());
+        record.insert("level".into(), metadata.level().as_str().into());
+        record.insert("target".into(), metadata.target().into());
+        record.insert("service".into(), self.service.as_str().into());
+        record.insert("environment".into(), self.environment.as_str().into());
+
+        // Outermost span first, so inner spans and the event itself win on conflicting names
+        if let Some(scope) = ctx.event_scope() {
+            let mut spans = Vec::new();
+            for span in scope.from_root() {
+                spans.push(Value::from(span.name()));
+
+                let extensions = span.extensions();
+                let Some(fields) = extensions.get::<FormattedFields<N>>() else {
+                    continue;
+                };
+                if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(fields.as_str()) {
+                    record.extend(fields);
+                }
+            }
+            record.insert("spans".into(), Value::Array(spans));
+        }
+
+        event.record(&mut FieldVisitor(&mut record));
+        self.redactor.redact(&mut record);
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
+
+/// Collects event fields into a JSON object
+struct FieldVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value).into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.0.insert(field.name().into(), value.to_string().into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_names_are_recognised() {
+        assert!(is_secret_name("WALLET_SEED_PHRASE"));
+        assert!(is_secret_name("admin_api_key"));
+        assert!(is_secret_name("SUMSUB_SECRET_KEY"));
+        assert!(!is_secret_name("wallet"));
+        assert!(!is_secret_name("request_id"));
+    }
+
+    #[test]
+    fn redacts_secret_fields_and_known_values() {
+        let redactor = Redactor::new(vec![
+            "bottom drive obey lake curtain smoke".to_string(),
+            "short".to_string(),
+        ]);
+
+        let mut record = Map::new();
+        record.insert("message".into(), "Signing with bottom drive obey lake curtain smoke".into());
+        record.insert("api_key".into(), "lsrwa_0123456789".into());
+        record.insert("wallet".into(), "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".into());
+        record.insert("note".into(), "short".into());
+        redactor.redact(&mut record);
+
+        assert_eq!(record["message"], "Signing with [REDACTED]");
+        assert_eq!(record["api_key"], REDACTED);
+        assert_eq!(record["wallet"], "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+        assert_eq!(record["note"], "short");
+    }
+}
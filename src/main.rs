@@ -1,105 +1,448 @@
 use anyhow::{Context, Result};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tokio::time;
 
+use lsrwa_express_rust::api::admission::Admission;
 use lsrwa_express_rust::api::blockchain::BlockchainState;
+use lsrwa_express_rust::config::{Config, LoggingConfig, SecretsConfig, Settings};
 use lsrwa_express_rust::db;
+use lsrwa_express_rust::logging::{self, Redactor};
 use lsrwa_express_rust::services::BlockchainService;
-// Add this line to import the indexer module
+use lsrwa_express_rust::services::alerting::{AlertMonitorJob, Alerter};
+use lsrwa_express_rust::services::archival::{ArchivalWorker, EventArchiveJob};
+use lsrwa_express_rust::services::audit::AuditLog;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use lsrwa_express_rust::services::cache::Cache;
+use lsrwa_express_rust::services::changes::{ChangeFeed, ChangeListener};
 use lsrwa_express_rust::services::indexer;
-
-// Remove local module declarations that conflict with imports
-// mod api;
-mod contract;
-mod models;
-// mod services;
-
-// Use the API module from the crate
+use lsrwa_express_rust::services::event_bus::{self, EventPublisher};
+use lsrwa_express_rust::services::epochs::{EpochAutoCloseJob, EpochProcessingDeps, EpochProcessingService};
+use lsrwa_express_rust::services::http_client::HttpClient;
+use lsrwa_express_rust::services::interest::{DebtStatementJob, DebtStatementService, InterestAccrualService};
+use lsrwa_express_rust::services::leader::{run_while_leader, LeaderElection};
+use lsrwa_express_rust::services::liquidation::LiquidationService;
+use lsrwa_express_rust::services::oracle::PriceFeed;
+use lsrwa_express_rust::services::liquidity::LiquidityPlanningService;
+use lsrwa_express_rust::services::notifications::{self, EmailDeliveryWorker};
+use lsrwa_express_rust::services::rewards::RewardCalculationService;
+use lsrwa_express_rust::services::risk::RiskParameterService;
+use lsrwa_express_rust::services::kyc::{KycDocumentStore, KycManager, KycRouter, KycServiceFactory, KycSyncWorker};
+use lsrwa_express_rust::services::scheduler::Scheduler;
+use lsrwa_express_rust::services::screening::{RescreenWorker, ScreeningService};
+use lsrwa_express_rust::services::secrets::SecretStore;
+use lsrwa_express_rust::services::self_check::SelfCheck;
+use lsrwa_express_rust::services::shutdown::{wait_for_signal, Shutdown};
+use lsrwa_express_rust::services::treasury::TreasuryService;
+use lsrwa_express_rust::services::webhooks::{DeliveryWorker, WebhookSecrets, WebhookStore};
 use lsrwa_express_rust::api;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Load environment variables from .env.local, written by `lsrwa-cli devnet`, then .env
+    dotenv::from_filename(".env.local").ok();
+    dotenv::dotenv().ok();
+    
+    // Layer the profile files and environment overrides
+    let mut settings = Settings::load().context("Failed to load configuration files")?;
+    
+    // Read seed phrases and API keys from the secrets backend before anything uses them
+    let secrets_config = SecretsConfig::from_settings(&settings).context("Invalid secrets configuration")?;
+    let secrets = SecretStore::from_config(&secrets_config, &settings).context("Failed to initialize secrets backend")?;
+    let resolved_secrets = secrets.resolve_settings(&mut settings).await.context("Failed to read secrets")?;
+    
+    // Initialize tracing before the rest of the configuration, so its errors are logged
+    let logging_config = LoggingConfig::from_settings(&settings).context("Invalid logging configuration")?;
+    logging::init(&logging_config, settings.environment(), Redactor::new(settings.secret_values()))
+        .context("Failed to initialize logging")?;
     
     tracing::info!("Starting LSRWA Express API server");
+    tracing::info!("Read {} secrets from the {} secrets backend", resolved_secrets, secrets.backend_name());
     
-    // Load environment variables
-    dotenv::dotenv().ok();
+    // Validate the whole configuration before connecting to anything
+    let config = Config::from_settings(&settings).context("Invalid configuration")?;
+    let http_config = &config.http;
+    tracing::info!("Running in {} environment", config.environment());
+    
+    // Check every dependency up front, so problems are reported together instead of mid-request
+    if config.self_check.enabled {
+        let report = SelfCheck::new(&config, &secrets).run().await;
+        report.log();
+        if !report.passed() {
+            anyhow::bail!("Startup self-check failed: {}", report);
+        }
+        tracing::info!("Startup self-check passed");
+    } else {
+        tracing::warn!("Startup self-check is disabled");
+    }
+    
+    // Install the Prometheus recorder before anything records metrics
+    let metrics = PrometheusBuilder::new()
+        .install_recorder()
+        .context("Failed to install metrics recorder")?;
     
     // Ensure database exists
-    db::migration::ensure_database_exists().await.context("Failed to ensure database exists")?;
+    db::migration::ensure_database_exists(&config.database.url).await.context("Failed to ensure database exists")?;
     
     // Initialize database connections
-    let pool = db::init_db().await.context("Failed to initialize database")?;
+    let pool = db::init_db(&config.database).await.context("Failed to initialize database")?;
     
     // Test connection
     db::pg::test_connection(&pool.pg).await.context("Failed to test connection")?;
     
+    // Set up operator alerting
+    let alerts = Alerter::from_config(&config.alerts).context("Failed to initialize alert channels")?;
+    
+    // Record privileged operations
+    let audit = AuditLog::new(pool.pg.clone());
+    
+    // Connect the cache
+    let cache = Cache::from_config(&config.cache).await.context("Failed to initialize cache")?;
+    
     // Create the blockchain state
     let blockchain_state = Arc::new(RwLock::new(BlockchainState::default()));
     
     // Initialize the blockchain service
     let blockchain_service = Arc::new(
-        BlockchainService::new(pool.clone(), blockchain_state.clone())
+        BlockchainService::new(pool.clone(), config.blockchain.clone(), secrets.clone())
             .await
             .context("Failed to initialize blockchain service")?
     );
     
+    let parameters = db::SystemParameterRepository::new(
+        pool.pg.clone(),
+        Duration::from_secs(60), // cache TTL
+        cache.clone(),
+    );
+    let flags = db::FeatureFlagRepository::new(
+        pool.pg.clone(),
+        config.environment(),
+        Duration::from_secs(30), // cache TTL
+        cache.clone(),
+    );
+    let risk = RiskParameterService::new(pool.pg.clone(), parameters.clone(), blockchain_service.clone());
+    let changes = ChangeFeed::new(256);
+    
+    // Set up the price oracle
+    let prices = PriceFeed::from_config(&config.oracle, blockchain_service.clone())
+        .context("Failed to initialize price oracle")?;
+    
+    // Connect the event bus downstream consumers read protocol events from
+    let event_bus_config = &config.event_bus;
+    let bus = event_bus::bus_from_config(event_bus_config).await.context("Failed to connect to event bus")?;
+    match &bus {
+        Some(bus) => tracing::info!("Publishing events to {} under {}", bus.name(), event_bus_config.topic_prefix),
+        None => tracing::info!("No event bus configured; events are not published"),
+    }
+    let events = EventPublisher::new(bus, event_bus_config.topic_prefix.clone());
+    
+    let rewards = RewardCalculationService::new(pool.pg.clone(), parameters.clone(), flags.clone(), events.clone());
+    let interest = InterestAccrualService::new(pool.pg.clone(), parameters.clone());
+    let statements = DebtStatementService::new(pool.pg.clone());
+    let liquidity = LiquidityPlanningService::new(pool.pg.clone(), blockchain_service.clone(), alerts.clone());
+    let epochs = EpochProcessingService::new(
+        pool.pg.clone(),
+        EpochProcessingDeps {
+            cache: cache.clone(),
+            blockchain: blockchain_service.clone(),
+            rewards: rewards.clone(),
+            interest: interest.clone(),
+            liquidity: liquidity.clone(),
+            alerts: alerts.clone(),
+            events: events.clone(),
+        },
+    );
+    
+    // Set up treasury reporting
+    let treasury = TreasuryService::new(pool.pg.clone(), blockchain_service.clone(), config.treasury.clone(), alerts.clone());
+    
+    // Register recurring jobs
+    let mut scheduler = Scheduler::new();
+    scheduler.register(
+        Arc::new(EpochAutoCloseJob::new(pool.pg.clone(), parameters.clone(), epochs.clone())),
+        config.jobs.epoch_auto_close.clone(),
+    );
+    scheduler.register(
+        Arc::new(LiquidationService::new(
+            pool.pg.clone(),
+            risk.clone(),
+            prices.clone(),
+            blockchain_service.clone(),
+        )),
+        config.jobs.liquidation_monitor.clone(),
+    );
+    scheduler.register(
+        Arc::new(DebtStatementJob::new(statements.clone())),
+        config.jobs.debt_statements.clone(),
+    );
+    scheduler.register(
+        Arc::new(AlertMonitorJob::new(liquidity.clone(), treasury.clone())),
+        config.jobs.alert_monitor.clone(),
+    );
+    scheduler.register(
+        Arc::new(indexer::RequestOwnerBackfillJob::new(pool.pg.clone())),
+        config.jobs.request_owner_backfill.clone(),
+    );
+    match config.event_archive.clone() {
+        Some(archive_config) => scheduler.register(
+            Arc::new(EventArchiveJob::new(pool.pg.clone(), archive_config).context("Failed to initialize event archive")?),
+            config.jobs.event_archive.clone(),
+        ),
+        None => tracing::warn!("No event archive bucket configured; indexed events are not exported"),
+    }
+    
+    // One pooled client for calls to KYC providers and webhook endpoints
+    let http_client = HttpClient::from_config(&config.outbound_http)
+        .context("Failed to initialize outbound HTTP client")?;
+    
+    // Set up the configured KYC providers
+    let kyc_config = &config.kyc;
+    let kyc_services = KycServiceFactory::create_configured(kyc_config, &http_client)
+        .context("Failed to initialize KYC providers")?;
+    let kyc_router = KycRouter::new(kyc_services, kyc_config.provider, kyc_config.routing.clone());
+    let kyc = KycManager::new(pool.pg.clone(), kyc_router, kyc_config.webhook_tolerance_secs);
+    let kyc_documents = kyc_config.documents.clone()
+        .map(|documents| KycDocumentStore::new(db::KycRepository::new(pool.pg.clone()), documents))
+        .transpose()
+        .context("Failed to initialize KYC document storage")?;
+    
+    // Set up sanctions screening
+    let screening_config = &config.screening;
+    let screening = ScreeningService::from_config(pool.pg.clone(), screening_config)
+        .context("Failed to initialize sanctions screening")?;
+    
+    // Events the indexer has queued and not yet handled; submissions are refused while too many are
+    let queue_depth = indexer::QueueDepth::new();
+    
     // Create the app state
     let app_state = api::AppState {
         db: pool.clone(),
         blockchain_state: blockchain_state.clone(),
+        chain: blockchain_service.clone(),
+        secrets: secrets.clone(),
+        admin_api_key: http_config.admin_api_key.clone(),
+        parameters: parameters.clone(),
+        flags: flags.clone(),
+        risk,
+        cache: cache.clone(),
+        changes: changes.clone(),
+        kyc,
+        kyc_documents,
+        rewards: rewards.clone(),
+        interest,
+        statements,
+        liquidity,
+        prices,
+        epochs,
+        treasury,
+        scheduler: scheduler.clone(),
+        screening: screening.clone(),
+        alerts: alerts.clone(),
+        audit,
+        metrics,
+        admission: Admission::new(queue_depth.clone(), &config.indexer),
     };
     
-    // Create the event indexer
-    let event_processor = indexer::EventProcessor::new(
+    // Background workers stop at the next unit of work once this is triggered
+    let shutdown = Shutdown::new();
+    let mut workers = Vec::new();
+    
+    // Apply database change notifications from every instance
+    let change_listener = ChangeListener::new(pool.pg.clone(), cache.clone(), parameters, changes);
+    let worker_shutdown = shutdown.clone();
+    workers.push(tokio::spawn(async move {
+        if let Err(err) = change_listener.start(worker_shutdown).await {
+            tracing::error!("Change listener error: {}", err);
+        }
+    }));
+    
+    // Only the elected leader runs the indexer, the scheduled jobs and the other singleton workers
+    let election = LeaderElection::new(pool.pg.clone(), config.leader_election.clone());
+    let leadership = election.leadership();
+    let release_leadership = Shutdown::new();
+    let election_release = release_leadership.clone();
+    let election_task = tokio::spawn(async move { election.start(election_release).await });
+    
+    // Run the event indexer while leading; each term resumes from the last checkpoint
+    let (indexer_pool, indexer_cache, indexer_blockchain, indexer_state) =
+        (pool.clone(), cache.clone(), blockchain_service.clone(), blockchain_state.clone());
+    let (alert_config, batch_size, flush_interval, event_workers) = (
+        config.alerts.clone(),
+        config.indexer.batch_size,
+        Duration::from_millis(config.indexer.flush_interval_ms),
+        config.indexer.workers,
+    );
+    workers.push(tokio::spawn(run_while_leader("event indexer", leadership.clone(), shutdown.clone(), move |term| {
+        let event_queue = indexer::EventQueue::new(
+            indexer_pool.pg.clone(),
+            indexer_cache.clone(),
+            rewards.clone(),
+            events.clone(),
+            batch_size,
+            flush_interval,
+            queue_depth.clone(),
+        );
+        let (pool, blockchain, state, alerts, alert_config) = (
+            indexer_pool.clone(),
+            indexer_blockchain.clone(),
+            indexer_state.clone(),
+            alerts.clone(),
+            alert_config.clone(),
+        );
+        async move {
+            indexer::EventProcessor::new(&pool, event_queue, blockchain, state, alerts, &alert_config, event_workers)
+                .await
+                .context("Failed to initialize event processor")?
+                .start(term)
+                .await
+        }
+    })));
+    
+    // Start the webhook delivery worker, once secrets stored before they were encrypted are
+    let webhook_secrets = WebhookSecrets::new(secrets.clone());
+    webhook_secrets
+        .seal_stored(&WebhookStore::new(pool.pg.clone()))
+        .await
+        .context("Failed to encrypt stored webhook secrets")?;
+    let webhook_worker = DeliveryWorker::new(
+        pool.pg.clone(),
+        webhook_secrets,
+        http_client.clone(),
+        8,   // max attempts
+        30,  // base retry delay in seconds
+        5,   // polling interval in seconds
+        50,  // batch size
+    );
+    let worker_shutdown = shutdown.clone();
+    workers.push(tokio::spawn(async move {
+        if let Err(err) = webhook_worker.start(worker_shutdown).await {
+            tracing::error!("Webhook delivery worker error: {}", err);
+        }
+    }));
+    
+    // Send queued notification emails
+    let notification_config = &config.notifications;
+    match notifications::sender_from_config(notification_config).context("Failed to initialize email sender")? {
+        Some(sender) => {
+            let email_worker = EmailDeliveryWorker::new(pool.pg.clone(), sender, notification_config);
+            let worker_shutdown = shutdown.clone();
+            workers.push(tokio::spawn(async move {
+                if let Err(err) = email_worker.start(worker_shutdown).await {
+                    tracing::error!("Email delivery worker error: {}", err);
+                }
+            }));
+        },
+        None => tracing::warn!("No email provider configured; notification emails will be queued but not sent"),
+    }
+    
+    // Add approved wallets to the contract's KYC allowlist; one signer, so only on the leader
+    let kyc_sync_worker = Arc::new(KycSyncWorker::new(pool.pg.clone(), blockchain_service.clone(), flags.clone(), kyc_config.onchain_sync.clone()));
+    workers.push(tokio::spawn(run_while_leader("KYC on-chain sync worker", leadership.clone(), shutdown.clone(), move |term| {
+        let worker = kyc_sync_worker.clone();
+        async move { worker.start(term).await }
+    })));
+    
+    // Periodically re-screen registered wallets
+    if screening.has_provider() {
+        let rescreen_worker = Arc::new(RescreenWorker::new(
+            pool.pg.clone(),
+            screening,
+            screening_config.rescreen_interval_secs,
+            screening_config.rescreen_batch_size,
+        ));
+        workers.push(tokio::spawn(run_while_leader("Re-screen worker", leadership.clone(), shutdown.clone(), move |term| {
+            let worker = rescreen_worker.clone();
+            async move { worker.start(term).await }
+        })));
+    }
+    
+    // Sample connection pool usage
+    let pool_metrics = db::PoolMetricsReporter::new(
         pool.clone(),
-        blockchain_service.clone(),
-        blockchain_state.clone(),
-        100, // buffer size
-        3,   // max attempts
-        300, // retry delay in seconds
-        60,  // polling interval in seconds
-    ).await.context("Failed to initialize event processor")?;
-    
-    // Start the event indexer in a separate task
-    let mut event_processor_clone = event_processor;
-    tokio::spawn(async move {
-        tracing::info!("Starting event indexer");
-        if let Err(err) = event_processor_clone.start().await {
-            tracing::error!("Event indexer error: {}", err);
+        15, // sampling interval in seconds
+    );
+    let worker_shutdown = shutdown.clone();
+    workers.push(tokio::spawn(async move { pool_metrics.start(worker_shutdown).await }));
+    
+    // Start the archival worker
+    let archival_worker = Arc::new(ArchivalWorker::new(pool.pg.clone(), config.retention.clone()));
+    workers.push(tokio::spawn(run_while_leader("Archival worker", leadership.clone(), shutdown.clone(), move |term| {
+        let worker = archival_worker.clone();
+        async move { worker.start(term).await }
+    })));
+    
+    // Run recurring jobs while leading
+    let job_scheduler = scheduler.clone();
+    workers.push(tokio::spawn(run_while_leader("Scheduler", leadership, shutdown.clone(), move |term| {
+        job_scheduler.start(term.clone());
+        async move {
+            term.triggered().await;
+            Ok(())
         }
+    })));
+    
+    // Start shutting down on SIGINT or SIGTERM
+    let signal_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        tracing::info!("Shutting down");
+        signal_shutdown.trigger();
     });
     
     // Build the API router
-    let app = api::create_router(app_state)
-        .layer(TraceLayer::new_for_http());
-    
-    // Get the port from environment or use default
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string())
-        .parse::<u16>()
-        .context("Failed to parse PORT environment variable")?;
+    let app = api::create_router(app_state, http_config);
     
     // Create the socket address
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let addr = SocketAddr::from(([0, 0, 0, 0], http_config.port));
     
     tracing::info!("Listening on {}", addr);
     
-    // Start the server
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .context("Server error")?;
+    // Start the server; on shutdown it stops accepting connections and drains in-flight requests.
+    // Peer addresses are kept so the audit log can record them.
+    let server_shutdown = shutdown.clone();
+    let server = axum::Server::bind(&addr)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move { server_shutdown.triggered().await });
+    let http_drain = async {
+        shutdown.triggered().await;
+        time::sleep(Duration::from_secs(config.shutdown.http_drain_secs)).await;
+    };
+    let server_result = tokio::select! {
+        result = server => result.context("Server error"),
+        _ = http_drain => {
+            tracing::warn!("In-flight requests didn't finish within {} seconds", config.shutdown.http_drain_secs);
+            Ok(())
+        },
+    };
+    
+    // Stop the workers even if the server failed, so the error is reported after a clean stop
+    shutdown.trigger();
+    let drained = time::timeout(Duration::from_secs(config.shutdown.worker_drain_secs), async {
+        for worker in workers {
+            if let Err(err) = worker.await {
+                tracing::error!("Background worker panicked: {}", err);
+            }
+        }
+        scheduler.wait_idle().await;
+    }).await;
+    if drained.is_err() {
+        tracing::warn!("Background workers didn't finish within {} seconds", config.shutdown.worker_drain_secs);
+    }
+    
+    // Hand leadership over only once the singleton workers have stopped
+    release_leadership.trigger();
+    if let Err(err) = election_task.await {
+        tracing::error!("Leader election panicked: {}", err);
+    }
+    
+    // Close the pools last, once nothing is using them
+    pool.pg.close().await;
+    if let Some(read) = &pool.read {
+        read.close().await;
+    }
+    tracing::info!("Shutdown complete");
     
-    Ok(())
+    server_result
 }
@@ -0,0 +1,178 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+
+/// Ledger account journal lines are posted to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    /// Stablecoins held by the protocol (asset)
+    Cash,
+    /// Principal and interest owed by borrowers (asset)
+    LoansReceivable,
+    /// Deposits owed back to users (liability)
+    UserDeposits,
+    /// Rewards credited to users and not yet claimed (liability)
+    RewardsPayable,
+    /// Protocol fees collected by the treasury (income)
+    FeeIncome,
+    /// Interest charged on borrows (income)
+    InterestIncome,
+    /// Rewards paid to depositors (expense)
+    RewardExpense,
+}
+
+impl LedgerAccount {
+    /// Every account, in chart order
+    pub const ALL: [LedgerAccount; 7] = [
+        LedgerAccount::Cash,
+        LedgerAccount::LoansReceivable,
+        LedgerAccount::UserDeposits,
+        LedgerAccount::RewardsPayable,
+        LedgerAccount::FeeIncome,
+        LedgerAccount::InterestIncome,
+        LedgerAccount::RewardExpense,
+    ];
+
+    /// Account code in the chart of accounts
+    pub fn code(self) -> &'static str {
+        match self {
+            LedgerAccount::Cash => "1000",
+            LedgerAccount::LoansReceivable => "1200",
+            LedgerAccount::UserDeposits => "2000",
+            LedgerAccount::RewardsPayable => "2100",
+            LedgerAccount::FeeIncome => "4000",
+            LedgerAccount::InterestIncome => "4100",
+            LedgerAccount::RewardExpense => "5000",
+        }
+    }
+
+    /// Account name in the chart of accounts
+    pub fn name(self) -> &'static str {
+        match self {
+            LedgerAccount::Cash => "Cash (USDC)",
+            LedgerAccount::LoansReceivable => "Loans receivable",
+            LedgerAccount::UserDeposits => "User deposits",
+            LedgerAccount::RewardsPayable => "Rewards payable",
+            LedgerAccount::FeeIncome => "Protocol fee income",
+            LedgerAccount::InterestIncome => "Interest income",
+            LedgerAccount::RewardExpense => "Reward expense",
+        }
+    }
+}
+
+/// Protocol event recorded in the ledger
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEntryKind {
+    /// A deposit was processed
+    Deposit,
+    /// A withdrawal was executed
+    Withdrawal,
+    /// A borrow was paid out
+    Borrow,
+    /// A borrow was settled by liquidating its collateral
+    Liquidation,
+    /// The treasury collected a protocol fee
+    Fee,
+    /// Interest accrued on a borrow for an epoch
+    Interest,
+    /// A reward was credited to a user
+    Reward,
+    /// A reward was claimed
+    RewardClaim,
+    /// A reward expired unclaimed
+    RewardExpiry,
+}
+
+impl JournalEntryKind {
+    /// Accounts debited and credited by the event
+    pub fn accounts(self) -> (LedgerAccount, LedgerAccount) {
+        match self {
+            JournalEntryKind::Deposit => (LedgerAccount::Cash, LedgerAccount::UserDeposits),
+            JournalEntryKind::Withdrawal => (LedgerAccount::UserDeposits, LedgerAccount::Cash),
+            JournalEntryKind::Borrow => (LedgerAccount::LoansReceivable, LedgerAccount::Cash),
+            JournalEntryKind::Liquidation => (LedgerAccount::Cash, LedgerAccount::LoansReceivable),
+            JournalEntryKind::Fee => (LedgerAccount::Cash, LedgerAccount::FeeIncome),
+            JournalEntryKind::Interest => (LedgerAccount::LoansReceivable, LedgerAccount::InterestIncome),
+            JournalEntryKind::Reward => (LedgerAccount::RewardExpense, LedgerAccount::RewardsPayable),
+            JournalEntryKind::RewardClaim => (LedgerAccount::RewardsPayable, LedgerAccount::Cash),
+            JournalEntryKind::RewardExpiry => (LedgerAccount::RewardsPayable, LedgerAccount::RewardExpense),
+        }
+    }
+
+    /// Stable name used in entry ids
+    pub fn name(self) -> &'static str {
+        match self {
+            JournalEntryKind::Deposit => "deposit",
+            JournalEntryKind::Withdrawal => "withdrawal",
+            JournalEntryKind::Borrow => "borrow",
+            JournalEntryKind::Liquidation => "liquidation",
+            JournalEntryKind::Fee => "fee",
+            JournalEntryKind::Interest => "interest",
+            JournalEntryKind::Reward => "reward",
+            JournalEntryKind::RewardClaim => "reward_claim",
+            JournalEntryKind::RewardExpiry => "reward_expiry",
+        }
+    }
+}
+
+/// Protocol event with a monetary effect, as read from the database
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LedgerEvent {
+    pub occurred_at: DateTime<Utc>,
+    pub kind: JournalEntryKind,
+    /// Identifies the event within its kind, e.g. the on-chain request id
+    pub reference: String,
+    pub wallet_address: Option<String>,
+    pub amount: BigDecimal,
+}
+
+/// One side of a journal entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalLine {
+    pub account: LedgerAccount,
+    pub account_code: String,
+    pub account_name: String,
+    /// Amount debited, `0` on the credit line
+    pub debit: String,
+    /// Amount credited, `0` on the debit line
+    pub credit: String,
+}
+
+/// Balanced double-entry journal entry for a protocol event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Stable id, e.g. `deposit-42`, so re-exports can be deduplicated on import
+    pub id: String,
+    pub date: DateTime<Utc>,
+    pub kind: JournalEntryKind,
+    pub description: String,
+    pub reference: String,
+    pub wallet_address: Option<String>,
+    pub lines: Vec<JournalLine>,
+}
+
+/// Format of an accounting export
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountingFormat {
+    Json,
+    /// Journal lines, one row per debit or credit
+    #[default]
+    Csv,
+    /// Cash account movements as an OFX bank statement
+    Ofx,
+}
+
+/// Query parameters of an accounting export
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountingExportQuery {
+    /// First day of the period, inclusive
+    pub from: NaiveDate,
+    /// Last day of the period, inclusive
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub format: AccountingFormat,
+}
@@ -4,7 +4,7 @@ use serde_json::Value;
 use sqlx::types::Uuid;
 
 /// Activity log model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct ActivityLog {
     pub id: Uuid,
     pub user_id: Option<Uuid>,
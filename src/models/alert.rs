@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Severity of an operator alert
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl fmt::Display for AlertSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertSeverity::Info => write!(f, "info"),
+            AlertSeverity::Warning => write!(f, "warning"),
+            AlertSeverity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+impl FromStr for AlertSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(AlertSeverity::Info),
+            "warning" | "warn" => Ok(AlertSeverity::Warning),
+            "critical" | "crit" => Ok(AlertSeverity::Critical),
+            other => Err(anyhow::anyhow!("Unknown alert severity '{}'", other)),
+        }
+    }
+}
+
+/// A condition operators are alerted about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    /// Identifies the condition: repeats of an active alert are throttled, and resolving the key
+    /// clears it
+    pub key: String,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub details: String,
+}
+
+/// An alert that has been raised and not resolved
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveAlert {
+    #[serde(flatten)]
+    pub alert: Alert,
+    pub raised_at: DateTime<Utc>,
+    pub last_notified_at: DateTime<Utc>,
+    /// Repeats held back since the last notification
+    pub suppressed: u32,
+}
+
+/// Sends a test alert through the configured channels
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestAlertRequest {
+    #[serde(default = "default_test_severity")]
+    pub severity: AlertSeverity,
+}
+
+fn default_test_severity() -> AlertSeverity {
+    AlertSeverity::Info
+}
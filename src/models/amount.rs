@@ -0,0 +1,314 @@
+//! Token amounts in on-chain units
+//!
+//! The contract keeps every balance as a `u128` of base units, and how many decimals a token has
+//! depends on the network. An [`Amount`] holds the units themselves; it is only turned into or
+//! out of a human decimal with the decimals of the network it belongs to, so no amount passes
+//! through a float on its way on-chain.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::types::BigDecimal;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Failure reading an amount
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("'{0}' is not a decimal amount")]
+    Invalid(String),
+
+    #[error("'{value}' has more than {decimals} decimals")]
+    TooPrecise { value: String, decimals: u32 },
+
+    #[error("'{0}' is too large")]
+    Overflow(String),
+}
+
+/// Amount of a token in on-chain base units
+///
+/// Serialized as its units in a decimal string, since JSON numbers can't hold every `u128`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u128);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    /// An amount of `units` base units
+    pub const fn from_units(units: u128) -> Self {
+        Self(units)
+    }
+
+    /// Base units of the amount
+    pub const fn units(self) -> u128 {
+        self.0
+    }
+
+    /// Reads a human decimal such as `"12.5"` of a token with `decimals` decimals
+    ///
+    /// Signs, exponents and digits beyond the token's precision are refused rather than
+    /// rounded; trailing zeros past it are fine.
+    pub fn parse(value: &str, decimals: u32) -> Result<Self, AmountError> {
+        let invalid = || AmountError::Invalid(value.to_string());
+
+        let (whole, fraction) = value.trim().split_once('.').unwrap_or((value.trim(), ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        if !whole.bytes().chain(fraction.bytes()).all(|byte| byte.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > decimals as usize {
+            return Err(AmountError::TooPrecise { value: value.to_string(), decimals });
+        }
+
+        let digits = format!("{}{}{}", whole, fraction, "0".repeat(decimals as usize - fraction.len()));
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(Self::ZERO);
+        }
+        digits.parse::<u128>().map(Self).map_err(|_| AmountError::Overflow(value.to_string()))
+    }
+
+    /// Reads a float, as JSON clients send amounts, by its shortest decimal form
+    pub fn from_f64(value: f64, decimals: u32) -> Result<Self, AmountError> {
+        if !value.is_finite() {
+            return Err(AmountError::Invalid(value.to_string()));
+        }
+        Self::parse(&value.to_string(), decimals)
+    }
+
+    /// The amount as a human decimal of a token with `decimals` decimals, without trailing zeros
+    pub fn format(self, decimals: u32) -> String {
+        let Some(unit) = 10u128.checked_pow(decimals) else {
+            // Only reachable past 38 decimals, which no token has
+            return self.to_decimal(decimals).to_string();
+        };
+
+        let (whole, fraction) = (self.0 / unit, self.0 % unit);
+        if fraction == 0 {
+            return whole.to_string();
+        }
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        format!("{}.{}", whole, fraction.trim_end_matches('0'))
+    }
+
+    /// The amount in tokens, exactly
+    pub fn to_decimal(self, decimals: u32) -> BigDecimal {
+        BigDecimal::new(self.0.into(), decimals as i64)
+    }
+
+    /// The amount in tokens as a float, for comparisons against float limits
+    pub fn to_f64(self, decimals: u32) -> f64 {
+        self.0 as f64 / 10f64.powi(decimals as i32)
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn checked_mul(self, factor: u128) -> Option<Amount> {
+        self.0.checked_mul(factor).map(Self)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl fmt::Display for Amount {
+    /// Base units; use [`Amount::format`] for tokens
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = AmountError;
+
+    /// Reads base units
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(AmountError::Invalid(s.to_string()));
+        }
+        s.parse::<u128>().map(Self).map_err(|_| AmountError::Overflow(s.to_string()))
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    /// Reads base units from a string, or from an integer small enough for JSON
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UnitsVisitor;
+
+        impl<'de> Visitor<'de> for UnitsVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an amount in base units")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+                Ok(Amount(value.into()))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(UnitsVisitor)
+    }
+}
+
+/// Reads a human decimal amount sent as either a JSON number or a string, keeping its digits
+/// for [`Amount::parse`]
+pub fn decimal_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    struct DecimalVisitor;
+
+    impl<'de> Visitor<'de> for DecimalVisitor {
+        type Value = String;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a decimal amount")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+    }
+
+    deserializer.deserialize_any(DecimalVisitor)
+}
+
+/// Decimals in tokens, serialized as strings so no digit is lost and read from either a JSON
+/// number or a string; use with `#[serde(with = "decimal_text")]`
+pub mod decimal_text {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&value.normalized())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigDecimal, D::Error> {
+        BigDecimal::from_str(&decimal_string(deserializer)?).map_err(de::Error::custom)
+    }
+
+    /// The same for optional decimals, `null` when unset
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(value: &Option<BigDecimal>, serializer: S) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BigDecimal>, D::Error> {
+            #[derive(Deserialize)]
+            struct Decimal(#[serde(with = "super")] BigDecimal);
+
+            Ok(Option::<Decimal>::deserialize(deserializer)?.map(|Decimal(value)| value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT: u128 = 1_000_000_000_000;
+
+    #[test]
+    fn decimals_are_read_exactly() {
+        assert_eq!(Amount::parse("250", 12).unwrap().units(), 250 * UNIT);
+        assert_eq!(Amount::parse("40.5", 12).unwrap().units(), 40_500_000_000_000);
+        assert_eq!(Amount::parse("0.000000000001", 12).unwrap().units(), 1);
+        assert_eq!(Amount::parse(".5", 10).unwrap().units(), 5_000_000_000);
+        assert_eq!(Amount::parse("1.50000000000000", 12).unwrap().units(), 1_500_000_000_000);
+        assert_eq!(Amount::parse("0", 12).unwrap(), Amount::ZERO);
+        assert_eq!(Amount::from_f64(0.1, 12).unwrap().units(), 100_000_000_000);
+    }
+
+    #[test]
+    fn malformed_or_too_precise_decimals_are_refused() {
+        for value in ["", ".", "-1", "+1", "1e3", "1.2.3", "12 USDC", "0x10"] {
+            assert_eq!(Amount::parse(value, 12), Err(AmountError::Invalid(value.to_string())), "{}", value);
+        }
+        assert!(matches!(Amount::parse("1.00000000001", 10), Err(AmountError::TooPrecise { decimals: 10, .. })));
+        assert!(matches!(Amount::parse("340282366920938463463374607.431768211456", 12), Err(AmountError::Overflow(_))));
+        assert!(Amount::from_f64(f64::NAN, 12).is_err());
+    }
+
+    #[test]
+    fn amounts_are_formatted_with_the_network_decimals() {
+        let amount = Amount::from_units(40_500_000_000_000);
+        assert_eq!(amount.format(12), "40.5");
+        assert_eq!(amount.format(10), "4050");
+        assert_eq!(Amount::from_units(1).format(12), "0.000000000001");
+        assert_eq!(Amount::from_units(u128::MAX).format(12), "340282366920938463463374607.431768211455");
+        assert_eq!(amount.to_decimal(12), BigDecimal::from_str("40.5").unwrap());
+        assert_eq!(Amount::parse(&amount.format(10), 10).unwrap(), amount);
+    }
+
+    #[test]
+    fn arithmetic_is_checked() {
+        let max = Amount::from_units(u128::MAX);
+        assert_eq!(Amount::from_units(2).checked_add(Amount::from_units(3)), Some(Amount::from_units(5)));
+        assert_eq!(max.checked_add(Amount::from_units(1)), None);
+        assert_eq!(Amount::ZERO.checked_sub(Amount::from_units(1)), None);
+        assert_eq!(max.checked_mul(2), None);
+    }
+
+    #[test]
+    fn units_round_trip_through_json() {
+        let amount = Amount::from_units(u128::MAX);
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, format!("\"{}\"", u128::MAX));
+        assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), amount);
+        assert_eq!(serde_json::from_str::<Amount>("1500").unwrap(), Amount::from_units(1500));
+        assert!(serde_json::from_str::<Amount>("\"1.5\"").is_err());
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+    }
+
+    #[test]
+    fn decimals_round_trip_through_json_as_text() {
+        #[derive(Serialize, Deserialize)]
+        struct Limit(#[serde(with = "decimal_text::option")] Option<BigDecimal>);
+
+        let limit = Limit(Some(BigDecimal::from_str("2500.500000").unwrap()));
+        assert_eq!(serde_json::to_string(&limit).unwrap(), "\"2500.5\"");
+        assert_eq!(serde_json::to_string(&Limit(Some(BigDecimal::from(300)))).unwrap(), "\"300\"");
+        assert_eq!(serde_json::to_string(&Limit(None)).unwrap(), "null");
+
+        let read = |json: &str| serde_json::from_str::<Limit>(json).unwrap().0;
+        assert_eq!(read("0.1"), Some(BigDecimal::from_str("0.1").unwrap()));
+        assert_eq!(read("\"1000000.000000000001\""), Some(BigDecimal::from_str("1000000.000000000001").unwrap()));
+        assert_eq!(read("null"), None);
+        assert!(serde_json::from_str::<Limit>("\"ten\"").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::blockchain_request::RequestType;
+
+/// Day of indexed events exported to object storage
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EventArchive {
+    /// UTC day the events were indexed on
+    pub day: NaiveDate,
+    pub bucket: String,
+    pub object_key: String,
+    /// File format, e.g. `jsonl.gz`
+    pub format: String,
+    pub event_count: i64,
+    pub first_block: i64,
+    pub last_block: i64,
+    pub byte_size: i64,
+    /// SHA-256 of the stored object, hex encoded
+    pub checksum: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Indexed event as stored in `event_queue`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct StoredEvent {
+    pub id: String,
+    pub event_type: i32,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    pub request_id: Option<i64>,
+    pub wallet_address: Option<String>,
+    pub amount: Option<String>,
+    pub request_type: Option<RequestType>,
+    pub timestamp: DateTime<Utc>,
+    /// Fields of the event's payload
+    pub raw_data: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One line of an event archive file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedEvent {
+    pub id: String,
+    /// Event type code as stored in `event_queue`
+    pub event_type: i32,
+    /// Event type name, e.g. `deposit_request`; absent for unknown codes
+    pub event_name: Option<String>,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    pub request_id: Option<i64>,
+    pub wallet_address: Option<String>,
+    pub amount: Option<String>,
+    pub request_type: Option<RequestType>,
+    /// Block timestamp of the event
+    pub timestamp: DateTime<Utc>,
+    /// When the event was indexed
+    pub indexed_at: DateTime<Utc>,
+    /// Event payload as JSON text
+    pub raw_data: String,
+    /// `raw_data` decoded
+    pub data: Option<Value>,
+}
+
+/// Query parameters for listing event archives
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventArchiveFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,161 @@
+//! Assets amounts are denominated in
+//!
+//! Every amount belongs to an asset, and an asset's decimals decide how its base units read as
+//! tokens. The default asset is the one the contract holds; requests, balances and rewards carry
+//! the symbol of the asset they were recorded in.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+use std::fmt;
+
+use crate::models::amount::{Amount, AmountError};
+
+/// Symbol of the network's own token, as seeded
+pub const NATIVE_SYMBOL: &str = "NATIVE";
+
+/// Largest number of decimals an asset can have
+pub const MAX_DECIMALS: i16 = 30;
+
+/// How an asset is held on-chain
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    /// The network's own token
+    Native,
+    /// A PSP22 token contract
+    Psp22,
+}
+
+impl fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetKind::Native => write!(f, "native"),
+            AssetKind::Psp22 => write!(f, "psp22"),
+        }
+    }
+}
+
+/// An asset amounts can be denominated in
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Asset {
+    pub symbol: String,
+    pub name: String,
+    pub decimals: i16,
+    pub kind: AssetKind,
+    /// Token contract of PSP22 assets
+    pub address: Option<String>,
+    /// Whether this is the asset the contract holds
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Asset {
+    /// The network's own token with `decimals` decimals, as seeded
+    pub fn native(decimals: u32) -> Self {
+        let now = Utc::now();
+        Self {
+            symbol: NATIVE_SYMBOL.to_string(),
+            name: "Network token".to_string(),
+            decimals: decimals as i16,
+            kind: AssetKind::Native,
+            address: None,
+            is_default: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Decimals as amounts are converted with
+    pub fn decimal_places(&self) -> u32 {
+        self.decimals.max(0) as u32
+    }
+
+    /// Reads a human decimal such as `"12.5"` of this asset
+    pub fn parse_amount(&self, value: &str) -> Result<Amount, AmountError> {
+        Amount::parse(value, self.decimal_places())
+    }
+
+    /// An amount of this asset as a human decimal
+    pub fn format(&self, amount: Amount) -> String {
+        amount.format(self.decimal_places())
+    }
+
+    /// An amount of this asset in tokens, exactly
+    pub fn to_decimal(&self, amount: Amount) -> BigDecimal {
+        amount.to_decimal(self.decimal_places())
+    }
+}
+
+/// Register or change asset request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpsertAssetRequest {
+    pub name: String,
+    pub decimals: i16,
+    pub kind: AssetKind,
+    /// Token contract, required for PSP22 assets
+    pub address: Option<String>,
+    /// Makes the asset the contract's; takes effect when the service restarts
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl UpsertAssetRequest {
+    /// Checks the request describes a usable asset
+    pub fn validate(&self, symbol: &str) -> Result<(), String> {
+        if symbol.is_empty()
+            || symbol.len() > 16
+            || !symbol.bytes().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit())
+        {
+            return Err("Symbol must be 1 to 16 uppercase letters or digits".to_string());
+        }
+        if self.name.trim().is_empty() {
+            return Err("Name must not be empty".to_string());
+        }
+        if !(0..=MAX_DECIMALS).contains(&self.decimals) {
+            return Err(format!("Decimals must be between 0 and {}", MAX_DECIMALS));
+        }
+        match (self.kind, &self.address) {
+            (AssetKind::Native, Some(_)) => Err("Native assets have no address".to_string()),
+            (AssetKind::Psp22, None) => Err("PSP22 assets need a token contract address".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc() -> UpsertAssetRequest {
+        UpsertAssetRequest {
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            kind: AssetKind::Psp22,
+            address: Some("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string()),
+            is_default: false,
+        }
+    }
+
+    #[test]
+    fn amounts_are_read_with_the_assets_decimals() {
+        let native = Asset::native(12);
+        let usdc = Asset { symbol: "USDC".to_string(), decimals: 6, ..native.clone() };
+
+        assert_eq!(native.parse_amount("1.5").unwrap().units(), 1_500_000_000_000);
+        assert_eq!(usdc.parse_amount("1.5").unwrap().units(), 1_500_000);
+        assert!(usdc.parse_amount("0.0000001").is_err());
+        assert_eq!(usdc.format(Amount::from_units(2_500_000)), "2.5");
+    }
+
+    #[test]
+    fn assets_are_validated_by_kind() {
+        assert_eq!(usdc().validate("USDC"), Ok(()));
+        assert!(usdc().validate("usdc").is_err());
+        assert!(UpsertAssetRequest { address: None, ..usdc() }.validate("USDC").is_err());
+        assert!(UpsertAssetRequest { kind: AssetKind::Native, ..usdc() }.validate("USDC").is_err());
+        assert!(UpsertAssetRequest { decimals: 31, ..usdc() }.validate("USDC").is_err());
+    }
+}
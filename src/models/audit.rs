@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Kind of privileged operation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A call to the admin API
+    AdminRequest,
+    /// A protocol parameter, risk parameter or feature flag changed
+    ParameterChange,
+    /// An operator re-ran or repaired processing by hand
+    Reconciliation,
+    /// An extrinsic signed by the contract owner
+    Extrinsic,
+    /// An operator signing key was staged or rotated
+    KeyChange,
+}
+
+/// A recorded privileged operation
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Operator named by `X-Admin-Actor`, or `system` for background jobs
+    pub actor: String,
+    pub action: AuditAction,
+    pub target: String,
+    pub before_state: Option<Value>,
+    pub after_state: Option<Value>,
+    pub ip_address: Option<String>,
+    pub request_id: Option<String>,
+    /// HTTP status of admin requests
+    pub status_code: Option<i16>,
+    pub transaction_hash: Option<String>,
+    pub details: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A privileged operation to record; who did it is taken from the audit context
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub action: AuditAction,
+    pub target: String,
+    pub before_state: Option<Value>,
+    pub after_state: Option<Value>,
+    pub status_code: Option<i16>,
+    pub transaction_hash: Option<String>,
+    pub details: Option<Value>,
+}
+
+impl NewAuditEntry {
+    /// An entry for an action on `target`, with nothing else recorded yet
+    pub fn new(action: AuditAction, target: impl Into<String>) -> Self {
+        Self {
+            action,
+            target: target.into(),
+            before_state: None,
+            after_state: None,
+            status_code: None,
+            transaction_hash: None,
+            details: None,
+        }
+    }
+
+    /// Records the state before and after the change
+    pub fn with_change<B: Serialize, A: Serialize>(mut self, before: Option<&B>, after: Option<&A>) -> Self {
+        self.before_state = before.and_then(|before| serde_json::to_value(before).ok());
+        self.after_state = after.and_then(|after| serde_json::to_value(after).ok());
+        self
+    }
+
+    /// Records the transaction the operation submitted
+    pub fn with_transaction(mut self, transaction_hash: Option<String>) -> Self {
+        self.transaction_hash = transaction_hash;
+        self
+    }
+
+    /// Records anything else worth knowing about the operation
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Audit log query parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    /// Entries whose target starts with this, e.g. `system_parameter:`
+    pub target: Option<String>,
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -2,11 +2,15 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 
+use crate::models::wallet::WalletAddress;
+
 /// User balance model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UserBalance {
     pub id: Uuid,
     pub user_id: Uuid,
+    /// Symbol of the asset the balance is held in
+    pub asset: String,
     pub active_balance: String,
     pub pending_deposits: String,
     pub pending_withdrawals: String,
@@ -28,4 +32,39 @@ pub struct UpdateUserBalanceRequest {
     pub total_withdrawn: Option<String>,
     pub total_rewards: Option<String>,
     pub last_reward_claim_timestamp: Option<DateTime<Utc>>,
-} 
\ No newline at end of file
+} 
+/// What caps the amount a wallet can withdraw
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalLimit {
+    /// Active balance not already claimed by pending withdrawals
+    Balance,
+    /// Contract balance and expected deposits not already promised to pending withdrawals
+    Liquidity,
+    /// What's left of the wallet's KYC level limit for the epoch
+    KycLimit,
+    /// What's left of the wallet's withdrawal volume limits
+    VolumeLimit,
+    /// The wallet hasn't completed KYC
+    KycRequired,
+    /// The epoch is closing and submissions are paused until the next one opens
+    EpochClosing,
+}
+
+/// How much a wallet could withdraw right now, in tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithdrawableBalance {
+    pub wallet_address: WalletAddress,
+    /// Symbol of the asset the amounts are in
+    pub asset: String,
+    pub active_balance: String,
+    pub pending_withdrawals: String,
+    /// Liquidity left for new withdrawals across the protocol
+    pub available_liquidity: String,
+    /// What's left of the KYC level's withdrawal limit this epoch, if the level has one
+    pub kyc_epoch_remaining: Option<String>,
+    /// What's left of the tightest withdrawal volume limit, if one applies
+    pub volume_remaining: Option<String>,
+    pub withdrawable: String,
+    pub limited_by: WithdrawalLimit,
+}
@@ -1,38 +1,82 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
+use std::fmt;
+use std::str::FromStr;
 
-/// Request types enum
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+use crate::models::request_status::RequestStatus;
+use crate::models::wallet::WalletAddress;
+
+/// Kind of a request, as the contract's events, the API and the database all name it
+///
+/// Stored and serialized by its lowercase name. SCALE encoding follows the contract's own
+/// `RequestType`, whose variant indices are pinned here, and the contract's capitalized variant
+/// names are accepted wherever a name is parsed.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, scale::Encode, scale::Decode, PartialEq, Eq, Hash)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum RequestType {
+    #[codec(index = 0)]
+    #[serde(alias = "Deposit")]
     Deposit,
+    #[codec(index = 1)]
+    #[serde(alias = "Withdrawal")]
     Withdrawal,
+    #[codec(index = 2)]
+    #[serde(alias = "Borrow")]
     Borrow,
 }
 
-impl ToString for RequestType {
-    fn to_string(&self) -> String {
+impl RequestType {
+    /// Variant name in the contract, as decoded contract events carry it
+    pub fn contract_name(&self) -> &'static str {
+        match self {
+            RequestType::Deposit => "Deposit",
+            RequestType::Withdrawal => "Withdrawal",
+            RequestType::Borrow => "Borrow",
+        }
+    }
+}
+
+impl fmt::Display for RequestType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            RequestType::Deposit => "deposit".to_string(),
-            RequestType::Withdrawal => "withdrawal".to_string(),
-            RequestType::Borrow => "borrow".to_string(),
+            RequestType::Deposit => write!(f, "deposit"),
+            RequestType::Withdrawal => write!(f, "withdrawal"),
+            RequestType::Borrow => write!(f, "borrow"),
+        }
+    }
+}
+
+impl FromStr for RequestType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "deposit" => Ok(RequestType::Deposit),
+            "withdrawal" => Ok(RequestType::Withdrawal),
+            "borrow" => Ok(RequestType::Borrow),
+            other => Err(anyhow::anyhow!("Unknown request type '{}'", other)),
         }
     }
 }
 
 /// Blockchain request model - mirrors on-chain request data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct BlockchainRequest {
     pub id: i32,
     pub request_type: RequestType,
     pub on_chain_id: i64,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub user_id: Option<Uuid>,
     pub amount: String,
     pub collateral_amount: Option<String>,
+    /// Symbol of the asset `amount` is denominated in
+    pub asset: String,
     pub submission_timestamp: DateTime<Utc>,
+    /// Whether the request is settled, i.e. `status` is executed
     pub is_processed: bool,
+    pub status: RequestStatus,
     pub block_number: i64,
     pub transaction_hash: String,
     pub created_at: DateTime<Utc>,
@@ -58,7 +102,7 @@ pub struct RequestProcessingEvent {
 pub struct RequestExecutionEvent {
     pub id: i32,
     pub request_id: i64,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub amount: String,
     pub transaction_hash: String,
     pub block_number: i64,
@@ -92,7 +136,7 @@ pub struct BatchProcessingItem {
 pub struct RecordBlockchainRequestDto {
     pub request_type: RequestType,
     pub on_chain_id: i64,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub amount: String,
     pub collateral_amount: Option<String>,
     pub block_number: i64,
@@ -113,22 +157,147 @@ pub struct RecordBatchProcessingDto {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RecordExecutionEventDto {
     pub request_id: i64,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub amount: String,
     pub transaction_hash: String,
     pub block_number: i64,
 }
 
+/// Filter of a user's request history
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RequestHistoryFilter {
+    pub request_type: Option<RequestType>,
+    pub status: Option<RequestStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Step of a request's history
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineStage {
+    /// Submission transaction included in a block
+    Submitted,
+    /// Request event indexed
+    Confirmed,
+    /// Included in an epoch's processing batch
+    Batched,
+    /// Settled on-chain
+    Executed,
+    Cancelled,
+    Expired,
+}
+
+/// One step of a request's history
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TimelineEntry {
+    pub stage: TimelineStage,
+    pub timestamp: DateTime<Utc>,
+    pub transaction_hash: Option<String>,
+    pub block_number: Option<i64>,
+    /// Epoch of the batch, for batched entries
+    pub epoch_id: Option<i32>,
+    /// How the batch handled the request (`included`, `processed` or `failed`), for batched
+    /// entries
+    pub batch_status: Option<String>,
+}
+
+/// A request with its history, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTimeline {
+    pub request: BlockchainRequest,
+    pub entries: Vec<TimelineEntry>,
+}
+
+/// Recorded field of a request that a correction can bring in line with the contract
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectableField {
+    Amount,
+    CollateralAmount,
+    /// Whether the request has been processed, as its status records it
+    Status,
+}
+
+/// A recorded field that differs from the contract's copy of the request
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FieldCorrection {
+    pub field: CorrectableField,
+    pub recorded: Option<String>,
+    /// Value the correction records
+    pub on_chain: String,
+}
+
+/// How a recorded request differs from the contract's copy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestCorrectionPreview {
+    pub request: BlockchainRequest,
+    /// Empty when the recorded request matches the contract
+    pub corrections: Vec<FieldCorrection>,
+}
+
+/// A correction that was applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestCorrection {
+    pub before: BlockchainRequest,
+    pub after: BlockchainRequest,
+    pub corrections: Vec<FieldCorrection>,
+    pub reason: String,
+    /// Audit log entry recording the correction
+    pub audit_entry_id: i64,
+}
+
+/// Correct a recorded request from the contract's copy
+///
+/// Only values read from the contract are ever written; `fields` must name exactly the fields
+/// that currently differ, so a correction reviewed against a stale preview is refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrectRequestPayload {
+    pub fields: Vec<CorrectableField>,
+    pub reason: String,
+}
+
 /// New blockchain request - used for creating a new request
 #[derive(Debug, Clone)]
 pub struct NewBlockchainRequest {
     pub request_type: RequestType,
     pub on_chain_id: i64,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub amount: f64,
     pub collateral_amount: Option<f64>,
     pub timestamp: chrono::NaiveDateTime,
-    pub is_processed: bool,
+    pub status: RequestStatus,
     pub block_number: i64,
     pub transaction_hash: String,
-} 
\ No newline at end of file
+    /// Symbol of the asset the amounts are in; the default asset when `None`
+    pub asset: Option<String>,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scale::{Decode, Encode};
+
+    const ALL: [RequestType; 3] = [RequestType::Deposit, RequestType::Withdrawal, RequestType::Borrow];
+
+    #[test]
+    fn every_name_parses_back() {
+        for request_type in ALL {
+            assert_eq!(request_type.to_string().parse::<RequestType>().unwrap(), request_type);
+            assert_eq!(request_type.contract_name().parse::<RequestType>().unwrap(), request_type);
+            assert_eq!(serde_json::to_value(&request_type).unwrap(), request_type.to_string());
+            assert_eq!(serde_json::from_value::<RequestType>(request_type.contract_name().into()).unwrap(), request_type);
+        }
+        assert!("repay".parse::<RequestType>().is_err());
+    }
+
+    #[test]
+    fn scale_indices_follow_the_contract() {
+        // Declaration order of the contract's `RequestType`
+        for (index, request_type) in ALL.into_iter().enumerate() {
+            assert_eq!(request_type.encode(), [index as u8]);
+            assert_eq!(RequestType::decode(&mut &[index as u8][..]).unwrap(), request_type);
+        }
+        assert!(RequestType::decode(&mut &[3u8][..]).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::balance::UserBalance;
+use super::blockchain_request::RequestType;
+use super::epoch::EpochStatus;
+use super::kyc::{KycLevel, KycVerification};
+use super::reward::UserReward;
+use super::user::{KycStatus, User};
+
+/// Where an unprocessed request is in the processing pipeline
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum OpenRequestStatus {
+    /// Waiting for the next processing batch
+    Pending,
+    /// Included in a processing batch that hasn't been confirmed yet
+    Batched,
+}
+
+/// Request of a wallet that hasn't been processed yet
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OpenRequest {
+    pub on_chain_id: i64,
+    pub request_type: RequestType,
+    pub amount: String,
+    pub collateral_amount: Option<String>,
+    pub status: OpenRequestStatus,
+    pub submission_timestamp: DateTime<Utc>,
+    pub transaction_hash: String,
+}
+
+/// Pending rewards a user can claim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimableRewards {
+    pub total: String,
+    pub rewards: Vec<UserReward>,
+}
+
+/// Active epoch with the time left until it closes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochCountdown {
+    pub id: i32,
+    pub status: EpochStatus,
+    pub start_timestamp: DateTime<Utc>,
+    pub closes_at: DateTime<Utc>,
+    /// Zero once the epoch is due to close
+    pub seconds_remaining: i64,
+}
+
+/// A user's KYC state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardKyc {
+    pub status: KycStatus,
+    /// Level of the most recently approved verification
+    pub approved_level: Option<KycLevel>,
+    pub latest_verification: Option<KycVerification>,
+}
+
+/// Everything a user dashboard shows, in one response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDashboard {
+    pub user: User,
+    /// `None` until the wallet's first deposit is recorded
+    pub balance: Option<UserBalance>,
+    /// Unprocessed requests, oldest first
+    pub open_requests: Vec<OpenRequest>,
+    pub claimable_rewards: ClaimableRewards,
+    /// `None` between epochs
+    pub current_epoch: Option<EpochCountdown>,
+    pub kyc: DashboardKyc,
+    pub generated_at: DateTime<Utc>,
+}
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::BigDecimal;
+
+use crate::models::amount::decimal_text;
+
+/// Where a deployment stands in the registry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeploymentStatus {
+    /// Recorded, but its on-chain code hasn't been verified against the build
+    Pending,
+    /// The contract services on the network use
+    Live,
+    /// Replaced by a later live deployment
+    Superseded,
+}
+
+/// A recorded contract deployment
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ContractDeployment {
+    pub id: i64,
+    /// Network name as configured by `CHAIN_NETWORK`
+    pub network: String,
+    pub chain_genesis_hash: String,
+    pub contract_address: String,
+    pub code_hash: String,
+    pub salt: String,
+    pub deployer: String,
+    pub transaction_hash: String,
+    pub block_number: i64,
+    pub gas_ref_time: i64,
+    pub gas_proof_size: i64,
+    /// Storage deposit charged for the instantiation, in base units
+    #[serde(with = "decimal_text")]
+    pub storage_deposit: BigDecimal,
+    /// Whether the on-chain code hash matched the local build
+    pub verified: bool,
+    pub status: DeploymentStatus,
+    pub created_at: DateTime<Utc>,
+    /// Last change of status or, after an upgrade, of code hash
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A deployment to record
+#[derive(Debug, Clone)]
+pub struct NewContractDeployment {
+    pub network: String,
+    pub chain_genesis_hash: String,
+    pub contract_address: String,
+    pub code_hash: String,
+    pub salt: String,
+    pub deployer: String,
+    pub transaction_hash: String,
+    pub block_number: i64,
+    pub gas_ref_time: i64,
+    pub gas_proof_size: i64,
+    pub storage_deposit: BigDecimal,
+    pub verified: bool,
+}
+
+/// Query parameters for listing deployments
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeploymentFilter {
+    pub network: Option<String>,
+    pub status: Option<DeploymentStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A recorded upgrade of a deployed contract's code
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ContractVersion {
+    pub id: i64,
+    /// Network name as configured by `CHAIN_NETWORK`
+    pub network: String,
+    pub contract_address: String,
+    pub previous_code_hash: String,
+    pub code_hash: String,
+    /// `None` when the code had already been uploaded
+    pub upload_transaction_hash: Option<String>,
+    pub schedule_transaction_hash: String,
+    /// When the timelock allowed the upgrade, in chain time, as estimated when it was scheduled
+    pub executable_at: DateTime<Utc>,
+    pub upgrade_transaction_hash: String,
+    pub block_number: i64,
+    pub upgraded_by: String,
+    pub migration_notes: String,
+    /// Whether the on-chain code hash matched the new build after the upgrade
+    pub verified: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An upgrade to record
+#[derive(Debug, Clone)]
+pub struct NewContractVersion {
+    pub network: String,
+    pub contract_address: String,
+    pub previous_code_hash: String,
+    pub code_hash: String,
+    pub upload_transaction_hash: Option<String>,
+    pub schedule_transaction_hash: String,
+    pub executable_at: DateTime<Utc>,
+    pub upgrade_transaction_hash: String,
+    pub block_number: i64,
+    pub upgraded_by: String,
+    pub migration_notes: String,
+    pub verified: bool,
+}
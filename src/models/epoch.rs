@@ -1,13 +1,18 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use std::fmt;
 
 /// Epoch status enum
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
 pub enum EpochStatus {
+    #[default]
+    #[serde(alias = "active")]
     Active,
+    #[serde(alias = "processing")]
     Processing,
+    #[serde(alias = "completed")]
     Completed,
 }
 
@@ -21,14 +26,8 @@ impl fmt::Display for EpochStatus {
     }
 }
 
-impl Default for EpochStatus {
-    fn default() -> Self {
-        Self::Active
-    }
-}
-
 /// Epoch model - tracks epoch lifecycle
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Epoch {
     pub id: i32,
     pub start_timestamp: DateTime<Utc>,
@@ -40,14 +39,83 @@ pub struct Epoch {
     pub updated_at: DateTime<Utc>,
 }
 
-/// Epoch summary model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An epoch as the API reports it, whether read from the contract, the database or both
+///
+/// The contract knows how many requests it processed, the database how far processing got and
+/// the transaction that did it. Each source maps into this; [`EpochSummary::with_record`] lays a
+/// database record over what the contract reported.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct EpochSummary {
-    pub id: i32,
+    pub id: u128,
     pub status: EpochStatus,
     pub start_timestamp: DateTime<Utc>,
     pub end_timestamp: Option<DateTime<Utc>>,
+    /// Seconds from start to end, once the epoch has ended
+    pub duration_secs: Option<i64>,
+    pub processed_deposit_count: u32,
+    pub processed_withdrawal_count: u32,
+    pub processed_borrow_count: u32,
+    pub processing_tx_hash: Option<String>,
     pub processed_at: Option<DateTime<Utc>>,
+    /// Share of the configured epoch duration that has passed, while the epoch is active
+    pub progress_percent: Option<f64>,
+}
+
+impl EpochSummary {
+    /// The epoch as the database records it, with the counts of its processing run if it has one
+    pub fn from_record(epoch: &Epoch, run: Option<&EpochProcessingRun>) -> Self {
+        let count = |processed: fn(&EpochProcessingRun) -> i32| {
+            run.map(processed).and_then(|count| u32::try_from(count).ok()).unwrap_or(0)
+        };
+
+        Self {
+            // Epoch IDs are serial, so never negative
+            id: u128::try_from(epoch.id).unwrap_or_default(),
+            status: epoch.status.clone(),
+            start_timestamp: epoch.start_timestamp,
+            end_timestamp: epoch.end_timestamp,
+            duration_secs: epoch.end_timestamp.map(|end| (end - epoch.start_timestamp).num_seconds()),
+            processed_deposit_count: count(|run| run.deposits_processed),
+            processed_withdrawal_count: count(|run| run.withdrawals_processed),
+            processed_borrow_count: count(|run| run.borrows_processed),
+            processing_tx_hash: epoch.processing_tx_hash.clone(),
+            processed_at: epoch.processed_at,
+            progress_percent: None,
+        }
+    }
+
+    /// Takes the status and processing details from the epoch's database record, which tracks
+    /// processing past the on-chain close
+    pub fn with_record(self, epoch: &Epoch) -> Self {
+        Self {
+            status: epoch.status.clone(),
+            processing_tx_hash: epoch.processing_tx_hash.clone(),
+            processed_at: epoch.processed_at,
+            ..self
+        }
+    }
+
+    /// Sets how far an active epoch is through `epoch_duration` at `now`, as a percentage
+    /// rounded to two decimals. Epochs run past their duration until they're closed, so this
+    /// stops at 100.
+    pub fn with_progress(self, epoch_duration: Duration, now: DateTime<Utc>) -> Self {
+        let duration_ms = epoch_duration.num_milliseconds();
+        if self.status != EpochStatus::Active || duration_ms <= 0 {
+            return Self { progress_percent: None, ..self };
+        }
+
+        let elapsed_ms = (now - self.start_timestamp).num_milliseconds().max(0);
+        let percent = (elapsed_ms as f64 / duration_ms as f64 * 100.0).min(100.0);
+        Self { progress_percent: Some((percent * 100.0).round() / 100.0), ..self }
+    }
+}
+
+/// Filter and page of an epoch listing
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EpochFilter {
+    pub status: Option<EpochStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
 
 /// Update epoch status request
@@ -67,4 +135,102 @@ pub struct ProcessEpochResult {
     pub deposits_processed: i32,
     pub withdrawals_processed: i32,
     pub borrows_processed: i32,
-} 
\ No newline at end of file
+}
+
+/// State of an epoch's close sequence
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EpochProcessingStatus {
+    Running,
+    Completed,
+    /// Stopped at its current step; starting it again resumes from there
+    Failed,
+}
+
+/// Steps of an epoch's close sequence, in the order they run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, PartialOrd, Ord)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EpochProcessingStep {
+    /// New submissions are refused until the epoch is closed on-chain
+    ClosingSubmissions,
+    ProcessingDeposits,
+    ProcessingWithdrawals,
+    ClosingEpoch,
+    /// Calculates depositor rewards and accrues borrower interest
+    CalculatingRewards,
+    SnapshottingStats,
+    Completed,
+}
+
+/// Protocol totals captured when an epoch finishes processing
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EpochStatsSnapshot {
+    pub total_value_locked: String,
+    pub active_depositors: i64,
+    pub pending_withdrawals: String,
+    pub deposit_volume: String,
+    pub withdrawal_volume: String,
+    pub rewards_distributed: String,
+}
+
+/// Progress of an epoch's close sequence
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EpochProcessingRun {
+    pub epoch_id: i32,
+    pub status: EpochProcessingStatus,
+    /// Step that is running, or that failed
+    pub step: EpochProcessingStep,
+    pub deposits_processed: i32,
+    pub withdrawals_processed: i32,
+    pub borrows_processed: i32,
+    /// Transaction that closed the epoch on-chain
+    pub close_tx_hash: Option<String>,
+    /// Epoch opened for new submissions once this one closed
+    pub next_epoch_id: Option<i32>,
+    pub stats: Option<Json<EpochStatsSnapshot>>,
+    pub result: Option<Json<ProcessEpochResult>>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(status: EpochStatus, start_timestamp: DateTime<Utc>) -> EpochSummary {
+        EpochSummary {
+            id: 1,
+            status,
+            start_timestamp,
+            end_timestamp: None,
+            duration_secs: None,
+            processed_deposit_count: 0,
+            processed_withdrawal_count: 0,
+            processed_borrow_count: 0,
+            processing_tx_hash: None,
+            processed_at: None,
+            progress_percent: None,
+        }
+    }
+
+    #[test]
+    fn active_epochs_report_their_progress() {
+        let now = Utc::now();
+        let week = Duration::days(7);
+
+        let started = summary(EpochStatus::Active, now - Duration::hours(42));
+        assert_eq!(started.clone().with_progress(week, now).progress_percent, Some(25.0));
+        let third = summary(EpochStatus::Active, now - Duration::hours(56));
+        assert_eq!(third.with_progress(week, now).progress_percent, Some(33.33));
+        let overdue = summary(EpochStatus::Active, now - Duration::days(8));
+        assert_eq!(overdue.with_progress(week, now).progress_percent, Some(100.0));
+
+        let closed = summary(EpochStatus::Processing, now - Duration::hours(42));
+        assert_eq!(closed.with_progress(week, now).progress_percent, None);
+        assert_eq!(started.with_progress(Duration::zero(), now).progress_percent, None);
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::blockchain_request::RequestType;
+
+/// Version of the message schema, bumped on changes consumers have to adapt to. Adding fields
+/// is not such a change.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Message published on the event bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusMessage {
+    pub schema_version: u32,
+    /// Identifies the event; a republished event keeps its id, so consumers can deduplicate
+    pub id: String,
+    /// When the event happened
+    pub occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: BusEvent,
+}
+
+/// Event carried by a bus message, tagged by `event_type` with its fields under `data`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event_type", content = "data", rename_all = "snake_case")]
+pub enum BusEvent {
+    /// A contract event decoded by the indexer
+    ChainEvent(ChainEventData),
+    /// A deposit, withdrawal or borrow request was processed
+    RequestProcessed(RequestProcessedData),
+    /// A user was credited an epoch reward
+    RewardCreated(RewardCreatedData),
+}
+
+impl BusEvent {
+    /// Topic (Kafka) or subject (NATS) suffix the event is published on
+    pub fn topic(&self) -> &'static str {
+        match self {
+            BusEvent::ChainEvent(_) => "chain_events",
+            BusEvent::RequestProcessed(_) => "requests",
+            BusEvent::RewardCreated(_) => "rewards",
+        }
+    }
+
+    /// Partition key; events about the same account are kept in order
+    pub fn key(&self) -> String {
+        match self {
+            BusEvent::ChainEvent(data) => data.wallet_address.clone().unwrap_or_else(|| data.transaction_hash.clone()),
+            BusEvent::RequestProcessed(data) => data.wallet_address.clone(),
+            BusEvent::RewardCreated(data) => data.wallet_address.clone(),
+        }
+    }
+}
+
+/// Contract event as decoded by the indexer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainEventData {
+    /// Event name in snake case, e.g. `deposit_request`
+    pub event: String,
+    pub block_number: u64,
+    pub transaction_hash: String,
+    /// On-chain request id, as a string since it can exceed 64 bits
+    pub request_id: Option<String>,
+    pub wallet_address: Option<String>,
+    pub amount: Option<String>,
+    pub request_type: Option<RequestType>,
+}
+
+/// Request that was settled off-chain after being processed on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestProcessedData {
+    pub request_type: RequestType,
+    pub on_chain_id: i64,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    pub amount: String,
+    /// Epoch whose batch processed the request, for requests processed at epoch close
+    pub epoch_id: Option<i32>,
+    /// Transaction that processed the request
+    pub transaction_hash: String,
+}
+
+/// Reward credited to a user at epoch close
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardCreatedData {
+    pub epoch_id: i32,
+    pub user_id: Uuid,
+    pub wallet_address: String,
+    pub amount: String,
+    pub apr_bps: i32,
+}
@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::types::Uuid;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Feature flags checked by the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureFlag {
+    /// Accepting borrow requests
+    Borrows,
+    /// Time-weighted epoch rewards; when off, rewards are paid on closing balances
+    NewRewardEngine,
+    /// Adding approved wallets to the contract's KYC allowlist
+    OnChainKycGating,
+}
+
+impl FeatureFlag {
+    /// Every flag, in display order
+    pub const ALL: [FeatureFlag; 3] = [FeatureFlag::Borrows, FeatureFlag::NewRewardEngine, FeatureFlag::OnChainKycGating];
+
+    /// Name the flag is stored and addressed under
+    pub fn name(self) -> &'static str {
+        match self {
+            FeatureFlag::Borrows => "borrows",
+            FeatureFlag::NewRewardEngine => "new_reward_engine",
+            FeatureFlag::OnChainKycGating => "onchain_kyc_gating",
+        }
+    }
+
+    /// State of a flag with no stored row in the environment
+    pub fn default_enabled(self) -> bool {
+        match self {
+            FeatureFlag::Borrows | FeatureFlag::NewRewardEngine | FeatureFlag::OnChainKycGating => true,
+        }
+    }
+
+    /// What the flag controls, shown until a description is stored
+    pub fn default_description(self) -> &'static str {
+        match self {
+            FeatureFlag::Borrows => "Accept borrow requests",
+            FeatureFlag::NewRewardEngine => "Calculate epoch rewards from time-weighted balances rather than closing balances",
+            FeatureFlag::OnChainKycGating => "Add approved KYC verifications to the contract allowlist",
+        }
+    }
+}
+
+impl fmt::Display for FeatureFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FeatureFlag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FeatureFlag::ALL
+            .into_iter()
+            .find(|flag| flag.name() == s)
+            .ok_or_else(|| format!("Unknown feature flag '{}'", s))
+    }
+}
+
+/// A flag's state in one environment
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FeatureFlagState {
+    pub name: String,
+    pub environment: String,
+    pub enabled: bool,
+    /// Share of wallets the flag is on for, in percent
+    pub rollout_percentage: i16,
+    pub description: Option<String>,
+    pub updated_by: Option<Uuid>,
+    /// `None` while the flag has its default state
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Update feature flag request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateFeatureFlagRequest {
+    /// Environment to change the flag in; defaults to the one the server runs in
+    pub environment: Option<String>,
+    pub enabled: Option<bool>,
+    /// Share of wallets the flag is on for, 0 to 100
+    pub rollout_percentage: Option<i16>,
+    pub description: Option<String>,
+    pub updated_by: Option<Uuid>,
+}
+
+/// Feature flag filter
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeatureFlagFilter {
+    /// Defaults to the environment the server runs in
+    pub environment: Option<String>,
+}
+
+/// Wallet to evaluate flags for
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeatureFlagQuery {
+    pub wallet: Option<String>,
+}
+
+/// Rollout of one flag
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FlagRollout {
+    pub enabled: bool,
+    pub rollout_percentage: i16,
+}
+
+/// Effective flags in one environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeatureFlags {
+    flags: HashMap<String, FlagRollout>,
+}
+
+impl FeatureFlags {
+    /// Flags from their stored states; flags without one keep their defaults
+    pub fn from_states(states: &[FeatureFlagState]) -> Self {
+        Self {
+            flags: states
+                .iter()
+                .map(|state| {
+                    let rollout = FlagRollout { enabled: state.enabled, rollout_percentage: state.rollout_percentage };
+                    (state.name.clone(), rollout)
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether a flag is on for everyone. A flag rolled out to only part of the wallets is off
+    /// for checks that aren't about a wallet.
+    pub fn is_enabled(&self, flag: FeatureFlag) -> bool {
+        match self.flags.get(flag.name()) {
+            Some(rollout) => rollout.enabled && rollout.rollout_percentage >= 100,
+            None => flag.default_enabled(),
+        }
+    }
+
+    /// Whether a flag is on for a wallet. Each wallet falls in a fixed bucket per flag, so
+    /// raising the rollout percentage only ever adds wallets.
+    pub fn is_enabled_for(&self, flag: FeatureFlag, wallet: &str) -> bool {
+        match self.flags.get(flag.name()) {
+            Some(rollout) => rollout.enabled && i16::from(rollout_bucket(flag, wallet)) < rollout.rollout_percentage,
+            None => flag.default_enabled(),
+        }
+    }
+}
+
+/// Bucket from 0 to 99 a wallet falls in for a flag
+fn rollout_bucket(flag: FeatureFlag, wallet: &str) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", flag.name(), wallet).as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(flag: FeatureFlag, enabled: bool, rollout_percentage: i16) -> FeatureFlagState {
+        FeatureFlagState {
+            name: flag.name().to_string(),
+            environment: "production".to_string(),
+            enabled,
+            rollout_percentage,
+            description: None,
+            updated_by: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn unstored_flags_use_their_defaults() {
+        let flags = FeatureFlags::from_states(&[state(FeatureFlag::Borrows, false, 100)]);
+
+        assert!(!flags.is_enabled(FeatureFlag::Borrows));
+        assert!(!flags.is_enabled_for(FeatureFlag::Borrows, "5Grwva"));
+        assert!(flags.is_enabled(FeatureFlag::NewRewardEngine));
+    }
+
+    #[test]
+    fn partial_rollouts_cover_a_stable_share_of_wallets() {
+        let half = FeatureFlags::from_states(&[state(FeatureFlag::Borrows, true, 50)]);
+        let more = FeatureFlags::from_states(&[state(FeatureFlag::Borrows, true, 80)]);
+        let wallets: Vec<String> = (0..1000).map(|i| format!("wallet-{}", i)).collect();
+
+        let enabled = wallets.iter().filter(|wallet| half.is_enabled_for(FeatureFlag::Borrows, wallet)).count();
+        assert!((400..600).contains(&enabled), "{} of 1000 wallets enabled at 50%", enabled);
+        assert!(wallets
+            .iter()
+            .filter(|wallet| half.is_enabled_for(FeatureFlag::Borrows, wallet))
+            .all(|wallet| more.is_enabled_for(FeatureFlag::Borrows, wallet)));
+        assert!(!half.is_enabled(FeatureFlag::Borrows));
+    }
+}
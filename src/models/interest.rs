@@ -0,0 +1,69 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use uuid::Uuid;
+
+/// Interest accrued by a borrow position over one epoch
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InterestAccrual {
+    pub id: i64,
+    /// On-chain borrow request ID
+    pub request_id: i64,
+    pub epoch_id: i32,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    pub principal: String,
+    pub rate_bps: i32,
+    /// Part of the epoch the position was open for
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub interest: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A borrow position's line on a debt statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebtStatementPosition {
+    /// On-chain borrow request ID
+    pub request_id: i64,
+    pub principal: String,
+    pub borrowed_at: DateTime<Utc>,
+    /// `open`, or `liquidated` when the position was liquidated by the end of the period
+    pub status: String,
+    pub opening_debt: String,
+    pub new_borrow: String,
+    pub interest_accrued: String,
+    pub liquidated_debt: String,
+    pub closing_debt: String,
+}
+
+/// Monthly debt statement of a borrower
+///
+/// Debt is principal plus accrued interest. Interest is counted in the month its epoch ended; an
+/// epoch ending at midnight on the first counts towards the month before.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DebtStatement {
+    pub id: i64,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    /// First day of the month
+    pub period_start: NaiveDate,
+    /// First day of the following month
+    pub period_end: NaiveDate,
+    pub opening_debt: String,
+    pub new_borrows: String,
+    pub interest_accrued: String,
+    pub liquidated_debt: String,
+    pub closing_debt: String,
+    pub positions: Json<Vec<DebtStatementPosition>>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Format a debt statement is downloaded in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    #[default]
+    Json,
+    Csv,
+}
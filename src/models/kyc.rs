@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Uuid;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::user::KycStatus;
+
+/// KYC provider enum
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KycProvider {
+    SumSub,
+    Onfido,
+    Persona,
+    Shufti,
+    /// Manual review by the operator
+    Internal,
+}
+
+impl fmt::Display for KycProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycProvider::SumSub => write!(f, "sumsub"),
+            KycProvider::Onfido => write!(f, "onfido"),
+            KycProvider::Persona => write!(f, "persona"),
+            KycProvider::Shufti => write!(f, "shufti"),
+            KycProvider::Internal => write!(f, "internal"),
+        }
+    }
+}
+
+impl FromStr for KycProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sumsub" => Ok(KycProvider::SumSub),
+            "onfido" => Ok(KycProvider::Onfido),
+            "persona" => Ok(KycProvider::Persona),
+            "shufti" | "shuftipro" => Ok(KycProvider::Shufti),
+            "internal" => Ok(KycProvider::Internal),
+            other => Err(anyhow::anyhow!("Unknown KYC provider '{}'", other)),
+        }
+    }
+}
+
+/// Verification depth a user has been checked to
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KycLevel {
+    #[default]
+    Basic,
+    Advanced,
+    Full,
+}
+
+impl fmt::Display for KycLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycLevel::Basic => write!(f, "basic"),
+            KycLevel::Advanced => write!(f, "advanced"),
+            KycLevel::Full => write!(f, "full"),
+        }
+    }
+}
+
+impl FromStr for KycLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "basic" => Ok(KycLevel::Basic),
+            "advanced" => Ok(KycLevel::Advanced),
+            "full" => Ok(KycLevel::Full),
+            other => Err(anyhow::anyhow!("Unknown KYC level '{}'", other)),
+        }
+    }
+}
+
+/// Progress of an approved verification onto the contract's KYC allowlist
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KycOnchainSyncStatus {
+    /// Waiting to be submitted, or for a retry
+    Pending,
+    Synced,
+    /// Gave up after the maximum number of attempts
+    Failed,
+}
+
+/// KYC verification model - one attempt with a provider
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct KycVerification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: KycProvider,
+    pub level: KycLevel,
+    pub applicant_id: Option<String>,
+    pub status: KycStatus,
+    pub review_answer: Option<String>,
+    pub rejection_reasons: Vec<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Set once the verification is approved
+    pub onchain_sync_status: Option<KycOnchainSyncStatus>,
+    /// Transaction that added the wallet to the contract's KYC allowlist
+    pub onchain_tx_hash: Option<String>,
+    pub onchain_synced_at: Option<DateTime<Utc>>,
+    pub onchain_sync_attempts: i32,
+    pub onchain_sync_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Approved verification waiting to be synced on-chain
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PendingKycSync {
+    pub verification_id: Uuid,
+    pub wallet_address: String,
+    pub onchain_sync_attempts: i32,
+}
+
+/// Start verification request data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateKycVerificationRequest {
+    #[serde(default)]
+    pub level: KycLevel,
+    pub email: Option<String>,
+    /// ISO 3166-1 alpha-3 country code
+    pub country: Option<String>,
+}
+
+/// Stored KYC provider webhook
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct KycWebhookEvent {
+    pub id: Uuid,
+    pub provider: KycProvider,
+    pub event_type: String,
+    pub applicant_id: String,
+    pub verification_id: Option<Uuid>,
+    pub payload: Value,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Kind of identity document uploaded for manual review
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum KycDocumentType {
+    Passport,
+    IdCard,
+    DrivingLicense,
+    ResidencePermit,
+    ProofOfAddress,
+    Selfie,
+}
+
+impl FromStr for KycDocumentType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "passport" => Ok(KycDocumentType::Passport),
+            "id_card" => Ok(KycDocumentType::IdCard),
+            "driving_license" => Ok(KycDocumentType::DrivingLicense),
+            "residence_permit" => Ok(KycDocumentType::ResidencePermit),
+            "proof_of_address" => Ok(KycDocumentType::ProofOfAddress),
+            "selfie" => Ok(KycDocumentType::Selfie),
+            other => Err(anyhow::anyhow!("Unknown document type '{}'", other)),
+        }
+    }
+}
+
+/// Side of a two-sided document
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KycDocumentSide {
+    Front,
+    Back,
+}
+
+impl FromStr for KycDocumentSide {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "front" => Ok(KycDocumentSide::Front),
+            "back" => Ok(KycDocumentSide::Back),
+            other => Err(anyhow::anyhow!("Unknown document side '{}'", other)),
+        }
+    }
+}
+
+/// Identity document uploaded for a verification; the file itself is in object storage
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct KycDocument {
+    pub id: Uuid,
+    pub verification_id: Uuid,
+    pub document_type: KycDocumentType,
+    pub side: Option<KycDocumentSide>,
+    pub file_name: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+    /// Hex SHA-256 of the file, for integrity checks by reviewers
+    pub sha256: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Document to record for a verification once its file is stored
+#[derive(Debug, Clone)]
+pub struct NewKycDocument {
+    pub id: Uuid,
+    pub verification_id: Uuid,
+    pub document_type: KycDocumentType,
+    pub side: Option<KycDocumentSide>,
+    pub file_name: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub storage_key: String,
+}
+
+/// Document with a time-limited download link for reviewers
+#[derive(Debug, Clone, Serialize)]
+pub struct KycDocumentLink {
+    #[serde(flatten)]
+    pub document: KycDocument,
+    pub url: String,
+    pub url_expires_at: DateTime<Utc>,
+}
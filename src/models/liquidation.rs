@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use std::fmt;
+use uuid::Uuid;
+
+/// Where a flagged borrow stands
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationStatus {
+    /// Under the threshold, waiting out the grace period
+    AtRisk,
+    /// Back above the threshold before the grace period ended
+    Recovered,
+    /// Liquidated on-chain
+    Liquidated,
+}
+
+impl fmt::Display for LiquidationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiquidationStatus::AtRisk => write!(f, "at_risk"),
+            LiquidationStatus::Recovered => write!(f, "recovered"),
+            LiquidationStatus::Liquidated => write!(f, "liquidated"),
+        }
+    }
+}
+
+/// Something the liquidation monitor did to a position
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationActionType {
+    Flagged,
+    Notified,
+    Recovered,
+    Liquidated,
+    LiquidationFailed,
+}
+
+impl fmt::Display for LiquidationActionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiquidationActionType::Flagged => write!(f, "flagged"),
+            LiquidationActionType::Notified => write!(f, "notified"),
+            LiquidationActionType::Recovered => write!(f, "recovered"),
+            LiquidationActionType::Liquidated => write!(f, "liquidated"),
+            LiquidationActionType::LiquidationFailed => write!(f, "liquidation_failed"),
+        }
+    }
+}
+
+/// Open borrow position with its collateral ratio at the current price
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BorrowPosition {
+    /// On-chain borrow request ID
+    pub request_id: i64,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    pub borrow_amount: String,
+    pub collateral_amount: String,
+    pub collateral_ratio_bps: i32,
+    /// Liquidation record of the position, if it has been flagged before
+    pub liquidation_id: Option<i64>,
+    pub liquidation_status: Option<LiquidationStatus>,
+    pub grace_ends_at: Option<DateTime<Utc>>,
+    pub notified_at: Option<DateTime<Utc>>,
+}
+
+/// Entry in a position's liquidation log
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LiquidationAction {
+    pub id: i64,
+    pub action: LiquidationActionType,
+    pub collateral_ratio_bps: Option<i32>,
+    pub details: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Liquidation model - a borrow that fell under the liquidation threshold
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Liquidation {
+    pub id: i64,
+    /// On-chain borrow request ID
+    pub request_id: i64,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    pub status: LiquidationStatus,
+    pub borrow_amount: String,
+    pub collateral_amount: String,
+    /// Collateral price at the last check
+    pub collateral_price: String,
+    /// Collateral ratio at the last check
+    pub collateral_ratio_bps: i32,
+    pub flagged_at: DateTime<Utc>,
+    pub grace_ends_at: DateTime<Utc>,
+    pub notified_at: Option<DateTime<Utc>>,
+    pub liquidated_at: Option<DateTime<Utc>>,
+    pub liquidation_tx_hash: Option<String>,
+    /// Failed on-chain liquidation attempts
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Actions taken on the position, oldest first
+    pub actions: Json<Vec<LiquidationAction>>,
+}
+
+/// Filters for listing liquidations
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LiquidationFilter {
+    pub status: Option<LiquidationStatus>,
+    pub wallet_address: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Amounts and counts of requests that haven't been processed yet
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PendingRequestTotals {
+    pub deposit_total: String,
+    pub deposit_count: i64,
+    pub withdrawal_total: String,
+    pub withdrawal_count: i64,
+}
+
+/// Whether pending withdrawals can be paid out of the contract balance and expected deposits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidityReport {
+    /// Free balance of the contract account
+    pub contract_balance: String,
+    /// Pending deposits, which are processed before withdrawals in an epoch close
+    pub expected_deposit_inflows: String,
+    pub pending_deposit_count: i64,
+    /// Contract balance plus expected deposit inflows
+    pub available_liquidity: String,
+    pub pending_withdrawals: String,
+    pub pending_withdrawal_count: i64,
+    /// Amount by which pending withdrawals exceed available liquidity, zero when covered
+    pub shortfall: String,
+    pub sufficient: bool,
+    pub generated_at: DateTime<Utc>,
+}
@@ -1,7 +1,32 @@
+pub mod accounting;
 pub mod activity_log;
+pub mod alert;
+pub mod amount;
+pub mod asset;
+pub mod archive;
+pub mod audit;
 pub mod balance;
 pub mod blockchain_request;
+pub mod dashboard;
+pub mod deployment;
 pub mod epoch;
+pub mod event_bus;
+pub mod feature_flag;
+pub mod interest;
+pub mod kyc;
+pub mod liquidation;
+pub mod liquidity;
+pub mod notification;
+pub mod receipt;
+pub mod referral;
+pub mod request_status;
 pub mod reward;
+pub mod risk;
+pub mod screening;
+pub mod stats;
 pub mod system_parameter;
+pub mod treasury;
 pub mod user;
+pub mod user_limit;
+pub mod wallet;
+pub mod webhook;
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use std::fmt;
+use std::str::FromStr;
+
+/// Lifecycle events users are emailed about
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationType {
+    DepositProcessed,
+    WithdrawalExecutable,
+    KycApproved,
+    KycRejected,
+    RewardsAvailable,
+}
+
+impl NotificationType {
+    /// Every notification type, in the order preferences are listed
+    pub const ALL: [NotificationType; 5] = [
+        NotificationType::DepositProcessed,
+        NotificationType::WithdrawalExecutable,
+        NotificationType::KycApproved,
+        NotificationType::KycRejected,
+        NotificationType::RewardsAvailable,
+    ];
+}
+
+impl fmt::Display for NotificationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationType::DepositProcessed => write!(f, "deposit_processed"),
+            NotificationType::WithdrawalExecutable => write!(f, "withdrawal_executable"),
+            NotificationType::KycApproved => write!(f, "kyc_approved"),
+            NotificationType::KycRejected => write!(f, "kyc_rejected"),
+            NotificationType::RewardsAvailable => write!(f, "rewards_available"),
+        }
+    }
+}
+
+impl FromStr for NotificationType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deposit_processed" => Ok(NotificationType::DepositProcessed),
+            "withdrawal_executable" => Ok(NotificationType::WithdrawalExecutable),
+            "kyc_approved" => Ok(NotificationType::KycApproved),
+            "kyc_rejected" => Ok(NotificationType::KycRejected),
+            "rewards_available" => Ok(NotificationType::RewardsAvailable),
+            other => Err(format!("Unknown notification type '{}'", other)),
+        }
+    }
+}
+
+/// Email delivery status enum
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmailStatus {
+    #[default]
+    Pending,
+    Sent,
+    Failed,
+}
+
+/// Whether a user receives one type of notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationPreference {
+    pub notification_type: NotificationType,
+    pub enabled: bool,
+}
+
+/// Changes some of a user's notification preferences, leaving the rest unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub preferences: Vec<NotificationPreference>,
+}
+
+/// Email delivery log entry
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EmailNotification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub notification_type: String,
+    pub recipient: String,
+    pub subject: String,
+    #[serde(skip_serializing)]
+    pub body: String,
+    pub dedupe_key: String,
+    pub status: EmailStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    /// Message id assigned by the email provider
+    pub provider_message_id: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Email delivery log query parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmailNotificationFilter {
+    pub user_id: Option<Uuid>,
+    pub status: Option<EmailStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
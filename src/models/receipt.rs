@@ -0,0 +1,74 @@
+//! Signed receipts of executed requests
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::blockchain_request::{RequestType, TimelineStage};
+use crate::models::request_status::RequestStatus;
+use crate::models::wallet::WalletAddress;
+
+/// Version of the receipt format, raised whenever its fields change
+pub const RECEIPT_VERSION: u32 = 1;
+
+/// Signature scheme of receipts
+pub const RECEIPT_ALGORITHM: &str = "sr25519";
+
+/// A transaction that moved a request along
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReceiptTransaction {
+    pub stage: TimelineStage,
+    pub transaction_hash: String,
+    pub block_number: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+    /// Epoch of the batch, for batched transactions
+    pub epoch_id: Option<i32>,
+}
+
+/// What a receipt attests to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Receipt {
+    pub version: u32,
+    pub request_id: i64,
+    pub request_type: RequestType,
+    pub wallet_address: WalletAddress,
+    /// Amount in tokens of `asset`
+    pub amount: String,
+    pub collateral_amount: Option<String>,
+    pub asset: String,
+    pub status: RequestStatus,
+    pub submitted_at: DateTime<Utc>,
+    pub executed_at: Option<DateTime<Utc>>,
+    /// Submission, batch and execution transactions, oldest first
+    pub transactions: Vec<ReceiptTransaction>,
+    pub issued_at: DateTime<Utc>,
+}
+
+/// A key receipts are signed with
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReceiptSigner {
+    /// `0x`-prefixed hex public key
+    pub public_key: String,
+    pub address: WalletAddress,
+}
+
+/// A receipt and its signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedReceipt {
+    pub receipt: Receipt,
+    /// The receipt's JSON text exactly as signed
+    pub payload: String,
+    pub algorithm: String,
+    /// `0x`-prefixed hex signature of `<Bytes>{payload}</Bytes>`
+    pub signature: String,
+    pub signer: ReceiptSigner,
+}
+
+/// Keys receipts can be verified with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptKeys {
+    pub algorithm: String,
+    /// Key new receipts are signed with
+    pub active: ReceiptSigner,
+    /// Key replaced at the last rotation, which receipts issued before it were signed with
+    pub previous: Option<ReceiptSigner>,
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::wallet::WalletAddress;
+
+/// What a referrer has earned from one referee
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RefereeEarnings {
+    pub wallet_address: WalletAddress,
+    pub referred_at: DateTime<Utc>,
+    /// Epochs in which the referee's rewards paid a bonus
+    pub epochs_paid: i64,
+    pub total_earned: String,
+}
+
+/// A wallet's referrals and the bonuses they have earned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferralSummary {
+    pub wallet_address: WalletAddress,
+    /// Wallet that referred this one, if any
+    pub referred_by: Option<WalletAddress>,
+    pub total_earned: String,
+    pub referees: Vec<RefereeEarnings>,
+}
@@ -0,0 +1,137 @@
+//! Request lifecycle
+//!
+//! A request is `submitted` once its transaction is in a block, `confirmed` when the indexer sees
+//! its event, `processed` when an epoch batch includes it and `executed` once it is settled.
+//! Open requests can also end `cancelled` or `expired`. Requests only move forward: indexed
+//! events can skip steps the indexer missed, but nothing leaves a final status. Every change of
+//! status, in the database or in the API's view of the chain, is checked here.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+/// Where a request is in its lifecycle
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RequestStatus {
+    /// Transaction included in a block, event not indexed yet
+    #[default]
+    Submitted,
+    /// Event indexed
+    Confirmed,
+    /// Included in an epoch's processing batch
+    Processed,
+    /// Settled
+    Executed,
+    /// Called off before it was settled
+    Cancelled,
+    /// Left unsettled past its deadline
+    Expired,
+}
+
+/// A status change the lifecycle doesn't allow
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("A {from} request can't become {to}")]
+pub struct IllegalTransition {
+    pub from: RequestStatus,
+    pub to: RequestStatus,
+}
+
+/// Outcome of moving a request to a status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusChange {
+    /// The request moved from this status
+    Moved(RequestStatus),
+    /// The request already had the status, e.g. when an event is replayed
+    Unchanged,
+}
+
+impl RequestStatus {
+    /// Every status, in lifecycle order
+    pub const ALL: [RequestStatus; 6] = [
+        RequestStatus::Submitted,
+        RequestStatus::Confirmed,
+        RequestStatus::Processed,
+        RequestStatus::Executed,
+        RequestStatus::Cancelled,
+        RequestStatus::Expired,
+    ];
+
+    /// Whether the request can still change
+    pub fn is_open(&self) -> bool {
+        matches!(self, RequestStatus::Submitted | RequestStatus::Confirmed | RequestStatus::Processed)
+    }
+
+    /// Whether the request is still waiting for an epoch to process it
+    pub fn is_pending(&self) -> bool {
+        matches!(self, RequestStatus::Submitted | RequestStatus::Confirmed)
+    }
+
+    /// Whether a request with this status can move to `next`
+    pub fn can_become(&self, next: RequestStatus) -> bool {
+        use RequestStatus::*;
+
+        match (self, next) {
+            (Submitted, Confirmed | Processed | Executed) => true,
+            (Confirmed, Processed | Executed) => true,
+            (Processed, Executed) => true,
+            (from, Cancelled | Expired) => from.is_open(),
+            _ => false,
+        }
+    }
+
+    /// Statuses a request can move to `next` from
+    pub fn sources(next: RequestStatus) -> Vec<RequestStatus> {
+        Self::ALL.into_iter().filter(|from| from.can_become(next)).collect()
+    }
+
+    /// Checks a move to `next`
+    pub fn change_to(self, next: RequestStatus) -> Result<StatusChange, IllegalTransition> {
+        if self == next {
+            Ok(StatusChange::Unchanged)
+        } else if self.can_become(next) {
+            Ok(StatusChange::Moved(self))
+        } else {
+            Err(IllegalTransition { from: self, to: next })
+        }
+    }
+}
+
+impl fmt::Display for RequestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestStatus::Submitted => write!(f, "submitted"),
+            RequestStatus::Confirmed => write!(f, "confirmed"),
+            RequestStatus::Processed => write!(f, "processed"),
+            RequestStatus::Executed => write!(f, "executed"),
+            RequestStatus::Cancelled => write!(f, "cancelled"),
+            RequestStatus::Expired => write!(f, "expired"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RequestStatus::*;
+
+    #[test]
+    fn requests_only_move_forward() {
+        assert_eq!(Submitted.change_to(Confirmed), Ok(StatusChange::Moved(Submitted)));
+        assert_eq!(Confirmed.change_to(Executed), Ok(StatusChange::Moved(Confirmed)));
+        assert_eq!(Processed.change_to(Processed), Ok(StatusChange::Unchanged));
+        assert_eq!(Processed.change_to(Confirmed), Err(IllegalTransition { from: Processed, to: Confirmed }));
+        assert_eq!(Executed.change_to(Processed), Err(IllegalTransition { from: Executed, to: Processed }));
+    }
+
+    #[test]
+    fn only_open_requests_can_end_early() {
+        for from in RequestStatus::ALL {
+            assert_eq!(from.can_become(Cancelled), from.is_open(), "{}", from);
+            assert_eq!(from.can_become(Expired), from.is_open(), "{}", from);
+        }
+        assert_eq!(Cancelled.change_to(Expired), Err(IllegalTransition { from: Cancelled, to: Expired }));
+        assert_eq!(RequestStatus::sources(Processed), [Submitted, Confirmed]);
+    }
+}
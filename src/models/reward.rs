@@ -1,12 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::types::Uuid;
+use sqlx::types::{Json, Uuid};
 use std::fmt;
 
 /// Reward status enum
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
 pub enum RewardStatus {
+    #[default]
     Pending,
     Claimed,
     Expired,
@@ -22,20 +23,29 @@ impl fmt::Display for RewardStatus {
     }
 }
 
-impl Default for RewardStatus {
-    fn default() -> Self {
-        Self::Pending
-    }
+/// Kind of reward a row pays
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RewardType {
+    /// Time-weighted reward on the user's own active balance
+    #[default]
+    Epoch,
+    /// Bonus on the epoch rewards of users they referred
+    Referral,
 }
 
 /// User reward model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UserReward {
     pub id: Uuid,
     pub user_id: Uuid,
     pub epoch_id: i32,
     pub amount: String,
+    /// Symbol of the asset the reward is paid in
+    pub asset: String,
     pub apr_bps: i32,
+    pub reward_type: RewardType,
     pub status: RewardStatus,
     pub claim_timestamp: Option<DateTime<Utc>>,
     pub claim_transaction_hash: Option<String>,
@@ -61,11 +71,35 @@ pub struct UpdateUserRewardStatusRequest {
 }
 
 /// User rewards summary
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UserRewardsSummary {
     pub user_id: Uuid,
     pub total_pending: String,
     pub total_claimed: String,
     pub total_lifetime: String,
     pub last_claim_timestamp: Option<DateTime<Utc>>,
-} 
\ No newline at end of file
+}
+
+/// A user's calculated share of an epoch's rewards
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EpochRewardLine {
+    pub user_id: Uuid,
+    pub wallet_address: String,
+    /// Active balance averaged over the epoch by how long each balance was held
+    pub time_weighted_balance: String,
+    pub amount: String,
+}
+
+/// Distribution report of an epoch's rewards
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EpochRewardReport {
+    pub epoch_id: i32,
+    pub apr_bps: i32,
+    pub epoch_start: DateTime<Utc>,
+    pub epoch_end: DateTime<Utc>,
+    pub recipient_count: i32,
+    pub total_time_weighted_balance: String,
+    pub total_rewards: String,
+    pub distribution: Json<Vec<EpochRewardLine>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,264 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{Json, Uuid};
+
+use crate::models::kyc::KycLevel;
+use crate::models::system_parameter::SystemParametersCache;
+
+/// Collateral, liquidation and limit settings, changed and validated as one set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskParameters {
+    /// Collateral a borrow must post, in basis points of the borrowed amount
+    pub collateral_ratio_bps: i32,
+    /// Collateral ratio under which a borrow is flagged for liquidation, in basis points
+    pub liquidation_threshold_bps: i32,
+    /// Time a flagged borrower has to restore collateral before liquidation
+    pub liquidation_grace_period_seconds: i64,
+    pub max_epochs_before_liquidation: i32,
+    /// Minimum request amounts in base units
+    pub min_deposit_amount: String,
+    pub min_withdrawal_amount: String,
+    pub min_borrow_amount: String,
+    /// Per-epoch cap on each request type by KYC level, in USDC (`None` is unlimited)
+    pub kyc_basic_epoch_limit: Option<f64>,
+    pub kyc_advanced_epoch_limit: Option<f64>,
+    pub kyc_full_epoch_limit: Option<f64>,
+}
+
+impl From<&SystemParametersCache> for RiskParameters {
+    fn from(parameters: &SystemParametersCache) -> Self {
+        Self {
+            collateral_ratio_bps: parameters.collateral_ratio_bps,
+            liquidation_threshold_bps: parameters.liquidation_threshold_bps,
+            liquidation_grace_period_seconds: parameters.liquidation_grace_period_seconds,
+            max_epochs_before_liquidation: parameters.max_epochs_before_liquidation,
+            min_deposit_amount: parameters.min_deposit_amount.clone(),
+            min_withdrawal_amount: parameters.min_withdrawal_amount.clone(),
+            min_borrow_amount: parameters.min_borrow_amount.clone(),
+            kyc_basic_epoch_limit: parameters.kyc_basic_epoch_limit,
+            kyc_advanced_epoch_limit: parameters.kyc_advanced_epoch_limit,
+            kyc_full_epoch_limit: parameters.kyc_full_epoch_limit,
+        }
+    }
+}
+
+impl RiskParameters {
+    /// Names of the `system_parameters` rows backing the risk parameters
+    pub const NAMES: [&'static str; 10] = [
+        "collateral_ratio_bps",
+        "liquidation_threshold_bps",
+        "liquidation_grace_period_seconds",
+        "max_epochs_before_liquidation",
+        "min_deposit_amount",
+        "min_withdrawal_amount",
+        "min_borrow_amount",
+        "kyc_basic_epoch_limit",
+        "kyc_advanced_epoch_limit",
+        "kyc_full_epoch_limit",
+    ];
+
+    /// Names of the parameters the contract also enforces
+    pub const ONCHAIN_NAMES: [&'static str; 3] = ["collateral_ratio_bps", "min_deposit_amount", "min_withdrawal_amount"];
+
+    /// Each parameter's value as stored in `system_parameters`, in [`NAMES`](Self::NAMES) order
+    pub fn stored_values(&self) -> Vec<(&'static str, String)> {
+        fn limit(value: Option<f64>) -> String {
+            value.map_or_else(|| "unlimited".to_string(), |limit| limit.to_string())
+        }
+
+        vec![
+            ("collateral_ratio_bps", self.collateral_ratio_bps.to_string()),
+            ("liquidation_threshold_bps", self.liquidation_threshold_bps.to_string()),
+            ("liquidation_grace_period_seconds", self.liquidation_grace_period_seconds.to_string()),
+            ("max_epochs_before_liquidation", self.max_epochs_before_liquidation.to_string()),
+            ("min_deposit_amount", self.min_deposit_amount.clone()),
+            ("min_withdrawal_amount", self.min_withdrawal_amount.clone()),
+            ("min_borrow_amount", self.min_borrow_amount.clone()),
+            ("kyc_basic_epoch_limit", limit(self.kyc_basic_epoch_limit)),
+            ("kyc_advanced_epoch_limit", limit(self.kyc_advanced_epoch_limit)),
+            ("kyc_full_epoch_limit", limit(self.kyc_full_epoch_limit)),
+        ]
+    }
+
+    /// Checks each value and the invariants between them, returning every violation found
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut violations = Vec::new();
+
+        // The contract stores the collateral ratio in whole percent
+        if self.collateral_ratio_bps < 10_000 || self.collateral_ratio_bps % 100 != 0 {
+            violations.push(format!(
+                "collateral_ratio_bps must be a whole percentage of at least 10000, got {}",
+                self.collateral_ratio_bps
+            ));
+        }
+        if self.liquidation_threshold_bps < 10_000 {
+            violations.push(format!(
+                "liquidation_threshold_bps must be at least 10000, got {}",
+                self.liquidation_threshold_bps
+            ));
+        }
+        if self.liquidation_threshold_bps >= self.collateral_ratio_bps {
+            violations.push(format!(
+                "liquidation_threshold_bps ({}) must be below collateral_ratio_bps ({})",
+                self.liquidation_threshold_bps, self.collateral_ratio_bps
+            ));
+        }
+        if self.liquidation_grace_period_seconds < 0 {
+            violations.push("liquidation_grace_period_seconds can't be negative".to_string());
+        }
+        if self.max_epochs_before_liquidation < 1 {
+            violations.push("max_epochs_before_liquidation must be at least 1".to_string());
+        }
+
+        for (name, value) in [
+            ("min_deposit_amount", &self.min_deposit_amount),
+            ("min_withdrawal_amount", &self.min_withdrawal_amount),
+            ("min_borrow_amount", &self.min_borrow_amount),
+        ] {
+            if value.parse::<u128>().is_err() {
+                violations.push(format!("{} must be a whole number of base units, got '{}'", name, value));
+            }
+        }
+
+        let limits = [
+            ("kyc_basic_epoch_limit", self.kyc_basic_epoch_limit),
+            ("kyc_advanced_epoch_limit", self.kyc_advanced_epoch_limit),
+            ("kyc_full_epoch_limit", self.kyc_full_epoch_limit),
+        ];
+        for (name, limit) in limits {
+            if limit.is_some_and(|limit| !limit.is_finite() || limit < 0.0) {
+                violations.push(format!("{} must be a non-negative amount or unlimited", name));
+            }
+        }
+        // Higher verification levels never allow less
+        for pair in limits.windows(2) {
+            let ((lower_name, lower), (higher_name, higher)) = (pair[0], pair[1]);
+            if let (Some(lower), Some(higher)) = (lower, higher) {
+                if higher < lower {
+                    violations.push(format!("{} ({}) must not be below {} ({})", higher_name, higher, lower_name, lower));
+                }
+            } else if lower.is_none() && higher.is_some() {
+                violations.push(format!("{} can't be limited while {} is unlimited", higher_name, lower_name));
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Per-epoch cap on each request type for users verified at `level`
+    pub fn kyc_epoch_limit(&self, level: KycLevel) -> Option<f64> {
+        match level {
+            KycLevel::Basic => self.kyc_basic_epoch_limit,
+            KycLevel::Advanced => self.kyc_advanced_epoch_limit,
+            KycLevel::Full => self.kyc_full_epoch_limit,
+        }
+    }
+}
+
+/// Whether a risk parameter change has been pushed to the contract
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum OnchainSyncStatus {
+    /// Only off-chain parameters changed
+    NotRequired,
+    Pending,
+    Synced,
+    Failed,
+}
+
+/// A recorded change of the risk parameters
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RiskParameterVersion {
+    pub version: i64,
+    /// Every risk parameter as of this version
+    pub parameters: Json<RiskParameters>,
+    /// Parameters that changed from the previous version
+    pub changed: Vec<String>,
+    pub reason: Option<String>,
+    pub updated_by: Option<Uuid>,
+    pub onchain_status: OnchainSyncStatus,
+    pub onchain_transaction_hash: Option<String>,
+    pub onchain_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+/// Replaces the risk parameters with a new validated set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateRiskParametersRequest {
+    /// Every risk parameter; `null` KYC limits are unlimited
+    pub parameters: RiskParameters,
+    pub reason: Option<String>,
+    pub updated_by: Option<Uuid>,
+    /// Version the change was based on. When set, the update is refused if another change has
+    /// been recorded since.
+    pub expected_version: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> RiskParameters {
+        RiskParameters {
+            collateral_ratio_bps: 15_000,
+            liquidation_threshold_bps: 12_000,
+            liquidation_grace_period_seconds: 86_400,
+            max_epochs_before_liquidation: 3,
+            min_deposit_amount: "1000000".to_string(),
+            min_withdrawal_amount: "1000000".to_string(),
+            min_borrow_amount: "1000000".to_string(),
+            kyc_basic_epoch_limit: Some(10_000.0),
+            kyc_advanced_epoch_limit: Some(100_000.0),
+            kyc_full_epoch_limit: None,
+        }
+    }
+
+    #[test]
+    fn consistent_parameters_are_valid() {
+        assert_eq!(parameters().validate(), Ok(()));
+    }
+
+    #[test]
+    fn every_violation_is_reported() {
+        let parameters = RiskParameters {
+            collateral_ratio_bps: 15_050,
+            liquidation_threshold_bps: 16_000,
+            max_epochs_before_liquidation: 0,
+            min_borrow_amount: "1.5".to_string(),
+            kyc_advanced_epoch_limit: Some(f64::NAN),
+            ..parameters()
+        };
+
+        assert_eq!(
+            parameters.validate(),
+            Err(vec![
+                "collateral_ratio_bps must be a whole percentage of at least 10000, got 15050".to_string(),
+                "liquidation_threshold_bps (16000) must be below collateral_ratio_bps (15050)".to_string(),
+                "max_epochs_before_liquidation must be at least 1".to_string(),
+                "min_borrow_amount must be a whole number of base units, got '1.5'".to_string(),
+                "kyc_advanced_epoch_limit must be a non-negative amount or unlimited".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn higher_kyc_levels_never_allow_less() {
+        let lower = RiskParameters { kyc_advanced_epoch_limit: Some(5_000.0), ..parameters() };
+        assert_eq!(
+            lower.validate(),
+            Err(vec!["kyc_advanced_epoch_limit (5000) must not be below kyc_basic_epoch_limit (10000)".to_string()])
+        );
+
+        let unlimited_below = RiskParameters { kyc_basic_epoch_limit: None, ..parameters() };
+        assert_eq!(
+            unlimited_below.validate(),
+            Err(vec!["kyc_advanced_epoch_limit can't be limited while kyc_basic_epoch_limit is unlimited".to_string()])
+        );
+    }
+}
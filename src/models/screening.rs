@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// What prompted a screening
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ScreeningTrigger {
+    Registration,
+    Withdrawal,
+    Rescreen,
+    Manual,
+}
+
+impl fmt::Display for ScreeningTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningTrigger::Registration => write!(f, "registration"),
+            ScreeningTrigger::Withdrawal => write!(f, "withdrawal"),
+            ScreeningTrigger::Rescreen => write!(f, "rescreen"),
+            ScreeningTrigger::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+/// Risk a screening provider assigns to a subject, lowest first
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, PartialOrd, Ord)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Severe,
+}
+
+impl fmt::Display for RiskLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskLevel::None => write!(f, "none"),
+            RiskLevel::Low => write!(f, "low"),
+            RiskLevel::Medium => write!(f, "medium"),
+            RiskLevel::High => write!(f, "high"),
+            RiskLevel::Severe => write!(f, "severe"),
+        }
+    }
+}
+
+impl FromStr for RiskLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(RiskLevel::None),
+            "low" => Ok(RiskLevel::Low),
+            "medium" => Ok(RiskLevel::Medium),
+            "high" => Ok(RiskLevel::High),
+            "severe" => Ok(RiskLevel::Severe),
+            other => Err(anyhow::anyhow!("Unknown risk level '{}'", other)),
+        }
+    }
+}
+
+/// A sanctions or watchlist entry a subject matched
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScreeningMatch {
+    /// e.g. `sanctions`
+    pub category: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Screening model - one check of a wallet against the screening provider
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Screening {
+    pub id: Uuid,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    pub trigger: ScreeningTrigger,
+    pub provider: String,
+    pub risk_level: RiskLevel,
+    pub flagged: bool,
+    pub matches: Json<Vec<ScreeningMatch>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Blocked wallet model - set when a screening flags a wallet
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BlockedWallet {
+    pub wallet_address: String,
+    pub reason: String,
+    pub screening_id: Option<Uuid>,
+    pub blocked_at: DateTime<Utc>,
+    pub cleared_at: Option<DateTime<Utc>>,
+    pub cleared_reason: Option<String>,
+}
+
+/// Filters for listing screenings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScreeningFilter {
+    pub wallet_address: Option<String>,
+    pub flagged: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Request to lift a wallet block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearBlockRequest {
+    pub reason: String,
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Protocol-wide balance totals, in the vault asset
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProtocolTotals {
+    pub total_value_locked: String,
+    pub active_depositors: i64,
+    pub pending_deposits: String,
+    pub pending_withdrawals: String,
+}
+
+/// Protocol stats with the value locked in USD
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolStats {
+    #[serde(flatten)]
+    pub totals: ProtocolTotals,
+    /// Symbol of the vault asset the totals are denominated in
+    pub vault_asset: String,
+    /// USD price of the vault asset
+    pub vault_price_usd: String,
+    pub total_value_locked_usd: String,
+    /// When the vault asset price was observed
+    pub price_observed_at: DateTime<Utc>,
+}
+
+/// Projected rewards of a deposit held over a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApyProjection {
+    pub amount: String,
+    pub duration_seconds: i64,
+    pub apr_bps: i32,
+    pub epoch_duration_seconds: i64,
+    pub compounding: bool,
+    /// Time until the deposit starts earning, when the active epoch closes
+    pub activation_delay_seconds: i64,
+    /// Whole epochs the deposit earns over
+    pub full_epochs: i64,
+    /// Time held in the last, partial epoch
+    pub partial_epoch_seconds: i64,
+    pub projected_rewards: String,
+    pub final_balance: String,
+    /// Yield over a year at the current APR with the same compounding, in percent
+    pub effective_apy_percent: String,
+}
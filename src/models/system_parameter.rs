@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 
 /// System parameter model
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct SystemParameter {
     pub id: i32,
     pub parameter_name: String,
@@ -33,26 +33,111 @@ pub struct UpdateSystemParameterRequest {
 
 /// System parameters cache
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SystemParametersCache {
     pub reward_apr_bps: i32,
+    /// Share of a referee's epoch rewards paid to their referrer, in basis points
+    pub referral_bonus_bps: i32,
+    /// Annual interest rate charged on borrows, in basis points
+    pub borrow_interest_rate_bps: i32,
     pub epoch_duration_seconds: i64,
     pub max_epochs_before_liquidation: i32,
     pub collateral_ratio_bps: i32,
+    /// Collateral ratio under which a borrow is flagged for liquidation, in basis points
+    pub liquidation_threshold_bps: i32,
+    /// Time a flagged borrower has to restore collateral before liquidation
+    pub liquidation_grace_period_seconds: i64,
     pub min_deposit_amount: String,
     pub min_withdrawal_amount: String,
     pub min_borrow_amount: String,
+    /// Per-epoch cap on each request type for Basic KYC users, in USDC (`None` is unlimited)
+    pub kyc_basic_epoch_limit: Option<f64>,
+    /// Per-epoch cap on each request type for Advanced KYC users, in USDC
+    pub kyc_advanced_epoch_limit: Option<f64>,
+    /// Per-epoch cap on each request type for Full KYC users, in USDC
+    pub kyc_full_epoch_limit: Option<f64>,
+    /// Deposits a wallet may request per epoch, in tokens, unless its user limits override it
+    pub deposit_epoch_volume_limit: Option<f64>,
+    /// Withdrawals a wallet may request per epoch, in tokens
+    pub withdrawal_epoch_volume_limit: Option<f64>,
+    /// Deposits a wallet may request in any 24 hours, in tokens
+    pub deposit_daily_volume_limit: Option<f64>,
+    /// Withdrawals a wallet may request in any 24 hours, in tokens
+    pub withdrawal_daily_volume_limit: Option<f64>,
 }
 
 impl Default for SystemParametersCache {
     fn default() -> Self {
         Self {
             reward_apr_bps: 500,
+            referral_bonus_bps: 1000,
+            borrow_interest_rate_bps: 800,
             epoch_duration_seconds: 604800,
             max_epochs_before_liquidation: 2,
             collateral_ratio_bps: 15000,
+            liquidation_threshold_bps: 12000,
+            liquidation_grace_period_seconds: 86400,
             min_deposit_amount: "100000000".to_string(),
             min_withdrawal_amount: "100000000".to_string(),
             min_borrow_amount: "1000000000".to_string(),
+            kyc_basic_epoch_limit: Some(10_000.0),
+            kyc_advanced_epoch_limit: Some(100_000.0),
+            kyc_full_epoch_limit: None,
+            deposit_epoch_volume_limit: None,
+            withdrawal_epoch_volume_limit: None,
+            deposit_daily_volume_limit: None,
+            withdrawal_daily_volume_limit: None,
+        }
+    }
+}
+
+impl SystemParametersCache {
+    /// Sets the field backing a named parameter.
+    ///
+    /// Returns `Ok(false)` for parameters the cache doesn't track and an error when
+    /// the value doesn't parse as the field's type.
+    pub fn apply(&mut self, name: &str, value: &str) -> Result<bool, String> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .trim()
+                .parse::<T>()
+                .map_err(|_| format!("Invalid value '{}' for parameter {}", value, name))
+        }
+
+        /// Parses a non-negative amount, or `unlimited`
+        fn parse_limit(name: &str, value: &str) -> Result<Option<f64>, String> {
+            if value.trim().eq_ignore_ascii_case("unlimited") {
+                return Ok(None);
+            }
+
+            match parse::<f64>(name, value)? {
+                limit if limit.is_finite() && limit >= 0.0 => Ok(Some(limit)),
+                _ => Err(format!("Invalid value '{}' for parameter {}", value, name)),
+            }
+        }
+
+        match name {
+            "reward_apr_bps" => self.reward_apr_bps = parse(name, value)?,
+            "referral_bonus_bps" => self.referral_bonus_bps = parse(name, value)?,
+            "borrow_interest_rate_bps" => self.borrow_interest_rate_bps = parse(name, value)?,
+            "epoch_duration_seconds" => self.epoch_duration_seconds = parse(name, value)?,
+            "max_epochs_before_liquidation" => self.max_epochs_before_liquidation = parse(name, value)?,
+            "collateral_ratio_bps" => self.collateral_ratio_bps = parse(name, value)?,
+            "liquidation_threshold_bps" => self.liquidation_threshold_bps = parse(name, value)?,
+            "liquidation_grace_period_seconds" => self.liquidation_grace_period_seconds = parse(name, value)?,
+            "min_deposit_amount" => self.min_deposit_amount = parse::<u128>(name, value)?.to_string(),
+            "min_withdrawal_amount" => self.min_withdrawal_amount = parse::<u128>(name, value)?.to_string(),
+            "min_borrow_amount" => self.min_borrow_amount = parse::<u128>(name, value)?.to_string(),
+            "kyc_basic_epoch_limit" => self.kyc_basic_epoch_limit = parse_limit(name, value)?,
+            "kyc_advanced_epoch_limit" => self.kyc_advanced_epoch_limit = parse_limit(name, value)?,
+            "kyc_full_epoch_limit" => self.kyc_full_epoch_limit = parse_limit(name, value)?,
+            "deposit_epoch_volume_limit" => self.deposit_epoch_volume_limit = parse_limit(name, value)?,
+            "withdrawal_epoch_volume_limit" => self.withdrawal_epoch_volume_limit = parse_limit(name, value)?,
+            "deposit_daily_volume_limit" => self.deposit_daily_volume_limit = parse_limit(name, value)?,
+            "withdrawal_daily_volume_limit" => self.withdrawal_daily_volume_limit = parse_limit(name, value)?,
+            _ => return Ok(false),
         }
+
+        Ok(true)
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Kind of protocol fee
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FeeType {
+    /// Charged on executed withdrawals
+    #[serde(alias = "Withdrawal")]
+    Withdrawal,
+    /// Penalty charged on liquidated borrows
+    #[serde(alias = "Liquidation")]
+    Liquidation,
+}
+
+impl fmt::Display for FeeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeType::Withdrawal => write!(f, "withdrawal"),
+            FeeType::Liquidation => write!(f, "liquidation"),
+        }
+    }
+}
+
+/// Protocol fee model - a fee paid to the treasury
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProtocolFee {
+    pub id: i64,
+    pub fee_type: FeeType,
+    /// On-chain request the fee was charged on
+    pub request_id: i64,
+    pub wallet_address: String,
+    pub user_id: Option<Uuid>,
+    /// Epoch that was active when the fee was collected
+    pub epoch_id: Option<i32>,
+    pub amount: String,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    pub collected_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Fee recorded from a `FeeCollected` event
+#[derive(Debug, Clone)]
+pub struct NewProtocolFee {
+    pub fee_type: FeeType,
+    pub request_id: i64,
+    pub wallet_address: String,
+    pub amount: String,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    pub collected_at: DateTime<Utc>,
+}
+
+/// How treasury revenue is grouped
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    #[default]
+    Month,
+    Epoch,
+}
+
+/// Revenue of one fee type in one period
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RevenueLine {
+    /// Start of the month, or of the epoch
+    pub period_start: DateTime<Utc>,
+    /// Set when grouping by epoch; fees collected outside any indexed epoch have none
+    pub epoch_id: Option<i32>,
+    pub fee_type: FeeType,
+    pub fee_count: i64,
+    pub total: String,
+}
+
+/// Treasury revenue and balance, compared with the treasury account on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryReport {
+    pub period: ReportPeriod,
+    /// Revenue per period and fee type, most recent period first
+    pub revenue: Vec<RevenueLine>,
+    /// All fees ever recorded
+    pub recorded_balance: String,
+    /// Free balance of the treasury account, when it is configured and could be read
+    pub on_chain_balance: Option<String>,
+    /// On-chain balance minus recorded fees
+    pub drift: Option<String>,
+    pub generated_at: DateTime<Utc>,
+}
@@ -2,26 +2,24 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 
+use crate::models::wallet::WalletAddress;
+
 /// KYC status enum
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum KycStatus {
+    #[default]
     Pending,
     Approved,
     Rejected,
 }
 
-impl Default for KycStatus {
-    fn default() -> Self {
-        Self::Pending
-    }
-}
-
 /// User model - stores user information and KYC status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
     pub id: Uuid,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub email: Option<String>,
     pub kyc_status: KycStatus,
     pub kyc_timestamp: Option<DateTime<Utc>>,
@@ -30,11 +28,34 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
 }
 
+/// What anyone may see of a user's profile: no contact details or KYC provider references
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicUserProfile {
+    pub wallet_address: WalletAddress,
+    pub kyc_status: KycStatus,
+    pub kyc_timestamp: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<User> for PublicUserProfile {
+    fn from(user: User) -> Self {
+        Self {
+            wallet_address: user.wallet_address,
+            kyc_status: user.kyc_status,
+            kyc_timestamp: user.kyc_timestamp,
+            created_at: user.created_at,
+        }
+    }
+}
+
 /// Create user request data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateUserRequest {
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub email: Option<String>,
+    /// Wallet of the registered user who referred this one
+    #[serde(default)]
+    pub referrer_wallet: Option<WalletAddress>,
 }
 
 /// Update user request data
@@ -46,11 +67,52 @@ pub struct UpdateUserRequest {
     pub kyc_reference: Option<String>,
 }
 
+/// Filters for listing users
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserFilter {
+    pub kyc_status: Option<KycStatus>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Format users are exported in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UserExportFormat {
+    Json,
+    #[default]
+    Csv,
+}
+
+/// Query parameters of a user export
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserExportQuery {
+    pub kyc_status: Option<KycStatus>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub format: UserExportFormat,
+}
+
+impl UserExportQuery {
+    /// The users to export
+    pub fn filter(&self) -> UserFilter {
+        UserFilter {
+            kyc_status: self.kyc_status.clone(),
+            created_after: self.created_after,
+            created_before: self.created_before,
+            ..UserFilter::default()
+        }
+    }
+}
+
 /// User data with balance
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserWithBalance {
     pub id: Uuid,
-    pub wallet_address: String,
+    pub wallet_address: WalletAddress,
     pub email: Option<String>,
     pub kyc_status: KycStatus,
     pub active_balance: String,
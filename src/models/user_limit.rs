@@ -0,0 +1,219 @@
+//! Rolling submission volume limits
+//!
+//! Each wallet may request up to a volume of deposits and of withdrawals per epoch and per
+//! rolling 24 hours, in tokens of the contract's asset. The limits default to the
+//! `*_volume_limit` system parameters; a user's overrides replace any of them for that user alone.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{BigDecimal, Uuid};
+use std::str::FromStr;
+
+use crate::models::amount::decimal_text;
+use crate::models::blockchain_request::RequestType;
+use crate::models::system_parameter::SystemParametersCache;
+use crate::models::wallet::WalletAddress;
+
+/// Period a volume limit counts submissions over
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitWindow {
+    /// Since the active epoch started
+    Epoch,
+    /// The last 24 hours
+    Day,
+}
+
+/// Where an effective limit comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitSource {
+    /// The system parameter
+    Default,
+    /// The user's own limits
+    Override,
+}
+
+/// A user's overrides of the volume limits; unset limits fall back to the system parameters
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UserLimitOverrides {
+    pub user_id: Uuid,
+    #[serde(with = "decimal_text::option")]
+    pub deposit_epoch_limit: Option<BigDecimal>,
+    #[serde(with = "decimal_text::option")]
+    pub withdrawal_epoch_limit: Option<BigDecimal>,
+    #[serde(with = "decimal_text::option")]
+    pub deposit_daily_limit: Option<BigDecimal>,
+    #[serde(with = "decimal_text::option")]
+    pub withdrawal_daily_limit: Option<BigDecimal>,
+    /// Why the user's limits differ from everyone else's
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Replace a user's volume limit overrides; omitted limits fall back to the system parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateUserLimitsRequest {
+    #[serde(default, with = "decimal_text::option")]
+    pub deposit_epoch_limit: Option<BigDecimal>,
+    #[serde(default, with = "decimal_text::option")]
+    pub withdrawal_epoch_limit: Option<BigDecimal>,
+    #[serde(default, with = "decimal_text::option")]
+    pub deposit_daily_limit: Option<BigDecimal>,
+    #[serde(default, with = "decimal_text::option")]
+    pub withdrawal_daily_limit: Option<BigDecimal>,
+    pub reason: String,
+}
+
+impl UpdateUserLimitsRequest {
+    /// Checks every limit is a non-negative amount and a reason is given
+    pub fn validate(&self) -> Result<(), String> {
+        let limits = [
+            ("deposit_epoch_limit", &self.deposit_epoch_limit),
+            ("withdrawal_epoch_limit", &self.withdrawal_epoch_limit),
+            ("deposit_daily_limit", &self.deposit_daily_limit),
+            ("withdrawal_daily_limit", &self.withdrawal_daily_limit),
+        ];
+        if let Some((name, _)) = limits
+            .iter()
+            .find(|(_, limit)| limit.as_ref().is_some_and(|limit| limit < &BigDecimal::default()))
+        {
+            return Err(format!("{} must be a non-negative amount", name));
+        }
+        if self.reason.trim().is_empty() {
+            return Err("A reason for the override is required".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// Effective limit on one request type over one window
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeLimit {
+    /// Most that may be requested, `None` when unlimited
+    #[serde(with = "decimal_text::option")]
+    pub limit: Option<BigDecimal>,
+    pub source: LimitSource,
+}
+
+/// A user's effective volume limits
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VolumeLimits {
+    pub deposit_epoch: VolumeLimit,
+    pub withdrawal_epoch: VolumeLimit,
+    pub deposit_daily: VolumeLimit,
+    pub withdrawal_daily: VolumeLimit,
+}
+
+impl VolumeLimits {
+    /// The system parameters, with the user's overrides in place of those they set
+    pub fn resolve(defaults: &SystemParametersCache, overrides: Option<&UserLimitOverrides>) -> Self {
+        // The system parameters are floats; their shortest form is the decimal they were set to
+        let pick = |default: Option<f64>, overridden: Option<&BigDecimal>| match overridden {
+            Some(limit) => VolumeLimit { limit: Some(limit.clone()), source: LimitSource::Override },
+            None => VolumeLimit {
+                limit: default.and_then(|limit| BigDecimal::from_str(&limit.to_string()).ok()),
+                source: LimitSource::Default,
+            },
+        };
+
+        Self {
+            deposit_epoch: pick(defaults.deposit_epoch_volume_limit, overrides.and_then(|o| o.deposit_epoch_limit.as_ref())),
+            withdrawal_epoch: pick(defaults.withdrawal_epoch_volume_limit, overrides.and_then(|o| o.withdrawal_epoch_limit.as_ref())),
+            deposit_daily: pick(defaults.deposit_daily_volume_limit, overrides.and_then(|o| o.deposit_daily_limit.as_ref())),
+            withdrawal_daily: pick(defaults.withdrawal_daily_volume_limit, overrides.and_then(|o| o.withdrawal_daily_limit.as_ref())),
+        }
+    }
+
+    /// The limit on a request type over a window; borrows have none
+    pub fn get(&self, request_type: &RequestType, window: LimitWindow) -> Option<&VolumeLimit> {
+        match (request_type, window) {
+            (RequestType::Deposit, LimitWindow::Epoch) => Some(&self.deposit_epoch),
+            (RequestType::Withdrawal, LimitWindow::Epoch) => Some(&self.withdrawal_epoch),
+            (RequestType::Deposit, LimitWindow::Day) => Some(&self.deposit_daily),
+            (RequestType::Withdrawal, LimitWindow::Day) => Some(&self.withdrawal_daily),
+            (RequestType::Borrow, _) => None,
+        }
+    }
+}
+
+/// How much of one limit a wallet has used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeUsage {
+    pub request_type: RequestType,
+    pub window: LimitWindow,
+    #[serde(with = "decimal_text::option")]
+    pub limit: Option<BigDecimal>,
+    pub source: LimitSource,
+    /// Amount requested within the window, in tokens
+    #[serde(with = "decimal_text")]
+    pub used: BigDecimal,
+    /// `None` when unlimited
+    #[serde(with = "decimal_text::option")]
+    pub remaining: Option<BigDecimal>,
+}
+
+/// A wallet's volume limits and what it has used of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserLimitReport {
+    pub wallet_address: WalletAddress,
+    /// Asset the limits are in
+    pub asset: String,
+    pub overrides: Option<UserLimitOverrides>,
+    pub usage: Vec<VolumeUsage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    fn overrides(deposit_daily_limit: Option<BigDecimal>) -> UserLimitOverrides {
+        UserLimitOverrides {
+            user_id: Uuid::nil(),
+            deposit_epoch_limit: None,
+            withdrawal_epoch_limit: None,
+            deposit_daily_limit,
+            withdrawal_daily_limit: None,
+            reason: "Market maker".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn overrides_replace_only_the_limits_they_set() {
+        let defaults = SystemParametersCache {
+            deposit_daily_volume_limit: Some(1_000.0),
+            withdrawal_daily_volume_limit: Some(500.0),
+            ..SystemParametersCache::default()
+        };
+
+        let limits = VolumeLimits::resolve(&defaults, Some(&overrides(Some(tokens("50000.000001")))));
+        assert_eq!(limits.deposit_daily, VolumeLimit { limit: Some(tokens("50000.000001")), source: LimitSource::Override });
+        assert_eq!(limits.withdrawal_daily, VolumeLimit { limit: Some(tokens("500")), source: LimitSource::Default });
+        assert_eq!(limits.deposit_epoch.limit, None);
+        assert_eq!(limits.get(&RequestType::Borrow, LimitWindow::Day), None);
+
+        assert_eq!(VolumeLimits::resolve(&defaults, None).deposit_daily.limit, Some(tokens("1000")));
+    }
+
+    #[test]
+    fn overrides_need_amounts_and_a_reason() {
+        let request = UpdateUserLimitsRequest {
+            deposit_epoch_limit: Some(tokens("100")),
+            withdrawal_epoch_limit: None,
+            deposit_daily_limit: None,
+            withdrawal_daily_limit: None,
+            reason: "Raised after review".to_string(),
+        };
+        assert_eq!(request.validate(), Ok(()));
+        assert!(UpdateUserLimitsRequest { withdrawal_daily_limit: Some(tokens("-1")), ..request.clone() }.validate().is_err());
+        assert!(UpdateUserLimitsRequest { reason: " ".to_string(), ..request }.validate().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Wallet addresses
+//!
+//! The same account has a different SS58 address on every network prefix, so addresses can't be
+//! compared as users write them. A [`WalletAddress`] is read from any prefix and kept in one
+//! canonical form, the generic Substrate prefix that decoded chain accounts are written with,
+//! along with the public key it encodes.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef, Postgres};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use subxt::ext::sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use thiserror::Error;
+
+/// Prefix of the canonical form, as `subxt` writes account IDs
+pub const CANONICAL_SS58_PREFIX: u16 = 42;
+
+/// Failure reading a wallet address
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum WalletAddressError {
+    #[error("Wallet address is required")]
+    Empty,
+
+    #[error("'{0}' is not a valid SS58 wallet address")]
+    Invalid(String),
+}
+
+/// SS58 address of an account, in canonical form
+///
+/// Serialized and stored as the canonical address. Derefs to it, so it can be passed wherever
+/// an address is read as a `&str`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WalletAddress {
+    address: String,
+    public_key: [u8; 32],
+}
+
+impl WalletAddress {
+    /// Reads an SS58 address of any network prefix, checking its checksum
+    pub fn parse(value: &str) -> Result<Self, WalletAddressError> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(WalletAddressError::Empty);
+        }
+
+        let (account, _) = AccountId32::from_ss58check_with_version(value)
+            .map_err(|_| WalletAddressError::Invalid(value.to_string()))?;
+        Ok(Self::from_public_key(account.into()))
+    }
+
+    /// The address of a public key
+    pub fn from_public_key(public_key: [u8; 32]) -> Self {
+        let address = AccountId32::from(public_key)
+            .to_ss58check_with_version(Ss58AddressFormat::custom(CANONICAL_SS58_PREFIX));
+        Self { address, public_key }
+    }
+
+    /// Canonical address
+    pub fn as_str(&self) -> &str {
+        &self.address
+    }
+
+    /// Public key the address encodes
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    /// The address with the prefix of a network, as wallets on it display it
+    pub fn to_ss58(&self, prefix: u16) -> String {
+        AccountId32::from(self.public_key).to_ss58check_with_version(Ss58AddressFormat::custom(prefix))
+    }
+
+    /// Account ID the chain client signs and queries with
+    pub fn account_id(&self) -> subxt::utils::AccountId32 {
+        subxt::utils::AccountId32(self.public_key)
+    }
+}
+
+impl From<subxt::utils::AccountId32> for WalletAddress {
+    fn from(account: subxt::utils::AccountId32) -> Self {
+        Self::from_public_key(account.0)
+    }
+}
+
+impl From<WalletAddress> for String {
+    fn from(wallet: WalletAddress) -> Self {
+        wallet.address
+    }
+}
+
+impl Deref for WalletAddress {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.address
+    }
+}
+
+impl AsRef<str> for WalletAddress {
+    fn as_ref(&self) -> &str {
+        &self.address
+    }
+}
+
+impl PartialEq<str> for WalletAddress {
+    fn eq(&self, other: &str) -> bool {
+        self.address == other
+    }
+}
+
+impl PartialEq<&str> for WalletAddress {
+    fn eq(&self, other: &&str) -> bool {
+        self.address == *other
+    }
+}
+
+impl fmt::Display for WalletAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address)
+    }
+}
+
+impl FromStr for WalletAddress {
+    type Err = WalletAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Serialize for WalletAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.address)
+    }
+}
+
+impl<'de> Deserialize<'de> for WalletAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Self::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl sqlx::Type<Postgres> for WalletAddress {
+    fn type_info() -> PgTypeInfo {
+        <String as sqlx::Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as sqlx::Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for WalletAddress {
+    fn array_type_info() -> PgTypeInfo {
+        <String as PgHasArrayType>::array_type_info()
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        <String as PgHasArrayType>::array_compatible(ty)
+    }
+}
+
+impl sqlx::Encode<'_, Postgres> for WalletAddress {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <&str as sqlx::Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Postgres> for WalletAddress {
+    /// Reads a stored address, normalizing rows written before addresses were validated
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let address = <&str as sqlx::Decode<Postgres>>::decode(value)?;
+        Ok(Self::parse(address)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alice's well-known development account
+    const ALICE_PUBLIC_KEY: &str = "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+    const ALICE_GENERIC: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_POLKADOT: &str = "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5";
+    const ALICE_KUSAMA: &str = "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F";
+
+    #[test]
+    fn every_prefix_reads_as_the_same_wallet() {
+        let generic = WalletAddress::parse(ALICE_GENERIC).unwrap();
+        let polkadot = WalletAddress::parse(ALICE_POLKADOT).unwrap();
+        let kusama = WalletAddress::parse(&format!(" {} ", ALICE_KUSAMA)).unwrap();
+
+        assert_eq!(generic, polkadot);
+        assert_eq!(generic, kusama);
+        assert_eq!(polkadot.as_str(), ALICE_GENERIC);
+        assert_eq!(hex::encode(generic.public_key()), ALICE_PUBLIC_KEY);
+        assert_eq!(generic.to_ss58(0), ALICE_POLKADOT);
+        assert_eq!(generic.to_ss58(2), ALICE_KUSAMA);
+    }
+
+    #[test]
+    fn chain_accounts_are_already_canonical() {
+        let account = subxt::utils::AccountId32::from_str(ALICE_POLKADOT).unwrap();
+        assert_eq!(WalletAddress::from(account.clone()).as_str(), account.to_string());
+        assert_eq!(WalletAddress::parse(ALICE_GENERIC).unwrap().account_id(), account);
+    }
+
+    #[test]
+    fn malformed_addresses_are_refused() {
+        assert_eq!(WalletAddress::parse("  "), Err(WalletAddressError::Empty));
+        // Last character changed, so the checksum no longer matches
+        let bad_checksum = format!("{}Z", &ALICE_GENERIC[..ALICE_GENERIC.len() - 1]);
+        for value in ["wallet-0", "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d", bad_checksum.as_str()] {
+            assert_eq!(WalletAddress::parse(value), Err(WalletAddressError::Invalid(value.to_string())), "{}", value);
+        }
+    }
+
+    #[test]
+    fn addresses_round_trip_through_json() {
+        let wallet: WalletAddress = serde_json::from_value(ALICE_POLKADOT.into()).unwrap();
+        assert_eq!(serde_json::to_value(&wallet).unwrap(), ALICE_GENERIC);
+        assert!(serde_json::from_value::<WalletAddress>("wallet-0".into()).is_err());
+    }
+}
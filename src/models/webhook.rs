@@ -0,0 +1,207 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Uuid;
+use std::fmt;
+use std::str::FromStr;
+
+/// Protocol events that can be delivered to webhook endpoints
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    DepositProcessed,
+    WithdrawalExecuted,
+    EpochClosed,
+    KycStatusChanged,
+    ScreeningFlagged,
+    BorrowAtRisk,
+    BorrowLiquidated,
+}
+
+impl fmt::Display for WebhookEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookEventType::DepositProcessed => write!(f, "deposit_processed"),
+            WebhookEventType::WithdrawalExecuted => write!(f, "withdrawal_executed"),
+            WebhookEventType::EpochClosed => write!(f, "epoch_closed"),
+            WebhookEventType::KycStatusChanged => write!(f, "kyc_status_changed"),
+            WebhookEventType::ScreeningFlagged => write!(f, "screening_flagged"),
+            WebhookEventType::BorrowAtRisk => write!(f, "borrow_at_risk"),
+            WebhookEventType::BorrowLiquidated => write!(f, "borrow_liquidated"),
+        }
+    }
+}
+
+impl FromStr for WebhookEventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deposit_processed" => Ok(WebhookEventType::DepositProcessed),
+            "withdrawal_executed" => Ok(WebhookEventType::WithdrawalExecuted),
+            "epoch_closed" => Ok(WebhookEventType::EpochClosed),
+            "kyc_status_changed" => Ok(WebhookEventType::KycStatusChanged),
+            "screening_flagged" => Ok(WebhookEventType::ScreeningFlagged),
+            "borrow_at_risk" => Ok(WebhookEventType::BorrowAtRisk),
+            "borrow_liquidated" => Ok(WebhookEventType::BorrowLiquidated),
+            other => Err(format!("Unknown webhook event type '{}'", other)),
+        }
+    }
+}
+
+/// Webhook delivery status enum
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    #[default]
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// Registered webhook endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub url: String,
+    /// Secret in its stored, encrypted form
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+    pub is_active: bool,
+    /// Secret replaced by the last rotation
+    #[serde(skip_serializing)]
+    pub previous_secret: Option<String>,
+    /// When deliveries stop being signed with the previous secret
+    pub previous_secret_expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    /// Whether this endpoint is subscribed to the given event type
+    pub fn accepts(&self, event_type: WebhookEventType) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == &event_type.to_string())
+    }
+
+    /// Stored forms of the secrets payloads are signed with at `now`: the current one, then the
+    /// previous one while its rotation grace period lasts
+    pub fn signing_secrets(&self, now: DateTime<Utc>) -> Vec<&str> {
+        let mut secrets = vec![self.secret.as_str()];
+        if let (Some(previous), Some(expires_at)) = (&self.previous_secret, self.previous_secret_expires_at) {
+            if expires_at > now {
+                secrets.push(previous);
+            }
+        }
+
+        secrets
+    }
+}
+
+/// Webhook delivery log entry
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    /// Increases with every delivery recorded, across all endpoints
+    pub sequence: i64,
+    pub endpoint_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Body POSTed to webhook endpoints and returned when deliveries are fetched again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEnvelope {
+    pub id: Uuid,
+    pub sequence: i64,
+    pub event_type: String,
+    pub created_at: DateTime<Utc>,
+    pub data: Value,
+}
+
+impl From<&WebhookDelivery> for WebhookEnvelope {
+    fn from(delivery: &WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            sequence: delivery.sequence,
+            event_type: delivery.event_type.clone(),
+            created_at: delivery.created_at,
+            data: delivery.payload.clone(),
+        }
+    }
+}
+
+/// Create webhook endpoint request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWebhookEndpointRequest {
+    pub url: String,
+    /// Shared secret, generated when not given
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub event_types: Vec<WebhookEventType>,
+    pub description: Option<String>,
+}
+
+/// Newly registered endpoint. Its secret is only ever returned here.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhookEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+/// Update webhook endpoint request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateWebhookEndpointRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub event_types: Option<Vec<WebhookEventType>>,
+    pub description: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// Rotate webhook secret request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RotateWebhookSecretRequest {
+    /// New secret, generated when not given
+    pub secret: Option<String>,
+    /// How long the old secret keeps signing deliveries, defaults to a day. Zero drops it
+    /// straight away.
+    pub grace_period_secs: Option<i64>,
+}
+
+/// Result of a secret rotation. The new secret is only ever returned here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotatedWebhookSecret {
+    pub endpoint_id: Uuid,
+    pub secret: String,
+    pub previous_secret_expires_at: Option<DateTime<Utc>>,
+}
+
+/// Cursor of a missed-deliveries fetch
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookReplayQuery {
+    /// Sequence of the last delivery the consumer processed
+    #[serde(default)]
+    pub after: i64,
+    pub limit: Option<i64>,
+}
+
+/// Page of deliveries fetched by a consumer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookReplayPage {
+    pub deliveries: Vec<WebhookEnvelope>,
+    /// Cursor to pass as `after` for the next page
+    pub next_cursor: i64,
+    pub has_more: bool,
+}
@@ -0,0 +1,121 @@
+//! Renders the ledger in formats accounting tools import
+
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+use super::CashStatement;
+use crate::models::accounting::{JournalEntry, LedgerAccount};
+
+/// Header row of the journal CSV
+pub const CSV_HEADER: &str =
+    "Date,Journal Number,Account Code,Account Name,Debit,Credit,Description,Reference,Wallet Address\n";
+
+/// Renders journal entries as CSV, one row per debit or credit line. Rows of the same entry
+/// share its journal number; the side not posted to is left empty.
+pub fn render_csv(entries: &[JournalEntry]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    for entry in entries {
+        csv.push_str(&csv_rows(entry));
+    }
+
+    csv
+}
+
+/// The CSV rows of one journal entry, as [`render_csv`] writes them
+pub fn csv_rows(entry: &JournalEntry) -> String {
+    let mut csv = String::new();
+    for line in &entry.lines {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            entry.date.format("%Y-%m-%d"),
+            csv_field(&entry.id),
+            line.account_code,
+            csv_field(&line.account_name),
+            non_zero(&line.debit),
+            non_zero(&line.credit),
+            csv_field(&entry.description),
+            csv_field(&entry.reference),
+            csv_field(entry.wallet_address.as_deref().unwrap_or("")),
+        );
+    }
+
+    csv
+}
+
+/// Renders the cash account's movements as an OFX 2.2 bank statement
+pub fn render_ofx(statement: &CashStatement, generated_at: DateTime<Utc>) -> String {
+    let mut transactions = String::new();
+    for entry in &statement.entries {
+        let Some(cash) = entry.lines.iter().find(|line| line.account == LedgerAccount::Cash) else {
+            continue;
+        };
+        // Money in is a debit to the cash account and a credit on the statement
+        let (trn_type, amount) = if cash.debit != "0" {
+            ("CREDIT", cash.debit.clone())
+        } else {
+            ("DEBIT", format!("-{}", cash.credit))
+        };
+
+        let _ = writeln!(
+            transactions,
+            "<STMTTRN><TRNTYPE>{}</TRNTYPE><DTPOSTED>{}</DTPOSTED><TRNAMT>{}</TRNAMT><FITID>{}</FITID>\
+             <NAME>{}</NAME><MEMO>{}</MEMO></STMTTRN>",
+            trn_type,
+            ofx_date(entry.date),
+            amount,
+            xml_escape(&entry.id),
+            xml_escape(&entry.description.chars().take(32).collect::<String>()),
+            xml_escape(entry.wallet_address.as_deref().unwrap_or(&entry.reference)),
+        );
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX>
+<SIGNONMSGSRSV1><SONRS><STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS><DTSERVER>{generated}</DTSERVER><LANGUAGE>ENG</LANGUAGE></SONRS></SIGNONMSGSRSV1>
+<BANKMSGSRSV1><STMTTRNRS><TRNUID>0</TRNUID><STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>
+<STMTRS><CURDEF>USD</CURDEF>
+<BANKACCTFROM><BANKID>LSRWA</BANKID><ACCTID>{account}</ACCTID><ACCTTYPE>CHECKING</ACCTTYPE></BANKACCTFROM>
+<BANKTRANLIST><DTSTART>{start}</DTSTART><DTEND>{end}</DTEND>
+{transactions}</BANKTRANLIST>
+<LEDGERBAL><BALAMT>{balance}</BALAMT><DTASOF>{end}</DTASOF></LEDGERBAL>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+"#,
+        generated = ofx_date(generated_at),
+        account = LedgerAccount::Cash.code(),
+        start = ofx_date(statement.start),
+        end = ofx_date(statement.end),
+        transactions = transactions,
+        balance = statement.closing_balance.normalized(),
+    )
+}
+
+/// Amount, or an empty field when zero
+fn non_zero(amount: &str) -> &str {
+    if amount == "0" {
+        ""
+    } else {
+        amount
+    }
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// OFX date-time in UTC
+fn ofx_date(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%d%H%M%S.000[0:GMT]").to_string()
+}
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,134 @@
+//! Maps protocol events to journal entries
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::db::AccountingRepository;
+use crate::models::accounting::{JournalEntry, JournalEntryKind, JournalLine, LedgerAccount, LedgerEvent};
+
+/// Movements of the cash account over a period
+#[derive(Debug, Clone)]
+pub struct CashStatement {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Entries of the period that debit or credit cash
+    pub entries: Vec<JournalEntry>,
+    /// Cash balance at the end of the period
+    pub closing_balance: BigDecimal,
+}
+
+/// Builds the accounting ledger from protocol events
+#[derive(Clone)]
+pub struct AccountingService {
+    ledger: AccountingRepository,
+}
+
+impl AccountingService {
+    /// Creates an accounting service
+    pub fn new(db: PgPool) -> Self {
+        Self { ledger: AccountingRepository::new(db) }
+    }
+
+    /// Journal entries dated from the start of `from` to the end of `to`, oldest first
+    pub async fn journal(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<JournalEntry>> {
+        let (start, end) = period_bounds(from, to)?;
+        let events = self.ledger.list_ledger_events(Some(start), end).await?;
+
+        Ok(events.into_iter().map(journal_entry).collect())
+    }
+
+    /// Same as [`journal`](Self::journal), with the entries built as the stream is polled
+    pub fn stream_journal(&self, from: NaiveDate, to: NaiveDate) -> Result<impl Stream<Item = Result<JournalEntry>> + Send + '_> {
+        let (start, end) = period_bounds(from, to)?;
+
+        Ok(self.ledger.stream_ledger_events(Some(start), end).map(|event| event.map(journal_entry)))
+    }
+
+    /// Cash movements from the start of `from` to the end of `to`, with the balance they end at
+    pub async fn cash_statement(&self, from: NaiveDate, to: NaiveDate) -> Result<CashStatement> {
+        let (start, end) = period_bounds(from, to)?;
+        let events = self.ledger.list_ledger_events(None, end).await?;
+
+        let mut closing_balance = BigDecimal::from(0);
+        let mut entries = Vec::new();
+        for event in events {
+            let (debit, credit) = event.kind.accounts();
+            if debit != LedgerAccount::Cash && credit != LedgerAccount::Cash {
+                continue;
+            }
+
+            if debit == LedgerAccount::Cash {
+                closing_balance += &event.amount;
+            } else {
+                closing_balance -= &event.amount;
+            }
+
+            if event.occurred_at >= start {
+                entries.push(journal_entry(event));
+            }
+        }
+
+        Ok(CashStatement { start, end, entries, closing_balance })
+    }
+}
+
+/// Start of `from` and end of `to`, in UTC
+fn period_bounds(from: NaiveDate, to: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+    if to < from {
+        return Err(anyhow!("Period ends on {} before it starts on {}", to, from));
+    }
+
+    let start = from.and_hms_opt(0, 0, 0).ok_or_else(|| anyhow!("Invalid date {}", from))?.and_utc();
+    let end = to.and_hms_opt(0, 0, 0).ok_or_else(|| anyhow!("Invalid date {}", to))?.and_utc() + Duration::days(1);
+
+    Ok((start, end))
+}
+
+/// Balanced entry debiting and crediting the event's accounts with its amount
+fn journal_entry(event: LedgerEvent) -> JournalEntry {
+    let (debit, credit) = event.kind.accounts();
+    let amount = event.amount.normalized().to_string();
+
+    JournalEntry {
+        id: format!("{}-{}", event.kind.name(), event.reference),
+        date: event.occurred_at,
+        kind: event.kind,
+        description: format!("{} {}", description(event.kind), event.reference),
+        lines: vec![
+            JournalLine {
+                account: debit,
+                account_code: debit.code().to_string(),
+                account_name: debit.name().to_string(),
+                debit: amount.clone(),
+                credit: "0".to_string(),
+            },
+            JournalLine {
+                account: credit,
+                account_code: credit.code().to_string(),
+                account_name: credit.name().to_string(),
+                debit: "0".to_string(),
+                credit: amount,
+            },
+        ],
+        reference: event.reference,
+        wallet_address: event.wallet_address,
+    }
+}
+
+/// What an entry of the given kind records
+fn description(kind: JournalEntryKind) -> &'static str {
+    match kind {
+        JournalEntryKind::Deposit => "Deposit processed",
+        JournalEntryKind::Withdrawal => "Withdrawal executed",
+        JournalEntryKind::Borrow => "Borrow paid out",
+        JournalEntryKind::Liquidation => "Borrow liquidated",
+        JournalEntryKind::Fee => "Protocol fee collected",
+        JournalEntryKind::Interest => "Borrow interest accrued",
+        JournalEntryKind::Reward => "Reward credited",
+        JournalEntryKind::RewardClaim => "Reward claimed",
+        JournalEntryKind::RewardExpiry => "Reward expired",
+    }
+}
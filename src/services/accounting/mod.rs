@@ -0,0 +1,12 @@
+//! Double-entry accounting export for LSRWA Express
+//!
+//! Protocol events that move money are mapped to balanced journal entries against a small
+//! chart of accounts (see [`LedgerAccount`](crate::models::accounting::LedgerAccount)), and
+//! exported as journal CSV for import into accounting tools, or as an OFX statement of the cash
+//! account for bank-feed style imports.
+
+mod export;
+mod journal;
+
+pub use export::{csv_rows, render_csv, render_ofx, CSV_HEADER};
+pub use journal::{AccountingService, CashStatement};
@@ -0,0 +1,168 @@
+//! Fans alerts out to the configured channels, throttling repeats
+
+use anyhow::Result;
+use chrono::Utc;
+use metrics::increment_counter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use super::{AlertChannel, AlertState, PagerDutyChannel, SlackChannel, TelegramChannel};
+use crate::config::AlertConfig;
+use crate::models::alert::{ActiveAlert, Alert, AlertSeverity};
+
+/// An active alert with the time it was last sent, for throttling
+struct Tracked {
+    status: ActiveAlert,
+    last_sent: Instant,
+}
+
+/// Raises and resolves operator alerts
+///
+/// Active alerts are tracked per instance. A repeat of an active alert is only sent again once
+/// the throttle window has passed, or straight away if its severity went up.
+#[derive(Clone)]
+pub struct Alerter {
+    channels: Arc<Vec<Box<dyn AlertChannel>>>,
+    throttle: Duration,
+    active: Arc<Mutex<HashMap<String, Tracked>>>,
+}
+
+impl Alerter {
+    /// Creates an alerter sending to the given channels
+    pub fn new(channels: Vec<Box<dyn AlertChannel>>, throttle: Duration) -> Self {
+        Self {
+            channels: Arc::new(channels),
+            throttle,
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Creates an alerter sending to every channel with credentials in the configuration
+    pub fn from_config(config: &AlertConfig) -> Result<Self> {
+        let mut channels: Vec<Box<dyn AlertChannel>> = Vec::new();
+        if let Some(slack) = &config.slack {
+            channels.push(Box::new(SlackChannel::new(slack.clone())?));
+        }
+        if let Some(telegram) = &config.telegram {
+            channels.push(Box::new(TelegramChannel::new(telegram.clone())?));
+        }
+        if let Some(pagerduty) = &config.pagerduty {
+            channels.push(Box::new(PagerDutyChannel::new(pagerduty.clone())?));
+        }
+
+        if channels.is_empty() {
+            warn!("No alert channels configured; alerts will only be logged");
+        } else {
+            let names: Vec<&str> = channels.iter().map(|channel| channel.name()).collect();
+            info!("Sending alerts to {}", names.join(", "));
+        }
+
+        Ok(Self::new(channels, Duration::from_secs(config.throttle_secs)))
+    }
+
+    /// Raises an alert, or repeats it if it is already active
+    pub async fn raise(&self, alert: Alert) {
+        let suppressed = {
+            let mut active = self.active.lock().expect("alert state lock poisoned");
+            let now = Utc::now();
+
+            match active.get_mut(&alert.key) {
+                Some(tracked)
+                    if alert.severity <= tracked.status.alert.severity
+                        && tracked.last_sent.elapsed() < self.throttle =>
+                {
+                    tracked.status.suppressed += 1;
+                    return;
+                },
+                Some(tracked) => {
+                    let suppressed = tracked.status.suppressed;
+                    tracked.status.alert = alert.clone();
+                    tracked.status.last_notified_at = now;
+                    tracked.status.suppressed = 0;
+                    tracked.last_sent = Instant::now();
+                    suppressed
+                },
+                None => {
+                    active.insert(
+                        alert.key.clone(),
+                        Tracked {
+                            status: ActiveAlert {
+                                alert: alert.clone(),
+                                raised_at: now,
+                                last_notified_at: now,
+                                suppressed: 0,
+                            },
+                            last_sent: Instant::now(),
+                        },
+                    );
+                    0
+                },
+            }
+        };
+
+        match alert.severity {
+            AlertSeverity::Critical => error!("Alert {}: {} - {}", alert.key, alert.title, alert.details),
+            AlertSeverity::Warning => warn!("Alert {}: {} - {}", alert.key, alert.title, alert.details),
+            AlertSeverity::Info => info!("Alert {}: {} - {}", alert.key, alert.title, alert.details),
+        }
+        increment_counter!("alerts_raised_total", "severity" => alert.severity.to_string());
+
+        self.deliver(&alert, AlertState::Firing, suppressed).await;
+    }
+
+    /// Clears an active alert, telling the channels that were sent it
+    pub async fn resolve(&self, key: &str) {
+        let Some(tracked) = self.active.lock().expect("alert state lock poisoned").remove(key) else {
+            return;
+        };
+
+        info!("Alert {} resolved: {}", key, tracked.status.alert.title);
+        self.deliver(&tracked.status.alert, AlertState::Resolved, 0).await;
+    }
+
+    /// Alerts raised and not yet resolved, oldest first
+    pub fn active(&self) -> Vec<ActiveAlert> {
+        let mut alerts: Vec<ActiveAlert> = self.active
+            .lock()
+            .expect("alert state lock poisoned")
+            .values()
+            .map(|tracked| tracked.status.clone())
+            .collect();
+        alerts.sort_by_key(|alert| alert.raised_at);
+
+        alerts
+    }
+
+    /// Sends an alert to every channel that accepts its severity, bypassing throttling and
+    /// without tracking it. Returns the channels that failed.
+    pub async fn send_test(&self, severity: AlertSeverity) -> Vec<String> {
+        let alert = Alert {
+            key: "test".to_string(),
+            severity,
+            title: "Test alert".to_string(),
+            details: "Alert channels are configured correctly.".to_string(),
+        };
+
+        self.deliver(&alert, AlertState::Firing, 0).await
+    }
+
+    /// Sends to each channel accepting the alert's severity, returning the channels that failed
+    async fn deliver(&self, alert: &Alert, state: AlertState, suppressed: u32) -> Vec<String> {
+        let mut failed = Vec::new();
+
+        for channel in self.channels.iter().filter(|channel| alert.severity >= channel.min_severity()) {
+            match channel.send(alert, state, suppressed).await {
+                Ok(()) => increment_counter!("alert_notifications_total", "channel" => channel.name(), "status" => "sent"),
+                Err(err) => {
+                    error!("Failed to send alert {} to {}: {:#}", alert.key, channel.name(), err);
+                    increment_counter!("alert_notifications_total", "channel" => channel.name(), "status" => "failed");
+                    failed.push(channel.name().to_string());
+                },
+            }
+        }
+
+        failed
+    }
+}
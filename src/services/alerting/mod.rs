@@ -0,0 +1,52 @@
+//! Operator alerting for LSRWA Express
+//!
+//! Services raise an [`Alert`](crate::models::alert::Alert) when they detect a condition an
+//! operator has to act on (indexer lag, an unreachable RPC node, a failed epoch close, a liquidity
+//! shortfall, treasury drift) and resolve it once the condition clears. The [`Alerter`] fans each
+//! alert out to the configured Slack, Telegram and PagerDuty channels that accept its severity,
+//! and holds back repeats of an alert that is still active.
+
+mod alerter;
+mod monitor;
+mod pagerduty;
+mod slack;
+mod telegram;
+
+pub use alerter::Alerter;
+pub use monitor::AlertMonitorJob;
+pub use pagerduty::PagerDutyChannel;
+pub use slack::SlackChannel;
+pub use telegram::TelegramChannel;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::models::alert::{Alert, AlertSeverity};
+
+/// Whether a notification reports a new or repeated alert, or that it cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// Destination alerts are delivered to
+#[async_trait]
+pub trait AlertChannel: Send + Sync {
+    /// Name used in logs and metrics
+    fn name(&self) -> &'static str;
+
+    /// Lowest severity delivered to this channel
+    fn min_severity(&self) -> AlertSeverity;
+
+    /// Delivers a notification about the alert
+    async fn send(&self, alert: &Alert, state: AlertState, suppressed: u32) -> Result<()>;
+}
+
+/// One-line text summary shared by the chat channels
+fn summary(alert: &Alert, state: AlertState) -> String {
+    match state {
+        AlertState::Firing => format!("[{}] {}", alert.severity.to_string().to_ascii_uppercase(), alert.title),
+        AlertState::Resolved => format!("[RESOLVED] {}", alert.title),
+    }
+}
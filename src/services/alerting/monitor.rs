@@ -0,0 +1,37 @@
+//! Recurring checks for conditions nothing else looks at regularly
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::models::treasury::ReportPeriod;
+use crate::services::liquidity::LiquidityPlanningService;
+use crate::services::scheduler::ScheduledJob;
+use crate::services::treasury::TreasuryService;
+
+/// Rebuilds the liquidity and treasury reports, which raise or resolve their own alerts
+pub struct AlertMonitorJob {
+    liquidity: LiquidityPlanningService,
+    treasury: TreasuryService,
+}
+
+impl AlertMonitorJob {
+    /// Creates the alert monitor job
+    pub fn new(liquidity: LiquidityPlanningService, treasury: TreasuryService) -> Self {
+        Self { liquidity, treasury }
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for AlertMonitorJob {
+    fn name(&self) -> &'static str {
+        "alert_monitor"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.liquidity.report().await?;
+        // Only the balances matter here, so a single period keeps the query small
+        self.treasury.report(ReportPeriod::Month, Some(1)).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! Alerts sent to PagerDuty through the Events API v2
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{AlertChannel, AlertState};
+use crate::config::PagerDutyAlertConfig;
+use crate::models::alert::{Alert, AlertSeverity};
+
+/// PagerDuty client implementing [`AlertChannel`]
+pub struct PagerDutyChannel {
+    config: PagerDutyAlertConfig,
+    client: reqwest::Client,
+}
+
+impl PagerDutyChannel {
+    /// Creates a PagerDuty channel
+    pub fn new(config: PagerDutyAlertConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build PagerDuty HTTP client")?;
+
+        Ok(Self { config, client })
+    }
+}
+
+#[async_trait]
+impl AlertChannel for PagerDutyChannel {
+    fn name(&self) -> &'static str {
+        "pagerduty"
+    }
+
+    fn min_severity(&self) -> AlertSeverity {
+        self.config.min_severity
+    }
+
+    async fn send(&self, alert: &Alert, state: AlertState, suppressed: u32) -> Result<()> {
+        // PagerDuty deduplicates on the alert key, so repeats update the open incident and a
+        // resolve closes it
+        let event = match state {
+            AlertState::Firing => json!({
+                "routing_key": self.config.routing_key,
+                "event_action": "trigger",
+                "dedup_key": alert.key,
+                "payload": {
+                    "summary": alert.title,
+                    "source": "lsrwa-express",
+                    "severity": alert.severity.to_string(),
+                    "custom_details": {
+                        "details": alert.details,
+                        "suppressed_repeats": suppressed,
+                    },
+                },
+            }),
+            AlertState::Resolved => json!({
+                "routing_key": self.config.routing_key,
+                "event_action": "resolve",
+                "dedup_key": alert.key,
+            }),
+        };
+
+        let response = self.client
+            .post(format!("{}/v2/enqueue", self.config.base_url.trim_end_matches('/')))
+            .json(&event)
+            .send()
+            .await
+            .context("PagerDuty request failed")?;
+
+        if !response.status().is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow!("PagerDuty rejected the event: {}", detail));
+        }
+
+        Ok(())
+    }
+}
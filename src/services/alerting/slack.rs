@@ -0,0 +1,69 @@
+//! Alerts posted to a Slack incoming webhook
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{summary, AlertChannel, AlertState};
+use crate::config::SlackAlertConfig;
+use crate::models::alert::{Alert, AlertSeverity};
+
+/// Slack client implementing [`AlertChannel`]
+pub struct SlackChannel {
+    config: SlackAlertConfig,
+    client: reqwest::Client,
+}
+
+impl SlackChannel {
+    /// Creates a Slack channel
+    pub fn new(config: SlackAlertConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Slack HTTP client")?;
+
+        Ok(Self { config, client })
+    }
+}
+
+#[async_trait]
+impl AlertChannel for SlackChannel {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn min_severity(&self) -> AlertSeverity {
+        self.config.min_severity
+    }
+
+    async fn send(&self, alert: &Alert, state: AlertState, suppressed: u32) -> Result<()> {
+        let icon = match (state, alert.severity) {
+            (AlertState::Resolved, _) => ":white_check_mark:",
+            (_, AlertSeverity::Critical) => ":rotating_light:",
+            (_, AlertSeverity::Warning) => ":warning:",
+            (_, AlertSeverity::Info) => ":information_source:",
+        };
+
+        let mut text = format!("{} *{}*", icon, summary(alert, state));
+        if state == AlertState::Firing {
+            text.push_str(&format!("\n{}", alert.details));
+            if suppressed > 0 {
+                text.push_str(&format!("\n_Repeated {} times since the last notice_", suppressed));
+            }
+        }
+
+        let response = self.client
+            .post(&self.config.webhook_url)
+            .json(&json!({ "text": text }))
+            .send()
+            .await
+            .context("Slack request failed")?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Slack responded with {}", response.status()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+//! Alerts sent to a Telegram chat by a bot
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{summary, AlertChannel, AlertState};
+use crate::config::TelegramAlertConfig;
+use crate::models::alert::{Alert, AlertSeverity};
+
+/// Telegram Bot API client implementing [`AlertChannel`]
+pub struct TelegramChannel {
+    config: TelegramAlertConfig,
+    client: reqwest::Client,
+}
+
+impl TelegramChannel {
+    /// Creates a Telegram channel
+    pub fn new(config: TelegramAlertConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Telegram HTTP client")?;
+
+        Ok(Self { config, client })
+    }
+}
+
+#[async_trait]
+impl AlertChannel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn min_severity(&self) -> AlertSeverity {
+        self.config.min_severity
+    }
+
+    async fn send(&self, alert: &Alert, state: AlertState, suppressed: u32) -> Result<()> {
+        // Sent as plain text, so details need no escaping
+        let mut text = summary(alert, state);
+        if state == AlertState::Firing {
+            text.push_str(&format!("\n\n{}", alert.details));
+            if suppressed > 0 {
+                text.push_str(&format!("\n\nRepeated {} times since the last notice", suppressed));
+            }
+        }
+
+        let response = self.client
+            .post(format!(
+                "{}/bot{}/sendMessage",
+                self.config.base_url.trim_end_matches('/'),
+                self.config.bot_token
+            ))
+            .json(&json!({
+                "chat_id": self.config.chat_id,
+                "text": text,
+                "disable_web_page_preview": true,
+            }))
+            .send()
+            .await
+            // The URL carries the bot token, so it is left out of the error
+            .map_err(|err| anyhow!("Telegram request failed: {}", err.without_url()))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Telegram responded with {}", response.status()));
+        }
+
+        Ok(())
+    }
+}
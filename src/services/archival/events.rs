@@ -0,0 +1,155 @@
+//! Export of raw indexed events to object storage
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use metrics::counter;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::io::Write;
+use tracing::info;
+
+use crate::config::EventArchiveConfig;
+use crate::db::ArchiveRepository;
+use crate::models::archive::{ArchivedEvent, EventArchive};
+use crate::services::indexer::EventType;
+use crate::services::scheduler::ScheduledJob;
+use crate::services::storage::{ObjectStorage, ServerSideEncryption};
+
+/// Format of the exported files
+const FORMAT: &str = "jsonl.gz";
+
+/// Events read per query while building a file
+const PAGE_SIZE: i64 = 5000;
+
+/// Most days exported per run, so catching up after downtime is spread over several runs
+const MAX_DAYS_PER_RUN: usize = 31;
+
+/// Exports each finished UTC day of indexed events as a gzipped JSON Lines file, one event per
+/// line, and records it in the `event_archives` manifest. Days are exported in order and only
+/// once they are over, so a file never misses events indexed later on its day.
+pub struct EventArchiveJob {
+    archive: ArchiveRepository,
+    storage: ObjectStorage,
+    bucket: String,
+    prefix: String,
+}
+
+impl EventArchiveJob {
+    /// Creates the export job from configuration
+    pub fn new(db: PgPool, config: EventArchiveConfig) -> Result<Self> {
+        let encryption = match config.kms_key_id {
+            Some(key_id) => ServerSideEncryption::Kms { key_id },
+            None => ServerSideEncryption::Aes256,
+        };
+
+        Ok(Self {
+            archive: ArchiveRepository::new(db),
+            storage: ObjectStorage::new(config.s3, config.bucket.clone(), encryption)?,
+            bucket: config.bucket,
+            prefix: config.prefix,
+        })
+    }
+
+    /// Exports the events indexed on `day`, returning the manifest entry, or `None` when no
+    /// events were indexed that day
+    pub async fn export_day(&self, day: NaiveDate) -> Result<Option<EventArchive>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let mut event_count = 0;
+        let mut first_block = i64::MAX;
+        let mut last_block = i64::MIN;
+        let mut after = None;
+
+        loop {
+            let events = self.archive.list_events_indexed_on(day, after.take(), PAGE_SIZE).await?;
+            let Some(last) = events.last() else {
+                break;
+            };
+            after = Some((last.created_at, last.id.clone()));
+            let full_page = events.len() as i64 == PAGE_SIZE;
+
+            for event in events {
+                first_block = first_block.min(event.block_number);
+                last_block = last_block.max(event.block_number);
+                event_count += 1;
+
+                let line = ArchivedEvent {
+                    event_name: EventType::from_code(event.event_type).map(|event_type| event_type.name().to_string()),
+                    raw_data: event.raw_data.to_string(),
+                    id: event.id,
+                    event_type: event.event_type,
+                    block_number: event.block_number,
+                    transaction_hash: event.transaction_hash,
+                    request_id: event.request_id,
+                    wallet_address: event.wallet_address,
+                    amount: event.amount,
+                    request_type: event.request_type,
+                    timestamp: event.timestamp,
+                    indexed_at: event.created_at,
+                    data: Some(event.raw_data),
+                };
+                serde_json::to_writer(&mut encoder, &line).context("Failed to serialize archived event")?;
+                encoder.write_all(b"\n").context("Failed to compress archived events")?;
+            }
+
+            if !full_page {
+                break;
+            }
+        }
+
+        if event_count == 0 {
+            return Ok(None);
+        }
+
+        let body = encoder.finish().context("Failed to compress archived events")?;
+        let checksum = hex::encode(Sha256::digest(&body));
+        let byte_size = body.len() as i64;
+        let object_key = format!("{}/date={}/events-{}.{}", self.prefix, day, day, FORMAT);
+
+        self.storage.put_object(&object_key, body, "application/gzip").await?;
+
+        let archive = self.archive
+            .record_event_archive(&EventArchive {
+                day,
+                bucket: self.bucket.clone(),
+                object_key,
+                format: FORMAT.to_string(),
+                event_count,
+                first_block,
+                last_block,
+                byte_size,
+                checksum,
+                created_at: Utc::now(),
+            })
+            .await?;
+
+        info!("Exported {} events indexed on {} to {}", event_count, day, archive.object_key);
+        counter!("event_archive_events_exported_total", event_count as u64);
+
+        Ok(Some(archive))
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for EventArchiveJob {
+    fn name(&self) -> &'static str {
+        "event_archive"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let today = Utc::now().date_naive();
+
+        for _ in 0..MAX_DAYS_PER_RUN {
+            let Some(day) = self.archive.next_unexported_event_day(today).await? else {
+                break;
+            };
+            if self.export_day(day).await?.is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Retention and archival of high-volume tables
+//!
+//! `event_queue` and `activity_logs` are partitioned by month. A background worker keeps
+//! partitions created ahead of time and moves those past their retention window to the
+//! `lsrwa_express_archive` schema; processed `blockchain_requests` are moved there in batches.
+//!
+//! Independently of retention, [`EventArchiveJob`] exports every day of raw indexed events to
+//! object storage, so the event history can be rebuilt or analyzed after it is pruned.
+
+mod events;
+mod worker;
+
+pub use events::EventArchiveJob;
+pub use worker::ArchivalWorker;
@@ -0,0 +1,87 @@
+//! Background worker applying the retention policy
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info};
+
+use crate::config::RetentionConfig;
+use crate::db::archive_repository::{ArchiveRepository, PARTITIONED_TABLES};
+use crate::services::shutdown::Shutdown;
+
+/// Number of blockchain requests moved per statement
+const REQUEST_BATCH_SIZE: u32 = 1000;
+
+/// Worker that maintains partitions and archives expired data
+pub struct ArchivalWorker {
+    /// Partition and archive persistence
+    archive: ArchiveRepository,
+    /// Retention settings
+    config: RetentionConfig,
+}
+
+impl ArchivalWorker {
+    /// Creates a new archival worker
+    pub fn new(db: PgPool, config: RetentionConfig) -> Self {
+        Self {
+            archive: ArchiveRepository::new(db),
+            config,
+        }
+    }
+
+    /// Runs the archival loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        info!("Starting archival worker with interval {} seconds", self.config.archival_interval_secs);
+
+        let mut interval = time::interval(Duration::from_secs(self.config.archival_interval_secs));
+
+        while shutdown.tick(&mut interval).await {
+            if let Err(err) = self.run_once().await {
+                error!("Archival run failed: {}", err);
+            }
+        }
+
+        info!("Archival worker stopped");
+        Ok(())
+    }
+
+    /// Creates upcoming partitions and archives everything past its retention window
+    pub async fn run_once(&self) -> Result<()> {
+        for table in PARTITIONED_TABLES {
+            self.archive.ensure_partitions(table, self.config.partitions_ahead_months).await?;
+
+            if let Some(retention_days) = self.config.retention_days(table) {
+                let older_than = Utc::now() - ChronoDuration::days(retention_days.into());
+                let archived = self.archive.archive_partitions(table, older_than).await?;
+
+                if archived > 0 {
+                    info!("Archived {} partitions of {}", archived, table);
+                }
+            }
+        }
+
+        if let Some(retention_days) = self.config.retention_days("blockchain_requests") {
+            let older_than = Utc::now() - ChronoDuration::days(retention_days.into());
+            let mut total = 0;
+
+            loop {
+                let archived = self.archive
+                    .archive_blockchain_requests(older_than, REQUEST_BATCH_SIZE)
+                    .await?;
+                total += archived;
+
+                if archived < REQUEST_BATCH_SIZE {
+                    break;
+                }
+            }
+
+            if total > 0 {
+                info!("Archived {} finished blockchain requests", total);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! Audit trail of privileged operations
+//!
+//! Admin requests run inside an [`AuditContext`] naming the operator, their IP and the request
+//! ID, so anything recorded while handling the request, down to the extrinsics the blockchain
+//! service submits, is attributed to them. Work started outside a request, by the scheduler or
+//! background workers, is attributed to `system`.
+//!
+//! Recording never fails the operation: by the time it's recorded the change has happened, so
+//! a failed write is logged and counted instead.
+
+use metrics::increment_counter;
+use sqlx::PgPool;
+use std::future::Future;
+use tracing::error;
+
+use crate::db::AuditRepository;
+use crate::models::audit::{AuditEntry, AuditFilter, NewAuditEntry};
+
+tokio::task_local! {
+    static CONTEXT: AuditContext;
+}
+
+/// Who is performing the current operation
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: String,
+    pub ip_address: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl AuditContext {
+    /// Context of work the service starts on its own
+    pub fn system() -> Self {
+        Self {
+            actor: "system".to_string(),
+            ip_address: None,
+            request_id: None,
+        }
+    }
+
+    /// Context of the operation running on this task
+    pub fn current() -> Self {
+        CONTEXT.try_with(Clone::clone).unwrap_or_else(|_| Self::system())
+    }
+
+    /// Runs `operation` in this context. Tasks it spawns don't inherit the context.
+    pub async fn scope<F: Future>(self, operation: F) -> F::Output {
+        CONTEXT.scope(self, operation).await
+    }
+}
+
+/// Records privileged operations in the admin audit log
+#[derive(Clone)]
+pub struct AuditLog {
+    repository: AuditRepository,
+}
+
+impl AuditLog {
+    /// Creates an audit log writing to `db`
+    pub fn new(db: PgPool) -> Self {
+        Self { repository: AuditRepository::new(db) }
+    }
+
+    /// Records an operation, attributed to the current context
+    pub async fn record(&self, entry: NewAuditEntry) {
+        if let Err(err) = self.repository.record(&AuditContext::current(), &entry).await {
+            error!(target = %entry.target, "Failed to record audit entry: {:#}", err);
+            increment_counter!("audit_write_failures_total");
+        }
+    }
+
+    /// Lists recorded operations, newest first
+    pub async fn list(&self, filter: &AuditFilter) -> anyhow::Result<Vec<AuditEntry>> {
+        self.repository.list(filter).await
+    }
+}
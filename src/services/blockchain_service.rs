@@ -1,22 +1,38 @@
 use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
 use subxt::{
+    blocks::ExtrinsicEvents,
+    dynamic::At,
     tx::PairSigner, 
     OnlineClient, 
     PolkadotConfig,
     utils::AccountId32,
-    ext::sp_core::{sr25519, Pair as PairTrait, H256}
+    events::{EventDetails, Phase},
+    ext::sp_core::{hashing::blake2_256, sr25519, Pair as PairTrait, H256}
 };
-use std::sync::Arc;
-use std::str::FromStr;
-use tokio::sync::RwLock;
-use tracing::info;
 use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{info, warn};
+use scale::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 use serde_json;
 
-use crate::api::blockchain::{BlockchainState, BlockchainStateManager, OnChainRequest};
+use crate::api::blockchain::OnChainRequest;
+use crate::config::BlockchainConfig;
+use crate::models::amount::Amount;
+use crate::models::asset::Asset;
 use crate::models::blockchain_request::{RequestType, NewBlockchainRequest};
-use crate::db::DbPools;
-use crate::contract::{self, LsrwaExpressContract};
+use crate::models::request_status::RequestStatus;
+use crate::models::wallet::WalletAddress;
+use crate::db::{AssetRepository, BlockchainRequestRepository, DbPools, DeploymentRepository};
+use crate::contract::{self, ContractSigner, LsrwaExpressContract};
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::services::audit::AuditLog;
+use crate::services::indexer::{decode_contract_event, ContractEmitted};
+use crate::services::keystore;
+use crate::services::rpc_cache::RpcCache;
+use crate::services::secrets::SecretStore;
 
 /// Event data structure
 #[derive(Debug, Clone)]
@@ -28,15 +44,158 @@ pub struct BlockchainEvent {
     pub data: serde_json::Value,
 }
 
+/// A single item of a batch submission
+#[derive(Debug, Clone)]
+pub struct BatchSubmissionItem {
+    pub request_type: RequestType,
+    pub wallet_address: WalletAddress,
+    /// Asset `amount` is in
+    pub asset: Asset,
+    pub amount: Amount,
+}
+
+/// An admin transaction included on-chain
+#[derive(Debug, Clone)]
+pub struct SubmittedTransaction {
+    pub transaction_hash: String,
+    pub block_number: u64,
+}
+
+/// What the contract holds at a block, as read for snapshots. Amounts are in on-chain units.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContractState {
+    pub contract_address: String,
+    pub block_number: u64,
+    pub owner: String,
+    pub treasury: String,
+    pub balance: String,
+    pub total_pending_deposits: String,
+    pub total_pending_withdrawals: String,
+    pub min_deposit_amount: String,
+    pub min_withdrawal_amount: String,
+    pub min_collateral_ratio: String,
+}
+
+/// A request as the contract stores it. Amounts are in on-chain units.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ContractRequest {
+    pub id: u128,
+    pub request_type: RequestType,
+    pub wallet_address: WalletAddress,
+    pub amount: Amount,
+    /// Collateral pledged for a borrow, zero for deposits and withdrawals
+    pub collateral: Amount,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Whether an epoch batch has processed the request
+    pub is_processed: bool,
+}
+
+/// What the contract's `get_request` message returns
+#[derive(Decode)]
+struct StoredRequest {
+    id: u128,
+    request_type: RequestType,
+    wallet_address: AccountId32,
+    amount: u128,
+    collateral: u128,
+    /// Milliseconds since the epoch
+    timestamp: u64,
+    is_processed: bool,
+}
+
+impl TryFrom<StoredRequest> for ContractRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(stored: StoredRequest) -> Result<Self> {
+        let timestamp = i64::try_from(stored.timestamp)
+            .ok()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .with_context(|| format!("Request {} has an out-of-range timestamp {}", stored.id, stored.timestamp))?;
+
+        Ok(Self {
+            id: stored.id,
+            request_type: stored.request_type,
+            wallet_address: WalletAddress::from(stored.wallet_address),
+            amount: Amount::from_units(stored.amount),
+            collateral: Amount::from_units(stored.collateral),
+            timestamp,
+            is_processed: stored.is_processed,
+        })
+    }
+}
+
+/// Calls the services make on the node and contract
+///
+/// Implemented by `BlockchainService`; the API and background services hold it as a trait
+/// object, so they can run against a stand-in for the chain in tests.
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    /// Asset the contract holds, which amounts are read and shown in
+    fn asset(&self) -> Asset;
+
+    /// Decimals of the contract's asset
+    fn token_decimals(&self) -> u32 {
+        self.asset().decimal_places()
+    }
+
+    /// Submits a deposit of `amount` of `asset`, recording it in that asset
+    async fn submit_deposit_request(&self, wallet_address: &str, asset: &Asset, amount: Amount) -> Result<OnChainRequest>;
+
+    /// Submits a withdrawal of `amount` of `asset`, recording it in that asset
+    async fn submit_withdrawal_request(&self, wallet_address: &str, asset: &Asset, amount: Amount) -> Result<OnChainRequest>;
+
+    /// Submits a borrow of `amount` of `asset` against `collateral_amount` of it
+    async fn submit_borrow_request(
+        &self,
+        wallet_address: &str,
+        asset: &Asset,
+        amount: Amount,
+        collateral_amount: Amount,
+    ) -> Result<OnChainRequest>;
+
+    /// Submits each item, returning an outcome per item in order; with `use_utility_batch` as one
+    /// `Utility::batch` extrinsic signed by the items' wallet
+    async fn submit_batch_requests(&self, items: &[BatchSubmissionItem], use_utility_batch: bool) -> Vec<Result<OnChainRequest>>;
+
+    /// Adds wallets to the contract's KYC allowlist, returning the transaction hash
+    async fn submit_kyc_approvals(&self, wallet_addresses: &[String]) -> Result<String>;
+
+    async fn process_request_batch(&self, request_type: &RequestType, request_ids: &[i64]) -> Result<SubmittedTransaction>;
+
+    async fn close_current_epoch(&self) -> Result<SubmittedTransaction>;
+
+    async fn liquidate_borrow(&self, request_id: i64) -> Result<SubmittedTransaction>;
+
+    async fn set_risk_parameters(
+        &self,
+        min_deposit_amount: u128,
+        min_withdrawal_amount: u128,
+        min_collateral_ratio: u128,
+    ) -> Result<SubmittedTransaction>;
+
+    /// The contract's free balance, in tokens
+    async fn get_contract_balance(&self) -> Result<BigDecimal>;
+
+    /// The free balance of an SS58 account, in tokens
+    async fn get_account_balance(&self, address: &str) -> Result<BigDecimal>;
+
+    /// A raw oracle value and its timestamp in milliseconds, if one is stored
+    async fn read_oracle_value(&self, pallet: &str, storage_entry: &str, key: &str) -> Result<Option<(u128, u64)>>;
+
+    /// A request as the contract stores it, if it exists
+    async fn get_request(&self, request_id: u128) -> Result<Option<ContractRequest>>;
+
+    async fn get_current_block_number(&self) -> Result<u64>;
+
+    async fn get_events_for_block(&self, block_number: u64) -> Result<Vec<BlockchainEvent>>;
+}
+
 /// Service for interacting with the blockchain
 #[derive(Clone)]
 pub struct BlockchainService {
     /// Database connection pools
     db: DbPools,
     
-    /// Blockchain state
-    blockchain_state: Arc<RwLock<BlockchainState>>,
-    
     /// Blockchain client
     client: Arc<OnlineClient<PolkadotConfig>>,
     
@@ -47,118 +206,149 @@ pub struct BlockchainService {
     #[cfg(target_arch = "wasm32")]
     contract: Arc<LsrwaExpressContract>,
     
-    /// RPC URL for the testnet node
-    rpc_url: String,
+    /// Node and contract configuration
+    config: BlockchainConfig,
+    
+    /// Seed phrases of the signing accounts
+    secrets: SecretStore,
+    
+    /// Records the extrinsics the contract owner signs
+    audit: AuditLog,
+    
+    /// Reads already made at a block
+    rpc_cache: RpcCache,
+    
+    /// Asset the contract holds
+    asset: Asset,
 }
 
 impl BlockchainService {
     /// Creates a new blockchain service
-    pub async fn new(db: DbPools, blockchain_state: Arc<RwLock<BlockchainState>>) -> Result<Self> {
-        // Get the RPC URL from environment variables or use default testnet URL
-        let rpc_url = std::env::var("SUBSTRATE_RPC_URL")
-            .unwrap_or_else(|_| "wss://rococo-contracts-rpc.polkadot.io".to_string());
-        
-        info!("Connecting to blockchain node at {}", rpc_url);
+    pub async fn new(
+        db: DbPools,
+        mut config: BlockchainConfig,
+        secrets: SecretStore,
+    ) -> Result<Self> {
+        info!("Connecting to blockchain node at {}", config.rpc_url);
         
         // Connect to the blockchain node
         let client = Arc::new(
-            OnlineClient::<PolkadotConfig>::from_url(rpc_url.clone())
+            OnlineClient::<PolkadotConfig>::from_url(config.rpc_url.clone())
                 .await
                 .context("Failed to connect to blockchain node")?
         );
         
-        // Get the contract address from environment variables
-        let contract_address_str = std::env::var("CONTRACT_ADDRESS")
-            .context("CONTRACT_ADDRESS environment variable not set")?;
-        
-        info!("Using contract address: {}", contract_address_str);
+        // A configured address wins; otherwise the deployment registry says which contract is live
+        let contract_address = match &config.contract_address {
+            Some(address) => address.clone(),
+            None => {
+                let deployment = DeploymentRepository::new(db.pg.clone())
+                    .live(&config.network.to_string())
+                    .await?
+                    .with_context(|| {
+                        format!("No live contract deployment is registered for {}; deploy one or set CONTRACT_ADDRESS", config.network)
+                    })?;
+                let genesis = format!("{:?}", client.genesis_hash());
+                if deployment.chain_genesis_hash != genesis {
+                    return Err(anyhow!(
+                        "The live {} deployment is on the chain with genesis {}, not the node's {}",
+                        config.network,
+                        deployment.chain_genesis_hash,
+                        genesis
+                    ));
+                }
+                deployment.contract_address
+            },
+        };
+        info!("Using contract address: {}", contract_address);
         
         // Create the contract interface
         let contract_result = contract::create_contract_interface(
             client.as_ref().clone(),
-            &contract_address_str
+            &contract_address
         ).await;
+        config.contract_address = Some(contract_address);
         
         let contract = Arc::new(contract_result.map_err(|e| anyhow!("Failed to create contract interface: {}", e))?);
         
+        let audit = AuditLog::new(db.pg.clone());
+        let rpc_cache = RpcCache::new(config.rpc_cache_entries);
+        
+        // Amounts are converted with the decimals of the asset the contract holds
+        let assets = AssetRepository::new(db.pg.clone());
+        assets.sync_native_decimals(config.network.token_decimals()).await?;
+        let asset = assets.default_asset().await?;
+        info!("Amounts are denominated in {} ({} decimals)", asset.symbol, asset.decimals);
+        
         Ok(Self {
             db,
-            blockchain_state,
             client,
             contract,
-            rpc_url,
+            config,
+            secrets,
+            audit,
+            rpc_cache,
+            asset,
         })
     }
     
-    /// Submits a deposit request to the blockchain
+    /// Node and contract configuration, with the contract address resolved
+    pub fn config(&self) -> &BlockchainConfig {
+        &self.config
+    }
+    
+    /// Asset the contract holds
+    pub fn asset(&self) -> &Asset {
+        &self.asset
+    }
+    
+    /// Decimals of the contract's asset
+    pub fn token_decimals(&self) -> u32 {
+        self.asset.decimal_places()
+    }
+    
+    /// Submits a deposit request to the blockchain, recorded in `asset`
     pub async fn submit_deposit_request(
         &self,
         wallet_address: &str,
-        amount: f64,
+        asset: &Asset,
+        amount: Amount,
     ) -> Result<OnChainRequest> {
-        info!("Submitting deposit request for wallet {} with amount {}", wallet_address, amount);
+        let decimals = asset.decimal_places();
+        info!("Submitting deposit request for wallet {} with amount {}", wallet_address, amount.format(decimals));
         
-        // Convert amount to on-chain format (fixed point with 12 decimals for UNIT)
-        let on_chain_amount = (amount * 1_000_000_000_000.0) as u128;
+        let on_chain_amount = amount.units();
         
         // Get the blockchain account for the wallet
-        let account_pair = self.get_account_from_wallet(wallet_address)
+        let account_pair = self.get_account_from_wallet(wallet_address).await
             .context("Failed to get blockchain account from wallet address")?;
         
-        #[cfg(not(target_arch = "wasm32"))]
-        let _signer: PairSigner<PolkadotConfig, sr25519::Pair> = PairSigner::new(account_pair.clone());
-        
-        #[cfg(target_arch = "wasm32")]
-        let signer = PairSigner::new(account_pair.clone());
-        
         // Estimate gas for the call
         let gas_limit = contract::estimate_gas_for_deposit_request(on_chain_amount);
         info!("Estimated gas for deposit request: {}", gas_limit);
         
-        // Call the contract using our type-safe bindings
-        #[cfg(not(target_arch = "wasm32"))]
-        let tx_hash = {
-            if cfg!(debug_assertions) {
-                // In debug mode, generate a fake hash for testing
-                info!("Debug mode: Using fake transaction hash");
-                H256::from_slice(&[1; 32])
-            } else {
-                // In non-debug mode, this would fail because we can't actually call the contract
-                // But we'll just use a fake hash for now
-                H256::from_slice(&[1; 32])
-            }
-        };
+        let events = self
+            .submit_contract_call::<_, u128>(account_pair, "create_deposit_request", (on_chain_amount,), gas_limit)
+            .await?;
         
-        #[cfg(target_arch = "wasm32")]
-        let tx_hash = self.contract.create_deposit_request(&signer, on_chain_amount, gas_limit)
-            .await
-            .context("Failed to call contract create_deposit_request")?;
-        
-        // Get the block the transaction was included in
-        let tx_block = self.get_transaction_block(&tx_hash).await
-            .context("Failed to get transaction block")?;
-        
-        // Get the BlockchainStateManager
-        let _blockchain_manager = BlockchainStateManager::new(self.blockchain_state.clone());
-        
-        // For development, use a simple counter as the request ID
-        let request_id = chrono::Utc::now().timestamp() as u128;
+        // The contract assigns the request ID, and names it in the event it emits
+        let requested = self.emitted_event(&events, "DepositRequested").await?;
+        let request_id = requested_id(&requested)?;
         
-        // Create the request with actual transaction data
         let request = OnChainRequest {
             id: request_id,
             request_type: RequestType::Deposit,
             wallet_address: wallet_address.to_string(),
-            amount: amount.to_string(),
+            amount: amount.format(decimals),
             collateral_amount: None,
-            timestamp: chrono::Utc::now(),
-            is_processed: false,
-            block_number: tx_block as u64,
-            transaction_hash: format!("0x{}", hex::encode(tx_hash.as_ref())),
+            timestamp: requested.timestamp,
+            status: RequestStatus::Submitted,
+            block_number: requested.block_number,
+            transaction_hash: requested.transaction_hash,
         };
         
         // Store the request in the database
-        self.store_deposit_request_in_db(&request).await
+        self.store_request_in_db(&request, asset).await
             .context("Failed to store deposit request in database")?;
         
         info!("Deposit request submitted successfully with ID {} and tx hash {}", request_id, request.transaction_hash);
@@ -166,75 +356,48 @@ impl BlockchainService {
         Ok(request)
     }
     
-    /// Submits a withdrawal request to the blockchain
+    /// Submits a withdrawal request to the blockchain, recorded in `asset`
     pub async fn submit_withdrawal_request(
         &self,
         wallet_address: &str,
-        amount: f64,
+        asset: &Asset,
+        amount: Amount,
     ) -> Result<OnChainRequest> {
-        info!("Submitting withdrawal request for wallet {} with amount {}", wallet_address, amount);
+        let decimals = asset.decimal_places();
+        info!("Submitting withdrawal request for wallet {} with amount {}", wallet_address, amount.format(decimals));
         
-        // Convert amount to on-chain format (fixed point with 12 decimals for UNIT)
-        let on_chain_amount = (amount * 1_000_000_000_000.0) as u128;
+        let on_chain_amount = amount.units();
         
         // Get the blockchain account for the wallet
-        let account_pair = self.get_account_from_wallet(wallet_address)
+        let account_pair = self.get_account_from_wallet(wallet_address).await
             .context("Failed to get blockchain account from wallet address")?;
         
-        #[cfg(not(target_arch = "wasm32"))]
-        let _signer: PairSigner<PolkadotConfig, sr25519::Pair> = PairSigner::new(account_pair.clone());
-        
-        #[cfg(target_arch = "wasm32")]
-        let signer = PairSigner::new(account_pair.clone());
-        
         // Estimate gas for the call
         let gas_limit = contract::estimate_gas_for_withdrawal_request(on_chain_amount);
         info!("Estimated gas for withdrawal request: {}", gas_limit);
         
-        // Call the contract using our type-safe bindings
-        #[cfg(not(target_arch = "wasm32"))]
-        let tx_hash = {
-            if cfg!(debug_assertions) {
-                // In debug mode, generate a fake hash for testing
-                info!("Debug mode: Using fake transaction hash");
-                H256::from_slice(&[2; 32]) // Use a different pattern than deposit for easier identification
-            } else {
-                // In non-debug mode, this would fail because we can't actually call the contract
-                // But we'll just use a fake hash for now
-                H256::from_slice(&[2; 32])
-            }
-        };
-        
-        #[cfg(target_arch = "wasm32")]
-        let tx_hash = self.contract.create_withdrawal_request(&signer, on_chain_amount, gas_limit)
-            .await
-            .context("Failed to call contract create_withdrawal_request")?;
-        
-        // Get the block the transaction was included in
-        let tx_block = self.get_transaction_block(&tx_hash).await
-            .context("Failed to get transaction block")?;
+        let events = self
+            .submit_contract_call::<_, u128>(account_pair, "create_withdrawal_request", (on_chain_amount,), gas_limit)
+            .await?;
         
-        // Get the BlockchainStateManager
-        let _blockchain_manager = BlockchainStateManager::new(self.blockchain_state.clone());
+        // The contract assigns the request ID, and names it in the event it emits
+        let requested = self.emitted_event(&events, "WithdrawalRequested").await?;
+        let request_id = requested_id(&requested)?;
         
-        // For development, use a simple counter as the request ID
-        let request_id = chrono::Utc::now().timestamp() as u128;
-        
-        // Create the request with actual transaction data
         let request = OnChainRequest {
             id: request_id,
             request_type: RequestType::Withdrawal,
             wallet_address: wallet_address.to_string(),
-            amount: amount.to_string(),
+            amount: amount.format(decimals),
             collateral_amount: None,
-            timestamp: chrono::Utc::now(),
-            is_processed: false,
-            block_number: tx_block as u64,
-            transaction_hash: format!("0x{}", hex::encode(tx_hash.as_ref())),
+            timestamp: requested.timestamp,
+            status: RequestStatus::Submitted,
+            block_number: requested.block_number,
+            transaction_hash: requested.transaction_hash,
         };
         
         // Store the request in the database
-        self.store_withdrawal_request_in_db(&request).await
+        self.store_request_in_db(&request, asset).await
             .context("Failed to store withdrawal request in database")?;
         
         info!("Withdrawal request submitted successfully with ID {} and tx hash {}", request_id, request.transaction_hash);
@@ -242,19 +405,613 @@ impl BlockchainService {
         Ok(request)
     }
     
-    /// Gets the block number a transaction was included in
-    async fn get_transaction_block(&self, _tx_hash: &H256) -> Result<u32> {
-        // In a real implementation, we would query the chain for the transaction's block
-        // For development purposes, just return the current block number
+    /// Submits a borrow request backed by `collateral_amount` to the blockchain, both in `asset`
+    pub async fn submit_borrow_request(
+        &self,
+        wallet_address: &str,
+        asset: &Asset,
+        amount: Amount,
+        collateral_amount: Amount,
+    ) -> Result<OnChainRequest> {
+        let decimals = asset.decimal_places();
+        info!(
+            "Submitting borrow request for wallet {} with amount {} and collateral {}",
+            wallet_address, amount.format(decimals), collateral_amount.format(decimals)
+        );
+        
+        let on_chain_amount = amount.units();
+        let on_chain_collateral = collateral_amount.units();
+        
+        // Get the blockchain account for the wallet
+        let account_pair = self.get_account_from_wallet(wallet_address).await
+            .context("Failed to get blockchain account from wallet address")?;
+        
+        // Estimate gas for the call
+        let gas_limit = contract::estimate_gas_for_borrow_request(on_chain_amount, on_chain_collateral);
+        info!("Estimated gas for borrow request: {}", gas_limit);
+        
+        let events = self
+            .submit_contract_call::<_, u128>(account_pair, "create_borrow_request", (on_chain_amount, on_chain_collateral), gas_limit)
+            .await?;
+        
+        // The contract assigns the request ID, and names it in the event it emits
+        let requested = self.emitted_event(&events, "BorrowRequested").await?;
+        let request_id = requested_id(&requested)?;
+        
+        let request = OnChainRequest {
+            id: request_id,
+            request_type: RequestType::Borrow,
+            wallet_address: wallet_address.to_string(),
+            amount: amount.format(decimals),
+            collateral_amount: Some(collateral_amount.format(decimals)),
+            timestamp: requested.timestamp,
+            status: RequestStatus::Submitted,
+            block_number: requested.block_number,
+            transaction_hash: requested.transaction_hash,
+        };
+        
+        // Store the request in the database
+        self.store_request_in_db(&request, asset).await
+            .context("Failed to store borrow request in database")?;
+        
+        info!("Borrow request submitted successfully with ID {} and tx hash {}", request_id, request.transaction_hash);
+        
+        Ok(request)
+    }
+    
+    /// Submits several deposit/withdrawal requests in one call
+    ///
+    /// Each item is submitted as its own extrinsic, signed by its wallet, unless
+    /// `use_utility_batch` wraps them all in one `Utility::batch` extrinsic. One result is
+    /// returned per item, in order, so callers can report partial failures.
+    pub async fn submit_batch_requests(
+        &self,
+        items: &[BatchSubmissionItem],
+        use_utility_batch: bool,
+    ) -> Vec<Result<OnChainRequest>> {
+        if use_utility_batch {
+            return self.submit_utility_batch(items).await;
+        }
         
-        // Get the current block number
-        let current_block = self.client
-            .blocks()
-            .at_latest()
+        let mut results = Vec::with_capacity(items.len());
+        
+        for item in items {
+            let result = match item.request_type {
+                RequestType::Deposit => self.submit_deposit_request(&item.wallet_address, &item.asset, item.amount).await,
+                RequestType::Withdrawal => {
+                    self.submit_withdrawal_request(&item.wallet_address, &item.asset, item.amount).await
+                },
+                RequestType::Borrow => Err(anyhow!("Borrow requests cannot be submitted in a batch")),
+            };
+            results.push(result);
+        }
+        
+        results
+    }
+    
+    /// Submits deposit/withdrawal requests as one `Utility::batch` extrinsic
+    ///
+    /// A batch has a single signer, so every item must name the same wallet. Items the contract
+    /// would refuse fail on their own before the batch is sent. The batch stops at the first call
+    /// that fails on-chain; that item and the ones after it fail, while the ones before it stand.
+    async fn submit_utility_batch(&self, items: &[BatchSubmissionItem]) -> Vec<Result<OnChainRequest>> {
+        let Some(wallet_address) = items.first().map(|item| item.wallet_address.to_string()) else {
+            return Vec::new();
+        };
+        info!("Submitting {} requests for wallet {} as one utility.batch extrinsic", items.len(), wallet_address);
+        
+        let account_pair = match self.get_account_from_wallet(&wallet_address).await {
+            Ok(pair) => pair,
+            Err(err) => {
+                let message = format!("{:#}", err.context("Failed to get blockchain account from wallet address"));
+                return items.iter().map(|_| Err(anyhow!(message.clone()))).collect();
+            },
+        };
+        let signer: ContractSigner = PairSigner::new(account_pair);
+        let origin = contract::signer_account(&signer);
+        
+        // Dry-run every call, batching only the ones the contract would accept
+        let mut results: Vec<Option<Result<OnChainRequest>>> = Vec::with_capacity(items.len());
+        let mut batched = Vec::new();
+        let mut calls = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let call = async {
+                if item.wallet_address.to_string() != wallet_address {
+                    return Err(anyhow!("A utility batch is signed by one wallet, and this item names another"));
+                }
+                let amount = item.amount.units();
+                let (message, gas_limit) = match item.request_type {
+                    RequestType::Deposit => ("create_deposit_request", contract::estimate_gas_for_deposit_request(amount)),
+                    RequestType::Withdrawal => ("create_withdrawal_request", contract::estimate_gas_for_withdrawal_request(amount)),
+                    RequestType::Borrow => return Err(anyhow!("Borrow requests cannot be submitted in a batch")),
+                };
+                
+                let input = contract::message_input(contract::selector(message), (amount,));
+                let returned: std::result::Result<u128, u8> = self.contract
+                    .dry_run(&origin, input.clone())
+                    .await
+                    .with_context(|| format!("Failed to dry-run contract {}", message))?;
+                if let Err(error) = returned {
+                    return Err(anyhow!("Contract {} would fail with variant {} of the contract's Error", message, error));
+                }
+                
+                Ok((input, gas_limit))
+            }
+            .await;
+            
+            match call {
+                Ok(call) => {
+                    batched.push(index);
+                    calls.push(call);
+                    results.push(None);
+                },
+                Err(err) => results.push(Some(Err(err))),
+            }
+        }
+        
+        if !calls.is_empty() {
+            let outcomes = match self.contract.submit_batch_finalized(&signer, calls).await {
+                Ok(events) => self.batched_requests(&events, batched.len()).await,
+                Err(err) => {
+                    let message = format!("{:#}", err);
+                    batched.iter().map(|_| Err(anyhow!(message.clone()))).collect()
+                },
+            };
+            
+            for (index, outcome) in batched.into_iter().zip(outcomes) {
+                let item = &items[index];
+                let result = match outcome {
+                    Ok(requested) => self.record_batched_request(item, requested).await,
+                    Err(err) => Err(err),
+                };
+                results[index] = Some(result);
+            }
+        }
+        
+        results.into_iter().flatten().collect()
+    }
+    
+    /// The `*Requested` event of each of `count` calls of a finalized utility batch, in order, or
+    /// why the call didn't go through
+    async fn batched_requests(&self, events: &ExtrinsicEvents<PolkadotConfig>, count: usize) -> Vec<Result<BlockchainEvent>> {
+        let outcome = async {
+            // BatchInterrupted { index, error }
+            let mut interrupted_at = None;
+            for event in events.iter() {
+                let event = event.context("Failed to decode event")?;
+                if event.pallet_name() == "Utility" && event.variant_name() == "BatchInterrupted" {
+                    let index = u32::decode(&mut event.field_bytes()).context("Unexpected BatchInterrupted event layout")?;
+                    interrupted_at = Some(index as usize);
+                }
+            }
+            
+            let requested = self
+                .emitted_events(events)
+                .await?
+                .into_iter()
+                .filter(|event| matches!(event.event_type.as_str(), "DepositRequested" | "WithdrawalRequested"))
+                .collect::<Vec<_>>();
+            
+            Ok::<_, anyhow::Error>((interrupted_at, requested))
+        }
+        .await;
+        
+        let (interrupted_at, requested) = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                let message = format!("{:#}", err);
+                return (0..count).map(|_| Err(anyhow!(message.clone()))).collect();
+            },
+        };
+        
+        let mut requested = requested.into_iter();
+        (0..count)
+            .map(|index| match interrupted_at {
+                Some(at) if index == at => Err(anyhow!("The call failed on-chain, interrupting the batch")),
+                Some(at) if index > at => Err(anyhow!("Not submitted: the batch was interrupted at item {}", at)),
+                _ => requested.next().with_context(|| format!("The batch emitted no request event for item {}", index)),
+            })
+            .collect()
+    }
+    
+    /// Records a request a utility batch created, as its `*Requested` event names it
+    async fn record_batched_request(&self, item: &BatchSubmissionItem, requested: BlockchainEvent) -> Result<OnChainRequest> {
+        let request = OnChainRequest {
+            id: requested_id(&requested)?,
+            request_type: item.request_type.clone(),
+            wallet_address: item.wallet_address.to_string(),
+            amount: item.amount.format(item.asset.decimal_places()),
+            collateral_amount: None,
+            timestamp: requested.timestamp,
+            status: RequestStatus::Submitted,
+            block_number: requested.block_number,
+            transaction_hash: requested.transaction_hash,
+        };
+        
+        self.store_request_in_db(&request, &item.asset).await
+            .context("Failed to store batched request in database")?;
+        
+        Ok(request)
+    }
+    
+    /// Adds wallets to the contract's KYC allowlist in a single `set_kyc_approvals` call
+    ///
+    /// The call is signed by the contract owner. Returns the transaction hash.
+    pub async fn submit_kyc_approvals(&self, wallet_addresses: &[String]) -> Result<String> {
+        info!("Submitting KYC approvals for {} wallets", wallet_addresses.len());
+        
+        if wallet_addresses.is_empty() {
+            return Err(anyhow!("No wallets to approve"));
+        }
+        
+        let accounts = wallet_addresses
+            .iter()
+            .map(|wallet_address| {
+                wallet_address.parse::<AccountId32>()
+                    .map_err(|_| anyhow!("Invalid wallet address {}", wallet_address))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        let owner_pair = self.get_owner_account().await
+            .context("Failed to get contract owner account")?;
+        
+        let gas_limit = contract::estimate_gas_for_kyc_update(wallet_addresses.len());
+        info!("Estimated gas for KYC update: {}", gas_limit);
+        
+        let events = self
+            .submit_contract_call::<_, ()>(owner_pair, "set_kyc_approvals", (accounts, true), gas_limit)
+            .await?;
+        
+        let transaction = self
+            .finalized_transaction(&events, "set_kyc_approvals", serde_json::json!({ "wallet_addresses": wallet_addresses }))
+            .await?;
+        
+        Ok(transaction.transaction_hash)
+    }
+    
+    /// Processes a batch of pending requests of one type in a single `batch_process_*` call
+    ///
+    /// The call is signed by the contract owner. Requests the contract can't process are skipped
+    /// on-chain rather than failing the batch.
+    pub async fn process_request_batch(
+        &self,
+        request_type: &RequestType,
+        request_ids: &[i64],
+    ) -> Result<SubmittedTransaction> {
+        info!("Processing a batch of {} {} requests", request_ids.len(), request_type);
+        
+        if request_ids.is_empty() {
+            return Err(anyhow!("No requests to process"));
+        }
+        
+        let on_chain_ids = request_ids
+            .iter()
+            .map(|&id| u128::try_from(id).map_err(|_| anyhow!("Invalid request ID {}", id)))
+            .collect::<Result<Vec<_>>>()?;
+        
+        let owner_pair = self.get_owner_account().await
+            .context("Failed to get contract owner account")?;
+        
+        let gas_limit = contract::estimate_gas_for_batch_processing(request_ids.len());
+        info!("Estimated gas for batch processing: {}", gas_limit);
+        
+        let call = match request_type {
+            RequestType::Deposit => "batch_process_deposit_requests",
+            RequestType::Withdrawal => "batch_process_withdrawal_requests",
+            RequestType::Borrow => "batch_process_borrow_requests",
+        };
+        let events = self.submit_contract_call::<_, ()>(owner_pair, call, (on_chain_ids,), gas_limit).await?;
+        
+        self.finalized_transaction(&events, call, serde_json::json!({ "request_ids": request_ids })).await
+    }
+    
+    /// Closes the contract's current epoch, which also opens the next one
+    ///
+    /// The call is signed by the contract owner.
+    pub async fn close_current_epoch(&self) -> Result<SubmittedTransaction> {
+        info!("Closing the current epoch on-chain");
+        
+        let owner_pair = self.get_owner_account().await
+            .context("Failed to get contract owner account")?;
+        
+        let gas_limit = contract::estimate_gas_for_epoch_close();
+        info!("Estimated gas for epoch close: {}", gas_limit);
+        
+        // The contract returns the ID of the epoch it closed
+        let events = self.submit_contract_call::<_, u32>(owner_pair, "close_current_epoch", (), gas_limit).await?;
+        
+        self.finalized_transaction(&events, "close_current_epoch", serde_json::json!({})).await
+    }
+    
+    /// Liquidates a processed borrow on-chain
+    ///
+    /// The call is signed by the contract owner.
+    pub async fn liquidate_borrow(&self, request_id: i64) -> Result<SubmittedTransaction> {
+        info!("Liquidating borrow request {}", request_id);
+        
+        let on_chain_id = u128::try_from(request_id)
+            .map_err(|_| anyhow!("Invalid request ID {}", request_id))?;
+        
+        let owner_pair = self.get_owner_account().await
+            .context("Failed to get contract owner account")?;
+        
+        let gas_limit = contract::estimate_gas_for_liquidation();
+        info!("Estimated gas for liquidation: {}", gas_limit);
+        
+        let events = self.submit_contract_call::<_, ()>(owner_pair, "liquidate_borrow", (on_chain_id,), gas_limit).await?;
+        
+        self.finalized_transaction(&events, "liquidate_borrow", serde_json::json!({ "request_id": request_id })).await
+    }
+    
+    /// Sets the contract's minimum deposit and withdrawal amounts (base units) and minimum
+    /// collateral ratio (percent) in a single `set_risk_parameters` call
+    ///
+    /// The call is signed by the contract owner.
+    pub async fn set_risk_parameters(
+        &self,
+        min_deposit_amount: u128,
+        min_withdrawal_amount: u128,
+        min_collateral_ratio: u128,
+    ) -> Result<SubmittedTransaction> {
+        info!(
+            "Setting on-chain risk parameters: min deposit {}, min withdrawal {}, min collateral ratio {}%",
+            min_deposit_amount, min_withdrawal_amount, min_collateral_ratio
+        );
+
+        let owner_pair = self.get_owner_account().await
+            .context("Failed to get contract owner account")?;
+
+        let gas_limit = contract::estimate_gas_for_risk_parameters_update();
+        info!("Estimated gas for risk parameters update: {}", gas_limit);
+
+        let events = self
+            .submit_contract_call::<_, ()>(
+                owner_pair,
+                "set_risk_parameters",
+                (min_deposit_amount, min_withdrawal_amount, min_collateral_ratio),
+                gas_limit,
+            )
+            .await?;
+
+        // u128 amounts are recorded as strings, since JSON numbers can't hold them exactly
+        let args = serde_json::json!({
+            "min_deposit_amount": min_deposit_amount.to_string(),
+            "min_withdrawal_amount": min_withdrawal_amount.to_string(),
+            "min_collateral_ratio": min_collateral_ratio.to_string(),
+        });
+        self.finalized_transaction(&events, "set_risk_parameters", args).await
+    }
+
+    /// Submits a call of the contract's `message` with `args`, signed by `pair`, and waits for it
+    /// to be finalized
+    ///
+    /// The call is dry-run from the signer's account first, so a call the contract would reject
+    /// fails with the contract's error before anything is submitted. `T` is what the message
+    /// returns when it succeeds.
+    async fn submit_contract_call<A: Encode, T: Decode>(
+        &self,
+        pair: sr25519::Pair,
+        message: &str,
+        args: A,
+        gas_limit: u64,
+    ) -> Result<ExtrinsicEvents<PolkadotConfig>> {
+        let signer: ContractSigner = PairSigner::new(pair);
+        let input = contract::message_input(contract::selector(message), args);
+        
+        // The contract's errors are fieldless, so they're encoded as their variant index
+        let returned: std::result::Result<T, u8> = self.contract
+            .dry_run(&contract::signer_account(&signer), input.clone())
             .await
-            .context("Failed to get latest block")?;
+            .with_context(|| format!("Failed to dry-run contract {}", message))?;
+        if let Err(error) = returned {
+            return Err(anyhow!("Contract {} would fail with variant {} of the contract's Error", message, error));
+        }
+        
+        self.contract
+            .submit_finalized(&signer, input, gas_limit)
+            .await
+            .with_context(|| format!("Failed to call contract {}", message))
+    }
+    
+    /// Looks up the block a finalized admin call was included in, and records the contract `call`
+    /// and its `args` in the audit log
+    async fn finalized_transaction(
+        &self,
+        events: &ExtrinsicEvents<PolkadotConfig>,
+        call: &str,
+        args: serde_json::Value,
+    ) -> Result<SubmittedTransaction> {
+        let transaction = SubmittedTransaction {
+            transaction_hash: format!("0x{}", hex::encode(events.extrinsic_hash().as_ref())),
+            block_number: self.block_number_of(events.block_hash()).await?,
+        };
+        info!("Transaction {} included in block {}", transaction.transaction_hash, transaction.block_number);
+        
+        self.audit
+            .record(
+                NewAuditEntry::new(AuditAction::Extrinsic, format!("contract:{}", call))
+                    .with_transaction(Some(transaction.transaction_hash.clone()))
+                    .with_details(serde_json::json!({ "args": args, "block_number": transaction.block_number })),
+            )
+            .await;
+        
+        Ok(transaction)
+    }
+    
+    /// Decodes the `event_type` event the contract emitted in a finalized call
+    async fn emitted_event(&self, events: &ExtrinsicEvents<PolkadotConfig>, event_type: &str) -> Result<BlockchainEvent> {
+        self.emitted_events(events)
+            .await?
+            .into_iter()
+            .find(|event| event.event_type == event_type)
+            .with_context(|| format!("Transaction {:?} emitted no {} event", events.extrinsic_hash(), event_type))
+    }
+    
+    /// Decodes the events the contract emitted in a finalized call, in order
+    async fn emitted_events(&self, events: &ExtrinsicEvents<PolkadotConfig>) -> Result<Vec<BlockchainEvent>> {
+        let block_number = self.block_number_of(events.block_hash()).await?;
+        let timestamp = self.block_timestamp(events.block_hash()).await?;
+        let transaction_hash = format!("0x{}", hex::encode(events.extrinsic_hash().as_ref()));
+        
+        let mut decoded = Vec::new();
+        for event in events.iter() {
+            let event = event.context("Failed to decode event")?;
+            let Some((topics, data)) = self.emitted_by_contract(&event)? else {
+                continue;
+            };
             
-        Ok(current_block.header().number)
+            let emitted = ContractEmitted {
+                block_number,
+                transaction_hash: transaction_hash.clone(),
+                timestamp,
+                topics,
+                data,
+            };
+            if let Some(event) = decode_contract_event(&emitted, self.token_decimals())? {
+                decoded.push(event);
+            }
+        }
+        
+        Ok(decoded)
+    }
+    
+    /// Gets the contract's free balance, in tokens
+    pub async fn get_contract_balance(&self) -> Result<BigDecimal> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let balance = self.free_balance(self.contract.address.0).await?;
+        
+        #[cfg(target_arch = "wasm32")]
+        let balance = self.contract.get_contract_balance()
+            .await
+            .context("Failed to call contract get_contract_balance")?;
+        
+        Ok(Amount::from_units(balance).to_decimal(self.token_decimals()))
+    }
+    
+    /// Gets the free balance of an SS58 account, in tokens
+    pub async fn get_account_balance(&self, address: &str) -> Result<BigDecimal> {
+        let account_id = subxt::utils::AccountId32::from_str(address)
+            .map_err(|e| anyhow!("Invalid account address {}: {}", address, e))?;
+        let mut account = [0u8; 32];
+        account.copy_from_slice(account_id.as_ref());
+        
+        let balance = self.free_balance(account).await?;
+        
+        Ok(Amount::from_units(balance).to_decimal(self.token_decimals()))
+    }
+    
+    /// Reads the free balance of an account from `System.Account` at the latest block, in
+    /// on-chain units
+    async fn free_balance(&self, account: [u8; 32]) -> Result<u128> {
+        let block_hash = latest_block_hash(&self.client).await?;
+        self.rpc_cache
+            .get_or_fetch(block_hash, &format!("System.Account/{}", hex::encode(account)), || {
+                free_balance_at(&self.client, block_hash, account)
+            })
+            .await
+    }
+    
+    /// Reads a timestamped oracle value stored under `<pallet>.<storage_entry>` for a key, in the
+    /// layout of ORML's oracle pallet. Returns the raw fixed-point value and its timestamp in
+    /// milliseconds, or `None` when nothing is stored for the key.
+    pub async fn read_oracle_value(&self, pallet: &str, storage_entry: &str, key: &str) -> Result<Option<(u128, u64)>> {
+        let block_hash = latest_block_hash(&self.client).await?;
+        self.rpc_cache
+            .get_or_fetch(block_hash, &format!("{}.{}/{}", pallet, storage_entry, key), || {
+                self.read_oracle_value_at(block_hash, pallet, storage_entry, key)
+            })
+            .await
+    }
+    
+    async fn read_oracle_value_at(
+        &self,
+        block_hash: H256,
+        pallet: &str,
+        storage_entry: &str,
+        key: &str,
+    ) -> Result<Option<(u128, u64)>> {
+        let query = subxt::dynamic::storage(
+            pallet,
+            storage_entry,
+            vec![subxt::dynamic::Value::from_bytes(key.as_bytes())],
+        );
+        let stored = self.client
+            .storage()
+            .at(block_hash)
+            .fetch(&query)
+            .await
+            .with_context(|| format!("Failed to fetch {}.{} for {}", pallet, storage_entry, key))?;
+        
+        let Some(stored) = stored else {
+            return Ok(None);
+        };
+        
+        let value = stored.to_value().context("Failed to decode oracle value")?;
+        let price = value.at("value")
+            .and_then(|v| v.as_u128())
+            .context("Oracle value has no price")?;
+        let timestamp = value.at("timestamp")
+            .and_then(|v| v.as_u128())
+            .context("Oracle value has no timestamp")?;
+        
+        Ok(Some((price, timestamp as u64)))
+    }
+    
+    /// Reads the contract's owner, treasury, balances and risk parameters
+    pub async fn contract_state(&self) -> Result<ContractState> {
+        let block_number = self.get_current_block_number().await?;
+        let address = &self.contract.address;
+        let read = |label: &str| contract::message_input(contract::selector(label), ());
+
+        let owner: AccountId32 = self.contract.dry_run(address, read("get_owner")).await.context("Failed to read the contract owner")?;
+        let treasury: AccountId32 = self.contract.dry_run(address, read("get_treasury")).await.context("Failed to read the treasury")?;
+        let pending_deposits: u128 = self.contract
+            .dry_run(address, read("get_total_pending_deposits"))
+            .await
+            .context("Failed to read pending deposits")?;
+        let pending_withdrawals: u128 = self.contract
+            .dry_run(address, read("get_total_pending_withdrawals"))
+            .await
+            .context("Failed to read pending withdrawals")?;
+        let (min_deposit_amount, min_withdrawal_amount, min_collateral_ratio): (u128, u128, u128) = self.contract
+            .dry_run(address, read("get_risk_parameters"))
+            .await
+            .context("Failed to read risk parameters")?;
+
+        Ok(ContractState {
+            contract_address: address.to_string(),
+            block_number,
+            owner: owner.to_string(),
+            treasury: treasury.to_string(),
+            balance: self.free_balance(address.0).await?.to_string(),
+            total_pending_deposits: pending_deposits.to_string(),
+            total_pending_withdrawals: pending_withdrawals.to_string(),
+            min_deposit_amount: min_deposit_amount.to_string(),
+            min_withdrawal_amount: min_withdrawal_amount.to_string(),
+            min_collateral_ratio: min_collateral_ratio.to_string(),
+        })
+    }
+    
+    /// Reads a request from the contract, `None` when the contract has no request with the ID
+    pub async fn get_request(&self, request_id: u128) -> Result<Option<ContractRequest>> {
+        let input = contract::message_input(contract::selector("get_request"), request_id);
+        let stored: Option<StoredRequest> = self.contract
+            .dry_run(&self.contract.address, input)
+            .await
+            .with_context(|| format!("Failed to read request {} from the contract", request_id))?;
+        
+        stored.map(ContractRequest::try_from).transpose()
+    }
+    
+    /// Number of the block with `block_hash`
+    async fn block_number_of(&self, block_hash: H256) -> Result<u64> {
+        let block = self.client
+            .blocks()
+            .at(block_hash)
+            .await
+            .context("Failed to get transaction block")?;
+        
+        Ok(block.number() as u64)
     }
     
     /// Gets the current block number
@@ -269,82 +1026,120 @@ impl BlockchainService {
         Ok(current_block.header().number as u64)
     }
     
-    /// Gets events for a specific block
-    pub async fn get_events_for_block(&self, _block_number: u64) -> Result<Vec<BlockchainEvent>> {
-        // In a real implementation, we would query the chain for events in the block
-        // For development purposes, return an empty vector
-        // In production, this would use the Substrate API to get events
-        
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            // For non-wasm32 targets, return an empty vector
-            Ok(Vec::new())
+    /// Gets the contract's events in a block, decoded for the indexer
+    pub async fn get_events_for_block(&self, block_number: u64) -> Result<Vec<BlockchainEvent>> {
+        let mut events = Vec::new();
+        for emitted in self.contract_events_at(block_number).await? {
+            match decode_contract_event(&emitted, self.token_decimals())? {
+                Some(event) => events.push(event),
+                None => warn!("Skipping unknown contract event with topics {:?} in block {}", emitted.topics, block_number),
+            }
         }
         
-        #[cfg(target_arch = "wasm32")]
-        {
-            // For wasm32 targets, query the chain for events
-            let block_hash = self.client
-                .blocks()
-                .at_height(block_number as u32)
-                .await
-                .context("Failed to get block hash")?
-                .hash();
-                
-            // Get the block
-            let block = self.client
-                .blocks()
-                .at(block_hash)
-                .await
-                .context("Failed to get block")?;
-                
-            // Get the block timestamp
-            let timestamp = chrono::Utc::now(); // In production, get from block metadata
-            
-            // Get events for the block
-            let events = block
-                .events()
-                .await
-                .context("Failed to get events")?;
-                
-            // Process events
-            let mut blockchain_events = Vec::new();
-            
-            for event in events.iter() {
-                // Skip events that are not from our contract
-                // In production, filter by contract address
-                
-                // Extract event data
-                // This is a simplified example, in production you would decode the event data
-                let event_data = serde_json::json!({
-                    "event_index": event.index(),
-                    // Add more fields as needed
-                });
-                
-                blockchain_events.push(BlockchainEvent {
-                    event_type: "Unknown".to_string(), // In production, decode the event type
-                    transaction_hash: format!("0x{}", hex::encode(block_hash.as_ref())),
-                    block_number,
-                    timestamp,
-                    data: event_data,
-                });
-            }
+        Ok(events)
+    }
+    
+    /// Reads the `ContractEmitted` records of the contract in a block, undecoded
+    pub async fn contract_events_at(&self, block_number: u64) -> Result<Vec<ContractEmitted>> {
+        let block_hash = self.client
+            .rpc()
+            .block_hash(Some(block_number.into()))
+            .await
+            .context("Failed to get block hash")?
+            .with_context(|| format!("Block {} not found", block_number))?;
+        
+        self.rpc_cache
+            .get_or_fetch(block_hash, "Contracts.ContractEmitted", || self.read_contract_events(block_number, block_hash))
+            .await
+    }
+    
+    async fn read_contract_events(&self, block_number: u64, block_hash: H256) -> Result<Vec<ContractEmitted>> {
+        let block = self.client
+            .blocks()
+            .at(block_hash)
+            .await
+            .context("Failed to get block")?;
+        
+        // Events name the extrinsic that emitted them by its index in the block
+        let extrinsic_hashes = block
+            .body()
+            .await
+            .context("Failed to get block body")?
+            .extrinsics()
+            .iter()
+            .map(|extrinsic| extrinsic.map(|extrinsic| format!("0x{}", hex::encode(blake2_256(extrinsic.bytes())))))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to decode extrinsics")?;
+        
+        let timestamp = self.block_timestamp(block_hash).await?;
+        
+        let events = block.events().await.context("Failed to get events")?;
+        let mut emitted = Vec::new();
+        for event in events.iter() {
+            let event = event.context("Failed to decode event")?;
+            let Some((topics, data)) = self.emitted_by_contract(&event)? else {
+                continue;
+            };
             
-            Ok(blockchain_events)
+            let transaction_hash = match event.phase() {
+                Phase::ApplyExtrinsic(index) => extrinsic_hashes.get(index as usize).cloned(),
+                _ => None,
+            };
+            emitted.push(ContractEmitted {
+                block_number,
+                transaction_hash: transaction_hash.unwrap_or_else(|| format!("{:?}", block_hash)),
+                timestamp,
+                topics,
+                data,
+            });
         }
+        
+        Ok(emitted)
     }
     
-    /// Gets a blockchain account from a wallet address
-    fn get_account_from_wallet(&self, wallet_address: &str) -> Result<sr25519::Pair> {
-        // In a production environment, you would integrate with a secure key management system
-        // For testnet purposes, we'll derive keys from a mnemonic or seed phrase
+    /// The `0x`-prefixed topics and data of an event, if it's a `ContractEmitted` record of the
+    /// contract
+    fn emitted_by_contract(&self, event: &EventDetails<PolkadotConfig>) -> Result<Option<(Vec<String>, String)>> {
+        if event.pallet_name() != "Contracts" || event.variant_name() != "ContractEmitted" {
+            return Ok(None);
+        }
+        
+        // ContractEmitted { contract, data }
+        let (contract, data) = <(AccountId32, Vec<u8>)>::decode(&mut event.field_bytes())
+            .context("Unexpected ContractEmitted event layout")?;
+        if contract != self.contract.address {
+            return Ok(None);
+        }
         
-        let seed_phrase = std::env::var("WALLET_SEED_PHRASE")
-            .context("WALLET_SEED_PHRASE environment variable not set")?;
+        let topics = event.topics().iter().map(|topic| format!("{:?}", topic)).collect();
+        Ok(Some((topics, format!("0x{}", hex::encode(data)))))
+    }
+    
+    /// Time of the block with `block_hash`, as `Timestamp.Now` records it
+    async fn block_timestamp(&self, block_hash: H256) -> Result<chrono::DateTime<chrono::Utc>> {
+        let now = subxt::dynamic::storage("Timestamp", "Now", Vec::<subxt::dynamic::Value>::new());
+        let moment = self.client
+            .storage()
+            .at(block_hash)
+            .fetch(&now)
+            .await
+            .context("Failed to fetch block timestamp")?
+            .context("Block has no timestamp")?
+            .to_value()
+            .context("Failed to decode block timestamp")?
+            .as_u128()
+            .context("Block timestamp is not a number")?;
+        
+        chrono::DateTime::from_timestamp_millis(moment as i64).context("Block timestamp is out of range")
+    }
+    
+    /// Gets a blockchain account from a wallet address
+    async fn get_account_from_wallet(&self, wallet_address: &str) -> Result<sr25519::Pair> {
+        // For testnet purposes, we derive keys from a seed phrase held in the secrets backend
+        let seed_phrase = self.secrets.require("WALLET_SEED_PHRASE").await?;
             
         // Create a keyring from the seed phrase
-        let pair = sr25519::Pair::from_string(&seed_phrase, None)
-            .map_err(|_| anyhow!("Invalid seed phrase"))?;
+        let pair = keystore::keypair(&seed_phrase).context("Invalid wallet key")?;
             
         // Verify the account matches the expected wallet address
         let account_id = AccountId32::from(pair.public());
@@ -355,103 +1150,163 @@ impl BlockchainService {
         Ok(pair)
     }
     
-    /// Gets a signer for a wallet address
-    fn get_signer_for_wallet(&self, wallet_address: &str) -> Result<PairSigner<PolkadotConfig, sr25519::Pair>> {
-        let pair = self.get_account_from_wallet(wallet_address)?;
-        Ok(PairSigner::new(pair))
+    /// Gets the contract owner's account, which signs admin-only calls
+    async fn get_owner_account(&self) -> Result<sr25519::Pair> {
+        let seed_phrase = self.secrets.require("CONTRACT_OWNER_SEED_PHRASE").await?;
+        
+        keystore::keypair(&seed_phrase).context("Invalid contract owner key")
     }
     
-    /// Stores a deposit request in the database
-    async fn store_deposit_request_in_db(&self, request: &OnChainRequest) -> Result<()> {
-        // Create a new blockchain request record
+    /// Stores a submitted request in the database
+    async fn store_request_in_db(&self, request: &OnChainRequest, asset: &Asset) -> Result<()> {
         let new_request = NewBlockchainRequest {
-            request_type: RequestType::Deposit,
+            request_type: request.request_type.clone(),
             on_chain_id: request.id as i64,
-            wallet_address: request.wallet_address.clone(),
+            wallet_address: request.wallet_address.parse()
+                .with_context(|| format!("Request {} has an invalid wallet address", request.id))?,
             amount: request.amount.parse::<f64>().unwrap_or(0.0),
-            collateral_amount: None,
+            collateral_amount: request.collateral_amount.as_ref().and_then(|a| a.parse::<f64>().ok()),
             timestamp: request.timestamp.naive_utc(),
-            is_processed: request.is_processed,
+            status: request.status,
             block_number: request.block_number as i64,
             transaction_hash: request.transaction_hash.clone(),
+            asset: Some(asset.symbol.clone()),
         };
         
-        // Convert collateral_amount to BigDecimal for database compatibility
-        let collateral_amount_decimal: Option<BigDecimal> = new_request.collateral_amount
-            .map(|amount| BigDecimal::from_str(&amount.to_string()).unwrap_or_default());
-        
-        // Insert the request into the database
-        let result = sqlx::query!(
-            r#"
-            INSERT INTO lsrwa_express.blockchain_requests (
-                request_type, on_chain_id, wallet_address, amount, 
-                collateral_amount, is_processed, block_number, transaction_hash
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING id
-            "#,
-            new_request.request_type.to_string(),
-            new_request.on_chain_id,
-            new_request.wallet_address,
-            new_request.amount as f64,
-            collateral_amount_decimal,
-            new_request.is_processed,
-            new_request.block_number,
-            new_request.transaction_hash,
-        )
-        .fetch_one(&self.db.pg)
-        .await
-        .context("Failed to insert blockchain request")?;
+        let stored = BlockchainRequestRepository::new(self.db.pg.clone())
+            .insert(&new_request)
+            .await?;
         
-        info!("Stored deposit request in database with ID: {}", result.id);
+        info!("Stored {} request in database with ID: {}", stored.request_type, stored.id);
         
         Ok(())
     }
-    
-    /// Stores a withdrawal request in the database
-    async fn store_withdrawal_request_in_db(&self, request: &OnChainRequest) -> Result<()> {
-        // Create a new blockchain request record
-        let new_request = NewBlockchainRequest {
-            request_type: RequestType::Withdrawal,
-            on_chain_id: request.id as i64,
-            wallet_address: request.wallet_address.clone(),
-            amount: request.amount.parse::<f64>().unwrap_or(0.0),
-            collateral_amount: None,
-            timestamp: request.timestamp.naive_utc(),
-            is_processed: request.is_processed,
-            block_number: request.block_number as i64,
-            transaction_hash: request.transaction_hash.clone(),
-        };
-        
-        // Convert collateral_amount to BigDecimal for database compatibility
-        let collateral_amount_decimal: Option<BigDecimal> = new_request.collateral_amount
-            .map(|amount| BigDecimal::from_str(&amount.to_string()).unwrap_or_default());
-        
-        // Insert the request into the database
-        let result = sqlx::query!(
-            r#"
-            INSERT INTO lsrwa_express.blockchain_requests (
-                request_type, on_chain_id, wallet_address, amount, 
-                collateral_amount, is_processed, block_number, transaction_hash
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING id
-            "#,
-            new_request.request_type.to_string(),
-            new_request.on_chain_id,
-            new_request.wallet_address,
-            new_request.amount as f64,
-            collateral_amount_decimal,
-            new_request.is_processed,
-            new_request.block_number,
-            new_request.transaction_hash,
-        )
-        .fetch_one(&self.db.pg)
+}
+
+#[async_trait]
+impl ChainClient for BlockchainService {
+    fn asset(&self) -> Asset {
+        self.asset.clone()
+    }
+
+    async fn submit_deposit_request(&self, wallet_address: &str, asset: &Asset, amount: Amount) -> Result<OnChainRequest> {
+        BlockchainService::submit_deposit_request(self, wallet_address, asset, amount).await
+    }
+
+    async fn submit_withdrawal_request(&self, wallet_address: &str, asset: &Asset, amount: Amount) -> Result<OnChainRequest> {
+        BlockchainService::submit_withdrawal_request(self, wallet_address, asset, amount).await
+    }
+
+    async fn submit_borrow_request(
+        &self,
+        wallet_address: &str,
+        asset: &Asset,
+        amount: Amount,
+        collateral_amount: Amount,
+    ) -> Result<OnChainRequest> {
+        BlockchainService::submit_borrow_request(self, wallet_address, asset, amount, collateral_amount).await
+    }
+
+    async fn submit_batch_requests(&self, items: &[BatchSubmissionItem], use_utility_batch: bool) -> Vec<Result<OnChainRequest>> {
+        BlockchainService::submit_batch_requests(self, items, use_utility_batch).await
+    }
+
+    async fn submit_kyc_approvals(&self, wallet_addresses: &[String]) -> Result<String> {
+        BlockchainService::submit_kyc_approvals(self, wallet_addresses).await
+    }
+
+    async fn process_request_batch(&self, request_type: &RequestType, request_ids: &[i64]) -> Result<SubmittedTransaction> {
+        BlockchainService::process_request_batch(self, request_type, request_ids).await
+    }
+
+    async fn close_current_epoch(&self) -> Result<SubmittedTransaction> {
+        BlockchainService::close_current_epoch(self).await
+    }
+
+    async fn liquidate_borrow(&self, request_id: i64) -> Result<SubmittedTransaction> {
+        BlockchainService::liquidate_borrow(self, request_id).await
+    }
+
+    async fn set_risk_parameters(
+        &self,
+        min_deposit_amount: u128,
+        min_withdrawal_amount: u128,
+        min_collateral_ratio: u128,
+    ) -> Result<SubmittedTransaction> {
+        BlockchainService::set_risk_parameters(self, min_deposit_amount, min_withdrawal_amount, min_collateral_ratio).await
+    }
+
+    async fn get_contract_balance(&self) -> Result<BigDecimal> {
+        BlockchainService::get_contract_balance(self).await
+    }
+
+    async fn get_account_balance(&self, address: &str) -> Result<BigDecimal> {
+        BlockchainService::get_account_balance(self, address).await
+    }
+
+    async fn read_oracle_value(&self, pallet: &str, storage_entry: &str, key: &str) -> Result<Option<(u128, u64)>> {
+        BlockchainService::read_oracle_value(self, pallet, storage_entry, key).await
+    }
+
+    async fn get_request(&self, request_id: u128) -> Result<Option<ContractRequest>> {
+        BlockchainService::get_request(self, request_id).await
+    }
+
+    async fn get_current_block_number(&self) -> Result<u64> {
+        BlockchainService::get_current_block_number(self).await
+    }
+
+    async fn get_events_for_block(&self, block_number: u64) -> Result<Vec<BlockchainEvent>> {
+        BlockchainService::get_events_for_block(self, block_number).await
+    }
+}
+
+/// ID of the request a `*Requested` event names
+fn requested_id(event: &BlockchainEvent) -> Result<u128> {
+    event.data["request_id"]
+        .as_str()
+        .and_then(|id| id.parse().ok())
+        .with_context(|| format!("{} event has no request ID", event.event_type))
+}
+
+/// Hash of the node's latest block
+async fn latest_block_hash(client: &OnlineClient<PolkadotConfig>) -> Result<H256> {
+    client
+        .rpc()
+        .block_hash(None)
         .await
-        .context("Failed to insert blockchain request")?;
-        
-        info!("Stored withdrawal request in database with ID: {}", result.id);
-        
-        Ok(())
+        .context("Failed to get latest block")?
+        .context("Node has no latest block")
+}
+
+/// Reads the free balance of an account from `System.Account`, in on-chain units
+pub(crate) async fn free_balance(client: &OnlineClient<PolkadotConfig>, account: [u8; 32]) -> Result<u128> {
+    free_balance_at(client, latest_block_hash(client).await?, account).await
+}
+
+/// Reads the free balance of an account from `System.Account` at a block, in on-chain units
+async fn free_balance_at(client: &OnlineClient<PolkadotConfig>, block_hash: H256, account: [u8; 32]) -> Result<u128> {
+    let query = subxt::dynamic::storage(
+        "System",
+        "Account",
+        vec![subxt::dynamic::Value::from_bytes(account)],
+    );
+    let stored = client
+        .storage()
+        .at(block_hash)
+        .fetch(&query)
+        .await
+        .context("Failed to fetch account")?;
+    
+    match stored {
+        Some(stored) => stored
+            .to_value()
+            .context("Failed to decode account")?
+            .at("data")
+            .at("free")
+            .and_then(|free| free.as_u128())
+            .context("Account has no free balance"),
+        // Accounts without a balance are reaped
+        None => Ok(0),
     }
-} 
\ No newline at end of file
+}
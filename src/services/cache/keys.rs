@@ -0,0 +1,25 @@
+//! Cache keys shared by readers and the writers that invalidate them
+
+use uuid::Uuid;
+
+use crate::config::Environment;
+
+/// Blockchain state summary
+pub fn blockchain_summary() -> &'static str {
+    "lsrwa:blockchain:summary"
+}
+
+/// Effective system parameters
+pub fn system_parameters() -> &'static str {
+    "lsrwa:system_parameters"
+}
+
+/// Feature flags of an environment
+pub fn feature_flags(environment: Environment) -> String {
+    format!("lsrwa:feature_flags:{}", environment)
+}
+
+/// A user's balance in the contract's asset
+pub fn user_balance(user_id: Uuid) -> String {
+    format!("lsrwa:balance:{}", user_id)
+}
@@ -0,0 +1,64 @@
+//! In-process cache store
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::store::CacheStore;
+
+/// Cache store kept in process memory; suitable for single-instance deployments
+pub struct MemoryCacheStore {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+    max_entries: usize,
+}
+
+impl MemoryCacheStore {
+    /// Creates a store holding at most `max_entries` values
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries,
+        }
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Ok(Some(value.clone())),
+            Some(_) => {
+                entries.remove(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        if entries.len() >= self.max_entries && !entries.contains_key(key) {
+            entries.retain(|_, (_, expires_at)| *expires_at > now);
+
+            // Still full of live entries - skip caching rather than evicting hot keys
+            if entries.len() >= self.max_entries {
+                return Ok(());
+            }
+        }
+
+        entries.insert(key.to_string(), (value.to_string(), now + ttl));
+
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Cache for hot read paths
+//!
+//! Handlers read through [`Cache`], which wraps a pluggable [`CacheStore`]
+//! (Redis, in-process memory, or disabled). Cache failures are logged and treated
+//! as misses so a cache outage never fails a request. Writers invalidate the keys
+//! they affect using the helpers in [`keys`].
+
+mod memory;
+mod redis_store;
+mod store;
+
+pub mod keys;
+
+pub use memory::MemoryCacheStore;
+pub use redis_store::RedisCacheStore;
+pub use store::{CacheStore, NoopCacheStore};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::{CacheBackend, CacheConfig};
+
+/// Typed, fail-open cache facade shared across the application
+#[derive(Clone)]
+pub struct Cache {
+    store: Arc<dyn CacheStore>,
+    default_ttl: Duration,
+}
+
+impl Cache {
+    /// Creates a cache on top of the given store
+    pub fn new(store: Arc<dyn CacheStore>, default_ttl: Duration) -> Self {
+        Self { store, default_ttl }
+    }
+
+    /// A cache that never stores anything
+    pub fn disabled() -> Self {
+        Self::new(Arc::new(NoopCacheStore), Duration::ZERO)
+    }
+
+    /// Builds the cache selected by the configuration
+    pub async fn from_config(config: &CacheConfig) -> Result<Self> {
+        let default_ttl = Duration::from_secs(config.default_ttl_secs);
+
+        let store: Arc<dyn CacheStore> = match &config.backend {
+            CacheBackend::Disabled => return Ok(Self::disabled()),
+            CacheBackend::Memory => Arc::new(MemoryCacheStore::new(config.memory_max_entries)),
+            CacheBackend::Redis(url) => Arc::new(RedisCacheStore::connect(url).await?),
+        };
+
+        info!("Using {} cache with a default TTL of {:?}", config.backend, default_ttl);
+
+        Ok(Self::new(store, default_ttl))
+    }
+
+    /// Reads and deserializes a cached value
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        match self.store.get(key).await {
+            Ok(Some(raw)) => match serde_json::from_str(&raw) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    warn!("Discarding undecodable cache entry {}: {}", key, err);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(err) => {
+                warn!("Cache read for {} failed: {}", key, err);
+                None
+            }
+        }
+    }
+
+    /// Serializes and stores a value with the default TTL
+    pub async fn set_json<T: Serialize>(&self, key: &str, value: &T) {
+        self.set_json_with_ttl(key, value, self.default_ttl).await
+    }
+
+    /// Serializes and stores a value with an explicit TTL
+    pub async fn set_json_with_ttl<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+
+        let raw = match serde_json::to_string(value) {
+            Ok(raw) => raw,
+            Err(err) => {
+                warn!("Failed to serialize cache entry {}: {}", key, err);
+                return;
+            }
+        };
+
+        if let Err(err) = self.store.set(key, &raw, ttl).await {
+            warn!("Cache write for {} failed: {}", key, err);
+        }
+    }
+
+    /// Returns the cached value, or loads, caches and returns it on a miss
+    pub async fn get_or_load<T, F, Fut>(&self, key: &str, load: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Some(value) = self.get_json(key).await {
+            return Ok(value);
+        }
+
+        let value = load().await?;
+        self.set_json(key, &value).await;
+
+        Ok(value)
+    }
+
+    /// Removes a key so the next read reloads it
+    pub async fn invalidate(&self, key: &str) {
+        if let Err(err) = self.store.delete(key).await {
+            warn!("Cache invalidation for {} failed: {}", key, err);
+        }
+    }
+}
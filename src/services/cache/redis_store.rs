@@ -0,0 +1,48 @@
+//! Redis-backed cache store
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::time::Duration;
+
+use super::store::CacheStore;
+
+/// Cache store shared by every API replica through Redis
+#[derive(Clone)]
+pub struct RedisCacheStore {
+    connection: ConnectionManager,
+}
+
+impl RedisCacheStore {
+    /// Connects to Redis; the connection is re-established automatically if it drops
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url).context("Invalid REDIS_URL")?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .context("Failed to connect to Redis")?;
+
+        Ok(Self { connection })
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let mut connection = self.connection.clone();
+        connection.get(key).await.context("Redis GET failed")
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .set_ex(key, value, ttl.as_secs().max(1) as usize)
+            .await
+            .context("Redis SETEX failed")
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection.del(key).await.context("Redis DEL failed")
+    }
+}
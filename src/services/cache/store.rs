@@ -0,0 +1,36 @@
+//! Cache storage backends
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Key/value storage with per-entry expiry
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Gets a value if present and not expired
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+
+    /// Stores a value that expires after `ttl`
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<()>;
+
+    /// Removes a value
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Store used when caching is disabled
+pub struct NoopCacheStore;
+
+#[async_trait]
+impl CacheStore for NoopCacheStore {
+    async fn get(&self, _key: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    async fn set(&self, _key: &str, _value: &str, _ttl: Duration) -> Result<()> {
+        Ok(())
+    }
+
+    async fn delete(&self, _key: &str) -> Result<()> {
+        Ok(())
+    }
+}
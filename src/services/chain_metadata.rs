@@ -0,0 +1,108 @@
+//! Runtime metadata of the chain, cached per network
+//!
+//! Metadata is fetched with the node's `state_getMetadata` RPC and cached as SCALE bytes in
+//! `METADATA_DIR/<network>.scale`, next to a `.checksum` file holding its blake2-256 hash. The
+//! checksum tells whether a download changed anything, and lets the self-check notice when the
+//! node's runtime has moved on from the cached metadata.
+
+use anyhow::{anyhow, bail, Context, Result};
+use scale::Decode;
+use std::fs;
+use std::path::{Path, PathBuf};
+use subxt::ext::sp_core::blake2_256;
+use subxt::rpc::types::Bytes;
+use subxt::{Metadata, OnlineClient, PolkadotConfig};
+
+/// Metadata read from the cache
+#[derive(Debug, Clone)]
+pub struct CachedMetadata {
+    pub bytes: Vec<u8>,
+    pub checksum: String,
+}
+
+/// `0x`-prefixed blake2-256 hash of SCALE-encoded metadata
+pub fn checksum(metadata: &[u8]) -> String {
+    format!("0x{}", hex::encode(blake2_256(metadata)))
+}
+
+/// File holding the checksum of the metadata cached at `path`
+pub fn checksum_path(path: &Path) -> PathBuf {
+    path.with_extension("checksum")
+}
+
+/// Fetches the node's runtime metadata, checking it decodes
+pub async fn fetch(client: &OnlineClient<PolkadotConfig>) -> Result<Vec<u8>> {
+    let metadata: Bytes = client
+        .rpc()
+        .request("state_getMetadata", subxt::rpc_params![])
+        .await
+        .context("Failed to fetch runtime metadata")?;
+
+    Metadata::decode(&mut &metadata.0[..]).map_err(|e| anyhow!("The node returned invalid metadata: {}", e))?;
+
+    Ok(metadata.0)
+}
+
+/// Reads the metadata cached at `path`, or `None` if nothing is cached there. Fails if the
+/// metadata doesn't match its checksum.
+pub fn load(path: &Path) -> Result<Option<CachedMetadata>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let expected = fs::read_to_string(checksum_path(path))
+        .with_context(|| format!("{} has no checksum; download it again", path.display()))?;
+    let checksum = checksum(&bytes);
+    if checksum != expected.trim() {
+        bail!("{} doesn't match its checksum; download it again", path.display());
+    }
+
+    Ok(Some(CachedMetadata { bytes, checksum }))
+}
+
+/// Caches `metadata` at `path` with its checksum. Returns whether anything changed, leaving the
+/// files alone when the same metadata is already cached.
+pub fn store(path: &Path, metadata: &[u8]) -> Result<bool> {
+    let checksum = checksum(metadata);
+    if let Ok(Some(cached)) = load(path) {
+        if cached.checksum == checksum {
+            return Ok(false);
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(path, metadata).with_context(|| format!("Failed to write {}", path.display()))?;
+    fs::write(checksum_path(path), format!("{}\n", checksum))
+        .with_context(|| format!("Failed to write the checksum of {}", path.display()))?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_metadata_loads_until_it_is_tampered_with() {
+        let dir = std::env::temp_dir().join(format!("lsrwa-metadata-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("local.scale");
+        assert!(load(&path).unwrap().is_none());
+
+        assert!(store(&path, b"metadata v1").unwrap());
+        assert!(!store(&path, b"metadata v1").unwrap());
+        let cached = load(&path).unwrap().unwrap();
+        assert_eq!(cached.bytes, b"metadata v1");
+        assert_eq!(cached.checksum, checksum(b"metadata v1"));
+
+        assert!(store(&path, b"metadata v2").unwrap());
+        assert_eq!(load(&path).unwrap().unwrap().bytes, b"metadata v2");
+
+        fs::write(&path, b"edited").unwrap();
+        assert!(load(&path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
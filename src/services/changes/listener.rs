@@ -0,0 +1,101 @@
+//! Listener turning Postgres notifications into cache invalidations and feed updates
+
+use anyhow::{Context, Result};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tracing::{debug, info, warn};
+
+use super::{ChangeEvent, ChangeFeed, CHANGES_CHANNEL};
+use crate::db::SystemParameterRepository;
+use crate::services::cache::{keys, Cache};
+use crate::services::shutdown::Shutdown;
+
+/// Listens on the change channel until shutdown
+pub struct ChangeListener {
+    /// Pool the listener connection is opened from
+    db: PgPool,
+    /// Shared cache to invalidate
+    cache: Cache,
+    /// Parameter repository whose in-process cache is invalidated
+    parameters: SystemParameterRepository,
+    /// Feed changes are forwarded to
+    feed: ChangeFeed,
+}
+
+impl ChangeListener {
+    /// Creates a new change listener
+    pub fn new(db: PgPool, cache: Cache, parameters: SystemParameterRepository, feed: ChangeFeed) -> Self {
+        Self {
+            db,
+            cache,
+            parameters,
+            feed,
+        }
+    }
+
+    /// Runs the listen loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        let mut listener = PgListener::connect_with(&self.db)
+            .await
+            .context("Failed to open change listener connection")?;
+
+        listener
+            .listen(CHANGES_CHANNEL)
+            .await
+            .with_context(|| format!("Failed to listen on {}", CHANGES_CHANNEL))?;
+
+        info!("Listening for database changes on {}", CHANGES_CHANNEL);
+
+        loop {
+            let received = tokio::select! {
+                _ = shutdown.triggered() => break,
+                received = listener.try_recv() => received,
+            };
+
+            // `None` means the connection dropped; the listener reconnects on the next call, but
+            // anything sent in between is lost, so drop everything we can't reconcile.
+            let notification = match received {
+                Ok(Some(notification)) => notification,
+                Ok(None) => {
+                    warn!("Change listener connection lost, invalidating cached state");
+                    self.invalidate_all().await;
+                    continue;
+                },
+                Err(err) => return Err(err).context("Change listener failed"),
+            };
+
+            match serde_json::from_str::<ChangeEvent>(notification.payload()) {
+                Ok(change) => self.apply(change).await,
+                Err(err) => warn!("Ignoring malformed change notification: {}", err),
+            }
+        }
+
+        info!("Change listener stopped");
+        Ok(())
+    }
+
+    /// Invalidates what the change affects and forwards it to subscribers
+    async fn apply(&self, change: ChangeEvent) {
+        debug!("Database change: {:?}", change);
+
+        match &change {
+            ChangeEvent::BlockchainRequest { .. } => {
+                self.cache.invalidate(keys::blockchain_summary()).await;
+            },
+            ChangeEvent::UserBalance { user_id, .. } => {
+                self.cache.invalidate(&keys::user_balance(*user_id)).await;
+            },
+            ChangeEvent::SystemParameter { .. } => {
+                self.parameters.invalidate().await;
+            },
+        }
+
+        self.feed.publish(change);
+    }
+
+    /// Drops cached state that isn't keyed per row
+    async fn invalidate_all(&self) {
+        self.cache.invalidate(keys::blockchain_summary()).await;
+        self.parameters.invalidate().await;
+    }
+}
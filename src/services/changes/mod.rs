@@ -0,0 +1,82 @@
+//! Database change feed for LSRWA Express
+//!
+//! Triggers on `blockchain_requests`, `user_balances` and `system_parameters` publish on the
+//! `lsrwa_changes` Postgres channel. Every API process listens on it, drops the cache entries the
+//! change affects and forwards the change to its live subscribers, so replicas stay consistent
+//! without polling.
+
+mod listener;
+
+pub use listener::ChangeListener;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::blockchain_request::RequestType;
+use crate::models::request_status::RequestStatus;
+
+/// Postgres channel the change triggers notify on
+pub const CHANGES_CHANNEL: &str = "lsrwa_changes";
+
+/// A row change announced by the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum ChangeEvent {
+    /// A blockchain request was recorded or updated
+    BlockchainRequest {
+        operation: String,
+        request_type: RequestType,
+        on_chain_id: i64,
+        wallet_address: String,
+        user_id: Option<Uuid>,
+        is_processed: bool,
+        status: RequestStatus,
+    },
+    /// A user's balance changed
+    UserBalance {
+        operation: String,
+        user_id: Uuid,
+    },
+    /// A system parameter changed
+    SystemParameter {
+        operation: String,
+        parameter_name: String,
+    },
+}
+
+impl ChangeEvent {
+    /// Name used as the SSE event type
+    pub fn entity(&self) -> &'static str {
+        match self {
+            ChangeEvent::BlockchainRequest { .. } => "blockchain_request",
+            ChangeEvent::UserBalance { .. } => "user_balance",
+            ChangeEvent::SystemParameter { .. } => "system_parameter",
+        }
+    }
+}
+
+/// In-process broadcast of database changes to live subscribers
+#[derive(Clone)]
+pub struct ChangeFeed {
+    sender: broadcast::Sender<ChangeEvent>,
+}
+
+impl ChangeFeed {
+    /// Creates a feed; subscribers lagging more than `capacity` changes skip ahead
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Subscribes to changes published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ChangeEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publishes a change to every current subscriber
+    pub fn publish(&self, change: ChangeEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(change);
+    }
+}
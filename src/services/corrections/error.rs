@@ -0,0 +1,39 @@
+//! Errors returned when correcting a recorded request
+
+use thiserror::Error;
+
+use crate::models::request_status::RequestStatus;
+
+/// Why a request can't be corrected
+#[derive(Error, Debug)]
+pub enum CorrectionError {
+    #[error("Request {0} is not recorded")]
+    NotRecorded(i64),
+
+    #[error("Request {0} does not exist in the contract")]
+    NotOnChain(i64),
+
+    #[error("Request {0} is recorded with another type or wallet than the contract's")]
+    IdentityMismatch(i64),
+
+    #[error("Request {on_chain_id} is recorded as {recorded} but the contract has not processed it")]
+    NotProcessedOnChain { on_chain_id: i64, recorded: RequestStatus },
+
+    #[error("Request {on_chain_id} is {from} and can't become {to}")]
+    StatusRefused { on_chain_id: i64, from: RequestStatus, to: RequestStatus },
+
+    #[error("Request {0} has already been settled at its recorded amount")]
+    AlreadySettled(i64),
+
+    #[error("Withdrawal {0} has already reserved its recorded amount")]
+    AlreadyReserved(i64),
+
+    #[error("Request {0} already matches the contract")]
+    NothingToCorrect(i64),
+
+    #[error("A reason for the correction is required")]
+    MissingReason,
+
+    #[error("Correction lists [{listed}] but the fields that differ from the contract are [{differing}]")]
+    FieldsMismatch { listed: String, differing: String },
+}
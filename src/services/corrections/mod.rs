@@ -0,0 +1,13 @@
+//! Corrections of recorded requests
+//!
+//! [`RequestCorrectionService`] brings a request's database row back in line with the contract
+//! when its amounts or processed state were recorded wrongly. The contract's copy is the only
+//! source of new values: a correction re-reads the request on-chain, applies exactly the
+//! differences an operator reviewed, and records itself in the audit log in the same
+//! transaction.
+
+mod error;
+mod service;
+
+pub use error::CorrectionError;
+pub use service::RequestCorrectionService;
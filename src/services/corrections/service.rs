@@ -0,0 +1,321 @@
+//! Guarded corrections of recorded requests from the contract's copy
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::info;
+
+use super::error::CorrectionError;
+use crate::db::{AssetRepository, AuditRepository, BalanceRepository, BlockchainRequestRepository, UnitOfWork};
+use crate::models::asset::Asset;
+use crate::models::audit::{AuditAction, NewAuditEntry};
+use crate::models::blockchain_request::{
+    BlockchainRequest, CorrectRequestPayload, CorrectableField, FieldCorrection, RequestCorrection,
+    RequestCorrectionPreview, RequestType,
+};
+use crate::models::request_status::RequestStatus;
+use crate::services::audit::AuditContext;
+use crate::services::cache::{keys, Cache};
+use crate::services::{ChainClient, ContractRequest};
+
+/// Previews and applies corrections of recorded requests
+#[derive(Clone)]
+pub struct RequestCorrectionService {
+    db: PgPool,
+    cache: Cache,
+    blockchain: Arc<dyn ChainClient>,
+}
+
+impl RequestCorrectionService {
+    /// Creates a request correction service
+    pub fn new(db: PgPool, cache: Cache, blockchain: Arc<dyn ChainClient>) -> Self {
+        Self { db, cache, blockchain }
+    }
+
+    /// How a recorded request differs from the contract's copy
+    pub async fn preview(&self, on_chain_id: i64) -> Result<RequestCorrectionPreview> {
+        let request = BlockchainRequestRepository::new(self.db.clone())
+            .get_by_on_chain_id(on_chain_id)
+            .await?
+            .ok_or(CorrectionError::NotRecorded(on_chain_id))?;
+        let on_chain = self.read_on_chain(on_chain_id).await?;
+        let corrections = differences(&request, &on_chain, &self.asset_of(&request).await?)?;
+
+        Ok(RequestCorrectionPreview { request, corrections })
+    }
+
+    /// Corrects a recorded request from the contract's copy, and records the correction in the
+    /// audit log in the same transaction.
+    ///
+    /// The payload must list exactly the fields that differ when the row is locked. Statuses only
+    /// move forward, the way epoch processing moves them: a deposit the contract has processed
+    /// becomes executed and is credited at its corrected amount, other requests become processed.
+    ///
+    /// Amounts already booked against a balance are not rewritten: settled requests and reserved
+    /// withdrawals are refused, and a reserved deposit's reservation moves with its amount.
+    pub async fn apply(&self, on_chain_id: i64, payload: &CorrectRequestPayload) -> Result<RequestCorrection> {
+        let reason = payload.reason.trim();
+        if reason.is_empty() {
+            return Err(CorrectionError::MissingReason.into());
+        }
+
+        // Read before locking, so the row isn't held for a node round trip
+        let on_chain = self.read_on_chain(on_chain_id).await?;
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        let before = BlockchainRequestRepository::lock_by_on_chain_id_in(uow.conn(), on_chain_id)
+            .await?
+            .ok_or(CorrectionError::NotRecorded(on_chain_id))?;
+        let asset = self.asset_of(&before).await?;
+        let corrections = differences(&before, &on_chain, &asset)?;
+        if corrections.is_empty() {
+            uow.rollback().await?;
+            return Err(CorrectionError::NothingToCorrect(on_chain_id).into());
+        }
+        let differing: Vec<CorrectableField> = corrections.iter().map(|correction| correction.field).collect();
+        let mut listed = payload.fields.clone();
+        listed.sort();
+        listed.dedup();
+        if listed != differing {
+            uow.rollback().await?;
+            return Err(CorrectionError::FieldsMismatch { listed: field_names(&listed), differing: field_names(&differing) }.into());
+        }
+
+        let amount = asset.to_decimal(on_chain.amount);
+        if differing.iter().any(|field| matches!(field, CorrectableField::Amount | CorrectableField::CollateralAmount)) {
+            let collateral_amount = (before.request_type == RequestType::Borrow).then(|| asset.to_decimal(on_chain.collateral));
+            BlockchainRequestRepository::correct_amounts_in(uow.conn(), before.id, &amount, collateral_amount.as_ref()).await?;
+        }
+
+        // The indexer reserved the recorded amount, which crediting the deposit releases
+        let mut rebalanced = None;
+        if let (RequestType::Deposit, Some(user_id)) = (&before.request_type, before.user_id) {
+            if differing.contains(&CorrectableField::Amount) && before.status != RequestStatus::Submitted {
+                let delta = &amount - decimal(&before.amount)?;
+                BalanceRepository::adjust_pending_deposit_in(uow.conn(), user_id, &before.asset, &delta).await?;
+                rebalanced = Some(user_id);
+            }
+        }
+
+        if differing.contains(&CorrectableField::Status) {
+            let to = processed_status(&before.request_type);
+            let moved = BlockchainRequestRepository::transition_in(uow.conn(), &before.request_type, on_chain_id, to).await?;
+            if moved.is_none() {
+                uow.rollback().await?;
+                return Err(CorrectionError::StatusRefused { on_chain_id, from: before.status, to }.into());
+            }
+            if let (RequestType::Deposit, Some(user_id)) = (&before.request_type, before.user_id) {
+                BalanceRepository::apply_deposit_in(uow.conn(), user_id, &before.asset, &amount).await?;
+                rebalanced = Some(user_id);
+            }
+        }
+
+        let after = BlockchainRequestRepository::find_by_on_chain_id_in(uow.conn(), &before.request_type, on_chain_id)
+            .await?
+            .context("Corrected request disappeared")?;
+        let entry = NewAuditEntry::new(AuditAction::Reconciliation, format!("request:{}", on_chain_id))
+            .with_change(Some(&before), Some(&after))
+            .with_details(json!({ "reason": reason, "corrections": corrections }));
+        let audit_entry = AuditRepository::record_in(uow.conn(), &AuditContext::current(), &entry).await?;
+        uow.commit().await?;
+
+        if let Some(user_id) = rebalanced {
+            self.cache.invalidate(&keys::user_balance(user_id)).await;
+        }
+        info!("Corrected {} of request {} from the contract: {}", field_names(&differing), on_chain_id, reason);
+
+        Ok(RequestCorrection {
+            before,
+            after,
+            corrections,
+            reason: reason.to_string(),
+            audit_entry_id: audit_entry.id,
+        })
+    }
+
+    /// Asset the request's amounts are in
+    async fn asset_of(&self, request: &BlockchainRequest) -> Result<Asset> {
+        let contract_asset = self.blockchain.asset();
+        if request.asset == contract_asset.symbol {
+            return Ok(contract_asset);
+        }
+
+        AssetRepository::new(self.db.clone())
+            .get(&request.asset)
+            .await?
+            .with_context(|| format!("Asset {} of request {} is not registered", request.asset, request.on_chain_id))
+    }
+
+    async fn read_on_chain(&self, on_chain_id: i64) -> Result<ContractRequest> {
+        let request = self.blockchain.get_request(on_chain_id as u128).await?;
+
+        Ok(request.ok_or(CorrectionError::NotOnChain(on_chain_id))?)
+    }
+}
+
+/// Status a request takes once the contract has processed it
+fn processed_status(request_type: &RequestType) -> RequestStatus {
+    match request_type {
+        RequestType::Deposit => RequestStatus::Executed,
+        _ => RequestStatus::Processed,
+    }
+}
+
+/// Fields of `request` that differ from the contract's copy, in [`CorrectableField`] order, or
+/// why the request can't be corrected
+fn differences(request: &BlockchainRequest, on_chain: &ContractRequest, asset: &Asset) -> Result<Vec<FieldCorrection>> {
+    let on_chain_id = request.on_chain_id;
+    if request.request_type != on_chain.request_type || request.wallet_address != on_chain.wallet_address {
+        return Err(CorrectionError::IdentityMismatch(on_chain_id).into());
+    }
+
+    let mut corrections = Vec::new();
+    if decimal(&request.amount)? != asset.to_decimal(on_chain.amount) {
+        // Settling moved a balance by the recorded amount, and indexing a withdrawal reserved it
+        // against the active balance
+        match (&request.request_type, request.status) {
+            (RequestType::Deposit | RequestType::Withdrawal, RequestStatus::Executed) => {
+                return Err(CorrectionError::AlreadySettled(on_chain_id).into());
+            },
+            (RequestType::Withdrawal, status) if status != RequestStatus::Submitted => {
+                return Err(CorrectionError::AlreadyReserved(on_chain_id).into());
+            },
+            _ => {},
+        }
+        corrections.push(FieldCorrection {
+            field: CorrectableField::Amount,
+            recorded: Some(request.amount.clone()),
+            on_chain: asset.format(on_chain.amount),
+        });
+    }
+
+    if request.request_type == RequestType::Borrow {
+        let recorded = request.collateral_amount.as_deref().map(decimal).transpose()?;
+        if recorded != Some(asset.to_decimal(on_chain.collateral)) {
+            corrections.push(FieldCorrection {
+                field: CorrectableField::CollateralAmount,
+                recorded: request.collateral_amount.clone(),
+                on_chain: asset.format(on_chain.collateral),
+            });
+        }
+    }
+
+    let processed = processed_status(&request.request_type);
+    let recorded_processed = request.status == processed || request.status == RequestStatus::Executed;
+    match (on_chain.is_processed, recorded_processed) {
+        (true, false) if !request.status.can_become(processed) => {
+            return Err(CorrectionError::StatusRefused { on_chain_id, from: request.status, to: processed }.into());
+        },
+        (true, false) => corrections.push(FieldCorrection {
+            field: CorrectableField::Status,
+            recorded: Some(request.status.to_string()),
+            on_chain: processed.to_string(),
+        }),
+        (false, true) => {
+            return Err(CorrectionError::NotProcessedOnChain { on_chain_id, recorded: request.status }.into());
+        },
+        _ => {},
+    }
+
+    Ok(corrections)
+}
+
+fn decimal(value: &str) -> Result<BigDecimal> {
+    BigDecimal::from_str(value).with_context(|| format!("Invalid recorded amount {}", value))
+}
+
+fn field_names(fields: &[CorrectableField]) -> String {
+    fields
+        .iter()
+        .map(|field| serde_json::to_value(field).ok().and_then(|name| name.as_str().map(str::to_string)).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::amount::Amount;
+    use crate::models::wallet::WalletAddress;
+    use chrono::Utc;
+
+    const WALLET: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    fn recorded(request_type: RequestType, amount: &str, status: RequestStatus) -> BlockchainRequest {
+        let now = Utc::now();
+        BlockchainRequest {
+            id: 1,
+            request_type,
+            on_chain_id: 9,
+            wallet_address: WalletAddress::parse(WALLET).unwrap(),
+            user_id: None,
+            amount: amount.to_string(),
+            collateral_amount: None,
+            asset: "NATIVE".to_string(),
+            submission_timestamp: now,
+            is_processed: status == RequestStatus::Executed,
+            status,
+            block_number: 1,
+            transaction_hash: "0x01".to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn on_chain(request_type: RequestType, tokens: &str, is_processed: bool) -> ContractRequest {
+        ContractRequest {
+            id: 9,
+            request_type,
+            wallet_address: WalletAddress::parse(WALLET).unwrap(),
+            amount: Amount::parse(tokens, 12).unwrap(),
+            collateral: Amount::default(),
+            timestamp: Utc::now(),
+            is_processed,
+        }
+    }
+
+    #[test]
+    fn differences_only_name_what_the_contract_disagrees_with() {
+        let asset = Asset::native(12);
+        let deposit = recorded(RequestType::Deposit, "250.000000000000", RequestStatus::Confirmed);
+
+        assert!(differences(&deposit, &on_chain(RequestType::Deposit, "250", false), &asset).unwrap().is_empty());
+
+        let fields: Vec<_> = differences(&deposit, &on_chain(RequestType::Deposit, "25", true), &asset)
+            .unwrap()
+            .into_iter()
+            .map(|correction| (correction.field, correction.on_chain))
+            .collect();
+        assert_eq!(fields, [(CorrectableField::Amount, "25".to_string()), (CorrectableField::Status, "executed".to_string())]);
+
+        let borrow = recorded(RequestType::Borrow, "10", RequestStatus::Processed);
+        let corrections = differences(&borrow, &on_chain(RequestType::Borrow, "10", true), &asset).unwrap();
+        assert_eq!(corrections[0].field, CorrectableField::CollateralAmount);
+    }
+
+    #[test]
+    fn corrections_never_rewrite_identity_or_move_statuses_back() {
+        let asset = Asset::native(12);
+        let refused = |request: &BlockchainRequest, on_chain: &ContractRequest| {
+            differences(request, on_chain, &asset).unwrap_err().downcast::<CorrectionError>().unwrap()
+        };
+
+        let deposit = recorded(RequestType::Deposit, "250", RequestStatus::Confirmed);
+        assert!(matches!(refused(&deposit, &on_chain(RequestType::Withdrawal, "250", false)), CorrectionError::IdentityMismatch(9)));
+
+        let executed = recorded(RequestType::Deposit, "250", RequestStatus::Executed);
+        assert!(matches!(refused(&executed, &on_chain(RequestType::Deposit, "250", false)), CorrectionError::NotProcessedOnChain { .. }));
+        assert!(matches!(refused(&executed, &on_chain(RequestType::Deposit, "25", true)), CorrectionError::AlreadySettled(9)));
+
+        let withdrawal = recorded(RequestType::Withdrawal, "5", RequestStatus::Executed);
+        assert!(matches!(refused(&withdrawal, &on_chain(RequestType::Withdrawal, "4", true)), CorrectionError::AlreadySettled(9)));
+        let withdrawal = recorded(RequestType::Withdrawal, "5", RequestStatus::Confirmed);
+        assert!(matches!(refused(&withdrawal, &on_chain(RequestType::Withdrawal, "4", false)), CorrectionError::AlreadyReserved(9)));
+
+        let cancelled = recorded(RequestType::Withdrawal, "5", RequestStatus::Cancelled);
+        assert!(matches!(refused(&cancelled, &on_chain(RequestType::Withdrawal, "5", true)), CorrectionError::StatusRefused { .. }));
+    }
+}
@@ -0,0 +1,62 @@
+//! Scheduled close of epochs that have run their configured duration
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use tracing::info;
+
+use super::error::EpochProcessingError;
+use super::EpochProcessingService;
+use crate::db::{EpochRepository, SystemParameterRepository};
+use crate::services::scheduler::ScheduledJob;
+
+/// Starts processing the active epoch once it is older than the `epoch_duration_seconds`
+/// system parameter
+pub struct EpochAutoCloseJob {
+    epochs: EpochRepository,
+    parameters: SystemParameterRepository,
+    processing: EpochProcessingService,
+}
+
+impl EpochAutoCloseJob {
+    /// Name the job is scheduled under
+    pub const NAME: &'static str = "epoch_auto_close";
+
+    /// Creates the auto-close job
+    pub fn new(db: PgPool, parameters: SystemParameterRepository, processing: EpochProcessingService) -> Self {
+        Self {
+            epochs: EpochRepository::new(db),
+            parameters,
+            processing,
+        }
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for EpochAutoCloseJob {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    async fn run(&self) -> Result<()> {
+        let Some(epoch) = self.epochs.active().await? else {
+            return Ok(());
+        };
+
+        let duration = ChronoDuration::from_std(self.parameters.epoch_duration().await?)?;
+        if duration.is_zero() || epoch.start_timestamp + duration > Utc::now() {
+            return Ok(());
+        }
+
+        match self.processing.start(epoch.id).await {
+            Ok(_) => {
+                info!("Epoch {} reached its duration; processing started", epoch.id);
+                Ok(())
+            },
+            // Already picked up by an admin or an earlier run
+            Err(err) if matches!(err.downcast_ref(), Some(EpochProcessingError::AlreadyRunning(_))) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
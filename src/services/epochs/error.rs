@@ -0,0 +1,16 @@
+//! Errors returned when starting an epoch's close sequence
+
+use thiserror::Error;
+
+/// Why an epoch's close sequence can't be started
+#[derive(Error, Debug)]
+pub enum EpochProcessingError {
+    #[error("Epoch {0} not found")]
+    EpochNotFound(i32),
+
+    #[error("Epoch {0} has already been processed")]
+    AlreadyCompleted(i32),
+
+    #[error("Epoch {0} is already being processed")]
+    AlreadyRunning(i32),
+}
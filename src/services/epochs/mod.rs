@@ -0,0 +1,15 @@
+//! Epoch close sequence for LSRWA Express
+//!
+//! [`EpochProcessingService`] takes an epoch from active to completed: it stops new submissions,
+//! batch-processes the epoch's deposits and then its withdrawals on-chain, closes the epoch on-chain
+//! and opens the next one, calculates rewards and records a stats snapshot with the result. Progress
+//! is stored after every step so a failed run can be resumed where it stopped. [`EpochAutoCloseJob`]
+//! starts the sequence on schedule once an epoch has run its configured duration.
+
+mod auto_close;
+mod error;
+mod processing;
+
+pub use auto_close::EpochAutoCloseJob;
+pub use error::EpochProcessingError;
+pub use processing::{EpochProcessingDeps, EpochProcessingService};
@@ -0,0 +1,344 @@
+//! Runs an epoch's close sequence step by step
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use metrics::{counter, increment_counter};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use super::error::EpochProcessingError;
+use crate::db::{BalanceRepository, BlockchainRequestRepository, EpochProcessingRepository, EpochRepository, UnitOfWork};
+use crate::models::alert::{Alert, AlertSeverity};
+use crate::models::blockchain_request::{BatchItemStatus, RequestType};
+use crate::models::epoch::{EpochProcessingRun, EpochProcessingStep, EpochStatus, ProcessEpochResult};
+use crate::models::request_status::{RequestStatus, StatusChange};
+use crate::services::alerting::Alerter;
+use crate::services::audit::AuditContext;
+use crate::services::cache::{keys, Cache};
+use crate::services::event_bus::EventPublisher;
+use crate::services::interest::InterestAccrualService;
+use crate::services::liquidity::LiquidityPlanningService;
+use crate::services::rewards::RewardCalculationService;
+use crate::services::ChainClient;
+
+/// Requests included in each batch transaction, keeping it well within the block weight limit
+const BATCH_SIZE: i64 = 100;
+
+/// How long a running sequence may go without progress before it can be started again
+const STALE_RUN_SECS: i64 = 15 * 60;
+
+/// Services an [`EpochProcessingService`] runs the close sequence with
+#[derive(Clone)]
+pub struct EpochProcessingDeps {
+    pub cache: Cache,
+    pub blockchain: Arc<dyn ChainClient>,
+    pub rewards: RewardCalculationService,
+    pub interest: InterestAccrualService,
+    pub liquidity: LiquidityPlanningService,
+    pub alerts: Alerter,
+    pub events: EventPublisher,
+}
+
+/// Closes epochs: processes their requests on-chain, calculates rewards and records the outcome
+#[derive(Clone)]
+pub struct EpochProcessingService {
+    db: PgPool,
+    runs: EpochProcessingRepository,
+    requests: BlockchainRequestRepository,
+    cache: Cache,
+    blockchain: Arc<dyn ChainClient>,
+    rewards: RewardCalculationService,
+    interest: InterestAccrualService,
+    liquidity: LiquidityPlanningService,
+    alerts: Alerter,
+    events: EventPublisher,
+}
+
+impl EpochProcessingService {
+    /// Creates an epoch processing service
+    pub fn new(db: PgPool, deps: EpochProcessingDeps) -> Self {
+        let EpochProcessingDeps { cache, blockchain, rewards, interest, liquidity, alerts, events } = deps;
+        Self {
+            runs: EpochProcessingRepository::new(db.clone()),
+            requests: BlockchainRequestRepository::new(db.clone()),
+            db,
+            cache,
+            blockchain,
+            rewards,
+            interest,
+            liquidity,
+            alerts,
+            events,
+        }
+    }
+
+    /// Starts closing an epoch in the background, or resumes a sequence that failed. New
+    /// submissions are refused from the moment this returns until the epoch is closed on-chain.
+    pub async fn start(&self, epoch_id: i32) -> Result<EpochProcessingRun> {
+        let run = self.begin(epoch_id).await?;
+
+        // The close sequence is attributed to whoever started it
+        let service = self.clone();
+        let context = AuditContext::current();
+        tokio::spawn(context.scope(async move {
+            let _ = service.run(epoch_id).await;
+        }));
+
+        Ok(run)
+    }
+
+    /// Closes an epoch, or resumes a sequence that failed, and waits for the sequence to finish
+    pub async fn close(&self, epoch_id: i32) -> Result<ProcessEpochResult> {
+        self.begin(epoch_id).await?;
+        self.run(epoch_id).await
+    }
+
+    /// Batch-processes the requests of a type submitted so far in the active epoch, without
+    /// closing it
+    pub async fn process_pending(&self, request_type: RequestType) -> Result<()> {
+        let epoch = EpochRepository::new(self.db.clone())
+            .active()
+            .await?
+            .context("There is no active epoch")?;
+
+        // Closing the epoch processes these itself
+        if self.submissions_paused().await? {
+            return Err(EpochProcessingError::AlreadyRunning(epoch.id).into());
+        }
+
+        if request_type == RequestType::Withdrawal {
+            self.liquidity.ensure_sufficient().await?;
+        }
+
+        self.process_requests(epoch.id, request_type, Utc::now()).await
+    }
+
+    /// Marks the epoch's sequence as running, refusing epochs that are completed or already
+    /// being processed
+    async fn begin(&self, epoch_id: i32) -> Result<EpochProcessingRun> {
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        let epoch = EpochRepository::lock_in(uow.conn(), epoch_id)
+            .await?
+            .ok_or(EpochProcessingError::EpochNotFound(epoch_id))?;
+        if epoch.status == EpochStatus::Completed {
+            return Err(EpochProcessingError::AlreadyCompleted(epoch_id).into());
+        }
+
+        let run = EpochProcessingRepository::start_in(uow.conn(), epoch_id, STALE_RUN_SECS)
+            .await?
+            .ok_or(EpochProcessingError::AlreadyRunning(epoch_id))?;
+
+        uow.commit().await?;
+
+        info!("Processing epoch {} from step {:?}", epoch_id, run.step);
+
+        Ok(run)
+    }
+
+    /// Progress of an epoch's close sequence
+    pub async fn status(&self, epoch_id: i32) -> Result<Option<EpochProcessingRun>> {
+        self.runs.get(epoch_id).await
+    }
+
+    /// Whether new requests are being refused while an epoch closes
+    pub async fn submissions_paused(&self) -> Result<bool> {
+        self.runs.submissions_paused().await
+    }
+
+    /// Runs a started sequence to completion, recording where it stopped if it fails. A failure
+    /// raises an alert, which clears once a retry completes the sequence.
+    async fn run(&self, epoch_id: i32) -> Result<ProcessEpochResult> {
+        let alert_key = format!("epoch_processing:{}", epoch_id);
+
+        let result = self.process(epoch_id).await;
+        match &result {
+            Ok(result) => {
+                info!(
+                    "Processed epoch {}: {} deposits, {} withdrawals",
+                    epoch_id, result.deposits_processed, result.withdrawals_processed
+                );
+                increment_counter!("epoch_processing_runs_total", "status" => "completed");
+                self.alerts.resolve(&alert_key).await;
+            },
+            Err(err) => {
+                let message = format!("{:#}", err);
+                error!("Failed to process epoch {}: {}", epoch_id, message);
+                increment_counter!("epoch_processing_runs_total", "status" => "failed");
+
+                let step = self.runs.get(epoch_id).await.ok().flatten().map(|run| run.step);
+                self.alerts
+                    .raise(Alert {
+                        key: alert_key,
+                        severity: AlertSeverity::Critical,
+                        title: format!("Processing of epoch {} failed", epoch_id),
+                        details: match step {
+                            Some(step) => format!("Stopped at step {:?}: {}", step, message),
+                            None => message.clone(),
+                        },
+                    })
+                    .await;
+
+                if let Err(err) = self.runs.fail(epoch_id, &message).await {
+                    error!("Failed to record epoch {} processing failure: {}", epoch_id, err);
+                }
+            },
+        }
+
+        result
+    }
+
+    /// Runs the remaining steps of the sequence. Every step can be repeated after a failure
+    /// without doing its work twice.
+    async fn process(&self, epoch_id: i32) -> Result<ProcessEpochResult> {
+        let run = self.runs.get(epoch_id).await?.context("Epoch processing run disappeared")?;
+        let epochs = EpochRepository::new(self.db.clone());
+
+        if run.step <= EpochProcessingStep::ClosingSubmissions {
+            // Fixes the epoch's end; requests submitted until now belong to it
+            epochs.close(epoch_id, Utc::now()).await?.ok_or(EpochProcessingError::EpochNotFound(epoch_id))?;
+            self.runs.advance(epoch_id, EpochProcessingStep::ProcessingDeposits).await?;
+        }
+
+        let epoch = epochs.get(epoch_id).await?.ok_or(EpochProcessingError::EpochNotFound(epoch_id))?;
+        let epoch_end = epoch.end_timestamp.context("Closed epoch has no end time")?;
+
+        if run.step <= EpochProcessingStep::ProcessingDeposits {
+            self.process_requests(epoch_id, RequestType::Deposit, epoch_end).await?;
+            self.runs.advance(epoch_id, EpochProcessingStep::ProcessingWithdrawals).await?;
+        }
+
+        if run.step <= EpochProcessingStep::ProcessingWithdrawals {
+            // Stops here with the shortfall when withdrawals can't be paid out; the run resumes at
+            // this step once liquidity has been topped up
+            self.liquidity.ensure_sufficient().await?;
+            self.process_requests(epoch_id, RequestType::Withdrawal, epoch_end).await?;
+            self.runs.advance(epoch_id, EpochProcessingStep::ClosingEpoch).await?;
+        }
+
+        if run.step <= EpochProcessingStep::ClosingEpoch {
+            // A close that reached the chain but wasn't recorded here must not be sent again, or
+            // it would close the next epoch too
+            if run.close_tx_hash.is_none() {
+                let transaction = self.blockchain.close_current_epoch().await?;
+
+                let mut uow = UnitOfWork::begin(&self.db).await?;
+                let next_epoch_id = EpochRepository::open_next_in(uow.conn()).await?;
+                EpochProcessingRepository::record_close_in(uow.conn(), epoch_id, &transaction.transaction_hash, next_epoch_id)
+                    .await?;
+                EpochProcessingRepository::advance_in(uow.conn(), epoch_id, EpochProcessingStep::CalculatingRewards).await?;
+                uow.commit().await?;
+
+                info!("Closed epoch {} on-chain; epoch {} is now open", epoch_id, next_epoch_id);
+            } else {
+                self.runs.advance(epoch_id, EpochProcessingStep::CalculatingRewards).await?;
+            }
+        }
+
+        if run.step <= EpochProcessingStep::CalculatingRewards {
+            self.rewards.calculate_epoch(epoch_id).await?;
+            self.interest.accrue_epoch(epoch_id).await?;
+            self.runs.advance(epoch_id, EpochProcessingStep::SnapshottingStats).await?;
+        }
+
+        let run = self.runs.get(epoch_id).await?.context("Epoch processing run disappeared")?;
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        let epoch = EpochRepository::complete_in(uow.conn(), epoch_id, run.close_tx_hash.as_deref())
+            .await?
+            .ok_or(EpochProcessingError::EpochNotFound(epoch_id))?;
+        let stats = EpochProcessingRepository::snapshot_stats_in(uow.conn(), epoch_id).await?;
+
+        let result = ProcessEpochResult {
+            epoch_id,
+            status: epoch.status,
+            processed_at: epoch.processed_at.unwrap_or_else(Utc::now),
+            deposits_processed: run.deposits_processed,
+            withdrawals_processed: run.withdrawals_processed,
+            borrows_processed: run.borrows_processed,
+        };
+        EpochProcessingRepository::complete_in(uow.conn(), epoch_id, &stats, &result).await?;
+
+        uow.commit().await?;
+
+        Ok(result)
+    }
+
+    /// Batch-processes the requests of a type submitted before the epoch ended
+    ///
+    /// Processed deposits are settled into active balances straight away and become executed.
+    /// Withdrawals and borrows only become processed; they are settled when their execution is
+    /// indexed.
+    async fn process_requests(
+        &self,
+        epoch_id: i32,
+        request_type: RequestType,
+        epoch_end: DateTime<Utc>,
+    ) -> Result<()> {
+        let (item_status, request_status) = match request_type {
+            RequestType::Deposit => (BatchItemStatus::Processed, RequestStatus::Executed),
+            _ => (BatchItemStatus::Included, RequestStatus::Processed),
+        };
+
+        loop {
+            let batch = self.requests.list_unbatched(&request_type, epoch_end, BATCH_SIZE).await?;
+            if batch.is_empty() {
+                return Ok(());
+            }
+
+            let ids: Vec<i64> = batch.iter().map(|request| request.on_chain_id).collect();
+            let transaction = self.blockchain.process_request_batch(&request_type, &ids).await?;
+
+            let mut uow = UnitOfWork::begin(&self.db).await?;
+            BlockchainRequestRepository::record_batch_in(
+                uow.conn(),
+                epoch_id,
+                &request_type,
+                &ids,
+                &item_status,
+                &transaction.transaction_hash,
+                transaction.block_number as i64,
+            )
+            .await?;
+
+            let newly_moved: HashSet<i64> =
+                BlockchainRequestRepository::transition_batch_in(uow.conn(), &request_type, &ids, request_status)
+                    .await?
+                    .into_iter()
+                    .filter(|(_, change)| matches!(change, StatusChange::Moved(_)))
+                    .map(|(on_chain_id, _)| on_chain_id)
+                    .collect();
+
+            let mut processed = Vec::new();
+            let mut deposits = Vec::new();
+            if request_type == RequestType::Deposit {
+                for request in batch.iter().filter(|request| newly_moved.contains(&request.on_chain_id)) {
+                    processed.push(request.clone());
+                    if let Some(user_id) = request.user_id {
+                        let amount = BigDecimal::from_str(&request.amount)
+                            .with_context(|| format!("Invalid amount on request {}", request.id))?;
+                        deposits.push((user_id, request.asset.clone(), amount));
+                    }
+                }
+                BalanceRepository::apply_deposits_in(uow.conn(), &deposits).await?;
+            }
+
+            EpochProcessingRepository::add_processed_in(uow.conn(), epoch_id, &request_type, ids.len() as i32).await?;
+            uow.commit().await?;
+
+            for user_id in deposits.into_iter().map(|(user_id, _, _)| user_id).collect::<HashSet<_>>() {
+                self.cache.invalidate(&keys::user_balance(user_id)).await;
+            }
+            self.events
+                .publish_processed_requests(&processed, Some(epoch_id), &transaction.transaction_hash)
+                .await;
+
+            info!("Processed {} {} requests of epoch {} in {}", ids.len(), request_type, epoch_id, transaction.transaction_hash);
+            counter!("epoch_requests_processed_total", ids.len() as u64, "type" => request_type.to_string());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Kafka publishing
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::time::Duration;
+
+use super::MessageBus;
+use crate::config::KafkaConfig;
+
+/// Publishes to Kafka topics through an idempotent producer
+pub struct KafkaBus {
+    producer: FutureProducer,
+    timeout: Duration,
+}
+
+impl KafkaBus {
+    /// Creates a producer for the configured brokers; brokers are connected to lazily
+    pub fn new(config: &KafkaConfig) -> Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", &config.client_id)
+            .set("message.timeout.ms", config.message_timeout_ms.to_string())
+            .set("enable.idempotence", "true")
+            .create()
+            .context("Failed to create Kafka producer")?;
+
+        Ok(Self {
+            producer,
+            timeout: Duration::from_millis(config.message_timeout_ms),
+        })
+    }
+}
+
+#[async_trait]
+impl MessageBus for KafkaBus {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn publish(&self, topic: &str, key: &str, message_id: &str, payload: Vec<u8>) -> Result<()> {
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "message_id",
+            value: Some(message_id),
+        });
+        let record = FutureRecord::to(topic).key(key).payload(&payload).headers(headers);
+
+        self.producer
+            .send(record, self.timeout)
+            .await
+            .map_err(|(err, _)| anyhow!("Kafka rejected message {} on {}: {}", message_id, topic, err))?;
+
+        Ok(())
+    }
+}
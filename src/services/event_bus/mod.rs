@@ -0,0 +1,43 @@
+//! Event bus publishing for LSRWA Express
+//!
+//! Every event the indexer decodes, and domain changes such as processed requests and new
+//! rewards, are published as versioned [`BusMessage`](crate::models::event_bus::BusMessage)s on
+//! Kafka topics or NATS subjects named `<prefix>.<topic>`, for analytics and risk systems to
+//! consume. Publishing is best effort and happens after the change is committed; a failed publish
+//! is logged and counted but never fails the change.
+
+mod kafka;
+mod nats;
+mod publisher;
+
+pub use kafka::KafkaBus;
+pub use nats::NatsBus;
+pub use publisher::EventPublisher;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::config::{EventBusConfig, EventBusProvider};
+
+/// A message bus events can be published on
+#[async_trait]
+pub trait MessageBus: Send + Sync {
+    /// Name recorded in logs and metrics
+    fn name(&self) -> &'static str;
+
+    /// Publishes a serialized message on `topic`, keyed for partitioning and tagged with the
+    /// message id for deduplication
+    async fn publish(&self, topic: &str, key: &str, message_id: &str, payload: Vec<u8>) -> Result<()>;
+}
+
+/// Connects to the configured message bus, or returns `None` when events aren't published
+pub async fn bus_from_config(config: &EventBusConfig) -> Result<Option<Arc<dyn MessageBus>>> {
+    let bus: Arc<dyn MessageBus> = match &config.provider {
+        Some(EventBusProvider::Kafka(kafka)) => Arc::new(KafkaBus::new(kafka)?),
+        Some(EventBusProvider::Nats(nats)) => Arc::new(NatsBus::connect(nats).await?),
+        None => return Ok(None),
+    };
+
+    Ok(Some(bus))
+}
@@ -0,0 +1,53 @@
+//! NATS publishing
+
+use anyhow::{Context, Result};
+use async_nats::header::NATS_MESSAGE_ID;
+use async_nats::{Client, ConnectOptions, HeaderMap};
+use async_trait::async_trait;
+use std::time::Duration;
+
+use super::MessageBus;
+use crate::config::NatsConfig;
+
+/// Publishes to NATS subjects
+///
+/// Messages carry their id in the `Nats-Msg-Id` header, so a JetStream stream on the subjects
+/// drops duplicates within its deduplication window.
+pub struct NatsBus {
+    client: Client,
+}
+
+impl NatsBus {
+    /// Connects to the configured server; the client reconnects by itself after disconnects
+    pub async fn connect(config: &NatsConfig) -> Result<Self> {
+        let mut options = ConnectOptions::new()
+            .name("lsrwa-express")
+            .connection_timeout(Duration::from_secs(10));
+        if let Some(token) = &config.token {
+            options = options.token(token.clone());
+        }
+
+        let client = async_nats::connect_with_options(config.url.as_str(), options)
+            .await
+            .with_context(|| format!("Failed to connect to NATS at {}", config.url))?;
+
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl MessageBus for NatsBus {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    async fn publish(&self, topic: &str, _key: &str, message_id: &str, payload: Vec<u8>) -> Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_MESSAGE_ID, message_id);
+
+        self.client
+            .publish_with_headers(topic.to_string(), headers, payload.into())
+            .await
+            .with_context(|| format!("Failed to publish message {} on {}", message_id, topic))
+    }
+}
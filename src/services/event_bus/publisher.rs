@@ -0,0 +1,124 @@
+//! Builds versioned bus messages from protocol events and publishes them
+
+use chrono::{DateTime, Utc};
+use metrics::increment_counter;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use super::MessageBus;
+use crate::models::blockchain_request::BlockchainRequest;
+use crate::models::event_bus::{
+    BusEvent, BusMessage, ChainEventData, RequestProcessedData, RewardCreatedData, EVENT_SCHEMA_VERSION,
+};
+use crate::models::reward::EpochRewardLine;
+use crate::services::indexer::IndexedEvent;
+
+/// Publishes protocol events on the configured message bus, if any
+#[derive(Clone)]
+pub struct EventPublisher {
+    bus: Option<Arc<dyn MessageBus>>,
+    topic_prefix: String,
+}
+
+impl EventPublisher {
+    /// Creates a publisher; without a bus, events are dropped
+    pub fn new(bus: Option<Arc<dyn MessageBus>>, topic_prefix: impl Into<String>) -> Self {
+        Self {
+            bus,
+            topic_prefix: topic_prefix.into(),
+        }
+    }
+
+    /// Publishes an event, logging and counting failures instead of returning them
+    pub async fn publish(&self, id: String, occurred_at: DateTime<Utc>, event: BusEvent) {
+        let Some(bus) = &self.bus else {
+            return;
+        };
+
+        let topic = format!("{}.{}", self.topic_prefix, event.topic());
+        let key = event.key();
+        let message = BusMessage {
+            schema_version: EVENT_SCHEMA_VERSION,
+            id,
+            occurred_at,
+            event,
+        };
+
+        let payload = match serde_json::to_vec(&message) {
+            Ok(payload) => payload,
+            Err(err) => {
+                error!("Failed to serialize bus message {}: {}", message.id, err);
+                return;
+            },
+        };
+
+        match bus.publish(&topic, &key, &message.id, payload).await {
+            Ok(()) => {
+                debug!("Published {} on {}", message.id, topic);
+                increment_counter!("event_bus_messages_total", "topic" => topic, "status" => "published");
+            },
+            Err(err) => {
+                error!("Failed to publish {} to {}: {:#}", message.id, bus.name(), err);
+                increment_counter!("event_bus_messages_total", "topic" => topic, "status" => "failed");
+            },
+        }
+    }
+
+    /// Publishes a contract event decoded by the indexer
+    pub async fn publish_indexed_event(&self, event: &IndexedEvent) {
+        let data = ChainEventData {
+            event: event.event_type().name().to_string(),
+            block_number: event.block_number,
+            transaction_hash: event.transaction_hash.clone(),
+            request_id: event.request_id.map(|id| id.to_string()),
+            wallet_address: event.wallet_address.clone(),
+            amount: event.amount.clone(),
+            request_type: event.request_type.clone(),
+        };
+
+        self.publish(event.id.clone(), event.timestamp, BusEvent::ChainEvent(data)).await;
+    }
+
+    /// Publishes requests settled by the given transaction
+    pub async fn publish_processed_requests(
+        &self,
+        requests: &[BlockchainRequest],
+        epoch_id: Option<i32>,
+        transaction_hash: &str,
+    ) {
+        let now = Utc::now();
+
+        for request in requests {
+            let data = RequestProcessedData {
+                request_type: request.request_type.clone(),
+                on_chain_id: request.on_chain_id,
+                wallet_address: request.wallet_address.to_string(),
+                user_id: request.user_id,
+                amount: request.amount.clone(),
+                epoch_id,
+                transaction_hash: transaction_hash.to_string(),
+            };
+            let id = format!("request_processed:{}:{}", data.request_type, data.on_chain_id);
+
+            self.publish(id, now, BusEvent::RequestProcessed(data)).await;
+        }
+    }
+
+    /// Publishes the rewards credited for an epoch
+    pub async fn publish_rewards(&self, epoch_id: i32, apr_bps: i32, lines: &[EpochRewardLine]) {
+        let now = Utc::now();
+
+        for line in lines {
+            let data = RewardCreatedData {
+                epoch_id,
+                user_id: line.user_id,
+                wallet_address: line.wallet_address.clone(),
+                amount: line.amount.clone(),
+                apr_bps,
+            };
+            let id = format!("reward_created:{}:{}", epoch_id, line.user_id);
+
+            self.publish(id, now, BusEvent::RewardCreated(data)).await;
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Shared client for calls to third-party HTTP APIs
+//!
+//! KYC providers and webhook endpoints share one connection pool, sized and timed by
+//! `OUTBOUND_HTTP_*` and optionally routed through a proxy. Requests that never reached the
+//! destination are retried with exponential backoff, and so are idempotent requests that timed
+//! out or got a 5xx or 429 back.
+//!
+//! Each destination (scheme, host and port) has a circuit breaker. Once a destination has
+//! failed `OUTBOUND_HTTP_CIRCUIT_FAILURE_THRESHOLD` requests in a row, its requests fail fast
+//! for `OUTBOUND_HTTP_CIRCUIT_OPEN_SECS`; then a single request is let through to probe it,
+//! which closes the circuit when it succeeds.
+
+use anyhow::{Context, Result};
+use metrics::increment_counter;
+use reqwest::{IntoUrl, Method, RequestBuilder, Response, StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::config::OutboundHttpConfig;
+
+/// Failure sending a request through the [`HttpClient`]
+#[derive(Error, Debug)]
+pub enum HttpError {
+    #[error("{destination} is failing; requests are paused until it recovers")]
+    CircuitOpen { destination: String },
+
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+impl HttpError {
+    /// Whether the destination couldn't be reached, rather than answering badly
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            HttpError::CircuitOpen { .. } => true,
+            HttpError::Request(err) => err.is_timeout() || err.is_connect(),
+        }
+    }
+}
+
+/// Pooled HTTP client with retries and per-destination circuit breakers; clones share both
+#[derive(Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    max_retries: u32,
+    retry_base_delay: Duration,
+    circuits: Arc<CircuitBreakers>,
+}
+
+impl HttpClient {
+    /// Builds the client from `OUTBOUND_HTTP_*`
+    pub fn from_config(config: &OutboundHttpConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(config.pool_idle_timeout_secs));
+        if let Some(proxy_url) = &config.proxy_url {
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url).context("Invalid OUTBOUND_HTTP_PROXY_URL")?);
+        }
+
+        Ok(Self {
+            client: builder.build().context("Failed to build outbound HTTP client")?,
+            max_retries: config.max_retries,
+            retry_base_delay: Duration::from_millis(config.retry_base_delay_ms),
+            circuits: Arc::new(CircuitBreakers::new(
+                config.circuit_failure_threshold,
+                Duration::from_secs(config.circuit_open_secs),
+            )),
+        })
+    }
+
+    /// Starts a request, to be sent with [`send`](Self::send)
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Starts a POST request, to be sent with [`send`](Self::send)
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Sends a request, retrying it when that's safe, unless its destination's circuit is open
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, HttpError> {
+        let mut request = request.build()?;
+        let destination = destination(request.url());
+        let idempotent = is_idempotent(request.method());
+
+        let mut retries = 0;
+        loop {
+            if !self.circuits.admit(&destination) {
+                increment_counter!("outbound_http_circuit_rejections_total", "destination" => destination.clone());
+                return Err(HttpError::CircuitOpen { destination });
+            }
+
+            // Streamed bodies can't be copied, so those requests are only ever sent once
+            let retry = if retries < self.max_retries { request.try_clone() } else { None };
+            let result = self.client.execute(request).await;
+
+            let reached = matches!(&result, Ok(response) if !response.status().is_server_error());
+            self.circuits.record(&destination, reached);
+
+            let retryable = match &result {
+                Ok(response) => {
+                    idempotent && (response.status().is_server_error() || response.status() == StatusCode::TOO_MANY_REQUESTS)
+                },
+                Err(err) => err.is_connect() || (idempotent && err.is_timeout()),
+            };
+            match retry {
+                Some(next) if retryable => {
+                    tokio::time::sleep(self.retry_base_delay * 2u32.saturating_pow(retries)).await;
+                    retries += 1;
+                    request = next;
+                },
+                _ => return result.map_err(HttpError::from),
+            }
+        }
+    }
+}
+
+/// Scheme, host and port requests to `url` go to
+fn destination(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Whether sending the request twice has the same effect as sending it once
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS)
+}
+
+/// Circuit breakers of every destination requested so far
+struct CircuitBreakers {
+    /// 0 disables the breakers
+    failure_threshold: u32,
+    open_for: Duration,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+#[derive(Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    /// Requests fail fast until then; the first one after it is a probe
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreakers {
+    fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            failure_threshold,
+            open_for,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a request to `destination` may be sent
+    fn admit(&self, destination: &str) -> bool {
+        if self.failure_threshold == 0 {
+            return true;
+        }
+
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(destination) else {
+            return true;
+        };
+        match circuit.open_until {
+            None => true,
+            Some(until) if Instant::now() < until => false,
+            Some(_) => {
+                // Let this request probe the destination, and hold the rest back while it does
+                circuit.open_until = Some(Instant::now() + self.open_for);
+                true
+            },
+        }
+    }
+
+    /// Records whether a request to `destination` reached it
+    fn record(&self, destination: &str, reached: bool) {
+        if self.failure_threshold == 0 {
+            return;
+        }
+
+        let mut circuits = self.circuits.lock().unwrap();
+        if reached {
+            circuits.remove(destination);
+            return;
+        }
+
+        let circuit = circuits.entry(destination.to_string()).or_default();
+        circuit.consecutive_failures += 1;
+        if circuit.open_until.is_some() || circuit.consecutive_failures >= self.failure_threshold {
+            if circuit.open_until.is_none() {
+                warn!(
+                    "Pausing requests to {} for {:?} after {} consecutive failures",
+                    destination, self.open_for, circuit.consecutive_failures
+                );
+                increment_counter!("outbound_http_circuit_opened_total", "destination" => destination.to_string());
+            }
+            circuit.open_until = Some(Instant::now() + self.open_for);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn client(max_retries: u32, circuit_failure_threshold: u32) -> HttpClient {
+        HttpClient::from_config(&OutboundHttpConfig {
+            max_retries,
+            retry_base_delay_ms: 1,
+            circuit_failure_threshold,
+            ..OutboundHttpConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn idempotent_requests_are_retried_on_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/status"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET")).and(path("/status")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+        let client = client(2, 0);
+        let response = client.send(client.request(Method::GET, format!("{}/status", server.uri()))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn posts_are_not_retried_once_delivered() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(503)).mount(&server).await;
+
+        let client = client(2, 0);
+        let response = client.send(client.post(server.uri()).body("{}")).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_failing_destination_is_paused() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).respond_with(ResponseTemplate::new(500)).mount(&server).await;
+
+        let client = client(0, 2);
+        for _ in 0..2 {
+            client.send(client.post(server.uri())).await.unwrap();
+        }
+        let paused = client.send(client.post(server.uri())).await.unwrap_err();
+
+        assert!(matches!(paused, HttpError::CircuitOpen { .. }));
+        assert!(paused.is_unreachable());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn a_probe_closes_the_circuit_once_the_destination_recovers() {
+        let circuits = CircuitBreakers::new(1, Duration::ZERO);
+        let destination = "https://kyc.example";
+
+        circuits.record(destination, false);
+        // Open for no time at all, so the next request is the probe
+        assert!(circuits.admit(destination));
+        circuits.record(destination, true);
+
+        assert!(circuits.circuits.lock().unwrap().is_empty());
+        assert!(circuits.admit("https://other.example"));
+    }
+
+    #[test]
+    fn an_open_circuit_holds_requests_back() {
+        let circuits = CircuitBreakers::new(2, Duration::from_secs(60));
+        let destination = "https://kyc.example";
+
+        circuits.record(destination, false);
+        assert!(circuits.admit(destination));
+        circuits.record(destination, false);
+        assert!(!circuits.admit(destination));
+        assert!(circuits.admit("https://other.example"));
+    }
+}
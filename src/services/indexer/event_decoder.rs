@@ -0,0 +1,313 @@
+//! Decoder for the events the LSRWA Express contract emits
+//!
+//! Contract events reach the chain as `Contracts::ContractEmitted` records holding the
+//! SCALE-encoded event fields, with topics led by the event's signature topic: the BLAKE2-256 hash
+//! of `Name(FieldType,...)`, as ink! derives it. Decoded fields become the JSON the indexer reads,
+//! with request IDs as decimal strings, balances in tokens, accounts as SS58 addresses and enums by
+//! variant name.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use scale::Decode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use subxt::ext::sp_core::hashing::blake2_256;
+use subxt::utils::AccountId32;
+
+use super::event_processor::indexed_event;
+use super::event_types::IndexedEvent;
+use crate::models::amount::Amount;
+use crate::models::blockchain_request::RequestType;
+use crate::services::blockchain_service::BlockchainEvent;
+
+/// A `Contracts::ContractEmitted` record of the contract, as read from a block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractEmitted {
+    pub block_number: u64,
+    /// Hash of the extrinsic that emitted the event
+    pub transaction_hash: String,
+    /// Time of the block
+    pub timestamp: DateTime<Utc>,
+    /// `0x`-prefixed topics, the signature topic first
+    pub topics: Vec<String>,
+    /// `0x`-prefixed SCALE-encoded event fields
+    pub data: String,
+}
+
+/// Type of an event field
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    U32,
+    U128,
+    Timestamp,
+    Balance,
+    AccountId,
+    Bool,
+    RequestType,
+    FeeType,
+}
+
+impl Field {
+    /// Type name as the contract declares the field, which the signature topic covers
+    fn type_name(self) -> &'static str {
+        match self {
+            Field::U32 => "u32",
+            Field::U128 => "u128",
+            Field::Timestamp => "Timestamp",
+            Field::Balance => "Balance",
+            Field::AccountId => "AccountId",
+            Field::Bool => "bool",
+            Field::RequestType => "RequestType",
+            Field::FeeType => "FeeType",
+        }
+    }
+
+    /// Decodes the field, rendering balances in tokens of `decimals` decimals
+    fn decode(self, input: &mut &[u8], decimals: u32) -> Result<Value, scale::Error> {
+        let variant = |names: &[&str], index: u8| {
+            names
+                .get(index as usize)
+                .map(|name| Value::from(*name))
+                .ok_or_else(|| scale::Error::from("unknown enum variant"))
+        };
+
+        Ok(match self {
+            Field::U32 => Value::from(u32::decode(input)?),
+            Field::U128 => Value::from(u128::decode(input)?.to_string()),
+            Field::Timestamp => Value::from(u64::decode(input)?),
+            Field::Balance => Value::from(Amount::from_units(u128::decode(input)?).format(decimals)),
+            Field::AccountId => Value::from(AccountId32::from(<[u8; 32]>::decode(input)?).to_string()),
+            Field::Bool => Value::from(bool::decode(input)?),
+            Field::RequestType => Value::from(RequestType::decode(input)?.contract_name()),
+            Field::FeeType => variant(&["Withdrawal", "Liquidation"], u8::decode(input)?)?,
+        })
+    }
+}
+
+/// Events of the contract with their fields in declaration order
+const EVENTS: &[(&str, &[(&str, Field)])] = &[
+    ("DepositRequested", &[("request_id", Field::U128), ("wallet_address", Field::AccountId), ("amount", Field::Balance)]),
+    ("WithdrawalRequested", &[("request_id", Field::U128), ("wallet_address", Field::AccountId), ("amount", Field::Balance)]),
+    ("RequestProcessed", &[("request_id", Field::U128), ("wallet_address", Field::AccountId), ("amount", Field::Balance)]),
+    ("UserRegistered", &[("wallet_address", Field::AccountId)]),
+    (
+        "BorrowRequested",
+        &[
+            ("request_id", Field::U128),
+            ("wallet_address", Field::AccountId),
+            ("amount", Field::Balance),
+            ("collateral", Field::Balance),
+        ],
+    ),
+    ("BatchProcessed", &[("request_type", Field::RequestType), ("processed_count", Field::U32), ("failed_count", Field::U32)]),
+    ("KycStatusUpdated", &[("wallet_address", Field::AccountId), ("approved", Field::Bool)]),
+    ("BorrowLiquidated", &[("request_id", Field::U128), ("wallet_address", Field::AccountId), ("amount", Field::Balance)]),
+    (
+        "FeeCollected",
+        &[
+            ("request_id", Field::U128),
+            ("wallet_address", Field::AccountId),
+            ("fee_type", Field::FeeType),
+            ("amount", Field::Balance),
+        ],
+    ),
+    (
+        "RiskParametersUpdated",
+        &[
+            ("min_deposit_amount", Field::Balance),
+            ("min_withdrawal_amount", Field::Balance),
+            ("min_collateral_ratio", Field::U128),
+        ],
+    ),
+    (
+        "EpochClosed",
+        &[
+            ("epoch_id", Field::U32),
+            ("start_timestamp", Field::Timestamp),
+            ("end_timestamp", Field::Timestamp),
+            ("processed_deposit_count", Field::U32),
+            ("processed_withdrawal_count", Field::U32),
+            ("processed_borrow_count", Field::U32),
+        ],
+    ),
+    ("WithdrawalExecuted", &[("request_id", Field::U128), ("wallet_address", Field::AccountId), ("amount", Field::Balance)]),
+    ("EmergencyWithdrawal", &[("wallet_address", Field::AccountId), ("amount", Field::Balance)]),
+];
+
+/// Signature topic of an event
+fn signature_topic(name: &str, fields: &[(&str, Field)]) -> [u8; 32] {
+    let types = fields.iter().map(|(_, field)| field.type_name()).collect::<Vec<_>>();
+    blake2_256(format!("{}({})", name, types.join(",")).as_bytes())
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).with_context(|| format!("Invalid hex '{}'", value))
+}
+
+/// Decodes a contract event with balances in tokens of `decimals` decimals, or returns `None`
+/// when its signature topic isn't one of the contract's events
+pub fn decode_contract_event(emitted: &ContractEmitted, decimals: u32) -> Result<Option<BlockchainEvent>> {
+    // Anonymous events have no signature topic; the contract emits none
+    let Some(signature) = emitted.topics.first() else {
+        return Ok(None);
+    };
+    let signature = decode_hex(signature).context("Invalid signature topic")?;
+    let Some((name, fields)) = EVENTS.iter().find(|(name, fields)| signature_topic(name, fields)[..] == signature[..]) else {
+        return Ok(None);
+    };
+
+    let bytes = decode_hex(&emitted.data).with_context(|| format!("Invalid {} event data", name))?;
+    let mut input = &bytes[..];
+    let mut data = Map::new();
+    for (field_name, field) in fields.iter() {
+        let value = field
+            .decode(&mut input, decimals)
+            .with_context(|| format!("Failed to decode {} of {} event", field_name, name))?;
+        data.insert(field_name.to_string(), value);
+    }
+    if !input.is_empty() {
+        bail!("{} event has {} bytes left over after its fields", name, input.len());
+    }
+
+    Ok(Some(BlockchainEvent {
+        event_type: name.to_string(),
+        transaction_hash: emitted.transaction_hash.clone(),
+        block_number: emitted.block_number,
+        timestamp: emitted.timestamp,
+        data: Value::Object(data),
+    }))
+}
+
+/// Decodes a contract event into the event the indexer queues, or returns `None` when it isn't
+/// one of the contract's events
+pub fn index_contract_event(emitted: &ContractEmitted, decimals: u32) -> Result<Option<IndexedEvent>> {
+    Ok(decode_contract_event(emitted, decimals)?.map(|event| indexed_event(emitted.block_number, event)))
+}
+
+/// An event as `tests/fixtures/events` records it: the raw event next to the event the indexer
+/// queues for it, without its random ID. Returns `None` when it isn't one of the contract's
+/// events.
+pub fn event_recording(emitted: &ContractEmitted, decimals: u32) -> Result<Option<Value>> {
+    let Some(indexed) = index_contract_event(emitted, decimals)? else {
+        return Ok(None);
+    };
+
+    let mut indexed_json = serde_json::to_value(&indexed).context("Failed to serialize indexed event")?;
+    if let Some(fields) = indexed_json.as_object_mut() {
+        fields.remove("id");
+    }
+
+    Ok(Some(serde_json::json!({
+        "emitted": emitted,
+        "indexed": indexed_json,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::config::ChainNetwork;
+
+    /// Decimals of the devnet the recordings are from
+    fn decimals() -> u32 {
+        ChainNetwork::Local.token_decimals()
+    }
+
+    /// Recordings of the contract's events from a devnet run
+    fn recordings() -> Vec<(String, Value)> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/events");
+        let mut paths = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap())
+            })
+            .collect()
+    }
+
+    fn recorded_event(recording: &Value) -> ContractEmitted {
+        serde_json::from_value(recording["emitted"].clone()).unwrap()
+    }
+
+    fn emitted(name: &str, data: &[u8]) -> ContractEmitted {
+        let fields = EVENTS.iter().find(|(event, _)| *event == name).unwrap().1;
+        ContractEmitted {
+            block_number: 1,
+            transaction_hash: format!("0x{}", hex::encode([7; 32])),
+            timestamp: Utc::now(),
+            topics: vec![format!("0x{}", hex::encode(signature_topic(name, fields)))],
+            data: format!("0x{}", hex::encode(data)),
+        }
+    }
+
+    #[test]
+    fn recorded_events_decode_to_their_golden_indexed_events() {
+        for (name, recording) in recordings() {
+            let decoded = event_recording(&recorded_event(&recording), decimals())
+                .unwrap_or_else(|e| panic!("{}: {:#}", name, e))
+                .unwrap_or_else(|| panic!("{}: signature topic not recognised", name));
+
+            assert_eq!(decoded, recording, "{} no longer decodes to its recorded indexed event", name);
+        }
+    }
+
+    #[test]
+    fn every_contract_event_has_a_recording() {
+        let recorded = recordings()
+            .iter()
+            .filter_map(|(_, recording)| decode_contract_event(&recorded_event(recording), decimals()).ok().flatten())
+            .map(|event| event.event_type)
+            .collect::<BTreeSet<_>>();
+
+        for (name, _) in EVENTS {
+            assert!(recorded.contains(*name), "No recording in tests/fixtures/events decodes to {}", name);
+        }
+    }
+
+    #[test]
+    fn events_of_other_contracts_are_skipped() {
+        let mut other = emitted("UserRegistered", &[1; 32]);
+        other.topics = vec![format!("0x{}", hex::encode(blake2_256(b"Transfer(Option<AccountId>,Option<AccountId>,Balance)")))];
+        assert!(decode_contract_event(&other, decimals()).unwrap().is_none());
+
+        other.topics.clear();
+        assert!(decode_contract_event(&other, decimals()).unwrap().is_none());
+    }
+
+    #[test]
+    fn malformed_event_data_is_an_error() {
+        // A request ID and part of an account
+        let truncated = emitted("DepositRequested", &[1; 20]);
+        assert!(decode_contract_event(&truncated, decimals()).is_err());
+
+        let trailing = emitted("UserRegistered", &[1; 33]);
+        assert!(decode_contract_event(&trailing, decimals()).is_err());
+
+        // RequestType has three variants
+        let mut batch = vec![3];
+        batch.extend_from_slice(&[0; 8]);
+        assert!(decode_contract_event(&emitted("BatchProcessed", &batch), decimals()).is_err());
+    }
+
+    #[test]
+    fn balances_are_rendered_in_tokens_of_the_network() {
+        let mut withdrawal = vec![1; 32];
+        withdrawal.extend_from_slice(&40_500_000_000_000u128.to_le_bytes());
+        let withdrawal = emitted("EmergencyWithdrawal", &withdrawal);
+
+        let amount = |decimals| decode_contract_event(&withdrawal, decimals).unwrap().unwrap().data["amount"].clone();
+        assert_eq!(amount(12), "40.5");
+        assert_eq!(amount(10), "4050");
+    }
+}
@@ -0,0 +1,322 @@
+//! Off-chain side effects of indexed events
+
+use super::event_types::{
+    BatchProcessed, EpochClosed, EpochCreated, EventPayload, FeeCollected, IndexedEvent, RequestEvent, UserRegistered,
+};
+use crate::db::{
+    ActivityLogRepository, BalanceRepository, BlockchainRequestRepository, EpochRepository, TreasuryRepository,
+    UnitOfWork, UserRepository,
+};
+use crate::models::activity_log::CreateActivityLogRequest;
+use crate::models::blockchain_request::{BlockchainRequest, NewBlockchainRequest, RequestType};
+use crate::models::request_status::{RequestStatus, StatusChange};
+use crate::models::treasury::NewProtocolFee;
+use crate::models::wallet::WalletAddress;
+use crate::services::cache::{keys, Cache};
+use crate::services::event_bus::EventPublisher;
+use crate::services::rewards::RewardCalculationService;
+
+use anyhow::{bail, Context, Result};
+use metrics::{counter, increment_counter};
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Applies indexed events to the database
+#[derive(Clone)]
+pub struct EventHandlers {
+    /// Database connection pool
+    db: PgPool,
+    /// Cache invalidated after balance changes
+    cache: Cache,
+    /// Rewards calculated when an epoch closes
+    rewards: RewardCalculationService,
+    /// Event bus processed requests are published on
+    events: EventPublisher,
+}
+
+impl EventHandlers {
+    /// Creates the event handlers
+    pub fn new(db: PgPool, cache: Cache, rewards: RewardCalculationService, events: EventPublisher) -> Self {
+        Self { db, cache, rewards, events }
+    }
+    
+    /// Dispatches an event to its handler
+    pub async fn handle(&self, event: &IndexedEvent) -> Result<()> {
+        match &event.payload {
+            EventPayload::UserRegistration(registered) => self.handle_user_registration(registered).await,
+            EventPayload::DepositRequest(request) => {
+                self.handle_request_submitted(event, request, RequestType::Deposit, None).await
+            },
+            EventPayload::WithdrawalRequest(request) => {
+                self.handle_request_submitted(event, request, RequestType::Withdrawal, None).await
+            },
+            EventPayload::BorrowRequest(borrow) => {
+                self.handle_request_submitted(event, &borrow.request, RequestType::Borrow, Some(&borrow.collateral)).await
+            },
+            EventPayload::RequestExecution(request) => self.handle_request_execution(event, request).await,
+            EventPayload::EpochClosing(closed) => self.handle_epoch_closing(event, closed).await,
+            EventPayload::FeeCollection(fee) => self.handle_fee_collected(event, fee).await,
+            EventPayload::BatchProcessing(batch) => {
+                self.handle_batch_processed(batch);
+                Ok(())
+            },
+            EventPayload::EpochCreation(created) => self.handle_epoch_created(created).await,
+            // Failing records the event as failed, so unknown or malformed events are visible
+            EventPayload::ValidationFailure(data) => bail!("Event {} is not one the indexer handles: {}", event.id, data),
+        }
+    }
+    
+    /// Creates or refreshes the user and links their existing requests
+    async fn handle_user_registration(&self, registered: &UserRegistered) -> Result<()> {
+        let wallet_address = parse_wallet(&registered.wallet_address)?;
+        
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        
+        // Registration doesn't carry a KYC status, so the user's is left as it is
+        let user = UserRepository::upsert_from_chain_event_in(uow.conn(), &wallet_address, false).await?;
+        let linked = BlockchainRequestRepository::link_to_user_in(uow.conn(), &wallet_address, user.id).await?;
+        
+        uow.commit().await?;
+        
+        if linked > 0 {
+            info!("Linked {} existing requests to user {}", linked, user.id);
+        }
+        
+        Ok(())
+    }
+    
+    /// Records a newly submitted request, with the collateral pledged for a borrow
+    async fn handle_request_submitted(
+        &self,
+        event: &IndexedEvent,
+        submitted: &RequestEvent,
+        request_type: RequestType,
+        collateral: Option<&str>,
+    ) -> Result<()> {
+        let new_request = NewBlockchainRequest {
+            request_type,
+            on_chain_id: submitted.request_id as i64,
+            wallet_address: parse_wallet(&submitted.wallet_address)?,
+            amount: parse_amount(&submitted.amount)?,
+            collateral_amount: collateral.map(parse_amount).transpose()?,
+            timestamp: event.timestamp.naive_utc(),
+            status: RequestStatus::Confirmed,
+            block_number: event.block_number as i64,
+            transaction_hash: event.transaction_hash.clone(),
+            // Events don't name an asset; requests the API recorded keep theirs
+            asset: None,
+        };
+        
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        
+        // Replayed events must not reserve the same amount twice
+        let already_indexed = BlockchainRequestRepository::find_by_on_chain_id_in(
+            uow.conn(),
+            &new_request.request_type,
+            new_request.on_chain_id,
+        )
+        .await?
+        .is_some();
+        
+        let request = BlockchainRequestRepository::insert_in(uow.conn(), &new_request).await?;
+        
+        // Requests recorded when the API submitted them are confirmed by their event
+        if request.status == RequestStatus::Submitted {
+            BlockchainRequestRepository::transition_in(
+                uow.conn(),
+                &request.request_type,
+                request.on_chain_id,
+                RequestStatus::Confirmed,
+            )
+            .await?;
+        }
+        
+        if !already_indexed {
+            if let Some(user_id) = request.user_id {
+                let amount = request_amount(&request)?;
+                
+                let reserved = BalanceRepository::reserve_pending_in(uow.conn(), user_id, &request.asset, &request.request_type, &amount)
+                    .await?
+                    .is_some();
+                
+                if !reserved {
+                    warn!(
+                        "Could not reserve {} for user {} on {} request {}",
+                        amount, user_id, request.request_type, request.on_chain_id
+                    );
+                }
+                
+                ActivityLogRepository::record_in(
+                    uow.conn(),
+                    &request_activity(&request, &format!("{}_requested", request.request_type)),
+                )
+                .await?;
+            }
+        }
+        
+        uow.commit().await?;
+        
+        if let (false, Some(user_id)) = (already_indexed, request.user_id) {
+            self.cache.invalidate(&keys::user_balance(user_id)).await;
+        }
+        
+        Ok(())
+    }
+    
+    /// Marks a request executed and settles the user's balance
+    async fn handle_request_execution(&self, event: &IndexedEvent, executed: &RequestEvent) -> Result<()> {
+        let request_id = executed.request_id;
+        
+        // Only withdrawals are executed on-chain when the event doesn't say otherwise
+        let request_type = event.request_type.clone().unwrap_or(RequestType::Withdrawal);
+        
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        
+        let change = BlockchainRequestRepository::transition_in(
+            uow.conn(),
+            &request_type,
+            request_id as i64,
+            RequestStatus::Executed,
+        )
+        .await?;
+        if !matches!(change, Some(StatusChange::Moved(_))) {
+            info!("Executed {} request {} is not indexed or already executed", request_type, request_id);
+            return uow.rollback().await;
+        }
+        
+        let request = BlockchainRequestRepository::find_by_on_chain_id_in(uow.conn(), &request_type, request_id as i64)
+            .await?
+            .context("Executed request disappeared")?;
+        
+        if let Some(user_id) = request.user_id {
+            if request.request_type == RequestType::Withdrawal {
+                let amount = request_amount(&request)?;
+                
+                if BalanceRepository::apply_withdrawal_in(uow.conn(), user_id, &request.asset, &amount).await?.is_none() {
+                    warn!("Active balance of {} does not cover executed withdrawal {}", user_id, request_id);
+                }
+            }
+            
+            ActivityLogRepository::record_in(
+                uow.conn(),
+                &request_activity(&request, &format!("{}_executed", request.request_type)),
+            )
+            .await?;
+        }
+        
+        uow.commit().await?;
+        
+        if let Some(user_id) = request.user_id {
+            self.cache.invalidate(&keys::user_balance(user_id)).await;
+        }
+        self.events
+            .publish_processed_requests(std::slice::from_ref(&request), None, &event.transaction_hash)
+            .await;
+        
+        Ok(())
+    }
+    
+    /// Records a fee paid to the treasury
+    async fn handle_fee_collected(&self, event: &IndexedEvent, collected: &FeeCollected) -> Result<()> {
+        let fee_type = collected.fee_type;
+        let fee = NewProtocolFee {
+            fee_type,
+            request_id: collected.request_id as i64,
+            wallet_address: collected.wallet_address.clone(),
+            amount: collected.amount.clone(),
+            block_number: event.block_number as i64,
+            transaction_hash: event.transaction_hash.clone(),
+            collected_at: event.timestamp,
+        };
+        
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        let recorded = TreasuryRepository::record_fee_in(uow.conn(), &fee).await?;
+        uow.commit().await?;
+        
+        match recorded {
+            Some(recorded) => {
+                increment_counter!("protocol_fees_collected_total", "type" => fee_type.to_string());
+                info!("Recorded {} fee of {} on request {}", fee_type, recorded.amount, recorded.request_id);
+            },
+            None => info!("{} fee on request {} is already recorded", fee_type, fee.request_id),
+        }
+        
+        Ok(())
+    }
+    
+    /// Counts the requests a batch processed. Each request is settled by its own execution event,
+    /// so there is nothing to apply to the database.
+    fn handle_batch_processed(&self, batch: &BatchProcessed) {
+        let request_type = batch.request_type.to_string();
+        for (outcome, count) in [("processed", batch.processed_count), ("failed", batch.failed_count)] {
+            counter!("indexer_batch_requests_total", u64::from(count), "type" => request_type.clone(), "outcome" => outcome);
+        }
+        
+        if batch.failed_count > 0 {
+            warn!(
+                "Batch of {} requests processed {} and failed {}",
+                batch.request_type, batch.processed_count, batch.failed_count
+            );
+        } else {
+            info!("Batch of {} requests processed {}", batch.request_type, batch.processed_count);
+        }
+    }
+    
+    /// Opens the epoch the contract started, unless the database already has it open
+    async fn handle_epoch_created(&self, created: &EpochCreated) -> Result<()> {
+        let active = EpochRepository::open_next_in(&self.db).await?;
+        if i64::from(active) != i64::from(created.epoch_id) {
+            bail!("Contract started epoch {} but epoch {} is active in the database", created.epoch_id, active);
+        }
+        
+        info!("Epoch {} is active", active);
+        Ok(())
+    }
+    
+    /// Ends the epoch and calculates its rewards
+    async fn handle_epoch_closing(&self, event: &IndexedEvent, closed: &EpochClosed) -> Result<()> {
+        let epoch_id = i32::try_from(closed.epoch_id).context("Closed epoch ID is out of range")?;
+        
+        if EpochRepository::new(self.db.clone()).close(epoch_id, event.timestamp).await?.is_none() {
+            warn!("Closed epoch {} is not indexed", epoch_id);
+            return Ok(());
+        }
+        
+        self.rewards.calculate_epoch(epoch_id).await?;
+        
+        Ok(())
+    }
+}
+
+/// Activity log entry for a request lifecycle step
+fn request_activity(request: &BlockchainRequest, activity_type: &str) -> CreateActivityLogRequest {
+    CreateActivityLogRequest {
+        user_id: request.user_id,
+        activity_type: activity_type.to_string(),
+        description: None,
+        data: Some(serde_json::json!({
+            "request_id": request.on_chain_id,
+            "amount": request.amount,
+            "transaction_hash": request.transaction_hash,
+        })),
+        ip_address: None,
+    }
+}
+
+/// Parses the wallet of an event
+fn parse_wallet(wallet_address: &str) -> Result<WalletAddress> {
+    wallet_address.parse().with_context(|| format!("Event has an invalid wallet address {}", wallet_address))
+}
+
+/// Parses an amount of an event
+fn parse_amount(amount: &str) -> Result<f64> {
+    amount.parse().with_context(|| format!("Event has an invalid amount {}", amount))
+}
+
+/// Parses a stored request amount
+fn request_amount(request: &BlockchainRequest) -> Result<BigDecimal> {
+    BigDecimal::from_str(&request.amount)
+        .with_context(|| format!("Invalid amount on request {}", request.id))
+}
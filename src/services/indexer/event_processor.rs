@@ -1,146 +1,139 @@
 //! Event processor for blockchain events
 
 use super::event_queue::EventQueue;
-use super::event_types::EventType;
+use super::event_types::{EventPayload, IndexedEvent};
+use super::queue_depth::QueueDepth;
 use crate::api::blockchain::BlockchainState;
+use crate::models::alert::{Alert, AlertSeverity};
 use crate::models::blockchain_request::RequestType;
-use crate::services::BlockchainService;
+use crate::services::blockchain_service::BlockchainEvent;
+use crate::services::ChainClient;
+use crate::config::AlertConfig;
 use crate::db::DbPools;
+use crate::services::alerting::Alerter;
+use crate::services::shutdown::Shutdown;
 
 use anyhow::{Context, Result};
+use metrics::gauge;
+use sqlx::PgPool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tokio::time::{self, Duration};
-use tracing::{info, error};
-use serde_json;
+use tracing::{info, error, warn};
+
+/// Seconds between polls of the chain for new blocks
+const POLLING_INTERVAL_SECS: u64 = 60;
+
+/// How often a backfill waiting for room in the queue checks its depth
+const QUEUE_ROOM_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Event processor for blockchain events
 pub struct EventProcessor {
-    /// Database connection pools
-    db: DbPools,
+    /// Database the checkpoint is kept in
+    db: PgPool,
     /// Blockchain service
-    blockchain_service: Arc<BlockchainService>,
-    /// Blockchain state
+    blockchain_service: Arc<dyn ChainClient>,
+    /// Blockchain state, updated with each indexed event
     blockchain_state: Arc<RwLock<BlockchainState>>,
     /// Event queue
     event_queue: Arc<EventQueue>,
+    /// Task handling queued events, finished once the queue is dropped and drained
+    queue_task: JoinHandle<()>,
     /// Last processed block
     last_processed_block: u64,
-    /// Polling interval in seconds
-    polling_interval: u64,
+    /// Operator alerting
+    alerts: Alerter,
+    /// Number of blocks behind the chain head before alerting
+    lag_alert_blocks: u64,
+    /// Consecutive failed block number lookups before alerting that the node is unreachable
+    rpc_failure_threshold: u32,
+    /// Consecutive failed block number lookups so far
+    rpc_failures: u32,
+    /// Events enqueued and not yet handled
+    queue_depth: QueueDepth,
 }
 
 impl EventProcessor {
-    /// Creates a new event processor
+    /// Creates an event processor indexing into `event_queue`, handled by `workers` tasks
     pub async fn new(
-        db: DbPools,
-        blockchain_service: Arc<BlockchainService>,
+        db: &DbPools,
+        event_queue: EventQueue,
+        blockchain_service: Arc<dyn ChainClient>,
         blockchain_state: Arc<RwLock<BlockchainState>>,
-        buffer_size: usize,
-        max_attempts: u32,
-        retry_delay: u64,
-        polling_interval: u64,
+        alerts: Alerter,
+        alert_config: &AlertConfig,
+        workers: usize,
     ) -> Result<Self> {
-        // Create the event queue
-        let event_queue = Arc::new(EventQueue::new(
-            db.pg.clone(),
-            buffer_size,
-            max_attempts,
-            retry_delay,
-        ));
+        let queue_depth = event_queue.depth();
+        let event_queue = Arc::new(event_queue);
         
         // Start the event queue processor
-        event_queue.start_processing().await?;
+        let queue_task = event_queue.start_processing(workers).await?;
         
-        // Get the last processed block from the database or use 0 as default
-        let last_processed_block = Self::get_last_processed_block(&db).await?;
+        // Resume after the last checkpoint, or from the start of the chain
+        let last_processed_block = Self::get_last_processed_block(&db.pg).await?;
         
         Ok(Self {
-            db,
+            db: db.pg.clone(),
             blockchain_service,
             blockchain_state,
             event_queue,
+            queue_task,
             last_processed_block,
-            polling_interval,
+            alerts,
+            lag_alert_blocks: alert_config.indexer_lag_blocks,
+            rpc_failure_threshold: alert_config.rpc_failure_threshold,
+            rpc_failures: 0,
+            queue_depth,
         })
     }
     
-    /// Gets the last processed block from the database
-    async fn get_last_processed_block(_db: &DbPools) -> Result<u64> {
-        // For now, return 0 to avoid database errors
-        // In a production environment, this would query the database
-        Ok(0)
-        
-        /*
-        let result = sqlx::query!(
-            r#"
-            SELECT value FROM lsrwa_express.system_settings
-            WHERE key = 'last_processed_block'
-            "#
+    /// Gets the block indexing resumes after, from the checkpoint in the database
+    async fn get_last_processed_block(db: &PgPool) -> Result<u64> {
+        let value: Option<String> = sqlx::query_scalar(
+            "SELECT value FROM lsrwa_express.system_settings WHERE key = 'last_processed_block'",
         )
-        .fetch_optional(&db.pg)
+        .fetch_optional(db)
         .await
         .context("Failed to query last processed block")?;
         
-        match result {
-            Some(row) => {
-                let block = row.value.parse::<u64>()
-                    .context("Failed to parse last processed block")?;
-                Ok(block)
-            },
-            None => {
-                // Insert a default value
-                sqlx::query!(
-                    r#"
-                    INSERT INTO lsrwa_express.system_settings (key, value)
-                    VALUES ('last_processed_block', '0')
-                    "#
-                )
-                .execute(&db.pg)
-                .await
-                .context("Failed to insert default last processed block")?;
-                
-                Ok(0)
-            }
+        match value {
+            Some(value) => value.parse().with_context(|| format!("Invalid last processed block '{}'", value)),
+            None => Ok(0),
         }
-        */
     }
     
-    /// Updates the last processed block in the database
-    async fn update_last_processed_block(&self, _block_number: u64) -> Result<()> {
-        // For now, do nothing to avoid database errors
-        // In a production environment, this would update the database
-        Ok(())
-        
-        /*
-        sqlx::query!(
+    /// Checkpoints the last processed block in the database
+    async fn update_last_processed_block(&self, block_number: u64) -> Result<()> {
+        sqlx::query(
             r#"
-            UPDATE lsrwa_express.system_settings
-            SET value = $1
-            WHERE key = 'last_processed_block'
+            INSERT INTO lsrwa_express.system_settings (key, value)
+            VALUES ('last_processed_block', $1)
+            ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value
             "#,
-            block_number.to_string(),
         )
-        .execute(&self.db.pg)
+        .bind(block_number.to_string())
+        .execute(&self.db)
         .await
         .context("Failed to update last processed block")?;
         
         Ok(())
-        */
     }
     
-    /// Starts the event processor
-    pub async fn start(&mut self) -> Result<()> {
-        info!("Starting event processor with polling interval {} seconds", self.polling_interval);
+    /// Runs the event processor until shutdown
+    ///
+    /// On shutdown the block being indexed is finished and checkpointed, then the events already
+    /// queued are handled before this returns.
+    pub async fn start(mut self, shutdown: Shutdown) -> Result<()> {
+        info!("Starting event processor with polling interval {} seconds", POLLING_INTERVAL_SECS);
         
         // Create a ticker for the polling interval
-        let mut interval = time::interval(Duration::from_secs(self.polling_interval));
+        let mut interval = time::interval(Duration::from_secs(POLLING_INTERVAL_SECS));
         
-        loop {
-            interval.tick().await;
-            
+        while shutdown.tick(&mut interval).await {
             // Process new events
-            match self.process_new_events().await {
+            match self.process_new_events(&shutdown).await {
                 Ok(count) => {
                     if count > 0 {
                         info!("Processed {} new events", count);
@@ -151,13 +144,70 @@ impl EventProcessor {
                 }
             }
         }
+        
+        info!("Event processor stopping at block {}", self.last_processed_block);
+        self.event_queue.flush().await
+            .context("Failed to write the events held for batching")?;
+        self.update_last_processed_block(self.last_processed_block).await
+            .context("Failed to checkpoint last processed block")?;
+        
+        // Dropping the queue closes its channel, so the queue task exits once it is drained
+        let Self { event_queue, queue_task, .. } = self;
+        drop(event_queue);
+        queue_task.await.context("Event queue processor panicked")?;
+        
+        info!("Event processor stopped");
+        Ok(())
+    }
+    
+    /// Records how far behind the chain head the indexer is, alerting past the threshold
+    async fn check_lag(&self, current_block: u64) {
+        let lag = current_block.saturating_sub(self.last_processed_block);
+        gauge!("indexer_lag_blocks", lag as f64);
+
+        if lag > self.lag_alert_blocks {
+            self.alerts
+                .raise(Alert {
+                    key: "indexer_lag".to_string(),
+                    severity: AlertSeverity::Warning,
+                    title: "Indexer falling behind".to_string(),
+                    details: format!(
+                        "{} blocks behind the chain head (last processed {}, head {})",
+                        lag, self.last_processed_block, current_block
+                    ),
+                })
+                .await;
+        } else {
+            self.alerts.resolve("indexer_lag").await;
+        }
     }
     
     /// Processes new events from the blockchain
-    async fn process_new_events(&mut self) -> Result<usize> {
+    async fn process_new_events(&mut self, shutdown: &Shutdown) -> Result<usize> {
         // Get the current block number
-        let current_block = self.blockchain_service.get_current_block_number().await
-            .context("Failed to get current block number")?;
+        let current_block = match self.blockchain_service.get_current_block_number().await {
+            Ok(block) => block,
+            Err(err) => {
+                self.rpc_failures += 1;
+                if self.rpc_failures >= self.rpc_failure_threshold {
+                    self.alerts
+                        .raise(Alert {
+                            key: "rpc_unreachable".to_string(),
+                            severity: AlertSeverity::Critical,
+                            title: "Blockchain node unreachable".to_string(),
+                            details: format!("{} consecutive failed block number lookups: {:#}", self.rpc_failures, err),
+                        })
+                        .await;
+                }
+                return Err(err).context("Failed to get current block number");
+            },
+        };
+        if self.rpc_failures > 0 {
+            self.rpc_failures = 0;
+            self.alerts.resolve("rpc_unreachable").await;
+        }
+
+        self.check_lag(current_block).await;
         
         // If there are no new blocks, return early
         if current_block <= self.last_processed_block {
@@ -170,167 +220,114 @@ impl EventProcessor {
         
         // Process each block
         for block_number in (self.last_processed_block + 1)..=current_block {
-            // Get events for this block
-            let events = self.blockchain_service.get_events_for_block(block_number).await
-                .context(format!("Failed to get events for block {}", block_number))?;
-            
-            // Process each event
-            for event in events {
-                // Create an indexed event
-                let indexed_event = match event.event_type.as_str() {
-                    "DepositRequested" => {
-                        let request_id = event.data.get("request_id")
-                            .and_then(|v| v.as_str())
-                            .and_then(|s| s.parse::<u128>().ok());
-                            
-                        let wallet_address = event.data.get("wallet_address")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        let amount = event.data.get("amount")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        EventQueue::create_event(
-                            EventType::DepositRequest,
-                            block_number,
-                            event.transaction_hash,
-                            request_id,
-                            wallet_address,
-                            amount,
-                            Some(RequestType::Deposit),
-                            event.timestamp,
-                            serde_json::to_string(&event.data).unwrap_or_default(),
-                        )
-                    },
-                    "WithdrawalRequested" => {
-                        let request_id = event.data.get("request_id")
-                            .and_then(|v| v.as_str())
-                            .and_then(|s| s.parse::<u128>().ok());
-                            
-                        let wallet_address = event.data.get("wallet_address")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        let amount = event.data.get("amount")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        EventQueue::create_event(
-                            EventType::WithdrawalRequest,
-                            block_number,
-                            event.transaction_hash,
-                            request_id,
-                            wallet_address,
-                            amount,
-                            Some(RequestType::Withdrawal),
-                            event.timestamp,
-                            serde_json::to_string(&event.data).unwrap_or_default(),
-                        )
-                    },
-                    "RequestExecuted" => {
-                        let request_id = event.data.get("request_id")
-                            .and_then(|v| v.as_str())
-                            .and_then(|s| s.parse::<u128>().ok());
-                            
-                        let wallet_address = event.data.get("wallet_address")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        let amount = event.data.get("amount")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        EventQueue::create_event(
-                            EventType::RequestExecution,
-                            block_number,
-                            event.transaction_hash,
-                            request_id,
-                            wallet_address,
-                            amount,
-                            None, // Request type not available in this event
-                            event.timestamp,
-                            serde_json::to_string(&event.data).unwrap_or_default(),
-                        )
-                    },
-                    "UserRegistered" => {
-                        let wallet_address = event.data.get("wallet_address")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        EventQueue::create_event(
-                            EventType::UserRegistration,
-                            block_number,
-                            event.transaction_hash,
-                            None,
-                            wallet_address,
-                            None,
-                            None,
-                            event.timestamp,
-                            serde_json::to_string(&event.data).unwrap_or_default(),
-                        )
-                    },
-                    "RequestValidationFailed" => {
-                        let wallet_address = event.data.get("wallet_address")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        let amount = event.data.get("amount")
-                            .and_then(|v| v.as_str())
-                            .map(|s| s.to_string());
-                            
-                        let request_type_str = event.data.get("request_type")
-                            .and_then(|v| v.as_str());
-                            
-                        let request_type = match request_type_str {
-                            Some("Deposit") => Some(RequestType::Deposit),
-                            Some("Withdrawal") => Some(RequestType::Withdrawal),
-                            Some("Borrow") => Some(RequestType::Borrow),
-                            _ => None,
-                        };
-                            
-                        EventQueue::create_event(
-                            EventType::ValidationFailure,
-                            block_number,
-                            event.transaction_hash,
-                            None,
-                            wallet_address,
-                            amount,
-                            request_type,
-                            event.timestamp,
-                            serde_json::to_string(&event.data).unwrap_or_default(),
-                        )
-                    },
-                    // Add more event types as needed
-                    _ => {
-                        // Unknown event type, create a generic event
-                        EventQueue::create_event(
-                            EventType::ValidationFailure, // Default to validation failure for unknown events
-                            block_number,
-                            event.transaction_hash,
-                            None,
-                            None,
-                            None,
-                            None,
-                            event.timestamp,
-                            serde_json::to_string(&event.data).unwrap_or_default(),
-                        )
-                    }
-                };
-                
-                // Enqueue the event for processing
-                self.event_queue.enqueue(indexed_event).await
-                    .context("Failed to enqueue event")?;
-                
-                event_count += 1;
+            // Stop between blocks; everything up to the last checkpoint is fully queued
+            if shutdown.is_triggered() {
+                break;
             }
             
-            // Update the last processed block
+            event_count += self.index_block(block_number).await?;
             self.last_processed_block = block_number;
-            self.update_last_processed_block(block_number).await
-                .context("Failed to update last processed block")?;
+            
+            // Only checkpoint blocks whose events are all written; the rest are written with
+            // their batch
+            if self.event_queue.pending().await == 0 {
+                self.update_last_processed_block(block_number).await
+                    .context("Failed to update last processed block")?;
+            }
         }
         
+        self.event_queue.flush().await
+            .context("Failed to write the events held for batching")?;
+        self.update_last_processed_block(self.last_processed_block).await
+            .context("Failed to update last processed block")?;
+        
         Ok(event_count)
     }
-} 
\ No newline at end of file
+    
+    /// Re-indexes the blocks in `from..=to` without moving the checkpoint, then handles the
+    /// events it queued before returning. Handlers skip events that are already recorded, so a
+    /// range can be backfilled more than once. Returns the number of events queued.
+    ///
+    /// Backfill gives way to indexing at the chain head: before each block it waits for the
+    /// queue to drain below `max_queue_depth` events, so the handlers, the database and the
+    /// node keep room for new blocks.
+    pub async fn backfill(self, from: u64, to: u64, max_queue_depth: usize, shutdown: &Shutdown) -> Result<usize> {
+        info!("Backfilling blocks {} to {}", from, to);
+        
+        let mut event_count = 0;
+        for block_number in from..=to {
+            self.wait_for_queue_room(max_queue_depth, shutdown).await?;
+            if shutdown.is_triggered() {
+                warn!("Backfill stopped before block {}", block_number);
+                break;
+            }
+            
+            event_count += self.index_block(block_number).await?;
+        }
+        
+        self.event_queue.flush().await
+            .context("Failed to write the events held for batching")?;
+        let Self { event_queue, queue_task, .. } = self;
+        drop(event_queue);
+        queue_task.await.context("Event queue processor panicked")?;
+        
+        info!("Backfilled {} events", event_count);
+        Ok(event_count)
+    }
+    
+    /// Waits until fewer than `max_queue_depth` events are queued, or shutdown. Events held for
+    /// batching are written first, since they would otherwise wait for the next block.
+    async fn wait_for_queue_room(&self, max_queue_depth: usize, shutdown: &Shutdown) -> Result<()> {
+        if self.queue_depth.get() < max_queue_depth {
+            return Ok(());
+        }
+        
+        self.event_queue.flush().await
+            .context("Failed to write the events held for batching")?;
+        while self.queue_depth.get() >= max_queue_depth && !shutdown.is_triggered() {
+            time::sleep(QUEUE_ROOM_POLL_INTERVAL).await;
+        }
+        
+        Ok(())
+    }
+    
+    /// Queues the events of one block. Returns the number of events queued.
+    async fn index_block(&self, block_number: u64) -> Result<usize> {
+        let mut event_count = 0;
+        
+        // Get events for this block
+        let events = self.blockchain_service.get_events_for_block(block_number).await
+            .context(format!("Failed to get events for block {}", block_number))?;
+        
+        // Apply the events to the in-memory state, then queue them for their handlers
+        {
+            let mut state = self.blockchain_state.write().await;
+            for event in &events {
+                state.apply(event);
+            }
+        }
+        
+        for event in events {
+            // Enqueue the event for processing
+            self.event_queue.enqueue(indexed_event(block_number, event)).await
+                .context("Failed to enqueue event")?;
+            
+            event_count += 1;
+        }
+        
+        Ok(event_count)
+    }
+}
+
+/// The event queued for a contract event, with its fields in the payload type of its event type
+pub(crate) fn indexed_event(block_number: u64, event: BlockchainEvent) -> IndexedEvent {
+    let payload = EventPayload::from_contract_event(&event.event_type, event.data);
+    let mut indexed = EventQueue::create_event(payload, block_number, event.transaction_hash, event.timestamp);
+    
+    // Only withdrawals have an execution event of their own
+    if event.event_type == "WithdrawalExecuted" {
+        indexed.request_type = Some(RequestType::Withdrawal);
+    }
+    
+    indexed
+}
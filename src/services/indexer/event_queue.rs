@@ -1,15 +1,27 @@
 //! Event queue for blockchain events
 
-use super::event_types::{IndexedEvent, ProcessingStatus};
-use crate::models::blockchain_request::RequestType;
+use super::event_handlers::EventHandlers;
+use super::event_types::{EventPayload, IndexedEvent, ProcessingStatus};
+use super::queue_depth::QueueDepth;
+use super::worker_pool;
+use crate::services::cache::Cache;
+use crate::services::event_bus::EventPublisher;
+use crate::services::rewards::RewardCalculationService;
+use crate::services::notifications::Notifier;
+use crate::services::webhooks::WebhookDispatcher;
 use anyhow::{Context, Result};
-use chrono::Utc;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
-use tracing::info;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
 use uuid::Uuid;
 
+/// Events the channel to the processor holds before enqueuing waits
+const CHANNEL_CAPACITY: usize = 100;
+
 /// Queue for blockchain events
 pub struct EventQueue {
     /// Database connection pool
@@ -18,271 +30,414 @@ pub struct EventQueue {
     sender: mpsc::Sender<IndexedEvent>,
     /// Channel receiver for event processing
     receiver: Arc<RwLock<Option<mpsc::Receiver<IndexedEvent>>>>,
-    /// Maximum number of processing attempts
-    max_attempts: u32,
-    /// Retry delay in seconds
-    retry_delay: u64,
+    /// Cache invalidated by event handlers
+    cache: Cache,
+    /// Rewards calculated when epochs close
+    rewards: RewardCalculationService,
+    /// Event bus every processed event is published on
+    events: EventPublisher,
+    /// Events enqueued but not yet written
+    pending: Mutex<PendingEvents>,
+    /// Events written to the event queue table in one statement
+    batch_size: usize,
+    /// How long an enqueued event may wait for its batch to fill
+    flush_interval: Duration,
+    /// Events enqueued and not yet handled
+    depth: QueueDepth,
+}
+
+/// Events waiting to be written, oldest first
+#[derive(Default)]
+struct PendingEvents {
+    events: Vec<IndexedEvent>,
+    /// When the oldest event was enqueued
+    since: Option<Instant>,
 }
 
 impl EventQueue {
     /// Creates a new event queue
-    pub fn new(db: PgPool, buffer_size: usize, max_attempts: u32, retry_delay: u64) -> Self {
-        let (sender, receiver) = mpsc::channel(buffer_size);
+    pub fn new(
+        db: PgPool,
+        cache: Cache,
+        rewards: RewardCalculationService,
+        events: EventPublisher,
+        batch_size: usize,
+        flush_interval: Duration,
+        depth: QueueDepth,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
         
         Self {
             db,
             sender,
             receiver: Arc::new(RwLock::new(Some(receiver))),
-            max_attempts,
-            retry_delay,
+            cache,
+            rewards,
+            events,
+            pending: Mutex::new(PendingEvents::default()),
+            batch_size: batch_size.max(1),
+            flush_interval,
+            depth,
         }
     }
     
+    /// Counter of the events enqueued and not yet handled
+    pub fn depth(&self) -> QueueDepth {
+        self.depth.clone()
+    }
+    
     /// Gets a clone of the sender
     pub fn get_sender(&self) -> mpsc::Sender<IndexedEvent> {
         self.sender.clone()
     }
     
     /// Enqueues an event for processing
+    ///
+    /// The event is held until `batch_size` events are waiting or the oldest has waited
+    /// `flush_interval`, then the batch is written and sent on. Events still held when the
+    /// queue is dropped are lost, so callers [`flush`](Self::flush) when they stop indexing.
     pub async fn enqueue(&self, event: IndexedEvent) -> Result<()> {
-        // For now, skip storing in the database to avoid errors
-        // In a production environment, this would store in the database
+        let mut pending = self.pending.lock().await;
+        pending.since.get_or_insert_with(Instant::now);
+        pending.events.push(event);
+        self.depth.enqueued();
         
-        // Then send it to the processing channel
-        self.sender.send(event).await
-            .context("Failed to enqueue event for processing")?;
+        let waited = pending.since.map_or(Duration::ZERO, |since| since.elapsed());
+        if pending.events.len() >= self.batch_size || waited >= self.flush_interval {
+            self.flush_pending(&mut pending).await?;
+        }
         
         Ok(())
     }
     
-    /// Stores an event in the database
-    async fn store_event(&self, _event: &IndexedEvent) -> Result<()> {
-        // For now, do nothing to avoid database errors
-        // In a production environment, this would store in the database
+    /// Number of events enqueued but not yet written
+    pub async fn pending(&self) -> usize {
+        self.pending.lock().await.events.len()
+    }
+    
+    /// Writes the events held for batching and sends them for processing. Returns the number
+    /// of events flushed.
+    pub async fn flush(&self) -> Result<usize> {
+        let mut pending = self.pending.lock().await;
+        self.flush_pending(&mut pending).await
+    }
+    
+    async fn flush_pending(&self, pending: &mut PendingEvents) -> Result<usize> {
+        // Store the raw events first; they are what the event archive is exported from. On
+        // failure they stay held, and are written with the next flush.
+        Self::store_batch_in(&self.db, &pending.events).await?;
+        
+        let count = pending.events.len();
+        pending.since = None;
+        for event in pending.events.drain(..) {
+            self.sender.send(event).await
+                .context("Failed to enqueue event for processing")?;
+        }
+        
+        Ok(count)
+    }
+    
+    /// Stores an event in the database, on the given executor
+    pub async fn store_in<'e>(executor: impl PgExecutor<'e>, event: &IndexedEvent) -> Result<()> {
+        Self::store_batch_in(executor, std::slice::from_ref(event)).await?;
         Ok(())
+    }
+    
+    /// Stores events in the database in one statement, on the given executor. Returns the
+    /// number of events stored.
+    pub async fn store_batch_in<'e>(executor: impl PgExecutor<'e>, events: &[IndexedEvent]) -> Result<u64> {
+        if events.is_empty() {
+            return Ok(0);
+        }
         
-        /*
-        // Convert request_type to string
-        let request_type_str = event.request_type.as_ref().map(|rt| rt.to_string());
+        let ids: Vec<&str> = events.iter().map(|e| e.id.as_str()).collect();
+        let event_types: Vec<i32> = events.iter().map(|e| e.event_type() as i32).collect();
+        let block_numbers: Vec<i64> = events.iter().map(|e| e.block_number as i64).collect();
+        let transaction_hashes: Vec<&str> = events.iter().map(|e| e.transaction_hash.as_str()).collect();
+        let request_ids: Vec<Option<i64>> = events.iter().map(|e| e.request_id.map(|id| id as i64)).collect();
+        let wallet_addresses: Vec<Option<&str>> = events.iter().map(|e| e.wallet_address.as_deref()).collect();
+        let amounts: Vec<Option<&str>> = events.iter().map(|e| e.amount.as_deref()).collect();
+        let request_types: Vec<Option<String>> = events
+            .iter()
+            .map(|e| e.request_type.as_ref().map(|request_type| request_type.to_string()))
+            .collect();
+        let timestamps: Vec<DateTime<Utc>> = events.iter().map(|e| e.timestamp).collect();
+        let raw_data = events
+            .iter()
+            .map(|e| e.payload.data())
+            .collect::<serde_json::Result<Vec<_>>>()
+            .context("Failed to serialize event payloads")?;
+        let statuses: Vec<i32> = events.iter().map(|e| e.status as i32).collect();
+        let attempts: Vec<i32> = events.iter().map(|e| e.attempts as i32).collect();
+        let last_attempts: Vec<Option<DateTime<Utc>>> = events.iter().map(|e| e.last_attempt).collect();
+        let error_messages: Vec<Option<&str>> = events.iter().map(|e| e.error_message.as_deref()).collect();
         
-        sqlx::query!(
+        let result = sqlx::query(
             r#"
             INSERT INTO lsrwa_express.event_queue (
-                id, event_type, block_number, transaction_hash, request_id, 
+                id, event_type, block_number, transaction_hash, request_id,
                 wallet_address, amount, request_type, timestamp, raw_data,
                 status, attempts, last_attempt, error_message
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            SELECT * FROM UNNEST(
+                $1::TEXT[], $2::INTEGER[], $3::BIGINT[], $4::TEXT[], $5::BIGINT[],
+                $6::TEXT[], $7::TEXT[], $8::TEXT[], $9::TIMESTAMPTZ[], $10::JSONB[],
+                $11::INTEGER[], $12::INTEGER[], $13::TIMESTAMPTZ[], $14::TEXT[]
+            )
             "#,
-            event.id,
-            event.event_type as i32,
-            event.block_number as i64,
-            event.transaction_hash,
-            event.request_id.map(|id| id as i64),
-            event.wallet_address,
-            event.amount,
-            request_type_str,
-            event.timestamp,
-            event.raw_data,
-            event.status as i32,
-            event.attempts as i32,
-            event.last_attempt,
-            event.error_message,
         )
-        .execute(&self.db)
+        .bind(&ids)
+        .bind(&event_types)
+        .bind(&block_numbers)
+        .bind(&transaction_hashes)
+        .bind(&request_ids)
+        .bind(&wallet_addresses)
+        .bind(&amounts)
+        .bind(&request_types)
+        .bind(&timestamps)
+        .bind(&raw_data)
+        .bind(&statuses)
+        .bind(&attempts)
+        .bind(&last_attempts)
+        .bind(&error_messages)
+        .execute(executor)
         .await
-        .context("Failed to store event in database")?;
+        .context("Failed to store events in database")?;
         
-        Ok(())
-        */
+        Ok(result.rows_affected())
     }
     
-    /// Updates an event's status in the database
-    pub async fn update_event_status(&self, _event_id: &str, _status: ProcessingStatus, _error: Option<String>) -> Result<()> {
-        // For now, do nothing to avoid database errors
-        // In a production environment, this would update the database
-        Ok(())
-        
-        /*
-        let now = Utc::now();
-        
-        sqlx::query!(
+    /// Records an event's processing status, on the given executor. Finishing an attempt, as
+    /// processed or failed, counts it.
+    pub async fn update_status_in<'e>(
+        executor: impl PgExecutor<'e>,
+        event_id: &str,
+        status: ProcessingStatus,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
             r#"
             UPDATE lsrwa_express.event_queue
-            SET status = $1, last_attempt = $2, error_message = $3, attempts = attempts + 1
-            WHERE id = $4
+            SET status = $2,
+                error_message = $3,
+                last_attempt = NOW(),
+                attempts = attempts + CASE WHEN $2 = $4 THEN 0 ELSE 1 END
+            WHERE id = $1
             "#,
-            status as i32,
-            now,
-            error,
-            event_id,
         )
-        .execute(&self.db)
+        .bind(event_id)
+        .bind(status as i32)
+        .bind(error)
+        .bind(ProcessingStatus::Processing as i32)
+        .execute(executor)
         .await
         .context("Failed to update event status in database")?;
         
         Ok(())
-        */
     }
     
-    /// Starts the event queue processor
-    pub async fn start_processing(&self) -> Result<()> {
-        let mut receiver = self.receiver.write().await.take()
+    /// Starts the event queue processor, handling events in `workers` lanes partitioned by
+    /// wallet; see [`worker_pool`]
+    ///
+    /// The processor runs until every sender is dropped, then handles what is left in the
+    /// channel before the returned task completes.
+    pub async fn start_processing(&self, workers: usize) -> Result<JoinHandle<()>> {
+        let receiver = self.receiver.write().await.take()
             .context("Event queue receiver already taken")?;
             
-        let _db = self.db.clone();
-        let _max_attempts = self.max_attempts;
-        let _retry_delay = self.retry_delay;
+        let events = self.events.clone();
+        let processor = Arc::new(EventSideEffects {
+            db: self.db.clone(),
+            handlers: EventHandlers::new(self.db.clone(), self.cache.clone(), self.rewards.clone(), events.clone()),
+            events,
+            webhooks: WebhookDispatcher::new(self.db.clone()),
+            notifier: Notifier::new(self.db.clone()),
+        });
+        let depth = self.depth.clone();
         
         // Spawn a task to process events
-        tokio::spawn(async move {
-            info!("Starting event queue processor");
+        let task = tokio::spawn(async move {
+            info!("Starting event queue processor with {} workers", workers);
             
-            while let Some(event) = receiver.recv().await {
-                // Process the event
-                info!("Processing event: {} (type: {:?})", event.id, event.event_type);
-                
-                // For now, skip database operations to avoid errors
-                // In a production environment, this would update the database
-                
-                /*
-                // Update the event status to Processing
-                let result = sqlx::query!(
-                    r#"
-                    UPDATE lsrwa_express.event_queue
-                    SET status = $1, last_attempt = $2
-                    WHERE id = $3
-                    "#,
-                    ProcessingStatus::Processing as i32,
-                    Utc::now(),
-                    event.id,
-                )
-                .execute(&db)
-                .await;
-                
-                if let Err(err) = result {
-                    error!("Failed to update event status: {}", err);
-                    continue;
+            worker_pool::run(receiver, workers, move |event| {
+                let (processor, depth) = (processor.clone(), depth.clone());
+                async move {
+                    processor.process(event).await;
+                    depth.handled();
                 }
-                */
-                
-                // TODO: Process the event based on its type
-                // This would call different handlers based on event.event_type
-                
-                // For now, just mark it as processed
-                // In a production environment, this would update the database
-                
-                /*
-                let result = sqlx::query!(
-                    r#"
-                    UPDATE lsrwa_express.event_queue
-                    SET status = $1, attempts = attempts + 1
-                    WHERE id = $3
-                    "#,
-                    ProcessingStatus::Processed as i32,
-                    event.id,
-                )
-                .execute(&db)
-                .await;
-                
-                if let Err(err) = result {
-                    error!("Failed to mark event as processed: {}", err);
-                }
-                */
-                
-                // Check for failed events that need to be retried
-                // For now, skip this to avoid database errors
-                // Self::retry_failed_events(&db, max_attempts, retry_delay).await;
-            }
+            })
+            .await;
             
             info!("Event queue processor stopped");
         });
         
-        Ok(())
-    }
-    
-    /// Retries failed events
-    #[allow(dead_code)]
-    async fn retry_failed_events(_db: &PgPool, _max_attempts: u32, _retry_delay: u64) {
-        // For now, do nothing to avoid database errors
-        // In a production environment, this would query and update the database
-        
-        /*
-        // Find failed events that are eligible for retry
-        let result = sqlx::query!(
-            r#"
-            SELECT id, event_type, block_number, transaction_hash, request_id, 
-                  wallet_address, amount, request_type, timestamp, raw_data,
-                  status, attempts, last_attempt, error_message
-            FROM lsrwa_express.event_queue
-            WHERE status = $1 AND attempts < $2 AND last_attempt < NOW() - INTERVAL '$3 seconds'
-            "#,
-            ProcessingStatus::Failed as i32,
-            max_attempts as i32,
-            retry_delay,
-        )
-        .fetch_all(db)
-        .await;
-        
-        match result {
-            Ok(rows) => {
-                if !rows.is_empty() {
-                    info!("Found {} failed events to retry", rows.len());
-                    
-                    for row in rows {
-                        // Update the event status to Pending
-                        let update_result = sqlx::query!(
-                            r#"
-                            UPDATE lsrwa_express.event_queue
-                            SET status = $1
-                            WHERE id = $2
-                            "#,
-                            ProcessingStatus::Pending as i32,
-                            row.id,
-                        )
-                        .execute(db)
-                        .await;
-                        
-                        if let Err(err) = update_result {
-                            error!("Failed to update event status for retry: {}", err);
-                        }
-                    }
-                }
-            },
-            Err(err) => {
-                error!("Failed to query failed events: {}", err);
-            }
-        }
-        */
+        Ok(task)
     }
     
-    /// Creates a new event
+    /// Creates a new event, with the request, wallet and amount taken from its payload
     pub fn create_event(
-        event_type: super::event_types::EventType,
+        payload: EventPayload,
         block_number: u64,
         transaction_hash: String,
-        request_id: Option<u128>,
-        wallet_address: Option<String>,
-        amount: Option<String>,
-        request_type: Option<RequestType>,
         timestamp: chrono::DateTime<Utc>,
-        raw_data: String,
     ) -> IndexedEvent {
         IndexedEvent {
             id: Uuid::new_v4().to_string(),
-            event_type,
             block_number,
             transaction_hash,
-            request_id,
-            wallet_address,
-            amount,
-            request_type,
+            request_id: payload.request_id(),
+            wallet_address: payload.wallet_address().map(str::to_string),
+            amount: payload.amount().map(str::to_string),
+            request_type: payload.request_type(),
             timestamp,
-            raw_data,
+            payload,
             status: ProcessingStatus::Pending,
             attempts: 0,
             last_attempt: None,
             error_message: None,
         }
     }
-} 
\ No newline at end of file
+}
+
+/// What processing an event does off-chain, for every worker of the queue
+struct EventSideEffects {
+    db: PgPool,
+    handlers: EventHandlers,
+    events: EventPublisher,
+    webhooks: WebhookDispatcher,
+    notifier: Notifier,
+}
+
+impl EventSideEffects {
+    async fn process(&self, event: IndexedEvent) {
+        // Process the event
+        info!("Processing event: {} (type: {:?})", event.id, event.event_type());
+        
+        self.record_status(&event, ProcessingStatus::Processing, None).await;
+        
+        // Apply the event's off-chain side effects
+        match self.handlers.handle(&event).await {
+            Ok(()) => self.record_status(&event, ProcessingStatus::Processed, None).await,
+            Err(err) => {
+                error!("Failed to handle event {}: {:#}", event.id, err);
+                self.record_status(&event, ProcessingStatus::Failed, Some(&format!("{:#}", err))).await;
+            },
+        }
+        
+        // Publish the event for downstream consumers
+        self.events.publish_indexed_event(&event).await;
+        
+        // Notify webhook subscribers about the processed event
+        if let Err(err) = self.webhooks.publish_indexed_event(&event).await {
+            error!("Failed to queue webhooks for event {}: {}", event.id, err);
+        }
+        
+        // Email the user the event concerns
+        if let Err(err) = self.notifier.notify_indexed_event(&event).await {
+            error!("Failed to queue notification for event {}: {}", event.id, err);
+        }
+    }
+    
+    /// Records how processing the event went; a failure to do so is logged, since the event's
+    /// side effects are applied either way
+    async fn record_status(&self, event: &IndexedEvent, status: ProcessingStatus, error: Option<&str>) {
+        if let Err(err) = EventQueue::update_status_in(&self.db, &event.id, status, error).await {
+            error!("Failed to mark event {} as {:?}: {:#}", event.id, status, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Environment;
+    use crate::db::{FeatureFlagRepository, SystemParameterRepository};
+    use crate::models::blockchain_request::RequestType;
+    use crate::test_support::EventBuilder;
+
+    fn queue(pool: &PgPool, batch_size: usize, flush_interval: Duration) -> EventQueue {
+        queue_with_depth(pool, batch_size, flush_interval, QueueDepth::new())
+    }
+
+    fn queue_with_depth(pool: &PgPool, batch_size: usize, flush_interval: Duration, depth: QueueDepth) -> EventQueue {
+        let events = EventPublisher::new(None, "test");
+        let ttl = Duration::from_secs(60);
+        let rewards = RewardCalculationService::new(
+            pool.clone(),
+            SystemParameterRepository::new(pool.clone(), ttl, Cache::disabled()),
+            FeatureFlagRepository::new(pool.clone(), Environment::Development, ttl, Cache::disabled()),
+            events.clone(),
+        );
+        EventQueue::new(pool.clone(), Cache::disabled(), rewards, events, batch_size, flush_interval, depth)
+    }
+
+    async fn stored(pool: &PgPool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM lsrwa_express.event_queue")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn deposit() -> IndexedEvent {
+        EventBuilder::request(RequestType::Deposit, 1).build()
+    }
+
+    #[sqlx::test]
+    async fn events_are_written_when_their_batch_fills(pool: PgPool) {
+        let queue = queue(&pool, 3, Duration::from_secs(3600));
+        let mut receiver = queue.receiver.write().await.take().unwrap();
+
+        queue.enqueue(deposit()).await.unwrap();
+        queue.enqueue(deposit()).await.unwrap();
+        assert_eq!(stored(&pool).await, 0);
+        assert_eq!(queue.pending().await, 2);
+        assert!(receiver.try_recv().is_err());
+
+        queue.enqueue(deposit()).await.unwrap();
+        assert_eq!(stored(&pool).await, 3);
+        assert_eq!(queue.pending().await, 0);
+        for _ in 0..3 {
+            receiver.try_recv().unwrap();
+        }
+
+        queue.enqueue(deposit()).await.unwrap();
+        assert_eq!(queue.flush().await.unwrap(), 1);
+        assert_eq!(stored(&pool).await, 4);
+        assert_eq!(queue.flush().await.unwrap(), 0);
+    }
+
+    #[sqlx::test]
+    async fn events_are_written_once_the_flush_interval_passes(pool: PgPool) {
+        let queue = queue(&pool, 100, Duration::ZERO);
+        let _receiver = queue.receiver.write().await.take().unwrap();
+
+        queue.enqueue(deposit()).await.unwrap();
+        assert_eq!(stored(&pool).await, 1);
+        assert_eq!(queue.pending().await, 0);
+    }
+
+    #[sqlx::test]
+    async fn the_depth_counts_events_until_they_are_handled(pool: PgPool) {
+        let depth = QueueDepth::new();
+        let queue = queue_with_depth(&pool, 100, Duration::from_secs(3600), depth.clone());
+        let task = queue.start_processing(2).await.unwrap();
+
+        queue.enqueue(deposit()).await.unwrap();
+        queue.enqueue(deposit()).await.unwrap();
+        assert_eq!(depth.get(), 2);
+
+        queue.flush().await.unwrap();
+        drop(queue);
+        task.await.unwrap();
+        assert_eq!(depth.get(), 0);
+        
+        let attempted: Vec<(i32, i32)> = sqlx::query_as("SELECT status, attempts FROM lsrwa_express.event_queue")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(attempted.len(), 2);
+        assert!(attempted.iter().all(|&(status, attempts)| {
+            status == ProcessingStatus::Processed as i32 && attempts == 1
+        }));
+    }
+}
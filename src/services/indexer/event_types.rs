@@ -1,8 +1,16 @@
 //! Event types for the indexer service
+//!
+//! Every [`EventType`] has a payload type holding the fields of the contract event it is
+//! indexed from, so handlers match on an [`EventPayload`] instead of reading JSON. Payloads
+//! serialize as the JSON the decoder produces, which `event_queue.raw_data` stores as `jsonb`.
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use tracing::warn;
 use crate::models::blockchain_request::RequestType;
+use crate::models::treasury::FeeType;
+use crate::models::wallet::WalletAddress;
 
 /// Status of event processing
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -40,6 +48,248 @@ pub enum EventType {
     EpochClosing,
     /// Validation failure event
     ValidationFailure,
+    /// Protocol fee collection event
+    FeeCollection,
+}
+
+impl EventType {
+    /// Every event type, in the order of the codes stored in `event_queue`
+    pub const ALL: [EventType; 10] = [
+        EventType::DepositRequest,
+        EventType::WithdrawalRequest,
+        EventType::BorrowRequest,
+        EventType::RequestExecution,
+        EventType::BatchProcessing,
+        EventType::UserRegistration,
+        EventType::EpochCreation,
+        EventType::EpochClosing,
+        EventType::ValidationFailure,
+        EventType::FeeCollection,
+    ];
+
+    /// Event type of a code stored in `event_queue`
+    pub fn from_code(code: i32) -> Option<EventType> {
+        usize::try_from(code).ok().and_then(|index| Self::ALL.get(index).copied())
+    }
+
+    /// Stable snake-case name used outside the service
+    pub fn name(self) -> &'static str {
+        match self {
+            EventType::DepositRequest => "deposit_request",
+            EventType::WithdrawalRequest => "withdrawal_request",
+            EventType::BorrowRequest => "borrow_request",
+            EventType::RequestExecution => "request_execution",
+            EventType::BatchProcessing => "batch_processing",
+            EventType::UserRegistration => "user_registration",
+            EventType::EpochCreation => "epoch_creation",
+            EventType::EpochClosing => "epoch_closing",
+            EventType::ValidationFailure => "validation_failure",
+            EventType::FeeCollection => "fee_collection",
+        }
+    }
+}
+
+/// A deposit, withdrawal or execution of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestEvent {
+    #[serde(with = "u128_string")]
+    pub request_id: u128,
+    pub wallet_address: String,
+    /// Amount in tokens
+    pub amount: String,
+}
+
+/// A borrow request with the collateral pledged for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BorrowRequested {
+    #[serde(flatten)]
+    pub request: RequestEvent,
+    /// Collateral in tokens
+    pub collateral: String,
+}
+
+/// Requests of one type processed in a batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchProcessed {
+    pub request_type: RequestType,
+    pub processed_count: u32,
+    pub failed_count: u32,
+}
+
+/// A wallet registered with the contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserRegistered {
+    pub wallet_address: String,
+}
+
+/// An epoch that started. The contract emits no such event; an epoch starts when the previous
+/// one closes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochCreated {
+    pub epoch_id: u32,
+    /// Start in milliseconds since the Unix epoch
+    pub start_timestamp: u64,
+}
+
+/// An epoch that closed, with what it processed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochClosed {
+    pub epoch_id: u32,
+    /// Start in milliseconds since the Unix epoch
+    pub start_timestamp: u64,
+    /// End in milliseconds since the Unix epoch
+    pub end_timestamp: u64,
+    pub processed_deposit_count: u32,
+    pub processed_withdrawal_count: u32,
+    pub processed_borrow_count: u32,
+}
+
+/// A protocol fee paid to the treasury
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeCollected {
+    #[serde(with = "u128_string")]
+    pub request_id: u128,
+    pub wallet_address: String,
+    pub fee_type: FeeType,
+    /// Fee in tokens
+    pub amount: String,
+}
+
+/// Fields of an indexed event, one variant per [`EventType`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event_type", content = "raw_data")]
+pub enum EventPayload {
+    DepositRequest(RequestEvent),
+    WithdrawalRequest(RequestEvent),
+    BorrowRequest(BorrowRequested),
+    RequestExecution(RequestEvent),
+    BatchProcessing(BatchProcessed),
+    UserRegistration(UserRegistered),
+    EpochCreation(EpochCreated),
+    EpochClosing(EpochClosed),
+    /// Events the indexer has no payload type for, kept as received
+    ValidationFailure(Value),
+    FeeCollection(FeeCollected),
+}
+
+impl EventPayload {
+    /// Payload of a contract event, by the event's name
+    ///
+    /// Events the indexer doesn't model, or whose fields don't match their payload type, are
+    /// kept as received in a [`ValidationFailure`](EventPayload::ValidationFailure).
+    pub fn from_contract_event(name: &str, data: Value) -> EventPayload {
+        let payload = match name {
+            "DepositRequested" => serde_json::from_value(data.clone()).map(EventPayload::DepositRequest),
+            "WithdrawalRequested" => serde_json::from_value(data.clone()).map(EventPayload::WithdrawalRequest),
+            "BorrowRequested" => serde_json::from_value(data.clone()).map(EventPayload::BorrowRequest),
+            "RequestExecuted" | "WithdrawalExecuted" => {
+                serde_json::from_value(data.clone()).map(EventPayload::RequestExecution)
+            },
+            "BatchProcessed" => serde_json::from_value(data.clone()).map(EventPayload::BatchProcessing),
+            "UserRegistered" => serde_json::from_value(data.clone()).map(EventPayload::UserRegistration),
+            "EpochClosed" => serde_json::from_value(data.clone()).map(EventPayload::EpochClosing),
+            "FeeCollected" => serde_json::from_value(data.clone()).map(EventPayload::FeeCollection),
+            _ => return EventPayload::ValidationFailure(data),
+        };
+
+        payload.unwrap_or_else(|err| {
+            warn!("{} event doesn't match its payload type: {}", name, err);
+            EventPayload::ValidationFailure(data)
+        })
+    }
+
+    /// Type of the event the payload belongs to
+    pub fn event_type(&self) -> EventType {
+        match self {
+            EventPayload::DepositRequest(_) => EventType::DepositRequest,
+            EventPayload::WithdrawalRequest(_) => EventType::WithdrawalRequest,
+            EventPayload::BorrowRequest(_) => EventType::BorrowRequest,
+            EventPayload::RequestExecution(_) => EventType::RequestExecution,
+            EventPayload::BatchProcessing(_) => EventType::BatchProcessing,
+            EventPayload::UserRegistration(_) => EventType::UserRegistration,
+            EventPayload::EpochCreation(_) => EventType::EpochCreation,
+            EventPayload::EpochClosing(_) => EventType::EpochClosing,
+            EventPayload::ValidationFailure(_) => EventType::ValidationFailure,
+            EventPayload::FeeCollection(_) => EventType::FeeCollection,
+        }
+    }
+
+    /// Request the event concerns
+    pub fn request_id(&self) -> Option<u128> {
+        match self {
+            EventPayload::DepositRequest(request)
+            | EventPayload::WithdrawalRequest(request)
+            | EventPayload::RequestExecution(request) => Some(request.request_id),
+            EventPayload::BorrowRequest(borrow) => Some(borrow.request.request_id),
+            EventPayload::FeeCollection(fee) => Some(fee.request_id),
+            _ => None,
+        }
+    }
+
+    /// Wallet the event concerns
+    pub fn wallet_address(&self) -> Option<&str> {
+        match self {
+            EventPayload::DepositRequest(request)
+            | EventPayload::WithdrawalRequest(request)
+            | EventPayload::RequestExecution(request) => Some(&request.wallet_address),
+            EventPayload::BorrowRequest(borrow) => Some(&borrow.request.wallet_address),
+            EventPayload::UserRegistration(registered) => Some(&registered.wallet_address),
+            EventPayload::FeeCollection(fee) => Some(&fee.wallet_address),
+            _ => None,
+        }
+    }
+
+    /// Amount the event moves, in tokens
+    pub fn amount(&self) -> Option<&str> {
+        match self {
+            EventPayload::DepositRequest(request)
+            | EventPayload::WithdrawalRequest(request)
+            | EventPayload::RequestExecution(request) => Some(&request.amount),
+            EventPayload::BorrowRequest(borrow) => Some(&borrow.request.amount),
+            EventPayload::FeeCollection(fee) => Some(&fee.amount),
+            _ => None,
+        }
+    }
+
+    /// Type of the requests the event concerns, when the payload says
+    pub fn request_type(&self) -> Option<RequestType> {
+        match self {
+            EventPayload::DepositRequest(_) => Some(RequestType::Deposit),
+            EventPayload::WithdrawalRequest(_) => Some(RequestType::Withdrawal),
+            EventPayload::BorrowRequest(_) => Some(RequestType::Borrow),
+            EventPayload::BatchProcessing(batch) => Some(batch.request_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// The payload's fields, as `event_queue.raw_data` stores them
+    pub fn data(&self) -> serde_json::Result<Value> {
+        match self {
+            EventPayload::DepositRequest(request)
+            | EventPayload::WithdrawalRequest(request)
+            | EventPayload::RequestExecution(request) => serde_json::to_value(request),
+            EventPayload::BorrowRequest(borrow) => serde_json::to_value(borrow),
+            EventPayload::BatchProcessing(batch) => serde_json::to_value(batch),
+            EventPayload::UserRegistration(registered) => serde_json::to_value(registered),
+            EventPayload::EpochCreation(created) => serde_json::to_value(created),
+            EventPayload::EpochClosing(closed) => serde_json::to_value(closed),
+            EventPayload::ValidationFailure(data) => Ok(data.clone()),
+            EventPayload::FeeCollection(fee) => serde_json::to_value(fee),
+        }
+    }
+}
+
+/// Request IDs as decimal strings, since JSON numbers can't hold every `u128`
+mod u128_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
 }
 
 /// Indexed blockchain event
@@ -47,8 +297,9 @@ pub enum EventType {
 pub struct IndexedEvent {
     /// Unique identifier for the event
     pub id: String,
-    /// Type of event
-    pub event_type: EventType,
+    /// Type of event, with the fields of the contract event
+    #[serde(flatten)]
+    pub payload: EventPayload,
     /// Block number where the event was emitted
     pub block_number: u64,
     /// Transaction hash of the event
@@ -63,8 +314,6 @@ pub struct IndexedEvent {
     pub request_type: Option<RequestType>,
     /// Timestamp of the event
     pub timestamp: DateTime<Utc>,
-    /// Raw event data
-    pub raw_data: String,
     /// Processing status
     pub status: ProcessingStatus,
     /// Number of processing attempts
@@ -73,4 +322,78 @@ pub struct IndexedEvent {
     pub last_attempt: Option<DateTime<Utc>>,
     /// Error message from last processing attempt
     pub error_message: Option<String>,
-} 
\ No newline at end of file
+}
+
+impl IndexedEvent {
+    /// Type of event
+    pub fn event_type(&self) -> EventType {
+        self.payload.event_type()
+    }
+
+    /// Related wallet, unless the event has none or it isn't a valid address
+    pub fn wallet(&self) -> Option<WalletAddress> {
+        self.wallet_address.as_deref().and_then(|address| address.parse().ok())
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn borrow(request_id: u128) -> Value {
+        json!({ "request_id": request_id.to_string(), "wallet_address": "alice", "amount": "10", "collateral": "15" })
+    }
+
+    #[test]
+    fn contract_events_become_the_payload_of_their_event_type() {
+        let payload = EventPayload::from_contract_event("BorrowRequested", borrow(u128::MAX));
+        assert_eq!(payload.event_type(), EventType::BorrowRequest);
+        assert_eq!(payload.request_id(), Some(u128::MAX));
+        assert_eq!((payload.wallet_address(), payload.amount()), (Some("alice"), Some("10")));
+        assert_eq!(payload.data().unwrap(), borrow(u128::MAX));
+
+        let fee = json!({ "request_id": "2", "wallet_address": "alice", "fee_type": "Liquidation", "amount": "1" });
+        let EventPayload::FeeCollection(fee) = EventPayload::from_contract_event("FeeCollected", fee) else {
+            panic!("Fee event isn't a fee collection");
+        };
+        assert_eq!(fee.fee_type, FeeType::Liquidation);
+
+        // Events without a payload type, or with fields that don't match theirs, are kept as received
+        let liquidated = json!({ "request_id": "3" });
+        assert_eq!(
+            EventPayload::from_contract_event("BorrowLiquidated", liquidated.clone()),
+            EventPayload::ValidationFailure(liquidated.clone())
+        );
+        assert_eq!(
+            EventPayload::from_contract_event("DepositRequested", liquidated.clone()),
+            EventPayload::ValidationFailure(liquidated)
+        );
+    }
+
+    #[test]
+    fn indexed_events_serialize_their_payload_as_type_and_raw_data() {
+        let payload = EventPayload::from_contract_event("BorrowRequested", borrow(3));
+        let event = IndexedEvent {
+            id: "1".to_string(),
+            block_number: 7,
+            transaction_hash: "0x01".to_string(),
+            request_id: payload.request_id(),
+            wallet_address: None,
+            amount: None,
+            request_type: payload.request_type(),
+            timestamp: Utc::now(),
+            payload,
+            status: ProcessingStatus::Pending,
+            attempts: 0,
+            last_attempt: None,
+            error_message: None,
+        };
+
+        let serialized = serde_json::to_value(&event).unwrap();
+        assert_eq!(serialized["event_type"], "BorrowRequest");
+        assert_eq!(serialized["raw_data"], borrow(3));
+
+        let read: IndexedEvent = serde_json::from_value(serialized).unwrap();
+        assert_eq!(read.payload, event.payload);
+    }
+}
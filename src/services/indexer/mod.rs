@@ -2,10 +2,22 @@
 //! 
 //! This module provides functionality to index and queue on-chain events from the LSRWA Express contract.
 
+mod event_decoder;
+mod event_handlers;
 mod event_processor;
 mod event_queue;
 mod event_types;
+mod owner_backfill;
+mod queue_depth;
+mod worker_pool;
 
+pub use event_decoder::{decode_contract_event, event_recording, ContractEmitted};
+pub use event_handlers::EventHandlers;
 pub use event_processor::EventProcessor;
 pub use event_queue::EventQueue;
-pub use event_types::{EventType, IndexedEvent, ProcessingStatus};
+pub use event_types::{
+    BatchProcessed, BorrowRequested, EpochClosed, EpochCreated, EventPayload, EventType, FeeCollected, IndexedEvent,
+    ProcessingStatus, RequestEvent, UserRegistered,
+};
+pub use owner_backfill::RequestOwnerBackfillJob;
+pub use queue_depth::QueueDepth;
@@ -0,0 +1,42 @@
+//! Scheduled linking of requests to the users of their wallets
+
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::db::{BlockchainRequestRepository, UnitOfWork};
+use crate::services::scheduler::ScheduledJob;
+
+/// Links requests recorded without a user, e.g. before linking on insert existed or when a
+/// concurrent insert created the wallet's user, creating users for wallets that have none
+pub struct RequestOwnerBackfillJob {
+    db: PgPool,
+}
+
+impl RequestOwnerBackfillJob {
+    /// Creates the backfill job
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for RequestOwnerBackfillJob {
+    fn name(&self) -> &'static str {
+        "request_owner_backfill"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        let created = BlockchainRequestRepository::create_missing_owners_in(uow.conn()).await?;
+        let linked = BlockchainRequestRepository::link_unowned_in(uow.conn()).await?;
+        uow.commit().await?;
+
+        if linked > 0 {
+            info!("Linked {} requests to their users, creating {} users", linked, created);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+//! Depth of the event queue
+//!
+//! Counts the events enqueued and not yet handled, whether held for batching, waiting in the
+//! channel or in a worker's lane. It's shared with the API, which stops admitting submissions
+//! while the queue is too deep, and reported as the `event_queue_depth` gauge.
+
+use metrics::gauge;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Shared count of the events enqueued and not yet handled
+#[derive(Clone, Default)]
+pub struct QueueDepth(Arc<AtomicUsize>);
+
+impl QueueDepth {
+    /// Creates an empty count
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events enqueued and not yet handled
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Counts an enqueued event
+    pub fn enqueued(&self) {
+        let depth = self.0.fetch_add(1, Ordering::Relaxed) + 1;
+        gauge!("event_queue_depth", depth as f64);
+    }
+
+    /// Counts a handled event off
+    pub fn handled(&self) {
+        let depth = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |depth| Some(depth.saturating_sub(1)))
+            .unwrap_or_default()
+            .saturating_sub(1);
+        gauge!("event_queue_depth", depth as f64);
+    }
+}
@@ -0,0 +1,175 @@
+//! Pool of tasks handling queued events
+//!
+//! Events are split into lanes by wallet, each lane handled by a task of its own, so a slow
+//! handler only holds up the events of wallets in its lane. A wallet's events always land in
+//! the same lane and are handled in the order they were queued. Events that concern no wallet,
+//! such as an epoch closing, wait for every event queued before them, and are handled before any
+//! queued after them.
+
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use tokio::sync::{mpsc, oneshot};
+use tracing::error;
+
+use super::event_types::IndexedEvent;
+
+/// Events waiting in each lane before the queue holds back
+const LANE_BUFFER: usize = 64;
+
+enum Work {
+    Event(Box<IndexedEvent>),
+    /// Sent on once the lane has handled everything before it
+    Drained(oneshot::Sender<()>),
+}
+
+/// Lane of the wallet's events, out of `lanes`
+fn lane(wallet_address: &str, lanes: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    wallet_address.hash(&mut hasher);
+    (hasher.finish() % lanes as u64) as usize
+}
+
+/// Handles the events from `receiver` with `handle` in `workers` lanes, until every sender is
+/// dropped and the events left are handled
+pub async fn run<H, Fut>(mut receiver: mpsc::Receiver<IndexedEvent>, workers: usize, handle: H)
+where
+    H: Fn(IndexedEvent) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (lanes, tasks): (Vec<_>, Vec<_>) = (0..workers.max(1))
+        .map(|_| {
+            let (sender, mut work) = mpsc::channel::<Work>(LANE_BUFFER);
+            let handle = handle.clone();
+            let task = tokio::spawn(async move {
+                while let Some(next) = work.recv().await {
+                    match next {
+                        Work::Event(event) => handle(*event).await,
+                        Work::Drained(done) => {
+                            let _ = done.send(());
+                        },
+                    }
+                }
+            });
+            (sender, task)
+        })
+        .unzip();
+
+    while let Some(event) = receiver.recv().await {
+        let Some(index) = event.wallet_address.as_deref().map(|wallet_address| lane(wallet_address, lanes.len())) else {
+            let mut drained = Vec::with_capacity(lanes.len());
+            for sender in &lanes {
+                let (done, wait) = oneshot::channel();
+                if sender.send(Work::Drained(done)).await.is_ok() {
+                    drained.push(wait);
+                }
+            }
+            for wait in drained {
+                let _ = wait.await;
+            }
+
+            handle(event).await;
+            continue;
+        };
+
+        if lanes[index].send(Work::Event(Box::new(event))).await.is_err() {
+            // The lane's task panicked; its events can't be handled in order any more
+            error!("Event lane {} stopped; dropping its event", index);
+        }
+    }
+
+    drop(lanes);
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::blockchain_request::RequestType;
+    use crate::test_support::EventBuilder;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn event(wallet_address: Option<&str>, sequence: u64) -> IndexedEvent {
+        let mut event = EventBuilder::request(RequestType::Deposit, sequence as u128).build();
+        event.wallet_address = wallet_address.map(str::to_string);
+        event.block_number = sequence;
+        event
+    }
+
+    /// Two wallets in different lanes of `lanes`
+    fn wallets_in_different_lanes(lanes: usize) -> (String, String) {
+        let first = "wallet-0".to_string();
+        let second = (1..)
+            .map(|n| format!("wallet-{}", n))
+            .find(|wallet| lane(wallet, lanes) != lane(&first, lanes))
+            .unwrap();
+        (first, second)
+    }
+
+    /// Runs the pool over `events`, recording the wallet and sequence of each event handled
+    async fn handled(workers: usize, events: Vec<IndexedEvent>, slow: Option<String>) -> Vec<(Option<String>, u64)> {
+        let (sender, receiver) = mpsc::channel(events.len().max(1));
+        for event in events {
+            sender.send(event).await.unwrap();
+        }
+        drop(sender);
+
+        let record = Arc::new(Mutex::new(Vec::new()));
+        let recorded = record.clone();
+        run(receiver, workers, move |event: IndexedEvent| {
+            let (recorded, slow) = (recorded.clone(), slow.clone());
+            async move {
+                if event.wallet_address.is_some() && event.wallet_address == slow {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                recorded.lock().unwrap().push((event.wallet_address, event.block_number));
+            }
+        })
+        .await;
+
+        Arc::try_unwrap(record).unwrap().into_inner().unwrap()
+    }
+
+    fn sequences(handled: &[(Option<String>, u64)], wallet_address: Option<&str>) -> Vec<u64> {
+        handled
+            .iter()
+            .filter(|(wallet, _)| wallet.as_deref() == wallet_address)
+            .map(|(_, sequence)| *sequence)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn a_wallets_events_are_handled_in_order() {
+        let (alice, bob) = wallets_in_different_lanes(4);
+        let events = (0..20).map(|n| event(Some(if n % 3 == 0 { &alice } else { &bob }), n)).collect();
+
+        let handled = handled(4, events, Some(alice.clone())).await;
+
+        assert_eq!(handled.len(), 20);
+        assert_eq!(sequences(&handled, Some(&alice)), [0, 3, 6, 9, 12, 15, 18]);
+        assert_eq!(sequences(&handled, Some(&bob)), (0..20).filter(|n| n % 3 != 0).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn a_slow_wallet_doesnt_hold_up_other_lanes() {
+        let (slow, fast) = wallets_in_different_lanes(2);
+        let events = vec![event(Some(&slow), 0), event(Some(&fast), 1)];
+
+        let handled = handled(2, events, Some(slow.clone())).await;
+
+        assert_eq!(handled, [(Some(fast), 1), (Some(slow), 0)]);
+    }
+
+    #[tokio::test]
+    async fn events_without_a_wallet_wait_for_earlier_events() {
+        let (slow, fast) = wallets_in_different_lanes(2);
+        let events = vec![event(Some(&slow), 0), event(None, 1), event(Some(&fast), 2)];
+
+        let handled = handled(2, events, Some(slow.clone())).await;
+
+        assert_eq!(handled, [(Some(slow), 0), (None, 1), (Some(fast), 2)]);
+    }
+}
@@ -0,0 +1,52 @@
+//! Per-epoch interest accrual on borrow positions
+
+use anyhow::Result;
+use metrics::counter;
+use sqlx::PgPool;
+use tracing::info;
+
+use super::error::InterestError;
+use crate::db::{EpochRepository, InterestRepository, SystemParameterRepository, UnitOfWork};
+use crate::models::interest::InterestAccrual;
+
+/// Accrues borrower interest over closed epochs
+#[derive(Clone)]
+pub struct InterestAccrualService {
+    db: PgPool,
+    parameters: SystemParameterRepository,
+}
+
+impl InterestAccrualService {
+    /// Creates an interest accrual service
+    pub fn new(db: PgPool, parameters: SystemParameterRepository) -> Self {
+        Self { db, parameters }
+    }
+
+    /// Accrues interest on every borrow that was open during a closed epoch. Positions that
+    /// already accrued for the epoch are left alone, so closing an epoch can safely be retried.
+    pub async fn accrue_epoch(&self, epoch_id: i32) -> Result<Vec<InterestAccrual>> {
+        let rate_bps = self.parameters.borrow_interest_rate_bps().await?;
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        let epoch = EpochRepository::lock_in(uow.conn(), epoch_id)
+            .await?
+            .ok_or(InterestError::EpochNotFound(epoch_id))?;
+        let epoch_end = epoch.end_timestamp.ok_or(InterestError::EpochNotClosed(epoch_id))?;
+
+        let accruals =
+            InterestRepository::accrue_epoch_in(uow.conn(), epoch_id, epoch.start_timestamp, epoch_end, rate_bps).await?;
+
+        uow.commit().await?;
+
+        info!("Accrued interest on {} borrows for epoch {} at {} bps", accruals.len(), epoch_id, rate_bps);
+        counter!("borrow_interest_accruals_total", accruals.len() as u64);
+
+        Ok(accruals)
+    }
+
+    /// Interest accrued by a wallet's borrows, most recent first
+    pub async fn list(&self, wallet_address: &str) -> Result<Vec<InterestAccrual>> {
+        InterestRepository::new(self.db.clone()).list_accruals(wallet_address).await
+    }
+}
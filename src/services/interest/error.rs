@@ -0,0 +1,13 @@
+//! Errors returned by interest accrual
+
+use thiserror::Error;
+
+/// Why an epoch's interest can't be accrued
+#[derive(Error, Debug)]
+pub enum InterestError {
+    #[error("Epoch {0} not found")]
+    EpochNotFound(i32),
+
+    #[error("Epoch {0} has not closed yet")]
+    EpochNotClosed(i32),
+}
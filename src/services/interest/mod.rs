@@ -0,0 +1,14 @@
+//! Borrow interest and debt statements
+//!
+//! When an epoch closes, [`InterestAccrualService`] accrues simple interest at the
+//! `borrow_interest_rate_bps` system parameter on every open borrow, one row per position and
+//! epoch. [`DebtStatementService`] rolls those rows up into monthly statements per borrower,
+//! generated by the `debt_statements` scheduled job and downloadable as JSON or CSV.
+
+mod accrual;
+mod error;
+mod statements;
+
+pub use accrual::InterestAccrualService;
+pub use error::InterestError;
+pub use statements::{render_csv, DebtStatementJob, DebtStatementService};
@@ -0,0 +1,123 @@
+//! Monthly debt statements
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate, Utc};
+use sqlx::PgPool;
+use std::fmt::Write;
+use tracing::info;
+
+use crate::db::InterestRepository;
+use crate::models::interest::DebtStatement;
+use crate::services::scheduler::ScheduledJob;
+
+/// Generates and serves borrowers' monthly debt statements
+#[derive(Clone)]
+pub struct DebtStatementService {
+    db: PgPool,
+}
+
+impl DebtStatementService {
+    /// Creates a debt statement service
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Generates the statements of the month containing `month`, replacing earlier ones.
+    /// Returns the number of statements written.
+    pub async fn generate(&self, month: NaiveDate) -> Result<u64> {
+        let (period_start, period_end) = month_bounds(month)?;
+
+        let written = InterestRepository::generate_statements_in(&self.db, period_start, period_end).await?;
+        info!("Generated {} debt statements for {}", written, period_start.format("%Y-%m"));
+
+        Ok(written)
+    }
+
+    /// Gets a wallet's statement for the month containing `month`
+    pub async fn get(&self, wallet_address: &str, month: NaiveDate) -> Result<Option<DebtStatement>> {
+        let (period_start, _) = month_bounds(month)?;
+        InterestRepository::new(self.db.clone()).get_statement(wallet_address, period_start).await
+    }
+
+    /// A wallet's statements, most recent first
+    pub async fn list(&self, wallet_address: &str) -> Result<Vec<DebtStatement>> {
+        InterestRepository::new(self.db.clone()).list_statements(wallet_address).await
+    }
+}
+
+/// First day of the month containing `date`, and of the month after it
+fn month_bounds(date: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+    let start = date.with_day(1).ok_or_else(|| anyhow!("Invalid statement month {}", date))?;
+    let end = if start.month() == 12 {
+        NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+    };
+
+    Ok((start, end.ok_or_else(|| anyhow!("Invalid statement month {}", date))?))
+}
+
+/// Renders a statement as CSV: one row per position followed by a totals row
+pub fn render_csv(statement: &DebtStatement) -> String {
+    let mut csv = String::from(
+        "request_id,borrowed_at,status,principal,opening_debt,new_borrow,interest_accrued,liquidated_debt,closing_debt\n",
+    );
+
+    for position in statement.positions.iter() {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            position.request_id,
+            position.borrowed_at.to_rfc3339(),
+            position.status,
+            position.principal,
+            position.opening_debt,
+            position.new_borrow,
+            position.interest_accrued,
+            position.liquidated_debt,
+            position.closing_debt,
+        );
+    }
+
+    let _ = writeln!(
+        csv,
+        "total,,,,{},{},{},{},{}",
+        statement.opening_debt,
+        statement.new_borrows,
+        statement.interest_accrued,
+        statement.liquidated_debt,
+        statement.closing_debt,
+    );
+
+    csv
+}
+
+/// Generates the statements of the last full month. Runs are idempotent, so the job can run
+/// more often than monthly and catch up after downtime.
+pub struct DebtStatementJob {
+    statements: DebtStatementService,
+}
+
+impl DebtStatementJob {
+    /// Creates the statement job
+    pub fn new(statements: DebtStatementService) -> Self {
+        Self { statements }
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for DebtStatementJob {
+    fn name(&self) -> &'static str {
+        "debt_statements"
+    }
+
+    async fn run(&self) -> Result<()> {
+        let this_month = Utc::now().date_naive().with_day(1).ok_or_else(|| anyhow!("Invalid current date"))?;
+        let last_month = this_month.pred_opt().ok_or_else(|| anyhow!("Invalid current date"))?;
+
+        self.statements.generate(last_month).await?;
+
+        Ok(())
+    }
+}
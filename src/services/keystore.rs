@@ -0,0 +1,231 @@
+//! Operator keys held in the secrets backend
+//!
+//! Each signing role keeps its active secret under its setting name, e.g.
+//! `CONTRACT_OWNER_SEED_PHRASE`. A new key is staged under `<name>_NEXT` first, so its address
+//! can be funded and checked before rotation makes it active; the key it replaces is kept
+//! under `<name>_PREVIOUS`.
+//!
+//! Secrets are seed phrases or secret URIs such as `//Alice`, or the `0x`-prefixed 64-byte
+//! sr25519 secret key of a key imported from a polkadot.js JSON export.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use crypto_secretbox::aead::{Aead, KeyInit};
+use crypto_secretbox::XSalsa20Poly1305;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use subxt::ext::sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
+use subxt::ext::sp_core::{sr25519, Pair as PairTrait};
+
+use crate::config::ChainNetwork;
+
+/// Signing role whose key the keystore manages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRole {
+    /// Contract owner, signing admin-only calls, deployments and upgrades
+    Owner,
+    /// Service wallet, signing deposits and withdrawals
+    Wallet,
+    /// Receipt signer, signing receipts of executed requests; it never sends a transaction
+    Receipt,
+}
+
+impl KeyRole {
+    /// Setting holding the role's active key
+    pub fn secret_name(self) -> &'static str {
+        match self {
+            KeyRole::Owner => "CONTRACT_OWNER_SEED_PHRASE",
+            KeyRole::Wallet => "WALLET_SEED_PHRASE",
+            KeyRole::Receipt => "RECEIPT_SIGNING_SEED_PHRASE",
+        }
+    }
+
+    /// Whether the role's key signs transactions, and so needs funds to pay their fees
+    pub fn signs_transactions(self) -> bool {
+        !matches!(self, KeyRole::Receipt)
+    }
+
+    /// Setting holding the key staged to replace the active one
+    pub fn next_secret_name(self) -> String {
+        format!("{}_NEXT", self.secret_name())
+    }
+
+    /// Setting holding the key the last rotation replaced
+    pub fn previous_secret_name(self) -> String {
+        format!("{}_PREVIOUS", self.secret_name())
+    }
+}
+
+impl FromStr for KeyRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "owner" => Ok(KeyRole::Owner),
+            "wallet" => Ok(KeyRole::Wallet),
+            "receipt" => Ok(KeyRole::Receipt),
+            other => Err(anyhow!("Unknown key role '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for KeyRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyRole::Owner => write!(f, "owner"),
+            KeyRole::Wallet => write!(f, "wallet"),
+            KeyRole::Receipt => write!(f, "receipt"),
+        }
+    }
+}
+
+/// Length of an sr25519 secret key: the scalar followed by the nonce
+const SECRET_KEY_LENGTH: usize = 64;
+
+/// The key pair of a stored secret
+pub fn keypair(secret: &SecretString) -> Result<sr25519::Pair> {
+    let secret = secret.expose_secret().trim();
+
+    if let Some(hex_key) = secret.strip_prefix("0x").filter(|key| key.len() == SECRET_KEY_LENGTH * 2) {
+        let bytes = hex::decode(hex_key).map_err(|_| anyhow!("Secret key is not valid hex"))?;
+        return secret_key_pair(&bytes);
+    }
+
+    sr25519::Pair::from_string(secret, None).map_err(|_| anyhow!("Not a valid seed phrase or secret key"))
+}
+
+/// A new key, returned with the seed phrase it's stored as
+pub fn generate() -> (sr25519::Pair, SecretString) {
+    let (pair, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+    (pair, SecretString::new(phrase))
+}
+
+/// SS58 address of a key on a network
+pub fn address(pair: &sr25519::Pair, network: ChainNetwork) -> String {
+    pair.public().to_ss58check_with_version(Ss58AddressFormat::custom(network.ss58_prefix()))
+}
+
+/// Key exported from polkadot.js, in the v3 encrypted JSON format
+#[derive(Deserialize)]
+struct KeyFile {
+    encoded: String,
+    encoding: KeyFileEncoding,
+}
+
+#[derive(Deserialize)]
+struct KeyFileEncoding {
+    content: Vec<String>,
+    #[serde(rename = "type")]
+    kind: Vec<String>,
+    version: String,
+}
+
+/// Header of the PKCS#8 document polkadot.js wraps secret keys in
+const PKCS8_HEADER: [u8; 16] = [48, 83, 2, 1, 1, 48, 5, 6, 3, 43, 101, 112, 4, 34, 4, 32];
+
+/// Scrypt parameters and salt prefixed to a v3 export: salt, then N, p and r as little-endian u32s
+const SCRYPT_PARAMS_LENGTH: usize = 32 + 3 * 4;
+
+/// Nonce prefixed to the sealed PKCS#8 document
+const NONCE_LENGTH: usize = 24;
+
+/// Decrypts a polkadot.js JSON export of an sr25519 key, returning the key and its secret in
+/// the form it's stored as
+pub fn import_json(json: &str, password: &SecretString) -> Result<(sr25519::Pair, SecretString)> {
+    let file: KeyFile = serde_json::from_str(json).context("Not a polkadot.js key export")?;
+
+    if file.encoding.version != "3" {
+        bail!("Unsupported key export version {}; export it again from polkadot.js", file.encoding.version);
+    }
+    if !file.encoding.content.iter().any(|content| content == "sr25519") {
+        bail!("Only sr25519 keys can be imported, not {}", file.encoding.content.join("/"));
+    }
+    if !file.encoding.kind.iter().any(|kind| kind == "scrypt") || !file.encoding.kind.iter().any(|kind| kind == "xsalsa20-poly1305") {
+        bail!("Only scrypt and xsalsa20-poly1305 encrypted exports can be imported");
+    }
+
+    let encoded = base64::engine::general_purpose::STANDARD
+        .decode(&file.encoded)
+        .context("Key export is not valid base64")?;
+    if encoded.len() < SCRYPT_PARAMS_LENGTH + NONCE_LENGTH {
+        bail!("Key export is truncated");
+    }
+
+    let (params, sealed) = encoded.split_at(SCRYPT_PARAMS_LENGTH);
+    let salt = &params[..32];
+    let param = |index: usize| u32::from_le_bytes(params[32 + index * 4..36 + index * 4].try_into().unwrap());
+    let (n, p, r) = (param(0), param(1), param(2));
+    if !n.is_power_of_two() {
+        bail!("Key export has an invalid scrypt cost {}", n);
+    }
+    let scrypt_params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, 32)
+        .map_err(|e| anyhow!("Key export has invalid scrypt parameters: {}", e))?;
+
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.expose_secret().as_bytes(), salt, &scrypt_params, &mut key)
+        .map_err(|e| anyhow!("Failed to derive the export's key: {}", e))?;
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+    let document = XSalsa20Poly1305::new(&key.into())
+        .decrypt(nonce.into(), ciphertext)
+        .map_err(|_| anyhow!("Wrong password for the key export"))?;
+
+    let secret_key = document
+        .strip_prefix(&PKCS8_HEADER[..])
+        .and_then(|rest| rest.get(..SECRET_KEY_LENGTH))
+        .context("Key export doesn't hold an sr25519 secret key")?;
+    let secret_key = from_ed25519_encoding(secret_key);
+    let pair = secret_key_pair(&secret_key)?;
+
+    Ok((pair, SecretString::new(format!("0x{}", hex::encode(&secret_key)))))
+}
+
+/// Key pair from a 64-byte sr25519 secret key in schnorrkel's encoding
+fn secret_key_pair(bytes: &[u8]) -> Result<sr25519::Pair> {
+    sr25519::Pair::from_seed_slice(bytes).map_err(|_| anyhow!("Not a valid sr25519 secret key"))
+}
+
+/// Converts a secret key from the ed25519-compatible encoding polkadot.js exports, whose scalar
+/// is multiplied by the cofactor, to schnorrkel's
+fn from_ed25519_encoding(secret_key: &[u8]) -> Vec<u8> {
+    let mut bytes = secret_key.to_vec();
+    let mut carry = 0u8;
+    for byte in bytes[..32].iter_mut().rev() {
+        let low = *byte & 0b111;
+        *byte = (*byte >> 3) | carry;
+        carry = low << 5;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_phrases_and_secret_keys_give_the_same_key() {
+        let alice = keypair(&SecretString::new("//Alice".to_string())).unwrap();
+        assert_eq!(address(&alice, ChainNetwork::Local), "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+        assert_eq!(address(&alice, ChainNetwork::Polkadot), "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5");
+
+        let (generated, phrase) = generate();
+        assert_eq!(keypair(&phrase).unwrap().public(), generated.public());
+
+        let secret_key = SecretString::new(format!("0x{}", hex::encode(alice.to_raw_vec())));
+        assert_eq!(keypair(&secret_key).unwrap().public(), alice.public());
+        assert!(keypair(&SecretString::new("not a phrase".to_string())).is_err());
+    }
+
+    #[test]
+    fn roles_name_their_secrets() {
+        let role: KeyRole = "Owner".parse().unwrap();
+        assert_eq!(role.secret_name(), "CONTRACT_OWNER_SEED_PHRASE");
+        assert_eq!(role.next_secret_name(), "CONTRACT_OWNER_SEED_PHRASE_NEXT");
+        assert_eq!(KeyRole::Wallet.previous_secret_name(), "WALLET_SEED_PHRASE_PREVIOUS");
+        assert_eq!("receipt".parse::<KeyRole>().unwrap().secret_name(), "RECEIPT_SIGNING_SEED_PHRASE");
+        assert!(!KeyRole::Receipt.signs_transactions());
+        assert!("treasury".parse::<KeyRole>().is_err());
+    }
+}
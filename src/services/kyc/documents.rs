@@ -0,0 +1,127 @@
+//! Identity documents uploaded for manual review
+//!
+//! Files go to object storage encrypted at rest, under keys derived from the verification and
+//! document ids so nothing about the user leaks into object names. Reviewers download them
+//! through short-lived presigned URLs.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::KycDocumentConfig;
+use crate::db::KycRepository;
+use crate::models::kyc::{
+    KycDocument, KycDocumentLink, KycDocumentSide, KycDocumentType, KycVerification, NewKycDocument,
+};
+use crate::services::storage::{ObjectStorage, ServerSideEncryption};
+
+/// Document received from a user
+#[derive(Debug, Clone)]
+pub struct DocumentUpload {
+    pub document_type: KycDocumentType,
+    pub side: Option<KycDocumentSide>,
+    pub file_name: Option<String>,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+/// Stores documents and issues reviewer download links
+#[derive(Clone)]
+pub struct KycDocumentStore {
+    repository: KycRepository,
+    storage: ObjectStorage,
+    url_ttl_secs: u64,
+    max_bytes: usize,
+}
+
+impl KycDocumentStore {
+    /// Creates a document store from configuration
+    pub fn new(repository: KycRepository, config: KycDocumentConfig) -> Result<Self> {
+        let encryption = match config.kms_key_id {
+            Some(key_id) => ServerSideEncryption::Kms { key_id },
+            None => ServerSideEncryption::Aes256,
+        };
+
+        Ok(Self {
+            repository,
+            storage: ObjectStorage::new(config.s3, config.bucket, encryption)?,
+            url_ttl_secs: config.url_ttl_secs,
+            max_bytes: config.max_bytes,
+        })
+    }
+
+    /// Largest document accepted
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Stores a document and links it to the verification
+    pub async fn upload(&self, verification: &KycVerification, upload: DocumentUpload) -> Result<KycDocument> {
+        let id = Uuid::new_v4();
+        let new_document = NewKycDocument {
+            id,
+            verification_id: verification.id,
+            document_type: upload.document_type,
+            side: upload.side,
+            file_name: upload.file_name,
+            content_type: upload.content_type.to_string(),
+            size_bytes: i64::try_from(upload.bytes.len()).context("Document is too large")?,
+            sha256: hex::encode(Sha256::digest(&upload.bytes)),
+            storage_key: format!("kyc/{}/{}", verification.id, id),
+        };
+        let storage_key = &new_document.storage_key;
+
+        self.storage.put_object(storage_key, upload.bytes, upload.content_type).await?;
+
+        match self.repository.create_document(&new_document).await {
+            Ok(document) => {
+                info!("Stored {:?} document {} for KYC verification {}", document.document_type, id, verification.id);
+                Ok(document)
+            },
+            Err(err) => {
+                // Don't leave an identity document behind that nothing refers to
+                if let Err(cleanup) = self.storage.delete_object(storage_key).await {
+                    warn!("Failed to remove orphaned KYC document {}: {:#}", storage_key, cleanup);
+                }
+                Err(err)
+            },
+        }
+    }
+
+    /// Documents uploaded for a verification
+    pub async fn list(&self, verification_id: Uuid) -> Result<Vec<KycDocument>> {
+        self.repository.list_documents(verification_id).await
+    }
+
+    /// Documents of a verification with download links for reviewers
+    pub async fn links(&self, verification_id: Uuid) -> Result<Vec<KycDocumentLink>> {
+        self.list(verification_id)
+            .await?
+            .into_iter()
+            .map(|document| {
+                let presigned = self.storage.presigned_get(&document.storage_key, self.url_ttl_secs)?;
+
+                Ok(KycDocumentLink {
+                    document,
+                    url: presigned.url.to_string(),
+                    url_expires_at: presigned.expires_at,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Content type of a supported document format, from the file's leading bytes. Clients'
+/// declared types aren't trusted.
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,46 @@
+//! Errors returned by KYC provider clients
+
+use thiserror::Error;
+
+use crate::models::kyc::KycProvider;
+use crate::services::http_client::HttpError;
+
+/// Failure talking to a KYC provider
+#[derive(Error, Debug)]
+pub enum KycError {
+    #[error("{provider} returned {status}: {message}")]
+    Provider {
+        provider: KycProvider,
+        status: u16,
+        message: String,
+    },
+
+    #[error("{provider} request failed: {source}")]
+    Transport {
+        provider: KycProvider,
+        #[source]
+        source: HttpError,
+    },
+
+    #[error("{provider} webhook rejected: {reason}")]
+    WebhookRejected {
+        provider: KycProvider,
+        reason: String,
+    },
+}
+
+impl KycError {
+    /// Whether the failure is on the provider's side and worth retrying elsewhere
+    pub fn is_provider_outage(&self) -> bool {
+        match self {
+            KycError::Provider { status, .. } => *status >= 500,
+            KycError::Transport { source, .. } => source.is_unreachable(),
+            KycError::WebhookRejected { .. } => false,
+        }
+    }
+
+    /// Whether the provider refused our credentials
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, KycError::Provider { status: 401 | 403, .. })
+    }
+}
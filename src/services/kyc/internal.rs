@@ -0,0 +1,61 @@
+//! Manual review by the operator
+//!
+//! Internal verifications need no third party: the user uploads identity documents through the
+//! API, which authenticates the wallet, and reviewers decide from the stored documents. The
+//! applicant id is the verification id and there are no webhooks.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use reqwest::header::HeaderMap;
+
+use super::error::KycError;
+use super::types::{CreateApplicantRequest, KycAccessToken, KycApplicant, KycApplicantStatus, KycWebhookPayload};
+use super::KycService;
+use crate::models::kyc::KycProvider;
+
+/// How long the client may keep uploading documents with one session
+const UPLOAD_SESSION_HOURS: i64 = 24;
+
+/// Internal provider implementing [`KycService`]
+pub struct InternalKycService;
+
+#[async_trait]
+impl KycService for InternalKycService {
+    fn provider(&self) -> KycProvider {
+        KycProvider::Internal
+    }
+
+    async fn create_applicant(&self, request: &CreateApplicantRequest) -> Result<KycApplicant> {
+        Ok(KycApplicant {
+            provider: KycProvider::Internal,
+            applicant_id: request.external_user_id.clone(),
+            external_user_id: request.external_user_id.clone(),
+            level: request.level,
+        })
+    }
+
+    async fn generate_access_token(&self, applicant: &KycApplicant) -> Result<KycAccessToken> {
+        // Uploads are authorised by the wallet signature; the token only names the verification
+        Ok(KycAccessToken {
+            token: applicant.applicant_id.clone(),
+            expires_at: Utc::now() + Duration::hours(UPLOAD_SESSION_HOURS),
+        })
+    }
+
+    async fn get_applicant_status(&self, applicant_id: &str) -> Result<KycApplicantStatus> {
+        bail!("Internal verification {} has no provider-side status; reviewers record the outcome", applicant_id)
+    }
+
+    fn verify_webhook(&self, _headers: &HeaderMap, _body: &[u8]) -> Result<()> {
+        Err(KycError::WebhookRejected {
+            provider: KycProvider::Internal,
+            reason: "internal verifications have no webhooks".to_string(),
+        }
+        .into())
+    }
+
+    async fn parse_webhook(&self, _body: &[u8]) -> Result<KycWebhookPayload> {
+        bail!("Internal verifications have no webhooks")
+    }
+}
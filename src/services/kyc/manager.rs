@@ -0,0 +1,340 @@
+//! Verification lifecycle on top of the configured providers
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use metrics::increment_counter;
+use reqwest::header::HeaderMap;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::error::KycError;
+use super::router::KycRouter;
+use super::types::{CreateApplicantRequest, KycApplicant, KycSession, KycWebhookPayload};
+use super::KycService;
+use crate::db::{ActivityLogRepository, KycRepository, UnitOfWork, UserRepository};
+use crate::models::activity_log::CreateActivityLogRequest;
+use crate::models::kyc::{CreateKycVerificationRequest, KycLevel, KycProvider, KycVerification};
+use crate::models::user::{KycStatus, UpdateUserRequest, User};
+use crate::services::notifications::{Notification, NotificationStore};
+use crate::services::webhooks::WebhookDispatcher;
+
+/// Starts verifications with the routed provider and applies provider webhooks
+#[derive(Clone)]
+pub struct KycManager {
+    db: PgPool,
+    repository: KycRepository,
+    router: KycRouter,
+    webhook_tolerance: Option<Duration>,
+    webhooks: WebhookDispatcher,
+}
+
+impl KycManager {
+    /// Creates a manager routing verifications with `router`. Webhooks whose event time is more
+    /// than `webhook_tolerance_secs` from now are rejected; 0 disables the check.
+    pub fn new(db: PgPool, router: KycRouter, webhook_tolerance_secs: u64) -> Self {
+        Self {
+            repository: KycRepository::new(db.clone()),
+            webhooks: WebhookDispatcher::new(db.clone()),
+            db,
+            router,
+            webhook_tolerance: (webhook_tolerance_secs > 0)
+                .then(|| Duration::seconds(i64::try_from(webhook_tolerance_secs).unwrap_or(i64::MAX))),
+        }
+    }
+
+    /// Client for a provider, if it is configured
+    pub fn service(&self, provider: KycProvider) -> Option<Arc<dyn KycService>> {
+        self.router.service(provider)
+    }
+
+    /// Looks up a verification by id
+    pub async fn get_verification(&self, id: Uuid) -> Result<Option<KycVerification>> {
+        self.repository.get_verification(id).await
+    }
+
+    /// Queues a verification whose on-chain sync failed for another round of attempts
+    pub async fn requeue_onchain_sync(&self, id: Uuid) -> Result<Option<KycVerification>> {
+        let verification = self.repository.requeue_onchain_sync(id).await?;
+        if verification.is_some() {
+            info!("Requeued on-chain KYC sync of verification {}", id);
+        }
+
+        Ok(verification)
+    }
+
+    /// Latest verification started by a user
+    pub async fn latest_for_user(&self, user_id: Uuid) -> Result<Option<KycVerification>> {
+        self.repository.latest_for_user(user_id).await
+    }
+
+    /// Level the user is verified at, from their most recently approved verification
+    pub async fn approved_level(&self, user_id: Uuid) -> Result<Option<KycLevel>> {
+        self.repository.approved_level(user_id).await
+    }
+
+    /// Same as [`approved_level`](Self::approved_level), for several users in one query; users
+    /// without an approved verification are left out
+    pub async fn approved_levels(&self, user_ids: &[Uuid]) -> Result<HashMap<Uuid, KycLevel>> {
+        self.repository.approved_levels(user_ids).await
+    }
+
+    /// Starts a verification for the user, or resumes their pending one at the same level with
+    /// the provider that is handling it
+    pub async fn initiate(&self, user: &User, request: &CreateKycVerificationRequest) -> Result<KycSession> {
+        let pending = self
+            .repository
+            .latest_for_user(user.id)
+            .await?
+            .filter(|latest| latest.level == request.level && latest.status == KycStatus::Pending)
+            .and_then(|latest| self.service(latest.provider).map(|service| (latest, service)));
+
+        let (verification, service) = match pending {
+            Some(pending) => pending,
+            None => self.start(user, request).await?,
+        };
+
+        let access_token = service.generate_access_token(&Self::applicant(&verification)?).await?;
+
+        Ok(KycSession { verification, access_token })
+    }
+
+    /// Registers a new applicant with the first routed provider that isn't down
+    async fn start(&self, user: &User, request: &CreateKycVerificationRequest) -> Result<(KycVerification, Arc<dyn KycService>)> {
+        let candidates = self.router.candidates(request.country.as_deref(), request.level);
+        if candidates.is_empty() {
+            return Err(anyhow!("No configured KYC provider handles {} verification", request.level));
+        }
+
+        // The verification id is the provider's external user id, so every attempt is a fresh
+        // applicant and webhooks can be traced back without exposing the wallet
+        let id = Uuid::new_v4();
+        let applicant_request = CreateApplicantRequest {
+            external_user_id: id.to_string(),
+            level: request.level,
+            email: request.email.clone().or_else(|| user.email.clone()),
+            country: request.country.clone(),
+        };
+
+        let mut last_error = None;
+        for service in candidates {
+            let provider = service.provider();
+
+            match service.create_applicant(&applicant_request).await {
+                Ok(applicant) => {
+                    self.router.record_success(provider);
+                    if let Some(failed) = &last_error {
+                        warn!("KYC verification {} failed over to {} after: {:#}", id, provider, failed);
+                    }
+                    info!("Created {} applicant {} for user {}", provider, applicant.applicant_id, user.id);
+
+                    let verification = self
+                        .repository
+                        .create_verification(id, user.id, provider, request.level, &applicant.applicant_id)
+                        .await?;
+                    return Ok((verification, service));
+                }
+                Err(e) if self.router.record_failure(provider, &e) => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error
+            .expect("at least one provider was tried")
+            .context("Every KYC provider for this verification is unavailable"))
+    }
+
+    /// Tells the provider the user has finished the SDK flow for a verification
+    pub async fn submit(&self, verification: &KycVerification) -> Result<()> {
+        let service = self
+            .service(verification.provider)
+            .ok_or_else(|| anyhow!("KYC provider {} is not configured", verification.provider))?;
+
+        service.submit_applicant(&Self::applicant(verification)?).await?;
+
+        info!("Submitted KYC verification {} to {}", verification.id, verification.provider);
+        Ok(())
+    }
+
+    /// The provider applicant behind a verification
+    fn applicant(verification: &KycVerification) -> Result<KycApplicant> {
+        Ok(KycApplicant {
+            provider: verification.provider,
+            applicant_id: verification
+                .applicant_id
+                .clone()
+                .context("KYC verification has no applicant yet")?,
+            external_user_id: verification.id.to_string(),
+            level: verification.level,
+        })
+    }
+
+    /// Verifies a webhook's signature and event time, then parses it. Rejections are logged and
+    /// counted here, before anything is stored.
+    ///
+    /// Events without an event time can't be refused as stale; [`process_webhook`](Self::process_webhook)
+    /// skips their replays instead.
+    pub async fn authenticate_webhook(
+        &self,
+        service: &dyn KycService,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<KycWebhookPayload> {
+        let provider = service.provider();
+
+        let payload = async {
+            service.verify_webhook(headers, body)?;
+            let payload = service.parse_webhook(body).await?;
+
+            let stale = match (self.webhook_tolerance, payload.occurred_at) {
+                (Some(tolerance), Some(occurred_at)) => (Utc::now() - occurred_at).abs() > tolerance,
+                _ => false,
+            };
+            if stale {
+                return Err(KycError::WebhookRejected {
+                    provider,
+                    reason: format!("event time {} is outside the tolerance", payload.occurred_at.unwrap_or_default()),
+                }
+                .into());
+            }
+
+            Ok(payload)
+        }
+        .await;
+
+        if let Err(e) = &payload {
+            warn!("Rejected {} webhook ({} bytes): {:#}", provider, body.len(), e);
+            increment_counter!("kyc_webhook_rejections_total", "provider" => provider.to_string());
+        }
+
+        payload
+    }
+
+    /// Stores a provider webhook and applies its review outcome to the verification and user.
+    /// Returns the affected verification, or `None` when the applicant is unknown or the same
+    /// event was already applied.
+    pub async fn process_webhook(&self, payload: KycWebhookPayload) -> Result<Option<KycVerification>> {
+        if self.repository.webhook_applied(payload.provider, &payload.applicant_id, &payload.raw).await? {
+            warn!("Ignoring replayed {} webhook {} for applicant {}", payload.provider, payload.event_type, payload.applicant_id);
+            increment_counter!("kyc_webhook_replays_total", "provider" => payload.provider.to_string());
+            return Ok(None);
+        }
+
+        let event = self
+            .repository
+            .save_webhook_event(payload.provider, &payload.event_type, &payload.applicant_id, &payload.raw)
+            .await?;
+
+        let Some(verification) = self
+            .repository
+            .find_by_applicant(payload.provider, &payload.applicant_id)
+            .await?
+        else {
+            warn!("{} webhook {} for unknown applicant {}", payload.provider, payload.event_type, payload.applicant_id);
+            self.repository
+                .mark_webhook_processed(event.id, None, Some("Unknown applicant"))
+                .await?;
+            return Ok(None);
+        };
+
+        let Some(outcome) = payload.status else {
+            self.repository
+                .mark_webhook_processed(event.id, Some(verification.id), None)
+                .await?;
+            return Ok(Some(verification));
+        };
+
+        // A rejected upgrade must not revoke a lower level the user already passed
+        let user = UserRepository::new(self.db.clone())
+            .get_by_id(verification.user_id)
+            .await?
+            .context("KYC verification has no user")?;
+        let user_status = match outcome.status {
+            KycStatus::Approved => Some(KycStatus::Approved),
+            KycStatus::Rejected if user.kyc_status != KycStatus::Approved => Some(KycStatus::Rejected),
+            _ => None,
+        };
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        let updated = KycRepository::update_status_in(
+            uow.conn(),
+            verification.id,
+            &outcome.status,
+            outcome.review_answer.as_deref(),
+            &outcome.rejection_reasons,
+        )
+        .await?
+        .context("KYC verification disappeared while applying webhook")?;
+
+        let mut user_after = user.clone();
+        if let Some(kyc_status) = user_status {
+            let activity_type = match kyc_status {
+                KycStatus::Approved => "kyc_approved",
+                _ => "kyc_rejected",
+            };
+
+            user_after = UserRepository::update_in(
+                uow.conn(),
+                user.id,
+                &UpdateUserRequest {
+                    email: None,
+                    kyc_status: Some(kyc_status),
+                    kyc_timestamp: Some(Utc::now()),
+                    kyc_reference: Some(payload.applicant_id.clone()),
+                },
+            )
+            .await?
+            .context("User disappeared while applying KYC webhook")?;
+
+            ActivityLogRepository::record_in(
+                uow.conn(),
+                &CreateActivityLogRequest {
+                    user_id: Some(user.id),
+                    activity_type: activity_type.to_string(),
+                    description: Some(format!("{} review completed", payload.provider)),
+                    data: Some(json!({
+                        "verification_id": updated.id,
+                        "provider": payload.provider,
+                        "level": updated.level,
+                        "review_answer": updated.review_answer,
+                        "rejection_reasons": updated.rejection_reasons,
+                    })),
+                    ip_address: None,
+                },
+            )
+            .await?;
+        }
+
+        // Rejected upgrades are emailed too, even though they leave the user's status alone
+        let notification = match updated.status {
+            KycStatus::Approved => Some(Notification::KycApproved {
+                verification_id: updated.id,
+                level: updated.level,
+            }),
+            KycStatus::Rejected => Some(Notification::KycRejected {
+                verification_id: updated.id,
+                level: updated.level,
+                reasons: updated.rejection_reasons.clone(),
+            }),
+            _ => None,
+        };
+        if let Some(notification) = notification {
+            NotificationStore::queue_in(uow.conn(), &[(user.id, notification)]).await?;
+        }
+
+        KycRepository::mark_webhook_processed_in(uow.conn(), event.id, Some(updated.id), None).await?;
+        uow.commit().await?;
+
+        info!("KYC verification {} is now {:?}", updated.id, updated.status);
+        if updated.status != verification.status {
+            if let Err(err) = self.webhooks.publish_kyc_status_change(&user_after, &user.kyc_status, Some(&updated)).await {
+                warn!("Failed to publish KYC status change of verification {}: {}", updated.id, err);
+            }
+        }
+        Ok(Some(updated))
+    }
+}
@@ -0,0 +1,165 @@
+//! KYC provider integrations for LSRWA Express
+//!
+//! Each provider implements [`KycService`]; [`KycServiceFactory`] builds the one selected by
+//! configuration. Providers report outcomes as the user-level [`KycStatus`].
+//!
+//! [`KycStatus`]: crate::models::user::KycStatus
+
+mod documents;
+mod error;
+mod internal;
+mod manager;
+mod onchain_sync;
+mod onfido;
+mod persona;
+mod router;
+mod shufti;
+mod signature;
+mod sumsub;
+mod types;
+
+pub use documents::{sniff_content_type, DocumentUpload, KycDocumentStore};
+pub use error::KycError;
+pub use internal::InternalKycService;
+pub use manager::KycManager;
+pub use onchain_sync::KycSyncWorker;
+pub use onfido::OnfidoKycService;
+pub use persona::PersonaKycService;
+pub use router::KycRouter;
+pub use shufti::ShuftiKycService;
+pub use sumsub::SumSubKycService;
+pub use types::{
+    CreateApplicantRequest, KycAccessToken, KycApplicant, KycApplicantStatus, KycSession, KycWebhookPayload,
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::KycConfig;
+use crate::models::kyc::KycProvider;
+use crate::services::http_client::HttpClient;
+
+/// Operations every KYC provider supports
+#[async_trait]
+pub trait KycService: Send + Sync {
+    /// Provider this service talks to
+    fn provider(&self) -> KycProvider;
+
+    /// Registers an applicant to be verified at the requested level
+    async fn create_applicant(&self, request: &CreateApplicantRequest) -> Result<KycApplicant>;
+
+    /// Issues a short-lived token for the provider's web/mobile SDK
+    async fn generate_access_token(&self, applicant: &KycApplicant) -> Result<KycAccessToken>;
+
+    /// Called once the applicant has finished the SDK flow. Providers that review submissions
+    /// on their own need nothing here; Onfido starts a check.
+    async fn submit_applicant(&self, _applicant: &KycApplicant) -> Result<()> {
+        Ok(())
+    }
+
+    /// Fetches the applicant's current review outcome
+    async fn get_applicant_status(&self, applicant_id: &str) -> Result<KycApplicantStatus>;
+
+    /// Checks that a webhook was signed by the provider, failing with
+    /// [`KycError::WebhookRejected`] otherwise
+    fn verify_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<()>;
+
+    /// Parses a webhook body sent by the provider, fetching whatever the body only references.
+    /// Only call this on verified bodies.
+    async fn parse_webhook(&self, body: &[u8]) -> Result<KycWebhookPayload>;
+
+    /// Makes a cheap authenticated request to confirm the provider accepts our credentials.
+    /// Providers without remote credentials have nothing to check.
+    async fn check_credentials(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Passes a credentials probe unless the provider refused the credentials; a probe for a
+/// resource that doesn't exist still proves they were accepted
+fn probe_accepted<T>(provider: KycProvider, result: Result<T>) -> Result<()> {
+    let Err(err) = result else {
+        return Ok(());
+    };
+
+    let answered = err
+        .downcast_ref::<KycError>()
+        .is_some_and(|err| !err.is_unauthorized() && !err.is_provider_outage());
+    if answered {
+        Ok(())
+    } else {
+        Err(err.context(format!("{} credentials check failed", provider)))
+    }
+}
+
+/// Builds KYC services from configuration
+pub struct KycServiceFactory;
+
+impl KycServiceFactory {
+    /// Builds the configured default provider
+    pub fn create_default(config: &KycConfig, http: &HttpClient) -> Result<Arc<dyn KycService>> {
+        Self::create(config, config.provider, http)
+    }
+
+    /// Builds every provider that has credentials configured
+    pub fn create_configured(config: &KycConfig, http: &HttpClient) -> Result<Vec<Arc<dyn KycService>>> {
+        let mut services = Vec::new();
+
+        if config.sumsub.is_some() {
+            services.push(Self::create(config, KycProvider::SumSub, http)?);
+        }
+        if config.onfido.is_some() {
+            services.push(Self::create(config, KycProvider::Onfido, http)?);
+        }
+        if config.persona.is_some() {
+            services.push(Self::create(config, KycProvider::Persona, http)?);
+        }
+        if config.shufti.is_some() {
+            services.push(Self::create(config, KycProvider::Shufti, http)?);
+        }
+        // Manual review needs somewhere to keep the documents
+        if config.documents.is_some() {
+            services.push(Self::create(config, KycProvider::Internal, http)?);
+        }
+
+        if !services.iter().any(|service| service.provider() == config.provider) {
+            warn!("The default KYC provider {} is not configured; verifications can't be started", config.provider);
+        }
+
+        Ok(services)
+    }
+
+    /// Builds the given provider
+    pub fn create(config: &KycConfig, provider: KycProvider, http: &HttpClient) -> Result<Arc<dyn KycService>> {
+        match provider {
+            KycProvider::SumSub => {
+                let sumsub = config.sumsub.clone()
+                    .ok_or_else(|| anyhow!("SumSub is not configured; set SUMSUB_API_KEY and SUMSUB_SECRET_KEY"))?;
+
+                Ok(Arc::new(SumSubKycService::new(sumsub, config.environment, http.clone())?))
+            },
+            KycProvider::Onfido => {
+                let onfido = config.onfido.clone()
+                    .ok_or_else(|| anyhow!("Onfido is not configured; set ONFIDO_API_TOKEN"))?;
+
+                Ok(Arc::new(OnfidoKycService::new(onfido, config.environment, http.clone())?))
+            },
+            KycProvider::Persona => {
+                let persona = config.persona.clone()
+                    .ok_or_else(|| anyhow!("Persona is not configured; set PERSONA_API_KEY and PERSONA_TEMPLATE_*"))?;
+
+                Ok(Arc::new(PersonaKycService::new(persona, config.environment, http.clone())?))
+            },
+            KycProvider::Shufti => {
+                let shufti = config.shufti.clone()
+                    .ok_or_else(|| anyhow!("Shufti Pro is not configured; set SHUFTI_CLIENT_ID and SHUFTI_SECRET_KEY"))?;
+
+                Ok(Arc::new(ShuftiKycService::new(shufti, http.clone())?))
+            },
+            KycProvider::Internal => Ok(Arc::new(InternalKycService)),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Background worker adding approved wallets to the contract's KYC allowlist
+//!
+//! Approved verifications are queued by [`KycRepository::update_status_in`]. Each run submits a
+//! batch of them in a single contract call and records the transaction hash on every
+//! verification it covered, so the on-chain allowlist can be traced back to the off-chain
+//! decision. Failed submissions are retried with exponential backoff. While the
+//! `onchain_kyc_gating` flag is off, approvals stay queued until it's turned back on.
+
+use anyhow::Result;
+use chrono::Utc;
+use metrics::{counter, increment_counter};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use subxt::utils::AccountId32;
+use tokio::time;
+use tracing::{debug, error, info, warn};
+
+use crate::config::KycSyncConfig;
+use crate::db::{FeatureFlagRepository, KycRepository};
+use crate::models::feature_flag::FeatureFlag;
+use crate::models::kyc::PendingKycSync;
+use crate::services::shutdown::Shutdown;
+use crate::services::ChainClient;
+
+/// Maximum backoff between sync attempts
+const MAX_BACKOFF_SECS: u64 = 6 * 60 * 60;
+
+/// Worker that submits approved wallets to the contract and schedules retries
+pub struct KycSyncWorker {
+    repository: KycRepository,
+    blockchain: Arc<dyn ChainClient>,
+    flags: FeatureFlagRepository,
+    config: KycSyncConfig,
+}
+
+impl KycSyncWorker {
+    /// Creates a new sync worker
+    pub fn new(db: PgPool, blockchain: Arc<dyn ChainClient>, flags: FeatureFlagRepository, config: KycSyncConfig) -> Self {
+        Self {
+            repository: KycRepository::new(db),
+            blockchain,
+            flags,
+            config,
+        }
+    }
+
+    /// Runs the sync loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        info!("Starting KYC on-chain sync worker with polling interval {} seconds", self.config.interval_secs);
+
+        let mut interval = time::interval(Duration::from_secs(self.config.interval_secs));
+
+        while shutdown.tick(&mut interval).await {
+            match self.run_once().await {
+                Ok(count) => {
+                    if count > 0 {
+                        info!("Synced {} approved KYC verifications on-chain", count);
+                    }
+                },
+                Err(err) => {
+                    error!("Failed to sync KYC approvals on-chain: {}", err);
+                }
+            }
+        }
+
+        info!("KYC on-chain sync worker stopped");
+        Ok(())
+    }
+
+    /// Submits one batch of due verifications. Returns how many were synced.
+    pub async fn run_once(&self) -> Result<usize> {
+        if !self.flags.is_enabled(FeatureFlag::OnChainKycGating).await? {
+            debug!("On-chain KYC gating is disabled; leaving approvals queued");
+            return Ok(0);
+        }
+
+        let due = self.repository.claim_due_onchain_syncs(self.config.batch_size).await?;
+        if due.is_empty() {
+            return Ok(0);
+        }
+
+        // A malformed address would fail the whole call, so it fails on its own instead
+        let (valid, invalid): (Vec<_>, Vec<_>) = due
+            .into_iter()
+            .partition(|sync| sync.wallet_address.parse::<AccountId32>().is_ok());
+        for sync in &invalid {
+            warn!("KYC verification {} has no valid wallet to sync on-chain: {}", sync.verification_id, sync.wallet_address);
+            self.repository
+                .record_onchain_sync_failure(sync.verification_id, "Wallet address is not a valid account", None)
+                .await?;
+        }
+        if valid.is_empty() {
+            return Ok(0);
+        }
+
+        // An upgraded user can have several approved verifications in the batch
+        let mut wallets: Vec<String> = valid.iter().map(|sync| sync.wallet_address.clone()).collect();
+        wallets.sort();
+        wallets.dedup();
+
+        match self.blockchain.submit_kyc_approvals(&wallets).await {
+            Ok(tx_hash) => {
+                let ids: Vec<_> = valid.iter().map(|sync| sync.verification_id).collect();
+                self.repository.mark_onchain_synced(&ids, &tx_hash).await?;
+                counter!("kyc_onchain_synced_total", wallets.len() as u64);
+
+                Ok(valid.len())
+            },
+            Err(err) => {
+                let message = format!("{:#}", err);
+                warn!("Failed to submit {} KYC approvals on-chain: {}", wallets.len(), message);
+                increment_counter!("kyc_onchain_sync_failures_total");
+
+                for sync in &valid {
+                    self.record_failure(sync, &message).await?;
+                }
+
+                Ok(0)
+            },
+        }
+    }
+
+    /// Schedules the next attempt of a failed sync, or gives up after the maximum attempts
+    async fn record_failure(&self, sync: &PendingKycSync, message: &str) -> Result<()> {
+        let attempts = sync.onchain_sync_attempts as u32 + 1;
+        let next_attempt_at = if attempts >= self.config.max_attempts {
+            error!(
+                "KYC verification {} failed to sync on-chain after {} attempts: {}",
+                sync.verification_id, attempts, message
+            );
+            None
+        } else {
+            let delay = self.retry_delay(attempts);
+            Some(Utc::now() + chrono::Duration::seconds(delay as i64))
+        };
+
+        self.repository
+            .record_onchain_sync_failure(sync.verification_id, message, next_attempt_at)
+            .await
+    }
+
+    /// Exponential backoff for the given attempt number
+    fn retry_delay(&self, attempts: u32) -> u64 {
+        self.config.retry_delay_secs
+            .saturating_mul(2u64.saturating_pow(attempts.saturating_sub(1)))
+            .min(MAX_BACKOFF_SECS)
+    }
+}
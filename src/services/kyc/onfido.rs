@@ -0,0 +1,487 @@
+//! Onfido integration
+//!
+//! Requests authenticate with `Authorization: Token token=<api token>`. The SDK uploads documents
+//! against an applicant, then [`submit_applicant`](KycService::submit_applicant) starts a check
+//! running the reports configured for the level.
+//!
+//! Webhooks carry `X-SHA2-Signature`, the hex HMAC-SHA256 of the body keyed with the webhook
+//! token. They only reference the check or report, which is fetched to find the applicant.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::Hmac;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use super::error::KycError;
+use super::signature::{decode_hex, hmac_matches, required_header};
+use super::types::{CreateApplicantRequest, KycAccessToken, KycApplicant, KycApplicantStatus, KycWebhookPayload};
+use super::{probe_accepted, KycService};
+use crate::config::{KycEnvironment, OnfidoConfig};
+use crate::models::kyc::KycProvider;
+use crate::models::user::KycStatus;
+use crate::services::http_client::HttpClient;
+
+/// Onfido SDK tokens are valid for 90 minutes
+const SDK_TOKEN_TTL_MINUTES: i64 = 90;
+
+/// Onfido client implementing [`KycService`]
+pub struct OnfidoKycService {
+    config: OnfidoConfig,
+    client: HttpClient,
+}
+
+impl OnfidoKycService {
+    /// Creates an Onfido client, refusing tokens that belong to the other environment
+    pub fn new(config: OnfidoConfig, environment: KycEnvironment, client: HttpClient) -> Result<Self> {
+        // Onfido API tokens are prefixed with the environment they were issued for
+        let expected_prefix = match environment {
+            KycEnvironment::Sandbox => "api_sandbox.",
+            KycEnvironment::Live => "api_live.",
+        };
+        if !config.api_token.starts_with(expected_prefix) {
+            bail!("ONFIDO_API_TOKEN is not a {} token (expected a '{}' prefix)", environment, expected_prefix);
+        }
+
+        Ok(Self { config, client })
+    }
+
+    /// Sends an authenticated request and deserializes the JSON response
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<&Value>,
+    ) -> Result<T> {
+        let base = format!("{}{}", self.config.base_url.trim_end_matches('/'), path);
+        let url = if query.is_empty() { Url::parse(&base) } else { Url::parse_with_params(&base, query) }
+            .context("Invalid Onfido URL")?;
+
+        let mut request = self.client
+            .request(method, url)
+            .header("Accept", "application/json")
+            .header("Authorization", format!("Token token={}", self.config.api_token));
+
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = self.client
+            .send(request)
+            .await
+            .map_err(|source| KycError::Transport { provider: KycProvider::Onfido, source })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(KycError::Provider {
+                provider: KycProvider::Onfido,
+                status: status.as_u16(),
+                message,
+            }.into());
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|source| KycError::Transport { provider: KycProvider::Onfido, source: source.into() }.into())
+    }
+
+    /// Reports of a check, needed to explain a `consider` result
+    async fn reports(&self, check_id: &str) -> Result<Vec<Report>> {
+        let reports: ReportList = self
+            .send(Method::GET, "/reports", &[("check_id", check_id)], None)
+            .await
+            .context("Failed to list Onfido reports")?;
+
+        Ok(reports.reports)
+    }
+
+    /// Review outcome of a check
+    async fn check_status(&self, check: &Check) -> Result<KycApplicantStatus> {
+        let reports = if check.result.as_deref() == Some("consider") {
+            self.reports(&check.id).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(check.status(&reports))
+    }
+}
+
+/// Applicant as returned by `POST /applicants`
+#[derive(Debug, Deserialize)]
+struct ApplicantResponse {
+    id: String,
+}
+
+/// Token as returned by `POST /sdk_token`
+#[derive(Debug, Deserialize)]
+struct SdkTokenResponse {
+    token: String,
+}
+
+/// Check as returned by `/checks`
+#[derive(Debug, Deserialize)]
+struct Check {
+    id: String,
+    applicant_id: String,
+    /// `in_progress`, `awaiting_applicant`, `complete`, `withdrawn`, `paused` or `reopened`
+    status: String,
+    /// `clear` or `consider`, once complete
+    result: Option<String>,
+}
+
+/// Response of `GET /checks`, newest first
+#[derive(Debug, Deserialize)]
+struct CheckList {
+    checks: Vec<Check>,
+}
+
+/// Report as returned by `/reports`
+#[derive(Debug, Deserialize)]
+struct Report {
+    name: String,
+    check_id: String,
+    result: Option<String>,
+    sub_result: Option<String>,
+}
+
+/// Response of `GET /reports`
+#[derive(Debug, Deserialize)]
+struct ReportList {
+    reports: Vec<Report>,
+}
+
+/// Webhook body
+#[derive(Debug, Deserialize)]
+struct WebhookBody {
+    payload: WebhookEvent,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookEvent {
+    /// `check`, `report`, ...
+    resource_type: String,
+    /// e.g. `check.completed`
+    action: String,
+    object: WebhookObject,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookObject {
+    id: String,
+    completed_at_iso8601: Option<DateTime<Utc>>,
+}
+
+impl Check {
+    /// Maps the check onto our KYC status. A completed `consider` check needs the applicant to
+    /// verify again, so it is reported as a final rejection of this verification.
+    fn status(&self, reports: &[Report]) -> KycApplicantStatus {
+        let completed = self.status == "complete";
+        let status = match (completed, self.result.as_deref()) {
+            (true, Some("clear")) => KycStatus::Approved,
+            (true, Some("consider")) => KycStatus::Rejected,
+            _ => KycStatus::Pending,
+        };
+
+        let rejected = status == KycStatus::Rejected;
+
+        let rejection_reasons = if rejected {
+            reports
+                .iter()
+                .filter(|report| report.check_id == self.id && report.result.as_deref() != Some("clear"))
+                .map(|report| match &report.sub_result {
+                    Some(sub_result) => format!("{}: {}", report.name, sub_result),
+                    None => format!("{}: {}", report.name, report.result.as_deref().unwrap_or("pending")),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        KycApplicantStatus {
+            status,
+            review_answer: self.result.clone(),
+            rejection_reasons,
+            final_rejection: rejected,
+        }
+    }
+}
+
+#[async_trait]
+impl KycService for OnfidoKycService {
+    fn provider(&self) -> KycProvider {
+        KycProvider::Onfido
+    }
+
+    async fn create_applicant(&self, request: &CreateApplicantRequest) -> Result<KycApplicant> {
+        // Names are required up front but only known after the document check; the external id
+        // keeps applicants traceable from the Onfido dashboard
+        let mut body = json!({
+            "first_name": "LSRWA",
+            "last_name": request.external_user_id,
+        });
+        if let Some(email) = &request.email {
+            body["email"] = json!(email);
+        }
+        if let Some(country) = &request.country {
+            body["location"] = json!({ "country_of_residence": country });
+        }
+
+        let applicant: ApplicantResponse = self
+            .send(Method::POST, "/applicants", &[], Some(&body))
+            .await
+            .context("Failed to create Onfido applicant")?;
+
+        Ok(KycApplicant {
+            provider: KycProvider::Onfido,
+            applicant_id: applicant.id,
+            external_user_id: request.external_user_id.clone(),
+            level: request.level,
+        })
+    }
+
+    async fn generate_access_token(&self, applicant: &KycApplicant) -> Result<KycAccessToken> {
+        let mut body = json!({ "applicant_id": applicant.applicant_id });
+        if let Some(referrer) = &self.config.sdk_referrer {
+            body["referrer"] = json!(referrer);
+        }
+
+        let token: SdkTokenResponse = self
+            .send(Method::POST, "/sdk_token", &[], Some(&body))
+            .await
+            .context("Failed to generate Onfido SDK token")?;
+
+        Ok(KycAccessToken {
+            token: token.token,
+            expires_at: Utc::now() + ChronoDuration::minutes(SDK_TOKEN_TTL_MINUTES),
+        })
+    }
+
+    async fn submit_applicant(&self, applicant: &KycApplicant) -> Result<()> {
+        let report_names = self.config.report_names(applicant.level);
+        if report_names.is_empty() {
+            bail!("No Onfido reports are configured for the {} level", applicant.level);
+        }
+
+        let _: Check = self
+            .send(
+                Method::POST,
+                "/checks",
+                &[],
+                Some(&json!({ "applicant_id": applicant.applicant_id, "report_names": report_names })),
+            )
+            .await
+            .context("Failed to create Onfido check")?;
+
+        Ok(())
+    }
+
+    async fn get_applicant_status(&self, applicant_id: &str) -> Result<KycApplicantStatus> {
+        let checks: CheckList = self
+            .send(Method::GET, "/checks", &[("applicant_id", applicant_id)], None)
+            .await
+            .context("Failed to list Onfido checks")?;
+
+        match checks.checks.first() {
+            Some(check) => self.check_status(check).await,
+            // Nothing submitted yet
+            None => Ok(KycApplicantStatus {
+                status: KycStatus::Pending,
+                review_answer: None,
+                rejection_reasons: Vec::new(),
+                final_rejection: false,
+            }),
+        }
+    }
+
+    fn verify_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<()> {
+        let rejected = |reason: &str| KycError::WebhookRejected {
+            provider: KycProvider::Onfido,
+            reason: reason.to_string(),
+        };
+
+        let token = self.config.webhook_token.as_deref()
+            .ok_or_else(|| rejected("ONFIDO_WEBHOOK_SECRET is not configured"))?;
+        let signature = decode_hex(KycProvider::Onfido, required_header(headers, KycProvider::Onfido, "X-SHA2-Signature")?)?;
+
+        if !hmac_matches::<Hmac<Sha256>>(token.as_bytes(), &[body], &signature) {
+            return Err(rejected("signature mismatch").into());
+        }
+
+        Ok(())
+    }
+
+    async fn parse_webhook(&self, body: &[u8]) -> Result<KycWebhookPayload> {
+        let raw: Value = serde_json::from_slice(body).context("Onfido webhook is not valid JSON")?;
+        let webhook: WebhookBody = serde_json::from_value(raw.clone()).context("Unrecognised Onfido webhook")?;
+        let event = webhook.payload;
+
+        let check_id = match event.resource_type.as_str() {
+            "check" => event.object.id.clone(),
+            "report" => {
+                let report: Report = self
+                    .send(Method::GET, &format!("/reports/{}", event.object.id), &[], None)
+                    .await
+                    .context("Failed to fetch Onfido report")?;
+                report.check_id
+            }
+            other => return Err(anyhow!("Unsupported Onfido webhook resource '{}'", other)),
+        };
+
+        let check: Check = self
+            .send(Method::GET, &format!("/checks/{}", check_id), &[], None)
+            .await
+            .context("Failed to fetch Onfido check")?;
+
+        // Only a completed check carries a review outcome
+        let status = match event.action.as_str() {
+            "check.completed" => Some(self.check_status(&check).await?),
+            _ => None,
+        };
+
+        Ok(KycWebhookPayload {
+            provider: KycProvider::Onfido,
+            event_type: event.action,
+            applicant_id: check.applicant_id,
+            status,
+            occurred_at: event.object.completed_at_iso8601,
+            raw,
+        })
+    }
+
+    async fn check_credentials(&self) -> Result<()> {
+        let probe = self
+            .send::<Value>(Method::GET, "/applicants", &[("page", "1"), ("per_page", "1")], None)
+            .await;
+        probe_accepted(KycProvider::Onfido, probe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Path, routing::get, Json, Router};
+    use hmac::Mac;
+
+    use crate::config::OutboundHttpConfig;
+
+    const WEBHOOK_TOKEN: &str = "test_webhook_token";
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/onfido/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+    }
+
+    fn fixture_json<T: DeserializeOwned>(name: &str) -> T {
+        serde_json::from_str(&fixture(name)).expect("fixture should deserialize")
+    }
+
+    fn http_client() -> HttpClient {
+        HttpClient::from_config(&OutboundHttpConfig::default()).unwrap()
+    }
+
+    fn service(base_url: &str) -> OnfidoKycService {
+        let config = OnfidoConfig {
+            base_url: base_url.to_string(),
+            api_token: "api_sandbox.test_token".to_string(),
+            webhook_token: Some(WEBHOOK_TOKEN.to_string()),
+            sdk_referrer: None,
+            report_names: [vec!["document".to_string()], Vec::new(), Vec::new()],
+        };
+
+        OnfidoKycService::new(config, KycEnvironment::Sandbox, http_client()).expect("sandbox token should be accepted")
+    }
+
+    fn signed_headers(body: &[u8]) -> HeaderMap {
+        let mut mac = Hmac::<Sha256>::new_from_slice(WEBHOOK_TOKEN.as_bytes()).unwrap();
+        mac.update(body);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-SHA2-Signature", hex::encode(mac.finalize().into_bytes()).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn live_token_is_refused_in_sandbox() {
+        let mut config = service("http://localhost").config;
+        config.api_token = "api_live.test_token".to_string();
+
+        assert!(OnfidoKycService::new(config, KycEnvironment::Sandbox, http_client()).is_err());
+    }
+
+    #[test]
+    fn clear_check_is_approved() {
+        let check: Check = fixture_json("check_clear.json");
+
+        let status = check.status(&[]);
+
+        assert_eq!(status.status, KycStatus::Approved);
+        assert_eq!(status.review_answer.as_deref(), Some("clear"));
+        assert!(status.rejection_reasons.is_empty());
+    }
+
+    #[test]
+    fn consider_check_is_rejected_with_report_reasons() {
+        let check: Check = fixture_json("check_consider.json");
+        let reports: ReportList = fixture_json("reports_consider.json");
+
+        let status = check.status(&reports.reports);
+
+        assert_eq!(status.status, KycStatus::Rejected);
+        assert!(status.final_rejection);
+        assert_eq!(status.rejection_reasons, vec!["document: rejected".to_string()]);
+    }
+
+    #[test]
+    fn in_progress_check_is_pending() {
+        let check: Check = fixture_json("check_in_progress.json");
+
+        assert_eq!(check.status(&[]).status, KycStatus::Pending);
+    }
+
+    #[test]
+    fn webhook_signature_is_verified() {
+        let onfido = service("http://localhost");
+        let body = fixture("webhook_check_completed.json");
+
+        assert!(onfido.verify_webhook(&signed_headers(body.as_bytes()), body.as_bytes()).is_ok());
+
+        let tampered = body.replace("complete", "withdrawn");
+        let err = onfido.verify_webhook(&signed_headers(body.as_bytes()), tampered.as_bytes()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<KycError>(), Some(KycError::WebhookRejected { .. })));
+
+        let err = onfido.verify_webhook(&HeaderMap::new(), body.as_bytes()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<KycError>(), Some(KycError::WebhookRejected { .. })));
+    }
+
+    #[tokio::test]
+    async fn completed_check_webhook_resolves_applicant() {
+        // Serve the recorded check the webhook refers to
+        let app = Router::new().route(
+            "/checks/:id",
+            get(|Path(id): Path<String>| async move {
+                assert_eq!(id, "8546921-123123-123123");
+                Json(fixture_json::<Value>("check_clear.json"))
+            }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        let onfido = service(&format!("http://{}", address));
+        let payload = onfido.parse_webhook(fixture("webhook_check_completed.json").as_bytes()).await.unwrap();
+
+        assert_eq!(payload.event_type, "check.completed");
+        assert_eq!(payload.applicant_id, "1030303-123123-123123");
+        assert_eq!(payload.status.map(|status| status.status), Some(KycStatus::Approved));
+        assert_eq!(payload.occurred_at.map(|at| at.to_rfc3339()), Some("2019-10-28T15:00:39+00:00".to_string()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Persona integration
+//!
+//! Each verification is a Persona inquiry created from the template configured for its level;
+//! the client resumes it in the embedded flow with a session token. Requests authenticate with the
+//! API key as a bearer token.
+//!
+//! Webhooks carry `Persona-Signature: t=<unix timestamp>,v1=<signature>`, the hex HMAC-SHA256 of
+//! `<timestamp>.<body>` keyed with the webhook secret. During secret rotation several `v1`
+//! signatures are sent, separated by spaces.
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::Hmac;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use super::error::KycError;
+use super::signature::{hmac_matches, required_header};
+use super::types::{CreateApplicantRequest, KycAccessToken, KycApplicant, KycApplicantStatus, KycWebhookPayload};
+use super::{probe_accepted, KycService};
+use crate::config::{KycEnvironment, PersonaConfig};
+use crate::models::kyc::KycProvider;
+use crate::models::user::KycStatus;
+use crate::services::http_client::HttpClient;
+
+/// API version requests are pinned to
+const API_VERSION: &str = "2023-01-05";
+
+/// Persona doesn't report session token lifetimes; clients fetch a new one when it lapses
+const SESSION_TOKEN_TTL_MINUTES: i64 = 60;
+
+/// Persona client implementing [`KycService`]
+pub struct PersonaKycService {
+    config: PersonaConfig,
+    client: HttpClient,
+}
+
+impl PersonaKycService {
+    /// Creates a Persona client, refusing keys that belong to the other environment
+    pub fn new(config: PersonaConfig, environment: KycEnvironment, client: HttpClient) -> Result<Self> {
+        // Persona API keys are prefixed with the environment they were issued for
+        let expected_prefix = match environment {
+            KycEnvironment::Sandbox => "persona_sandbox_",
+            KycEnvironment::Live => "persona_production_",
+        };
+        if !config.api_key.starts_with(expected_prefix) {
+            bail!("PERSONA_API_KEY is not a {} key (expected a '{}' prefix)", environment, expected_prefix);
+        }
+
+        Ok(Self { config, client })
+    }
+
+    /// Sends an authenticated request and deserializes the JSON response
+    async fn send<T: DeserializeOwned>(&self, method: Method, path: &str, body: Option<&Value>) -> Result<T> {
+        let url = Url::parse(&format!("{}{}", self.config.base_url.trim_end_matches('/'), path))
+            .context("Invalid Persona URL")?;
+
+        let mut request = self.client
+            .request(method, url)
+            .header("Accept", "application/json")
+            .header("Persona-Version", API_VERSION)
+            .bearer_auth(&self.config.api_key);
+
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = self.client
+            .send(request)
+            .await
+            .map_err(|source| KycError::Transport { provider: KycProvider::Persona, source })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(KycError::Provider {
+                provider: KycProvider::Persona,
+                status: status.as_u16(),
+                message,
+            }.into());
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|source| KycError::Transport { provider: KycProvider::Persona, source: source.into() }.into())
+    }
+}
+
+/// JSON:API document wrapping an inquiry
+#[derive(Debug, Deserialize)]
+struct InquiryDocument {
+    data: Inquiry,
+}
+
+#[derive(Debug, Deserialize)]
+struct Inquiry {
+    id: String,
+    attributes: InquiryAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+struct InquiryAttributes {
+    /// `created`, `pending`, `completed`, `needs_review`, `approved`, `declined`, `failed` or `expired`
+    status: String,
+}
+
+/// Response of `POST /inquiries/{id}/resume`
+#[derive(Debug, Deserialize)]
+struct ResumeResponse {
+    meta: ResumeMeta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResumeMeta {
+    #[serde(rename = "session-token")]
+    session_token: String,
+}
+
+/// Webhook body: an event wrapping the inquiry it is about
+#[derive(Debug, Deserialize)]
+struct WebhookBody {
+    data: WebhookEvent,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookEvent {
+    attributes: WebhookEventAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookEventAttributes {
+    /// e.g. `inquiry.approved`
+    name: String,
+    payload: InquiryDocument,
+    #[serde(rename = "created-at")]
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl InquiryAttributes {
+    /// Maps the inquiry status onto our KYC status. Declined and failed inquiries are final;
+    /// expired ones were abandoned and stay pending until the user starts again.
+    fn into_status(self) -> KycApplicantStatus {
+        let status = match self.status.as_str() {
+            "approved" => KycStatus::Approved,
+            "declined" | "failed" => KycStatus::Rejected,
+            _ => KycStatus::Pending,
+        };
+        let rejected = status == KycStatus::Rejected;
+
+        KycApplicantStatus {
+            status,
+            rejection_reasons: if rejected { vec![self.status.clone()] } else { Vec::new() },
+            review_answer: Some(self.status),
+            final_rejection: rejected,
+        }
+    }
+}
+
+#[async_trait]
+impl KycService for PersonaKycService {
+    fn provider(&self) -> KycProvider {
+        KycProvider::Persona
+    }
+
+    async fn create_applicant(&self, request: &CreateApplicantRequest) -> Result<KycApplicant> {
+        let mut fields = json!({});
+        if let Some(email) = &request.email {
+            fields["email-address"] = json!(email);
+        }
+
+        let body = json!({
+            "data": {
+                "attributes": {
+                    "inquiry-template-id": self.config.template_id(request.level),
+                    "reference-id": request.external_user_id,
+                    "fields": fields,
+                }
+            }
+        });
+
+        let inquiry: InquiryDocument = self
+            .send(Method::POST, "/inquiries", Some(&body))
+            .await
+            .context("Failed to create Persona inquiry")?;
+
+        Ok(KycApplicant {
+            provider: KycProvider::Persona,
+            applicant_id: inquiry.data.id,
+            external_user_id: request.external_user_id.clone(),
+            level: request.level,
+        })
+    }
+
+    async fn generate_access_token(&self, applicant: &KycApplicant) -> Result<KycAccessToken> {
+        let resumed: ResumeResponse = self
+            .send(Method::POST, &format!("/inquiries/{}/resume", applicant.applicant_id), None)
+            .await
+            .context("Failed to resume Persona inquiry")?;
+
+        Ok(KycAccessToken {
+            token: resumed.meta.session_token,
+            expires_at: Utc::now() + ChronoDuration::minutes(SESSION_TOKEN_TTL_MINUTES),
+        })
+    }
+
+    async fn get_applicant_status(&self, applicant_id: &str) -> Result<KycApplicantStatus> {
+        let inquiry: InquiryDocument = self
+            .send(Method::GET, &format!("/inquiries/{}", applicant_id), None)
+            .await
+            .context("Failed to fetch Persona inquiry")?;
+
+        Ok(inquiry.data.attributes.into_status())
+    }
+
+    fn verify_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<()> {
+        let rejected = |reason: &str| KycError::WebhookRejected {
+            provider: KycProvider::Persona,
+            reason: reason.to_string(),
+        };
+
+        let secret = self.config.webhook_secret.as_deref()
+            .ok_or_else(|| rejected("PERSONA_WEBHOOK_SECRET is not configured"))?;
+        let header = required_header(headers, KycProvider::Persona, "Persona-Signature")?;
+
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for entry in header.split([',', ' ']) {
+            match entry.split_once('=') {
+                Some(("t", value)) => timestamp = Some(value),
+                Some(("v1", value)) => signatures.push(value),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or_else(|| rejected("signature has no timestamp"))?;
+
+        // Any current signature will do while the secret is being rotated
+        let valid = signatures.iter().any(|signature| {
+            hex::decode(signature)
+                .map(|signature| hmac_matches::<Hmac<Sha256>>(secret.as_bytes(), &[timestamp.as_bytes(), b".", body], &signature))
+                .unwrap_or(false)
+        });
+        if !valid {
+            return Err(rejected("signature mismatch").into());
+        }
+
+        Ok(())
+    }
+
+    async fn parse_webhook(&self, body: &[u8]) -> Result<KycWebhookPayload> {
+        let raw: Value = serde_json::from_slice(body).context("Persona webhook is not valid JSON")?;
+        let webhook: WebhookBody = serde_json::from_value(raw.clone()).context("Unrecognised Persona webhook")?;
+        let event = webhook.data.attributes;
+
+        // Only decisions carry an outcome; progress events just touch the verification
+        let status = match event.name.as_str() {
+            "inquiry.approved" | "inquiry.declined" | "inquiry.failed" => Some(event.payload.data.attributes.into_status()),
+            _ => None,
+        };
+
+        Ok(KycWebhookPayload {
+            provider: KycProvider::Persona,
+            event_type: event.name,
+            applicant_id: event.payload.data.id,
+            status,
+            occurred_at: event.created_at,
+            raw,
+        })
+    }
+
+    async fn check_credentials(&self) -> Result<()> {
+        let probe = self.send::<Value>(Method::GET, "/inquiries?page%5Bsize%5D=1", None).await;
+        probe_accepted(KycProvider::Persona, probe)
+    }
+}
@@ -0,0 +1,117 @@
+//! Provider selection by country and level, with failover on provider outages
+
+use anyhow::Error;
+use metrics::increment_counter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::error::KycError;
+use super::KycService;
+use crate::config::KycRoutingConfig;
+use crate::models::kyc::{KycLevel, KycProvider};
+
+/// Consecutive outages of a provider
+#[derive(Debug, Default)]
+struct ProviderHealth {
+    consecutive_failures: u32,
+    skipped_until: Option<Instant>,
+}
+
+/// Picks the providers to try for a verification and tracks their outages
+#[derive(Clone)]
+pub struct KycRouter {
+    services: HashMap<KycProvider, Arc<dyn KycService>>,
+    default_provider: KycProvider,
+    policy: KycRoutingConfig,
+    health: Arc<Mutex<HashMap<KycProvider, ProviderHealth>>>,
+}
+
+impl KycRouter {
+    /// Creates a router over the given provider clients
+    pub fn new(services: Vec<Arc<dyn KycService>>, default_provider: KycProvider, policy: KycRoutingConfig) -> Self {
+        let services = services
+            .into_iter()
+            .map(|service| (service.provider(), service))
+            .collect();
+
+        Self {
+            services,
+            default_provider,
+            policy,
+            health: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Client for a provider, if it is configured
+    pub fn service(&self, provider: KycProvider) -> Option<Arc<dyn KycService>> {
+        self.services.get(&provider).cloned()
+    }
+
+    /// Configured providers to try, in order, for a verification. Providers cooling down after
+    /// repeated outages go last rather than being dropped, so there is always something to try.
+    pub fn candidates(&self, country: Option<&str>, level: KycLevel) -> Vec<Arc<dyn KycService>> {
+        let route = match self.policy.routes.iter().find(|route| route.matches(country, level)) {
+            Some(route) => route.providers.clone(),
+            None => std::iter::once(self.default_provider).chain(self.policy.fallback.iter().copied()).collect(),
+        };
+        let mut chain: Vec<KycProvider> = Vec::with_capacity(route.len());
+        for provider in route {
+            if !chain.contains(&provider) {
+                chain.push(provider);
+            }
+        }
+
+        let now = Instant::now();
+        let health = self.health.lock().expect("KYC provider health lock poisoned");
+        let cooling_down = |provider: &KycProvider| {
+            health
+                .get(provider)
+                .and_then(|health| health.skipped_until)
+                .is_some_and(|until| until > now)
+        };
+
+        let (available, cooling): (Vec<_>, Vec<_>) = chain
+            .into_iter()
+            .filter_map(|provider| self.service(provider))
+            .partition(|service| !cooling_down(&service.provider()));
+
+        available.into_iter().chain(cooling).collect()
+    }
+
+    /// Records a successful call, clearing the provider's outage count
+    pub fn record_success(&self, provider: KycProvider) {
+        let mut health = self.health.lock().expect("KYC provider health lock poisoned");
+        health.remove(&provider);
+    }
+
+    /// Records a failed call. Returns whether the failure was a provider outage, in which case
+    /// the next provider should be tried.
+    pub fn record_failure(&self, provider: KycProvider, error: &Error) -> bool {
+        let outage = error
+            .chain()
+            .any(|cause| cause.downcast_ref::<KycError>().is_some_and(KycError::is_provider_outage));
+        if !outage {
+            return false;
+        }
+
+        let mut health = self.health.lock().expect("KYC provider health lock poisoned");
+        let entry = health.entry(provider).or_default();
+        entry.consecutive_failures += 1;
+
+        if entry.consecutive_failures >= self.policy.failure_threshold && entry.skipped_until.is_none() {
+            warn!(
+                "KYC provider {} failed {} times in a row; skipping it for {}s",
+                provider, entry.consecutive_failures, self.policy.cooldown_secs
+            );
+            entry.skipped_until = Some(Instant::now() + Duration::from_secs(self.policy.cooldown_secs));
+        } else if entry.skipped_until.is_some_and(|until| until <= Instant::now()) {
+            // Still failing after the cooldown; skip it for another round
+            entry.skipped_until = Some(Instant::now() + Duration::from_secs(self.policy.cooldown_secs));
+        }
+
+        increment_counter!("kyc_provider_outages_total", "provider" => provider.to_string());
+        true
+    }
+}
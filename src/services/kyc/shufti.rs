@@ -0,0 +1,216 @@
+//! Shufti Pro integration
+//!
+//! Each verification is a Shufti Pro request whose reference is our external user id; the client
+//! completes it on Shufti Pro's hosted page. Requests authenticate with basic auth using the client
+//! id and secret key.
+//!
+//! Callbacks carry `Signature`, the hex SHA-256 of the body followed by the hex SHA-256 of the
+//! secret key.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::error::KycError;
+use super::signature::{decode_hex, required_header};
+use super::types::{CreateApplicantRequest, KycAccessToken, KycApplicant, KycApplicantStatus, KycWebhookPayload};
+use super::{probe_accepted, KycService};
+use crate::config::ShuftiConfig;
+use crate::models::kyc::{KycLevel, KycProvider};
+use crate::models::user::KycStatus;
+use crate::services::http_client::HttpClient;
+
+/// Shufti Pro client implementing [`KycService`]
+pub struct ShuftiKycService {
+    config: ShuftiConfig,
+    client: HttpClient,
+}
+
+impl ShuftiKycService {
+    /// Creates a Shufti Pro client. Shufti Pro has no separate sandbox; test requests are made
+    /// with the trial credentials of the account.
+    pub fn new(config: ShuftiConfig, client: HttpClient) -> Result<Self> {
+        Ok(Self { config, client })
+    }
+
+    /// Sends an authenticated request and deserializes the JSON response
+    async fn send<T: DeserializeOwned>(&self, path: &str, body: &Value) -> Result<T> {
+        let url = Url::parse(&format!("{}{}", self.config.base_url.trim_end_matches('/'), path))
+            .context("Invalid Shufti Pro URL")?;
+
+        let request = self.client
+            .request(Method::POST, url)
+            .header("Accept", "application/json")
+            .basic_auth(&self.config.client_id, Some(&self.config.secret_key))
+            .json(body);
+
+        let response = self.client
+            .send(request)
+            .await
+            .map_err(|source| KycError::Transport { provider: KycProvider::Shufti, source })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(KycError::Provider {
+                provider: KycProvider::Shufti,
+                status: status.as_u16(),
+                message,
+            }.into());
+        }
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|source| KycError::Transport { provider: KycProvider::Shufti, source: source.into() }.into())
+    }
+
+    /// Current state of a verification request
+    async fn request_status(&self, reference: &str) -> Result<VerificationResponse> {
+        self.send("/status", &json!({ "reference": reference }))
+            .await
+            .context("Failed to fetch Shufti Pro verification status")
+    }
+}
+
+/// Verification request state, as returned on creation, by `/status` and in callbacks
+#[derive(Debug, Deserialize)]
+struct VerificationResponse {
+    reference: String,
+    /// e.g. `request.pending`, `verification.accepted`, `verification.declined`
+    event: String,
+    /// Hosted verification page, while the request is pending
+    verification_url: Option<String>,
+    declined_reason: Option<String>,
+}
+
+impl VerificationResponse {
+    /// Maps the request event onto our KYC status. A declined verification is final; the user
+    /// has to start a new request.
+    fn status(&self) -> KycApplicantStatus {
+        let status = match self.event.as_str() {
+            "verification.accepted" => KycStatus::Approved,
+            "verification.declined" => KycStatus::Rejected,
+            _ => KycStatus::Pending,
+        };
+        let rejected = status == KycStatus::Rejected;
+
+        KycApplicantStatus {
+            status,
+            review_answer: Some(self.event.clone()),
+            rejection_reasons: if rejected { self.declined_reason.iter().cloned().collect() } else { Vec::new() },
+            final_rejection: rejected,
+        }
+    }
+}
+
+#[async_trait]
+impl KycService for ShuftiKycService {
+    fn provider(&self) -> KycProvider {
+        KycProvider::Shufti
+    }
+
+    async fn create_applicant(&self, request: &CreateApplicantRequest) -> Result<KycApplicant> {
+        // Empty values are collected from the user on the hosted page
+        let mut body = json!({
+            "reference": request.external_user_id,
+            "verification_mode": "any",
+            "ttl": self.config.verification_ttl_mins,
+            "document": {
+                "supported_types": ["id_card", "driving_license", "passport"],
+                "name": "",
+                "dob": "",
+            },
+        });
+        if request.level >= KycLevel::Advanced {
+            body["face"] = json!({ "proof": "" });
+        }
+        if request.level >= KycLevel::Full {
+            body["background_checks"] = json!({ "name": "", "dob": "" });
+        }
+        if let Some(callback_url) = &self.config.callback_url {
+            body["callback_url"] = json!(callback_url);
+        }
+        if let Some(email) = &request.email {
+            body["email"] = json!(email);
+        }
+
+        let created: VerificationResponse = self
+            .send("/", &body)
+            .await
+            .context("Failed to create Shufti Pro verification")?;
+
+        Ok(KycApplicant {
+            provider: KycProvider::Shufti,
+            applicant_id: created.reference,
+            external_user_id: request.external_user_id.clone(),
+            level: request.level,
+        })
+    }
+
+    async fn generate_access_token(&self, applicant: &KycApplicant) -> Result<KycAccessToken> {
+        // The hosted page link is the only credential the client needs
+        let status = self.request_status(&applicant.applicant_id).await?;
+        let verification_url = status
+            .verification_url
+            .ok_or_else(|| anyhow!("Shufti Pro verification {} is no longer pending ({})", applicant.applicant_id, status.event))?;
+
+        Ok(KycAccessToken {
+            token: verification_url,
+            expires_at: Utc::now() + ChronoDuration::minutes(i64::from(self.config.verification_ttl_mins)),
+        })
+    }
+
+    async fn get_applicant_status(&self, applicant_id: &str) -> Result<KycApplicantStatus> {
+        Ok(self.request_status(applicant_id).await?.status())
+    }
+
+    fn verify_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<()> {
+        let signature = decode_hex(KycProvider::Shufti, required_header(headers, KycProvider::Shufti, "Signature")?)?;
+
+        let secret_hash = hex::encode(Sha256::digest(self.config.secret_key.as_bytes()));
+        let expected = Sha256::new()
+            .chain_update(body)
+            .chain_update(secret_hash.as_bytes())
+            .finalize();
+
+        ring::constant_time::verify_slices_are_equal(&expected, &signature).map_err(|_| KycError::WebhookRejected {
+            provider: KycProvider::Shufti,
+            reason: "signature mismatch".to_string(),
+        })?;
+
+        Ok(())
+    }
+
+    async fn parse_webhook(&self, body: &[u8]) -> Result<KycWebhookPayload> {
+        let raw: Value = serde_json::from_slice(body).context("Shufti Pro callback is not valid JSON")?;
+        let callback: VerificationResponse = serde_json::from_value(raw.clone()).context("Unrecognised Shufti Pro callback")?;
+
+        let status = match callback.event.as_str() {
+            "verification.accepted" | "verification.declined" => Some(callback.status()),
+            _ => None,
+        };
+
+        Ok(KycWebhookPayload {
+            provider: KycProvider::Shufti,
+            event_type: callback.event,
+            applicant_id: callback.reference,
+            status,
+            // Callbacks carry no event time
+            occurred_at: None,
+            raw,
+        })
+    }
+
+    async fn check_credentials(&self) -> Result<()> {
+        // Shufti Pro rejects the unknown reference only once the credentials are accepted
+        let probe = self.send::<Value>("/status", &json!({ "reference": "lsrwa-self-check" })).await;
+        probe_accepted(KycProvider::Shufti, probe)
+    }
+}
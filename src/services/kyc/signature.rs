@@ -0,0 +1,37 @@
+//! Webhook signature checks shared by the provider integrations
+
+use hmac::digest::KeyInit;
+use hmac::Mac;
+use reqwest::header::HeaderMap;
+
+use super::error::KycError;
+use crate::models::kyc::KycProvider;
+
+/// Whether `expected` is the HMAC of the concatenated `parts`, compared in constant time
+pub(crate) fn hmac_matches<M: Mac + KeyInit>(secret: &[u8], parts: &[&[u8]], expected: &[u8]) -> bool {
+    let mut mac = <M as KeyInit>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+
+    mac.verify_slice(expected).is_ok()
+}
+
+/// Reads a header the provider must send, rejecting the webhook when it is missing
+pub(crate) fn required_header<'h>(headers: &'h HeaderMap, provider: KycProvider, name: &str) -> Result<&'h str, KycError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| KycError::WebhookRejected {
+            provider,
+            reason: format!("missing {} header", name),
+        })
+}
+
+/// Decodes a hex signature, rejecting the webhook when it is malformed
+pub(crate) fn decode_hex(provider: KycProvider, signature: &str) -> Result<Vec<u8>, KycError> {
+    hex::decode(signature.trim()).map_err(|_| KycError::WebhookRejected {
+        provider,
+        reason: "signature is not hex".to_string(),
+    })
+}
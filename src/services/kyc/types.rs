@@ -0,0 +1,70 @@
+//! Provider-neutral KYC request and response types
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::kyc::{KycLevel, KycProvider, KycVerification};
+use crate::models::user::KycStatus;
+
+/// Applicant details sent to the provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApplicantRequest {
+    /// Our identifier for the applicant, echoed back in webhooks
+    pub external_user_id: String,
+    pub level: KycLevel,
+    pub email: Option<String>,
+    /// ISO 3166-1 alpha-3 country code, when known
+    pub country: Option<String>,
+}
+
+/// Applicant registered with a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KycApplicant {
+    pub provider: KycProvider,
+    /// Provider's applicant identifier
+    pub applicant_id: String,
+    pub external_user_id: String,
+    pub level: KycLevel,
+}
+
+/// Token handed to the provider SDK on the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KycAccessToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Review outcome reported by a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KycApplicantStatus {
+    pub status: KycStatus,
+    /// Provider's raw review answer, e.g. SumSub's `GREEN`/`RED`
+    pub review_answer: Option<String>,
+    /// Provider reasons for a rejection
+    pub rejection_reasons: Vec<String>,
+    /// Whether a rejection is final rather than a request to resubmit
+    pub final_rejection: bool,
+}
+
+/// Provider webhook normalised across providers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KycWebhookPayload {
+    pub provider: KycProvider,
+    /// Provider's event name, e.g. SumSub's `applicantReviewed`
+    pub event_type: String,
+    pub applicant_id: String,
+    /// Review outcome, for events that carry one
+    pub status: Option<KycApplicantStatus>,
+    /// When the provider created the event, if the signed body says
+    pub occurred_at: Option<DateTime<Utc>>,
+    /// Body as received
+    pub raw: Value,
+}
+
+/// A started verification and the token the client SDK needs to continue it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KycSession {
+    pub verification: KycVerification,
+    pub access_token: KycAccessToken,
+}
@@ -0,0 +1,265 @@
+//! Leader election between instances
+//!
+//! Every replica serves the API, but the indexer, the schedulers and the other singleton
+//! workers must run on only one of them. Instances compete for a session-level Postgres
+//! advisory lock on a dedicated connection; whoever holds it leads. The lock goes with the
+//! connection, so when the leader dies Postgres releases it and a follower takes over on its
+//! next attempt. The lock connection asks the server to probe it with TCP keepalives, so a
+//! leader that vanishes without closing its connection is noticed within about half a minute.
+//!
+//! A leader that can no longer reach its lock connection steps down straight away, before the
+//! server gives the lock to anyone else, and [`run_while_leader`] stops its workers.
+
+use anyhow::{Context, Result};
+use metrics::gauge;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time;
+use tracing::{error, info, warn};
+
+use crate::config::LeaderElectionConfig;
+use crate::services::shutdown::Shutdown;
+
+/// Server-side keepalive settings for the lock connection: probe after 10 idle seconds, every
+/// 5 seconds, giving up after 3 unanswered probes
+const KEEPALIVE_SETTINGS: [&str; 3] = [
+    "SET tcp_keepalives_idle = 10",
+    "SET tcp_keepalives_interval = 5",
+    "SET tcp_keepalives_count = 3",
+];
+
+/// Cloneable view of whether this instance currently leads
+#[derive(Clone)]
+pub struct Leadership {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Leadership {
+    /// Whether this instance leads right now
+    pub fn is_leader(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Waits until this instance leads; `false` if shutdown comes first
+    pub async fn acquired(&self, shutdown: &Shutdown) -> bool {
+        let mut receiver = self.receiver.clone();
+        tokio::select! {
+            biased;
+            _ = shutdown.triggered() => false,
+            result = receiver.wait_for(|leader| *leader) => result.is_ok(),
+        }
+    }
+
+    /// Completes once this instance no longer leads
+    pub async fn lost(&self) {
+        let mut receiver = self.receiver.clone();
+        if receiver.wait_for(|leader| !*leader).await.is_err() {
+            // The election stopped; nobody will tell us we lost, so never claim we did
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Competes for leadership through a Postgres advisory lock
+pub struct LeaderElection {
+    db: PgPool,
+    config: LeaderElectionConfig,
+    state: watch::Sender<bool>,
+}
+
+impl LeaderElection {
+    /// Creates an election; this instance follows until [`LeaderElection::start`] wins the lock.
+    /// With election disabled it leads as soon as it starts.
+    pub fn new(db: PgPool, config: LeaderElectionConfig) -> Self {
+        let (state, _) = watch::channel(false);
+        Self {
+            db,
+            config,
+            state,
+        }
+    }
+
+    /// Handle to watch this instance's leadership
+    pub fn leadership(&self) -> Leadership {
+        Leadership { receiver: self.state.subscribe() }
+    }
+
+    /// Campaigns for the lock, and checks on it while leading, until `release` is triggered.
+    ///
+    /// Trigger `release` only once this instance's singleton workers have stopped, so no other
+    /// instance starts them while they are still finishing.
+    pub async fn start(&self, release: Shutdown) {
+        if !self.config.enabled {
+            info!("Leader election is disabled; this instance runs all singleton work");
+            self.set_leader(true);
+            release.triggered().await;
+            self.set_leader(false);
+            return;
+        }
+
+        info!("Campaigning for leadership under lock '{}'", self.config.lock_name);
+
+        let mut connection: Option<PgConnection> = None;
+        loop {
+            let wait = match connection.as_mut() {
+                Some(lock) => match self.check(lock).await {
+                    Ok(()) => Duration::from_secs(self.config.check_secs),
+                    Err(err) => {
+                        warn!("Stepping down: the leader lock connection failed: {:#}", err);
+                        connection = None;
+                        self.set_leader(false);
+                        Duration::from_secs(self.config.retry_secs)
+                    },
+                },
+                None => match self.try_acquire().await {
+                    Ok(Some(lock)) => {
+                        info!("This instance is now the leader");
+                        connection = Some(lock);
+                        self.set_leader(true);
+                        Duration::from_secs(self.config.check_secs)
+                    },
+                    Ok(None) => Duration::from_secs(self.config.retry_secs),
+                    Err(err) => {
+                        error!("Failed to campaign for leadership: {:#}", err);
+                        Duration::from_secs(self.config.retry_secs)
+                    },
+                },
+            };
+
+            tokio::select! {
+                _ = release.triggered() => break,
+                _ = time::sleep(wait) => {},
+            }
+        }
+
+        self.set_leader(false);
+        if let Some(lock) = connection {
+            // Closing the session releases the lock
+            if let Err(err) = lock.close().await {
+                warn!("Failed to close the leader lock connection: {}", err);
+            }
+            info!("Released leadership");
+        }
+    }
+
+    /// Takes the lock on a new connection, or `None` while another instance holds it
+    async fn try_acquire(&self) -> Result<Option<PgConnection>> {
+        // Detached, so the pool never hands the locked session to anyone else
+        let mut lock = self.db.acquire().await.context("Failed to open the leader lock connection")?.detach();
+
+        for setting in KEEPALIVE_SETTINGS {
+            sqlx::query(setting)
+                .execute(&mut lock)
+                .await
+                .context("Failed to configure the leader lock connection")?;
+        }
+
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock(hashtext($1))")
+            .bind(&self.config.lock_name)
+            .fetch_one(&mut lock)
+            .await
+            .context("Failed to take the leader lock")?;
+
+        if acquired {
+            Ok(Some(lock))
+        } else {
+            let _ = lock.close().await;
+            Ok(None)
+        }
+    }
+
+    /// Checks the lock connection still answers, within one check interval
+    async fn check(&self, lock: &mut PgConnection) -> Result<()> {
+        time::timeout(Duration::from_secs(self.config.check_secs), lock.ping())
+            .await
+            .context("Timed out")?
+            .context("Ping failed")
+    }
+
+    fn set_leader(&self, leader: bool) {
+        self.state.send_replace(leader);
+        gauge!("leader", if leader { 1.0 } else { 0.0 });
+    }
+}
+
+/// Runs a singleton worker for as long as this instance leads, until shutdown.
+///
+/// Each time this instance becomes leader, `worker` is started with a [`Shutdown`] that is
+/// triggered when leadership is lost or the process shuts down, and is awaited before the next
+/// term. A worker that fails isn't restarted until the next term.
+pub async fn run_while_leader<F, Fut>(name: &'static str, leadership: Leadership, shutdown: Shutdown, mut worker: F)
+where
+    F: FnMut(Shutdown) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    while leadership.acquired(&shutdown).await {
+        let term = Shutdown::new();
+        let watcher = {
+            let (term, leadership, shutdown) = (term.clone(), leadership.clone(), shutdown.clone());
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = shutdown.triggered() => {},
+                    _ = leadership.lost() => info!("Stopping {}: this instance is no longer the leader", name),
+                    _ = term.triggered() => {},
+                }
+                term.trigger();
+            })
+        };
+
+        info!("Starting {} as leader", name);
+        if let Err(err) = worker(term.clone()).await {
+            error!("{} error: {:#}", name, err);
+        }
+
+        // A worker that returned on its own waits out the rest of the term
+        term.triggered().await;
+        let _ = watcher.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LeaderElectionConfig {
+        LeaderElectionConfig {
+            enabled: true,
+            lock_name: "lsrwa-express:leader-test".to_string(),
+            retry_secs: 1,
+            check_secs: 1,
+        }
+    }
+
+    #[sqlx::test]
+    async fn a_follower_takes_over_once_the_leader_releases(pool: PgPool) {
+        let first = LeaderElection::new(pool.clone(), config());
+        let second = LeaderElection::new(pool, config());
+        let (first_leads, second_leads) = (first.leadership(), second.leadership());
+        let (first_release, second_release) = (Shutdown::new(), Shutdown::new());
+
+        let first_task = tokio::spawn({
+            let release = first_release.clone();
+            async move { first.start(release).await }
+        });
+        assert!(first_leads.acquired(&Shutdown::new()).await);
+
+        let second_task = tokio::spawn({
+            let release = second_release.clone();
+            async move { second.start(release).await }
+        });
+        time::sleep(Duration::from_millis(500)).await;
+        assert!(!second_leads.is_leader());
+
+        first_release.trigger();
+        first_task.await.unwrap();
+        assert!(!first_leads.is_leader());
+
+        let took_over = time::timeout(Duration::from_secs(5), second_leads.acquired(&Shutdown::new())).await;
+        assert_eq!(took_over.ok(), Some(true));
+
+        second_release.trigger();
+        second_task.await.unwrap();
+    }
+}
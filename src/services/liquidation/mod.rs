@@ -0,0 +1,11 @@
+//! Liquidation of under-collateralized borrows for LSRWA Express
+//!
+//! [`LiquidationService`] runs as a scheduled job. Each run values the collateral of every open
+//! borrow at the configured price and compares the ratio against `liquidation_threshold_bps`.
+//! Positions under the threshold are flagged and their borrowers warned through a
+//! `borrow_at_risk` webhook; positions still under it once `liquidation_grace_period_seconds` has
+//! passed are liquidated on-chain. Every step is recorded in the position's liquidation log.
+
+mod service;
+
+pub use service::LiquidationService;
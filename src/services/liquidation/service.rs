@@ -0,0 +1,289 @@
+//! Collateral checks, borrower warnings and on-chain liquidations
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use metrics::{gauge, increment_counter};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::db::{LiquidationRepository, UnitOfWork};
+use crate::models::liquidation::{BorrowPosition, LiquidationActionType, LiquidationStatus};
+use crate::models::webhook::WebhookEventType;
+use crate::services::oracle::PriceFeed;
+use crate::services::risk::RiskParameterService;
+use crate::services::scheduler::ScheduledJob;
+use crate::services::webhooks::WebhookDispatcher;
+use crate::services::ChainClient;
+
+/// Watches borrow collateral ratios and liquidates positions that stay under the threshold
+#[derive(Clone)]
+pub struct LiquidationService {
+    db: PgPool,
+    repository: LiquidationRepository,
+    risk: RiskParameterService,
+    prices: PriceFeed,
+    webhooks: WebhookDispatcher,
+    blockchain: Arc<dyn ChainClient>,
+}
+
+/// Liquidation settings for one run
+struct Thresholds<'a> {
+    collateral_price: &'a str,
+    threshold_bps: i32,
+    grace_secs: i64,
+}
+
+impl LiquidationService {
+    /// Creates a liquidation service
+    pub fn new(
+        db: PgPool,
+        risk: RiskParameterService,
+        prices: PriceFeed,
+        blockchain: Arc<dyn ChainClient>,
+    ) -> Self {
+        Self {
+            repository: LiquidationRepository::new(db.clone()),
+            webhooks: WebhookDispatcher::new(db.clone()),
+            db,
+            risk,
+            prices,
+            blockchain,
+        }
+    }
+
+    /// Checks every open borrow. A position that can't be handled is logged and retried on the
+    /// next run without holding the others up.
+    pub async fn check_positions(&self) -> Result<()> {
+        // A missing or stale price fails the run rather than judging positions on a wrong one
+        let collateral_price = self.prices.collateral_price().await?.to_string();
+        let risk = self.risk.current().await?;
+        let thresholds = Thresholds {
+            collateral_price: &collateral_price,
+            threshold_bps: risk.liquidation_threshold_bps,
+            grace_secs: risk.liquidation_grace_period_seconds.max(0),
+        };
+
+        let positions = self.repository.open_positions(&collateral_price).await?;
+        let at_risk = positions.iter().filter(|p| p.collateral_ratio_bps < thresholds.threshold_bps).count();
+        gauge!("borrow_positions_at_risk", at_risk as f64);
+
+        let mut failed = 0;
+        for position in &positions {
+            if let Err(err) = self.check(position, &thresholds).await {
+                error!("Failed to check borrow {}: {:#}", position.request_id, err);
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(anyhow!("{} of {} borrow positions could not be checked", failed, positions.len()));
+        }
+
+        Ok(())
+    }
+
+    /// Moves a position on according to its current collateral ratio
+    async fn check(&self, position: &BorrowPosition, thresholds: &Thresholds<'_>) -> Result<()> {
+        let under_threshold = position.collateral_ratio_bps < thresholds.threshold_bps;
+
+        match (position.liquidation_id, position.liquidation_status) {
+            (Some(id), Some(LiquidationStatus::AtRisk)) if !under_threshold => self.recover(id, position, thresholds).await,
+            (Some(id), Some(LiquidationStatus::AtRisk)) => {
+                self.repository
+                    .refresh(id, thresholds.collateral_price, position.collateral_ratio_bps)
+                    .await?;
+
+                if position.notified_at.is_none() {
+                    self.notify(id, position, thresholds).await?;
+                }
+
+                if position.grace_ends_at.is_some_and(|ends| ends <= Utc::now()) {
+                    self.liquidate(id, position).await?;
+                }
+
+                Ok(())
+            },
+            _ if under_threshold => {
+                if let Some(id) = self.flag(position, thresholds).await? {
+                    self.notify(id, position, thresholds).await?;
+                }
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Flags a position and starts its grace period
+    async fn flag(&self, position: &BorrowPosition, thresholds: &Thresholds<'_>) -> Result<Option<i64>> {
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        let Some(id) = LiquidationRepository::flag_in(
+            uow.conn(),
+            position,
+            thresholds.collateral_price,
+            thresholds.grace_secs,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let details = json!({
+            "collateral_price": thresholds.collateral_price,
+            "threshold_bps": thresholds.threshold_bps,
+            "grace_period_seconds": thresholds.grace_secs,
+        });
+        LiquidationRepository::record_action_in(
+            uow.conn(),
+            id,
+            LiquidationActionType::Flagged,
+            Some(position.collateral_ratio_bps),
+            Some(&details),
+        )
+        .await?;
+
+        uow.commit().await?;
+
+        warn!(
+            "Borrow {} of {} is under the liquidation threshold ({} < {} bps)",
+            position.request_id, position.wallet_address, position.collateral_ratio_bps, thresholds.threshold_bps
+        );
+        increment_counter!("liquidation_actions_total", "action" => "flagged");
+
+        Ok(Some(id))
+    }
+
+    /// Warns the borrower that the position will be liquidated unless collateral is restored
+    async fn notify(&self, id: i64, position: &BorrowPosition, thresholds: &Thresholds<'_>) -> Result<()> {
+        let data = json!({
+            "request_id": position.request_id.to_string(),
+            "wallet_address": position.wallet_address,
+            "user_id": position.user_id,
+            "borrow_amount": position.borrow_amount,
+            "collateral_amount": position.collateral_amount,
+            "collateral_ratio_bps": position.collateral_ratio_bps,
+            "threshold_bps": thresholds.threshold_bps,
+            "grace_period_seconds": thresholds.grace_secs,
+        });
+        let deliveries = self.webhooks.publish(WebhookEventType::BorrowAtRisk, data).await?;
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        LiquidationRepository::mark_notified_in(uow.conn(), id).await?;
+        LiquidationRepository::record_action_in(
+            uow.conn(),
+            id,
+            LiquidationActionType::Notified,
+            Some(position.collateral_ratio_bps),
+            Some(&json!({ "webhook_deliveries": deliveries })),
+        )
+        .await?;
+        uow.commit().await?;
+
+        increment_counter!("liquidation_actions_total", "action" => "notified");
+
+        Ok(())
+    }
+
+    /// Clears the flag of a position that is back above the threshold
+    async fn recover(&self, id: i64, position: &BorrowPosition, thresholds: &Thresholds<'_>) -> Result<()> {
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        if !LiquidationRepository::recover_in(uow.conn(), id, thresholds.collateral_price, position.collateral_ratio_bps)
+            .await?
+        {
+            return Ok(());
+        }
+        LiquidationRepository::record_action_in(
+            uow.conn(),
+            id,
+            LiquidationActionType::Recovered,
+            Some(position.collateral_ratio_bps),
+            Some(&json!({ "collateral_price": thresholds.collateral_price })),
+        )
+        .await?;
+
+        uow.commit().await?;
+
+        info!("Borrow {} is back above the liquidation threshold", position.request_id);
+        increment_counter!("liquidation_actions_total", "action" => "recovered");
+
+        Ok(())
+    }
+
+    /// Liquidates a position on-chain, recording the attempt either way
+    async fn liquidate(&self, id: i64, position: &BorrowPosition) -> Result<()> {
+        let transaction = match self.blockchain.liquidate_borrow(position.request_id).await {
+            Ok(transaction) => transaction,
+            Err(err) => {
+                let message = format!("{:#}", err);
+
+                let mut uow = UnitOfWork::begin(&self.db).await?;
+                LiquidationRepository::record_failure_in(uow.conn(), id, &message).await?;
+                LiquidationRepository::record_action_in(
+                    uow.conn(),
+                    id,
+                    LiquidationActionType::LiquidationFailed,
+                    Some(position.collateral_ratio_bps),
+                    Some(&json!({ "error": message })),
+                )
+                .await?;
+                uow.commit().await?;
+
+                increment_counter!("liquidation_actions_total", "action" => "liquidation_failed");
+                return Err(err.context(format!("Failed to liquidate borrow {}", position.request_id)));
+            },
+        };
+
+        let details = json!({
+            "transaction_hash": transaction.transaction_hash,
+            "block_number": transaction.block_number,
+        });
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        LiquidationRepository::mark_liquidated_in(uow.conn(), id, &transaction.transaction_hash).await?;
+        LiquidationRepository::record_action_in(
+            uow.conn(),
+            id,
+            LiquidationActionType::Liquidated,
+            Some(position.collateral_ratio_bps),
+            Some(&details),
+        )
+        .await?;
+        uow.commit().await?;
+
+        info!(
+            "Liquidated borrow {} of {} in {}",
+            position.request_id, position.wallet_address, transaction.transaction_hash
+        );
+        increment_counter!("liquidation_actions_total", "action" => "liquidated");
+
+        let data = json!({
+            "request_id": position.request_id.to_string(),
+            "wallet_address": position.wallet_address,
+            "user_id": position.user_id,
+            "borrow_amount": position.borrow_amount,
+            "collateral_ratio_bps": position.collateral_ratio_bps,
+            "transaction_hash": transaction.transaction_hash,
+            "block_number": transaction.block_number,
+        });
+        if let Err(err) = self.webhooks.publish(WebhookEventType::BorrowLiquidated, data).await {
+            warn!("Failed to publish liquidation of borrow {}: {}", position.request_id, err);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ScheduledJob for LiquidationService {
+    fn name(&self) -> &'static str {
+        "liquidation_monitor"
+    }
+
+    async fn run(&self) -> Result<()> {
+        self.check_positions().await
+    }
+}
@@ -0,0 +1,17 @@
+//! Errors returned by liquidity checks
+
+use thiserror::Error;
+
+/// Why withdrawals can't be processed
+#[derive(Error, Debug)]
+pub enum LiquidityError {
+    #[error(
+        "Insufficient liquidity: pending withdrawals of {pending_withdrawals} exceed available liquidity of \
+         {available_liquidity} (shortfall {shortfall})"
+    )]
+    Insufficient {
+        pending_withdrawals: String,
+        available_liquidity: String,
+        shortfall: String,
+    },
+}
@@ -0,0 +1,12 @@
+//! Withdrawal liquidity planning for LSRWA Express
+//!
+//! [`LiquidityPlanningService`] compares the withdrawals waiting to be processed against what the
+//! contract can pay out: its free balance plus the pending deposits that an epoch close processes
+//! first. The epoch close sequence checks it before batch-processing withdrawals and stops with a
+//! [`LiquidityError`] when they aren't covered.
+
+mod error;
+mod planning;
+
+pub use error::LiquidityError;
+pub use planning::LiquidityPlanningService;
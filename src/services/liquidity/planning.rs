@@ -0,0 +1,127 @@
+//! Shortfall reports for pending withdrawals
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use metrics::gauge;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::error::LiquidityError;
+use crate::db::BlockchainRequestRepository;
+use crate::models::alert::{Alert, AlertSeverity};
+use crate::models::liquidity::{LiquidityReport, PendingRequestTotals};
+use crate::services::alerting::Alerter;
+use crate::services::ChainClient;
+
+/// Checks that pending withdrawals can be paid out
+#[derive(Clone)]
+pub struct LiquidityPlanningService {
+    requests: BlockchainRequestRepository,
+    blockchain: Arc<dyn ChainClient>,
+    alerts: Alerter,
+}
+
+impl LiquidityPlanningService {
+    /// Creates a liquidity planning service
+    pub fn new(db: PgPool, blockchain: Arc<dyn ChainClient>, alerts: Alerter) -> Self {
+        Self {
+            requests: BlockchainRequestRepository::new(db),
+            blockchain,
+            alerts,
+        }
+    }
+
+    /// Current pending withdrawals against contract balance and expected deposit inflows. A
+    /// shortfall raises an alert, which clears once withdrawals are covered again.
+    pub async fn report(&self) -> Result<LiquidityReport> {
+        let (totals, contract_balance) =
+            tokio::try_join!(self.requests.pending_totals(), self.blockchain.get_contract_balance())?;
+        let (inflows, withdrawals) = pending_amounts(&totals)?;
+
+        let available = &contract_balance + &inflows;
+        let shortfall = if withdrawals > available {
+            &withdrawals - &available
+        } else {
+            BigDecimal::from(0)
+        };
+        let sufficient = shortfall == BigDecimal::from(0);
+
+        gauge!("liquidity_shortfall", shortfall.to_string().parse::<f64>().unwrap_or(0.0));
+
+        if sufficient {
+            self.alerts.resolve("liquidity_shortfall").await;
+        } else {
+            self.alerts
+                .raise(Alert {
+                    key: "liquidity_shortfall".to_string(),
+                    severity: AlertSeverity::Critical,
+                    title: "Pending withdrawals exceed available liquidity".to_string(),
+                    details: format!(
+                        "{} pending in {} withdrawals against {} available ({} in the contract, {} in \
+                         pending deposits); shortfall {}",
+                        totals.withdrawal_total,
+                        totals.withdrawal_count,
+                        available,
+                        contract_balance,
+                        totals.deposit_total,
+                        shortfall
+                    ),
+                })
+                .await;
+        }
+
+        Ok(LiquidityReport {
+            contract_balance: contract_balance.to_string(),
+            expected_deposit_inflows: totals.deposit_total,
+            pending_deposit_count: totals.deposit_count,
+            available_liquidity: available.to_string(),
+            pending_withdrawals: totals.withdrawal_total,
+            pending_withdrawal_count: totals.withdrawal_count,
+            shortfall: shortfall.to_string(),
+            sufficient,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Contract balance and expected deposit inflows not already promised to pending
+    /// withdrawals, which is what new withdrawals can draw on. Unlike [`report`](Self::report),
+    /// this raises no alerts.
+    pub async fn uncommitted(&self) -> Result<BigDecimal> {
+        let (totals, contract_balance) =
+            tokio::try_join!(self.requests.pending_totals(), self.blockchain.get_contract_balance())?;
+        let (inflows, withdrawals) = pending_amounts(&totals)?;
+
+        let uncommitted = contract_balance + inflows - withdrawals;
+        Ok(if uncommitted > BigDecimal::from(0) { uncommitted } else { BigDecimal::from(0) })
+    }
+
+    /// Fails with [`LiquidityError::Insufficient`] when pending withdrawals aren't covered
+    pub async fn ensure_sufficient(&self) -> Result<LiquidityReport> {
+        let report = self.report().await?;
+
+        if !report.sufficient {
+            warn!(
+                "Withdrawal shortfall of {}: {} pending against {} available",
+                report.shortfall, report.pending_withdrawals, report.available_liquidity
+            );
+            return Err(LiquidityError::Insufficient {
+                pending_withdrawals: report.pending_withdrawals,
+                available_liquidity: report.available_liquidity,
+                shortfall: report.shortfall,
+            }
+            .into());
+        }
+
+        Ok(report)
+    }
+}
+
+/// Pending deposit inflows and pending withdrawals, in tokens
+fn pending_amounts(totals: &PendingRequestTotals) -> Result<(BigDecimal, BigDecimal)> {
+    let inflows = BigDecimal::from_str(&totals.deposit_total).context("Invalid pending deposit total")?;
+    let withdrawals = BigDecimal::from_str(&totals.withdrawal_total).context("Invalid pending withdrawal total")?;
+    Ok((inflows, withdrawals))
+}
@@ -1,7 +1,38 @@
+pub mod accounting;
+pub mod alerting;
+pub mod archival;
+pub mod audit;
 pub mod blockchain_service;
+pub mod cache;
+pub mod chain_metadata;
+pub mod changes;
+pub mod corrections;
+pub mod epochs;
+pub mod event_bus;
+pub mod http_client;
 pub mod indexer;
+pub mod interest;
+pub mod keystore;
+pub mod kyc;
+pub mod leader;
+pub mod liquidation;
+pub mod liquidity;
+pub mod notifications;
+pub mod oracle;
+pub mod receipts;
+pub mod rewards;
+pub mod risk;
+pub mod rpc_cache;
+pub mod scheduler;
+pub mod screening;
+pub mod secrets;
+pub mod self_check;
+pub mod shutdown;
+pub mod storage;
+pub mod treasury;
+pub mod webhooks;
 
-pub use blockchain_service::BlockchainService;
+pub use blockchain_service::{BatchSubmissionItem, BlockchainService, ChainClient, ContractRequest, ContractState, SubmittedTransaction};
 
 // Remove unused import
 // use crate::db::DbPools; 
\ No newline at end of file
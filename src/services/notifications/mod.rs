@@ -0,0 +1,56 @@
+//! Email notifications for LSRWA Express
+//!
+//! Lifecycle events are rendered into emails and queued in the delivery log, in the same
+//! transaction as the change they report where there is one. Users without an email on record,
+//! or who turned the notification type off, are skipped. A background worker sends queued emails
+//! through the configured [`EmailSender`], retrying with exponential backoff.
+
+mod notifier;
+mod sendgrid;
+mod smtp;
+mod store;
+mod templates;
+mod worker;
+
+pub use notifier::Notifier;
+pub use sendgrid::SendGridSender;
+pub use smtp::SmtpSender;
+pub use store::NotificationStore;
+pub use templates::{Notification, RenderedEmail};
+pub use worker::EmailDeliveryWorker;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::config::{EmailProvider, NotificationConfig};
+
+/// An email ready to be sent
+#[derive(Debug, Clone)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    /// Plain-text body
+    pub body: String,
+}
+
+/// Delivery of emails through an email service
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// Name recorded in logs
+    fn name(&self) -> &'static str;
+
+    /// Sends the email, returning the message id assigned to it when the service reports one
+    async fn send(&self, email: &OutgoingEmail) -> Result<Option<String>>;
+}
+
+/// Creates the sender for the configured provider, or `None` when emails aren't sent
+pub fn sender_from_config(config: &NotificationConfig) -> Result<Option<Arc<dyn EmailSender>>> {
+    let sender: Arc<dyn EmailSender> = match &config.provider {
+        Some(EmailProvider::Smtp(smtp)) => Arc::new(SmtpSender::new(smtp.clone(), &config.from_address)?),
+        Some(EmailProvider::SendGrid(sendgrid)) => Arc::new(SendGridSender::new(sendgrid.clone(), &config.from_address)?),
+        None => return Ok(None),
+    };
+
+    Ok(Some(sender))
+}
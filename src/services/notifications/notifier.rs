@@ -0,0 +1,64 @@
+//! Queues emails for lifecycle events that aren't part of a larger transaction
+
+use anyhow::{Context, Result};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use super::store::NotificationStore;
+use super::templates::Notification;
+use crate::db::UserRepository;
+use crate::models::blockchain_request::RequestType;
+use crate::services::indexer::{EventType, IndexedEvent};
+
+/// Queues notification emails
+#[derive(Clone)]
+pub struct Notifier {
+    db: PgPool,
+}
+
+impl Notifier {
+    /// Creates a new notifier
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Queues a notification for a user, returning whether an email was queued
+    pub async fn notify(&self, user_id: Uuid, notification: Notification) -> Result<bool> {
+        let mut conn = self.db.acquire().await.context("Failed to acquire connection")?;
+        let queued = NotificationStore::queue_in(&mut conn, &[(user_id, notification)]).await? > 0;
+
+        Ok(queued)
+    }
+
+    /// Queues the notification corresponding to an indexed chain event, if any
+    pub async fn notify_indexed_event(&self, event: &IndexedEvent) -> Result<bool> {
+        let (Some(request_id), Some(wallet_address)) = (event.request_id, event.wallet()) else {
+            return Ok(false);
+        };
+        let amount = event.amount.clone().unwrap_or_default();
+
+        let notification = match (event.event_type(), event.request_type.as_ref()) {
+            (EventType::BatchProcessing, Some(RequestType::Deposit)) => Notification::DepositProcessed {
+                request_id,
+                amount,
+                transaction_hash: event.transaction_hash.clone(),
+            },
+            (EventType::BatchProcessing, Some(RequestType::Withdrawal)) => {
+                Notification::WithdrawalExecutable { request_id, amount }
+            },
+            _ => return Ok(false),
+        };
+
+        let Some(user) = UserRepository::new(self.db.clone()).get_by_wallet(&wallet_address).await? else {
+            return Ok(false);
+        };
+
+        let queued = self.notify(user.id, notification).await?;
+        if queued {
+            info!("Queued email for {:?} event {}", event.event_type(), event.id);
+        }
+
+        Ok(queued)
+    }
+}
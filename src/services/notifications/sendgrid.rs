@@ -0,0 +1,84 @@
+//! Email delivery through the SendGrid v3 mail API
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{EmailSender, OutgoingEmail};
+use crate::config::SendGridConfig;
+
+/// SendGrid client implementing [`EmailSender`]
+pub struct SendGridSender {
+    config: SendGridConfig,
+    client: reqwest::Client,
+    from_email: String,
+    from_name: Option<String>,
+}
+
+impl SendGridSender {
+    /// Creates a SendGrid client sending from `from_address`
+    pub fn new(config: SendGridConfig, from_address: &str) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build SendGrid HTTP client")?;
+
+        // `Name <address>` is split into SendGrid's separate fields
+        let (from_name, from_email) = match from_address.rsplit_once('<') {
+            Some((name, email)) => (
+                Some(name.trim().trim_matches('"').to_string()).filter(|name| !name.is_empty()),
+                email.trim_end_matches('>').trim().to_string(),
+            ),
+            None => (None, from_address.trim().to_string()),
+        };
+
+        Ok(Self {
+            config,
+            client,
+            from_email,
+            from_name,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for SendGridSender {
+    fn name(&self) -> &'static str {
+        "sendgrid"
+    }
+
+    async fn send(&self, email: &OutgoingEmail) -> Result<Option<String>> {
+        let mut from = json!({ "email": self.from_email });
+        if let Some(name) = &self.from_name {
+            from["name"] = json!(name);
+        }
+
+        let body = json!({
+            "personalizations": [{ "to": [{ "email": email.to }] }],
+            "from": from,
+            "subject": email.subject,
+            "content": [{ "type": "text/plain", "value": email.body }],
+        });
+
+        let response = self.client
+            .post(format!("{}/v3/mail/send", self.config.base_url.trim_end_matches('/')))
+            .bearer_auth(&self.config.api_key)
+            .json(&body)
+            .send()
+            .await
+            .context("SendGrid request failed")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(anyhow!("SendGrid responded with {}: {}", status, detail));
+        }
+
+        Ok(response
+            .headers()
+            .get("X-Message-Id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string))
+    }
+}
@@ -0,0 +1,272 @@
+//! Email delivery over SMTP
+//!
+//! A minimal client that opens one session per email: EHLO, an optional STARTTLS upgrade,
+//! `AUTH PLAIN`, then a single plain-text message.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_native_tls::{native_tls, TlsConnector};
+use uuid::Uuid;
+
+use super::{EmailSender, OutgoingEmail};
+use crate::config::{SmtpConfig, SmtpSecurity};
+
+/// Longest a whole session may take
+const SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// SMTP client implementing [`EmailSender`]
+pub struct SmtpSender {
+    config: SmtpConfig,
+    /// `From` header, possibly with a display name
+    from_address: String,
+    /// Bare address used as the envelope sender
+    envelope_from: String,
+    /// Domain announced in `EHLO` and used in message ids
+    domain: String,
+    tls: TlsConnector,
+}
+
+impl SmtpSender {
+    /// Creates an SMTP client sending from `from_address`
+    pub fn new(config: SmtpConfig, from_address: &str) -> Result<Self> {
+        let envelope_from = match from_address.rsplit_once('<') {
+            Some((_, address)) => address.trim_end_matches('>').trim().to_string(),
+            None => from_address.trim().to_string(),
+        };
+        let domain = envelope_from
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.to_string())
+            .context("Email sender address has no domain")?;
+
+        let tls = native_tls::TlsConnector::new().context("Failed to create TLS connector for SMTP")?;
+
+        Ok(Self {
+            config,
+            from_address: from_address.to_string(),
+            envelope_from,
+            domain,
+            tls: TlsConnector::from(tls),
+        })
+    }
+
+    /// Runs a full session, returning the message id of the sent email
+    async fn deliver(&self, email: &OutgoingEmail) -> Result<String> {
+        let tcp = TcpStream::connect((self.config.host.as_str(), self.config.port))
+            .await
+            .with_context(|| format!("Failed to connect to SMTP server {}:{}", self.config.host, self.config.port))?;
+
+        let message_id = format!("<{}@{}>", Uuid::new_v4(), self.domain);
+        let message = self.message(email, &message_id);
+
+        match self.config.security {
+            SmtpSecurity::None => {
+                let mut session = SmtpSession::open(tcp).await?;
+                session.ehlo(&self.domain).await?;
+                self.transact(&mut session, email, &message).await?;
+            },
+            SmtpSecurity::Tls => {
+                let stream = self.tls.connect(&self.config.host, tcp).await.context("SMTP TLS handshake failed")?;
+                let mut session = SmtpSession::open(stream).await?;
+                session.ehlo(&self.domain).await?;
+                self.transact(&mut session, email, &message).await?;
+            },
+            SmtpSecurity::StartTls => {
+                let mut session = SmtpSession::open(tcp).await?;
+                let extensions = session.ehlo(&self.domain).await?;
+                if !extensions.iter().any(|extension| extension.eq_ignore_ascii_case("STARTTLS")) {
+                    bail!("SMTP server {} does not offer STARTTLS", self.config.host);
+                }
+                session.command("STARTTLS", &[220]).await?;
+
+                let stream = self.tls
+                    .connect(&self.config.host, session.into_inner())
+                    .await
+                    .context("SMTP STARTTLS handshake failed")?;
+                // The server forgets everything from before the upgrade
+                let mut session = SmtpSession::new(stream);
+                session.ehlo(&self.domain).await?;
+                self.transact(&mut session, email, &message).await?;
+            },
+        }
+
+        Ok(message_id)
+    }
+
+    /// Authenticates and sends the message on an established session
+    async fn transact<S>(&self, session: &mut SmtpSession<S>, email: &OutgoingEmail, message: &str) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            let credentials = BASE64.encode(format!("\0{}\0{}", username, password));
+            session.command(&format!("AUTH PLAIN {}", credentials), &[235]).await?;
+        }
+
+        session.command(&format!("MAIL FROM:<{}>", self.envelope_from), &[250]).await?;
+        session.command(&format!("RCPT TO:<{}>", email.to), &[250, 251]).await?;
+        session.command("DATA", &[354]).await?;
+        session.data(message).await?;
+
+        // The message is accepted at this point, so a failed goodbye doesn't matter
+        let _ = session.command("QUIT", &[221]).await;
+
+        Ok(())
+    }
+
+    /// Formats the headers and body of a plain-text message
+    fn message(&self, email: &OutgoingEmail, message_id: &str) -> String {
+        let body = email.body.replace("\r\n", "\n").replace('\n', "\r\n");
+
+        format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: {}\r\nMIME-Version: 1.0\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}",
+            self.from_address,
+            email.to,
+            encode_header(&email.subject),
+            Utc::now().to_rfc2822(),
+            message_id,
+            body
+        )
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpSender {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn send(&self, email: &OutgoingEmail) -> Result<Option<String>> {
+        // Anything that could end the address early would let the recipient inject commands
+        if email.to.contains(['\r', '\n', '<', '>']) {
+            bail!("Invalid recipient address '{}'", email.to.escape_debug());
+        }
+
+        let message_id = timeout(SESSION_TIMEOUT, self.deliver(email))
+            .await
+            .map_err(|_| anyhow!("SMTP session with {} timed out", self.config.host))??;
+
+        Ok(Some(message_id))
+    }
+}
+
+/// Encodes a header value as an RFC 2047 encoded word unless it is plain ASCII
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() && !value.contains(['\r', '\n']) {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(value))
+    }
+}
+
+/// Command/reply exchange with an SMTP server
+struct SmtpSession<S> {
+    stream: BufReader<S>,
+}
+
+impl<S> SmtpSession<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Wraps a new connection and waits for the server's greeting
+    async fn open(stream: S) -> Result<Self> {
+        let mut session = Self::new(stream);
+        let (code, text) = session.reply().await?;
+        if code != 220 {
+            bail!("SMTP server greeted with {} {}", code, text);
+        }
+
+        Ok(session)
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    /// Introduces the client, returning the extensions the server supports
+    async fn ehlo(&mut self, domain: &str) -> Result<Vec<String>> {
+        let reply = self.command(&format!("EHLO {}", domain), &[250]).await?;
+
+        // The first line is the server's greeting, each later one an extension
+        Ok(reply
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Sends a command and checks the reply code, returning the reply text
+    async fn command(&mut self, command: &str, expected: &[u16]) -> Result<String> {
+        self.stream.write_all(format!("{}\r\n", command).as_bytes()).await.context("Failed to write to SMTP server")?;
+        self.stream.flush().await.context("Failed to write to SMTP server")?;
+
+        let (code, text) = self.reply().await?;
+        if !expected.contains(&code) {
+            // Only the verb, so credentials never end up in errors
+            let verb = command.split_whitespace().next().unwrap_or_default();
+            bail!("SMTP server rejected {}: {} {}", verb, code, text);
+        }
+
+        Ok(text)
+    }
+
+    /// Sends the message content, dot-stuffed and terminated, and checks it was accepted
+    async fn data(&mut self, message: &str) -> Result<()> {
+        let mut content = String::with_capacity(message.len() + 8);
+        for line in message.trim_end_matches("\r\n").split("\r\n") {
+            if line.starts_with('.') {
+                content.push('.');
+            }
+            content.push_str(line);
+            content.push_str("\r\n");
+        }
+        content.push_str(".\r\n");
+
+        self.stream.write_all(content.as_bytes()).await.context("Failed to write to SMTP server")?;
+        self.stream.flush().await.context("Failed to write to SMTP server")?;
+
+        let (code, text) = self.reply().await?;
+        if code != 250 {
+            bail!("SMTP server rejected the message: {} {}", code, text);
+        }
+
+        Ok(())
+    }
+
+    /// Reads a possibly multi-line reply, joining the lines' text with newlines
+    async fn reply(&mut self) -> Result<(u16, String)> {
+        let mut lines = Vec::new();
+
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await.context("Failed to read from SMTP server")? == 0 {
+                bail!("SMTP server closed the connection");
+            }
+
+            let line = line.trim_end();
+            let code = line.get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .with_context(|| format!("Malformed SMTP reply '{}'", line))?;
+            lines.push(line.get(4..).unwrap_or_default().to_string());
+
+            // `250-` continues the reply, `250 ` ends it
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok((code, lines.join("\n")));
+            }
+        }
+    }
+}
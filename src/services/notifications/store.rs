@@ -0,0 +1,244 @@
+//! Persistence for notification preferences and the email delivery log
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::templates::Notification;
+use crate::models::notification::{
+    EmailNotification, EmailNotificationFilter, NotificationPreference, NotificationType,
+};
+
+const EMAIL_COLUMNS: &str = "id, user_id, notification_type, recipient, subject, body, dedupe_key, status, \
+     attempts, next_attempt_at, last_error, provider_message_id, sent_at, created_at, updated_at";
+
+/// A user an email can be sent to
+#[derive(sqlx::FromRow)]
+struct Recipient {
+    id: Uuid,
+    wallet_address: String,
+    email: String,
+}
+
+/// Database access for the notification subsystem
+#[derive(Clone)]
+pub struct NotificationStore {
+    db: PgPool,
+}
+
+impl NotificationStore {
+    /// Creates a new notification store
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Renders and queues each notification for its user, skipping users without an email and
+    /// users who turned the notification type off. Notifications already queued for the same
+    /// event are ignored. Returns the number of emails queued.
+    pub async fn queue_in(conn: &mut PgConnection, notifications: &[(Uuid, Notification)]) -> Result<u64> {
+        let mut queued = 0;
+
+        for notification_type in NotificationType::ALL {
+            let batch: Vec<&(Uuid, Notification)> = notifications
+                .iter()
+                .filter(|(_, notification)| notification.notification_type() == notification_type)
+                .collect();
+            if batch.is_empty() {
+                continue;
+            }
+
+            let user_ids: Vec<Uuid> = batch.iter().map(|(user_id, _)| *user_id).collect();
+            let recipients = sqlx::query_as::<_, Recipient>(
+                r#"
+                SELECT u.id, u.wallet_address, u.email
+                FROM lsrwa_express.users u
+                LEFT JOIN lsrwa_express.notification_preferences p
+                    ON p.user_id = u.id AND p.notification_type = $2
+                WHERE u.id = ANY($1) AND NULLIF(u.email, '') IS NOT NULL AND COALESCE(p.enabled, TRUE)
+                "#,
+            )
+            .bind(&user_ids)
+            .bind(notification_type.to_string())
+            .fetch_all(&mut *conn)
+            .await
+            .context("Failed to fetch notification recipients")?;
+
+            let mut ids = Vec::new();
+            let mut emails = Vec::new();
+            let mut subjects = Vec::new();
+            let mut bodies = Vec::new();
+            let mut dedupe_keys = Vec::new();
+            for (user_id, notification) in batch {
+                let Some(recipient) = recipients.iter().find(|recipient| recipient.id == *user_id) else {
+                    continue;
+                };
+                let rendered = notification.render(&recipient.wallet_address);
+
+                ids.push(recipient.id);
+                emails.push(recipient.email.clone());
+                subjects.push(rendered.subject);
+                bodies.push(rendered.body);
+                dedupe_keys.push(notification.dedupe_key(recipient.id));
+            }
+            if ids.is_empty() {
+                continue;
+            }
+
+            let result = sqlx::query(
+                r#"
+                INSERT INTO lsrwa_express.email_notifications
+                    (user_id, notification_type, recipient, subject, body, dedupe_key)
+                SELECT user_id, $2, recipient, subject, body, dedupe_key
+                FROM UNNEST($1::uuid[], $3::text[], $4::text[], $5::text[], $6::text[])
+                    AS t(user_id, recipient, subject, body, dedupe_key)
+                ON CONFLICT (notification_type, dedupe_key) DO NOTHING
+                "#,
+            )
+            .bind(&ids)
+            .bind(notification_type.to_string())
+            .bind(&emails)
+            .bind(&subjects)
+            .bind(&bodies)
+            .bind(&dedupe_keys)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to queue email notifications")?;
+
+            queued += result.rows_affected();
+        }
+
+        Ok(queued)
+    }
+
+    /// A user's preference for every notification type
+    pub async fn get_preferences(&self, user_id: Uuid) -> Result<Vec<NotificationPreference>> {
+        let disabled = sqlx::query_as::<_, (String,)>(
+            r#"
+            SELECT notification_type FROM lsrwa_express.notification_preferences
+            WHERE user_id = $1 AND NOT enabled
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to fetch notification preferences")?;
+
+        Ok(NotificationType::ALL
+            .into_iter()
+            .map(|notification_type| NotificationPreference {
+                notification_type,
+                enabled: !disabled.iter().any(|(name,)| name == &notification_type.to_string()),
+            })
+            .collect())
+    }
+
+    /// Stores the given preferences, leaving other notification types unchanged
+    pub async fn set_preferences(&self, user_id: Uuid, preferences: &[NotificationPreference]) -> Result<()> {
+        let types: Vec<String> = preferences.iter().map(|p| p.notification_type.to_string()).collect();
+        let enabled: Vec<bool> = preferences.iter().map(|p| p.enabled).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO lsrwa_express.notification_preferences (user_id, notification_type, enabled)
+            SELECT $1, notification_type, enabled
+            FROM UNNEST($2::text[], $3::boolean[]) AS t(notification_type, enabled)
+            ON CONFLICT (user_id, notification_type)
+            DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(&types)
+        .bind(&enabled)
+        .execute(&self.db)
+        .await
+        .context("Failed to store notification preferences")?;
+
+        Ok(())
+    }
+
+    /// Lists the most recent emails, newest first
+    pub async fn list_emails(&self, filter: &EmailNotificationFilter) -> Result<Vec<EmailNotification>> {
+        sqlx::query_as::<_, EmailNotification>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.email_notifications
+            WHERE ($1::uuid IS NULL OR user_id = $1)
+              AND ($2::text IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            EMAIL_COLUMNS
+        ))
+        .bind(filter.user_id)
+        .bind(&filter.status)
+        .bind(filter.limit.unwrap_or(50))
+        .bind(filter.offset.unwrap_or(0))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list email notifications")
+    }
+
+    /// Claims up to `limit` pending emails that are due, skipping rows locked by other workers
+    pub async fn claim_due_emails(&self, limit: i64) -> Result<Vec<EmailNotification>> {
+        sqlx::query_as::<_, EmailNotification>(&format!(
+            r#"
+            UPDATE lsrwa_express.email_notifications
+            SET next_attempt_at = NOW() + INTERVAL '5 minutes'
+            WHERE id IN (
+                SELECT id FROM lsrwa_express.email_notifications
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            EMAIL_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to claim due email notifications")
+    }
+
+    /// Marks an email as sent
+    pub async fn mark_sent(&self, id: Uuid, provider_message_id: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.email_notifications
+            SET status = 'sent', attempts = attempts + 1, last_error = NULL,
+                provider_message_id = $2, sent_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(provider_message_id)
+        .execute(&self.db)
+        .await
+        .context("Failed to mark email notification as sent")?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt, scheduling a retry or marking the email as failed
+    pub async fn record_failure(&self, id: Uuid, error: &str, next_attempt_at: Option<DateTime<Utc>>) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.email_notifications
+            SET attempts = attempts + 1,
+                last_error = $2,
+                status = CASE WHEN $3::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = COALESCE($3, next_attempt_at)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.db)
+        .await
+        .context("Failed to record email notification failure")?;
+
+        Ok(())
+    }
+}
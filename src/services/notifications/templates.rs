@@ -0,0 +1,123 @@
+//! Email templates for each notification type
+
+use uuid::Uuid;
+
+use crate::models::kyc::KycLevel;
+use crate::models::notification::NotificationType;
+
+/// A lifecycle event a user is emailed about, with what its template needs
+#[derive(Debug, Clone)]
+pub enum Notification {
+    DepositProcessed {
+        request_id: u128,
+        amount: String,
+        transaction_hash: String,
+    },
+    WithdrawalExecutable {
+        request_id: u128,
+        amount: String,
+    },
+    KycApproved {
+        verification_id: Uuid,
+        level: KycLevel,
+    },
+    KycRejected {
+        verification_id: Uuid,
+        level: KycLevel,
+        reasons: Vec<String>,
+    },
+    RewardsAvailable {
+        epoch_id: i32,
+        amount: String,
+    },
+}
+
+/// Subject and plain-text body of an email
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+impl Notification {
+    pub fn notification_type(&self) -> NotificationType {
+        match self {
+            Notification::DepositProcessed { .. } => NotificationType::DepositProcessed,
+            Notification::WithdrawalExecutable { .. } => NotificationType::WithdrawalExecutable,
+            Notification::KycApproved { .. } => NotificationType::KycApproved,
+            Notification::KycRejected { .. } => NotificationType::KycRejected,
+            Notification::RewardsAvailable { .. } => NotificationType::RewardsAvailable,
+        }
+    }
+
+    /// Identifies the event within its notification type, so it is emailed at most once
+    pub fn dedupe_key(&self, user_id: Uuid) -> String {
+        match self {
+            Notification::DepositProcessed { request_id, .. }
+            | Notification::WithdrawalExecutable { request_id, .. } => request_id.to_string(),
+            Notification::KycApproved { verification_id, .. }
+            | Notification::KycRejected { verification_id, .. } => verification_id.to_string(),
+            Notification::RewardsAvailable { epoch_id, .. } => format!("{}:{}", epoch_id, user_id),
+        }
+    }
+
+    /// Renders the email sent to the owner of `wallet_address`
+    pub fn render(&self, wallet_address: &str) -> RenderedEmail {
+        let (subject, message) = match self {
+            Notification::DepositProcessed { request_id, amount, transaction_hash } => (
+                format!("Your deposit of {} USDC has been processed", amount),
+                format!(
+                    "Your deposit request #{} for {} USDC has been processed and now counts towards \
+                     your active balance.\n\nTransaction: {}",
+                    request_id, amount, transaction_hash
+                ),
+            ),
+            Notification::WithdrawalExecutable { request_id, amount } => (
+                format!("Your withdrawal of {} USDC is ready", amount),
+                format!(
+                    "Your withdrawal request #{} for {} USDC has been approved. You can now execute \
+                     it to receive the funds in your wallet.",
+                    request_id, amount
+                ),
+            ),
+            Notification::KycApproved { level, .. } => (
+                "Your identity verification was approved".to_string(),
+                format!(
+                    "Your {} identity verification was approved. You can now deposit, withdraw and \
+                     borrow up to the limits of your verification level.",
+                    level
+                ),
+            ),
+            Notification::KycRejected { level, reasons, .. } => {
+                let mut message = format!("Your {} identity verification could not be approved.", level);
+                if !reasons.is_empty() {
+                    message.push_str("\n\nReasons given by the reviewer:\n");
+                    for reason in reasons {
+                        message.push_str(&format!("  - {}\n", reason));
+                    }
+                }
+                message.push_str("\nYou can start a new verification from your account at any time.");
+
+                ("Your identity verification was not approved".to_string(), message)
+            },
+            Notification::RewardsAvailable { epoch_id, amount } => (
+                format!("Your rewards for epoch {} are available", epoch_id),
+                format!(
+                    "Epoch {} has closed and you earned {} USDC in rewards. They are now available \
+                     to claim.",
+                    epoch_id, amount
+                ),
+            ),
+        };
+
+        RenderedEmail {
+            subject,
+            body: format!(
+                "Hello,\n\n{}\n\nWallet: {}\n\n-- \nLSRWA Express\n\
+                 You can turn these emails off in your notification preferences.\n",
+                message.trim_end(),
+                wallet_address
+            ),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Background worker sending queued emails
+
+use anyhow::Result;
+use chrono::Utc;
+use metrics::increment_counter;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info, warn};
+
+use super::store::NotificationStore;
+use super::{EmailSender, OutgoingEmail};
+use crate::config::NotificationConfig;
+use crate::models::notification::EmailNotification;
+use crate::services::shutdown::Shutdown;
+
+/// Maximum backoff between send attempts
+const MAX_BACKOFF_SECS: u64 = 6 * 60 * 60;
+
+/// Worker that sends pending emails and schedules retries
+pub struct EmailDeliveryWorker {
+    /// Notification persistence
+    store: NotificationStore,
+    /// Email service the emails are sent through
+    sender: Arc<dyn EmailSender>,
+    /// Maximum number of send attempts before giving up
+    max_attempts: u32,
+    /// Base retry delay in seconds, doubled on each attempt
+    base_retry_delay: u64,
+    /// Polling interval in seconds
+    polling_interval: u64,
+    /// Maximum number of emails sent per poll
+    batch_size: i64,
+}
+
+impl EmailDeliveryWorker {
+    /// Creates a new email delivery worker
+    pub fn new(db: PgPool, sender: Arc<dyn EmailSender>, config: &NotificationConfig) -> Self {
+        Self {
+            store: NotificationStore::new(db),
+            sender,
+            max_attempts: config.max_attempts,
+            base_retry_delay: config.retry_delay_secs,
+            polling_interval: config.polling_interval_secs,
+            batch_size: config.batch_size,
+        }
+    }
+
+    /// Runs the delivery loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        info!(
+            "Starting email delivery worker using {} with polling interval {} seconds",
+            self.sender.name(),
+            self.polling_interval
+        );
+
+        let mut interval = time::interval(Duration::from_secs(self.polling_interval));
+
+        while shutdown.tick(&mut interval).await {
+            match self.send_due().await {
+                Ok(count) => {
+                    if count > 0 {
+                        info!("Attempted {} email deliveries", count);
+                    }
+                },
+                Err(err) => {
+                    error!("Failed to process email deliveries: {}", err);
+                }
+            }
+        }
+
+        info!("Email delivery worker stopped");
+        Ok(())
+    }
+
+    /// Attempts every email that is currently due
+    async fn send_due(&self) -> Result<usize> {
+        let emails = self.store.claim_due_emails(self.batch_size).await?;
+        let count = emails.len();
+
+        for email in emails {
+            if let Err(err) = self.attempt(&email).await {
+                error!("Failed to record email delivery {}: {}", email.id, err);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Sends a single email and records the outcome
+    async fn attempt(&self, email: &EmailNotification) -> Result<()> {
+        let outgoing = OutgoingEmail {
+            to: email.recipient.clone(),
+            subject: email.subject.clone(),
+            body: email.body.clone(),
+        };
+
+        let error = match self.sender.send(&outgoing).await {
+            Ok(message_id) => {
+                increment_counter!("email_notifications_sent_total", "type" => email.notification_type.clone());
+                return self.store.mark_sent(email.id, message_id.as_deref()).await;
+            },
+            Err(err) => format!("{:#}", err),
+        };
+
+        let attempts = email.attempts as u32 + 1;
+        let next_attempt_at = if attempts >= self.max_attempts {
+            warn!("Email {} failed permanently after {} attempts: {}", email.id, attempts, error);
+            increment_counter!("email_notifications_failed_total", "type" => email.notification_type.clone());
+            None
+        } else {
+            let delay = self.retry_delay(attempts);
+            Some(Utc::now() + chrono::Duration::seconds(delay as i64))
+        };
+
+        self.store.record_failure(email.id, &error, next_attempt_at).await
+    }
+
+    /// Exponential backoff for the given attempt number
+    fn retry_delay(&self, attempts: u32) -> u64 {
+        self.base_retry_delay
+            .saturating_mul(2u64.saturating_pow(attempts.saturating_sub(1)))
+            .min(MAX_BACKOFF_SECS)
+    }
+}
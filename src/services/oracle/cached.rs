@@ -0,0 +1,93 @@
+//! Price caching and staleness checks
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use metrics::{gauge, increment_counter};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::{OracleError, OracleService, PriceQuote};
+
+/// Wraps an oracle, reusing each price for `ttl` and refusing prices observed more than
+/// `max_age_secs` ago. When the oracle fails, the last price is used for as long as it isn't stale.
+pub struct CachedOracle {
+    inner: Arc<dyn OracleService>,
+    ttl: Duration,
+    max_age_secs: u64,
+    quotes: Mutex<HashMap<String, (PriceQuote, Instant)>>,
+}
+
+impl CachedOracle {
+    /// Creates a caching wrapper around `inner`
+    pub fn new(inner: Arc<dyn OracleService>, ttl: Duration, max_age_secs: u64) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_age_secs,
+            quotes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the quote unless it is stale
+    fn fresh(&self, quote: PriceQuote) -> Result<PriceQuote> {
+        let age_secs = (Utc::now() - quote.observed_at).num_seconds();
+        if age_secs > self.max_age_secs as i64 {
+            return Err(OracleError::Stale {
+                asset: quote.asset,
+                age_secs,
+                max_age_secs: self.max_age_secs,
+            }
+            .into());
+        }
+
+        Ok(quote)
+    }
+}
+
+#[async_trait]
+impl OracleService for CachedOracle {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn price(&self, asset: &str) -> Result<PriceQuote> {
+        let cached = self.quotes.lock().expect("oracle cache lock poisoned").get(asset).cloned();
+        if let Some((quote, fetched_at)) = &cached {
+            if fetched_at.elapsed() < self.ttl {
+                return self.fresh(quote.clone());
+            }
+        }
+
+        match self.inner.price(asset).await {
+            Ok(quote) => {
+                gauge!(
+                    "oracle_price",
+                    quote.price.to_string().parse::<f64>().unwrap_or(0.0),
+                    "asset" => asset.to_string()
+                );
+                self.quotes
+                    .lock()
+                    .expect("oracle cache lock poisoned")
+                    .insert(asset.to_string(), (quote.clone(), Instant::now()));
+                self.fresh(quote)
+            },
+            Err(err) => {
+                increment_counter!("oracle_fetch_failures_total", "source" => self.inner.name());
+
+                if err.downcast_ref::<OracleError>().is_some() {
+                    return Err(err);
+                }
+                match cached {
+                    Some((quote, _)) => {
+                        warn!("Failed to fetch the price of {}, using the last one: {:#}", asset, err);
+                        self.fresh(quote)
+                    },
+                    None => Err(OracleError::Unavailable(err).into()),
+                }
+            },
+        }
+    }
+}
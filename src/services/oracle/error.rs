@@ -0,0 +1,20 @@
+//! Errors returned by price oracles
+
+use thiserror::Error;
+
+/// Why a price couldn't be provided
+#[derive(Error, Debug)]
+pub enum OracleError {
+    #[error("No price is available for {0}")]
+    UnknownAsset(String),
+
+    #[error("Price of {asset} is {age_secs} seconds old, beyond the {max_age_secs} second limit")]
+    Stale {
+        asset: String,
+        age_secs: i64,
+        max_age_secs: u64,
+    },
+
+    #[error("Price oracle unavailable: {0:#}")]
+    Unavailable(anyhow::Error),
+}
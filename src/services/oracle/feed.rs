@@ -0,0 +1,74 @@
+//! Prices derived from oracle quotes
+
+use anyhow::{anyhow, Context, Result};
+use sqlx::types::BigDecimal;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+use super::{CachedOracle, FixedPriceOracle, HttpPriceOracle, OnChainOracle, OracleError, OracleService, PriceQuote};
+use crate::config::{OracleConfig, OracleProviderKind};
+use crate::services::ChainClient;
+
+/// Decimal places kept on derived prices, matching the `NUMERIC(36, 18)` amount columns
+const PRICE_SCALE: i64 = 18;
+
+/// Collateral and vault asset prices
+#[derive(Clone)]
+pub struct PriceFeed {
+    oracle: Arc<dyn OracleService>,
+    collateral_asset: String,
+    vault_asset: String,
+}
+
+impl PriceFeed {
+    /// Creates a price feed over an oracle
+    pub fn new(oracle: Arc<dyn OracleService>, collateral_asset: &str, vault_asset: &str) -> Self {
+        Self {
+            oracle,
+            collateral_asset: collateral_asset.to_string(),
+            vault_asset: vault_asset.to_string(),
+        }
+    }
+
+    /// Creates a price feed over the configured provider, with caching and staleness checks
+    pub fn from_config(config: &OracleConfig, blockchain: Arc<dyn ChainClient>) -> Result<Self> {
+        let provider: Arc<dyn OracleService> = match config.provider {
+            OracleProviderKind::Fixed => Arc::new(FixedPriceOracle::new(&config.fixed_prices)?),
+            OracleProviderKind::Http => Arc::new(HttpPriceOracle::new(
+                config.http.clone().context("The HTTP price oracle is not configured")?,
+            )?),
+            OracleProviderKind::OnChain => Arc::new(OnChainOracle::new(config.onchain.clone(), blockchain)),
+        };
+        info!(
+            "Pricing {} collateral and {} deposits with the {} oracle",
+            config.collateral_asset, config.vault_asset, provider.name()
+        );
+
+        let oracle = CachedOracle::new(provider, Duration::from_secs(config.cache_ttl_secs), config.max_age_secs);
+
+        Ok(Self::new(Arc::new(oracle), &config.collateral_asset, &config.vault_asset))
+    }
+
+    /// Symbol of the asset deposited into the vault
+    pub fn vault_asset(&self) -> &str {
+        &self.vault_asset
+    }
+
+    /// USD price of the vault asset
+    pub async fn vault_price_usd(&self) -> Result<PriceQuote> {
+        self.oracle.price(&self.vault_asset).await
+    }
+
+    /// Price of one unit of collateral in the vault asset
+    pub async fn collateral_price(&self) -> Result<BigDecimal> {
+        let collateral = self.oracle.price(&self.collateral_asset).await?;
+        let vault = self.vault_price_usd().await?;
+
+        if vault.price <= BigDecimal::from(0) {
+            return Err(OracleError::Unavailable(anyhow!("{} has no positive price", self.vault_asset)).into());
+        }
+
+        Ok((&collateral.price / &vault.price).with_scale(PRICE_SCALE))
+    }
+}
@@ -0,0 +1,83 @@
+//! JSON price API
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use reqwest::Url;
+use serde_json::Value;
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::{OracleService, PriceQuote};
+use crate::config::HttpOracleConfig;
+
+/// Price API client implementing [`OracleService`]
+///
+/// Fetches `GET <url>` with the asset symbol substituted and reads the price, and optionally its
+/// timestamp, from the configured JSON pointers. Prices may be JSON numbers or strings.
+pub struct HttpPriceOracle {
+    config: HttpOracleConfig,
+    client: reqwest::Client,
+}
+
+impl HttpPriceOracle {
+    /// Creates a price API client
+    pub fn new(config: HttpOracleConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build price API HTTP client")?;
+
+        Ok(Self { config, client })
+    }
+}
+
+#[async_trait]
+impl OracleService for HttpPriceOracle {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn price(&self, asset: &str) -> Result<PriceQuote> {
+        let url = Url::parse(&self.config.url.replace("{asset}", asset)).context("Invalid price API URL")?;
+
+        let mut request = self.client.get(url).header("Accept", "application/json");
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("X-API-Key", api_key);
+        }
+
+        let body: Value = request
+            .send()
+            .await
+            .context("Price API request failed")?
+            .error_for_status()
+            .context("Price API rejected the request")?
+            .json()
+            .await
+            .context("Unrecognised price API response")?;
+
+        let price = match body.pointer(&self.config.price_pointer) {
+            Some(Value::String(price)) => BigDecimal::from_str(price).ok(),
+            Some(Value::Number(price)) => BigDecimal::from_str(&price.to_string()).ok(),
+            _ => None,
+        }
+        .ok_or_else(|| anyhow!("Price API response has no price at {}", self.config.price_pointer))?;
+
+        let observed_at = match &self.config.timestamp_pointer {
+            Some(pointer) => body
+                .pointer(pointer)
+                .and_then(|timestamp| timestamp.as_i64())
+                .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+                .ok_or_else(|| anyhow!("Price API response has no timestamp at {}", pointer))?,
+            None => Utc::now(),
+        };
+
+        Ok(PriceQuote {
+            asset: asset.to_string(),
+            price,
+            observed_at,
+            source: self.name(),
+        })
+    }
+}
@@ -0,0 +1,48 @@
+//! Asset prices for LSRWA Express
+//!
+//! Prices come from an [`OracleService`]: fixed prices for development ([`FixedPriceOracle`]), a
+//! JSON price API ([`HttpPriceOracle`]) or an oracle pallet on the connected chain
+//! ([`OnChainOracle`]). The configured provider is wrapped in a [`CachedOracle`], which reuses
+//! recent prices and refuses stale ones. [`PriceFeed`] turns USD quotes into the prices the rest of
+//! the service needs: collateral in terms of the vault asset for borrow checks and liquidation,
+//! and the vault asset in USD for TVL.
+
+mod cached;
+mod error;
+mod feed;
+mod fixed;
+mod http;
+mod onchain;
+
+pub use cached::CachedOracle;
+pub use error::OracleError;
+pub use feed::PriceFeed;
+pub use fixed::FixedPriceOracle;
+pub use http::HttpPriceOracle;
+pub use onchain::OnChainOracle;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::types::BigDecimal;
+
+/// USD price of one unit of an asset
+#[derive(Debug, Clone)]
+pub struct PriceQuote {
+    pub asset: String,
+    pub price: BigDecimal,
+    /// When the source observed the price
+    pub observed_at: DateTime<Utc>,
+    /// Provider the price came from
+    pub source: &'static str,
+}
+
+/// Source of asset prices
+#[async_trait]
+pub trait OracleService: Send + Sync {
+    /// Name reported with each quote
+    fn name(&self) -> &'static str;
+
+    /// Current USD price of one unit of `asset`
+    async fn price(&self, asset: &str) -> Result<PriceQuote>;
+}
@@ -0,0 +1,53 @@
+//! Oracle pallet on the connected chain
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::{OracleError, OracleService, PriceQuote};
+use crate::config::OnChainOracleConfig;
+use crate::services::ChainClient;
+
+/// Oracle reading prices fed on-chain, e.g. by ORML's oracle pallet
+pub struct OnChainOracle {
+    config: OnChainOracleConfig,
+    blockchain: Arc<dyn ChainClient>,
+}
+
+impl OnChainOracle {
+    /// Creates an on-chain oracle reader
+    pub fn new(config: OnChainOracleConfig, blockchain: Arc<dyn ChainClient>) -> Self {
+        Self { config, blockchain }
+    }
+}
+
+#[async_trait]
+impl OracleService for OnChainOracle {
+    fn name(&self) -> &'static str {
+        "onchain"
+    }
+
+    async fn price(&self, asset: &str) -> Result<PriceQuote> {
+        let (value, timestamp_ms) = self.blockchain
+            .read_oracle_value(&self.config.pallet, &self.config.storage_entry, asset)
+            .await?
+            .ok_or_else(|| OracleError::UnknownAsset(asset.to_string()))?;
+
+        let price = BigDecimal::from_str(&format!("{}e-{}", value, self.config.decimals))
+            .context("Invalid on-chain oracle value")?;
+        let observed_at = Utc
+            .timestamp_millis_opt(timestamp_ms as i64)
+            .single()
+            .context("Invalid on-chain oracle timestamp")?;
+
+        Ok(PriceQuote {
+            asset: asset.to_string(),
+            price,
+            observed_at,
+            source: self.name(),
+        })
+    }
+}
@@ -0,0 +1,18 @@
+//! Errors returned when issuing a receipt
+
+use thiserror::Error;
+
+use crate::models::request_status::RequestStatus;
+
+/// Why a receipt can't be issued
+#[derive(Error, Debug)]
+pub enum ReceiptError {
+    #[error("Request {0} not found")]
+    NotFound(i64),
+
+    #[error("Request {on_chain_id} is {status}; receipts are issued once a request is executed")]
+    NotExecuted { on_chain_id: i64, status: RequestStatus },
+
+    #[error("Receipts can't be signed: RECEIPT_SIGNING_SEED_PHRASE is not configured")]
+    NoSigningKey,
+}
@@ -0,0 +1,14 @@
+//! Signed receipts of executed requests
+//!
+//! [`ReceiptService`] issues a JSON receipt of an executed request, with its amounts and the
+//! transactions that submitted, batched and settled it, signed with the `receipt` role's sr25519
+//! key. That key signs nothing else, so it can be published and rotated apart from the keys that
+//! hold funds or own the contract. The signature covers the receipt's exact JSON text wrapped as
+//! `<Bytes>…</Bytes>`, the way polkadot.js signs and verifies raw messages, so anyone holding the
+//! published public key can check it without trusting this service.
+
+mod error;
+mod service;
+
+pub use error::ReceiptError;
+pub use service::{verify, ReceiptService};
@@ -0,0 +1,166 @@
+//! Issuing and verifying signed receipts
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use subxt::ext::sp_core::{sr25519, Pair as PairTrait};
+
+use super::error::ReceiptError;
+use crate::db::BlockchainRequestRepository;
+use crate::models::blockchain_request::{BlockchainRequest, TimelineEntry, TimelineStage};
+use crate::models::receipt::{
+    Receipt, ReceiptKeys, ReceiptSigner, ReceiptTransaction, SignedReceipt, RECEIPT_ALGORITHM, RECEIPT_VERSION,
+};
+use crate::models::request_status::RequestStatus;
+use crate::models::wallet::WalletAddress;
+use crate::services::keystore::{self, KeyRole};
+use crate::services::secrets::SecretStore;
+
+/// Issues receipts of executed requests, signed with the receipt signing key
+#[derive(Clone)]
+pub struct ReceiptService {
+    requests: BlockchainRequestRepository,
+    secrets: SecretStore,
+}
+
+impl ReceiptService {
+    /// Creates a receipt service reading requests from `db`
+    pub fn new(db: PgPool, secrets: SecretStore) -> Self {
+        Self { requests: BlockchainRequestRepository::new(db), secrets }
+    }
+
+    /// A signed receipt of an executed request
+    pub async fn issue(&self, on_chain_id: i64) -> Result<SignedReceipt> {
+        let request = self.requests
+            .get_by_on_chain_id(on_chain_id)
+            .await?
+            .ok_or(ReceiptError::NotFound(on_chain_id))?;
+        if request.status != RequestStatus::Executed {
+            return Err(ReceiptError::NotExecuted { on_chain_id, status: request.status }.into());
+        }
+        let timeline = self.requests.timeline(&request).await?;
+
+        let receipt = receipt(&request, &timeline)?;
+        let payload = serde_json::to_string(&receipt).context("Failed to serialize receipt")?;
+        let key = self.key(KeyRole::Receipt.secret_name()).await?.ok_or(ReceiptError::NoSigningKey)?;
+        let signature = key.sign(&wrap(&payload));
+
+        Ok(SignedReceipt {
+            receipt,
+            payload,
+            algorithm: RECEIPT_ALGORITHM.to_string(),
+            signature: format!("0x{}", hex::encode(signature.0)),
+            signer: signer(&key),
+        })
+    }
+
+    /// The keys receipts are verified with
+    pub async fn keys(&self) -> Result<ReceiptKeys> {
+        let active = self.key(KeyRole::Receipt.secret_name()).await?.ok_or(ReceiptError::NoSigningKey)?;
+        let previous = self.key(&KeyRole::Receipt.previous_secret_name()).await?;
+
+        Ok(ReceiptKeys {
+            algorithm: RECEIPT_ALGORITHM.to_string(),
+            active: signer(&active),
+            previous: previous.as_ref().map(signer),
+        })
+    }
+
+    async fn key(&self, name: &str) -> Result<Option<sr25519::Pair>> {
+        self.secrets
+            .get(name)
+            .await?
+            .map(|secret| keystore::keypair(&secret).with_context(|| format!("Invalid key in {}", name)))
+            .transpose()
+    }
+}
+
+/// Checks a receipt's signature: `payload` as issued, `signature` and `public_key` as `0x` hex
+pub fn verify(payload: &str, signature: &str, public_key: &str) -> bool {
+    let decode = |value: &str| hex::decode(value.trim_start_matches("0x")).ok();
+    let (Some(signature), Some(public_key)) = (decode(signature), decode(public_key)) else {
+        return false;
+    };
+    let (Ok(signature), Ok(public_key)) = (<[u8; 64]>::try_from(signature), <[u8; 32]>::try_from(public_key)) else {
+        return false;
+    };
+
+    sr25519::Pair::verify(
+        &sr25519::Signature::from_raw(signature),
+        wrap(payload),
+        &sr25519::Public::from_raw(public_key),
+    )
+}
+
+/// The message signed for a payload, as polkadot.js wraps raw messages
+fn wrap(payload: &str) -> Vec<u8> {
+    format!("<Bytes>{}</Bytes>", payload).into_bytes()
+}
+
+fn signer(key: &sr25519::Pair) -> ReceiptSigner {
+    let public_key = key.public().0;
+
+    ReceiptSigner {
+        public_key: format!("0x{}", hex::encode(public_key)),
+        address: WalletAddress::from_public_key(public_key),
+    }
+}
+
+/// The receipt of a request, from its row and history
+fn receipt(request: &BlockchainRequest, timeline: &[TimelineEntry]) -> Result<Receipt> {
+    let tokens = |amount: &str| -> Result<String> {
+        let amount = BigDecimal::from_str(amount).with_context(|| format!("Invalid amount on request {}", request.id))?;
+        Ok(amount.normalized().to_string())
+    };
+
+    Ok(Receipt {
+        version: RECEIPT_VERSION,
+        request_id: request.on_chain_id,
+        request_type: request.request_type.clone(),
+        wallet_address: request.wallet_address.clone(),
+        amount: tokens(&request.amount)?,
+        collateral_amount: request.collateral_amount.as_deref().map(tokens).transpose()?,
+        asset: request.asset.clone(),
+        status: request.status,
+        submitted_at: request.submission_timestamp,
+        executed_at: timeline
+            .iter()
+            .find(|entry| entry.stage == TimelineStage::Executed)
+            .map(|entry| entry.timestamp),
+        transactions: timeline
+            .iter()
+            .filter_map(|entry| {
+                Some(ReceiptTransaction {
+                    stage: entry.stage,
+                    transaction_hash: entry.transaction_hash.clone()?,
+                    block_number: entry.block_number,
+                    timestamp: entry.timestamp,
+                    epoch_id: entry.epoch_id,
+                })
+            })
+            .collect(),
+        issued_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receipts_verify_only_with_the_signers_key_and_payload() {
+        let key = sr25519::Pair::from_string("//Alice", None).unwrap();
+        let payload = r#"{"request_id":7,"amount":"250"}"#;
+        let signature = format!("0x{}", hex::encode(key.sign(&wrap(payload)).0));
+        let public_key = signer(&key).public_key;
+
+        assert!(verify(payload, &signature, &public_key));
+        assert!(!verify(&payload.replace("250", "2500"), &signature, &public_key));
+
+        let other = signer(&sr25519::Pair::from_string("//Bob", None).unwrap()).public_key;
+        assert!(!verify(payload, &signature, &other));
+        assert!(!verify(payload, "0x1234", &public_key));
+    }
+}
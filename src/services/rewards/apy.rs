@@ -0,0 +1,272 @@
+//! Reward projections for a deposit held over a period
+//!
+//! Projections follow how epoch rewards are paid: a deposit starts earning once the epoch it was
+//! submitted in closes, every epoch pays `apr_bps` pro rata over a 365-day year rounded to 18
+//! decimals, and a final partial epoch pays for the time held in it. With compounding, each
+//! epoch's reward is added to the balance before the next epoch.
+
+use sqlx::types::BigDecimal;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::models::stats::ApyProjection;
+
+/// Seconds in the 365-day year APRs are quoted over
+pub const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+
+/// Longest period a projection may cover
+const MAX_DURATION_SECS: i64 = 10 * SECONDS_PER_YEAR;
+
+/// Most epochs a projection may step through
+const MAX_EPOCHS: i64 = 10_000;
+
+/// Decimal places rewards are rounded to, as in the epoch reward calculation
+const REWARD_SCALE: i64 = 18;
+
+/// Why a projection can't be made
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProjectionError {
+    #[error("Amount must be a positive number, got '{0}'")]
+    InvalidAmount(String),
+
+    #[error("Invalid duration '{0}', expected seconds or a positive number with an s, h, d, w or y suffix")]
+    InvalidDuration(String),
+
+    #[error("Duration can't exceed 10 years")]
+    DurationTooLong,
+
+    #[error("Projection would span more than {MAX_EPOCHS} epochs")]
+    TooManyEpochs,
+
+    #[error("Epoch duration must be positive")]
+    InvalidEpochDuration,
+}
+
+/// What to project
+#[derive(Debug, Clone)]
+pub struct ProjectionInput {
+    pub amount: BigDecimal,
+    pub duration_secs: i64,
+    pub apr_bps: i32,
+    pub epoch_duration_secs: i64,
+    /// Time until the deposit starts earning
+    pub activation_delay_secs: i64,
+    pub compounding: bool,
+}
+
+/// Parses a positive decimal amount
+pub fn parse_amount(value: &str) -> Result<BigDecimal, ProjectionError> {
+    match BigDecimal::from_str(value.trim()) {
+        Ok(amount) if amount > BigDecimal::from(0) => Ok(amount),
+        _ => Err(ProjectionError::InvalidAmount(value.to_string())),
+    }
+}
+
+/// Parses a duration given in seconds, or with an `s`, `h`, `d`, `w` or `y` (365 days) suffix
+pub fn parse_duration(value: &str) -> Result<i64, ProjectionError> {
+    let invalid = || ProjectionError::InvalidDuration(value.to_string());
+
+    let trimmed = value.trim();
+    let (number, unit_secs) = match trimmed.char_indices().last() {
+        Some((index, 's')) => (&trimmed[..index], 1),
+        Some((index, 'h')) => (&trimmed[..index], 60 * 60),
+        Some((index, 'd')) => (&trimmed[..index], 24 * 60 * 60),
+        Some((index, 'w')) => (&trimmed[..index], 7 * 24 * 60 * 60),
+        Some((index, 'y')) => (&trimmed[..index], SECONDS_PER_YEAR),
+        _ => (trimmed, 1),
+    };
+
+    let count = number.parse::<i64>().map_err(|_| invalid())?;
+    if count <= 0 {
+        return Err(invalid());
+    }
+
+    match count.checked_mul(unit_secs) {
+        Some(secs) if secs <= MAX_DURATION_SECS => Ok(secs),
+        _ => Err(ProjectionError::DurationTooLong),
+    }
+}
+
+/// Projects the rewards of holding `input.amount` for `input.duration_secs`
+pub fn project(input: &ProjectionInput) -> Result<ApyProjection, ProjectionError> {
+    if input.epoch_duration_secs <= 0 {
+        return Err(ProjectionError::InvalidEpochDuration);
+    }
+    if input.duration_secs > MAX_DURATION_SECS {
+        return Err(ProjectionError::DurationTooLong);
+    }
+
+    let earning_secs = (input.duration_secs - input.activation_delay_secs.max(0)).max(0);
+    let full_epochs = earning_secs / input.epoch_duration_secs;
+    let partial_epoch_secs = earning_secs % input.epoch_duration_secs;
+    if full_epochs > MAX_EPOCHS {
+        return Err(ProjectionError::TooManyEpochs);
+    }
+
+    let mut balance = input.amount.clone();
+    let mut rewards = BigDecimal::from(0);
+    let epoch_lengths = std::iter::repeat_n(input.epoch_duration_secs, full_epochs as usize)
+        .chain((partial_epoch_secs > 0).then_some(partial_epoch_secs));
+    for held_secs in epoch_lengths {
+        let reward = epoch_reward(&balance, input.apr_bps, held_secs);
+        if input.compounding {
+            balance += &reward;
+        }
+        rewards += reward;
+    }
+
+    Ok(ApyProjection {
+        amount: input.amount.to_string(),
+        duration_seconds: input.duration_secs,
+        apr_bps: input.apr_bps,
+        epoch_duration_seconds: input.epoch_duration_secs,
+        compounding: input.compounding,
+        activation_delay_seconds: input.duration_secs - earning_secs,
+        full_epochs,
+        partial_epoch_seconds: partial_epoch_secs,
+        final_balance: (&input.amount + &rewards).with_scale(REWARD_SCALE).to_string(),
+        projected_rewards: rewards.with_scale(REWARD_SCALE).to_string(),
+        effective_apy_percent: format!("{:.4}", effective_apy(input.apr_bps, input.epoch_duration_secs, input.compounding)),
+    })
+}
+
+/// Reward for holding `balance` for `held_secs` at `apr_bps`, rounded like epoch rewards
+fn epoch_reward(balance: &BigDecimal, apr_bps: i32, held_secs: i64) -> BigDecimal {
+    let numerator = balance * BigDecimal::from(i64::from(apr_bps) * held_secs);
+    (numerator / BigDecimal::from(10_000 * SECONDS_PER_YEAR)).round(REWARD_SCALE)
+}
+
+/// Yield over a year in percent, compounding once per epoch when `compounding` is set
+fn effective_apy(apr_bps: i32, epoch_duration_secs: i64, compounding: bool) -> f64 {
+    let apr = f64::from(apr_bps) / 10_000.0;
+    if !compounding {
+        return apr * 100.0;
+    }
+
+    let epochs_per_year = SECONDS_PER_YEAR as f64 / epoch_duration_secs as f64;
+    ((1.0 + apr / epochs_per_year).powf(epochs_per_year) - 1.0) * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn input(amount: &str, duration_secs: i64, compounding: bool) -> ProjectionInput {
+        ProjectionInput {
+            amount: parse_amount(amount).unwrap(),
+            duration_secs,
+            apr_bps: 500,
+            epoch_duration_secs: 7 * DAY,
+            activation_delay_secs: 0,
+            compounding,
+        }
+    }
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("3600"), Ok(3600));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("2h"), Ok(2 * 60 * 60));
+        assert_eq!(parse_duration(" 30d "), Ok(30 * DAY));
+        assert_eq!(parse_duration("2w"), Ok(14 * DAY));
+        assert_eq!(parse_duration("1y"), Ok(SECONDS_PER_YEAR));
+    }
+
+    #[test]
+    fn rejects_bad_durations_and_amounts() {
+        for value in ["", "d", "0d", "-5", "1.5d", "3m", "1 d"] {
+            assert_eq!(parse_duration(value), Err(ProjectionError::InvalidDuration(value.to_string())), "{}", value);
+        }
+        assert_eq!(parse_duration("11y"), Err(ProjectionError::DurationTooLong));
+        assert_eq!(parse_duration("9223372036854775807y"), Err(ProjectionError::DurationTooLong));
+
+        for value in ["0", "-1", "abc", ""] {
+            assert_eq!(parse_amount(value), Err(ProjectionError::InvalidAmount(value.to_string())), "{}", value);
+        }
+    }
+
+    #[test]
+    fn partial_epoch_rounds_like_epoch_rewards() {
+        // 2000 at 5% APR for 10 of 365 days, as in the epoch reward calculation
+        let mut input = input("2000", 10 * DAY, false);
+        input.epoch_duration_secs = 30 * DAY;
+
+        let projection = project(&input).unwrap();
+        assert_eq!(projection.full_epochs, 0);
+        assert_eq!(projection.partial_epoch_seconds, 10 * DAY);
+        assert_eq!(projection.projected_rewards, "2.739726027397260274");
+        assert_eq!(projection.final_balance, "2002.739726027397260274");
+    }
+
+    #[test]
+    fn rounds_each_epoch_half_up() {
+        // 1 at 1 bps for 3 seconds is 9.5129375951e-12
+        let mut input = input("1", 3, false);
+        input.apr_bps = 1;
+        input.epoch_duration_secs = 3;
+        assert_eq!(project(&input).unwrap().projected_rewards, "0.000000000009512938");
+
+        // Over three 1-second epochs, each 3.1709791984e-12 rounds down
+        input.epoch_duration_secs = 1;
+        let projection = project(&input).unwrap();
+        assert_eq!(projection.full_epochs, 3);
+        assert_eq!(projection.projected_rewards, "0.000000000009512937");
+    }
+
+    #[test]
+    fn simple_rewards_scale_with_whole_epochs() {
+        let projection = project(&input("1000", 52 * 7 * DAY, false)).unwrap();
+        assert_eq!(projection.full_epochs, 52);
+        assert_eq!(projection.partial_epoch_seconds, 0);
+        // 52 weeks of 0.958904109589041096 each
+        assert_eq!(projection.projected_rewards, "49.863013698630136992");
+        assert_eq!(projection.effective_apy_percent, "5.0000");
+    }
+
+    #[test]
+    fn compounding_adds_each_epoch_to_the_balance() {
+        let simple = project(&input("1000", 52 * 7 * DAY, false)).unwrap();
+        let compounded = project(&input("1000", 52 * 7 * DAY, true)).unwrap();
+
+        let simple_rewards = BigDecimal::from_str(&simple.projected_rewards).unwrap();
+        let compounded_rewards = BigDecimal::from_str(&compounded.projected_rewards).unwrap();
+        assert!(compounded_rewards > simple_rewards);
+        assert_eq!(compounded.projected_rewards, "51.101983636809014582");
+        assert_eq!(compounded.effective_apy_percent, "5.1246");
+    }
+
+    #[test]
+    fn nothing_is_earned_before_activation() {
+        let mut input = input("1000", 5 * DAY, false);
+        input.activation_delay_secs = 7 * DAY;
+
+        let projection = project(&input).unwrap();
+        assert_eq!(projection.activation_delay_seconds, 5 * DAY);
+        assert_eq!(projection.full_epochs, 0);
+        assert_eq!(projection.partial_epoch_seconds, 0);
+        assert_eq!(projection.projected_rewards, "0.000000000000000000");
+        assert_eq!(projection.final_balance, "1000.000000000000000000");
+    }
+
+    #[test]
+    fn zero_apr_earns_nothing() {
+        let mut input = input("1000", 30 * DAY, true);
+        input.apr_bps = 0;
+
+        let projection = project(&input).unwrap();
+        assert_eq!(projection.projected_rewards, "0.000000000000000000");
+        assert_eq!(projection.effective_apy_percent, "0.0000");
+    }
+
+    #[test]
+    fn rejects_unbounded_projections() {
+        let mut input = input("1000", SECONDS_PER_YEAR, false);
+        input.epoch_duration_secs = 60;
+        assert_eq!(project(&input).unwrap_err(), ProjectionError::TooManyEpochs);
+
+        input.epoch_duration_secs = 0;
+        assert_eq!(project(&input).unwrap_err(), ProjectionError::InvalidEpochDuration);
+    }
+}
@@ -0,0 +1,117 @@
+//! Time-weighted reward calculation at epoch close
+//!
+//! With the `new_reward_engine` flag off, rewards fall back to the closing-balance rule.
+
+use anyhow::Result;
+use metrics::increment_counter;
+use sqlx::PgPool;
+use uuid::Uuid;
+use tracing::info;
+
+use super::error::RewardError;
+use crate::db::{EpochRepository, FeatureFlagRepository, RewardRepository, SystemParameterRepository, UnitOfWork};
+use crate::models::feature_flag::FeatureFlag;
+use crate::models::reward::{CreateUserRewardRequest, EpochRewardReport};
+use crate::services::event_bus::EventPublisher;
+use crate::services::notifications::{Notification, NotificationStore};
+
+/// Calculates and records the rewards of closed epochs
+#[derive(Clone)]
+pub struct RewardCalculationService {
+    db: PgPool,
+    parameters: SystemParameterRepository,
+    flags: FeatureFlagRepository,
+    events: EventPublisher,
+}
+
+impl RewardCalculationService {
+    /// Creates a reward calculation service
+    pub fn new(db: PgPool, parameters: SystemParameterRepository, flags: FeatureFlagRepository, events: EventPublisher) -> Self {
+        Self { db, parameters, flags, events }
+    }
+
+    /// Calculates the rewards of a closed epoch and writes them with their distribution report,
+    /// all in one transaction. Referrers are paid their bonus on the new rewards, and recipients
+    /// are emailed, in the same transaction. The new rewards are published once committed.
+    ///
+    /// Rewards are calculated once per epoch; later calls return the stored report, so closing
+    /// an epoch can safely be retried.
+    pub async fn calculate_epoch(&self, epoch_id: i32) -> Result<EpochRewardReport> {
+        let apr_bps = self.parameters.reward_apr_bps().await?;
+        let referral_bonus_bps = self.parameters.referral_bonus_bps().await?;
+        let time_weighted = self.flags.is_enabled(FeatureFlag::NewRewardEngine).await?;
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        // Concurrent calculations of the same epoch queue up behind the lock
+        let epoch = EpochRepository::lock_in(uow.conn(), epoch_id)
+            .await?
+            .ok_or(RewardError::EpochNotFound(epoch_id))?;
+
+        if let Some(report) = RewardRepository::get_report_in(uow.conn(), epoch_id).await? {
+            uow.rollback().await?;
+            return Ok(report);
+        }
+
+        let epoch_end = epoch.end_timestamp.ok_or(RewardError::EpochNotClosed(epoch_id))?;
+        if epoch_end <= epoch.start_timestamp {
+            anyhow::bail!("Epoch {} ends before it starts", epoch_id);
+        }
+
+        let distribution = if time_weighted {
+            RewardRepository::calculate_epoch_rewards_in(uow.conn(), epoch.start_timestamp, epoch_end, apr_bps).await?
+        } else {
+            RewardRepository::calculate_closing_balance_rewards_in(uow.conn(), epoch.start_timestamp, epoch_end, apr_bps).await?
+        };
+
+        let rewards: Vec<CreateUserRewardRequest> = distribution
+            .iter()
+            .map(|line| CreateUserRewardRequest {
+                user_id: line.user_id,
+                epoch_id,
+                amount: line.amount.clone(),
+                apr_bps,
+            })
+            .collect();
+        RewardRepository::insert_epoch_rewards_in(uow.conn(), epoch_id, &rewards).await?;
+
+        let referral_rewards = if referral_bonus_bps > 0 {
+            RewardRepository::insert_referral_rewards_in(uow.conn(), epoch_id, apr_bps, referral_bonus_bps).await?
+        } else {
+            0
+        };
+
+        let report = RewardRepository::create_report_in(
+            uow.conn(),
+            epoch_id,
+            apr_bps,
+            epoch.start_timestamp,
+            epoch_end,
+            &distribution,
+        )
+        .await?;
+
+        let notifications: Vec<(Uuid, Notification)> = distribution
+            .iter()
+            .map(|line| (line.user_id, Notification::RewardsAvailable { epoch_id, amount: line.amount.clone() }))
+            .collect();
+        NotificationStore::queue_in(uow.conn(), &notifications).await?;
+
+        uow.commit().await?;
+
+        info!(
+            "Calculated rewards for epoch {}: {} to {} users at {} bps, {} referral rewards at {} bps",
+            epoch_id, report.total_rewards, report.recipient_count, apr_bps, referral_rewards, referral_bonus_bps
+        );
+        increment_counter!("epoch_reward_calculations_total");
+
+        self.events.publish_rewards(epoch_id, apr_bps, &distribution).await;
+
+        Ok(report)
+    }
+
+    /// Distribution report of an epoch whose rewards have been calculated
+    pub async fn report(&self, epoch_id: i32) -> Result<Option<EpochRewardReport>> {
+        RewardRepository::new(self.db.clone()).get_report(epoch_id).await
+    }
+}
@@ -0,0 +1,13 @@
+//! Errors returned by reward calculation
+
+use thiserror::Error;
+
+/// Why an epoch's rewards can't be calculated
+#[derive(Error, Debug)]
+pub enum RewardError {
+    #[error("Epoch {0} not found")]
+    EpochNotFound(i32),
+
+    #[error("Epoch {0} has not closed yet")]
+    EpochNotClosed(i32),
+}
@@ -0,0 +1,16 @@
+//! Epoch reward calculation for LSRWA Express
+//!
+//! When an epoch closes, [`RewardCalculationService`] turns every user's active balance history
+//! over the epoch into a time-weighted balance, applies the configured `reward_apr_bps`, and
+//! writes the resulting `user_rewards` rows together with a distribution report. Users who were
+//! referred earn their referrer a `referral_bonus_bps` share of their reward, paid as a separate
+//! `referral` reward row.
+//!
+//! [`apy`] projects the rewards of a deposit ahead of time from the same rules.
+
+pub mod apy;
+mod calculation;
+mod error;
+
+pub use calculation::RewardCalculationService;
+pub use error::RewardError;
@@ -0,0 +1,13 @@
+//! Errors returned by the risk parameter engine
+
+use thiserror::Error;
+
+/// Why a risk parameter change was refused
+#[derive(Error, Debug)]
+pub enum RiskError {
+    #[error("Invalid risk parameters: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+
+    #[error("Risk parameters are at version {current:?}, not {expected}")]
+    VersionConflict { expected: i64, current: Option<i64> },
+}
@@ -0,0 +1,12 @@
+//! Risk parameter engine
+//!
+//! [`RiskParameterService`] owns the collateral ratio, liquidation threshold and grace period,
+//! minimum request amounts and per-epoch KYC limits. Changes replace the whole set, are checked
+//! against the invariants between the values, and are recorded as a new version. Values the
+//! contract also enforces are pushed on-chain once the change is stored.
+
+mod error;
+mod service;
+
+pub use error::RiskError;
+pub use service::RiskParameterService;
@@ -0,0 +1,136 @@
+//! Validated, versioned changes of the risk parameters
+
+use anyhow::{Context, Result};
+use metrics::increment_counter;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::error::RiskError;
+use crate::db::{RiskParameterRepository, SystemParameterRepository, UnitOfWork};
+use crate::models::risk::{OnchainSyncStatus, RiskParameterVersion, RiskParameters, UpdateRiskParametersRequest};
+use crate::services::ChainClient;
+
+/// Reads and changes the risk parameters
+#[derive(Clone)]
+pub struct RiskParameterService {
+    db: PgPool,
+    repository: RiskParameterRepository,
+    parameters: SystemParameterRepository,
+    blockchain: Arc<dyn ChainClient>,
+}
+
+impl RiskParameterService {
+    /// Creates a risk parameter service
+    pub fn new(db: PgPool, parameters: SystemParameterRepository, blockchain: Arc<dyn ChainClient>) -> Self {
+        Self {
+            repository: RiskParameterRepository::new(db.clone()),
+            db,
+            parameters,
+            blockchain,
+        }
+    }
+
+    /// Current risk parameters, served from the system parameter cache
+    pub async fn current(&self) -> Result<RiskParameters> {
+        Ok(RiskParameters::from(&self.parameters.parameters().await?))
+    }
+
+    /// Recorded versions, newest first
+    pub async fn versions(&self, limit: Option<i64>) -> Result<Vec<RiskParameterVersion>> {
+        self.repository.list(limit).await
+    }
+
+    /// Replaces the risk parameters after validating them, and records the change as a new
+    /// version.
+    ///
+    /// When a value the contract enforces changed, it is pushed on-chain after the change is
+    /// stored. A failed push leaves the version `failed` rather than undoing the change; it can
+    /// be retried with [`sync_onchain`](Self::sync_onchain).
+    pub async fn update(&self, request: &UpdateRiskParametersRequest) -> Result<RiskParameterVersion> {
+        request.parameters.validate().map_err(RiskError::Invalid)?;
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+        RiskParameterRepository::lock_in(uow.conn()).await?;
+
+        let latest = RiskParameterRepository::latest_in(uow.conn()).await?;
+        if let Some(expected) = request.expected_version {
+            let current = latest.as_ref().map(|version| version.version);
+            if current != Some(expected) {
+                uow.rollback().await?;
+                return Err(RiskError::VersionConflict { expected, current }.into());
+            }
+        }
+
+        let changed =
+            RiskParameterRepository::store_values_in(uow.conn(), &request.parameters, request.updated_by).await?;
+        let onchain_status = if changed.iter().any(|name| RiskParameters::ONCHAIN_NAMES.contains(&name.as_str())) {
+            OnchainSyncStatus::Pending
+        } else {
+            OnchainSyncStatus::NotRequired
+        };
+
+        let version = RiskParameterRepository::create_version_in(
+            uow.conn(),
+            &request.parameters,
+            &changed,
+            request.reason.as_deref(),
+            request.updated_by,
+            onchain_status,
+        )
+        .await?;
+
+        uow.commit().await?;
+        self.parameters.invalidate().await;
+
+        info!("Recorded risk parameters version {}, changed: {:?}", version.version, version.changed);
+        increment_counter!("risk_parameter_changes_total");
+
+        if onchain_status == OnchainSyncStatus::Pending {
+            return self.push(version).await;
+        }
+
+        Ok(version)
+    }
+
+    /// Pushes the latest version to the contract if it hasn't been yet. Returns `None` when no
+    /// version has been recorded.
+    pub async fn sync_onchain(&self) -> Result<Option<RiskParameterVersion>> {
+        match self.repository.latest().await? {
+            Some(version) if matches!(version.onchain_status, OnchainSyncStatus::Pending | OnchainSyncStatus::Failed) => {
+                Ok(Some(self.push(version).await?))
+            },
+            latest => Ok(latest),
+        }
+    }
+
+    /// Sends a version's on-chain values to the contract and records the outcome
+    async fn push(&self, version: RiskParameterVersion) -> Result<RiskParameterVersion> {
+        let parameters = &version.parameters.0;
+
+        let result = async {
+            let min_deposit_amount = parameters.min_deposit_amount.parse::<u128>().context("Invalid min_deposit_amount")?;
+            let min_withdrawal_amount =
+                parameters.min_withdrawal_amount.parse::<u128>().context("Invalid min_withdrawal_amount")?;
+            // The contract takes the ratio in whole percent
+            let min_collateral_ratio = (parameters.collateral_ratio_bps / 100) as u128;
+
+            self.blockchain
+                .set_risk_parameters(min_deposit_amount, min_withdrawal_amount, min_collateral_ratio)
+                .await
+        }
+        .await;
+
+        match result {
+            Ok(transaction) => {
+                info!("Pushed risk parameters version {} on-chain in {}", version.version, transaction.transaction_hash);
+                self.repository.record_sync(version.version, Some(&transaction.transaction_hash), None).await
+            },
+            Err(err) => {
+                warn!("Failed to push risk parameters version {} on-chain: {:#}", version.version, err);
+                increment_counter!("risk_parameter_sync_failures_total");
+                self.repository.record_sync(version.version, None, Some(&format!("{:#}", err))).await
+            },
+        }
+    }
+}
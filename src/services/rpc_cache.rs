@@ -0,0 +1,126 @@
+//! Cache of chain reads made at a block
+//!
+//! What a block holds, and the state at its end, never change: a reorganisation produces blocks
+//! with other hashes. Reads are therefore keyed by the hash of the block they were made at, and
+//! entries are never invalidated, only evicted once the cache is full, least recently used
+//! first. Reads of the latest state resolve the latest block's hash first, so repeated reads
+//! within a block share an entry and the next block starts afresh.
+
+use anyhow::Result;
+use lru::LruCache;
+use metrics::increment_counter;
+use std::any::Any;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use subxt::ext::sp_core::H256;
+
+type Entries = LruCache<(H256, String), Arc<dyn Any + Send + Sync>>;
+
+/// LRU cache of chain reads, keyed by block hash and query
+#[derive(Clone)]
+pub struct RpcCache {
+    /// `None` when caching is disabled
+    entries: Option<Arc<Mutex<Entries>>>,
+}
+
+impl RpcCache {
+    /// Creates a cache holding at most `capacity` reads; a capacity of 0 disables it
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity).map(|capacity| Arc::new(Mutex::new(LruCache::new(capacity)))),
+        }
+    }
+
+    /// The result of `query` at `block_hash`, read with `fetch` unless it's cached. Failed reads
+    /// aren't cached.
+    pub async fn get_or_fetch<T, F, Fut>(&self, block_hash: H256, query: &str, fetch: F) -> Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let Some(entries) = &self.entries else {
+            return fetch().await;
+        };
+
+        let key = (block_hash, query.to_string());
+        let cached = entries.lock().unwrap().get(&key).and_then(|entry| entry.downcast_ref::<T>().cloned());
+        if let Some(value) = cached {
+            increment_counter!("rpc_cache_hits_total");
+            return Ok(value);
+        }
+
+        increment_counter!("rpc_cache_misses_total");
+        let value = fetch().await?;
+        entries.lock().unwrap().put(key, Arc::new(value.clone()));
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Reads `value`, counting the reads
+    async fn read(reads: &AtomicUsize, value: u64) -> Result<u64> {
+        reads.fetch_add(1, Ordering::SeqCst);
+        Ok(value)
+    }
+
+    #[tokio::test]
+    async fn reads_are_made_once_per_block_and_query() {
+        let cache = RpcCache::new(16);
+        let reads = AtomicUsize::new(0);
+        let (first, second) = (H256::repeat_byte(1), H256::repeat_byte(2));
+
+        assert_eq!(cache.get_or_fetch(first, "balance", || read(&reads, 1)).await.unwrap(), 1);
+        assert_eq!(cache.get_or_fetch(first, "balance", || read(&reads, 2)).await.unwrap(), 1);
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+        assert_eq!(cache.get_or_fetch(second, "balance", || read(&reads, 3)).await.unwrap(), 3);
+        assert_eq!(cache.get_or_fetch(first, "price", || read(&reads, 4)).await.unwrap(), 4);
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn failed_reads_are_retried() {
+        let cache = RpcCache::new(16);
+        let block = H256::repeat_byte(1);
+
+        assert!(cache.get_or_fetch(block, "balance", || async { Err::<u64, _>(anyhow!("node unavailable")) }).await.is_err());
+        assert_eq!(cache.get_or_fetch(block, "balance", || async { Ok(5u64) }).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_reads_are_evicted() {
+        let cache = RpcCache::new(2);
+        let reads = AtomicUsize::new(0);
+        let block = |byte| H256::repeat_byte(byte);
+
+        cache.get_or_fetch(block(1), "events", || read(&reads, 1)).await.unwrap();
+        cache.get_or_fetch(block(2), "events", || read(&reads, 2)).await.unwrap();
+        cache.get_or_fetch(block(1), "events", || read(&reads, 1)).await.unwrap();
+        cache.get_or_fetch(block(3), "events", || read(&reads, 3)).await.unwrap();
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+
+        // Block 2 was used least recently
+        cache.get_or_fetch(block(1), "events", || read(&reads, 1)).await.unwrap();
+        cache.get_or_fetch(block(2), "events", || read(&reads, 2)).await.unwrap();
+        assert_eq!(reads.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn a_cache_without_capacity_reads_every_time() {
+        let cache = RpcCache::new(0);
+        let reads = AtomicUsize::new(0);
+
+        for _ in 0..3 {
+            cache.get_or_fetch(H256::zero(), "balance", || read(&reads, 1)).await.unwrap();
+        }
+        assert_eq!(reads.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,159 @@
+//! When the active epoch is expected to close
+//!
+//! An epoch closes at the first run of the auto-close job after it has lasted the
+//! `epoch_duration_seconds` system parameter. The job runs every interval, delayed by up to its
+//! jitter, so processing starts within one interval and jitter of the epoch's end. Requests
+//! submitted before the end are sure to be in the epoch; those submitted while processing hasn't
+//! started yet still make it, and from then on submissions are refused until the next epoch opens.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use super::JobStatus;
+use crate::models::epoch::Epoch;
+
+/// When processing of an epoch is expected to start
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct ProcessingWindow {
+    /// Earliest start, the epoch's end or now if it is overdue
+    pub opens_at: DateTime<Utc>,
+    /// Latest start, if the auto-close job runs on schedule
+    pub closes_at: DateTime<Utc>,
+}
+
+/// Schedule of the active epoch, for countdowns
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct EpochSchedule {
+    /// Configured epoch duration; zero when epochs are only closed by hand
+    pub epoch_duration_secs: i64,
+    /// Whether the auto-close job closes epochs once they reach their duration
+    pub auto_close: bool,
+    /// Seconds between two runs of the auto-close job, when it runs
+    pub auto_close_interval_secs: Option<u64>,
+    pub current_epoch_id: Option<i32>,
+    pub epoch_start: Option<DateTime<Utc>>,
+    /// When the epoch reaches its duration
+    pub estimated_epoch_end: Option<DateTime<Utc>>,
+    /// Seconds until the estimated end, zero once it has passed
+    pub seconds_remaining: Option<i64>,
+    pub next_processing_window: Option<ProcessingWindow>,
+    /// Submissions up to this moment are sure to be in the current epoch
+    pub inclusion_cutoff: Option<DateTime<Utc>>,
+    /// Submissions after this moment are sure to miss the current epoch
+    pub latest_inclusion: Option<DateTime<Utc>>,
+    /// Whether submissions are accepted right now; they aren't while an epoch closes
+    pub accepting_submissions: bool,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl EpochSchedule {
+    /// Works out the schedule of the active epoch, if any, from the configured duration and the
+    /// auto-close job's schedule, when that job is registered
+    pub fn compute(
+        epoch: Option<&Epoch>,
+        epoch_duration: Duration,
+        auto_close: Option<&JobStatus>,
+        submissions_paused: bool,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let auto_close = auto_close.filter(|job| job.enabled && epoch_duration > Duration::zero());
+        let estimated_epoch_end = epoch
+            .filter(|_| epoch_duration > Duration::zero())
+            .map(|epoch| epoch.start_timestamp + epoch_duration);
+
+        let next_processing_window = auto_close.zip(estimated_epoch_end).map(|(job, end)| {
+            let opens_at = end.max(now);
+            let latest_delay = Duration::seconds((job.interval_secs + job.jitter_secs) as i64);
+            ProcessingWindow { opens_at, closes_at: opens_at + latest_delay }
+        });
+
+        Self {
+            epoch_duration_secs: epoch_duration.num_seconds(),
+            auto_close: auto_close.is_some(),
+            auto_close_interval_secs: auto_close.map(|job| job.interval_secs),
+            current_epoch_id: epoch.map(|epoch| epoch.id),
+            epoch_start: epoch.map(|epoch| epoch.start_timestamp),
+            estimated_epoch_end,
+            seconds_remaining: estimated_epoch_end.map(|end| (end - now).num_seconds().max(0)),
+            next_processing_window,
+            inclusion_cutoff: next_processing_window.and(estimated_epoch_end),
+            latest_inclusion: next_processing_window.map(|window| window.closes_at),
+            accepting_submissions: !submissions_paused,
+            generated_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::epoch::EpochStatus;
+
+    fn epoch(start: DateTime<Utc>) -> Epoch {
+        Epoch {
+            id: 7,
+            start_timestamp: start,
+            end_timestamp: None,
+            status: EpochStatus::Active,
+            processed_at: None,
+            processing_tx_hash: None,
+            created_at: start,
+            updated_at: start,
+        }
+    }
+
+    fn auto_close(enabled: bool) -> JobStatus {
+        JobStatus {
+            name: "epoch_auto_close",
+            enabled,
+            interval_secs: 60,
+            jitter_secs: 30,
+            running: false,
+            last_started_at: None,
+            last_finished_at: None,
+            last_duration_ms: None,
+            last_status: None,
+            last_error: None,
+            run_count: 0,
+            failure_count: 0,
+            skipped_count: 0,
+        }
+    }
+
+    #[test]
+    fn processing_starts_within_an_interval_and_jitter_of_the_epochs_end() {
+        let now = Utc::now();
+        let active = epoch(now - Duration::hours(1));
+        let end = now + Duration::hours(23);
+
+        let schedule = EpochSchedule::compute(Some(&active), Duration::days(1), Some(&auto_close(true)), false, now);
+        assert_eq!(schedule.estimated_epoch_end, Some(end));
+        assert_eq!(schedule.seconds_remaining, Some(23 * 60 * 60));
+        assert_eq!(
+            schedule.next_processing_window,
+            Some(ProcessingWindow { opens_at: end, closes_at: end + Duration::seconds(90) })
+        );
+        assert_eq!(schedule.inclusion_cutoff, Some(end));
+        assert_eq!(schedule.latest_inclusion, Some(end + Duration::seconds(90)));
+
+        // An overdue epoch closes at the job's next run
+        let overdue = EpochSchedule::compute(Some(&active), Duration::minutes(30), Some(&auto_close(true)), false, now);
+        assert_eq!(overdue.seconds_remaining, Some(0));
+        assert_eq!(overdue.next_processing_window.unwrap().opens_at, now);
+    }
+
+    #[test]
+    fn epochs_closed_by_hand_have_no_processing_window() {
+        let now = Utc::now();
+        let active = epoch(now - Duration::hours(1));
+
+        let disabled = EpochSchedule::compute(Some(&active), Duration::days(1), Some(&auto_close(false)), false, now);
+        assert!(!disabled.auto_close);
+        assert!(disabled.estimated_epoch_end.is_some());
+        assert_eq!((disabled.next_processing_window, disabled.inclusion_cutoff), (None, None));
+
+        let unbounded = EpochSchedule::compute(Some(&active), Duration::zero(), Some(&auto_close(true)), true, now);
+        assert_eq!((unbounded.estimated_epoch_end, unbounded.next_processing_window), (None, None));
+        assert!(!unbounded.accepting_submissions);
+    }
+}
@@ -0,0 +1,241 @@
+//! Recurring background jobs
+//!
+//! Jobs implement [`ScheduledJob`] and are registered with the [`Scheduler`] under a schedule
+//! loaded by [`JobScheduleConfig::from_settings`]. Each enabled job runs on its own interval, delayed
+//! by a random jitter. A job never overlaps with itself: a run that is due while the previous one
+//! is still going, or that is triggered from the admin API, is skipped. The outcome of the last
+//! run is kept for the admin API. On shutdown no new runs start, and [`Scheduler::wait_idle`]
+//! waits for the ones in progress. [`EpochSchedule`] works out from the auto-close job's schedule
+//! when the active epoch is expected to close.
+
+mod epoch_schedule;
+
+pub use epoch_schedule::{EpochSchedule, ProcessingWindow};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use metrics::increment_counter;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::{self, MissedTickBehavior};
+use tracing::{error, info, warn};
+
+use crate::config::JobScheduleConfig;
+use crate::services::audit::AuditContext;
+use crate::services::shutdown::Shutdown;
+
+/// Work run on a schedule
+#[async_trait]
+pub trait ScheduledJob: Send + Sync {
+    /// Stable name, used in configuration variables and metrics
+    fn name(&self) -> &'static str;
+
+    /// Runs the job once
+    async fn run(&self) -> Result<()>;
+}
+
+/// Outcome of a job run
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobRunStatus {
+    Succeeded,
+    Failed,
+}
+
+/// Schedule and last run of a job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub jitter_secs: u64,
+    pub running: bool,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_status: Option<JobRunStatus>,
+    pub last_error: Option<String>,
+    pub run_count: u64,
+    pub failure_count: u64,
+    /// Runs skipped because the previous one was still going
+    pub skipped_count: u64,
+}
+
+/// A registered job with its schedule and run state
+struct Entry {
+    job: Arc<dyn ScheduledJob>,
+    schedule: JobScheduleConfig,
+    state: Mutex<JobStatus>,
+}
+
+/// Registry of recurring jobs
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    entries: Vec<Arc<Entry>>,
+}
+
+impl Scheduler {
+    /// Creates an empty scheduler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a job under the given schedule
+    pub fn register(&mut self, job: Arc<dyn ScheduledJob>, schedule: JobScheduleConfig) {
+        let state = JobStatus {
+            name: job.name(),
+            enabled: schedule.enabled,
+            interval_secs: schedule.interval_secs,
+            jitter_secs: schedule.jitter_secs,
+            running: false,
+            last_started_at: None,
+            last_finished_at: None,
+            last_duration_ms: None,
+            last_status: None,
+            last_error: None,
+            run_count: 0,
+            failure_count: 0,
+            skipped_count: 0,
+        };
+
+        self.entries.push(Arc::new(Entry {
+            job,
+            schedule,
+            state: Mutex::new(state),
+        }));
+    }
+
+    /// Starts a loop for every enabled job, running until shutdown
+    pub fn start(&self, shutdown: Shutdown) {
+        for entry in &self.entries {
+            if !entry.schedule.enabled {
+                info!("Scheduled job {} is disabled", entry.job.name());
+                continue;
+            }
+
+            info!(
+                "Scheduling job {} every {} seconds (jitter up to {} seconds)",
+                entry.job.name(), entry.schedule.interval_secs, entry.schedule.jitter_secs
+            );
+
+            let entry = entry.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                let mut interval = time::interval(Duration::from_secs(entry.schedule.interval_secs));
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                while shutdown.tick(&mut interval).await {
+                    tokio::select! {
+                        _ = shutdown.triggered() => break,
+                        _ = time::sleep(jitter(entry.schedule.jitter_secs)) => {},
+                    }
+
+                    // Runs go in the background so a slow one is skipped over rather than queued
+                    let entry = entry.clone();
+                    tokio::spawn(async move { entry.run_exclusive().await });
+                }
+            });
+        }
+    }
+
+    /// Waits until no job is running
+    pub async fn wait_idle(&self) {
+        while self.entries.iter().any(|entry| entry.status().running) {
+            time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Schedules and last runs of all jobs
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.entries.iter().map(|entry| entry.status()).collect()
+    }
+
+    /// Schedule and last run of a job, if it is registered
+    pub fn status(&self, name: &str) -> Option<JobStatus> {
+        self.entries.iter().find(|entry| entry.job.name() == name).map(|entry| entry.status())
+    }
+
+    /// Runs a job now, in the background. Fails when the job is unknown or already running.
+    pub fn trigger(&self, name: &str) -> Result<JobStatus> {
+        let entry = self.entries
+            .iter()
+            .find(|entry| entry.job.name() == name)
+            .ok_or_else(|| anyhow!("Unknown scheduled job {}", name))?
+            .clone();
+
+        if entry.status().running {
+            return Err(anyhow!("Scheduled job {} is already running", name));
+        }
+
+        // A manual run is attributed to whoever triggered it
+        let status = entry.status();
+        let context = AuditContext::current();
+        tokio::spawn(context.scope(async move { entry.run_exclusive().await }));
+
+        Ok(status)
+    }
+}
+
+impl Entry {
+    fn status(&self) -> JobStatus {
+        self.state.lock().expect("scheduler state lock poisoned").clone()
+    }
+
+    /// Runs the job unless a run is already going, and records the outcome
+    async fn run_exclusive(&self) {
+        let name = self.job.name();
+
+        {
+            let mut state = self.state.lock().expect("scheduler state lock poisoned");
+            if state.running {
+                warn!("Skipping scheduled job {}: the previous run is still going", name);
+                state.skipped_count += 1;
+                increment_counter!("scheduled_job_runs_total", "job" => name, "status" => "skipped");
+                return;
+            }
+            state.running = true;
+            state.last_started_at = Some(Utc::now());
+        }
+
+        let started = Instant::now();
+        let result = self.job.run().await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let mut state = self.state.lock().expect("scheduler state lock poisoned");
+        state.running = false;
+        state.last_finished_at = Some(Utc::now());
+        state.last_duration_ms = Some(duration_ms);
+        state.run_count += 1;
+
+        match result {
+            Ok(()) => {
+                state.last_status = Some(JobRunStatus::Succeeded);
+                state.last_error = None;
+                increment_counter!("scheduled_job_runs_total", "job" => name, "status" => "succeeded");
+            },
+            Err(err) => {
+                let message = format!("{:#}", err);
+                error!("Scheduled job {} failed after {} ms: {}", name, duration_ms, message);
+                state.last_status = Some(JobRunStatus::Failed);
+                state.last_error = Some(message);
+                state.failure_count += 1;
+                increment_counter!("scheduled_job_runs_total", "job" => name, "status" => "failed");
+            },
+        }
+    }
+}
+
+/// Random delay of up to `max_secs`
+fn jitter(max_secs: u64) -> Duration {
+    if max_secs == 0 {
+        return Duration::ZERO;
+    }
+
+    // Randomly keyed hasher, so no RNG dependency is needed for a spread this coarse
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % (max_secs * 1000 + 1))
+}
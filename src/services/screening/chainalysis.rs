@@ -0,0 +1,92 @@
+//! Chainalysis sanctions screening
+//!
+//! Looks addresses up with the free sanctions screening API. Any identification means the
+//! address is on a sanctions list, so it is reported as severe risk. Names are not screened.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Url;
+use serde::Deserialize;
+use std::time::Duration;
+
+use super::{ScreeningOutcome, ScreeningProvider, ScreeningSubject};
+use crate::config::ChainalysisConfig;
+use crate::models::screening::{RiskLevel, ScreeningMatch};
+
+/// Chainalysis client implementing [`ScreeningProvider`]
+pub struct ChainalysisScreeningProvider {
+    config: ChainalysisConfig,
+    client: reqwest::Client,
+}
+
+impl ChainalysisScreeningProvider {
+    /// Creates a Chainalysis client
+    pub fn new(config: ChainalysisConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Chainalysis HTTP client")?;
+
+        Ok(Self { config, client })
+    }
+}
+
+/// Response of `GET /address/{address}`
+#[derive(Debug, Deserialize)]
+struct AddressResponse {
+    #[serde(default)]
+    identifications: Vec<Identification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identification {
+    category: String,
+    name: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+}
+
+#[async_trait]
+impl ScreeningProvider for ChainalysisScreeningProvider {
+    fn name(&self) -> &'static str {
+        "chainalysis"
+    }
+
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ScreeningOutcome> {
+        let url = Url::parse(&format!(
+            "{}/address/{}",
+            self.config.base_url.trim_end_matches('/'),
+            subject.wallet_address
+        ))
+        .context("Invalid Chainalysis URL")?;
+
+        let response: AddressResponse = self.client
+            .get(url)
+            .header("Accept", "application/json")
+            .header("X-API-Key", &self.config.api_key)
+            .send()
+            .await
+            .context("Chainalysis request failed")?
+            .error_for_status()
+            .context("Chainalysis rejected the screening request")?
+            .json()
+            .await
+            .context("Unrecognised Chainalysis response")?;
+
+        let matches: Vec<ScreeningMatch> = response
+            .identifications
+            .into_iter()
+            .map(|identification| ScreeningMatch {
+                category: identification.category,
+                name: identification.name,
+                description: identification.description,
+                url: identification.url,
+            })
+            .collect();
+
+        Ok(ScreeningOutcome {
+            risk_level: if matches.is_empty() { RiskLevel::None } else { RiskLevel::Severe },
+            matches,
+        })
+    }
+}
@@ -0,0 +1,16 @@
+//! Errors returned by screening checks
+
+use thiserror::Error;
+
+/// Why a wallet may not proceed
+#[derive(Error, Debug)]
+pub enum ScreeningError {
+    #[error("Wallet {wallet_address} is blocked: {reason}")]
+    Blocked {
+        wallet_address: String,
+        reason: String,
+    },
+
+    #[error("Sanctions screening is unavailable: {0}")]
+    Unavailable(#[source] anyhow::Error),
+}
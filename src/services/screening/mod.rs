@@ -0,0 +1,60 @@
+//! Sanctions and AML screening for LSRWA Express
+//!
+//! Wallets are screened against a [`ScreeningProvider`] when they register, before their
+//! withdrawals are submitted, and periodically by [`RescreenWorker`]. A flagged wallet is
+//! blocked until an admin clears it, and raises a `screening_flagged` webhook.
+
+mod chainalysis;
+mod error;
+mod service;
+mod worker;
+
+pub use chainalysis::ChainalysisScreeningProvider;
+pub use error::ScreeningError;
+pub use service::ScreeningService;
+pub use worker::RescreenWorker;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::models::screening::{RiskLevel, ScreeningMatch};
+use crate::models::wallet::WalletAddress;
+
+/// Who is being screened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningSubject {
+    pub wallet_address: WalletAddress,
+    /// Full name, for providers that screen people as well as addresses
+    pub name: Option<String>,
+    /// ISO 3166-1 alpha-3 country code
+    pub country: Option<String>,
+}
+
+impl ScreeningSubject {
+    /// Subject identified by its wallet alone
+    pub fn wallet(wallet_address: &WalletAddress) -> Self {
+        Self {
+            wallet_address: wallet_address.clone(),
+            name: None,
+            country: None,
+        }
+    }
+}
+
+/// What a provider found about a subject
+#[derive(Debug, Clone, Default)]
+pub struct ScreeningOutcome {
+    pub risk_level: RiskLevel,
+    pub matches: Vec<ScreeningMatch>,
+}
+
+/// Sanctions/watchlist lookup behind a screening vendor
+#[async_trait]
+pub trait ScreeningProvider: Send + Sync {
+    /// Name recorded with each screening
+    fn name(&self) -> &'static str;
+
+    /// Checks the subject against the provider's lists
+    async fn screen(&self, subject: &ScreeningSubject) -> Result<ScreeningOutcome>;
+}
@@ -0,0 +1,221 @@
+//! Screening checks, wallet blocking and alerts
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use metrics::increment_counter;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::error::ScreeningError;
+use super::{ChainalysisScreeningProvider, ScreeningProvider, ScreeningSubject};
+use crate::config::ScreeningConfig;
+use crate::db::screening_repository::NewScreening;
+use crate::db::{ActivityLogRepository, ScreeningRepository, UnitOfWork, UserRepository};
+use crate::models::activity_log::CreateActivityLogRequest;
+use crate::models::screening::{BlockedWallet, RiskLevel, Screening, ScreeningTrigger};
+use crate::models::wallet::WalletAddress;
+use crate::models::webhook::WebhookEventType;
+use crate::services::webhooks::WebhookDispatcher;
+
+/// Screens wallets, blocks the flagged ones and raises alerts
+#[derive(Clone)]
+pub struct ScreeningService {
+    db: PgPool,
+    repository: ScreeningRepository,
+    provider: Option<Arc<dyn ScreeningProvider>>,
+    webhooks: WebhookDispatcher,
+    block_risk: RiskLevel,
+    max_age: Option<Duration>,
+}
+
+impl ScreeningService {
+    /// Creates a screening service. Without a provider only the block list is enforced.
+    pub fn new(db: PgPool, provider: Option<Arc<dyn ScreeningProvider>>, config: &ScreeningConfig) -> Self {
+        Self {
+            repository: ScreeningRepository::new(db.clone()),
+            webhooks: WebhookDispatcher::new(db.clone()),
+            db,
+            provider,
+            block_risk: config.block_risk,
+            max_age: (config.max_age_secs > 0)
+                .then(|| Duration::seconds(i64::try_from(config.max_age_secs).unwrap_or(i64::MAX))),
+        }
+    }
+
+    /// Creates a screening service with the configured provider
+    pub fn from_config(db: PgPool, config: &ScreeningConfig) -> Result<Self> {
+        let provider = match &config.chainalysis {
+            Some(chainalysis) => {
+                info!("Screening wallets with Chainalysis");
+                Some(Arc::new(ChainalysisScreeningProvider::new(chainalysis.clone())?) as Arc<dyn ScreeningProvider>)
+            },
+            None => {
+                warn!("No screening provider configured; only blocked wallets will be refused");
+                None
+            },
+        };
+
+        Ok(Self::new(db, provider, config))
+    }
+
+    /// Whether a screening provider is configured
+    pub fn has_provider(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Screens the subject and records the result, blocking the wallet when it is flagged.
+    /// Returns `None` when no provider is configured.
+    ///
+    /// A hit an admin has already cleared for this wallet is recorded but not flagged again;
+    /// only new matches re-block it.
+    pub async fn screen(&self, subject: &ScreeningSubject, trigger: ScreeningTrigger) -> Result<Option<Screening>> {
+        let Some(provider) = &self.provider else {
+            return Ok(None);
+        };
+
+        let outcome = provider.screen(subject).await.map_err(ScreeningError::Unavailable)?;
+
+        let mut flagged = outcome.risk_level >= self.block_risk;
+        if flagged {
+            let cleared = self.repository.cleared_matches(&subject.wallet_address).await?;
+            flagged = cleared.as_ref() != Some(&outcome.matches);
+        }
+
+        let user_id = UserRepository::new(self.db.clone())
+            .get_by_wallet(&subject.wallet_address)
+            .await?
+            .map(|user| user.id);
+
+        let mut uow = UnitOfWork::begin(&self.db).await?;
+
+        let screening = ScreeningRepository::record_screening_in(uow.conn(), &NewScreening {
+            wallet_address: &subject.wallet_address,
+            user_id,
+            trigger,
+            provider: provider.name(),
+            risk_level: outcome.risk_level,
+            flagged,
+            matches: &outcome.matches,
+        }).await?;
+
+        if flagged {
+            let reason = format!("{} screening returned {} risk", provider.name(), screening.risk_level);
+            ScreeningRepository::block_wallet_in(uow.conn(), &subject.wallet_address, &reason, Some(screening.id)).await?;
+
+            ActivityLogRepository::record_in(uow.conn(), &CreateActivityLogRequest {
+                user_id,
+                activity_type: "wallet_blocked".to_string(),
+                description: Some(reason),
+                data: Some(json!({
+                    "wallet_address": subject.wallet_address,
+                    "screening_id": screening.id,
+                    "trigger": trigger,
+                })),
+                ip_address: None,
+            }).await?;
+        }
+
+        uow.commit().await?;
+
+        if flagged {
+            self.alert(&screening).await;
+        }
+
+        Ok(Some(screening))
+    }
+
+    /// Refuses blocked wallets
+    pub async fn ensure_not_blocked(&self, wallet_address: &str) -> Result<()> {
+        match self.repository.active_block(wallet_address).await? {
+            Some(block) => Err(blocked(block).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Refuses blocked wallets, then screens the subject unless it had a clean screening within
+    /// the configured max age. Fails with [`ScreeningError`] when the wallet may not proceed.
+    pub async fn check(&self, subject: &ScreeningSubject, trigger: ScreeningTrigger) -> Result<()> {
+        self.ensure_not_blocked(&subject.wallet_address).await?;
+
+        if self.provider.is_none() {
+            return Ok(());
+        }
+
+        if let Some(max_age) = self.max_age {
+            let latest = self.repository.latest_for_wallet(&subject.wallet_address).await?;
+            if latest.is_some_and(|screening| !screening.flagged && Utc::now() - screening.created_at < max_age) {
+                return Ok(());
+            }
+        }
+
+        match self.screen(subject, trigger).await? {
+            Some(screening) if screening.flagged => Err(ScreeningError::Blocked {
+                wallet_address: subject.wallet_address.to_string(),
+                reason: format!("screening returned {} risk", screening.risk_level),
+            }.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lifts a wallet block. Returns `None` when the wallet isn't blocked.
+    pub async fn clear_block(&self, wallet_address: &WalletAddress, reason: &str) -> Result<Option<BlockedWallet>> {
+        let Some(block) = self.repository.clear_block(wallet_address, reason).await? else {
+            return Ok(None);
+        };
+
+        let user_id = UserRepository::new(self.db.clone())
+            .get_by_wallet(wallet_address)
+            .await?
+            .map(|user| user.id);
+
+        ActivityLogRepository::new(self.db.clone()).record(&CreateActivityLogRequest {
+            user_id,
+            activity_type: "wallet_unblocked".to_string(),
+            description: Some(reason.to_string()),
+            data: Some(json!({ "wallet_address": wallet_address })),
+            ip_address: None,
+        }).await?;
+
+        info!("Cleared block on wallet {}: {}", wallet_address, reason);
+
+        Ok(Some(block))
+    }
+
+    /// Reports a flagged screening. Webhook failures are logged; the block is already in place.
+    async fn alert(&self, screening: &Screening) {
+        warn!(
+            "Screening flagged wallet {} ({} risk, {} matches); wallet blocked",
+            screening.wallet_address, screening.risk_level, screening.matches.len()
+        );
+        increment_counter!(
+            "screening_flags_total",
+            "provider" => screening.provider.clone(),
+            "trigger" => screening.trigger.to_string()
+        );
+
+        let data = json!({
+            "screening_id": screening.id,
+            "wallet_address": screening.wallet_address,
+            "user_id": screening.user_id,
+            "trigger": screening.trigger,
+            "provider": screening.provider,
+            "risk_level": screening.risk_level,
+            "matches": screening.matches,
+            "created_at": screening.created_at,
+        });
+
+        if let Err(err) = self.webhooks.publish(WebhookEventType::ScreeningFlagged, data).await {
+            warn!("Failed to publish screening alert for {}: {}", screening.wallet_address, err);
+        }
+    }
+}
+
+/// Error for a wallet with an active block
+fn blocked(block: BlockedWallet) -> ScreeningError {
+    ScreeningError::Blocked {
+        wallet_address: block.wallet_address,
+        reason: block.reason,
+    }
+}
@@ -0,0 +1,77 @@
+//! Background worker re-screening registered wallets
+
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info, warn};
+
+use super::{ScreeningService, ScreeningSubject};
+use crate::db::ScreeningRepository;
+use crate::models::screening::ScreeningTrigger;
+use crate::services::shutdown::Shutdown;
+
+/// Seconds between checks for wallets due a re-screen
+const POLL_INTERVAL_SECS: u64 = 300;
+
+/// Worker that re-screens every registered wallet once per interval
+pub struct RescreenWorker {
+    /// Screening checks
+    screening: ScreeningService,
+    /// Wallets due a re-screen
+    repository: ScreeningRepository,
+    /// Seconds a wallet goes between screenings
+    interval_secs: u64,
+    /// Wallets screened per run
+    batch_size: i64,
+}
+
+impl RescreenWorker {
+    /// Creates a new re-screen worker
+    pub fn new(db: PgPool, screening: ScreeningService, interval_secs: u64, batch_size: i64) -> Self {
+        Self {
+            screening,
+            repository: ScreeningRepository::new(db),
+            interval_secs,
+            batch_size,
+        }
+    }
+
+    /// Runs the re-screen loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        info!("Starting re-screen worker with interval {} seconds", self.interval_secs);
+
+        let mut interval = time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+
+        while shutdown.tick(&mut interval).await {
+            if let Err(err) = self.run_once().await {
+                error!("Re-screen run failed: {}", err);
+            }
+        }
+
+        info!("Re-screen worker stopped");
+        Ok(())
+    }
+
+    /// Screens the wallets that have gone longest without a screening. Returns how many were
+    /// screened.
+    pub async fn run_once(&self) -> Result<usize> {
+        let screened_before = Utc::now() - ChronoDuration::seconds(i64::try_from(self.interval_secs).unwrap_or(i64::MAX));
+        let due = self.repository.wallets_due_for_rescreen(screened_before, self.batch_size).await?;
+
+        let mut screened = 0;
+        for wallet_address in due {
+            match self.screening.screen(&ScreeningSubject::wallet(&wallet_address), ScreeningTrigger::Rescreen).await {
+                Ok(_) => screened += 1,
+                Err(err) => warn!("Failed to re-screen wallet {}: {}", wallet_address, err),
+            }
+        }
+
+        if screened > 0 {
+            info!("Re-screened {} wallets", screened);
+        }
+
+        Ok(screened)
+    }
+}
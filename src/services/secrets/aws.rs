@@ -0,0 +1,156 @@
+//! AWS Secrets Manager backend
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::{StatusCode, Url};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use super::SecretsBackend;
+use crate::config::AwsSecretsConfig;
+use crate::services::storage::sigv4::{amz_date, payload_hash, SigningKey};
+
+/// Reads secrets with `GetSecretValue` and writes them with `PutSecretValue`, one secret per
+/// setting named `<prefix><setting>`
+pub struct AwsSecretsManager {
+    config: AwsSecretsConfig,
+    endpoint: Url,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetSecretValueResponse {
+    secret_string: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(rename = "__type")]
+    kind: Option<String>,
+    #[serde(alias = "Message")]
+    message: Option<String>,
+}
+
+impl AwsSecretsManager {
+    /// Creates a client for the configured region and credentials
+    pub fn new(config: AwsSecretsConfig) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint).context("Invalid SECRETS_AWS_ENDPOINT")?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Secrets Manager HTTP client")?;
+
+        Ok(Self { config, endpoint, client })
+    }
+
+    fn signing_key(&self) -> SigningKey<'_> {
+        SigningKey {
+            access_key_id: &self.config.access_key_id,
+            secret_access_key: &self.config.secret_access_key,
+            region: &self.config.region,
+            service: "secretsmanager",
+        }
+    }
+
+    /// Sends a signed request for a Secrets Manager action, returning the response whatever its status
+    async fn call(&self, action: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let body = body.to_string().into_bytes();
+        let now = Utc::now();
+
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("x-amz-date", amz_date(now)),
+            ("x-amz-target", format!("secretsmanager.{}", action)),
+        ];
+        if let Some(token) = &self.config.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let signed: Vec<(&str, &str)> = headers.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        let authorization = self.signing_key().authorization("POST", &self.endpoint, &signed, &payload_hash(&body), now);
+
+        let mut request = self.client.post(self.endpoint.clone()).header("authorization", authorization);
+        for (name, value) in &headers {
+            request = request.header(*name, value);
+        }
+
+        request.body(body).send().await.context("Secrets Manager request failed")
+    }
+}
+
+#[async_trait]
+impl SecretsBackend for AwsSecretsManager {
+    fn name(&self) -> &'static str {
+        "aws-secrets-manager"
+    }
+
+    async fn fetch(&self, name: &str) -> Result<Option<SecretString>> {
+        let secret_id = format!("{}{}", self.config.prefix, name);
+        let response = self.call("GetSecretValue", &json!({ "SecretId": secret_id })).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error = ErrorResponse::read(response).await;
+            if error.is_not_found() {
+                return Ok(None);
+            }
+            return Err(error.into_error(status, &secret_id));
+        }
+
+        let secret: GetSecretValueResponse = response
+            .json()
+            .await
+            .context("Failed to parse Secrets Manager response")?;
+
+        Ok(secret.secret_string.map(SecretString::new))
+    }
+
+    async fn store(&self, name: &str, value: &SecretString) -> Result<()> {
+        let secret_id = format!("{}{}", self.config.prefix, name);
+        let mut response = self
+            .call("PutSecretValue", &json!({ "SecretId": secret_id, "SecretString": value.expose_secret() }))
+            .await?;
+
+        let mut status = response.status();
+        if !status.is_success() {
+            let error = ErrorResponse::read(response).await;
+            if !error.is_not_found() {
+                return Err(error.into_error(status, &secret_id));
+            }
+
+            response = self
+                .call("CreateSecret", &json!({ "Name": secret_id, "SecretString": value.expose_secret() }))
+                .await?;
+            status = response.status();
+            if !status.is_success() {
+                return Err(ErrorResponse::read(response).await.into_error(status, &secret_id));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ErrorResponse {
+    async fn read(response: reqwest::Response) -> Self {
+        response.json().await.unwrap_or(ErrorResponse { kind: None, message: None })
+    }
+
+    fn is_not_found(&self) -> bool {
+        self.kind.as_deref().is_some_and(|kind| kind.ends_with("ResourceNotFoundException"))
+    }
+
+    fn into_error(self, status: StatusCode, secret_id: &str) -> anyhow::Error {
+        anyhow!(
+            "Secrets Manager returned {} for {}: {} {}",
+            status,
+            secret_id,
+            self.kind.unwrap_or_default(),
+            self.message.unwrap_or_default()
+        )
+    }
+}
@@ -0,0 +1,183 @@
+//! Encrypted secrets file backend
+//!
+//! The file holds base64 of a 12-byte nonce followed by the AES-256-GCM encrypted JSON object
+//! of setting names to values. It's read again on every fetch, so replacing the file rotates
+//! the secrets. Storing a secret re-encrypts the whole file with a fresh nonce.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use secrecy::zeroize::{Zeroize, Zeroizing};
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::SecretsBackend;
+use crate::config::EncryptedFileSecretsConfig;
+
+/// Reads and writes secrets in an AES-256-GCM encrypted JSON file
+pub struct EncryptedFileSecrets {
+    path: PathBuf,
+    key: LessSafeKey,
+}
+
+impl EncryptedFileSecrets {
+    /// Creates a backend for the configured file and key
+    pub fn new(config: &EncryptedFileSecretsConfig) -> Result<Self> {
+        Ok(Self {
+            path: PathBuf::from(&config.path),
+            key: parse_key(&config.key)?,
+        })
+    }
+
+    /// Encrypts a JSON object of setting names to values into the file format
+    pub fn encrypt(key: &str, secrets: &HashMap<String, String>) -> Result<String> {
+        seal(&parse_key(key)?, secrets)
+    }
+
+    /// Decrypts file contents into setting names and values
+    fn decrypt(&self, contents: &str) -> Result<HashMap<String, String>> {
+        let mut bytes = Zeroizing::new(BASE64.decode(contents.trim()).context("Secrets file is not valid base64")?);
+        if bytes.len() < NONCE_LEN {
+            return Err(anyhow!("Secrets file is too short"));
+        }
+
+        let (nonce, sealed) = bytes.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("Invalid nonce in secrets file"))?;
+        let plaintext = self.key
+            .open_in_place(nonce, Aad::empty(), sealed)
+            .map_err(|_| anyhow!("Failed to decrypt secrets file; is SECRETS_FILE_KEY correct?"))?;
+
+        serde_json::from_slice(plaintext).context("Secrets file must hold a JSON object of strings")
+    }
+}
+
+#[async_trait]
+impl SecretsBackend for EncryptedFileSecrets {
+    fn name(&self) -> &'static str {
+        "encrypted-file"
+    }
+
+    async fn fetch(&self, name: &str) -> Result<Option<SecretString>> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("Failed to read secrets file {}", self.path.display()))?;
+
+        let mut found = None;
+        for (key, mut value) in self.decrypt(&contents)? {
+            if key == name {
+                found = Some(SecretString::new(value));
+            } else {
+                value.zeroize();
+            }
+        }
+
+        Ok(found)
+    }
+
+    async fn store(&self, name: &str, value: &SecretString) -> Result<()> {
+        let mut secrets = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => self.decrypt(&contents)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err).with_context(|| format!("Failed to read secrets file {}", self.path.display())),
+        };
+        secrets.insert(name.to_string(), value.expose_secret().clone());
+        let sealed = seal(&self.key, &secrets);
+        secrets.values_mut().for_each(Zeroize::zeroize);
+
+        // Replace the file in one step, so a fetch never reads it half-written
+        let staged = self.path.with_extension("tmp");
+        tokio::fs::write(&staged, sealed?)
+            .await
+            .with_context(|| format!("Failed to write secrets file {}", staged.display()))?;
+        tokio::fs::rename(&staged, &self.path)
+            .await
+            .with_context(|| format!("Failed to replace secrets file {}", self.path.display()))
+    }
+}
+
+/// Encrypts setting names and values into the file format
+fn seal(key: &LessSafeKey, secrets: &HashMap<String, String>) -> Result<String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("Failed to generate a nonce"))?;
+
+    let mut sealed = Zeroizing::new(serde_json::to_vec(secrets).context("Failed to serialize secrets")?);
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut *sealed)
+        .map_err(|_| anyhow!("Failed to encrypt secrets"))?;
+
+    let mut file = nonce.to_vec();
+    file.extend_from_slice(&sealed);
+    Ok(BASE64.encode(file))
+}
+
+/// Parses a base64 AES-256 key
+fn parse_key(key: &str) -> Result<LessSafeKey> {
+    let bytes = Zeroizing::new(BASE64.decode(key.trim()).context("SECRETS_FILE_KEY must be base64")?);
+    let key = UnboundKey::new(&AES_256_GCM, &bytes).map_err(|_| anyhow!("SECRETS_FILE_KEY must be 32 bytes"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+    #[tokio::test]
+    async fn reads_secrets_back_from_an_encrypted_file() {
+        let secrets = HashMap::from([("WALLET_SEED_PHRASE".to_string(), "bottom drive obey lake".to_string())]);
+        let path = std::env::temp_dir().join(format!("lsrwa-secrets-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, EncryptedFileSecrets::encrypt(KEY, &secrets).unwrap()).unwrap();
+
+        let backend = EncryptedFileSecrets::new(&EncryptedFileSecretsConfig {
+            path: path.display().to_string(),
+            key: KEY.to_string(),
+        })
+        .unwrap();
+
+        let seed = backend.fetch("WALLET_SEED_PHRASE").await.unwrap().unwrap();
+        assert_eq!(seed.expose_secret(), "bottom drive obey lake");
+        assert!(backend.fetch("SMTP_PASSWORD").await.unwrap().is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stores_secrets_alongside_the_others() {
+        let path = std::env::temp_dir().join(format!("lsrwa-secrets-{}", uuid::Uuid::new_v4()));
+        let backend = EncryptedFileSecrets::new(&EncryptedFileSecretsConfig {
+            path: path.display().to_string(),
+            key: KEY.to_string(),
+        })
+        .unwrap();
+
+        backend.store("ADMIN_API_KEY", &SecretString::new("admin".to_string())).await.unwrap();
+        backend.store("WALLET_SEED_PHRASE", &SecretString::new("bottom drive obey lake".to_string())).await.unwrap();
+        backend.store("ADMIN_API_KEY", &SecretString::new("rotated".to_string())).await.unwrap();
+
+        assert_eq!(backend.fetch("ADMIN_API_KEY").await.unwrap().unwrap().expose_secret(), "rotated");
+        assert_eq!(backend.fetch("WALLET_SEED_PHRASE").await.unwrap().unwrap().expose_secret(), "bottom drive obey lake");
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_the_wrong_key() {
+        let secrets = HashMap::from([("ADMIN_API_KEY".to_string(), "admin".to_string())]);
+        let contents = EncryptedFileSecrets::encrypt(KEY, &secrets).unwrap();
+
+        let backend = EncryptedFileSecrets::new(&EncryptedFileSecretsConfig {
+            path: String::new(),
+            key: BASE64.encode([7u8; 32]),
+        })
+        .unwrap();
+
+        assert!(backend.decrypt(&contents).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Seed phrases and API keys from a secrets manager
+//!
+//! A [`SecretsBackend`] reads secrets by setting name from AWS Secrets Manager
+//! ([`AwsSecretsManager`]), a Vault KV v2 engine ([`VaultSecrets`]) or an AES-256-GCM encrypted
+//! file ([`EncryptedFileSecrets`]). The [`SecretStore`] in front of it caches each secret for
+//! `SECRETS_REFRESH_SECS` and fetches it again on the next use after that, so a rotated secret is
+//! picked up without a restart. Names the backend doesn't hold fall back to plain settings.
+//! Backends other than the environment can also be written through [`SecretStore::store`], which
+//! `lsrwa-cli keys` uses to stage and rotate operator keys.
+//!
+//! Values are held as [`SecretString`]s, which are zeroized when dropped.
+
+mod aws;
+mod file;
+mod vault;
+
+pub use aws::AwsSecretsManager;
+pub use file::EncryptedFileSecrets;
+pub use vault::VaultSecrets;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::config::{SecretsBackendConfig, SecretsConfig, Settings};
+
+/// Settings that may be held in the secrets backend
+pub const MANAGED_SECRETS: &[&str] = &[
+    "WALLET_SEED_PHRASE",
+    "CONTRACT_OWNER_SEED_PHRASE",
+    "RECEIPT_SIGNING_SEED_PHRASE",
+    "WEBHOOK_SECRETS_KEY",
+    "ADMIN_API_KEY",
+    "SUMSUB_API_KEY",
+    "SUMSUB_SECRET_KEY",
+    "SUMSUB_WEBHOOK_SECRET",
+    "ONFIDO_API_TOKEN",
+    "ONFIDO_WEBHOOK_SECRET",
+    "PERSONA_API_KEY",
+    "PERSONA_WEBHOOK_SECRET",
+    "SHUFTI_SECRET_KEY",
+    "CHAINALYSIS_API_KEY",
+    "ORACLE_HTTP_API_KEY",
+    "SENDGRID_API_KEY",
+    "SMTP_PASSWORD",
+    "ALERT_TELEGRAM_BOT_TOKEN",
+    "ALERT_PAGERDUTY_ROUTING_KEY",
+    "NATS_TOKEN",
+];
+
+/// Source of secrets, looked up by setting name
+#[async_trait]
+pub trait SecretsBackend: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Current value of a secret; `None` if the backend doesn't hold it
+    async fn fetch(&self, name: &str) -> Result<Option<SecretString>>;
+
+    /// Creates or replaces a secret
+    async fn store(&self, name: &str, _value: &SecretString) -> Result<()> {
+        Err(anyhow!("The {} secrets backend can't store {}", self.name(), name))
+    }
+}
+
+/// A fetched secret and when it was fetched
+struct CachedSecret {
+    value: Option<SecretString>,
+    fetched_at: Instant,
+}
+
+/// Cached access to secrets, refetched lazily once they're older than the refresh interval
+#[derive(Clone)]
+pub struct SecretStore {
+    /// `None` when secrets only come from settings
+    backend: Option<Arc<dyn SecretsBackend>>,
+    /// Values of managed secrets set as plain settings
+    fallback: Arc<HashMap<String, SecretString>>,
+    refresh: Duration,
+    cache: Arc<RwLock<HashMap<String, CachedSecret>>>,
+}
+
+impl SecretStore {
+    /// Creates a store reading from `backend`, falling back to the managed secrets in `settings`
+    pub fn new(backend: Option<Arc<dyn SecretsBackend>>, settings: &Settings, refresh: Duration) -> Self {
+        let fallback = MANAGED_SECRETS
+            .iter()
+            .filter_map(|name| {
+                let value = settings.var(name).ok().filter(|value| !value.is_empty())?;
+                Some((name.to_string(), SecretString::new(value)))
+            })
+            .collect();
+
+        Self {
+            backend,
+            fallback: Arc::new(fallback),
+            refresh,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Creates the store for the configured backend
+    pub fn from_config(config: &SecretsConfig, settings: &Settings) -> Result<Self> {
+        let backend: Option<Arc<dyn SecretsBackend>> = match &config.backend {
+            SecretsBackendConfig::Env => None,
+            SecretsBackendConfig::Aws(aws) => Some(Arc::new(AwsSecretsManager::new(aws.clone())?)),
+            SecretsBackendConfig::Vault(vault) => Some(Arc::new(VaultSecrets::new(vault.clone())?)),
+            SecretsBackendConfig::EncryptedFile(file) => Some(Arc::new(EncryptedFileSecrets::new(file)?)),
+        };
+
+        Ok(Self::new(backend, settings, Duration::from_secs(config.refresh_secs)))
+    }
+
+    /// Name of the backend secrets are read from
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.as_ref().map(|backend| backend.name()).unwrap_or("env")
+    }
+
+    /// Gets a secret, from the cache while it's fresh
+    ///
+    /// If refetching fails, the cached value keeps being served and the fetch is retried on the
+    /// next call, so a backend outage doesn't stop signing.
+    pub async fn get(&self, name: &str) -> Result<Option<SecretString>> {
+        let Some(backend) = &self.backend else {
+            return Ok(self.fallback.get(name).cloned());
+        };
+
+        if let Some(cached) = self.cache.read().await.get(name) {
+            if cached.fetched_at.elapsed() < self.refresh {
+                return Ok(cached.value.clone());
+            }
+        }
+
+        let mut cache = self.cache.write().await;
+        // Another caller may have refreshed it while we waited for the lock
+        if let Some(cached) = cache.get(name) {
+            if cached.fetched_at.elapsed() < self.refresh {
+                return Ok(cached.value.clone());
+            }
+        }
+
+        match backend.fetch(name).await {
+            Ok(value) => {
+                let value = value.or_else(|| self.fallback.get(name).cloned());
+                cache.insert(name.to_string(), CachedSecret { value: value.clone(), fetched_at: Instant::now() });
+                Ok(value)
+            },
+            Err(err) => match cache.get(name) {
+                Some(cached) => {
+                    warn!("Failed to refresh secret {} from {}, using the cached value: {:#}", name, backend.name(), err);
+                    Ok(cached.value.clone())
+                },
+                None => Err(err.context(format!("Failed to read secret {} from {}", name, backend.name()))),
+            },
+        }
+    }
+
+    /// Gets a secret that must be set
+    pub async fn require(&self, name: &str) -> Result<SecretString> {
+        self.get(name).await?.ok_or_else(|| anyhow!("{} is not set", name))
+    }
+
+    /// Creates or replaces a secret in the backend; the next read returns the new value
+    pub async fn store(&self, name: &str, value: &SecretString) -> Result<()> {
+        let Some(backend) = &self.backend else {
+            return Err(anyhow!("Secrets come from environment settings, which can't be written; set {} yourself", name));
+        };
+
+        backend
+            .store(name, value)
+            .await
+            .map_err(|err| err.context(format!("Failed to store secret {} in {}", name, backend.name())))?;
+        self.cache.write().await.remove(name);
+
+        Ok(())
+    }
+
+    /// Copies every managed secret held by the backend into `settings`, so configuration
+    /// sections read at startup see them; returns how many were found
+    pub async fn resolve_settings(&self, settings: &mut Settings) -> Result<usize> {
+        if self.backend.is_none() {
+            return Ok(0);
+        }
+
+        let mut resolved = 0;
+        for name in MANAGED_SECRETS {
+            if let Some(value) = self.get(name).await? {
+                settings.set(name, value.expose_secret().clone());
+                resolved += 1;
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Environment;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Backend that holds only `WALLET_SEED_PHRASE`, counting fetches
+    struct CountingBackend {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SecretsBackend for CountingBackend {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        async fn fetch(&self, name: &str) -> Result<Option<SecretString>> {
+            let fetch = self.fetches.fetch_add(1, Ordering::SeqCst);
+            Ok((name == "WALLET_SEED_PHRASE").then(|| SecretString::new(format!("seed-{}", fetch))))
+        }
+    }
+
+    fn settings() -> Settings {
+        let mut settings = Settings::from_files(Environment::Development, Path::new("/nonexistent")).unwrap();
+        settings.set("ADMIN_API_KEY", "from-settings".to_string());
+        settings
+    }
+
+    #[tokio::test]
+    async fn caches_until_refresh_and_falls_back_to_settings() {
+        let backend = Arc::new(CountingBackend { fetches: AtomicUsize::new(0) });
+        let store = SecretStore::new(Some(backend.clone()), &settings(), Duration::from_secs(60));
+
+        let first = store.require("WALLET_SEED_PHRASE").await.unwrap();
+        let second = store.require("WALLET_SEED_PHRASE").await.unwrap();
+        assert_eq!(first.expose_secret(), "seed-0");
+        assert_eq!(second.expose_secret(), "seed-0");
+        assert_eq!(backend.fetches.load(Ordering::SeqCst), 1);
+
+        let admin_key = store.require("ADMIN_API_KEY").await.unwrap();
+        assert_eq!(admin_key.expose_secret(), "from-settings");
+        assert!(store.get("SMTP_PASSWORD").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn refetches_rotated_secrets_once_stale() {
+        let backend = Arc::new(CountingBackend { fetches: AtomicUsize::new(0) });
+        let store = SecretStore::new(Some(backend), &settings(), Duration::ZERO);
+
+        assert_eq!(store.require("WALLET_SEED_PHRASE").await.unwrap().expose_secret(), "seed-0");
+        assert_eq!(store.require("WALLET_SEED_PHRASE").await.unwrap().expose_secret(), "seed-1");
+    }
+}
@@ -0,0 +1,125 @@
+//! HashiCorp Vault KV v2 backend
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use secrecy::zeroize::Zeroize;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::SecretsBackend;
+use crate::config::VaultSecretsConfig;
+
+/// Reads and writes secrets in one KV v2 secret whose keys are setting names
+pub struct VaultSecrets {
+    config: VaultSecretsConfig,
+    url: Url,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct ReadResponse {
+    data: ReadData,
+}
+
+#[derive(Deserialize)]
+struct ReadData {
+    data: HashMap<String, String>,
+}
+
+impl VaultSecrets {
+    /// Creates a client for the configured secret
+    pub fn new(config: VaultSecretsConfig) -> Result<Self> {
+        let url = Url::parse(&config.address)
+            .and_then(|address| {
+                address.join(&format!(
+                    "v1/{}/data/{}",
+                    config.mount.trim_matches('/'),
+                    config.path.trim_matches('/')
+                ))
+            })
+            .context("Invalid SECRETS_VAULT_ADDR")?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build Vault HTTP client")?;
+
+        Ok(Self { config, url, client })
+    }
+
+    /// Request to the secret, authenticated
+    fn request(&self, method: Method) -> RequestBuilder {
+        let mut request = self.client.request(method, self.url.clone()).header("X-Vault-Token", &self.config.token);
+        if let Some(namespace) = &self.config.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        request
+    }
+}
+
+#[async_trait]
+impl SecretsBackend for VaultSecrets {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    async fn fetch(&self, name: &str) -> Result<Option<SecretString>> {
+        let response = self.request(Method::GET).send().await.context("Vault request failed")?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Vault returned {} for {}: {}", status, self.config.path, message));
+        }
+
+        let secret: ReadResponse = response.json().await.context("Failed to parse Vault response")?;
+
+        // Every key comes back with each read; wipe the ones that weren't asked for
+        let mut found = None;
+        for (key, mut value) in secret.data.data {
+            if key == name {
+                found = Some(SecretString::new(value));
+            } else {
+                value.zeroize();
+            }
+        }
+
+        Ok(found)
+    }
+
+    async fn store(&self, name: &str, value: &SecretString) -> Result<()> {
+        let body = json!({ "data": { name: value.expose_secret() } }).to_string();
+
+        // A merge patch leaves the secret's other keys alone, but needs the secret to exist
+        let mut response = self
+            .request(Method::PATCH)
+            .header("Content-Type", "application/merge-patch+json")
+            .body(body.clone())
+            .send()
+            .await
+            .context("Vault request failed")?;
+        if response.status() == StatusCode::NOT_FOUND {
+            response = self
+                .request(Method::POST)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+                .await
+                .context("Vault request failed")?;
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Vault returned {} writing {}: {}", status, self.config.path, message));
+        }
+
+        Ok(())
+    }
+}
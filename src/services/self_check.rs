@@ -0,0 +1,396 @@
+//! Startup self-check
+//!
+//! Before the server starts accepting requests, every dependency a request would otherwise trip
+//! over half-way through is checked: the database and its migrations, the node and the chain it
+//! follows, the contract, the signing accounts and the KYC providers' credentials. All checks
+//! run even after one fails, and the outcome is reported together, so a misconfigured
+//! deployment lists every problem at once.
+
+use anyhow::{anyhow, Context, Result};
+use sqlx::migrate::MigrateDatabase;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgPool, Postgres};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use subxt::ext::sp_core::Pair as PairTrait;
+use subxt::utils::AccountId32;
+use subxt::{OnlineClient, PolkadotConfig};
+use tracing::{error, info, warn};
+
+use crate::config::{ChainNetwork, Config};
+use crate::db::migration;
+use crate::models::amount::Amount;
+use crate::services::blockchain_service::free_balance;
+use crate::services::chain_metadata;
+use crate::services::http_client::HttpClient;
+use crate::services::keystore;
+use crate::services::kyc::KycServiceFactory;
+use crate::services::secrets::SecretStore;
+
+/// Accounts that sign extrinsics, by the secret holding their seed phrase
+const SIGNERS: [(&str, &str); 2] = [
+    ("owner signer", "CONTRACT_OWNER_SEED_PHRASE"),
+    ("wallet signer", "WALLET_SEED_PHRASE"),
+];
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Works, but something looks off
+    Warn,
+    /// The service would fail requests
+    Fail,
+    /// Not run because a check it depends on failed
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Warn => write!(f, "WARN"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+            CheckStatus::Skipped => write!(f, "SKIP"),
+        }
+    }
+}
+
+/// One check in the report
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub duration: Duration,
+}
+
+/// Outcome of every check
+#[derive(Debug, Clone, Default)]
+pub struct SelfCheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl SelfCheckReport {
+    /// Whether no check failed
+    pub fn passed(&self) -> bool {
+        !self.results.iter().any(|result| result.status == CheckStatus::Fail)
+    }
+
+    /// Number of checks with a status
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.results.iter().filter(|result| result.status == status).count()
+    }
+
+    /// Logs each check at a level matching its outcome
+    pub fn log(&self) {
+        for result in &self.results {
+            match result.status {
+                CheckStatus::Pass => info!(check = %result.name, "Self-check passed: {}", result.detail),
+                CheckStatus::Warn => warn!(check = %result.name, "Self-check warning: {}", result.detail),
+                CheckStatus::Fail => error!(check = %result.name, "Self-check failed: {}", result.detail),
+                CheckStatus::Skipped => warn!(check = %result.name, "Self-check skipped: {}", result.detail),
+            }
+        }
+    }
+
+    fn push(&mut self, name: impl Into<String>, status: CheckStatus, detail: impl Into<String>, duration: Duration) {
+        self.results.push(CheckResult { name: name.into(), status, detail: detail.into(), duration });
+    }
+}
+
+impl fmt::Display for SelfCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} warnings, {} failed, {} skipped",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail),
+            self.count(CheckStatus::Skipped),
+        )?;
+        for result in &self.results {
+            write!(f, "\n  [{}] {} ({} ms): {}", result.status, result.name, result.duration.as_millis(), result.detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the startup checks against the configured environment
+pub struct SelfCheck<'a> {
+    config: &'a Config,
+    secrets: &'a SecretStore,
+    timeout: Duration,
+}
+
+impl<'a> SelfCheck<'a> {
+    /// Creates a self-check of a configuration, reading signing keys from `secrets`
+    pub fn new(config: &'a Config, secrets: &'a SecretStore) -> Self {
+        Self {
+            config,
+            secrets,
+            timeout: Duration::from_secs(config.self_check.timeout_secs),
+        }
+    }
+
+    /// Runs every check. Connections opened here are closed again; the service opens its own.
+    pub async fn run(&self) -> SelfCheckReport {
+        let mut report = SelfCheckReport::default();
+
+        match self.timed(&mut report, "database", self.connect_database()).await.flatten() {
+            Some(pool) => {
+                self.timed(&mut report, "migrations", check_migrations(&pool)).await;
+                pool.close().await;
+            },
+            None => report.push("migrations", CheckStatus::Skipped, "database is unavailable", Duration::ZERO),
+        }
+
+        match self.timed(&mut report, "rpc", self.connect_node()).await {
+            Some(client) => {
+                self.timed(&mut report, "chain", self.check_chain(&client)).await;
+                self.timed(&mut report, "metadata", self.check_metadata(&client)).await;
+                self.timed(&mut report, "contract", self.check_contract(&client)).await;
+                for (name, secret) in SIGNERS {
+                    self.timed(&mut report, name, self.check_signer(&client, secret)).await;
+                }
+            },
+            None => {
+                report.push("chain", CheckStatus::Skipped, "node is unreachable", Duration::ZERO);
+                report.push("metadata", CheckStatus::Skipped, "node is unreachable", Duration::ZERO);
+                report.push("contract", CheckStatus::Skipped, "node is unreachable", Duration::ZERO);
+                for (name, _) in SIGNERS {
+                    report.push(name, CheckStatus::Skipped, "node is unreachable", Duration::ZERO);
+                }
+            },
+        }
+
+        self.check_kyc(&mut report).await;
+
+        report
+    }
+
+    /// Runs one check within the timeout and records its outcome, returning what a passing or
+    /// warning check produced
+    async fn timed<T, F>(&self, report: &mut SelfCheckReport, name: &str, check: F) -> Option<T>
+    where
+        F: Future<Output = Result<(CheckStatus, String, T)>>,
+    {
+        let started = Instant::now();
+        match tokio::time::timeout(self.timeout, check).await {
+            Ok(Ok((status, detail, value))) => {
+                report.push(name, status, detail, started.elapsed());
+                (status != CheckStatus::Fail).then_some(value)
+            },
+            Ok(Err(err)) => {
+                report.push(name, CheckStatus::Fail, format!("{:#}", err), started.elapsed());
+                None
+            },
+            Err(_) => {
+                let detail = format!("timed out after {}s", self.timeout.as_secs());
+                report.push(name, CheckStatus::Fail, detail, started.elapsed());
+                None
+            },
+        }
+    }
+
+    /// Connects to the database, which doesn't exist yet on a first deploy
+    async fn connect_database(&self) -> Result<(CheckStatus, String, Option<PgPool>)> {
+        let url = &self.config.database.url;
+        if !Postgres::database_exists(url).await.context("Failed to connect to the database server")? {
+            return Ok((CheckStatus::Warn, "database doesn't exist yet; it is created at startup".to_string(), None));
+        }
+
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(self.timeout)
+            .connect(url)
+            .await
+            .context("Failed to connect to the database")?;
+
+        let version: String = sqlx::query_scalar("SHOW server_version")
+            .fetch_one(&pool)
+            .await
+            .context("Failed to query the database")?;
+
+        Ok((CheckStatus::Pass, format!("connected to PostgreSQL {}", version), Some(pool)))
+    }
+
+    async fn connect_node(&self) -> Result<(CheckStatus, String, OnlineClient<PolkadotConfig>)> {
+        let rpc_url = &self.config.blockchain.rpc_url;
+        let client = OnlineClient::<PolkadotConfig>::from_url(rpc_url)
+            .await
+            .with_context(|| format!("Failed to connect to the node at {}", rpc_url))?;
+
+        let runtime = client.runtime_version();
+        let detail = format!("connected to {} (runtime spec version {})", rpc_url, runtime.spec_version);
+        Ok((CheckStatus::Pass, detail, client))
+    }
+
+    /// Checks the node follows the configured network: its genesis hash when one is pinned,
+    /// and otherwise at least its chain name
+    async fn check_chain(&self, client: &OnlineClient<PolkadotConfig>) -> Result<(CheckStatus, String, ())> {
+        let genesis = format!("{:?}", client.genesis_hash());
+        let chain = client.rpc().system_chain().await.context("Failed to fetch the chain name")?;
+        let network = self.config.http.explorer.network;
+
+        if let Some(expected) = &self.config.self_check.genesis_hash {
+            if &genesis != expected {
+                return Err(anyhow!(
+                    "node is on '{}' with genesis {}, but CHAIN_GENESIS_HASH is {}",
+                    chain,
+                    genesis,
+                    expected
+                ));
+            }
+            return Ok((CheckStatus::Pass, format!("'{}' with genesis {}", chain, genesis), ()));
+        }
+
+        // Development chains go by many names; public ones are named after their network
+        let name_matches = network == ChainNetwork::Local || chain.to_ascii_lowercase().contains(&network.to_string());
+        if !name_matches {
+            let detail = format!("node is on '{}', which doesn't look like the configured {} network", chain, network);
+            return Ok((CheckStatus::Warn, detail, ()));
+        }
+
+        let detail = format!("'{}' with genesis {}; set CHAIN_GENESIS_HASH to pin it", chain, genesis);
+        Ok((CheckStatus::Pass, detail, ()))
+    }
+
+    /// Checks the cached runtime metadata of the configured network is the node's. The service
+    /// builds calls from the node's metadata, so stale metadata only affects offline tooling.
+    async fn check_metadata(&self, client: &OnlineClient<PolkadotConfig>) -> Result<(CheckStatus, String, ())> {
+        let path = &self.config.blockchain.metadata_path;
+        let live = chain_metadata::checksum(&chain_metadata::fetch(client).await?);
+
+        let (status, detail) = match chain_metadata::load(path) {
+            Ok(Some(cached)) if cached.checksum == live => (CheckStatus::Pass, format!("{} is current", path.display())),
+            Ok(Some(_)) => (
+                CheckStatus::Warn,
+                format!("{} is older than the node's runtime; run download_metadata", path.display()),
+            ),
+            Ok(None) => (CheckStatus::Warn, format!("{} is missing; run download_metadata", path.display())),
+            Err(err) => (CheckStatus::Warn, format!("{:#}", err)),
+        };
+        Ok((status, detail, ()))
+    }
+
+    /// Checks a contract is instantiated at the configured address. Without one, the live
+    /// deployment is looked up in the registry at startup, once the database is up.
+    async fn check_contract(&self, client: &OnlineClient<PolkadotConfig>) -> Result<(CheckStatus, String, ())> {
+        let Some(address) = &self.config.blockchain.contract_address else {
+            let detail = format!("CONTRACT_ADDRESS is unset; the live {} deployment is used", self.config.blockchain.network);
+            return Ok((CheckStatus::Pass, detail, ()));
+        };
+        let account = AccountId32::from_str(address)
+            .map_err(|e| anyhow!("CONTRACT_ADDRESS {} is not a valid address: {}", address, e))?;
+
+        let query = subxt::dynamic::storage(
+            "Contracts",
+            "ContractInfoOf",
+            vec![subxt::dynamic::Value::from_bytes(account.0)],
+        );
+        let stored = client
+            .storage()
+            .at_latest()
+            .await
+            .context("Failed to get latest block")?
+            .fetch(&query)
+            .await
+            .context("Failed to fetch contract info")?;
+
+        match stored {
+            Some(_) => Ok((CheckStatus::Pass, format!("contract found at {}", address), ())),
+            None => Err(anyhow!("no contract is instantiated at {}", address)),
+        }
+    }
+
+    /// Checks a signing account's seed phrase is set and the account can pay fees
+    async fn check_signer(&self, client: &OnlineClient<PolkadotConfig>, secret: &str) -> Result<(CheckStatus, String, ())> {
+        let seed_phrase = self.secrets.require(secret).await?;
+        let pair = keystore::keypair(&seed_phrase).with_context(|| format!("{} is not a valid key", secret))?;
+        let account = AccountId32::from(pair.public());
+
+        let free = free_balance(client, account.0).await?;
+        let balance = Amount::from_units(free).to_decimal(self.config.blockchain.network.token_decimals());
+        let minimum = &self.config.self_check.min_signer_balance;
+
+        if &balance < minimum {
+            return Err(anyhow!("{} holds {}, below the minimum of {}", account, balance, minimum));
+        }
+
+        Ok((CheckStatus::Pass, format!("{} holds {}", account, balance), ()))
+    }
+
+    /// Checks each configured KYC provider accepts our credentials
+    async fn check_kyc(&self, report: &mut SelfCheckReport) {
+        let kyc_config = &self.config.kyc;
+        let started = Instant::now();
+
+        let services = match HttpClient::from_config(&self.config.outbound_http)
+            .and_then(|http| KycServiceFactory::create_configured(kyc_config, &http))
+        {
+            Ok(services) => services,
+            Err(err) => {
+                report.push("kyc", CheckStatus::Fail, format!("{:#}", err), started.elapsed());
+                return;
+            },
+        };
+
+        if !services.iter().any(|service| service.provider() == kyc_config.provider) {
+            let detail = format!("the default provider {} is not configured", kyc_config.provider);
+            report.push("kyc", CheckStatus::Warn, detail, started.elapsed());
+        }
+
+        for service in services {
+            let name = format!("kyc {}", service.provider());
+            let check = async {
+                service.check_credentials().await?;
+                Ok::<_, anyhow::Error>((CheckStatus::Pass, "credentials accepted".to_string(), ()))
+            };
+            self.timed(report, &name, check).await;
+        }
+    }
+}
+
+async fn check_migrations(pool: &PgPool) -> Result<(CheckStatus, String, ())> {
+    let status = migration::migration_status(pool).await?;
+
+    if !status.failed.is_empty() {
+        return Err(anyhow!("migrations {:?} failed part-way and need fixing by hand", status.failed));
+    }
+    if !status.modified.is_empty() {
+        return Err(anyhow!("migrations {:?} were edited after they were applied", status.modified));
+    }
+    if !status.unknown.is_empty() {
+        return Err(anyhow!("applied migrations {:?} are newer than this build", status.unknown));
+    }
+
+    let detail = match status.pending.len() {
+        0 => "up to date".to_string(),
+        pending => format!("{} pending, applied at startup", pending),
+    };
+    Ok((CheckStatus::Pass, detail, ()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_fails_only_on_failed_checks() {
+        let mut report = SelfCheckReport::default();
+        report.push("database", CheckStatus::Pass, "connected", Duration::from_millis(3));
+        report.push("chain", CheckStatus::Warn, "unexpected chain name", Duration::from_millis(5));
+        report.push("contract", CheckStatus::Skipped, "node is unreachable", Duration::ZERO);
+        assert!(report.passed());
+
+        report.push("kyc sumsub", CheckStatus::Fail, "SumSub returned 401: unauthorized", Duration::from_millis(80));
+        assert!(!report.passed());
+
+        let rendered = report.to_string();
+        assert!(rendered.starts_with("1 passed, 1 warnings, 1 failed, 1 skipped"));
+        assert!(rendered.contains("[FAIL] kyc sumsub (80 ms): SumSub returned 401: unauthorized"));
+    }
+}
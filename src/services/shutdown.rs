@@ -0,0 +1,90 @@
+//! Process-wide shutdown signal
+//!
+//! Background workers wait on [`Shutdown::tick`] between units of work (a delivery batch, a
+//! block, a job run), so the unit in progress always finishes before the worker returns.
+
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::Interval;
+use tracing::info;
+
+/// Cloneable handle that is triggered once, when the process starts shutting down
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: Arc<watch::Sender<bool>>,
+    receiver: watch::Receiver<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    /// Creates an untriggered shutdown signal
+    pub fn new() -> Self {
+        let (sender, receiver) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+            receiver,
+        }
+    }
+
+    /// Tells every holder to stop. Triggering more than once has no further effect.
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Whether shutdown has been triggered
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Completes once shutdown has been triggered
+    pub async fn triggered(&self) {
+        let mut receiver = self.receiver.clone();
+        // The sender lives as long as any handle, so this only fails if it was already dropped
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Waits for the next tick of a worker's interval; `false` once shutdown has been triggered
+    pub async fn tick(&self, interval: &mut Interval) -> bool {
+        tokio::select! {
+            biased;
+            _ = self.triggered() => false,
+            _ = interval.tick() => true,
+        }
+    }
+}
+
+/// Completes on SIGINT, or SIGTERM on Unix
+pub async fn wait_for_signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            },
+            Err(err) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            },
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
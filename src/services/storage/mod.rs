@@ -0,0 +1,166 @@
+//! S3-compatible object storage
+//!
+//! A small client for the handful of S3 operations the service needs, signed with SigV4 so
+//! it works against AWS S3 as well as MinIO, R2 and other compatible stores.
+
+pub(crate) mod sigv4;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use reqwest::{Method, Url};
+use std::time::Duration;
+
+use crate::config::S3Config;
+use sigv4::{amz_date, payload_hash, uri_encode, SigningKey};
+
+/// Longest lifetime S3 accepts for a presigned URL
+const MAX_PRESIGN_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Server-side encryption applied to stored objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerSideEncryption {
+    /// Keys managed by the store (SSE-S3)
+    Aes256,
+    /// A KMS key (SSE-KMS)
+    Kms { key_id: String },
+}
+
+/// Presigned URL and when it stops working
+#[derive(Debug, Clone)]
+pub struct PresignedUrl {
+    pub url: Url,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Client for one bucket
+#[derive(Clone)]
+pub struct ObjectStorage {
+    config: S3Config,
+    bucket: String,
+    encryption: ServerSideEncryption,
+    client: reqwest::Client,
+}
+
+impl ObjectStorage {
+    /// Creates a client storing objects in `bucket` with the given encryption
+    pub fn new(config: S3Config, bucket: String, encryption: ServerSideEncryption) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .context("Failed to build object storage HTTP client")?;
+
+        Ok(Self { config, bucket, encryption, client })
+    }
+
+    /// Uploads an object, encrypted at rest
+    pub async fn put_object(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        let url = self.object_url(key)?;
+        let now = Utc::now();
+        let content_hash = payload_hash(&body);
+        let date = amz_date(now);
+
+        let mut headers = vec![
+            ("content-type", content_type.to_string()),
+            ("x-amz-content-sha256", content_hash.clone()),
+            ("x-amz-date", date),
+        ];
+        match &self.encryption {
+            ServerSideEncryption::Aes256 => headers.push(("x-amz-server-side-encryption", "AES256".to_string())),
+            ServerSideEncryption::Kms { key_id } => {
+                headers.push(("x-amz-server-side-encryption", "aws:kms".to_string()));
+                headers.push(("x-amz-server-side-encryption-aws-kms-key-id", key_id.clone()));
+            },
+        }
+
+        self.send(Method::PUT, url, &headers, &content_hash, now, Some(body))
+            .await
+            .with_context(|| format!("Failed to store object {}", key))
+    }
+
+    /// Deletes an object; deleting a missing object succeeds
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        let url = self.object_url(key)?;
+        let now = Utc::now();
+        let content_hash = payload_hash(b"");
+
+        let headers = vec![
+            ("x-amz-content-sha256", content_hash.clone()),
+            ("x-amz-date", amz_date(now)),
+        ];
+
+        self.send(Method::DELETE, url, &headers, &content_hash, now, None)
+            .await
+            .with_context(|| format!("Failed to delete object {}", key))
+    }
+
+    /// URL anyone holding it can download the object with until it expires
+    pub fn presigned_get(&self, key: &str, ttl_secs: u64) -> Result<PresignedUrl> {
+        let ttl_secs = ttl_secs.clamp(1, MAX_PRESIGN_SECS);
+        let now = Utc::now();
+
+        let url = self.signing_key().presign("GET", &self.object_url(key)?, ttl_secs, now);
+
+        Ok(PresignedUrl {
+            url,
+            expires_at: now + ChronoDuration::seconds(ttl_secs as i64),
+        })
+    }
+
+    /// Signs and sends a request, failing on non-success responses
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        headers: &[(&str, String)],
+        content_hash: &str,
+        now: DateTime<Utc>,
+        body: Option<Vec<u8>>,
+    ) -> Result<()> {
+        let signed: Vec<(&str, &str)> = headers.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        let authorization = self.signing_key().authorization(method.as_str(), &url, &signed, content_hash, now);
+
+        let mut request = self.client.request(method, url).header("authorization", authorization);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = request.send().await.context("Object storage request failed")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Object storage returned {}: {}", status, message));
+        }
+
+        Ok(())
+    }
+
+    /// URL of an object, virtual-hosted or path style depending on configuration
+    fn object_url(&self, key: &str) -> Result<Url> {
+        let mut url = Url::parse(&self.config.endpoint).context("Invalid S3_ENDPOINT")?;
+        let key = uri_encode(key.trim_start_matches('/'), false);
+
+        if self.config.path_style {
+            url.set_path(&format!("/{}/{}", self.bucket, key));
+        } else {
+            let host = url.host_str().context("S3_ENDPOINT has no host")?;
+            url.set_host(Some(&format!("{}.{}", self.bucket, host)))
+                .context("Invalid bucket name for a virtual-hosted URL")?;
+            url.set_path(&format!("/{}", key));
+        }
+
+        Ok(url)
+    }
+
+    fn signing_key(&self) -> SigningKey<'_> {
+        SigningKey {
+            access_key_id: &self.config.access_key_id,
+            secret_access_key: &self.config.secret_access_key,
+            region: &self.config.region,
+            service: "s3",
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! Protocol fee accounting
+//!
+//! Fees the contract pays to the treasury account are recorded from `FeeCollected` events by the
+//! indexer. [`TreasuryService`] aggregates them into revenue per month or epoch and fee type, and
+//! compares the recorded total with the treasury account's balance on-chain.
+
+mod report;
+
+pub use report::TreasuryService;
@@ -0,0 +1,96 @@
+//! Treasury revenue reporting and on-chain reconciliation
+
+use anyhow::Result;
+use chrono::Utc;
+use metrics::gauge;
+use sqlx::types::BigDecimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::TreasuryConfig;
+use crate::db::TreasuryRepository;
+use crate::models::alert::{Alert, AlertSeverity};
+use crate::models::treasury::{ReportPeriod, TreasuryReport};
+use crate::services::alerting::Alerter;
+use crate::services::ChainClient;
+
+/// Reports treasury revenue and checks it against the chain
+#[derive(Clone)]
+pub struct TreasuryService {
+    repository: TreasuryRepository,
+    blockchain: Arc<dyn ChainClient>,
+    config: TreasuryConfig,
+    alerts: Alerter,
+}
+
+impl TreasuryService {
+    /// Creates a treasury service
+    pub fn new(db: PgPool, blockchain: Arc<dyn ChainClient>, config: TreasuryConfig, alerts: Alerter) -> Self {
+        Self {
+            repository: TreasuryRepository::new(db),
+            blockchain,
+            config,
+            alerts,
+        }
+    }
+
+    /// Revenue over the most recent `periods` months or epochs, with the recorded treasury balance
+    /// next to the on-chain one. A treasury account that can't be read leaves the on-chain side
+    /// of the report empty rather than failing it.
+    ///
+    /// Drift beyond the configured tolerance raises an alert, which clears once the balances
+    /// agree again.
+    pub async fn report(&self, period: ReportPeriod, periods: Option<i64>) -> Result<TreasuryReport> {
+        let on_chain_balance = async {
+            let address = self.config.address.as_ref()?;
+            match self.blockchain.get_account_balance(address).await {
+                Ok(balance) => Some(balance),
+                Err(err) => {
+                    warn!("Failed to read the treasury balance of {}: {:#}", address, err);
+                    None
+                },
+            }
+        };
+        let (revenue, recorded_balance, on_chain_balance) = tokio::try_join!(
+            self.repository.revenue(period, periods),
+            self.repository.recorded_balance(),
+            async { Ok::<_, anyhow::Error>(on_chain_balance.await) },
+        )?;
+
+        let drift = match &on_chain_balance {
+            Some(balance) => {
+                let drift = balance - BigDecimal::from_str(&recorded_balance)?;
+                gauge!("treasury_balance_drift", drift.to_string().parse::<f64>().unwrap_or(0.0));
+
+                if drift.abs() > self.config.drift_alert_threshold {
+                    self.alerts
+                        .raise(Alert {
+                            key: "treasury_drift".to_string(),
+                            severity: AlertSeverity::Warning,
+                            title: "Treasury balance doesn't match recorded fees".to_string(),
+                            details: format!(
+                                "On-chain balance {} against {} recorded; drift {}",
+                                balance, recorded_balance, drift
+                            ),
+                        })
+                        .await;
+                } else {
+                    self.alerts.resolve("treasury_drift").await;
+                }
+                Some(drift.to_string())
+            },
+            None => None,
+        };
+
+        Ok(TreasuryReport {
+            period,
+            revenue,
+            recorded_balance,
+            on_chain_balance: on_chain_balance.map(|balance| balance.to_string()),
+            drift,
+            generated_at: Utc::now(),
+        })
+    }
+}
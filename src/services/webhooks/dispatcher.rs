@@ -0,0 +1,137 @@
+//! Fans protocol events out into per-endpoint webhook deliveries
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::info;
+
+use super::store::WebhookStore;
+use crate::models::blockchain_request::RequestType;
+use crate::models::kyc::KycVerification;
+use crate::models::user::{KycStatus, User};
+use crate::models::webhook::WebhookEventType;
+use crate::services::indexer::{EventType, IndexedEvent};
+
+/// Records webhook deliveries for protocol events
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    store: WebhookStore,
+}
+
+impl WebhookDispatcher {
+    /// Creates a new webhook dispatcher
+    pub fn new(db: PgPool) -> Self {
+        Self {
+            store: WebhookStore::new(db),
+        }
+    }
+
+    /// Queues a delivery of `data` to every active endpoint subscribed to `event_type`
+    pub async fn publish(&self, event_type: WebhookEventType, data: Value) -> Result<usize> {
+        let endpoints = self.store.list_active_endpoints().await?;
+        let event_name = event_type.to_string();
+
+        let mut queued = 0;
+        for endpoint in endpoints.iter().filter(|e| e.accepts(event_type)) {
+            self.store.create_delivery(endpoint.id, &event_name, &data).await?;
+            queued += 1;
+        }
+
+        if queued > 0 {
+            info!("Queued {} webhook deliveries for {}", queued, event_name);
+        }
+
+        Ok(queued)
+    }
+
+    /// Publishes the webhook event corresponding to an indexed chain event, if any
+    pub async fn publish_indexed_event(&self, event: &IndexedEvent) -> Result<usize> {
+        let event_type = match (event.event_type(), event.request_type.as_ref()) {
+            (EventType::BatchProcessing, Some(RequestType::Deposit)) => WebhookEventType::DepositProcessed,
+            (EventType::RequestExecution, _) => WebhookEventType::WithdrawalExecuted,
+            (EventType::EpochClosing, _) => WebhookEventType::EpochClosed,
+            _ => return Ok(0),
+        };
+
+        let data = json!({
+            "request_id": event.request_id.map(|id| id.to_string()),
+            "wallet_address": event.wallet_address,
+            "amount": event.amount,
+            "block_number": event.block_number,
+            "transaction_hash": event.transaction_hash,
+            "timestamp": event.timestamp,
+        });
+
+        self.publish(event_type, data).await
+    }
+
+    /// Publishes a change of a user's KYC state, from a provider review of `verification` or an
+    /// operator's decision when there is none
+    pub async fn publish_kyc_status_change(
+        &self,
+        user: &User,
+        previous_status: &KycStatus,
+        verification: Option<&KycVerification>,
+    ) -> Result<usize> {
+        let data = json!({
+            "user_id": user.id,
+            "wallet_address": user.wallet_address,
+            "kyc_status": user.kyc_status,
+            "previous_kyc_status": previous_status,
+            "source": if verification.is_some() { "provider" } else { "manual" },
+            "verification": verification.map(|verification| json!({
+                "id": verification.id,
+                "provider": verification.provider,
+                "level": verification.level,
+                "status": verification.status,
+                "rejection_reasons": verification.rejection_reasons,
+            })),
+            "timestamp": user.kyc_timestamp.unwrap_or(user.updated_at),
+        });
+
+        self.publish(WebhookEventType::KycStatusChanged, data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::webhook::CreateWebhookEndpointRequest;
+    use crate::test_support::UserBuilder;
+
+    #[sqlx::test]
+    async fn kyc_status_changes_reach_only_subscribed_endpoints(pool: PgPool) {
+        let store = WebhookStore::new(pool.clone());
+        let subscribe = |url: &str, event_types: Vec<WebhookEventType>| CreateWebhookEndpointRequest {
+            url: url.to_string(),
+            secret: None,
+            event_types,
+            description: None,
+        };
+        let kyc = store
+            .create_endpoint(&subscribe("https://hooks.example.com/kyc", vec![WebhookEventType::KycStatusChanged]), "whsec_test")
+            .await
+            .unwrap();
+        let all = store.create_endpoint(&subscribe("https://hooks.example.com/all", vec![]), "whsec_test").await.unwrap();
+        store
+            .create_endpoint(&subscribe("https://hooks.example.com/epochs", vec![WebhookEventType::EpochClosed]), "whsec_test")
+            .await
+            .unwrap();
+
+        let user = UserBuilder::new().approved().insert(&pool).await.unwrap();
+        let queued = WebhookDispatcher::new(pool)
+            .publish_kyc_status_change(&user, &KycStatus::Pending, None)
+            .await
+            .unwrap();
+        assert_eq!(queued, 2);
+
+        for endpoint in [kyc.id, all.id] {
+            let deliveries = store.list_deliveries(endpoint, 10, 0).await.unwrap();
+            assert_eq!(deliveries.len(), 1);
+            assert_eq!(deliveries[0].event_type, "kyc_status_changed");
+            assert_eq!(deliveries[0].payload["kyc_status"], "approved");
+            assert_eq!(deliveries[0].payload["previous_kyc_status"], "pending");
+            assert_eq!(deliveries[0].payload["source"], "manual");
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Outbound webhook subsystem for LSRWA Express
+//!
+//! Admins register endpoints with event-type filters and a shared secret. Protocol events are
+//! recorded as pending deliveries, and a background worker POSTs HMAC-signed JSON payloads to each
+//! endpoint, retrying with exponential backoff.
+//!
+//! Every delivery carries a sequence number that increases with each delivery recorded. The
+//! signature covers the timestamp header, so consumers can reject stale or replayed requests and
+//! drop sequences they already processed. Secrets are rotated with a grace period during which
+//! payloads are signed with both the old and new secret. Consumers that were down can fetch the
+//! deliveries they missed by sequence.
+//!
+//! Endpoint secrets are stored encrypted with a key from the secrets backend, and only returned
+//! when an endpoint is created or its secret rotated.
+
+mod dispatcher;
+mod sealing;
+mod signing;
+mod store;
+mod worker;
+
+pub use dispatcher::WebhookDispatcher;
+pub use sealing::{WebhookSecrets, SECRETS_KEY_NAME};
+pub use signing::{
+    sign_payload, sign_with_secrets, verify_signature, SignatureError, REPLAY_TOLERANCE_SECS, SEQUENCE_HEADER,
+    SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+pub use store::WebhookStore;
+pub use worker::DeliveryWorker;
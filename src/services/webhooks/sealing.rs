@@ -0,0 +1,160 @@
+//! Encryption of endpoint secrets at rest
+//!
+//! Endpoint secrets are stored as `sealed:` followed by base64 of a 12-byte nonce and the
+//! AES-256-GCM encrypted secret. The base64 key is the `WEBHOOK_SECRETS_KEY` secret, read through
+//! the [`SecretStore`] so it can be held in the secrets backend rather than the database.
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use secrecy::zeroize::Zeroizing;
+use secrecy::{ExposeSecret, SecretString};
+use tracing::info;
+
+use super::store::WebhookStore;
+use crate::models::webhook::WebhookEndpoint;
+use crate::services::secrets::SecretStore;
+
+/// Secret holding the key endpoint secrets are encrypted with
+pub const SECRETS_KEY_NAME: &str = "WEBHOOK_SECRETS_KEY";
+
+/// Prefix of stored secrets that are encrypted
+const SEALED_PREFIX: &str = "sealed:";
+
+/// Encrypts endpoint secrets before they're stored and decrypts them for signing
+#[derive(Clone)]
+pub struct WebhookSecrets {
+    secrets: SecretStore,
+}
+
+impl WebhookSecrets {
+    /// Creates a sealer reading its key from `secrets`
+    pub fn new(secrets: SecretStore) -> Self {
+        Self { secrets }
+    }
+
+    /// Encrypts a secret into its stored form
+    pub async fn seal(&self, secret: &str) -> Result<String> {
+        let key = self.key().await?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("Failed to generate a nonce"))?;
+
+        let mut sealed = Zeroizing::new(secret.as_bytes().to_vec());
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut *sealed)
+            .map_err(|_| anyhow!("Failed to encrypt webhook secret"))?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend_from_slice(&sealed);
+        Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(stored)))
+    }
+
+    /// Decrypts a stored secret
+    pub async fn open(&self, stored: &str) -> Result<SecretString> {
+        let encoded = stored
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| anyhow!("Webhook secret is stored unencrypted"))?;
+        let mut bytes = Zeroizing::new(BASE64.decode(encoded).context("Stored webhook secret is not valid base64")?);
+        if bytes.len() < NONCE_LEN {
+            return Err(anyhow!("Stored webhook secret is too short"));
+        }
+
+        let key = self.key().await?;
+        let (nonce, sealed) = bytes.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("Invalid nonce in webhook secret"))?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::empty(), sealed)
+            .map_err(|_| anyhow!("Failed to decrypt webhook secret; is {} correct?", SECRETS_KEY_NAME))?;
+
+        let secret = String::from_utf8(plaintext.to_vec()).context("Webhook secret is not valid UTF-8")?;
+        Ok(SecretString::new(secret))
+    }
+
+    /// Decrypts the secrets an endpoint signs with at `now`
+    pub async fn signing_secrets(&self, endpoint: &WebhookEndpoint, now: DateTime<Utc>) -> Result<Vec<SecretString>> {
+        let mut secrets = Vec::new();
+        for stored in endpoint.signing_secrets(now) {
+            secrets.push(self.open(stored).await?);
+        }
+
+        Ok(secrets)
+    }
+
+    /// Encrypts the secrets of endpoints registered before secrets were encrypted; returns how
+    /// many endpoints were updated
+    pub async fn seal_stored(&self, store: &WebhookStore) -> Result<usize> {
+        let mut sealed = 0;
+        for endpoint in store.list_endpoints().await? {
+            let previous = endpoint.previous_secret.as_deref().filter(|secret| !is_sealed(secret));
+            if is_sealed(&endpoint.secret) && previous.is_none() {
+                continue;
+            }
+
+            let secret = if is_sealed(&endpoint.secret) {
+                endpoint.secret.clone()
+            } else {
+                self.seal(&endpoint.secret).await?
+            };
+            let previous_secret = match previous {
+                Some(previous) => Some(self.seal(previous).await?),
+                None => endpoint.previous_secret.clone(),
+            };
+            store.replace_stored_secrets(endpoint.id, &endpoint.secret, &secret, previous_secret.as_deref()).await?;
+            sealed += 1;
+        }
+
+        if sealed > 0 {
+            info!("Encrypted the secrets of {} webhook endpoints", sealed);
+        }
+
+        Ok(sealed)
+    }
+
+    async fn key(&self) -> Result<LessSafeKey> {
+        let key = self.secrets.require(SECRETS_KEY_NAME).await?;
+        let bytes = Zeroizing::new(
+            BASE64
+                .decode(key.expose_secret().trim())
+                .with_context(|| format!("{} must be base64", SECRETS_KEY_NAME))?,
+        );
+        let key = UnboundKey::new(&AES_256_GCM, &bytes).map_err(|_| anyhow!("{} must be 32 bytes", SECRETS_KEY_NAME))?;
+
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+/// Whether a stored secret is encrypted
+fn is_sealed(stored: &str) -> bool {
+    stored.starts_with(SEALED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Environment, Settings};
+    use std::path::Path;
+    use std::time::Duration;
+
+    fn sealer(key: &str) -> WebhookSecrets {
+        let mut settings = Settings::from_files(Environment::Development, Path::new("/nonexistent")).unwrap();
+        settings.set(SECRETS_KEY_NAME, key.to_string());
+        WebhookSecrets::new(SecretStore::new(None, &settings, Duration::from_secs(60)))
+    }
+
+    #[tokio::test]
+    async fn secrets_are_stored_encrypted_and_opened_with_the_same_key() {
+        let secrets = sealer(&BASE64.encode([1u8; 32]));
+        let sealed = secrets.seal("whsec_0123456789abcdef").await.unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("whsec_"));
+        assert_eq!(secrets.open(&sealed).await.unwrap().expose_secret(), "whsec_0123456789abcdef");
+
+        assert!(sealer(&BASE64.encode([2u8; 32])).open(&sealed).await.is_err());
+        assert!(secrets.open("whsec_0123456789abcdef").await.is_err(), "plaintext secrets aren't used");
+    }
+}
@@ -0,0 +1,115 @@
+//! HMAC signing of webhook payloads
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+
+/// Header carrying the HMAC-SHA256 signatures, comma separated while a secret is being rotated
+pub const SIGNATURE_HEADER: &str = "X-LSRWA-Signature";
+
+/// Header carrying the unix timestamp the signature was computed at
+pub const TIMESTAMP_HEADER: &str = "X-LSRWA-Timestamp";
+
+/// Header carrying the delivery's sequence number
+pub const SEQUENCE_HEADER: &str = "X-LSRWA-Sequence";
+
+/// How far a signed timestamp may be from now before the request is treated as a replay
+pub const REPLAY_TOLERANCE_SECS: i64 = 300;
+
+/// Why a signed request was rejected
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("Signature timestamp is outside the {REPLAY_TOLERANCE_SECS} second tolerance")]
+    Expired,
+
+    #[error("Signature header is malformed")]
+    Malformed,
+
+    #[error("Signature does not match")]
+    Mismatch,
+}
+
+/// Signs `"{timestamp}.{body}"` with the endpoint secret and returns `sha256=<hex>`
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!("sha256={}", hex::encode(mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+/// Signs with each secret, joining the signatures so consumers holding either secret can verify
+pub fn sign_with_secrets(secrets: &[&str], timestamp: i64, body: &[u8]) -> String {
+    secrets
+        .iter()
+        .map(|secret| sign_payload(secret, timestamp, body))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Checks a signature header against any of the secrets, rejecting timestamps too far from `now`
+pub fn verify_signature(
+    secrets: &[&str],
+    header: &str,
+    timestamp: i64,
+    body: &[u8],
+    now: i64,
+) -> Result<(), SignatureError> {
+    if (now - timestamp).abs() > REPLAY_TOLERANCE_SECS {
+        return Err(SignatureError::Expired);
+    }
+
+    let signatures = header
+        .split(',')
+        .map(|signature| signature.trim().strip_prefix("sha256=").and_then(|hex| hex::decode(hex).ok()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(SignatureError::Malformed)?;
+
+    let matches = signatures.iter().any(|signature| {
+        secrets.iter().any(|secret| mac(secret, timestamp, body).verify_slice(signature).is_ok())
+    });
+    if matches {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn signatures_verify_with_the_secret_and_timestamp_they_cover() {
+        let body = br#"{"event_type":"epoch_closed"}"#;
+        let header = sign_payload("whsec_current", NOW, body);
+
+        assert_eq!(verify_signature(&["whsec_current"], &header, NOW, body, NOW + 10), Ok(()));
+        assert_eq!(verify_signature(&["whsec_other"], &header, NOW, body, NOW), Err(SignatureError::Mismatch));
+        assert_eq!(verify_signature(&["whsec_current"], &header, NOW + 1, body, NOW), Err(SignatureError::Mismatch));
+        assert_eq!(verify_signature(&["whsec_current"], &header, NOW, b"{}", NOW), Err(SignatureError::Mismatch));
+        assert_eq!(verify_signature(&["whsec_current"], "sha256=zz", NOW, body, NOW), Err(SignatureError::Malformed));
+        assert_eq!(
+            verify_signature(&["whsec_current"], &header, NOW, body, NOW + REPLAY_TOLERANCE_SECS + 1),
+            Err(SignatureError::Expired)
+        );
+    }
+
+    #[test]
+    fn rotated_secrets_sign_with_both_until_consumers_switch() {
+        let body = b"payload";
+        let header = sign_with_secrets(&["whsec_new", "whsec_old"], NOW, body);
+
+        assert_eq!(header.split(',').count(), 2);
+        assert_eq!(verify_signature(&["whsec_old"], &header, NOW, body, NOW), Ok(()));
+        assert_eq!(verify_signature(&["whsec_new"], &header, NOW, body, NOW), Ok(()));
+    }
+}
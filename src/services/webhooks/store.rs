@@ -0,0 +1,425 @@
+//! Persistence for webhook endpoints and deliveries
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::webhook::{
+    CreateWebhookEndpointRequest, UpdateWebhookEndpointRequest, WebhookDelivery, WebhookEndpoint,
+};
+
+const ENDPOINT_COLUMNS: &str =
+    "id, url, secret, event_types, description, is_active, previous_secret, previous_secret_expires_at, \
+     created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "id, sequence, endpoint_id, event_type, payload, status, attempts, next_attempt_at, \
+     last_status_code, last_error, delivered_at, created_at, updated_at";
+
+/// Database access for the webhook subsystem
+#[derive(Clone)]
+pub struct WebhookStore {
+    db: PgPool,
+}
+
+impl WebhookStore {
+    /// Creates a new webhook store
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Registers a new endpoint with its stored (sealed) secret
+    pub async fn create_endpoint(&self, request: &CreateWebhookEndpointRequest, secret: &str) -> Result<WebhookEndpoint> {
+        let event_types: Vec<String> = request.event_types.iter().map(|t| t.to_string()).collect();
+
+        sqlx::query_as::<_, WebhookEndpoint>(&format!(
+            r#"
+            INSERT INTO lsrwa_express.webhook_endpoints (url, secret, event_types, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING {}
+            "#,
+            ENDPOINT_COLUMNS
+        ))
+        .bind(&request.url)
+        .bind(secret)
+        .bind(&event_types)
+        .bind(&request.description)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to insert webhook endpoint")
+    }
+
+    /// Updates an endpoint, leaving unspecified fields unchanged; `secret` is the sealed
+    /// replacement for the request's secret
+    pub async fn update_endpoint(
+        &self,
+        id: Uuid,
+        request: &UpdateWebhookEndpointRequest,
+        secret: Option<&str>,
+    ) -> Result<Option<WebhookEndpoint>> {
+        let event_types: Option<Vec<String>> = request
+            .event_types
+            .as_ref()
+            .map(|types| types.iter().map(|t| t.to_string()).collect());
+
+        sqlx::query_as::<_, WebhookEndpoint>(&format!(
+            r#"
+            UPDATE lsrwa_express.webhook_endpoints
+            SET url = COALESCE($2, url),
+                secret = COALESCE($3, secret),
+                event_types = COALESCE($4, event_types),
+                description = COALESCE($5, description),
+                is_active = COALESCE($6, is_active)
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            ENDPOINT_COLUMNS
+        ))
+        .bind(id)
+        .bind(&request.url)
+        .bind(secret)
+        .bind(&event_types)
+        .bind(&request.description)
+        .bind(request.is_active)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to update webhook endpoint")
+    }
+
+    /// Replaces an endpoint's secret. The old one keeps signing deliveries until `previous_expires_at`,
+    /// or stops straight away when that is `None`; a secret still in its grace period is dropped.
+    pub async fn rotate_secret(
+        &self,
+        id: Uuid,
+        secret: &str,
+        previous_expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>(&format!(
+            r#"
+            UPDATE lsrwa_express.webhook_endpoints
+            SET previous_secret = CASE WHEN $3::timestamptz IS NULL THEN NULL ELSE secret END,
+                previous_secret_expires_at = $3,
+                secret = $2
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            ENDPOINT_COLUMNS
+        ))
+        .bind(id)
+        .bind(secret)
+        .bind(previous_expires_at)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to rotate webhook endpoint secret")
+    }
+
+    /// Overwrites the stored form of an endpoint's secrets, unless its secret has changed from
+    /// `current` since it was read
+    pub async fn replace_stored_secrets(
+        &self,
+        id: Uuid,
+        current: &str,
+        secret: &str,
+        previous_secret: Option<&str>,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE lsrwa_express.webhook_endpoints
+            SET secret = $3, previous_secret = $4
+            WHERE id = $1 AND secret = $2
+            "#,
+        )
+        .bind(id)
+        .bind(current)
+        .bind(secret)
+        .bind(previous_secret)
+        .execute(&self.db)
+        .await
+        .context("Failed to replace stored webhook endpoint secrets")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes an endpoint and its delivery log
+    pub async fn delete_endpoint(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM lsrwa_express.webhook_endpoints WHERE id = $1")
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .context("Failed to delete webhook endpoint")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Gets an endpoint by ID
+    pub async fn get_endpoint(&self, id: Uuid) -> Result<Option<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>(&format!(
+            "SELECT {} FROM lsrwa_express.webhook_endpoints WHERE id = $1",
+            ENDPOINT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch webhook endpoint")
+    }
+
+    /// Lists all endpoints
+    pub async fn list_endpoints(&self) -> Result<Vec<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>(&format!(
+            "SELECT {} FROM lsrwa_express.webhook_endpoints ORDER BY created_at",
+            ENDPOINT_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list webhook endpoints")
+    }
+
+    /// Lists active endpoints
+    pub async fn list_active_endpoints(&self) -> Result<Vec<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>(&format!(
+            "SELECT {} FROM lsrwa_express.webhook_endpoints WHERE is_active ORDER BY created_at",
+            ENDPOINT_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list active webhook endpoints")
+    }
+
+    /// Records a pending delivery for an endpoint
+    pub async fn create_delivery(&self, endpoint_id: Uuid, event_type: &str, payload: &Value) -> Result<Uuid> {
+        let (id,) = sqlx::query_as::<_, (Uuid,)>(
+            r#"
+            INSERT INTO lsrwa_express.webhook_deliveries (endpoint_id, event_type, payload)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            "#,
+        )
+        .bind(endpoint_id)
+        .bind(event_type)
+        .bind(payload)
+        .fetch_one(&self.db)
+        .await
+        .context("Failed to insert webhook delivery")?;
+
+        Ok(id)
+    }
+
+    /// Gets a delivery by ID
+    pub async fn get_delivery(&self, id: Uuid) -> Result<Option<WebhookDelivery>> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            "SELECT {} FROM lsrwa_express.webhook_deliveries WHERE id = $1",
+            DELIVERY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.db)
+        .await
+        .context("Failed to fetch webhook delivery")
+    }
+
+    /// Lists the most recent deliveries for an endpoint
+    pub async fn list_deliveries(&self, endpoint_id: Uuid, limit: i64, offset: i64) -> Result<Vec<WebhookDelivery>> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.webhook_deliveries
+            WHERE endpoint_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(endpoint_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list webhook deliveries")
+    }
+
+    /// Lists an endpoint's deliveries with a sequence after `after`, oldest first
+    pub async fn list_deliveries_after(&self, endpoint_id: Uuid, after: i64, limit: i64) -> Result<Vec<WebhookDelivery>> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            SELECT {} FROM lsrwa_express.webhook_deliveries
+            WHERE endpoint_id = $1 AND sequence > $2
+            ORDER BY sequence
+            LIMIT $3
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(endpoint_id)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to list webhook deliveries after cursor")
+    }
+
+    /// Claims up to `limit` pending deliveries that are due, skipping rows locked by other workers
+    pub async fn claim_due_deliveries(&self, limit: i64) -> Result<Vec<WebhookDelivery>> {
+        sqlx::query_as::<_, WebhookDelivery>(&format!(
+            r#"
+            UPDATE lsrwa_express.webhook_deliveries
+            SET next_attempt_at = NOW() + INTERVAL '5 minutes'
+            WHERE id IN (
+                SELECT id FROM lsrwa_express.webhook_deliveries
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            DELIVERY_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .context("Failed to claim due webhook deliveries")
+    }
+
+    /// Marks a delivery as successfully delivered
+    pub async fn mark_delivered(&self, id: Uuid, status_code: i32) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, last_status_code = $2,
+                last_error = NULL, delivered_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(status_code)
+        .execute(&self.db)
+        .await
+        .context("Failed to mark webhook delivery as delivered")?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt, scheduling a retry or marking the delivery as failed
+    pub async fn record_failure(
+        &self,
+        id: Uuid,
+        status_code: Option<i32>,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE lsrwa_express.webhook_deliveries
+            SET attempts = attempts + 1,
+                last_status_code = $2,
+                last_error = $3,
+                status = CASE WHEN $4::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = COALESCE($4, next_attempt_at)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(status_code)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.db)
+        .await
+        .context("Failed to record webhook delivery failure")?;
+
+        Ok(())
+    }
+
+    /// Resets a delivery so the worker picks it up again immediately
+    pub async fn requeue_delivery(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE lsrwa_express.webhook_deliveries
+            SET status = 'pending', next_attempt_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.db)
+        .await
+        .context("Failed to requeue webhook delivery")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::webhook::{DeliveryStatus, WebhookEventType};
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn endpoint(url: &str, event_types: Vec<WebhookEventType>) -> CreateWebhookEndpointRequest {
+        CreateWebhookEndpointRequest {
+            url: url.to_string(),
+            secret: None,
+            event_types,
+            description: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn endpoints_are_updated_in_place_and_rotated_with_a_grace_period(pool: PgPool) {
+        let store = WebhookStore::new(pool);
+        let created = store
+            .create_endpoint(&endpoint("https://hooks.example.com/a", vec![WebhookEventType::EpochClosed]), "whsec_first")
+            .await
+            .unwrap();
+        assert_eq!(created.event_types, ["epoch_closed"]);
+
+        let paused = UpdateWebhookEndpointRequest {
+            url: None,
+            secret: None,
+            event_types: None,
+            description: None,
+            is_active: Some(false),
+        };
+        let updated = store.update_endpoint(created.id, &paused, None).await.unwrap().unwrap();
+        assert_eq!((updated.url.as_str(), updated.is_active), ("https://hooks.example.com/a", false));
+        assert!(store.list_active_endpoints().await.unwrap().is_empty());
+
+        let expires_at = Utc::now() + Duration::hours(1);
+        let rotated = store.rotate_secret(created.id, "whsec_second", Some(expires_at)).await.unwrap().unwrap();
+        assert_eq!(rotated.signing_secrets(Utc::now()), ["whsec_second", "whsec_first"]);
+        assert_eq!(rotated.signing_secrets(expires_at + Duration::seconds(1)), ["whsec_second"]);
+
+        let immediate = store.rotate_secret(created.id, "whsec_third", None).await.unwrap().unwrap();
+        assert_eq!(immediate.previous_secret, None);
+
+        assert!(store.delete_endpoint(created.id).await.unwrap());
+        assert!(store.get_endpoint(created.id).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn deliveries_are_claimed_once_and_retried_until_they_fail(pool: PgPool) {
+        let store = WebhookStore::new(pool);
+        let endpoint = store.create_endpoint(&endpoint("https://hooks.example.com/b", vec![]), "whsec_first").await.unwrap();
+        let first = store.create_delivery(endpoint.id, "epoch_closed", &json!({ "epoch_id": 1 })).await.unwrap();
+        let second = store.create_delivery(endpoint.id, "epoch_closed", &json!({ "epoch_id": 2 })).await.unwrap();
+
+        let claimed = store.claim_due_deliveries(10).await.unwrap();
+        assert_eq!(claimed.len(), 2);
+        assert!(store.claim_due_deliveries(10).await.unwrap().is_empty(), "claimed deliveries aren't due again");
+
+        store.record_failure(first, Some(500), "Endpoint responded with 500", Some(Utc::now())).await.unwrap();
+        let retrying = store.get_delivery(first).await.unwrap().unwrap();
+        assert_eq!((retrying.status, retrying.attempts, retrying.last_status_code), (DeliveryStatus::Pending, 1, Some(500)));
+
+        store.record_failure(first, None, "Connection refused", None).await.unwrap();
+        let failed = store.get_delivery(first).await.unwrap().unwrap();
+        assert_eq!((failed.status, failed.attempts), (DeliveryStatus::Failed, 2));
+        assert!(store.requeue_delivery(first).await.unwrap());
+        assert_eq!(store.get_delivery(first).await.unwrap().unwrap().status, DeliveryStatus::Pending);
+
+        store.mark_delivered(second, 204).await.unwrap();
+        let delivered = store.get_delivery(second).await.unwrap().unwrap();
+        assert_eq!((delivered.status, delivered.last_error), (DeliveryStatus::Delivered, None));
+        assert!(delivered.delivered_at.is_some());
+
+        let after_first = store.list_deliveries_after(endpoint.id, retrying.sequence, 10).await.unwrap();
+        assert_eq!(after_first.iter().map(|delivery| delivery.id).collect::<Vec<_>>(), [second]);
+    }
+}
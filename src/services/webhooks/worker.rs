@@ -0,0 +1,173 @@
+//! Background worker delivering pending webhooks
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time;
+use tracing::{error, info, warn};
+
+use super::sealing::WebhookSecrets;
+use super::signing::{sign_with_secrets, SEQUENCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use super::store::WebhookStore;
+use crate::services::http_client::HttpClient;
+use crate::services::shutdown::Shutdown;
+use crate::models::webhook::{WebhookDelivery, WebhookEnvelope};
+
+/// Maximum backoff between delivery attempts
+const MAX_BACKOFF_SECS: u64 = 6 * 60 * 60;
+
+/// Worker that POSTs pending deliveries and schedules retries
+pub struct DeliveryWorker {
+    /// Webhook persistence
+    store: WebhookStore,
+    /// Decrypts endpoint secrets for signing
+    secrets: WebhookSecrets,
+    /// HTTP client used for deliveries
+    client: HttpClient,
+    /// Maximum number of delivery attempts before giving up
+    max_attempts: u32,
+    /// Base retry delay in seconds, doubled on each attempt
+    base_retry_delay: u64,
+    /// Polling interval in seconds
+    polling_interval: u64,
+    /// Maximum number of deliveries sent per poll
+    batch_size: i64,
+}
+
+impl DeliveryWorker {
+    /// Creates a new delivery worker
+    pub fn new(
+        db: PgPool,
+        secrets: WebhookSecrets,
+        client: HttpClient,
+        max_attempts: u32,
+        base_retry_delay: u64,
+        polling_interval: u64,
+        batch_size: i64,
+    ) -> Self {
+        Self {
+            store: WebhookStore::new(db),
+            secrets,
+            client,
+            max_attempts,
+            base_retry_delay,
+            polling_interval,
+            batch_size,
+        }
+    }
+
+    /// Runs the delivery loop until shutdown
+    pub async fn start(&self, shutdown: Shutdown) -> Result<()> {
+        info!("Starting webhook delivery worker with polling interval {} seconds", self.polling_interval);
+
+        let mut interval = time::interval(Duration::from_secs(self.polling_interval));
+
+        while shutdown.tick(&mut interval).await {
+            match self.deliver_due().await {
+                Ok(count) => {
+                    if count > 0 {
+                        info!("Attempted {} webhook deliveries", count);
+                    }
+                },
+                Err(err) => {
+                    error!("Failed to process webhook deliveries: {}", err);
+                }
+            }
+        }
+
+        info!("Webhook delivery worker stopped");
+        Ok(())
+    }
+
+    /// Attempts every delivery that is currently due
+    async fn deliver_due(&self) -> Result<usize> {
+        let deliveries = self.store.claim_due_deliveries(self.batch_size).await?;
+        let count = deliveries.len();
+
+        for delivery in deliveries {
+            if let Err(err) = self.attempt(&delivery).await {
+                error!("Failed to record webhook delivery {}: {}", delivery.id, err);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Sends a single delivery and records the outcome
+    async fn attempt(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let endpoint = match self.store.get_endpoint(delivery.endpoint_id).await? {
+            Some(endpoint) if endpoint.is_active => endpoint,
+            _ => {
+                return self.store
+                    .record_failure(delivery.id, None, "Endpoint is inactive or deleted", None)
+                    .await;
+            }
+        };
+
+        let body = serde_json::to_vec(&WebhookEnvelope::from(delivery))
+            .context("Failed to serialize webhook payload")?;
+
+        let now = Utc::now();
+        let timestamp = now.timestamp();
+        let secrets = self.secrets.signing_secrets(&endpoint, now).await
+            .with_context(|| format!("Failed to decrypt the secrets of webhook endpoint {}", endpoint.id))?;
+        let secrets: Vec<&str> = secrets.iter().map(|secret| secret.expose_secret().as_str()).collect();
+        let signature = sign_with_secrets(&secrets, timestamp, &body);
+
+        let request = self.client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header("X-LSRWA-Event", &delivery.event_type)
+            .header("X-LSRWA-Delivery", delivery.id.to_string())
+            .header(SEQUENCE_HEADER, delivery.sequence.to_string())
+            .body(body);
+        let result = self.client.send(request).await;
+
+        let (status_code, error) = match result {
+            Ok(response) if response.status().is_success() => {
+                return self.store.mark_delivered(delivery.id, response.status().as_u16() as i32).await;
+            },
+            Ok(response) => (
+                Some(response.status().as_u16() as i32),
+                format!("Endpoint responded with {}", response.status()),
+            ),
+            Err(err) => (None, err.to_string()),
+        };
+
+        let attempts = delivery.attempts as u32 + 1;
+        let next_attempt_at = if attempts >= self.max_attempts {
+            warn!("Webhook delivery {} failed permanently after {} attempts: {}", delivery.id, attempts, error);
+            None
+        } else {
+            let delay = retry_delay(self.base_retry_delay, attempts);
+            Some(Utc::now() + chrono::Duration::seconds(delay as i64))
+        };
+
+        self.store.record_failure(delivery.id, status_code, &error, next_attempt_at).await
+    }
+}
+
+/// Exponential backoff after the given attempt number, starting from `base_retry_delay` seconds
+fn retry_delay(base_retry_delay: u64, attempts: u32) -> u64 {
+    base_retry_delay
+        .saturating_mul(2u64.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_BACKOFF_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_back_off_exponentially_up_to_the_cap() {
+        let delays: Vec<u64> = (1..=4).map(|attempts| retry_delay(30, attempts)).collect();
+        assert_eq!(delays, [30, 60, 120, 240]);
+
+        assert_eq!(retry_delay(30, 20), MAX_BACKOFF_SECS);
+        assert_eq!(retry_delay(30, u32::MAX), MAX_BACKOFF_SECS);
+    }
+}
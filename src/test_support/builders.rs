@@ -0,0 +1,362 @@
+//! Builders for the rows tests need, filled with fake values until told otherwise
+//!
+//! `build` returns the request a repository takes; `insert` writes it through that repository
+//! and returns the stored row.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::fake;
+use crate::db::{BlockchainRequestRepository, EpochRepository, RewardRepository, UserRepository};
+use crate::models::blockchain_request::{BlockchainRequest, NewBlockchainRequest, RequestType};
+use crate::models::epoch::Epoch;
+use crate::models::request_status::RequestStatus;
+use crate::models::reward::{CreateUserRewardRequest, UserReward};
+use crate::models::user::{CreateUserRequest, KycStatus, UpdateUserRequest, User};
+use crate::models::wallet::WalletAddress;
+use crate::services::indexer::{BorrowRequested, EventPayload, EventQueue, IndexedEvent, ProcessingStatus, RequestEvent};
+
+/// A user with a fake wallet, no email and pending KYC
+#[derive(Debug, Clone)]
+pub struct UserBuilder {
+    wallet_address: WalletAddress,
+    email: Option<String>,
+    referrer_wallet: Option<WalletAddress>,
+    kyc_status: KycStatus,
+}
+
+impl Default for UserBuilder {
+    fn default() -> Self {
+        Self {
+            wallet_address: fake::wallet_address(),
+            email: None,
+            referrer_wallet: None,
+            kyc_status: KycStatus::Pending,
+        }
+    }
+}
+
+impl UserBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wallet(mut self, wallet_address: &WalletAddress) -> Self {
+        self.wallet_address = wallet_address.clone();
+        self
+    }
+
+    /// Gives the user a fake email address
+    pub fn with_email(mut self) -> Self {
+        self.email = Some(fake::email());
+        self
+    }
+
+    pub fn referred_by(mut self, referrer_wallet: &WalletAddress) -> Self {
+        self.referrer_wallet = Some(referrer_wallet.clone());
+        self
+    }
+
+    pub fn kyc(mut self, kyc_status: KycStatus) -> Self {
+        self.kyc_status = kyc_status;
+        self
+    }
+
+    /// KYC approved at the Basic level
+    pub fn approved(self) -> Self {
+        self.kyc(KycStatus::Approved)
+    }
+
+    pub fn build(&self) -> CreateUserRequest {
+        CreateUserRequest {
+            wallet_address: self.wallet_address.clone(),
+            email: self.email.clone(),
+            referrer_wallet: self.referrer_wallet.clone(),
+        }
+    }
+
+    /// Registers the user, then records a KYC decision when it isn't pending
+    pub async fn insert(&self, pool: &PgPool) -> Result<User> {
+        let users = UserRepository::new(pool.clone());
+        let user = users.create(&self.build()).await?;
+        if self.kyc_status == KycStatus::Pending {
+            return Ok(user);
+        }
+
+        users
+            .update(user.id, &UpdateUserRequest {
+                email: None,
+                kyc_status: Some(self.kyc_status.clone()),
+                kyc_timestamp: Some(Utc::now()),
+                kyc_reference: Some(format!("applicant-{}", Uuid::new_v4())),
+            })
+            .await?
+            .context("User disappeared before its KYC was recorded")
+    }
+}
+
+/// A confirmed on-chain request with a fake wallet, amount, block and transaction
+#[derive(Debug, Clone)]
+pub struct RequestBuilder {
+    request: NewBlockchainRequest,
+}
+
+impl RequestBuilder {
+    pub fn new(request_type: RequestType) -> Self {
+        let collateral_amount = (request_type == RequestType::Borrow).then(|| fake::amount() * 2.0);
+        Self {
+            request: NewBlockchainRequest {
+                request_type,
+                on_chain_id: fake::on_chain_id(),
+                wallet_address: fake::wallet_address(),
+                amount: fake::amount(),
+                collateral_amount,
+                timestamp: Utc::now().naive_utc(),
+                status: RequestStatus::Confirmed,
+                block_number: fake::block_number(),
+                transaction_hash: fake::transaction_hash(),
+                asset: None,
+            },
+        }
+    }
+
+    pub fn deposit() -> Self {
+        Self::new(RequestType::Deposit)
+    }
+
+    pub fn withdrawal() -> Self {
+        Self::new(RequestType::Withdrawal)
+    }
+
+    /// A borrow with collateral worth twice a fake amount
+    pub fn borrow() -> Self {
+        Self::new(RequestType::Borrow)
+    }
+
+    pub fn wallet(mut self, wallet_address: &WalletAddress) -> Self {
+        self.request.wallet_address = wallet_address.clone();
+        self
+    }
+
+    pub fn amount(mut self, amount: f64) -> Self {
+        self.request.amount = amount;
+        self
+    }
+
+    pub fn collateral(mut self, collateral_amount: f64) -> Self {
+        self.request.collateral_amount = Some(collateral_amount);
+        self
+    }
+
+    pub fn on_chain_id(mut self, on_chain_id: i64) -> Self {
+        self.request.on_chain_id = on_chain_id;
+        self
+    }
+
+    pub fn submitted_at(mut self, timestamp: NaiveDateTime) -> Self {
+        self.request.timestamp = timestamp;
+        self
+    }
+
+    pub fn status(mut self, status: RequestStatus) -> Self {
+        self.request.status = status;
+        self
+    }
+
+    pub fn build(&self) -> NewBlockchainRequest {
+        self.request.clone()
+    }
+
+    /// Records the request, linked to the user registered for its wallet if there is one
+    pub async fn insert(&self, pool: &PgPool) -> Result<BlockchainRequest> {
+        BlockchainRequestRepository::new(pool.clone()).insert(&self.request).await
+    }
+}
+
+/// The next epoch, left active unless it is closed or completed
+#[derive(Debug, Clone, Default)]
+pub struct EpochBuilder {
+    end_timestamp: Option<DateTime<Utc>>,
+    processing_tx_hash: Option<String>,
+}
+
+impl EpochBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ends the epoch, leaving it processing
+    pub fn closed_at(mut self, end_timestamp: DateTime<Utc>) -> Self {
+        self.end_timestamp = Some(end_timestamp);
+        self
+    }
+
+    /// Ends the epoch now and completes it with a fake closing transaction
+    pub fn completed(mut self) -> Self {
+        self.end_timestamp.get_or_insert_with(Utc::now);
+        self.processing_tx_hash = Some(fake::transaction_hash());
+        self
+    }
+
+    /// Builds on the active epoch, opening one when none is active: an active epoch is returned
+    /// as is, and a closed or completed one is ended so the next insert opens a new epoch
+    pub async fn insert(&self, pool: &PgPool) -> Result<Epoch> {
+        let epochs = EpochRepository::new(pool.clone());
+        let id = EpochRepository::open_next_in(pool).await?;
+
+        if let Some(end_timestamp) = self.end_timestamp {
+            epochs.close(id, end_timestamp).await?;
+        }
+        if let Some(processing_tx_hash) = &self.processing_tx_hash {
+            EpochRepository::complete_in(pool, id, Some(processing_tx_hash)).await?;
+        }
+
+        epochs.get(id).await?.context("Epoch disappeared after it was opened")
+    }
+}
+
+/// A pending reward of a fake amount at 5% APR, in the first epoch
+#[derive(Debug, Clone)]
+pub struct RewardBuilder {
+    request: CreateUserRewardRequest,
+}
+
+impl RewardBuilder {
+    pub fn new(user_id: Uuid) -> Self {
+        Self {
+            request: CreateUserRewardRequest {
+                user_id,
+                epoch_id: 1,
+                amount: fake::decimal_amount(),
+                apr_bps: 500,
+            },
+        }
+    }
+
+    pub fn epoch(mut self, epoch_id: i32) -> Self {
+        self.request.epoch_id = epoch_id;
+        self
+    }
+
+    pub fn amount(mut self, amount: impl Into<String>) -> Self {
+        self.request.amount = amount.into();
+        self
+    }
+
+    pub fn apr_bps(mut self, apr_bps: i32) -> Self {
+        self.request.apr_bps = apr_bps;
+        self
+    }
+
+    pub fn build(&self) -> CreateUserRewardRequest {
+        self.request.clone()
+    }
+
+    /// Records the reward for its epoch; a user has one reward per epoch
+    pub async fn insert(&self, pool: &PgPool) -> Result<UserReward> {
+        let rewards = RewardRepository::new(pool.clone());
+        rewards.insert_epoch_rewards(self.request.epoch_id, std::slice::from_ref(&self.request)).await?;
+        rewards
+            .list_by_user(self.request.user_id)
+            .await?
+            .into_iter()
+            .find(|reward| reward.epoch_id == self.request.epoch_id)
+            .context("Reward was not recorded")
+    }
+}
+
+/// A pending indexed event in a fake block and transaction
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    event: IndexedEvent,
+}
+
+impl EventBuilder {
+    pub fn new(payload: EventPayload) -> Self {
+        Self {
+            event: EventQueue::create_event(payload, fake::block_number() as u64, fake::transaction_hash(), Utc::now()),
+        }
+    }
+
+    /// A request event of the matching type, for a fake wallet and amount, with fake collateral
+    /// for borrows
+    pub fn request(request_type: RequestType, request_id: u128) -> Self {
+        let request = RequestEvent {
+            request_id,
+            wallet_address: fake::wallet_address().to_string(),
+            amount: fake::amount().to_string(),
+        };
+        Self::new(match request_type {
+            RequestType::Deposit => EventPayload::DepositRequest(request),
+            RequestType::Withdrawal => EventPayload::WithdrawalRequest(request),
+            RequestType::Borrow => EventPayload::BorrowRequest(BorrowRequested {
+                collateral: (fake::amount() * 2.0).to_string(),
+                request,
+            }),
+        })
+    }
+
+    pub fn block(mut self, block_number: u64) -> Self {
+        self.event.block_number = block_number;
+        self
+    }
+
+    /// Marks the event as already attempted, with the given outcome
+    pub fn status(mut self, status: ProcessingStatus) -> Self {
+        self.event.status = status;
+        self.event.attempts = 1;
+        self.event.last_attempt = Some(Utc::now());
+        if status == ProcessingStatus::Failed {
+            self.event.error_message = Some("Handler failed".to_string());
+        }
+        self
+    }
+
+    pub fn build(&self) -> IndexedEvent {
+        self.event.clone()
+    }
+
+    /// Stores the event in the event queue table, without handing it to a processor
+    pub async fn insert(&self, pool: &PgPool) -> Result<IndexedEvent> {
+        EventQueue::store_in(pool, &self.event).await?;
+        Ok(self.event.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::epoch::EpochStatus;
+
+    #[sqlx::test]
+    async fn builders_insert_linked_rows(pool: PgPool) {
+        let referrer = UserBuilder::new().approved().insert(&pool).await.unwrap();
+        let user = UserBuilder::new().with_email().referred_by(&referrer.wallet_address).insert(&pool).await.unwrap();
+        assert_eq!(referrer.kyc_status, KycStatus::Approved);
+        assert_eq!(user.kyc_status, KycStatus::Pending);
+        assert_ne!(user.wallet_address, referrer.wallet_address);
+
+        let request = RequestBuilder::borrow().wallet(&user.wallet_address).insert(&pool).await.unwrap();
+        assert_eq!(request.user_id, Some(user.id));
+        assert!(request.collateral_amount.is_some());
+
+        let completed = EpochBuilder::new().completed().insert(&pool).await.unwrap();
+        assert_eq!(completed.status, EpochStatus::Completed);
+        let active = EpochBuilder::new().insert(&pool).await.unwrap();
+        assert_eq!(active.status, EpochStatus::Active);
+        assert!(active.id > completed.id);
+
+        let reward = RewardBuilder::new(user.id).epoch(completed.id).insert(&pool).await.unwrap();
+        assert_eq!(reward.epoch_id, completed.id);
+
+        let event = EventBuilder::request(RequestType::Borrow, 7).status(ProcessingStatus::Failed).insert(&pool).await.unwrap();
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM lsrwa_express.event_queue WHERE id = $1")
+            .bind(&event.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+    }
+}
@@ -0,0 +1,70 @@
+//! Realistic fake values
+//!
+//! Every call draws from one process-wide SplitMix64 stream, so values never repeat within a
+//! test run and wallets, hashes and IDs can be generated freely without clashing on unique
+//! constraints.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::db::seed::{splitmix, SPLITMIX_INCREMENT};
+use crate::models::wallet::WalletAddress;
+
+/// First block number handed out; block numbers only grow
+const BASE_BLOCK: u64 = 1_000_000;
+
+static STATE: AtomicU64 = AtomicU64::new(0);
+static NEXT_BLOCK: AtomicU64 = AtomicU64::new(BASE_BLOCK);
+static NEXT_ON_CHAIN_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_u64() -> u64 {
+    splitmix(STATE.fetch_add(SPLITMIX_INCREMENT, Ordering::Relaxed).wrapping_add(SPLITMIX_INCREMENT))
+}
+
+/// Uniform value in `low..=high`
+fn range(low: u64, high: u64) -> u64 {
+    low + next_u64() % (high - low + 1)
+}
+
+fn bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    for chunk in bytes.chunks_mut(8) {
+        chunk.copy_from_slice(&next_u64().to_le_bytes()[..chunk.len()]);
+    }
+    bytes
+}
+
+/// Address of a random account
+pub fn wallet_address() -> WalletAddress {
+    WalletAddress::from_public_key(bytes::<32>())
+}
+
+/// `0x`-prefixed 32-byte hash, as transaction and block hashes are written
+pub fn transaction_hash() -> String {
+    format!("0x{}", hex::encode(bytes::<32>()))
+}
+
+/// Email address on a reserved domain
+pub fn email() -> String {
+    format!("user{:08x}@example.test", next_u64() as u32)
+}
+
+/// Amount between 10 and 10,000 tokens, in whole hundredths
+pub fn amount() -> f64 {
+    range(1_000, 1_000_000) as f64 / 100.0
+}
+
+/// NUMERIC literal between 0.000001 and 100 tokens with six decimals, as rewards are written
+pub fn decimal_amount() -> String {
+    let micros = range(1, 100_000_000);
+    format!("{}.{:06}", micros / 1_000_000, micros % 1_000_000)
+}
+
+/// Next block number; later calls return later blocks
+pub fn block_number() -> i64 {
+    NEXT_BLOCK.fetch_add(range(1, 20), Ordering::Relaxed) as i64
+}
+
+/// Next contract request ID
+pub fn on_chain_id() -> i64 {
+    NEXT_ON_CHAIN_ID.fetch_add(1, Ordering::Relaxed) as i64
+}
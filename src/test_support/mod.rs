@@ -0,0 +1,10 @@
+//! Fixtures for tests: fake values and builders for users, requests, epochs, rewards and
+//! indexed events that insert through the repositories
+//!
+//! Compiled for the crate's own tests, and for integration tests through the `test-support`
+//! feature, which the crate enables on itself as a dev-dependency.
+
+pub mod builders;
+pub mod fake;
+
+pub use builders::{EpochBuilder, EventBuilder, RequestBuilder, RewardBuilder, UserBuilder};
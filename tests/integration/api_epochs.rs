@@ -0,0 +1,134 @@
+//! Epoch listing, lookups and close sequences, end to end against Postgres
+
+use axum::http::StatusCode;
+
+use crate::common::TestApp;
+use lsrwa_express_rust::test_support::EpochBuilder;
+
+#[tokio::test]
+async fn the_current_epoch_is_not_taken_for_an_epoch_id() {
+    let app = TestApp::spawn().await;
+
+    // The chain hasn't reported an epoch yet, so the first one is current; "current" must not be
+    // parsed as an ID
+    let (status, body) = app.get("/api/v1/epochs/current").await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+    assert_eq!(body["error"]["message"], "Epoch with ID 1 not found");
+}
+
+#[tokio::test]
+async fn epochs_are_listed_a_page_at_a_time_by_status() {
+    let app = TestApp::spawn().await;
+    let completed = EpochBuilder::new().completed().insert(&app.pool).await.unwrap();
+    let active = EpochBuilder::new().insert(&app.pool).await.unwrap();
+
+    let (status, body) = app.get("/api/v1/epochs?limit=1").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let listed = body.as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["id"], active.id);
+    assert!(listed[0]["progress_percent"].as_f64().is_some());
+
+    let (status, body) = app.get("/api/v1/epochs?status=completed&offset=0").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let listed = body.as_array().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["id"], completed.id);
+    assert_eq!(listed[0]["progress_percent"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn listed_epochs_are_revalidated_against_every_field() {
+    let app = TestApp::spawn().await;
+    let completed = EpochBuilder::new().completed().insert(&app.pool).await.unwrap();
+    let path = "/api/v1/epochs?status=completed";
+
+    let (status, etag) = app.revalidate(path, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let etag = etag.expect("the list carries an ETag");
+
+    let (status, unchanged) = app.revalidate(path, Some(&etag)).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(unchanged.as_deref(), Some(etag.as_str()));
+
+    sqlx::query("UPDATE lsrwa_express.epochs SET processed_at = NOW() WHERE id = $1")
+        .bind(completed.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, changed) = app.revalidate(path, Some(&etag)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(changed.as_deref(), Some(etag.as_str()));
+}
+
+#[tokio::test]
+async fn the_schedule_counts_down_to_the_active_epochs_end() {
+    let app = TestApp::spawn().await;
+    let active = EpochBuilder::new().insert(&app.pool).await.unwrap();
+
+    let (status, body) = app.get("/api/v1/epochs/schedule").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["current_epoch_id"], active.id);
+    assert!(body["estimated_epoch_end"].is_string());
+    assert!(body["seconds_remaining"].as_i64().is_some());
+    assert_eq!(body["accepting_submissions"], true);
+}
+
+#[tokio::test]
+async fn a_stalled_close_is_resumed_from_the_step_it_reached() {
+    let app = TestApp::spawn().await;
+    let epoch = EpochBuilder::new().insert(&app.pool).await.unwrap();
+    let path = format!("/api/v1/admin/epochs/{}/process", epoch.id);
+
+    // A sequence that is still making progress isn't started a second time
+    running_close(&app, epoch.id, "processing_withdrawals", 1).await;
+    let (status, body) = app.admin_post(&path, serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(body["error"]["message"], format!("Epoch {} is already being processed", epoch.id));
+
+    // One that has gone quiet for longer than a step can take is picked up where it stopped
+    running_close(&app, epoch.id, "processing_withdrawals", 16).await;
+    let (status, body) = app.admin_post(&path, serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    assert_eq!((body["status"].as_str(), body["step"].as_str()), (Some("running"), Some("processing_withdrawals")));
+}
+
+#[tokio::test]
+async fn a_failed_close_is_retried_from_the_step_that_failed() {
+    let app = TestApp::spawn().await;
+    let epoch = EpochBuilder::new().insert(&app.pool).await.unwrap();
+    sqlx::query(
+        "INSERT INTO lsrwa_express.epoch_processing_runs (epoch_id, status, step, error) \
+         VALUES ($1, 'failed', 'closing_epoch', 'Chain unavailable')",
+    )
+    .bind(epoch.id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let (status, body) = app.admin_post(&format!("/api/v1/admin/epochs/{}/process", epoch.id), serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    assert_eq!((body["status"].as_str(), body["step"].as_str()), (Some("running"), Some("closing_epoch")));
+    assert_eq!(body["error"], serde_json::Value::Null);
+}
+
+/// Records a running close sequence for the epoch that last made progress `idle_minutes` ago
+async fn running_close(app: &TestApp, epoch_id: i32, step: &str, idle_minutes: i32) {
+    sqlx::query("DELETE FROM lsrwa_express.epoch_processing_runs WHERE epoch_id = $1")
+        .bind(epoch_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    // Inserted rather than updated, which would stamp the row as just updated
+    sqlx::query(
+        "INSERT INTO lsrwa_express.epoch_processing_runs (epoch_id, step, started_at, updated_at) \
+         VALUES ($1, $2, NOW() - make_interval(mins => $3), NOW() - make_interval(mins => $3))",
+    )
+    .bind(epoch_id)
+    .bind(step)
+    .bind(idle_minutes)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
@@ -0,0 +1,430 @@
+//! Request submission through the API, end to end against Postgres and a mock chain
+
+use axum::http::StatusCode;
+use serde_json::json;
+use sqlx::types::BigDecimal;
+
+use crate::common::{Submission, TestApp, TOKEN_DECIMALS};
+use lsrwa_express_rust::db::BalanceRepository;
+use lsrwa_express_rust::models::amount::Amount;
+use lsrwa_express_rust::models::blockchain_request::RequestType;
+use lsrwa_express_rust::models::request_status::RequestStatus;
+use lsrwa_express_rust::models::wallet::WalletAddress;
+use lsrwa_express_rust::services::receipts;
+use lsrwa_express_rust::services::ChainClient;
+use lsrwa_express_rust::test_support::RequestBuilder;
+
+const WALLET: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+const OTHER_WALLET: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+#[tokio::test]
+async fn deposits_of_approved_wallets_are_submitted() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0 }))
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["request_id"], 1);
+    assert_eq!(body["wallet_address"], WALLET);
+    assert_eq!(body["amount"], "250");
+    assert_eq!(
+        app.chain.submissions(),
+        [Submission {
+            request_type: RequestType::Deposit,
+            wallet_address: WALLET.to_string(),
+            amount: Amount::parse("250", TOKEN_DECIMALS).unwrap(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn submissions_are_in_a_registered_asset() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0, "asset": "USDC" }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["message"], "Asset USDC is not registered");
+
+    let (status, body) = app
+        .admin_put(
+            "/api/v1/admin/assets/USDC",
+            json!({ "name": "USD Coin", "decimals": 6, "kind": "psp22", "address": OTHER_WALLET }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    for asset in ["USDC", "NATIVE"] {
+        let (status, body) = app
+            .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0, "asset": asset }))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+    }
+    assert_eq!(
+        app.chain.submissions().into_iter().map(|submission| submission.amount).collect::<Vec<_>>(),
+        [Amount::parse("250", 6).unwrap(), Amount::parse("250", TOKEN_DECIMALS).unwrap()]
+    );
+}
+
+#[tokio::test]
+async fn submissions_need_kyc_within_its_limit() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0 }))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "KYC_REQUIRED");
+
+    // Basic verification allows 10,000 per epoch
+    app.approved_user(WALLET).await;
+    let (status, body) = app
+        .post("/api/v1/requests/withdraw", json!({ "wallet_address": WALLET, "amount": 20_000.0 }))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "KYC_LIMIT_EXCEEDED");
+
+    assert!(app.chain.submissions().is_empty());
+}
+
+#[tokio::test]
+async fn chain_failures_are_bad_gateway() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+    app.chain.set_unavailable(true);
+
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0 }))
+        .await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(body["error"]["code"], "BLOCKCHAIN_ERROR");
+    assert_eq!(body["error"]["message"], "Failed to submit blockchain request");
+    assert_eq!(body["error"]["status"], 502);
+}
+
+#[tokio::test]
+async fn batches_submit_the_valid_items_and_report_the_rest() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+
+    let (status, body) = app
+        .post(
+            "/api/v1/requests/batch",
+            json!({
+                "items": [
+                    { "request_type": "Deposit", "wallet_address": WALLET, "amount": 100.0 },
+                    { "request_type": "Borrow", "wallet_address": WALLET, "amount": 100.0 },
+                    { "request_type": "Deposit", "wallet_address": WALLET, "amount": -5.0 },
+                ]
+            }),
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::MULTI_STATUS, "{}", body);
+    assert_eq!(body["submitted"], 1);
+    assert_eq!(body["failed"], 2);
+    let statuses = body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["status"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, ["submitted", "rejected", "rejected"]);
+    assert_eq!(body["results"][1]["error"], "Borrow requests cannot be submitted in a batch");
+    assert_eq!(app.chain.submissions().len(), 1);
+}
+
+#[tokio::test]
+async fn batches_report_submission_failures_per_item() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+    app.approved_user(OTHER_WALLET).await;
+    let batch = json!({
+        "items": [
+            { "request_type": "Deposit", "wallet_address": WALLET, "amount": 100.0 },
+            { "request_type": "Withdrawal", "wallet_address": OTHER_WALLET, "amount": 0.0 },
+            { "request_type": "Deposit", "wallet_address": OTHER_WALLET, "amount": 40.0 },
+        ]
+    });
+
+    app.chain.refuse_wallet(OTHER_WALLET);
+    let (status, body) = app.post("/api/v1/requests/batch", batch.clone()).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS, "{}", body);
+    assert_eq!((body["submitted"].as_u64(), body["failed"].as_u64()), (Some(1), Some(2)));
+    let outcomes: Vec<_> = body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| (result["index"].as_u64().unwrap(), result["status"].as_str().unwrap(), result["request"].is_object()))
+        .collect();
+    assert_eq!(outcomes, [(0, "submitted", true), (1, "rejected", false), (2, "failed", false)]);
+    assert!(body["results"][2]["error"].as_str().unwrap().contains("rejected by the node"));
+
+    // Every item going through is a plain 200
+    let (status, body) = app
+        .post(
+            "/api/v1/requests/batch",
+            json!({ "items": [{ "request_type": "Deposit", "wallet_address": WALLET, "amount": 10.0 }] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((body["submitted"].as_u64(), body["failed"].as_u64()), (Some(1), Some(0)));
+
+    // A utility batch has one signer, so its items must all name the same wallet
+    let (status, body) = app
+        .post("/api/v1/requests/batch", json!({ "items": batch["items"], "use_utility_batch": true }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    assert_eq!(app.chain.submissions().len(), 2);
+
+    let (status, body) = app
+        .post(
+            "/api/v1/requests/batch",
+            json!({
+                "items": [
+                    { "request_type": "Deposit", "wallet_address": WALLET, "amount": 10.0 },
+                    { "request_type": "Deposit", "wallet_address": WALLET, "amount": 20.0 },
+                ],
+                "use_utility_batch": true,
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((body["submitted"].as_u64(), body["failed"].as_u64()), (Some(2), Some(0)));
+    let first = &body["results"][0]["request"];
+    let second = &body["results"][1]["request"];
+    assert_ne!(first["request_id"], second["request_id"]);
+    assert_eq!(first["transaction_hash"], second["transaction_hash"]);
+    assert_eq!(app.chain.submissions().len(), 4);
+}
+
+#[tokio::test]
+async fn malformed_submissions_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    let (status, _) = app.post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, body) = app.post("/api/v1/requests/batch", json!({ "items": [] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["message"], "Batch must contain at least one item");
+
+    let (status, _) = app.get("/api/v1/requests/42").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    assert!(app.chain.submissions().is_empty());
+}
+
+#[tokio::test]
+async fn withdrawals_are_previewed_as_they_would_be_checked() {
+    let app = TestApp::spawn().await;
+
+    let (status, _) = app.get(&format!("/api/v1/users/{}/withdrawable", WALLET)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Approved, with nothing deposited yet
+    app.approved_user(WALLET).await;
+    let (status, body) = app.get(&format!("/api/v1/users/{}/withdrawable", WALLET)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["withdrawable"], "0");
+    assert_eq!(body["limited_by"], "balance");
+    assert_eq!(body["kyc_epoch_remaining"].as_str().map(|remaining| remaining.starts_with("10000")), Some(true));
+
+    let (status, body) = app
+        .post("/api/v1/requests/withdraw", json!({ "wallet_address": WALLET, "amount": 5.0 }))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "INSUFFICIENT_BALANCE");
+    assert!(app.chain.submissions().is_empty());
+}
+
+#[tokio::test]
+async fn recorded_requests_are_corrected_from_the_contract() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0 }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Indexed and reserved at a wrong amount, then processed by the contract without the
+    // service noticing
+    let recorded = RequestBuilder::deposit()
+        .wallet(&WalletAddress::parse(WALLET).unwrap())
+        .amount(25.0)
+        .on_chain_id(1)
+        .status(RequestStatus::Confirmed)
+        .insert(&app.pool)
+        .await
+        .unwrap();
+    BalanceRepository::new(app.pool.clone())
+        .reserve_pending(recorded.user_id.unwrap(), &recorded.asset, &RequestType::Deposit, &BigDecimal::from(25))
+        .await
+        .unwrap();
+    app.chain.process_request_batch(&RequestType::Deposit, &[1]).await.unwrap();
+
+    let (status, body) = app.admin_get("/api/v1/admin/requests/1/correction").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let fields: Vec<_> = body["corrections"].as_array().unwrap().iter().map(|correction| correction["field"].clone()).collect();
+    assert_eq!(fields, [json!("amount"), json!("status")]);
+    assert_eq!(body["corrections"][0]["on_chain"], "250");
+
+    // Only the reviewed differences, with a reason, are applied
+    let (status, body) = app
+        .admin_post("/api/v1/admin/requests/1/correction", json!({ "fields": ["amount"], "reason": "Amount mistyped" }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, _) = app
+        .admin_post("/api/v1/admin/requests/1/correction", json!({ "fields": ["amount", "status"], "reason": " " }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .admin_post(
+            "/api/v1/admin/requests/1/correction",
+            json!({ "fields": ["status", "amount"], "reason": "Amount mistyped" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["after"]["status"], "executed");
+    assert_eq!(body["after"]["amount"].as_str().map(|amount| amount.starts_with("250")), Some(true));
+
+    // The reservation follows the corrected amount, so crediting it leaves nothing pending
+    let (credited, pending, reason): (String, String, String) = sqlx::query_as(
+        r#"
+        SELECT b.active_balance::TEXT, b.pending_deposits::TEXT, a.details->>'reason'
+        FROM lsrwa_express.user_balances b, lsrwa_express.admin_audit_log a
+        WHERE a.id = $1
+        "#,
+    )
+    .bind(body["audit_entry_id"].as_i64().unwrap())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(credited.starts_with("250"), "{}", credited);
+    assert_eq!(pending.parse::<BigDecimal>().unwrap(), BigDecimal::from(0));
+    assert_eq!(reason, "Amount mistyped");
+
+    let (status, _) = app
+        .admin_post("/api/v1/admin/requests/1/correction", json!({ "fields": [], "reason": "Again" }))
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn withdrawals_are_not_corrected_once_reserved() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+    let amount = Amount::parse("5", TOKEN_DECIMALS).unwrap();
+    app.chain.submit_withdrawal_request(WALLET, &app.chain.asset(), amount).await.unwrap();
+    RequestBuilder::withdrawal()
+        .wallet(&WalletAddress::parse(WALLET).unwrap())
+        .amount(4.0)
+        .on_chain_id(1)
+        .status(RequestStatus::Confirmed)
+        .insert(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, body) = app.admin_get("/api/v1/admin/requests/1/correction").await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    assert_eq!(body["error"]["message"], "Withdrawal 1 has already reserved its recorded amount");
+}
+
+#[tokio::test]
+async fn deposits_stay_within_the_wallets_volume_limits() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+    let limits = format!("/api/v1/admin/users/{}/limits", WALLET);
+
+    let (status, body) = app.admin_put(&limits, json!({ "deposit_daily_limit": 300, "reason": "" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let (status, body) = app
+        .admin_put(&limits, json!({ "deposit_daily_limit": "300.000000000000000001", "reason": "Capped pending review" }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["deposit_daily_limit"], "300.000000000000000001");
+
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 250.0 }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    // Recorded as the node client records what it submits
+    RequestBuilder::deposit()
+        .wallet(&WalletAddress::parse(WALLET).unwrap())
+        .amount(250.0)
+        .on_chain_id(1)
+        .insert(&app.pool)
+        .await
+        .unwrap();
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 100.0 }))
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "VOLUME_LIMIT_EXCEEDED");
+
+    let (status, body) = app.admin_get(&limits).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let daily = body["usage"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|usage| usage["request_type"] == "deposit" && usage["window"] == "day")
+        .unwrap();
+    assert_eq!(
+        (daily["source"].as_str(), daily["used"].as_str(), daily["remaining"].as_str()),
+        (Some("override"), Some("250"), Some("50.000000000000000001"))
+    );
+
+    // Without the override the default, unlimited, applies again
+    let (status, _) = app.request(axum::http::Method::DELETE, &limits, None, true).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, body) = app
+        .post("/api/v1/requests/deposit", json!({ "wallet_address": WALLET, "amount": 100.0 }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(app.chain.submissions().len(), 2);
+}
+
+#[tokio::test]
+async fn executed_requests_have_receipts_verifiable_with_the_published_key() {
+    let app = TestApp::spawn().await;
+    app.approved_user(WALLET).await;
+    RequestBuilder::deposit()
+        .wallet(&WalletAddress::parse(WALLET).unwrap())
+        .amount(250.0)
+        .on_chain_id(1)
+        .insert(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, _) = app.get("/api/v1/requests/1/receipt").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = app.get("/api/v1/requests/2/receipt").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    sqlx::query("UPDATE lsrwa_express.blockchain_requests SET status = 'executed', is_processed = TRUE WHERE on_chain_id = 1")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, body) = app.get("/api/v1/requests/1/receipt").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["receipt"]["request_id"], 1);
+    assert_eq!(body["receipt"]["amount"], "250");
+    assert_eq!(body["receipt"]["transactions"][0]["stage"], "submitted");
+
+    let (status, keys) = app.get("/api/v1/receipts/public-key").await;
+    assert_eq!(status, StatusCode::OK, "{}", keys);
+    let public_key = keys["active"]["public_key"].as_str().unwrap();
+    let payload = body["payload"].as_str().unwrap();
+    let signature = body["signature"].as_str().unwrap();
+    assert!(receipts::verify(payload, signature, public_key));
+    assert!(!receipts::verify(&payload.replace("\"250\"", "\"2500\""), signature, public_key));
+}
@@ -0,0 +1,41 @@
+//! Risk parameter changes, end to end against Postgres
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::common::TestApp;
+
+#[tokio::test]
+async fn invalid_parameter_sets_are_refused_without_recording_a_version() {
+    let app = TestApp::spawn().await;
+    let (status, before) = app.admin_get("/api/v1/admin/risk-parameters").await;
+    assert_eq!(status, StatusCode::OK, "{}", before);
+
+    let mut parameters = before.clone();
+    parameters["liquidation_threshold_bps"] = json!(before["collateral_ratio_bps"]);
+    parameters["kyc_basic_epoch_limit"] = json!(-1.0);
+    let (status, body) = app
+        .admin_put("/api/v1/admin/risk-parameters", json!({ "parameters": parameters, "reason": "Tighten liquidations" }))
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    let message = body["error"]["message"].as_str().unwrap();
+    let ratio = &before["collateral_ratio_bps"];
+    let inverted = format!("liquidation_threshold_bps ({}) must be below collateral_ratio_bps ({})", ratio, ratio);
+    assert!(message.contains(&inverted), "{}", message);
+    assert!(message.contains("kyc_basic_epoch_limit must be a non-negative amount or unlimited"), "{}", message);
+
+    let (_, versions) = app.admin_get("/api/v1/admin/risk-parameters/versions").await;
+    assert_eq!(versions, json!([]));
+    let (_, after) = app.admin_get("/api/v1/admin/risk-parameters").await;
+    assert_eq!(after, before);
+
+    // The same change made consistent goes through
+    parameters["liquidation_threshold_bps"] = json!(12_500);
+    parameters["kyc_basic_epoch_limit"] = before["kyc_basic_epoch_limit"].clone();
+    let (status, body) = app
+        .admin_put("/api/v1/admin/risk-parameters", json!({ "parameters": parameters, "reason": "Tighten liquidations" }))
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!((body["version"].as_i64(), body["changed"].clone()), (Some(1), json!(["liquidation_threshold_bps"])));
+    assert_eq!(body["onchain_status"], "not_required");
+}
@@ -0,0 +1,162 @@
+//! User registration and the admin user listing, end to end against Postgres
+
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::json;
+use tower::ServiceExt;
+
+use crate::common::{TestApp, TestWallet};
+use lsrwa_express_rust::test_support::fake;
+
+#[tokio::test]
+async fn registrations_are_listed_a_page_at_a_time() {
+    let app = TestApp::spawn().await;
+    let wallets: Vec<TestWallet> = (0..5).map(|_| TestWallet::new()).collect();
+    for wallet in &wallets {
+        let (status, body) = register(&app, wallet, json!({ "wallet_address": wallet.address })).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+    }
+
+    let mut listed = Vec::new();
+    for offset in [0, 2, 4] {
+        let (status, body) = app.admin_get(&format!("/api/v1/admin/users?limit=2&offset={}", offset)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        listed.extend(body.as_array().unwrap().iter().map(|user| user["wallet_address"].as_str().unwrap().to_string()));
+    }
+
+    // Newest first, each user on exactly one page
+    let newest_first: Vec<String> = wallets.iter().rev().map(|wallet| wallet.address.to_string()).collect();
+    assert_eq!(listed, newest_first);
+
+    let (_, body) = app.admin_get("/api/v1/admin/users?kyc_status=approved").await;
+    assert_eq!(body, json!([]));
+}
+
+#[tokio::test]
+async fn wallets_register_once() {
+    let app = TestApp::spawn().await;
+    let wallet = TestWallet::new();
+
+    let (status, _) = register(&app, &wallet, json!({ "wallet_address": wallet.address })).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The same account under the Polkadot prefix
+    let (status, body) = register(&app, &wallet, json!({ "wallet_address": wallet.address.to_ss58(0) })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["message"], format!("A user is already registered for wallet {}", wallet.address));
+
+    let other = TestWallet::new();
+    let payload = json!({ "wallet_address": other.address, "referrer_wallet": other.address });
+    let (status, _) = register(&app, &other, payload).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = register(&app, &other, json!({ "wallet_address": "wallet-0" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn wallets_register_only_themselves() {
+    let app = TestApp::spawn().await;
+    let wallet = TestWallet::new();
+
+    let (status, body) = app.post("/api/v1/users", json!({ "wallet_address": wallet.address })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["message"], "Missing X-Wallet-Address header");
+
+    let (status, body) = register(&app, &TestWallet::new(), json!({ "wallet_address": wallet.address })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "WALLET_MISMATCH");
+}
+
+#[tokio::test]
+async fn profiles_show_contact_details_only_to_their_wallet() {
+    let app = TestApp::spawn().await;
+    let wallet = TestWallet::new();
+    let email = fake::email();
+    let (status, _) = register(&app, &wallet, json!({ "wallet_address": wallet.address, "email": email })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let path = format!("/api/v1/users/{}/profile", wallet.address);
+
+    let (status, body) = app.request_as(&wallet, Method::GET, &path, None).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["email"], email);
+
+    for (status, body) in [
+        app.get(&path).await,
+        app.request_as(&TestWallet::new(), Method::GET, &path, None).await,
+    ] {
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["wallet_address"], wallet.address.to_string());
+        assert!(body.get("email").is_none(), "{}", body);
+        assert!(body.get("kyc_reference").is_none(), "{}", body);
+    }
+}
+
+#[tokio::test]
+async fn notification_preferences_are_changed_only_by_their_wallet() {
+    let app = TestApp::spawn().await;
+    let wallet = TestWallet::new();
+    let (status, _) = register(&app, &wallet, json!({ "wallet_address": wallet.address, "email": fake::email() })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let path = format!("/api/v1/users/{}/notification-preferences", wallet.address);
+    let change = json!({ "preferences": [{ "notification_type": "deposit_processed", "enabled": false }] });
+
+    let (status, _) = app.request(Method::PUT, &path, Some(change.clone()), false).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = app.request_as(&TestWallet::new(), Method::PUT, &path, Some(change.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "WALLET_MISMATCH");
+
+    let (status, body) = app.request_as(&wallet, Method::PUT, &path, Some(change)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let deposits = body.as_array().unwrap().iter().find(|preference| preference["notification_type"] == "deposit_processed");
+    assert_eq!(deposits.map(|preference| preference["enabled"].clone()), Some(json!(false)));
+}
+
+#[tokio::test]
+async fn admin_endpoints_need_the_admin_key() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = app.get("/api/v1/admin/users").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["message"], "Missing admin credentials");
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/v1/admin/users")
+        .header(header::AUTHORIZATION, "Bearer not-the-key")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app.admin_get("/api/v1/admin/users").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn browsers_may_send_wallet_signatures_cross_origin() {
+    let app = TestApp::spawn().await;
+
+    let preflight = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/v1/users")
+        .header(header::ORIGIN, "http://localhost:3000")
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type,x-wallet-address,x-wallet-timestamp,x-wallet-signature")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let response = app.router.clone().oneshot(preflight).await.unwrap();
+
+    let allowed = response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap().to_string();
+    for wallet_header in ["x-wallet-address", "x-wallet-timestamp", "x-wallet-signature"] {
+        assert!(allowed.contains(wallet_header), "{} not in {}", wallet_header, allowed);
+    }
+
+    // Notification preferences are replaced with PUT
+    let allowed = response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap().to_string();
+    assert!(allowed.contains("PUT"), "PUT not in {}", allowed);
+}
+
+async fn register(app: &TestApp, wallet: &TestWallet, payload: serde_json::Value) -> (StatusCode, serde_json::Value) {
+    app.request_as(wallet, Method::POST, "/api/v1/users", Some(payload)).await
+}
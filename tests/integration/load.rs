@@ -0,0 +1,301 @@
+//! Load harness: hammers submission and read endpoints over HTTP, against Postgres and the
+//! mock chain, and checks latencies and connection pool saturation against the budgets in
+//! `tests/fixtures/load_budgets.json`
+//!
+//! Ignored by default. Budgets are only enforced in an optimized build, so run it with
+//! `cargo test --profile perf --features test-support --test integration load:: -- --ignored
+//! --nocapture`; other builds print the report without failing. `LOAD_CONCURRENCY` (default 16)
+//! and `LOAD_DURATION_SECS` (default 20) size the run, and `LOAD_REPORT` names a file the report
+//! is also written to as JSON.
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::common::{TestApp, ADMIN_API_KEY};
+use lsrwa_express_rust::models::wallet::WalletAddress;
+
+/// Budgets the run is checked against
+const BUDGETS: &str = "tests/fixtures/load_budgets.json";
+
+/// Wallets requests are spread over; each stays far below its KYC limit with 1 token submissions
+const WALLETS: usize = 50;
+
+/// How often pool usage is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A call the driver makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Scenario {
+    SubmitDeposit,
+    SubmitBatch,
+    UserProfile,
+    RequestsByWallet,
+    ListUsers,
+}
+
+/// One round of a worker's loop, weighted towards reads as live traffic is
+const MIX: [Scenario; 8] = [
+    Scenario::SubmitDeposit,
+    Scenario::UserProfile,
+    Scenario::RequestsByWallet,
+    Scenario::SubmitDeposit,
+    Scenario::UserProfile,
+    Scenario::ListUsers,
+    Scenario::SubmitBatch,
+    Scenario::RequestsByWallet,
+];
+
+impl Scenario {
+    fn name(self) -> &'static str {
+        match self {
+            Scenario::SubmitDeposit => "submit_deposit",
+            Scenario::SubmitBatch => "submit_batch",
+            Scenario::UserProfile => "user_profile",
+            Scenario::RequestsByWallet => "requests_by_wallet",
+            Scenario::ListUsers => "list_users",
+        }
+    }
+
+    fn request(self, client: &Client, base: &str, wallet: &str) -> reqwest::RequestBuilder {
+        match self {
+            Scenario::SubmitDeposit => client
+                .post(format!("{}/api/v1/requests/deposit", base))
+                .json(&json!({ "wallet_address": wallet, "amount": 1.0 })),
+            Scenario::SubmitBatch => {
+                let item = json!({ "request_type": "Deposit", "wallet_address": wallet, "amount": 1.0 });
+                client
+                    .post(format!("{}/api/v1/requests/batch", base))
+                    .json(&json!({ "items": [item.clone(), item] }))
+            },
+            Scenario::UserProfile => client.get(format!("{}/api/v1/users/{}/profile", base, wallet)),
+            Scenario::RequestsByWallet => client.get(format!("{}/api/v1/requests/wallet/{}", base, wallet)),
+            Scenario::ListUsers => client
+                .get(format!("{}/api/v1/admin/users?limit=20", base))
+                .bearer_auth(ADMIN_API_KEY),
+        }
+    }
+}
+
+/// A finished call
+struct Sample {
+    scenario: Scenario,
+    latency: Duration,
+    succeeded: bool,
+}
+
+fn wallet(index: usize) -> String {
+    WalletAddress::from_public_key([(index % WALLETS) as u8; 32]).to_string()
+}
+
+/// Runs the mix until `deadline`
+async fn worker(client: Client, base: String, worker: usize, deadline: Instant) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    let mut round = 0;
+    while Instant::now() < deadline {
+        let wallet = wallet(worker * 7 + round);
+        for scenario in MIX {
+            let started = Instant::now();
+            let succeeded = match scenario.request(&client, &base, &wallet).send().await {
+                Ok(response) => response.status().is_success() && response.bytes().await.is_ok(),
+                Err(_) => false,
+            };
+            samples.push(Sample {
+                scenario,
+                latency: started.elapsed(),
+                succeeded,
+            });
+        }
+        round += 1;
+    }
+    samples
+}
+
+/// Share of the pool's connections in use, sampled until `deadline`
+async fn sample_pool(pool: PgPool, deadline: Instant) -> Vec<f64> {
+    let max_connections = pool.options().get_max_connections() as f64;
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    let mut samples = Vec::new();
+    while Instant::now() < deadline {
+        interval.tick().await;
+        let in_use = (pool.size() as usize).saturating_sub(pool.num_idle());
+        samples.push(in_use as f64 / max_connections);
+    }
+    samples
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    concurrency: usize,
+    duration_secs: f64,
+    requests: usize,
+    errors: usize,
+    requests_per_sec: f64,
+    pool: PoolReport,
+    endpoints: BTreeMap<&'static str, EndpointReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct PoolReport {
+    max_connections: u32,
+    mean_saturation: f64,
+    peak_saturation: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct EndpointReport {
+    requests: usize,
+    errors: usize,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Budgets {
+    /// Share of calls allowed to fail, across every endpoint
+    max_error_rate: f64,
+    /// Mean share of the pool's connections in use
+    max_mean_pool_saturation: f64,
+    endpoints: BTreeMap<String, LatencyBudget>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LatencyBudget {
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+}
+
+/// Nearest-rank percentile of sorted latencies, in milliseconds
+fn percentile(sorted: &[Duration], percent: f64) -> f64 {
+    let rank = ((percent / 100.0 * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1].as_secs_f64() * 1_000.0
+}
+
+fn report(samples: &[Sample], pool_samples: &[f64], max_connections: u32, concurrency: usize, elapsed: Duration) -> Report {
+    let mut by_scenario = BTreeMap::<Scenario, Vec<&Sample>>::new();
+    for sample in samples {
+        by_scenario.entry(sample.scenario).or_default().push(sample);
+    }
+
+    let endpoints = by_scenario
+        .into_iter()
+        .map(|(scenario, samples)| {
+            let mut latencies = samples.iter().map(|sample| sample.latency).collect::<Vec<_>>();
+            latencies.sort();
+            let report = EndpointReport {
+                requests: samples.len(),
+                errors: samples.iter().filter(|sample| !sample.succeeded).count(),
+                p50_ms: percentile(&latencies, 50.0),
+                p95_ms: percentile(&latencies, 95.0),
+                p99_ms: percentile(&latencies, 99.0),
+                max_ms: percentile(&latencies, 100.0),
+            };
+            (scenario.name(), report)
+        })
+        .collect();
+
+    Report {
+        concurrency,
+        duration_secs: elapsed.as_secs_f64(),
+        requests: samples.len(),
+        errors: samples.iter().filter(|sample| !sample.succeeded).count(),
+        requests_per_sec: samples.len() as f64 / elapsed.as_secs_f64(),
+        pool: PoolReport {
+            max_connections,
+            mean_saturation: pool_samples.iter().sum::<f64>() / pool_samples.len().max(1) as f64,
+            peak_saturation: pool_samples.iter().copied().fold(0.0, f64::max),
+        },
+        endpoints,
+    }
+}
+
+/// Every budget the report exceeds
+fn violations(report: &Report, budgets: &Budgets) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    let error_rate = report.errors as f64 / report.requests.max(1) as f64;
+    if error_rate > budgets.max_error_rate {
+        violations.push(format!("error rate {:.4} is over {}", error_rate, budgets.max_error_rate));
+    }
+    if report.pool.mean_saturation > budgets.max_mean_pool_saturation {
+        violations.push(format!(
+            "mean pool saturation {:.2} is over {}",
+            report.pool.mean_saturation, budgets.max_mean_pool_saturation
+        ));
+    }
+
+    for (name, budget) in &budgets.endpoints {
+        let Some(endpoint) = report.endpoints.get(name.as_str()) else {
+            violations.push(format!("{} has a budget but was not called", name));
+            continue;
+        };
+        for (percentile, measured, budget) in [
+            ("p50", endpoint.p50_ms, budget.p50_ms),
+            ("p95", endpoint.p95_ms, budget.p95_ms),
+            ("p99", endpoint.p99_ms, budget.p99_ms),
+        ] {
+            if measured > budget {
+                violations.push(format!("{} {} is {:.1}ms, over its {}ms budget", name, percentile, measured, budget));
+            }
+        }
+    }
+
+    violations
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[ignore = "load test; run with `cargo test --profile perf --features test-support --test integration load:: -- --ignored --nocapture`"]
+async fn endpoints_stay_within_their_budgets() {
+    let budgets: Budgets = serde_json::from_str(&fs::read_to_string(BUDGETS).unwrap()).unwrap();
+    let concurrency = env_or("LOAD_CONCURRENCY", 16);
+    let duration = Duration::from_secs(env_or("LOAD_DURATION_SECS", 20));
+
+    let app = TestApp::spawn().await;
+    for index in 0..WALLETS {
+        app.approved_user(&wallet(index)).await;
+    }
+    let base = format!("http://{}", app.serve());
+    let client = Client::builder().pool_max_idle_per_host(concurrency).build().unwrap();
+
+    let started = Instant::now();
+    let deadline = started + duration;
+    let pool_sampler = tokio::spawn(sample_pool(app.pool.clone(), deadline));
+    let workers = (0..concurrency)
+        .map(|index| tokio::spawn(worker(client.clone(), base.clone(), index, deadline)))
+        .collect::<Vec<_>>();
+
+    let mut samples = Vec::new();
+    for handle in workers {
+        samples.extend(handle.await.unwrap());
+    }
+    let elapsed = started.elapsed();
+    let pool_samples = pool_sampler.await.unwrap();
+
+    let report = report(&samples, &pool_samples, app.pool.options().get_max_connections(), concurrency, elapsed);
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    println!("{}", json);
+    if let Ok(path) = env::var("LOAD_REPORT") {
+        fs::write(path, &json).unwrap();
+    }
+
+    let violations = violations(&report, &budgets);
+    if cfg!(debug_assertions) {
+        for violation in &violations {
+            println!("over budget (not enforced in unoptimized builds): {}", violation);
+        }
+    } else {
+        assert!(violations.is_empty(), "Over budget:\n{}", violations.join("\n"));
+    }
+}
@@ -0,0 +1,14 @@
+//! Integration tests against Postgres, built with the `test-support` feature
+//!
+//! The suites share one test binary so the harness in `common` is compiled once, and only the
+//! helpers some suite uses are kept. Run a single suite by filtering on its module, e.g.
+//! `cargo test --features test-support --test integration api_requests::`.
+
+mod common;
+
+mod api_epochs;
+mod api_requests;
+mod api_risk;
+mod api_users;
+mod load;
+mod migrations;
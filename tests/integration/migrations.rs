@@ -0,0 +1,230 @@
+//! Schema drift checks: the migrations against a fresh database, the models against the tables
+//! they're stored in, and the crate's `sqlx::query!` macros against the migrated schema
+//!
+//! Each test starts its own Postgres container, so Docker must be running. The macro check also
+//! needs sqlx-cli and is ignored by default.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::types::BigDecimal;
+use sqlx::Executor;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::common::TestDatabase;
+use lsrwa_express_rust::db::migration::{migration_status, run_migrations, MIGRATOR};
+use lsrwa_express_rust::db::{BlockchainRequestRepository, EpochRepository, RewardRepository, UserRepository};
+use lsrwa_express_rust::models::archive::StoredEvent;
+use lsrwa_express_rust::models::blockchain_request::RequestType;
+use lsrwa_express_rust::models::epoch::EpochStatus;
+use lsrwa_express_rust::models::user::KycStatus;
+use lsrwa_express_rust::services::indexer::EventPayload;
+use lsrwa_express_rust::test_support::{EpochBuilder, EventBuilder, RequestBuilder, RewardBuilder, UserBuilder};
+
+/// Postgres keeps timestamps to the microsecond
+fn same_instant(written: DateTime<Utc>, read: DateTime<Utc>) -> bool {
+    written.timestamp_micros() == read.timestamp_micros()
+}
+
+fn same_decimal(written: &str, read: &str) -> bool {
+    BigDecimal::from_str(written).unwrap() == BigDecimal::from_str(read).unwrap()
+}
+
+async fn migrated_database() -> TestDatabase {
+    let database = TestDatabase::start().await;
+    run_migrations(&database.pool).await.unwrap();
+    database
+}
+
+#[tokio::test]
+async fn migrations_apply_to_a_fresh_database() {
+    let database = TestDatabase::start().await;
+    let embedded = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .collect::<Vec<_>>();
+
+    let status = migration_status(&database.pool).await.unwrap();
+    assert_eq!(status.pending, embedded);
+
+    run_migrations(&database.pool).await.unwrap();
+    let status = migration_status(&database.pool).await.unwrap();
+    assert!(status.pending.is_empty() && status.is_runnable(), "{:?}", status);
+
+    // A database that is up to date is left as it is
+    run_migrations(&database.pool).await.unwrap();
+    let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations WHERE success")
+        .fetch_one(&database.pool)
+        .await
+        .unwrap();
+    assert_eq!(applied, embedded.len() as i64);
+}
+
+#[tokio::test]
+async fn users_round_trip_through_their_table() {
+    let database = migrated_database().await;
+    let users = UserRepository::new(database.pool.clone());
+
+    let builder = UserBuilder::new().with_email().approved();
+    let written = builder.build();
+    let inserted = builder.insert(&database.pool).await.unwrap();
+    let read = users.get_by_id(inserted.id).await.unwrap().unwrap();
+
+    assert_eq!(read.wallet_address, written.wallet_address);
+    assert_eq!(read.email, written.email);
+    assert_eq!(read.kyc_status, KycStatus::Approved);
+    assert_eq!(read.kyc_reference, inserted.kyc_reference);
+    assert!(same_instant(inserted.kyc_timestamp.unwrap(), read.kyc_timestamp.unwrap()));
+    assert_eq!(serde_json::to_value(&inserted).unwrap(), serde_json::to_value(&read).unwrap());
+}
+
+#[tokio::test]
+async fn requests_round_trip_through_their_table() {
+    let database = migrated_database().await;
+    let requests = BlockchainRequestRepository::new(database.pool.clone());
+
+    let submitted_at = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap().and_hms_micro_opt(23, 59, 59, 999_999).unwrap();
+    let builder = RequestBuilder::borrow().amount(1234.56).collateral(2469.12).submitted_at(submitted_at);
+    let written = builder.build();
+    builder.insert(&database.pool).await.unwrap();
+    let read = requests
+        .find_by_on_chain_id(&RequestType::Borrow, written.on_chain_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(read.request_type, RequestType::Borrow);
+    assert_eq!(read.wallet_address, written.wallet_address);
+    assert_eq!(read.amount.parse::<f64>().unwrap(), written.amount);
+    assert_eq!(read.collateral_amount.unwrap().parse::<f64>().unwrap(), written.collateral_amount.unwrap());
+    assert_eq!(read.submission_timestamp, submitted_at.and_utc());
+    assert_eq!(read.status, written.status);
+    assert!(!read.is_processed);
+    assert_eq!(read.block_number, written.block_number);
+    assert_eq!(read.transaction_hash, written.transaction_hash);
+}
+
+#[tokio::test]
+async fn epochs_and_rewards_round_trip_through_their_tables() {
+    let database = migrated_database().await;
+    let epochs = EpochRepository::new(database.pool.clone());
+    let rewards = RewardRepository::new(database.pool.clone());
+
+    let end_timestamp = Utc::now() - Duration::minutes(5);
+    let epoch = EpochBuilder::new().closed_at(end_timestamp).insert(&database.pool).await.unwrap();
+    let read = epochs.get(epoch.id).await.unwrap().unwrap();
+    assert_eq!(read.status, EpochStatus::Processing);
+    assert!(same_instant(end_timestamp, read.end_timestamp.unwrap()));
+    assert_eq!(read.processing_tx_hash, None);
+
+    let user = UserBuilder::new().insert(&database.pool).await.unwrap();
+    let builder = RewardBuilder::new(user.id).epoch(epoch.id).amount("12.345678").apr_bps(725);
+    let inserted = builder.insert(&database.pool).await.unwrap();
+    let read = rewards.get(inserted.id).await.unwrap().unwrap();
+    assert_eq!(read.user_id, user.id);
+    assert_eq!(read.epoch_id, epoch.id);
+    assert!(same_decimal("12.345678", &read.amount), "{}", read.amount);
+    assert_eq!(read.apr_bps, 725);
+    assert_eq!(serde_json::to_value(&inserted).unwrap(), serde_json::to_value(&read).unwrap());
+}
+
+#[tokio::test]
+async fn indexed_events_round_trip_through_the_event_queue() {
+    let database = migrated_database().await;
+
+    let written = EventBuilder::request(RequestType::Borrow, u32::MAX as u128 + 1)
+        .insert(&database.pool)
+        .await
+        .unwrap();
+    let read = sqlx::query_as::<_, StoredEvent>(
+        r#"
+        SELECT id, event_type, block_number, transaction_hash, request_id, wallet_address, amount,
+               request_type::TEXT AS request_type, timestamp, raw_data, created_at
+        FROM lsrwa_express.event_queue
+        WHERE id = $1
+        "#,
+    )
+    .bind(&written.id)
+    .fetch_one(&database.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(read.event_type, written.event_type() as i32);
+    assert_eq!(read.block_number as u64, written.block_number);
+    assert_eq!(read.transaction_hash, written.transaction_hash);
+    assert_eq!(read.request_id.map(|id| id as u128), written.request_id);
+    assert_eq!(read.wallet_address, written.wallet_address);
+    assert_eq!(read.amount, written.amount);
+    assert_eq!(read.request_type, Some(RequestType::Borrow));
+    assert!(same_instant(written.timestamp, read.timestamp));
+    assert_eq!(read.raw_data, written.payload.data().unwrap());
+    assert_eq!(
+        EventPayload::from_contract_event("BorrowRequested", read.raw_data),
+        written.payload
+    );
+}
+
+#[tokio::test]
+async fn capitalized_request_types_in_the_event_queue_are_normalized() {
+    let database = migrated_database().await;
+    let written = EventBuilder::request(RequestType::Borrow, 7).insert(&database.pool).await.unwrap();
+
+    // The contract's variant name, as rows from before the event queue was checked may hold
+    database
+        .pool
+        .execute("ALTER TABLE lsrwa_express.event_queue DROP CONSTRAINT check_event_queue_request_type")
+        .await
+        .unwrap();
+    sqlx::query("UPDATE lsrwa_express.event_queue SET request_type = 'Borrow' WHERE id = $1")
+        .bind(&written.id)
+        .execute(&database.pool)
+        .await
+        .unwrap();
+
+    database
+        .pool
+        .execute(include_str!("../../migrations/20231201000023_normalize_request_types.sql"))
+        .await
+        .unwrap();
+    let read: Option<RequestType> =
+        sqlx::query_scalar("SELECT request_type::TEXT FROM lsrwa_express.event_queue WHERE id = $1")
+            .bind(&written.id)
+            .fetch_one(&database.pool)
+            .await
+            .unwrap();
+    assert_eq!(read, Some(RequestType::Borrow));
+}
+
+/// Compiles every `sqlx::query!` in the crate against a freshly migrated database with
+/// `cargo sqlx prepare`, failing when one no longer matches the schema. The offline query data
+/// in `.sqlx` is checked against the schema, or written when there is none yet or
+/// `SQLX_PREPARE_WRITE` is set.
+#[tokio::test]
+#[ignore = "needs sqlx-cli; run with `cargo test --features test-support --test integration migrations:: -- --ignored`"]
+async fn query_macros_compile_against_the_migrated_schema() {
+    let database = migrated_database().await;
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let check = manifest_dir.join(".sqlx").is_dir() && env::var_os("SQLX_PREPARE_WRITE").is_none();
+
+    let mut prepare = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    prepare.current_dir(manifest_dir).args(["sqlx", "prepare"]);
+    if check {
+        prepare.arg("--check");
+    }
+    let status = prepare
+        .args(["--", "--lib"])
+        .env("DATABASE_URL", &database.url)
+        // The target directory this test was built in stays locked while it runs
+        .env("CARGO_TARGET_DIR", manifest_dir.join("target").join("sqlx-prepare"))
+        .status()
+        .expect("sqlx-cli is installed: cargo install sqlx-cli --no-default-features --features postgres,native-tls");
+
+    assert!(
+        status.success(),
+        "`cargo sqlx prepare{}` failed; a query no longer matches the schema{}",
+        if check { " --check" } else { "" },
+        if check { ", or .sqlx is stale and needs SQLX_PREPARE_WRITE=1" } else { "" }
+    );
+}